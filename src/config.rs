@@ -2,22 +2,33 @@
 // Original code by: CryZe
 // Original repository: github.com/CryZe/livesplit-one-desktop
 // Commit: c636ba8
-use crate::formatters::{TimeFormat, TimeFormatPreset};
+use crate::formatters::{Rounding, TimeFormat, TimeFormatPreset};
+use crate::io::hotkey_profiles::{ProfileError, resolve_profile};
+use crate::io::integrity::{self, IntegrityStatus, run_to_save, save_run_stamped};
+use crate::io::recovery::recover_run;
+use crate::io::save_dedup::{write_fresh, write_if_changed};
+use crate::io::trash::{Trash, default_trash_dir};
+use crate::race::DEFAULT_PORT;
+use crate::utils::attempt_edit::LaterSplits;
+use crate::utils::comparisons::persist_padded_golds;
+use crate::utils::crash_report::set_splits_path;
+use crate::utils::trace_timing::splits_io_span;
 
+use livesplit_core::hotkey::Hotkey;
 use livesplit_core::{
-    HotkeyConfig, HotkeySystem, Run, Segment, SharedTimer, Timer, TimingMethod, auto_splitting,
-    run::{parser::composite, saver::livesplit::save_timer},
+    HotkeyConfig, Run, Segment, Timer, TimingMethod, auto_splitting, run::parser::composite,
 };
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeMap,
     fs,
     path::{Path, PathBuf},
 };
-use tracing::error;
+use tracing::{debug, error, warn};
 
 pub type SharedConfig = std::sync::Arc<std::sync::RwLock<Config>>;
 
-#[derive(Default, Deserialize, Serialize)]
+#[derive(Default, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Config {
     #[serde(default)]
@@ -27,13 +38,49 @@ pub struct Config {
     #[serde(default)]
     pub style: Style,
     #[serde(default)]
-    pub hotkeys: HotkeyConfig,
+    pub theme: Theme,
+    #[serde(default)]
+    pub behavior: Behavior,
+    #[serde(default)]
+    pub debug: DebugOptions,
+    #[serde(default)]
+    pub race: RaceOptions,
+    #[serde(default)]
+    pub stats: StatsOptions,
+    #[serde(default)]
+    pub layout: LayoutOptions,
+    #[serde(default)]
+    pub storage: StorageOptions,
+    #[serde(default)]
+    pub feedback: FeedbackOptions,
+    #[serde(default)]
+    pub detached_timer: DetachedTimerOptions,
+    #[serde(default)]
+    pub drill: DrillOptions,
+    #[serde(default)]
+    pub freeze_watch: FreezeWatchOptions,
+    #[serde(default)]
+    pub logging: LoggingOptions,
+    #[serde(default)]
+    pub hotkeys: HotkeyOptions,
+    #[serde(default)]
+    pub gamepad: GamepadOptions,
     #[serde(default)]
     pub format: Format,
     #[serde(default)]
     connections: Connections,
+    #[serde(default)]
+    splits_state: BTreeMap<PathBuf, SplitsState>,
+    #[serde(skip)]
+    session_start_attempt: i32,
+    #[serde(skip)]
+    splits_recovery: Option<SplitsRecovery>,
+    #[serde(skip)]
+    splits_integrity: Option<IntegrityStatus>,
+    /// Directory of the config file this was read from. Relative paths in
+    /// the config are resolved against it first.
     #[serde(skip)]
-    hotkey_system: Option<HotkeySystem>,
+    base_dir: Option<PathBuf>,
 }
 
 #[allow(clippy::missing_fields_in_debug)]
@@ -43,34 +90,199 @@ impl std::fmt::Debug for Config {
             .field("general", &self.general)
             .field("window", &self.window)
             .field("style", &self.style)
+            .field("theme", &self.theme)
+            .field("behavior", &self.behavior)
+            .field("debug", &self.debug)
+            .field("race", &self.race)
+            .field("stats", &self.stats)
+            .field("layout", &self.layout)
+            .field("storage", &self.storage)
+            .field("feedback", &self.feedback)
+            .field("detached_timer", &self.detached_timer)
+            .field("drill", &self.drill)
+            .field("freeze_watch", &self.freeze_watch)
+            .field("logging", &self.logging)
             .field("hotkeys", &self.hotkeys)
+            .field("gamepad", &self.gamepad)
             .field("format", &self.format)
+            .field("splits_state", &self.splits_state)
             .finish()
     }
 }
 
-impl Clone for Config {
-    fn clone(&self) -> Self {
-        Self {
-            general: self.general.clone(),
-            window: self.window.clone(),
-            style: self.style.clone(),
-            hotkeys: self.hotkeys,
-            format: self.format.clone(),
-            connections: self.connections.clone(),
-            hotkey_system: None,
-        }
-    }
-}
-
-#[derive(Default, Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct General {
     pub splits: Option<PathBuf>,
     pub timing_method: Option<TimingMethod>,
     pub comparison: Option<String>,
     pub auto_splitter: Option<PathBuf>,
+    #[serde(default)]
     pub additional_info: AdditionalInfoVisibility,
+    #[serde(default)]
+    pub gold_source: GoldSource,
+    /// Generated comparisons are left out of the comparison cycle until the
+    /// splits have this many attempts. Personal Best and Best Segments are
+    /// always offered.
+    #[serde(default = "default_min_attempts_for_generated")]
+    pub min_attempts_for_generated: u32,
+    /// Comparison the analytical infos (previous segment, possible time save,
+    /// current pace) use. `None` follows the current comparison.
+    #[serde(default)]
+    pub analytics_comparison: Option<String>,
+    /// How segments without a best time count toward the sum of best.
+    #[serde(default)]
+    pub skipped_in_sum_of_best: SkippedInSumOfBest,
+    /// What the remaining time at gold pace does for segments without a
+    /// best time.
+    #[serde(default)]
+    pub remaining_without_best: RemainingWithoutBest,
+    #[serde(default)]
+    pub padded_gold_comparison: PaddedGoldComparison,
+    /// Comparisons whose split times are written out here, added to the
+    /// matching splits when they're loaded.
+    #[serde(default)]
+    pub inline_comparisons: Vec<InlineComparison>,
+}
+
+fn default_min_attempts_for_generated() -> u32 {
+    3
+}
+
+impl Default for General {
+    fn default() -> Self {
+        Self {
+            splits: None,
+            timing_method: None,
+            comparison: None,
+            auto_splitter: None,
+            additional_info: AdditionalInfoVisibility::default(),
+            gold_source: GoldSource::default(),
+            min_attempts_for_generated: default_min_attempts_for_generated(),
+            analytics_comparison: None,
+            skipped_in_sum_of_best: SkippedInSumOfBest::default(),
+            remaining_without_best: RemainingWithoutBest::default(),
+            padded_gold_comparison: PaddedGoldComparison::default(),
+            inline_comparisons: Vec::new(),
+        }
+    }
+}
+
+/// A generated comparison pacing every segment at its gold plus a margin,
+/// named like "Golds +5%". Taken into account when splits are loaded.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct PaddedGoldComparison {
+    pub enabled: bool,
+    /// Added to each gold, in percent of it.
+    pub percent: f64,
+    /// Added to each gold on top of the percentage.
+    pub flat_seconds: f64,
+    /// Save the comparison's times in the splits file, as a custom
+    /// comparison other timers can read. Otherwise it's left out, like the
+    /// other generated comparisons.
+    pub persist: bool,
+}
+
+impl Default for PaddedGoldComparison {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            percent: 5.0,
+            flat_seconds: 0.0,
+            persist: false,
+        }
+    }
+}
+
+impl PaddedGoldComparison {
+    /// The most `percent` taken.
+    pub const MAX_PERCENT: f64 = 1_000.0;
+    /// The most `flat_seconds` taken, an hour per segment.
+    pub const MAX_FLAT_SECONDS: f64 = 3_600.0;
+
+    /// This padding with `percent` and `flat_seconds` kept between zero
+    /// and their maximum, as a hand-edited config may have anything there.
+    /// What isn't a number counts as zero.
+    pub fn clamped(&self) -> Self {
+        let clamp = |value: f64, max| {
+            if value.is_nan() {
+                0.0
+            } else {
+                value.clamp(0.0, max)
+            }
+        };
+        Self {
+            percent: clamp(self.percent, Self::MAX_PERCENT),
+            flat_seconds: clamp(self.flat_seconds, Self::MAX_FLAT_SECONDS),
+            ..*self
+        }
+    }
+}
+
+/// A comparison of split times from the config, e.g. a target pace. See
+/// [`crate::utils::inline_comparison`].
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct InlineComparison {
+    pub name: String,
+    /// Only add it to splits of this game, compared without case.
+    pub game: Option<String>,
+    /// Only add it to splits of this category, compared without case.
+    pub category: Option<String>,
+    pub times: InlineTimes,
+}
+
+/// Split times of an inline comparison.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum InlineTimes {
+    /// One per segment, in order.
+    InOrder(Vec<String>),
+    /// By segment name.
+    ByName(BTreeMap<String, String>),
+}
+
+impl Default for InlineTimes {
+    fn default() -> Self {
+        InlineTimes::InOrder(Vec::new())
+    }
+}
+
+/// How a segment without a best time, e.g. one that was always skipped,
+/// counts toward the sum of best and the best possible time.
+#[derive(Default, Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SkippedInSumOfBest {
+    /// The total can't be known and shows as `--`.
+    #[default]
+    Void,
+    /// The segment is left out, the other bests are summed.
+    Exclude,
+}
+
+/// How the remaining time at gold pace counts a segment without a best.
+#[derive(Default, Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RemainingWithoutBest {
+    /// The comparison's segment time stands in, shown with a `~`.
+    #[default]
+    Comparison,
+    /// The remaining time shows as `--` until past the segment.
+    Hide,
+}
+
+/// What a split has to beat to be classified as gold.
+#[derive(Default, Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum GoldSource {
+    /// The best segment stored in the splits file.
+    #[default]
+    StoredBest,
+    /// The best segment among the attempts made since the splits were loaded.
+    SessionBest,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -79,6 +291,21 @@ pub struct Style {
     pub max_segments_displayed: Option<usize>,
     pub segments_scroll_follow_from: Option<usize>,
     pub show_icons: Option<bool>,
+    /// Show the comparison's final time above the running timer.
+    pub show_target_time: Option<bool>,
+    /// Show the running time of the current segment under the current split info.
+    pub show_segment_running_time: Option<bool>,
+    /// Show best, PB and current time of the selected segment in its info box.
+    pub show_segment_breakdown: Option<bool>,
+    /// List the splits from the last one up, with the final split on top.
+    pub reverse_segments: Option<bool>,
+    /// Leave the gold, green and red out of deltas and times. The signs of
+    /// the deltas still tell ahead from behind.
+    pub monochrome: Option<bool>,
+    /// Extra CSS class or hex color per segment, keyed by segment name or
+    /// 0-based index. Name keys take precedence.
+    #[serde(default)]
+    pub segment_classes: BTreeMap<String, String>,
 }
 
 impl Default for Style {
@@ -87,10 +314,565 @@ impl Default for Style {
             max_segments_displayed: Some(10),
             segments_scroll_follow_from: Some(8),
             show_icons: Some(true),
+            show_target_time: Some(true),
+            show_segment_running_time: Some(false),
+            show_segment_breakdown: Some(false),
+            reverse_segments: Some(false),
+            monochrome: Some(false),
+            segment_classes: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Default, Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct Theme {
+    /// Font family forced on the running timer. When unset, the default timer
+    /// font is used as long as its digits are equal width.
+    pub timer_font: Option<String>,
+    /// Profile picked in the settings dialog. Falls back to `default`.
+    pub profile: Option<String>,
+    /// Named color profiles, each mapping a style class (e.g. `goldsplit`) to a
+    /// CSS color. Colors a profile leaves out are taken from `default`.
+    pub profiles: BTreeMap<String, BTreeMap<String, String>>,
+    /// Colors used instead while the desktop prefers a dark style, per
+    /// profile like `profiles`. Colors left out are derived from the light
+    /// ones, lightened until they are readable on the dark background.
+    pub dark_profiles: BTreeMap<String, BTreeMap<String, String>>,
+    pub auto: ThemeSchedule,
+}
+
+#[derive(Default, Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct ThemeSchedule {
+    /// "HH:MM-HH:MM" ranges during which the `stream` profile is forced.
+    pub stream_profile_during: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Behavior {
+    /// Prevent screen blanking and suspend while an attempt is running.
+    pub inhibit_idle: bool,
+    /// Mark the current split's row while its gold is still reachable.
+    pub live_gold_indicator: bool,
+    /// Only listen for global hotkeys while no TuxSplit window has focus.
+    /// While focused, the same bindings are handled as local key presses.
+    pub hotkeys_only_unfocused: bool,
+    /// Pause global hotkeys while a text field in TuxSplit has keyboard focus.
+    pub suppress_hotkeys_while_typing: bool,
+    /// Only handle hotkeys as key presses in the focused window. Global
+    /// hotkeys need X11, so this lets TuxSplit run natively on Wayland.
+    /// Read at startup.
+    pub window_hotkeys: bool,
+    /// What happens to hotkeys pressed while a dialog waits for an answer.
+    pub actions_during_dialog: DialogActionPolicy,
+    /// How late the capture shows the game. Live times are displayed this
+    /// much earlier; recorded times are left alone. Not exported.
+    pub video_offset_ms: u32,
+    /// Takes back the latest comparison or timing method switch.
+    pub undo_setting_hotkey: Option<Hotkey>,
+    /// Switch the current comparison to Personal Best once a new PB is saved.
+    pub switch_to_pb_after_pb: bool,
+    /// How far the buttons of "Adjust Times" move a split.
+    pub adjust_step_ms: u32,
+    /// What correcting a split in "Adjust Times" does to the later ones.
+    pub adjust_later_splits: LaterSplits,
+}
+
+/// Hotkey handling while a dialog that freezes the display is open.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DialogActionPolicy {
+    /// Drop the action and say so in a toast.
+    #[default]
+    Reject,
+    /// Run the actions in order once the dialog closes.
+    Queue,
+}
+
+impl Default for Behavior {
+    fn default() -> Self {
+        Self {
+            inhibit_idle: false,
+            live_gold_indicator: false,
+            hotkeys_only_unfocused: false,
+            suppress_hotkeys_while_typing: true,
+            window_hotkeys: false,
+            actions_during_dialog: DialogActionPolicy::default(),
+            video_offset_ms: 0,
+            undo_setting_hotkey: None,
+            switch_to_pb_after_pb: false,
+            adjust_step_ms: 100,
+            adjust_later_splits: LaterSplits::default(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct DebugOptions {
+    /// Show refresh loop percentiles in a corner of the timer.
+    pub frame_stats: bool,
+    /// A refresh tick taking longer than this is logged with its breakdown.
+    pub frame_budget_ms: u64,
+    /// Maximum number of split rows refreshed per tick when many change at once.
+    pub rebuild_chunk_size: usize,
+    /// Seconds without a display refresh before the refresh loop is restarted.
+    pub stall_threshold_secs: u64,
+    /// Schedule refresh ticks on fixed boundaries of the monotonic clock, so
+    /// a late tick doesn't delay the ones after it. Read when the refresh
+    /// loop starts.
+    pub align_ticks: bool,
+}
+
+impl Default for DebugOptions {
+    fn default() -> Self {
+        Self {
+            frame_stats: false,
+            frame_budget_ms: 16,
+            rebuild_chunk_size: 8,
+            stall_threshold_secs: 3,
+            align_ticks: false,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct RaceOptions {
+    /// Name shown to the opponent.
+    pub name: Option<String>,
+    /// Port used when hosting a race.
+    pub port: u16,
+    /// Accept opponents from other machines when hosting. Off by default,
+    /// hosting then only listens on this machine.
+    pub allow_remote: bool,
+    pub racetime: RacetimeOptions,
+}
+
+impl Default for RaceOptions {
+    fn default() -> Self {
+        Self {
+            name: None,
+            port: DEFAULT_PORT,
+            allow_remote: false,
+            racetime: RacetimeOptions::default(),
+        }
+    }
+}
+
+/// racetime.gg room to follow. Only used in builds with the `racetime` feature.
+#[derive(Default, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct RacetimeOptions {
+    pub enabled: bool,
+    /// Room slug ("smw/cool-pika-1234") or URL.
+    pub room: Option<String>,
+    /// OAuth token, needed to send `.done` and `.forfeit`.
+    pub token: Option<String>,
+}
+
+impl std::fmt::Debug for RacetimeOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RacetimeOptions")
+            .field("enabled", &self.enabled)
+            .field("room", &self.room)
+            .field("token", &self.token.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct StatsOptions {
+    /// Segment times further than this many standard deviations from the
+    /// mean are left out of the consistency statistics.
+    pub outlier_z_score: f64,
+    /// List the least consistent segments in the segment statistics dialog.
+    pub show_least_consistent: bool,
+    /// Show the expected time of the running segment, given how long it has
+    /// been running, on its row.
+    pub show_projection: bool,
+    /// Past times a segment needs before its projection is shown.
+    pub projection_min_samples: usize,
+    /// Mark the segments where many of the attempts were reset.
+    pub show_danger_markers: bool,
+    /// Share of the resets, in percent, a segment needs to be marked.
+    pub danger_marker_threshold: f64,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        Self {
+            outlier_z_score: 3.0,
+            show_least_consistent: true,
+            show_projection: false,
+            projection_min_samples: 5,
+            show_danger_markers: false,
+            danger_marker_threshold: 25.0,
         }
     }
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct LayoutOptions {
+    /// Multiplier for the running timer font size.
+    pub timer_font_scale: f64,
+    /// Multiplier for the smaller segment timer font size.
+    pub small_timer_font_scale: f64,
+    /// Show a column with the time that can be saved on upcoming segments
+    /// and the time saved or lost on completed ones.
+    pub show_timesave_column: bool,
+    /// Width in pixels reserved for each time on the split rows, so they line
+    /// up in columns whatever their length. 0 sizes them to their content.
+    pub value_min_width: i32,
+    /// Give the split times, the target time and the additional info values
+    /// one shared width, so the right-hand column lines up across the timer.
+    pub align_value_column: bool,
+    /// Draw a thin bar in the delta's color next to each completed split,
+    /// as long as the delta relative to the largest one of the attempt.
+    pub show_delta_bars: bool,
+    /// Show the name of the segment being run above the running timer.
+    pub show_current_split_name: bool,
+    /// Add the name of the segment after it as a second, dimmer line.
+    pub show_next_split_name: bool,
+    /// What the split name shows before the attempt starts.
+    pub split_name_before_start: SplitNameBeforeStart,
+    /// A large split button under the timer, for touchscreens.
+    pub show_split_button: bool,
+    /// Height of that button in pixels.
+    pub split_button_height: i32,
+    /// Show the comparison's segment time below the timer with its sign,
+    /// flagging segments whose comparison split is earlier than the one
+    /// before, instead of its absolute value.
+    pub signed_segment_comparison: bool,
+    /// Show the comparison's split time at the segment below the timer, the
+    /// time since the start of the run, instead of its segment time.
+    pub cumulative_segment_comparison: bool,
+    /// Which segment the times under the splits are for.
+    pub segment_info_follows: SegmentInfoFollows,
+    /// How the split rows are laid out.
+    pub mode: LayoutMode,
+    /// The comparison shown next to the current one in the dual comparison
+    /// layout, e.g. a friend's imported PB.
+    pub secondary_comparison: Option<String>,
+    /// Run metadata shown in a line under the category, in order:
+    /// `platform`, `region`, `emulator`, or the name of a speedrun.com or
+    /// custom variable. Empty fields are left out.
+    pub run_metadata: Vec<String>,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            timer_font_scale: 1.0,
+            small_timer_font_scale: 1.0,
+            show_timesave_column: false,
+            value_min_width: 0,
+            align_value_column: false,
+            show_delta_bars: false,
+            show_current_split_name: false,
+            show_next_split_name: false,
+            split_name_before_start: SplitNameBeforeStart::default(),
+            show_split_button: false,
+            split_button_height: 96,
+            signed_segment_comparison: false,
+            cumulative_segment_comparison: false,
+            segment_info_follows: SegmentInfoFollows::default(),
+            mode: LayoutMode::default(),
+            secondary_comparison: None,
+            run_metadata: Vec::new(),
+        }
+    }
+}
+
+#[derive(Default, Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LayoutMode {
+    /// One comparison per row.
+    #[default]
+    Standard,
+    /// The current comparison and `secondary-comparison` side by side, with
+    /// column headers.
+    DualComparison,
+}
+
+/// Which segment the best, comparison and PB segment times under the splits
+/// are for.
+#[derive(Default, Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SegmentInfoFollows {
+    /// The selected row while no attempt is running, the first segment if
+    /// none is. The current split during an attempt.
+    #[default]
+    SelectionWhenIdle,
+    /// The current split, whatever is selected. The first segment before
+    /// the attempt starts.
+    CurrentSplit,
+}
+
+/// What the current split name shows while the timer hasn't started.
+#[derive(Default, Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SplitNameBeforeStart {
+    /// The first segment, the one the attempt starts on.
+    #[default]
+    FirstSegment,
+    /// Nothing until the timer starts.
+    Hidden,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct StorageOptions {
+    /// Size cap of the trash holding copies of splits files taken before
+    /// destructive operations. 0 turns the copies off.
+    pub trash_mb: u64,
+}
+
+impl Default for StorageOptions {
+    fn default() -> Self {
+        Self { trash_mb: 50 }
+    }
+}
+
+#[derive(Default, Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct FeedbackOptions {
+    /// Briefly tint the window when a split is taken.
+    pub flash_on_split: SplitFlashMode,
+    /// Flash the start of an attempt too, the way `flash_on_split` does.
+    pub flash_on_start: bool,
+    /// Sound file played on every start and split.
+    pub confirm_sound: Option<PathBuf>,
+}
+
+/// The window opened by "Detach Timer Display", showing only the running
+/// timer, e.g. on the capture monitor.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct DetachedTimerOptions {
+    /// Multiplier for the timer font size in the detached window.
+    pub font_scale: f64,
+    /// Connector of the monitor the window was last closed on, e.g. `HDMI-1`.
+    pub last_monitor: Option<String>,
+    /// Last size of the window on each monitor, by connector.
+    pub geometry: BTreeMap<String, DetachedGeometry>,
+    /// Show the current split name in this window, whatever
+    /// `layout.show-current-split-name` says. Unset follows the layout.
+    pub show_current_split_name: Option<bool>,
+}
+
+impl Default for DetachedTimerOptions {
+    fn default() -> Self {
+        Self {
+            font_scale: 2.0,
+            last_monitor: None,
+            geometry: BTreeMap::new(),
+            show_current_split_name: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct DetachedGeometry {
+    pub width: i32,
+    pub height: i32,
+    pub fullscreen: bool,
+}
+
+impl Default for DetachedGeometry {
+    fn default() -> Self {
+        Self {
+            width: 480,
+            height: 160,
+            fullscreen: false,
+        }
+    }
+}
+
+/// Drilling a single segment from its context menu.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct DrillOptions {
+    /// Reps before the summary is shown. 0 drills until stopped.
+    pub reps: u32,
+    /// How long a finished rep stays on screen before the timer resets.
+    pub pause_ms: u32,
+}
+
+impl Default for DrillOptions {
+    fn default() -> Self {
+        Self {
+            reps: 10,
+            pause_ms: 1500,
+        }
+    }
+}
+
+/// Pausing the timer when the game freezes.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct FreezeWatchOptions {
+    /// Command name of the game process, as in `/proc/<pid>/comm`. Nothing
+    /// is watched without one.
+    pub process: Option<String>,
+    /// Seconds without CPU time used that count as frozen. 0 only counts a
+    /// stopped or zombie process.
+    pub idle_secs: u32,
+    pub action: FreezeAction,
+    /// Resume a timer paused by the watch once the game responds again.
+    pub resume_on_recovery: bool,
+}
+
+impl Default for FreezeWatchOptions {
+    fn default() -> Self {
+        Self {
+            process: None,
+            idle_secs: 10,
+            action: FreezeAction::default(),
+            resume_on_recovery: true,
+        }
+    }
+}
+
+/// Timing traces to attach to bug reports. Read at startup.
+#[derive(Default, Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct LoggingOptions {
+    /// Log timer commands, splits file loads and saves, and refresh ticks
+    /// with their timings, on the `tuxsplit::timing` target.
+    pub trace_timing: bool,
+    /// File the timing traces are appended to instead of the standard
+    /// output. Relative to the config directory.
+    pub trace_file: Option<PathBuf>,
+}
+
+/// A run salvaged from a splits file that didn't parse, see
+/// [`crate::io::recovery`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SplitsRecovery {
+    pub segments: usize,
+    /// Segments and other elements left out as unreadable.
+    pub skipped: usize,
+}
+
+impl SplitsRecovery {
+    pub fn describe(&self) -> String {
+        format!(
+            "Recovered {} segments from a damaged splits file, times may be incomplete",
+            self.segments
+        )
+    }
+}
+
+/// The hotkey bindings, with the profile picked in `active-profile` in
+/// place of them. See [`crate::io::hotkey_profiles`].
+#[derive(Default, Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct HotkeyOptions {
+    /// The default profile.
+    #[serde(flatten)]
+    pub bindings: HotkeyConfig,
+    /// Name of the profile file to bind instead, without its extension.
+    pub active_profile: Option<String>,
+    /// Bindings read from the active profile.
+    #[serde(skip)]
+    profile: Option<HotkeyConfig>,
+    /// Why the active profile couldn't be read, while the default one is
+    /// bound in its place.
+    #[serde(skip)]
+    profile_error: Option<String>,
+}
+
+impl HotkeyOptions {
+    /// The bindings in use.
+    pub fn active(&self) -> &HotkeyConfig {
+        self.profile.as_ref().unwrap_or(&self.bindings)
+    }
+
+    pub fn profile_error(&self) -> Option<&str> {
+        self.profile_error.as_deref()
+    }
+}
+
+/// What happens when the watched game freezes.
+#[derive(Default, Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FreezeAction {
+    /// Pause the timer and say so in a toast.
+    #[default]
+    Pause,
+    /// Only show a toast.
+    NotifyOnly,
+}
+
+/// Foot pedals and gamepad buttons bound to timer actions, with the
+/// `gamepad` feature.
+#[derive(Default, Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct GamepadOptions {
+    /// Name of the device to listen to. Nothing is listened to without one.
+    pub device: Option<String>,
+    /// Buttons of the device and the action each one runs.
+    pub buttons: Vec<PadBinding>,
+}
+
+/// Something a hotkey, a pedal button or the split button can do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HotkeyAction {
+    Split,
+    Reset,
+    Undo,
+    Skip,
+    Pause,
+    UndoAllPauses,
+    PreviousComparison,
+    NextComparison,
+    ToggleTimingMethod,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct PadBinding {
+    /// The button as the device reports it, e.g. `South`, or `code-289` for
+    /// buttons without a gamepad meaning.
+    pub button: String,
+    pub action: HotkeyAction,
+}
+
+/// What a split flash tints. Never changes the layout.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SplitFlashMode {
+    #[default]
+    Off,
+    Border,
+    Background,
+}
+
 #[derive(Default, Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
 #[serde(default)]
@@ -105,6 +887,23 @@ struct Connections {
     twitch: Option<String>,
 }
 
+/// State that belongs to a single splits file rather than to the whole app.
+/// Stored in the config, keyed by the splits path.
+#[derive(Default, Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct SplitsState {
+    /// Explicit timing method chosen by the user for this file.
+    pub timing_method: Option<TimingMethod>,
+    /// CSS loaded on top of the theme while this file is the active run.
+    pub css: Option<String>,
+    /// The auto splitter was seen starting this file's runs.
+    pub auto_splitter_starts: bool,
+    /// Starts made by the auto splitter are undone, the runs are started by
+    /// hand, e.g. for a countdown offset.
+    pub ignore_auto_splitter_start: bool,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
 #[serde(default)]
@@ -113,6 +912,26 @@ pub struct Format {
     pub timer: TimeFormat,
     pub segment: TimeFormat,
     pub comparison: TimeFormat,
+    /// Show more decimals on the running timer while closing in on a gold or the comparison.
+    pub adaptive_precision: bool,
+    /// How deltas drop the digits past the shown ones. This alone decides
+    /// it: the `rounding` of the formats only applies to times, so deltas
+    /// can be rounded while split times stay truncated, and the other way
+    /// around.
+    pub delta_rounding: Rounding,
+    /// Where deltas read as time left, positive while ahead.
+    pub invert_deltas: InvertDeltas,
+}
+
+/// Widgets showing deltas with the sign flipped. Their colors don't change.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+pub struct InvertDeltas {
+    /// The deltas in the split list.
+    pub splits: bool,
+    /// The delta readouts under the timer.
+    pub info: bool,
 }
 
 impl Default for Format {
@@ -122,6 +941,9 @@ impl Default for Format {
             timer: TimeFormat::from_preset(TimeFormatPreset::ShowDecimals),
             segment: TimeFormat::from_preset(TimeFormatPreset::ShowDecimals),
             comparison: TimeFormat::from_preset(TimeFormatPreset::ShowDecimals),
+            adaptive_precision: false,
+            delta_rounding: Rounding::Truncate,
+            invert_deltas: InvertDeltas::default(),
         }
     }
 }
@@ -138,6 +960,10 @@ pub struct AdditionalInfoVisibility {
     pub show_current_pace: bool,
     pub show_total_playtime: bool,
     pub show_pb_chance: bool,
+    pub show_gold_pace: bool,
+    pub show_segment_timer: bool,
+    pub show_remaining_best: bool,
+    pub show_race: bool,
 }
 
 impl Default for AdditionalInfoVisibility {
@@ -150,14 +976,86 @@ impl Default for AdditionalInfoVisibility {
             show_current_pace: false,
             show_total_playtime: false,
             show_pb_chance: false,
+            show_gold_pace: false,
+            show_segment_timer: false,
+            show_remaining_best: false,
+            show_race: false,
         }
     }
 }
 
+/// TuxSplit's data directory: `TUXSPLIT_DATADIR`, else
+/// `$XDG_DATA_HOME/tuxsplit`, else `~/.local/share/tuxsplit`. A relative
+/// `XDG_DATA_HOME` is ignored, as the XDG spec asks.
+pub fn data_dir() -> Option<PathBuf> {
+    if let Ok(dir) = std::env::var("TUXSPLIT_DATADIR") {
+        Some(std::path::absolute(&dir).unwrap_or_else(|_| PathBuf::from(dir)))
+    } else if let Ok(dir) = std::env::var("XDG_DATA_HOME")
+        && Path::new(&dir).is_absolute()
+    {
+        Some(PathBuf::from(dir).join("tuxsplit"))
+    } else {
+        std::env::var("HOME").ok().map(|home| {
+            PathBuf::from(home)
+                .join(".local")
+                .join("share")
+                .join("tuxsplit")
+        })
+    }
+}
+
+/// Resolves a path read from the config. Absolute paths are kept. A relative
+/// one is looked up in each of `bases`, then in `cwd`, and the first place it
+/// exists wins. When it exists nowhere it goes in the first base, so a file
+/// the config names ends up in the same place wherever TuxSplit was launched
+/// from.
+pub fn resolve_path(path: &Path, bases: &[PathBuf], cwd: Option<&Path>) -> PathBuf {
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    let candidates: Vec<PathBuf> = bases
+        .iter()
+        .map(PathBuf::as_path)
+        .chain(cwd)
+        .map(|base| base.join(path))
+        .collect();
+    candidates
+        .iter()
+        .find(|candidate| candidate.exists())
+        .or_else(|| candidates.first())
+        .cloned()
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
 impl Config {
     pub fn parse(path: impl AsRef<Path>) -> Option<Self> {
+        let path = path.as_ref();
         let buf = fs::read(path).ok()?;
-        serde_yaml::from_slice(&buf).ok()
+        let mut config: Self = serde_yaml::from_slice(&buf).ok()?;
+        config.base_dir = std::path::absolute(path)
+            .ok()
+            .and_then(|path| path.parent().map(Path::to_path_buf));
+        Some(config)
+    }
+
+    /// Where relative paths in this config are looked up: the config's own
+    /// directory, then the data directory.
+    fn path_bases(&self) -> Vec<PathBuf> {
+        self.base_dir.iter().cloned().chain(data_dir()).collect()
+    }
+
+    /// `path` as named in the config, made absolute. See [`resolve_path`].
+    pub fn resolve(&self, path: &Path) -> PathBuf {
+        let cwd = std::env::current_dir().ok();
+        resolve_path(path, &self.path_bases(), cwd.as_deref())
+    }
+
+    /// The loaded splits file, resolved.
+    pub fn splits_path(&self) -> Option<PathBuf> {
+        self.general
+            .splits
+            .as_deref()
+            .map(|path| self.resolve(path))
     }
 
     pub fn save(&self, path: impl AsRef<Path>) -> Result<(), std::io::Error> {
@@ -166,22 +1064,154 @@ impl Config {
         Ok(())
     }
 
+    /// Copy of the config with only the sections that make up a shareable
+    /// setup: the general settings without their file paths, the window,
+    /// style, stats, layout and format settings, how the detached timer
+    /// shows things and the hotkey bindings. Hosts, credentials, sound and
+    /// storage locations, per-file state and the like stay on this machine.
+    /// The theme is shared on its own.
+    pub fn portable(&self) -> Config {
+        let mut config = Config::default();
+        config.apply_imported(self.clone());
+        config
+    }
+
+    /// Takes over the sections of an imported config that `portable` shares.
+    /// Everything else, including the splits and auto-splitter paths, stays
+    /// as it is here. The new bindings are registered by the caller.
+    pub fn apply_imported(&mut self, imported: Config) {
+        let splits = self.general.splits.take();
+        let auto_splitter = self.general.auto_splitter.take();
+        self.general = imported.general;
+        self.general.splits = splits;
+        self.general.auto_splitter = auto_splitter;
+        self.window = imported.window;
+        self.style = imported.style;
+        self.stats = imported.stats;
+        self.layout = imported.layout;
+        self.detached_timer.font_scale = imported.detached_timer.font_scale;
+        self.detached_timer.show_current_split_name =
+            imported.detached_timer.show_current_split_name;
+        self.format = imported.format;
+        self.hotkeys.bindings = imported.hotkeys.bindings;
+    }
+
+    /// Reads `hotkeys.active-profile` from the profiles in `dir` and binds
+    /// it. A profile that can't be read leaves the default bindings in
+    /// place, and the reason is returned and kept for the keybindings
+    /// overlay.
+    pub fn load_hotkey_profile(&mut self, dir: &Path) -> Option<ProfileError> {
+        let (hotkeys, err) = resolve_profile(
+            dir,
+            self.hotkeys.active_profile.as_deref(),
+            self.hotkeys.bindings,
+        );
+        self.hotkeys.profile = self.hotkeys.active_profile.is_some().then_some(hotkeys);
+        self.hotkeys.profile_error = err.as_ref().map(ToString::to_string);
+        if let Some(err) = &err {
+            warn!("Using the default hotkeys: {err}");
+        }
+        err
+    }
+
+    /// Switches to the profile `name` in `dir`, or back to the default one.
+    pub fn set_hotkey_profile(&mut self, dir: &Path, name: Option<String>) -> Option<ProfileError> {
+        self.hotkeys.active_profile = name;
+        self.load_hotkey_profile(dir)
+    }
+
     pub fn parse_run(&self) -> Option<Run> {
-        let path = self.general.splits.clone()?;
+        self.read_run().map(|(run, ..)| run)
+    }
+
+    /// Reads the splits like [`Self::parse_run`], to load them into the
+    /// timer. A run recovered from a damaged file is reported in
+    /// [`Self::splits_recovery`] until the next load, and the damaged file
+    /// is copied to the trash first, so saving over it can be undone.
+    pub fn load_run(&mut self) -> Option<Run> {
+        self.splits_recovery = None;
+        self.splits_integrity = None;
+        let (run, recovery, integrity) = self.read_run()?;
+        if recovery.is_some() {
+            self.trash_splits_file("Recover damaged splits");
+        }
+        self.splits_recovery = recovery;
+        self.splits_integrity = integrity;
+        Some(run)
+    }
+
+    /// What the loaded run is missing from its damaged splits file.
+    pub fn splits_recovery(&self) -> Option<SplitsRecovery> {
+        self.splits_recovery
+    }
+
+    /// How the times of the loaded run compared with the hash stored in its
+    /// file. Kept until the next load: saving stores a new hash, but times
+    /// edited elsewhere stay in the run.
+    pub fn splits_integrity(&self) -> Option<&IntegrityStatus> {
+        self.splits_integrity.as_ref()
+    }
+
+    /// The splits, salvaged with [`recover_run`] when the file doesn't parse.
+    /// Only a file that parses is checked against its hash.
+    fn read_run(&self) -> Option<(Run, Option<SplitsRecovery>, Option<IntegrityStatus>)> {
+        let path = self.splits_path()?;
+        let span = splits_io_span("load", &path);
+        let _entered = span.enter();
+        set_splits_path(&path);
         let file = fs::read(&path).ok()?;
-        let mut run = composite::parse(&file, Some(&path)).ok()?.run;
+        span.record("bytes", file.len());
+        let (mut run, recovery, integrity) = match composite::parse(&file, Some(&path)) {
+            Ok(parsed) => {
+                let status = integrity::check(&parsed.run);
+                match &status {
+                    IntegrityStatus::Modified { stored, actual } => warn!(
+                        "Splits times changed since they were hashed (stored {}, now {})",
+                        integrity::short_hash(stored),
+                        integrity::short_hash(actual)
+                    ),
+                    status => debug!("Splits integrity: {}", status.describe()),
+                }
+                (parsed.run, None, Some(status))
+            }
+            Err(err) => {
+                let recovered = recover_run(&file)?;
+                warn!(
+                    "Could not read {} ({err}), recovered {} segments and left out {} damaged elements",
+                    path.display(),
+                    recovered.segments,
+                    recovered.skipped
+                );
+                let recovery = SplitsRecovery {
+                    segments: recovered.segments,
+                    skipped: recovered.skipped,
+                };
+                (recovered.run, Some(recovery), None)
+            }
+        };
         run.fix_splits();
-        Some(run)
+        Some((run, recovery, integrity))
     }
 
-    pub fn parse_run_or_default(&self) -> Run {
-        self.parse_run().unwrap_or_else(|| {
-            let mut run = Run::new();
-            run.set_game_name("Game");
-            run.set_category_name("Category");
-            run.push_segment(Segment::new("Time"));
-            run
-        })
+    /// The loaded splits, or a run of one segment without any. The timer
+    /// can't hold a run without segments, so one is added to splits that
+    /// have none.
+    pub fn parse_run_or_default(&mut self) -> Run {
+        let mut run = match self.load_run() {
+            Some(run) if run.is_empty() => {
+                warn!("The splits have no segments, adding one to time");
+                run
+            }
+            Some(run) => return run,
+            None => {
+                let mut run = Run::new();
+                run.set_game_name("Game");
+                run.set_category_name("Category");
+                run
+            }
+        };
+        run.push_segment(Segment::new("Time"));
+        run
     }
 
     pub fn is_game_time(&self) -> bool {
@@ -190,70 +1220,143 @@ impl Config {
 
     pub fn set_splits_path(&mut self, path: PathBuf) {
         self.general.splits = Some(path);
+        self.splits_integrity = None;
     }
 
-    pub fn disable_hotkey_system(&mut self) {
-        if self.hotkey_system.is_none() {
-            return;
-        }
-        let hotkey_system: &mut HotkeySystem = self.hotkey_system.get_or_insert(
-            // Will never happen
-            HotkeySystem::with_config(
-                Timer::new(self.parse_run_or_default())
-                    .expect("Failed to create timer")
-                    .into_shared(),
-                self.hotkeys,
-            )
-            .expect("Failed to create HotkeySystem"),
-        );
-        hotkey_system.deactivate();
+    /// Marks the start of a session: attempts newer than the run's latest
+    /// attempt at this point belong to the session.
+    pub fn begin_session(&mut self, run: &Run) {
+        self.session_start_attempt = run
+            .attempt_history()
+            .iter()
+            .map(livesplit_core::run::Attempt::index)
+            .max()
+            .unwrap_or(0);
     }
 
-    pub fn enable_hotkey_system(&mut self) {
-        if self.hotkey_system.is_none() {
-            return;
-        }
-        let hotkey_system: &mut HotkeySystem = self.hotkey_system.get_or_insert(
-            // Will never happen
-            HotkeySystem::with_config(
-                Timer::new(self.parse_run_or_default())
-                    .expect("Failed to create timer")
-                    .into_shared(),
-                self.hotkeys,
-            )
-            .expect("Failed to create HotkeySystem"),
-        );
-        hotkey_system.activate();
+    /// Index of the last attempt made before the current session started.
+    pub fn session_start_attempt(&self) -> i32 {
+        self.session_start_attempt
+    }
+
+    /// State stored for the currently loaded splits file, if any.
+    pub fn splits_state(&self) -> Option<&SplitsState> {
+        self.general
+            .splits
+            .as_ref()
+            .and_then(|path| self.splits_state.get(path))
     }
 
-    pub fn create_hotkey_system(&mut self, timer: SharedTimer) -> Option<()> {
-        let hotkey_system_res = HotkeySystem::with_config(timer, self.hotkeys);
-        if let Ok(hotkey_system) = hotkey_system_res {
-            self.hotkey_system = Some(hotkey_system);
-            Some(())
+    /// Mutable state for the currently loaded splits file, created on demand.
+    /// Returns `None` when no splits file is loaded.
+    pub fn splits_state_mut(&mut self) -> Option<&mut SplitsState> {
+        let path = self.general.splits.clone()?;
+        Some(self.splits_state.entry(path).or_default())
+    }
+
+    /// Timing method the given run should be displayed with.
+    /// See `resolve_timing_method` for the precedence rules.
+    pub fn timing_method_for(&self, run: &Run) -> TimingMethod {
+        resolve_timing_method(
+            self.splits_state().and_then(|s| s.timing_method),
+            run_timing_method(run),
+            self.general.timing_method,
+        )
+    }
+
+    /// Persists a user-chosen timing method. With a splits file loaded the
+    /// choice is stored for that file only; otherwise it becomes the global default.
+    pub fn set_timing_method_override(&mut self, method: TimingMethod) {
+        if let Some(state) = self.splits_state_mut() {
+            state.timing_method = Some(method);
         } else {
-            None
+            self.general.timing_method = Some(method);
         }
     }
 
     pub fn configure_timer(&self, timer: &mut Timer) {
-        if self.is_game_time() {
-            timer.set_current_timing_method(TimingMethod::GameTime);
-        }
+        let method = self.timing_method_for(timer.run());
+        timer.set_current_timing_method(method);
         if let Some(comparison) = &self.general.comparison {
             timer.set_current_comparison(&**comparison).ok();
         }
     }
 
+    pub fn trash(&self) -> Trash {
+        Trash::new(default_trash_dir(), self.storage.trash_mb * 1024 * 1024)
+    }
+
+    /// Stores `timer`'s run in the trash before `operation` changes it,
+    /// attempts not saved yet included.
+    pub fn trash_splits(&self, timer: &Timer, operation: &str) {
+        let Some(path) = self.splits_path() else {
+            return;
+        };
+        let span = splits_io_span("trash", &path);
+        let _entered = span.enter();
+        let stored = self
+            .splits_text(timer)
+            .inspect(|text| {
+                span.record("bytes", text.len());
+            })
+            .map_err(|_| std::io::Error::other("the splits could not be serialized"))
+            .and_then(|text| self.trash().store(&path, text.as_bytes(), operation));
+        if let Err(e) = stored {
+            error!("Could not store {} in the trash: {e}", path.display());
+        }
+    }
+
+    /// Copies the splits file itself into the trash before `operation`
+    /// writes over it.
+    fn trash_splits_file(&self, operation: &str) {
+        let Some(path) = self.splits_path() else {
+            return;
+        };
+        if let Err(e) = self.trash().snapshot(&path, operation) {
+            error!("Could not copy {} to the trash: {e}", path.display());
+        }
+    }
+
+    /// The splits file's text for `timer`'s run.
+    fn splits_text(&self, timer: &Timer) -> Result<String, std::fmt::Error> {
+        let mut run = run_to_save(timer);
+        if self.general.padded_gold_comparison.persist {
+            persist_padded_golds(&mut run);
+        }
+        let mut buf = String::new();
+        save_run_stamped(run, &mut buf)?;
+        Ok(buf)
+    }
+
     pub fn save_splits(&self, timer: &Timer) {
-        if let Some(path) = &self.general.splits {
-            let mut buf = String::new();
-            let _ = save_timer(timer, &mut buf);
+        if let Some(path) = self.splits_path() {
+            let span = splits_io_span("save", &path);
+            let _entered = span.enter();
+            let Ok(buf) = self.splits_text(timer) else {
+                error!("Could not serialize the splits for {}", path.display());
+                return;
+            };
+            span.record("bytes", buf.len());
             // FIXME: Don't ignore not being able to save.
-            let _ = fs::write(path, &buf);
+            let _ = write_if_changed(&path, &buf);
         }
     }
 
+    /// Writes the splits file again from scratch, even if it's up to date.
+    /// Returns its size before and after.
+    pub fn compact_splits(&self, timer: &Timer) -> Result<(u64, u64), String> {
+        let path = self.splits_path().ok_or("No splits file is loaded")?;
+        let before = fs::metadata(&path).map_or(0, |metadata| metadata.len());
+        self.trash_splits_file("Compact splits file");
+        let span = splits_io_span("compact", &path);
+        let _entered = span.enter();
+        let buf = self.splits_text(timer).map_err(|err| err.to_string())?;
+        span.record("bytes", buf.len());
+        write_fresh(&path, &buf)
+            .map_err(|err| format!("Could not write {}: {err}", path.display()))?;
+        Ok((before, buf.len() as u64))
+    }
+
     pub const fn setup_logging(&self) {
         // TODO: Setup logging
         // if let Some(log) = &self.log {
@@ -291,7 +1394,7 @@ impl Config {
 
     pub fn maybe_load_auto_splitter(&self, runtime: &auto_splitting::Runtime) {
         if let Some(auto_splitter) = &self.general.auto_splitter
-            && let Err(e) = runtime.load_script_blocking(auto_splitter.clone())
+            && let Err(e) = runtime.load_script_blocking(self.resolve(auto_splitter))
         {
             error!("Auto Splitter failed to load: {}", &e); // TODO: Create a custom error that
             // pops up in the UI
@@ -302,3 +1405,345 @@ impl Config {
         std::sync::Arc::new(std::sync::RwLock::new(self))
     }
 }
+
+/// Resolves which timing method a run is displayed with.
+///
+/// Precedence, from strongest to weakest:
+/// 1. `file_override`: explicit choice stored in the per-splits state.
+/// 2. `run_default`: the method implied by the run itself (see `run_timing_method`).
+/// 3. `global`: the `general.timing-method` config value.
+///
+/// Falls back to Real Time when none of them is set.
+pub fn resolve_timing_method(
+    file_override: Option<TimingMethod>,
+    run_default: Option<TimingMethod>,
+    global: Option<TimingMethod>,
+) -> TimingMethod {
+    file_override
+        .or(run_default)
+        .or(global)
+        .unwrap_or(TimingMethod::RealTime)
+}
+
+/// Infers the timing method a run was recorded with.
+///
+/// .lss files don't store one explicitly, so we look at the personal best's
+/// final split: if it carries game time the category is timed in game time,
+/// if it only carries real time it is timed in real time. Game time on some
+/// splits only, e.g. from a load remover that stopped hooking, doesn't count.
+/// Runs without a PB yield `None`.
+pub fn run_timing_method(run: &Run) -> Option<TimingMethod> {
+    let final_split = run.segments().last()?.personal_best_split_time();
+    if final_split.game_time.is_some() {
+        Some(TimingMethod::GameTime)
+    } else if final_split.real_time.is_some() {
+        Some(TimingMethod::RealTime)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod hotkey_profile_tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn active_profile_sits_next_to_the_default_bindings() {
+        use livesplit_core::hotkey::KeyCode;
+
+        let yaml = "hotkeys:\n  split: Numpad1\n  active-profile: laptop\n";
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.hotkeys.active_profile.as_deref(), Some("laptop"));
+        let split = config.hotkeys.bindings.split.map(|hotkey| hotkey.key_code);
+        assert_eq!(split, Some(KeyCode::Numpad1));
+
+        // The profile directory has no laptop.yaml
+        let dir = temp_dir("config-profiles");
+        assert!(config.load_hotkey_profile(&dir).is_some());
+        assert_eq!(*config.hotkeys.active(), config.hotkeys.bindings);
+        assert!(config.hotkeys.profile_error().is_some());
+        assert!(config.set_hotkey_profile(&dir, None).is_none());
+        assert!(config.hotkeys.profile_error().is_none());
+
+        let saved = serde_yaml::to_string(&config.portable()).unwrap();
+        let reloaded: Config = serde_yaml::from_str(&saved).unwrap();
+        assert_eq!(reloaded.hotkeys.bindings, config.hotkeys.bindings);
+    }
+}
+
+#[cfg(test)]
+mod general_tests {
+    use super::*;
+
+    #[test]
+    fn configs_from_before_padded_golds_still_load() {
+        let yaml = "general:\n  splits: game.lss\n  additional-info: {}\n";
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            config.general.padded_gold_comparison,
+            PaddedGoldComparison::default()
+        );
+    }
+}
+
+#[cfg(test)]
+mod timing_method_tests {
+    use super::*;
+    use livesplit_core::{Time, TimeSpan};
+
+    const N: Option<TimingMethod> = None;
+    const R: Option<TimingMethod> = Some(TimingMethod::RealTime);
+    const G: Option<TimingMethod> = Some(TimingMethod::GameTime);
+
+    fn run_with_pb(time: Time) -> Run {
+        let mut run = Run::new();
+        let mut segment = Segment::new("S1");
+        segment.set_personal_best_split_time(time);
+        run.push_segment(segment);
+        run
+    }
+
+    #[test]
+    fn resolve_precedence_all_combinations() {
+        use TimingMethod::{GameTime, RealTime};
+
+        // File override, run default, global default, resolved method
+        let table = [
+            (N, N, N, RealTime),
+            (N, N, R, RealTime),
+            (N, N, G, GameTime),
+            (N, R, N, RealTime),
+            (N, R, R, RealTime),
+            (N, R, G, RealTime),
+            (N, G, N, GameTime),
+            (N, G, R, GameTime),
+            (N, G, G, GameTime),
+            (R, N, N, RealTime),
+            (R, N, R, RealTime),
+            (R, N, G, RealTime),
+            (R, R, N, RealTime),
+            (R, R, R, RealTime),
+            (R, R, G, RealTime),
+            (R, G, N, RealTime),
+            (R, G, R, RealTime),
+            (R, G, G, RealTime),
+            (G, N, N, GameTime),
+            (G, N, R, GameTime),
+            (G, N, G, GameTime),
+            (G, R, N, GameTime),
+            (G, R, R, GameTime),
+            (G, R, G, GameTime),
+            (G, G, N, GameTime),
+            (G, G, R, GameTime),
+            (G, G, G, GameTime),
+        ];
+        for (file_override, run_default, global, expected) in table {
+            assert_eq!(
+                resolve_timing_method(file_override, run_default, global),
+                expected,
+                "override={file_override:?} run={run_default:?} global={global:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn file_override_beats_run_and_global() {
+        assert_eq!(
+            resolve_timing_method(
+                Some(TimingMethod::RealTime),
+                Some(TimingMethod::GameTime),
+                Some(TimingMethod::GameTime)
+            ),
+            TimingMethod::RealTime
+        );
+    }
+
+    #[test]
+    fn run_default_beats_global() {
+        assert_eq!(
+            resolve_timing_method(
+                None,
+                Some(TimingMethod::GameTime),
+                Some(TimingMethod::RealTime)
+            ),
+            TimingMethod::GameTime
+        );
+    }
+
+    #[test]
+    fn nothing_set_defaults_to_real_time() {
+        assert_eq!(
+            resolve_timing_method(None, None, None),
+            TimingMethod::RealTime
+        );
+    }
+
+    #[test]
+    fn run_timing_method_detects_game_time() {
+        let time = Time::new()
+            .with_real_time(Some(TimeSpan::from_seconds(10.0)))
+            .with_game_time(Some(TimeSpan::from_seconds(8.0)));
+        assert_eq!(
+            run_timing_method(&run_with_pb(time)),
+            Some(TimingMethod::GameTime)
+        );
+    }
+
+    #[test]
+    fn run_timing_method_detects_real_time_only() {
+        let time = Time::new().with_real_time(Some(TimeSpan::from_seconds(10.0)));
+        assert_eq!(
+            run_timing_method(&run_with_pb(time)),
+            Some(TimingMethod::RealTime)
+        );
+    }
+
+    #[test]
+    fn run_timing_method_needs_game_time_on_the_final_split() {
+        let real = TimeSpan::from_seconds(10.0);
+        let mut run = run_with_pb(
+            Time::new()
+                .with_real_time(Some(real))
+                .with_game_time(Some(real)),
+        );
+        let mut last = Segment::new("S2");
+        last.set_personal_best_split_time(Time::new().with_real_time(Some(real)));
+        run.push_segment(last);
+        assert_eq!(run_timing_method(&run), Some(TimingMethod::RealTime));
+    }
+
+    #[test]
+    fn explicit_choice_beats_game_time_in_the_pb() {
+        let mut config = Config::default();
+        config.set_splits_path(PathBuf::from("/tmp/igt.lss"));
+        let time = Time::new()
+            .with_real_time(Some(TimeSpan::from_seconds(10.0)))
+            .with_game_time(Some(TimeSpan::from_seconds(8.0)));
+        let run = run_with_pb(time);
+        assert_eq!(config.timing_method_for(&run), TimingMethod::GameTime);
+        config.set_timing_method_override(TimingMethod::RealTime);
+        assert_eq!(config.timing_method_for(&run), TimingMethod::RealTime);
+    }
+
+    #[test]
+    fn run_timing_method_none_without_pb() {
+        assert_eq!(run_timing_method(&run_with_pb(Time::new())), None);
+    }
+
+    #[test]
+    fn override_is_stored_per_splits_file() {
+        let mut config = Config::default();
+        config.set_splits_path(PathBuf::from("/tmp/a.lss"));
+        config.set_timing_method_override(TimingMethod::GameTime);
+        assert_eq!(config.general.timing_method, None);
+
+        let run = run_with_pb(Time::new().with_real_time(Some(TimeSpan::from_seconds(1.0))));
+        assert_eq!(config.timing_method_for(&run), TimingMethod::GameTime);
+
+        // A different file does not see the override
+        config.set_splits_path(PathBuf::from("/tmp/b.lss"));
+        assert_eq!(config.timing_method_for(&run), TimingMethod::RealTime);
+    }
+
+    #[test]
+    fn override_without_splits_file_is_global() {
+        let mut config = Config::default();
+        config.set_timing_method_override(TimingMethod::GameTime);
+        assert_eq!(config.general.timing_method, Some(TimingMethod::GameTime));
+    }
+}
+
+#[cfg(test)]
+mod path_tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn absolute_paths_are_kept() {
+        let absolute = std::env::temp_dir().join("game.lss");
+        assert_eq!(
+            resolve_path(&absolute, &[PathBuf::from("/elsewhere")], None),
+            absolute
+        );
+    }
+
+    #[test]
+    fn relative_paths_prefer_the_first_base_they_exist_in() {
+        let dir = temp_dir("paths-bases");
+        let config_dir = dir.join("config");
+        let data = dir.join("data");
+        let cwd = dir.join("cwd");
+        for d in [&config_dir, &data, &cwd] {
+            fs::create_dir_all(d.join("splits")).unwrap();
+        }
+        let bases = [config_dir.clone(), data.clone()];
+        let relative = Path::new("splits/game.lss");
+
+        fs::write(cwd.join(relative), "").unwrap();
+        assert_eq!(
+            resolve_path(relative, &bases, Some(&cwd)),
+            cwd.join(relative)
+        );
+
+        fs::write(data.join(relative), "").unwrap();
+        assert_eq!(
+            resolve_path(relative, &bases, Some(&cwd)),
+            data.join(relative)
+        );
+
+        fs::write(config_dir.join(relative), "").unwrap();
+        assert_eq!(
+            resolve_path(relative, &bases, Some(&cwd)),
+            config_dir.join(relative)
+        );
+    }
+
+    #[test]
+    fn missing_relative_paths_go_in_the_first_base() {
+        let dir = temp_dir("paths-missing");
+        let bases = [dir.join("config"), dir.join("data")];
+        assert_eq!(
+            resolve_path(Path::new("new.lss"), &bases, Some(&dir)),
+            dir.join("config").join("new.lss")
+        );
+        // Without any base the working directory is all there is
+        assert_eq!(
+            resolve_path(Path::new("new.lss"), &[], Some(&dir)),
+            dir.join("new.lss")
+        );
+    }
+
+    #[test]
+    fn splits_are_resolved_next_to_the_config_file() {
+        let dir = temp_dir("paths-config");
+        fs::write(dir.join("game.lss"), "").unwrap();
+        let config_file = dir.join("config.yaml");
+        fs::write(&config_file, "general:\n  splits: game.lss\n").unwrap();
+
+        let config = Config::parse(&config_file).unwrap();
+        assert_eq!(config.general.splits, Some(PathBuf::from("game.lss")));
+        assert_eq!(config.splits_path(), Some(dir.join("game.lss")));
+    }
+
+    #[test]
+    fn splits_without_segments_get_one_to_time() {
+        let dir = temp_dir("paths-empty");
+        let splits = dir.join("empty.lss");
+        fs::write(
+            &splits,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+             <Run version=\"1.7.0\"><GameIcon/><GameName>Celeste</GameName>\
+             <CategoryName>Any%</CategoryName><Offset>00:00:00</Offset>\
+             <AttemptCount>0</AttemptCount><AttemptHistory/><Segments/></Run>",
+        )
+        .unwrap();
+        let mut config = Config::default();
+        config.set_splits_path(splits);
+
+        let run = config.parse_run_or_default();
+        assert_eq!(run.game_name(), "Celeste");
+        assert_eq!(run.len(), 1);
+        assert!(livesplit_core::Timer::new(run).is_ok());
+    }
+}
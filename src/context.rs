@@ -14,16 +14,37 @@ use std::path::{Path, PathBuf};
 use gtk4::gio;
 
 use adw::prelude::*;
-use adw::{Application, ApplicationWindow, ToolbarView};
+use adw::{Application, ApplicationWindow, ToastOverlay, ToolbarView};
 
 use tracing::debug;
 use tracing::info;
+use tracing::warn;
 
 use livesplit_core::{Run, SharedTimer, Timer, auto_splitting::Runtime};
 
-use crate::config::Config;
+use crate::config::{Config, LoggingOptions};
+use crate::io::hotkey_profiles::PROFILE_DIR;
 use crate::ui::TuxSplitHeader;
+use crate::ui::crash_report::offer_crash_report;
+use crate::ui::export_image::set_export_source;
+use crate::ui::flash::start_split_flash;
+use crate::ui::freeze_watch::start_freeze_watch;
+use crate::ui::hotkeys::{attach_focus_aware_hotkeys, create_hotkey_system, set_hotkeys_suspended};
+use crate::ui::inhibit::start_idle_inhibitor;
+use crate::ui::integrity::{refresh_integrity_badge, set_integrity_badge};
+use crate::ui::offset_conflict::start_offset_conflict_watch;
+use crate::ui::pb_switch::start_pb_switch;
+use crate::ui::run_style::start_run_style;
+use crate::ui::scale::attach_monitor_scaling;
+use crate::ui::setting_undo::start_setting_undo;
+use crate::ui::theme::start_theme_schedule;
 use crate::ui::timer::TuxSplitTimer;
+use crate::ui::timer::font::apply_timer_font;
+use crate::ui::video_offset::{refresh_video_offset_indicator, set_video_offset_indicator};
+use crate::utils::comparisons::{ensure_latest_run, ensure_padded_golds};
+use crate::utils::crash_report::watch_timer;
+use crate::utils::inline_comparison::ensure_inline_comparisons;
+use crate::utils::startup::{self, StartupPhase, defer, measure};
 
 mod imp {
     use super::*;
@@ -81,25 +102,46 @@ glib::wrapper! {
     pub struct TuxSplitContext(ObjectSubclass<imp::TuxSplitContext>);
 }
 
+/// Hands what the window doesn't need to [`defer`], from [`TuxSplitContext::init`].
+pub fn defer_startup_work() {
+    // Compiling the script can take a while, and the window doesn't need it
+    defer(StartupPhase::AutoSplitter, || {
+        let ctx = TuxSplitContext::get_instance();
+        ctx.config().maybe_load_auto_splitter(&ctx.runtime());
+    });
+}
+
 impl TuxSplitContext {
     /// Construct a new initialized global context.
     ///
     /// Panics if the timer or hotkey system cannot be created.
     fn init() -> Self {
-        let mut config = load_config();
-        let run = config.parse_run_or_default();
+        let mut config = measure(StartupPhase::Config, load_config);
+        let mut run = measure(StartupPhase::Run, || config.parse_run_or_default());
+        // Before the timer is made from the run, which a restored attempt
+        // needs whole
+        measure(StartupPhase::Comparisons, || {
+            ensure_latest_run(&mut run);
+            ensure_padded_golds(&mut run, &config.general.padded_gold_comparison);
+            add_inline_comparisons(&mut run, &config);
+        });
+        config.begin_session(&run);
 
         let timer = Timer::new(run).expect("Failed to create timer");
         let shared_timer = timer.into_shared();
+        watch_timer(&shared_timer);
 
         let runtime = Runtime::new(shared_timer.clone());
 
         config.configure_timer(&mut shared_timer.write().unwrap());
-        config.maybe_load_auto_splitter(&runtime);
+        defer_startup_work();
 
-        let Some(()) = config.create_hotkey_system(shared_timer.clone()) else {
-            panic!("Could not load HotkeySystem");
-        };
+        measure(StartupPhase::Hotkeys, || {
+            config.load_hotkey_profile(&hotkey_profiles_dir());
+            let Some(()) = create_hotkey_system(&config, shared_timer.clone()) else {
+                panic!("Could not load HotkeySystem");
+            };
+        });
 
         let obj: Self = glib::Object::new();
         {
@@ -135,6 +177,10 @@ impl TuxSplitContext {
         self.imp().config.borrow()
     }
 
+    pub fn try_config(&self) -> Result<std::cell::Ref<'_, Config>, std::cell::BorrowError> {
+        self.imp().config.try_borrow()
+    }
+
     pub fn config_mut(&self) -> Result<std::cell::RefMut<'_, Config>, std::cell::BorrowMutError> {
         self.imp().config.try_borrow_mut()
     }
@@ -149,32 +195,59 @@ impl TuxSplitContext {
 
     /// Replace the run (full set_run) and emit run-changed. Re-configures
     /// timer based on current config (useful if comparisons / settings depend
-    /// on run contents).
-    pub fn set_run(&self, new_run: Run) {
+    /// on run contents). Runs without segments are refused, the current one
+    /// is kept and the reason is returned.
+    pub fn set_run(&self, mut new_run: Run) -> Result<(), String> {
+        ensure_latest_run(&mut new_run);
+        ensure_padded_golds(&mut new_run, &self.config().general.padded_gold_comparison);
+        add_inline_comparisons(&mut new_run, &self.config());
         let timer_arc = self.timer();
         {
             let mut timer = timer_arc.write().unwrap();
-            let _ = timer.set_run(new_run);
+            // The timer refuses runs without segments and keeps its own
+            if timer.set_run(new_run).is_err() {
+                warn!("Kept the current splits, the new ones have no segments");
+                return Err("The splits have no segments".to_owned());
+            }
             // Re-apply config in case it needs to reinitialize aspects of the timer.
             self.config().configure_timer(&mut timer);
         }
         self.emit_run_changed();
+        Ok(())
     }
 
     pub fn disable_hotkeys(&self) {
-        if let Ok(mut cfg_write) = self.config_mut() {
-            cfg_write.disable_hotkey_system();
+        if let Ok(cfg) = self.try_config() {
+            set_hotkeys_suspended(&cfg, true);
         }
     }
 
     pub fn enable_hotkeys(&self) {
-        if let Ok(mut cfg_write) = self.config_mut() {
-            cfg_write.enable_hotkey_system();
+        if let Ok(cfg) = self.try_config() {
+            set_hotkeys_suspended(&cfg, false);
         }
     }
 }
 
+/// Adds the config's inline comparisons to `run`, with a warning for each
+/// time that didn't fit it.
+fn add_inline_comparisons(run: &mut Run, config: &Config) {
+    for problem in ensure_inline_comparisons(run, &config.general.inline_comparisons) {
+        warn!("Inline comparison: {problem}");
+    }
+}
+
 pub fn build_ui(app: &Application) {
+    // Timed on its own rather than as part of the window
+    TuxSplitContext::get_instance();
+    let window = measure(StartupPhase::Window, || build_window(app));
+    window.present();
+    startup::window_shown(get_config_path());
+    crate::ui::saved_attempt::offer_saved_attempt(&window);
+    offer_crash_report(&window);
+}
+
+fn build_window(app: &Application) -> ApplicationWindow {
     let window: ApplicationWindow = ApplicationWindow::builder()
         .application(app)
         .title("TuxSplit")
@@ -184,24 +257,102 @@ pub fn build_ui(app: &Application) {
     let header = TuxSplitHeader::new(&window);
     toolbar_view.add_top_bar(header.header());
 
+    apply_timer_font(&TuxSplitContext::get_instance().config());
+    start_theme_schedule();
+    start_run_style();
+
     let mut timer_widget = TuxSplitTimer::new();
     timer_widget.start_refresh_loop();
-    toolbar_view.set_content(Some(timer_widget.clamped()));
+    toolbar_view.set_content(Some(timer_widget.widget()));
+    set_export_source(timer_widget.widget());
 
-    window.set_content(Some(&toolbar_view));
-    window.present();
+    let toasts = ToastOverlay::new();
+    toasts.set_child(Some(&toolbar_view));
+    crate::ui::timer::modal::set_toast_overlay(&toasts);
+    {
+        let ctx = TuxSplitContext::get_instance();
+        let config = ctx.config();
+        if let Some(recovery) = config.splits_recovery() {
+            crate::ui::timer::modal::show_notice(&recovery.describe());
+        }
+        if config.hotkeys.profile_error().is_some() {
+            crate::ui::timer::modal::show_notice(
+                "Hotkey profile unavailable, using the default bindings",
+            );
+        }
+    }
+    start_idle_inhibitor(app, &window, &toasts, header.inhibit_indicator());
+    start_split_flash(&toasts);
+    start_setting_undo(&toasts);
+    start_pb_switch();
+    start_freeze_watch(&toasts);
+    start_offset_conflict_watch(&toolbar_view);
+    attach_focus_aware_hotkeys(&window, header.hotkeys_paused_indicator());
+    attach_monitor_scaling(&window);
+    set_integrity_badge(header.integrity_badge());
+    refresh_integrity_badge(TuxSplitContext::get_instance().config().splits_integrity());
+    set_video_offset_indicator(header.video_offset_indicator());
+    refresh_video_offset_indicator(
+        TuxSplitContext::get_instance()
+            .config()
+            .behavior
+            .video_offset_ms,
+    );
+    #[cfg(feature = "racetime")]
+    crate::ui::racetime::start_racetime(header.racetime_indicator());
+    #[cfg(feature = "gamepad")]
+    crate::ui::pedal::sync_pedal_listener();
+
+    window.set_content(Some(&toasts));
+    window
 }
 
 pub fn shutdown() {
     info!("Shutting down TuxSplit");
     TuxSplitContext::get_instance()
         .config()
-        .save(get_config_path().join("config.yaml"))
+        .save(config_file_path())
         .expect("Failed to save config on shutdown");
 }
 
+/// Location of the user config file, whether it exists or not.
+pub fn config_file_path() -> PathBuf {
+    get_config_path().join("config.yaml")
+}
+
+/// Directory of the hotkey profiles, which may not exist yet.
+pub fn hotkey_profiles_dir() -> PathBuf {
+    get_config_path().join(PROFILE_DIR)
+}
+
+/// Human readable state of the user config file, for diagnostics.
+pub fn config_file_status() -> &'static str {
+    let path = config_file_path();
+    if !path.is_file() {
+        "Not found, using defaults"
+    } else if Config::parse(&path).is_none() {
+        "Invalid, using defaults"
+    } else {
+        "Loaded"
+    }
+}
+
+/// Whether the user config asks for window-only hotkeys, which don't need
+/// the X11 backend. Read before GTK starts.
+pub fn window_hotkeys_requested() -> bool {
+    load_config().behavior.window_hotkeys
+}
+
+/// The logging options of the user config, with the trace file resolved
+/// against the config directory. Read before the log is set up.
+pub fn logging_options() -> LoggingOptions {
+    let mut options = load_config().logging;
+    options.trace_file = options.trace_file.map(|path| get_config_path().join(path));
+    options
+}
+
 fn load_config() -> Config {
-    let user_cfg = get_config_path().join("config.yaml");
+    let user_cfg = config_file_path();
     if user_cfg.is_file()
         && let Some(cfg) = Config::parse(&user_cfg)
     {
@@ -211,10 +362,14 @@ fn load_config() -> Config {
     Config::default()
 }
 
+/// A relative `TUXSPLIT_DATADIR` is taken from the working directory once,
+/// here. A relative `XDG_CONFIG_HOME` is ignored, as the XDG spec asks.
 fn get_config_path() -> PathBuf {
     if let Ok(path_str) = env::var("TUXSPLIT_DATADIR") {
-        PathBuf::from(&path_str)
-    } else if let Ok(path_str) = env::var("XDG_CONFIG_HOME") {
+        std::path::absolute(&path_str).unwrap_or_else(|_| PathBuf::from(&path_str))
+    } else if let Ok(path_str) = env::var("XDG_CONFIG_HOME")
+        && Path::new(&path_str).is_absolute()
+    {
         PathBuf::from(path_str).join("tuxsplit")
     } else if let Ok(home) = env::var("HOME") {
         let path = PathBuf::from(home).join(".config").join("tuxsplit");
@@ -1,11 +1,15 @@
-use livesplit_core::{TimeSpan, Timer, TimingMethod};
+use livesplit_core::{TimeSpan, Timer, TimerPhase, TimingMethod};
 use serde::{Deserialize, Serialize};
 use std::fmt::Write as _;
+use std::sync::Mutex;
 use time::Duration as TimeDuration;
 
+use crate::utils::video_offset::displayed_attempt_duration;
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
 #[serde(default)]
+#[serde(from = "StoredTimeFormat")]
 #[allow(clippy::struct_excessive_bools)]
 pub struct TimeFormat {
     pub show_hours: bool,
@@ -14,7 +18,53 @@ pub struct TimeFormat {
     pub show_decimals: bool,
     pub decimal_places: u8,
     pub dynamic: bool,
-    cached_pattern: Option<String>,
+    /// How the digits past the shown precision are dropped, decimals
+    /// included: with `Nearest`, 1:23.46 shows as 1:23.5 with one decimal
+    /// and 1:23.5 as 1:24 with none. Deltas never use this, they're rounded
+    /// as `format.delta-rounding` says.
+    pub rounding: Rounding,
+    /// Show whole days in front of the hours, which then wrap at 24, as
+    /// "1d 05:33:12". Needs `show-hours`.
+    pub show_days: bool,
+    /// Leave out the decimals while they're all zero, "2:05" rather than
+    /// "2:05.00". The text gets shorter whenever that happens, so a running
+    /// timer with this changes width every second. It suits splits and
+    /// comparisons better; `value-min-width` or `align-value-column` keep a
+    /// column from jittering.
+    pub hide_zero_decimals: bool,
+    /// Show the time as a plain count of seconds, "7384.25" for 2:03:04.25,
+    /// for scripts reading the window and for debugging. The decimals are
+    /// still configured as usual, every other unit is left out.
+    pub total_seconds: bool,
+    #[serde(skip)]
+    pattern_cache: PatternCache,
+}
+
+/// What a pattern is built from. A dynamic pattern only depends on the
+/// range the duration falls in, not the duration itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+struct PatternKey {
+    show_hours: bool,
+    show_minutes: bool,
+    show_seconds: bool,
+    show_decimals: bool,
+    decimal_places: u8,
+    dynamic: bool,
+    show_days: bool,
+    total_seconds: bool,
+    range: usize,
+}
+
+/// Patterns built so far, one per duration range. An entry is only used
+/// while its key matches, so changing a field never needs to clear it.
+#[derive(Debug, Default)]
+struct PatternCache(Mutex<[Option<(PatternKey, String)>; 5]>);
+
+impl Clone for PatternCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
 }
 
 impl Default for TimeFormat {
@@ -27,10 +77,92 @@ impl Default for TimeFormat {
             show_decimals: true,
             decimal_places: 2,
             dynamic: false,
-            cached_pattern: None,
+            rounding: Rounding::Truncate,
+            show_days: false,
+            hide_zero_decimals: false,
+            total_seconds: false,
+            pattern_cache: PatternCache::default(),
         }
     }
 }
+/// How digits beyond the displayed precision are dropped.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rounding {
+    /// Cut off, so 2.149 shows as 2.14.
+    #[default]
+    Truncate,
+    /// Round half up, so 2.149 shows as 2.15. Halves are rounded away from
+    /// zero, -2.145 shows as -2.15.
+    Nearest,
+    /// Truncate the decimals that are shown, but round to the nearest second
+    /// when the pattern shows none, as dynamic formatting does over a
+    /// minute: 1:02.9 shows as 1:03 there.
+    NearestWhenNoDecimals,
+}
+
+/// A [`TimeFormat`] as read from a config. Configs from before
+/// [`Rounding::NearestWhenNoDecimals`] kept it in a `dropped-decimals` key
+/// of its own.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+#[allow(clippy::struct_excessive_bools)]
+struct StoredTimeFormat {
+    show_hours: bool,
+    show_minutes: bool,
+    show_seconds: bool,
+    show_decimals: bool,
+    decimal_places: u8,
+    dynamic: bool,
+    rounding: Rounding,
+    dropped_decimals: Option<Rounding>,
+    show_days: bool,
+    hide_zero_decimals: bool,
+    total_seconds: bool,
+}
+
+impl Default for StoredTimeFormat {
+    fn default() -> Self {
+        let format = TimeFormat::default();
+        Self {
+            show_hours: format.show_hours,
+            show_minutes: format.show_minutes,
+            show_seconds: format.show_seconds,
+            show_decimals: format.show_decimals,
+            decimal_places: format.decimal_places,
+            dynamic: format.dynamic,
+            rounding: format.rounding,
+            dropped_decimals: None,
+            show_days: format.show_days,
+            hide_zero_decimals: format.hide_zero_decimals,
+            total_seconds: format.total_seconds,
+        }
+    }
+}
+
+impl From<StoredTimeFormat> for TimeFormat {
+    fn from(stored: StoredTimeFormat) -> Self {
+        let rounding = match (stored.rounding, stored.dropped_decimals) {
+            (Rounding::Truncate, Some(Rounding::Nearest)) => Rounding::NearestWhenNoDecimals,
+            (rounding, _) => rounding,
+        };
+        Self {
+            show_hours: stored.show_hours,
+            show_minutes: stored.show_minutes,
+            show_seconds: stored.show_seconds,
+            show_decimals: stored.show_decimals,
+            decimal_places: stored.decimal_places,
+            dynamic: stored.dynamic,
+            rounding,
+            show_days: stored.show_days,
+            hide_zero_decimals: stored.hide_zero_decimals,
+            total_seconds: stored.total_seconds,
+            pattern_cache: PatternCache::default(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 #[allow(clippy::enum_variant_names)]
 pub enum TimeFormatPreset {
@@ -56,7 +188,11 @@ impl TimeFormat {
             show_decimals,
             decimal_places: decimal_places.clamp(1, 3),
             dynamic,
-            cached_pattern: None,
+            rounding: Rounding::Truncate,
+            show_days: false,
+            hide_zero_decimals: false,
+            total_seconds: false,
+            pattern_cache: PatternCache::default(),
         }
     }
 
@@ -74,23 +210,67 @@ impl TimeFormat {
 
     pub fn set_decimal_places(&mut self, places: u8) {
         self.decimal_places = places.clamp(1, 3);
-        self.cached_pattern = None;
     }
 
-    fn get_pattern(&mut self, total_millis: Option<i64>) -> String {
-        if self.dynamic || self.cached_pattern.is_none() {
-            self.cached_pattern = Some(self.compute_pattern(total_millis));
-        }
+    pub fn set_show_days(&mut self, show_days: bool) {
+        self.show_days = show_days;
+    }
 
-        self.cached_pattern.clone().unwrap()
+    /// Resolves the pattern for a duration of `total_millis`, built once per
+    /// duration range and settings.
+    pub fn pattern_for(&self, total_millis: Option<i64>) -> String {
+        // The ranges `compute_pattern` tells apart
+        let range = match total_millis {
+            Some(ms) if self.dynamic => {
+                1 + [60_000, 3_600_000, 86_400_000]
+                    .iter()
+                    .filter(|&&bound| ms >= bound)
+                    .count()
+            }
+            _ => 0,
+        };
+        let key = PatternKey {
+            show_hours: self.show_hours,
+            show_minutes: self.show_minutes,
+            show_seconds: self.show_seconds,
+            show_decimals: self.show_decimals,
+            decimal_places: self.decimal_places,
+            dynamic: self.dynamic,
+            show_days: self.show_days,
+            total_seconds: self.total_seconds,
+            range,
+        };
+        let mut cache = self.pattern_cache.0.lock().unwrap();
+        match &cache[range] {
+            Some((cached, pattern)) if *cached == key => pattern.clone(),
+            _ => {
+                let pattern = self.compute_pattern(total_millis);
+                cache[range] = Some((key, pattern.clone()));
+                pattern
+            }
+        }
     }
 
     /// Builds a pattern string (e.g., "h:m:s.dd") based on the configured flags.
     /// If `dynamic` is enabled and `total_millis` is provided, this adjusts the
     /// pattern to match the duration. For example, with minutes+seconds+decimals
     /// enabled and under a minute, this yields "s.dd"; over a minute, "m:s".
+    /// With `show_days`, a dynamic pattern only gains the days past 24 hours.
+    /// With `total_seconds` it's always "S" and the decimals.
     fn compute_pattern(&self, total_millis: Option<i64>) -> String {
+        if self.total_seconds {
+            let mut pattern = "S".to_owned();
+            if self.show_decimals && self.decimal_places > 0 {
+                pattern.push('.');
+                for _ in 0..self.decimal_places {
+                    pattern.push('d');
+                }
+            }
+            return pattern;
+        }
+
         // Resolve dynamic visibility for each component
+        let mut show_days = self.show_days && self.show_hours;
         let mut show_hours = self.show_hours;
         let mut show_minutes = self.show_minutes;
         let show_seconds = self.show_seconds;
@@ -99,6 +279,7 @@ impl TimeFormat {
         if self.dynamic
             && let Some(ms) = total_millis
         {
+            show_days &= ms >= 86_400_000;
             if ms < 60_000 {
                 // Under a minute: hide hours and minutes
                 show_hours = false;
@@ -126,6 +307,9 @@ impl TimeFormat {
             }
         };
 
+        if show_days {
+            pattern.push_str("D ");
+        }
         if show_hours {
             pattern.push('h');
         }
@@ -173,9 +357,11 @@ impl TimeFormat {
     /// Formats a `TimeSpan` using the class `pattern`.
     ///
     /// Supported tokens:
-    /// - h                -> hours (0+)
+    /// - D                -> days with a "d" suffix, hidden while zero
+    /// - h                -> hours (0+), or 0-23 after a `D` token
     /// - m                -> minutes (0-59)
     /// - s                -> seconds (0-59)
+    /// - S                -> total seconds (0+), e.g. for scripts
     /// - d / dd / ddd...  -> fractional seconds (tenths/centiseconds/milliseconds). Truncated, not rounded.
     ///
     /// Any other characters are treated as literals (e.g., ":" or ".").
@@ -185,20 +371,68 @@ impl TimeFormat {
     /// - "m:s.dd"       ->  "2:03.45"
     /// - "h:m:s.d"      ->  "1:02:03.4"
     /// - "m:s.ddd"      ->  "2:03.456"
+    /// - "D h:m:s"      ->  "1d 05:33:12"
+    /// - "S.ddd"        ->  "3723.456"
     ///
     /// Notes:
     /// - Negative values are prefixed with "-".
+    /// - The digits past the last one shown are truncated or rounded as set
+    ///   by `rounding`.
     pub fn format_time_span(&self, span: &TimeSpan) -> String {
-        // Determine sign and absolute time in milliseconds
-        let total_ms = span.total_milliseconds();
-        let abs_ms = total_ms.abs() as i64;
+        let abs_ms = span.total_milliseconds().abs() as i64;
+        let abs_nanos = span.to_duration().whole_nanoseconds().unsigned_abs();
+        self.format_millis(self.rounded_millis(abs_ms, abs_nanos, self.rounding), true)
+    }
 
-        let hours = abs_ms / 3_600_000;
+    /// The duration of `abs_ms`, or `abs_nanos` more precisely, with the
+    /// digits past the shown ones dropped as `rounding` says.
+    fn rounded_millis(&self, abs_ms: i64, abs_nanos: u128, rounding: Rounding) -> i64 {
+        match rounding {
+            Rounding::Truncate => abs_ms,
+            Rounding::Nearest => self.nearest_millis(abs_nanos),
+            Rounding::NearestWhenNoDecimals => {
+                if self.pattern_for(Some(abs_ms)).contains('d') {
+                    abs_ms
+                } else {
+                    self.nearest_millis(abs_nanos)
+                }
+            }
+        }
+    }
+
+    /// `abs_nanos` rounded to the last digit the pattern shows for it, in
+    /// milliseconds.
+    fn nearest_millis(&self, abs_nanos: u128) -> i64 {
+        let pattern = self.pattern_for(Some((abs_nanos / 1_000_000) as i64));
+        let decimals = pattern.chars().filter(|c| *c == 'd').count().min(3) as u32;
+        let unit = 10u128.pow(9 - decimals);
+        ((abs_nanos + unit / 2) / unit * unit / 1_000_000) as i64
+    }
+
+    /// Formats an absolute duration of `abs_ms` milliseconds. Without
+    /// `with_days` the hours keep counting past 24.
+    fn format_millis(&self, abs_ms: i64, with_days: bool) -> String {
+        let pattern_for = |ms| {
+            let pattern = self.pattern_for(Some(ms));
+            if with_days {
+                pattern
+            } else {
+                pattern.replacen("D ", "", 1)
+            }
+        };
+        let mut pattern = pattern_for(abs_ms);
+        let days = abs_ms / 86_400_000;
+        let hours = if pattern.contains('D') {
+            (abs_ms / 3_600_000) % 24
+        } else {
+            abs_ms / 3_600_000
+        };
         let minutes = (abs_ms / 60_000) % 60;
         let seconds = (abs_ms / 1_000) % 60;
         let millis = abs_ms % 1_000;
-
-        let pattern = self.compute_pattern(Some(abs_ms));
+        if self.hide_zero_decimals {
+            pattern = Self::without_zero_decimals(pattern, millis);
+        }
 
         let mut out = String::new();
 
@@ -217,9 +451,17 @@ impl TimeFormat {
             }
 
             match ch {
+                'D' => {
+                    if days > 0 {
+                        let _ = write!(out, "{days}d");
+                    }
+                }
                 'h' => Self::append_number(&mut out, hours, false),
                 'm' => Self::append_number(&mut out, minutes, false),
                 's' => Self::append_number(&mut out, seconds, true),
+                'S' => {
+                    let _ = write!(out, "{}", abs_ms / 1_000);
+                }
                 'd' => Self::append_fraction(&mut out, millis, count),
                 _ => {
                     // Literal character(s)
@@ -253,20 +495,18 @@ impl TimeFormat {
     }
 
     /// Formats the overall timer's current attempt duration into a string using this format.
-    pub fn format_timer(&self, timer: &Timer) -> String {
-        let dur = timer
-            .current_attempt_duration()
-            .to_duration()
-            .checked_add(timer.run().offset().to_duration())
-            .unwrap_or_default()
-            .checked_sub(timer.get_pause_time().unwrap_or_default().to_duration())
-            .unwrap_or_default()
-            .checked_sub(if timer.current_timing_method() == TimingMethod::GameTime {
-                timer.loading_times().to_duration()
-            } else {
-                TimeDuration::ZERO
-            })
-            .unwrap_or_default();
+    /// See [`current_adjusted_duration`] for how offsets, pauses and loads combine.
+    /// Once the run has ended this is the final split time, so the timer
+    /// shows exactly what was recorded and stops moving.
+    pub fn format_timer(&self, timer: &Timer, video_offset: TimeDuration) -> String {
+        let final_time = (timer.current_phase() == TimerPhase::Ended)
+            .then(|| timer.run().segments().last())
+            .flatten()
+            .and_then(|segment| segment.split_time()[timer.current_timing_method()]);
+        let dur = match final_time {
+            Some(final_time) => final_time.to_duration(),
+            None => current_adjusted_duration(timer, timer.current_timing_method(), video_offset),
+        };
         let out = self.format_duration(&dur);
         if dur < TimeDuration::ZERO {
             format!("-{out}")
@@ -286,6 +526,17 @@ impl TimeFormat {
         self.format_time_span(&span)
     }
 
+    /// Like [`Self::format_duration`], but with a choice of how the digits
+    /// past the displayed decimals are dropped. The precision is the one the
+    /// pattern shows for `duration`, and the sign is dropped as well. This is
+    /// what deltas are formatted with, so days are never shown: a delta stays
+    /// in hours.
+    pub fn format_duration_rounded(&self, duration: &TimeDuration, rounding: Rounding) -> String {
+        let abs_nanos = duration.whole_nanoseconds().unsigned_abs();
+        let abs_ms = (abs_nanos / 1_000_000) as i64;
+        self.format_millis(self.rounded_millis(abs_ms, abs_nanos, rounding), false)
+    }
+
     pub fn format_duration_opt(&self, duration: Option<TimeDuration>) -> String {
         match duration {
             Some(d) => self.format_duration(&d),
@@ -293,6 +544,20 @@ impl TimeFormat {
         }
     }
 
+    /// `pattern` without its decimals when they'd show only zeros for
+    /// `millis`.
+    fn without_zero_decimals(pattern: String, millis: i64) -> String {
+        let places = pattern.chars().filter(|c| *c == 'd').count();
+        if places == 0 {
+            return pattern;
+        }
+        let shown = millis / 10i64.pow(3u32.saturating_sub(places as u32));
+        if shown != 0 {
+            return pattern;
+        }
+        pattern.replacen(&format!(".{}", "d".repeat(places)), "", 1)
+    }
+
     fn append_number(out: &mut String, value: i64, always_show: bool) {
         if value <= 0 && out.is_empty() && !always_show {
         } else {
@@ -327,6 +592,63 @@ impl TimeFormat {
     }
 }
 
+/// Within this much of a threshold, the running timer gains one decimal.
+const ADAPTIVE_NEAR: TimeDuration = TimeDuration::seconds(5);
+/// Within this much, it shows full millisecond precision.
+const ADAPTIVE_CLOSE: TimeDuration = TimeDuration::seconds(1);
+
+/// Decimal places for the running timer when adaptive precision is enabled.
+/// `time_left` is how long until a gold or the comparison is lost; `None`
+/// (nothing to chase, or already lost) keeps the configured precision.
+pub fn adaptive_decimal_places(base: u8, time_left: Option<TimeDuration>) -> u8 {
+    match time_left {
+        Some(left) if left <= ADAPTIVE_CLOSE => 3,
+        Some(left) if left <= ADAPTIVE_NEAR => (base + 1).min(3),
+        _ => base,
+    }
+}
+
+/// The time shown by the main timer. `attempt` is the wall-clock time since
+/// the attempt started, pauses included.
+///
+/// The real time is `attempt + offset - pause`. With a negative offset it
+/// runs as a countdown first, and that countdown is shown as-is in game
+/// time too: loads reported before the run starts don't stretch or skip it.
+/// Once the countdown is over, game time is the real time minus the loading
+/// times and doesn't go below zero, so loads during the countdown hold the
+/// game timer at zero instead of showing a second countdown.
+pub fn displayed_timer_duration(
+    attempt: TimeDuration,
+    offset: TimeDuration,
+    pause: TimeDuration,
+    loading: TimeDuration,
+    method: TimingMethod,
+) -> TimeDuration {
+    let real = attempt.saturating_add(offset).saturating_sub(pause);
+    if method == TimingMethod::RealTime || real < TimeDuration::ZERO {
+        real
+    } else {
+        real.saturating_sub(loading).max(TimeDuration::ZERO)
+    }
+}
+
+/// The time the main timer shows for the attempt in `method`: the attempt
+/// shifted back by `video_offset`, combined with the run's offset, the pauses
+/// and the loads by [`displayed_timer_duration`].
+pub fn current_adjusted_duration(
+    timer: &Timer,
+    method: TimingMethod,
+    video_offset: TimeDuration,
+) -> TimeDuration {
+    displayed_timer_duration(
+        displayed_attempt_duration(timer, video_offset),
+        timer.run().offset().to_duration(),
+        timer.get_pause_time().unwrap_or_default().to_duration(),
+        timer.loading_times().to_duration(),
+        method,
+    )
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct TimeParseError;
 
@@ -336,8 +658,21 @@ impl std::fmt::Display for TimeParseError {
     }
 }
 
+/// Parses "h:m:s.frac", "m:s.frac" or "s.frac", where the fraction may be
+/// left out. A day count may come first as in "1d 5:33:12", the same time
+/// as "29:33:12", and then the hours are below 24.
 pub fn parse_hms(input: &str) -> Result<TimeDuration, TimeParseError> {
+    let (days, input) = match input.split_once('d') {
+        Some((days, rest)) => (
+            Some(days.parse::<u64>().map_err(|_| TimeParseError)?),
+            rest.trim_start(),
+        ),
+        None => (None, input),
+    };
     let parts: Vec<&str> = input.split(':').collect();
+    if days.is_some() && parts.len() != 3 {
+        return Err(TimeParseError);
+    }
 
     let (hours, mins, secs_part) = match parts.len() {
         1 => (0u64, 0u64, parts[0]), // s.frac
@@ -355,14 +690,15 @@ pub fn parse_hms(input: &str) -> Result<TimeDuration, TimeParseError> {
         _ => return Err(TimeParseError),
     };
 
-    let (s_whole, s_frac) = secs_part.split_once('.').ok_or(TimeParseError)?;
-    if s_frac.is_empty() {
-        return Err(TimeParseError);
-    }
+    let (s_whole, s_frac) = match secs_part.split_once('.') {
+        Some((_, "")) => return Err(TimeParseError),
+        Some((whole, frac)) => (whole, frac),
+        None => (secs_part, "0"),
+    };
 
     let secs: u64 = s_whole.parse().map_err(|_| TimeParseError)?;
 
-    if hours >= 60 || mins >= 60 || secs >= 60 {
+    if hours >= 60 || mins >= 60 || secs >= 60 || (days.is_some() && hours >= 24) {
         return Err(TimeParseError);
     }
 
@@ -376,14 +712,28 @@ pub fn parse_hms(input: &str) -> Result<TimeDuration, TimeParseError> {
 
     let nanos: u64 = frac_str.parse().map_err(|_| TimeParseError)?;
 
-    let total_secs = hours * 3600 + mins * 60 + secs;
+    let total_secs = days
+        .unwrap_or(0)
+        .checked_mul(86_400)
+        .and_then(|secs_in_days| secs_in_days.checked_add(hours * 3600 + mins * 60 + secs))
+        .and_then(|total| i64::try_from(total).ok())
+        .ok_or(TimeParseError)?;
+
+    Ok(TimeDuration::new(total_secs, nanos as i32))
+}
 
-    Ok(TimeDuration::new(total_secs as i64, nanos as i32))
+/// Same as [`parse_hms`], but accepts a leading `+` or `-` sign.
+pub fn parse_signed_hms(input: &str) -> Result<TimeDuration, TimeParseError> {
+    let input = input.trim();
+    match input.strip_prefix('-') {
+        Some(rest) => parse_hms(rest).map(|d| -d),
+        None => parse_hms(input.strip_prefix('+').unwrap_or(input)),
+    }
 }
 
 #[cfg(test)]
 mod format_tests {
-    use super::TimeFormat;
+    use super::{Rounding, TimeFormat};
     use livesplit_core::TimeSpan;
 
     fn make_tf(hours: bool, minutes: bool, seconds: bool, decimals: u8) -> TimeFormat {
@@ -394,7 +744,11 @@ mod format_tests {
             show_decimals: decimals > 0,
             decimal_places: decimals,
             dynamic: false,
-            cached_pattern: None,
+            rounding: Rounding::Truncate,
+            show_days: false,
+            hide_zero_decimals: false,
+            total_seconds: false,
+            pattern_cache: Default::default(),
         }
     }
 
@@ -407,7 +761,11 @@ mod format_tests {
             show_decimals: true,
             decimal_places: 2,
             dynamic: false,
-            cached_pattern: None,
+            rounding: Rounding::Truncate,
+            show_days: false,
+            hide_zero_decimals: false,
+            total_seconds: false,
+            pattern_cache: Default::default(),
         };
         assert_eq!(tf.compute_pattern(None), "h:m:s.dd");
         assert_eq!(tf.compute_pattern(Some(500)), "h:m:s.dd");
@@ -424,7 +782,11 @@ mod format_tests {
             show_decimals: false,
             decimal_places: 3,
             dynamic: false,
-            cached_pattern: None,
+            rounding: Rounding::Truncate,
+            show_days: false,
+            hide_zero_decimals: false,
+            total_seconds: false,
+            pattern_cache: Default::default(),
         };
         assert_eq!(tf.compute_pattern(None), "m:s");
         assert_eq!(tf.compute_pattern(Some(59_999)), "m:s");
@@ -439,7 +801,11 @@ mod format_tests {
             show_decimals: true,
             decimal_places: 2,
             dynamic: true,
-            cached_pattern: None,
+            rounding: Rounding::Truncate,
+            show_days: false,
+            hide_zero_decimals: false,
+            total_seconds: false,
+            pattern_cache: Default::default(),
         };
         // under 1 minute -> hide minutes, keep s.dd
         assert_eq!(tf.compute_pattern(Some(59_500)), "s.dd");
@@ -454,7 +820,11 @@ mod format_tests {
             show_decimals: true,
             decimal_places: 3,
             dynamic: true,
-            cached_pattern: None,
+            rounding: Rounding::Truncate,
+            show_days: false,
+            hide_zero_decimals: false,
+            total_seconds: false,
+            pattern_cache: Default::default(),
         };
         // >= 1 minute and < 1 hour -> m:s (no decimals)
         assert_eq!(tf.compute_pattern(Some(60_000)), "m:s");
@@ -470,7 +840,11 @@ mod format_tests {
             show_decimals: true,
             decimal_places: 2,
             dynamic: true,
-            cached_pattern: None,
+            rounding: Rounding::Truncate,
+            show_days: false,
+            hide_zero_decimals: false,
+            total_seconds: false,
+            pattern_cache: Default::default(),
         };
         // >= 1 hour -> h:m:s (no decimals)
         assert_eq!(tf.compute_pattern(Some(3_600_000)), "h:m:s");
@@ -486,7 +860,11 @@ mod format_tests {
             show_decimals: true,
             decimal_places: 4,
             dynamic: false,
-            cached_pattern: None,
+            rounding: Rounding::Truncate,
+            show_days: false,
+            hide_zero_decimals: false,
+            total_seconds: false,
+            pattern_cache: Default::default(),
         };
         assert_eq!(tf.compute_pattern(None), "s.dddd");
     }
@@ -500,7 +878,11 @@ mod format_tests {
             show_decimals: false,
             decimal_places: 0,
             dynamic: false,
-            cached_pattern: None,
+            rounding: Rounding::Truncate,
+            show_days: false,
+            hide_zero_decimals: false,
+            total_seconds: false,
+            pattern_cache: Default::default(),
         };
         assert_eq!(tf.compute_pattern(None), "s");
     }
@@ -586,12 +968,531 @@ mod format_tests {
         let d = time::Duration::seconds(10);
         assert_eq!(tf.format_duration_opt(Some(d)), "10.00");
     }
+
+    #[test]
+    fn pattern_for_matches_compute_pattern() {
+        let mut tf = make_tf(true, true, true, 2);
+        tf.dynamic = true;
+        for ms in [None, Some(0), Some(59_999), Some(60_000), Some(3_600_000)] {
+            assert_eq!(tf.pattern_for(ms), tf.compute_pattern(ms));
+        }
+    }
+
+    #[test]
+    fn cached_patterns_follow_the_settings() {
+        let mut tf = make_tf(true, true, true, 2);
+        assert_eq!(tf.pattern_for(Some(500)), "h:m:s.dd");
+        tf.dynamic = true;
+        assert_eq!(tf.pattern_for(Some(500)), "s.dd");
+        assert_eq!(tf.pattern_for(Some(65_000)), "m:s");
+        assert_eq!(tf.pattern_for(Some(30_000)), "s.dd");
+        tf.show_decimals = false;
+        assert_eq!(tf.pattern_for(Some(500)), "s");
+        tf.set_show_days(true);
+        assert_eq!(tf.pattern_for(Some(86_400_000)), "D h:m:s");
+        assert_eq!(tf.pattern_for(Some(86_399_999)), "h:m:s");
+        tf.total_seconds = true;
+        assert_eq!(tf.pattern_for(Some(86_400_000)), "S");
+    }
+
+    #[test]
+    fn decimal_change_updates_the_pattern() {
+        let mut tf = make_tf(false, true, true, 2);
+        assert_eq!(tf.pattern_for(None), "m:s.dd");
+        tf.set_decimal_places(3);
+        assert_eq!(tf.pattern_for(None), "m:s.ddd");
+    }
+
+    #[test]
+    fn rounded_durations_round_to_displayed_decimals() {
+        use super::Rounding;
+        let tf = make_tf(false, false, true, 2); // "s.dd"
+        let d = time::Duration::milliseconds(2_149);
+        assert_eq!(tf.format_duration_rounded(&d, Rounding::Truncate), "2.14");
+        assert_eq!(tf.format_duration_rounded(&d, Rounding::Nearest), "2.15");
+        assert_eq!(tf.format_duration_rounded(&-d, Rounding::Nearest), "2.15");
+        assert_eq!(
+            tf.format_duration_rounded(&time::Duration::milliseconds(2_144), Rounding::Nearest),
+            "2.14"
+        );
+        // Sub-millisecond precision counts too
+        assert_eq!(
+            tf.format_duration_rounded(&time::Duration::microseconds(2_145_000), Rounding::Nearest),
+            "2.15"
+        );
+        // Carries into the next unit
+        let tf = make_tf(false, true, true, 1); // "m:s.d"
+        assert_eq!(
+            tf.format_duration_rounded(&time::Duration::milliseconds(59_960), Rounding::Nearest),
+            "1:00.0"
+        );
+    }
+
+    #[test]
+    fn rounded_durations_use_the_dynamic_pattern() {
+        use super::Rounding;
+        let mut tf = make_tf(true, true, true, 2);
+        tf.dynamic = true;
+        // Over a minute no decimals are shown, so whole seconds are rounded
+        assert_eq!(
+            tf.format_duration_rounded(&time::Duration::milliseconds(65_600), Rounding::Nearest),
+            "1:06"
+        );
+        assert_eq!(
+            tf.format_duration_rounded(&time::Duration::milliseconds(65_600), Rounding::Truncate),
+            "1:05"
+        );
+    }
+
+    #[test]
+    fn truncated_rounding_matches_format_duration() {
+        use super::Rounding;
+        let tf = make_tf(true, true, true, 3);
+        for ms in [0, 7, 2_149, 61_230, 3_845_999] {
+            let d = time::Duration::milliseconds(ms);
+            assert_eq!(
+                tf.format_duration_rounded(&d, Rounding::Truncate),
+                tf.format_duration(&d)
+            );
+        }
+    }
+
+    #[test]
+    fn dropped_decimals_truncate_by_default() {
+        let mut tf = make_tf(true, true, true, 2);
+        tf.dynamic = true;
+        assert_eq!(
+            tf.format_duration(&time::Duration::milliseconds(62_500)),
+            "1:02"
+        );
+        assert_eq!(
+            tf.format_duration(&time::Duration::milliseconds(62_900)),
+            "1:02"
+        );
+    }
+
+    #[test]
+    fn dropped_decimals_can_round_to_the_nearest_second() {
+        let mut tf = make_tf(true, true, true, 2);
+        tf.dynamic = true;
+        tf.rounding = Rounding::NearestWhenNoDecimals;
+        assert_eq!(
+            tf.format_duration(&time::Duration::milliseconds(62_499)),
+            "1:02"
+        );
+        assert_eq!(
+            tf.format_duration(&time::Duration::milliseconds(62_500)),
+            "1:03"
+        );
+        assert_eq!(
+            tf.format_duration(&time::Duration::milliseconds(62_900)),
+            "1:03"
+        );
+        // Shown decimals are left alone
+        assert_eq!(
+            tf.format_duration(&time::Duration::milliseconds(2_509)),
+            "2.50"
+        );
+        // Rounding up into the next hour shows it
+        assert_eq!(
+            tf.format_duration(&time::Duration::milliseconds(3_599_500)),
+            "1:00:00"
+        );
+        // Deltas keep their own rounding
+        assert_eq!(
+            tf.format_duration_rounded(&time::Duration::milliseconds(62_900), Rounding::Truncate),
+            "1:02"
+        );
+    }
+
+    #[test]
+    fn dropped_decimals_from_old_configs_are_folded_into_rounding() {
+        let parse = |yaml| serde_yaml::from_str::<TimeFormat>(yaml).unwrap().rounding;
+        assert_eq!(
+            parse("rounding: truncate\ndropped-decimals: nearest\n"),
+            Rounding::NearestWhenNoDecimals
+        );
+        assert_eq!(parse("dropped-decimals: truncate\n"), Rounding::Truncate);
+        assert_eq!(
+            parse("rounding: nearest\ndropped-decimals: nearest\n"),
+            Rounding::Nearest
+        );
+        assert_eq!(
+            parse("rounding: nearest-when-no-decimals\n"),
+            Rounding::NearestWhenNoDecimals
+        );
+
+        let saved = serde_yaml::to_string(&TimeFormat::default()).unwrap();
+        assert!(!saved.contains("dropped-decimals"));
+    }
+
+    #[test]
+    fn rounding_applies_to_the_shown_decimals_too() {
+        let ms = |tf: &TimeFormat, ms| tf.format_duration(&time::Duration::milliseconds(ms));
+        let mut tf = make_tf(false, true, true, 0); // "m:s"
+        assert_eq!(ms(&tf, 83_500), "1:23");
+        tf.rounding = Rounding::Nearest;
+        assert_eq!(ms(&tf, 83_499), "1:23");
+        assert_eq!(ms(&tf, 83_500), "1:24");
+        // Away from zero, the sign is left to the caller
+        assert_eq!(tf.format_time_span(&TimeSpan::from_seconds(-83.5)), "1:24");
+
+        let mut tf = make_tf(false, true, true, 1); // "m:s.d"
+        assert_eq!(ms(&tf, 83_460), "1:23.4");
+        tf.rounding = Rounding::Nearest;
+        assert_eq!(ms(&tf, 83_460), "1:23.5");
+        assert_eq!(ms(&tf, 59_960), "1:00.0");
+        // Deltas keep their own rounding
+        assert_eq!(
+            tf.format_duration_rounded(&time::Duration::milliseconds(83_460), Rounding::Truncate),
+            "1:23.4"
+        );
+    }
+
+    #[test]
+    fn zero_decimals_can_be_hidden() {
+        let mut tf = make_tf(false, true, true, 2);
+        tf.hide_zero_decimals = true;
+        let ms = |ms| tf.format_duration(&time::Duration::milliseconds(ms));
+        assert_eq!(ms(125_000), "2:05");
+        assert_eq!(ms(125_009), "2:05");
+        assert_eq!(ms(125_010), "2:05.01");
+        assert_eq!(ms(125_500), "2:05.50");
+        // Leading zero units are dropped as usual, leaving the bare seconds
+        assert_eq!(ms(0), "0");
+        // Rounding up to a whole second drops them too
+        assert_eq!(
+            tf.format_duration_rounded(&time::Duration::milliseconds(124_996), Rounding::Nearest),
+            "2:05"
+        );
+        assert_eq!(
+            tf.format_duration_rounded(&time::Duration::milliseconds(124_994), Rounding::Nearest),
+            "2:04.99"
+        );
+    }
+
+    #[test]
+    fn total_seconds_count_every_unit_in_seconds() {
+        let ms = |tf: &TimeFormat, ms| tf.format_duration(&time::Duration::milliseconds(ms));
+        let mut tf = make_tf(true, true, true, 2);
+        tf.total_seconds = true;
+        assert_eq!(tf.compute_pattern(None), "S.dd");
+        assert_eq!(ms(&tf, 0), "0.00");
+        assert_eq!(ms(&tf, 450), "0.45");
+        assert_eq!(ms(&tf, 59_999), "59.99");
+        assert_eq!(ms(&tf, 125_500), "125.50");
+        assert_eq!(ms(&tf, 7_384_256), "7384.25");
+        assert_eq!(ms(&tf, 90_000_000), "90000.00");
+
+        tf.set_decimal_places(3);
+        tf.dynamic = true;
+        assert_eq!(ms(&tf, 3_723_456), "3723.456");
+        tf.show_decimals = false;
+        assert_eq!(ms(&tf, 3_723_456), "3723");
+        tf.rounding = Rounding::NearestWhenNoDecimals;
+        assert_eq!(ms(&tf, 3_723_656), "3724");
+    }
+
+    #[test]
+    fn zero_decimals_are_shown_by_default() {
+        let mut tf = make_tf(false, true, true, 3);
+        assert_eq!(
+            tf.format_duration(&time::Duration::seconds(125)),
+            "2:05.000"
+        );
+        tf.hide_zero_decimals = true;
+        assert_eq!(tf.format_duration(&time::Duration::seconds(125)), "2:05");
+        assert_eq!(
+            tf.format_duration(&time::Duration::milliseconds(125_001)),
+            "2:05.001"
+        );
+    }
+
+    #[test]
+    fn days_wrap_the_hours_at_24() {
+        let mut tf = make_tf(true, true, true, 0);
+        tf.set_show_days(true);
+        assert_eq!(tf.compute_pattern(None), "D h:m:s");
+        assert_eq!(
+            tf.format_duration(&time::Duration::milliseconds(106_392_000)),
+            "1d 05:33:12"
+        );
+        assert_eq!(
+            tf.format_duration(&time::Duration::hours(24)),
+            "1d 00:00:00"
+        );
+        assert_eq!(
+            tf.format_duration(&time::Duration::hours(49)),
+            "2d 01:00:00"
+        );
+        // Under a day the days are left out
+        assert_eq!(
+            tf.format_duration(&time::Duration::milliseconds(86_399_999)),
+            "23:59:59"
+        );
+        assert_eq!(tf.format_duration(&time::Duration::seconds(65)), "1:05");
+    }
+
+    #[test]
+    fn days_need_the_hours() {
+        let mut tf = make_tf(false, true, true, 2);
+        tf.set_show_days(true);
+        assert_eq!(tf.compute_pattern(None), "m:s.dd");
+    }
+
+    #[test]
+    fn dynamic_days_only_past_24_hours() {
+        let mut tf = make_tf(true, true, true, 2);
+        tf.dynamic = true;
+        tf.set_show_days(true);
+        assert_eq!(tf.compute_pattern(Some(86_399_999)), "h:m:s");
+        assert_eq!(tf.compute_pattern(Some(86_400_000)), "D h:m:s");
+        assert_eq!(
+            tf.format_duration(&time::Duration::milliseconds(106_392_000)),
+            "1d 05:33:12"
+        );
+        // Rounding up into the next day shows it
+        tf.rounding = Rounding::NearestWhenNoDecimals;
+        assert_eq!(
+            tf.format_duration(&time::Duration::milliseconds(86_399_500)),
+            "1d 00:00:00"
+        );
+    }
+
+    #[test]
+    fn deltas_stay_in_hours() {
+        let mut tf = make_tf(true, true, true, 0);
+        tf.set_show_days(true);
+        let d = time::Duration::milliseconds(106_392_000);
+        assert_eq!(tf.format_duration(&d), "1d 05:33:12");
+        assert_eq!(
+            tf.format_duration_rounded(&d, Rounding::Truncate),
+            "29:33:12"
+        );
+        assert_eq!(
+            tf.format_duration_rounded(&-d, Rounding::Nearest),
+            "29:33:12"
+        );
+    }
+
+    #[test]
+    fn adaptive_precision_keeps_base_when_far_or_lost() {
+        use super::adaptive_decimal_places;
+        assert_eq!(adaptive_decimal_places(2, None), 2);
+        assert_eq!(
+            adaptive_decimal_places(2, Some(time::Duration::seconds(30))),
+            2
+        );
+        assert_eq!(
+            adaptive_decimal_places(2, Some(time::Duration::milliseconds(5_001))),
+            2
+        );
+    }
+
+    #[test]
+    fn adaptive_precision_adds_a_decimal_when_near() {
+        use super::adaptive_decimal_places;
+        assert_eq!(
+            adaptive_decimal_places(1, Some(time::Duration::seconds(5))),
+            2
+        );
+        assert_eq!(
+            adaptive_decimal_places(2, Some(time::Duration::seconds(3))),
+            3
+        );
+        assert_eq!(
+            adaptive_decimal_places(3, Some(time::Duration::seconds(3))),
+            3
+        );
+    }
+
+    #[test]
+    fn adaptive_precision_shows_millis_when_close() {
+        use super::adaptive_decimal_places;
+        assert_eq!(
+            adaptive_decimal_places(1, Some(time::Duration::seconds(1))),
+            3
+        );
+        assert_eq!(
+            adaptive_decimal_places(1, Some(time::Duration::milliseconds(10))),
+            3
+        );
+        assert_eq!(adaptive_decimal_places(2, Some(time::Duration::ZERO)), 3);
+    }
+}
+
+#[cfg(test)]
+mod timer_display_tests {
+    use super::{TimeFormat, current_adjusted_duration, displayed_timer_duration};
+    use livesplit_core::TimingMethod::{self, GameTime, RealTime};
+    use livesplit_core::{Run, Segment, TimeSpan, Timer, TimerPhase};
+    use time::Duration;
+
+    fn ms(ms: i64) -> Duration {
+        Duration::milliseconds(ms)
+    }
+
+    /// Offset of -5s, as set for a countdown.
+    fn shown(attempt: i64, pause: i64, loading: i64, method: TimingMethod) -> String {
+        let tf = TimeFormat {
+            show_hours: false,
+            decimal_places: 2,
+            ..TimeFormat::default()
+        };
+        let dur = displayed_timer_duration(ms(attempt), ms(-5_000), ms(pause), ms(loading), method);
+        let out = tf.format_duration(&dur);
+        if dur < Duration::ZERO {
+            format!("-{out}")
+        } else {
+            out
+        }
+    }
+
+    #[test]
+    fn countdown_ignores_loads_in_game_time() {
+        assert_eq!(shown(0, 0, 0, GameTime), "-5.00");
+        assert_eq!(shown(2_000, 0, 1_500, GameTime), "-3.00");
+        assert_eq!(shown(2_000, 0, 1_500, RealTime), "-3.00");
+    }
+
+    #[test]
+    fn pauses_during_the_countdown_hold_it() {
+        assert_eq!(shown(3_000, 1_000, 0, GameTime), "-3.00");
+        assert_eq!(shown(3_000, 1_000, 0, RealTime), "-3.00");
+    }
+
+    #[test]
+    fn game_time_starts_at_zero_after_the_countdown() {
+        // 1.5s of loads during the countdown hold game time at zero.
+        assert_eq!(shown(5_000, 0, 1_500, GameTime), "0.00");
+        assert_eq!(shown(6_000, 0, 1_500, GameTime), "0.00");
+        assert_eq!(shown(7_000, 0, 1_500, GameTime), "0.50");
+        assert_eq!(shown(7_000, 0, 1_500, RealTime), "2.00");
+    }
+
+    #[test]
+    fn game_time_subtracts_pauses_and_loads_after_the_countdown() {
+        assert_eq!(shown(20_000, 2_000, 3_000, GameTime), "10.00");
+        assert_eq!(shown(20_000, 2_000, 3_000, RealTime), "13.00");
+    }
+
+    fn finished_timer(method: TimingMethod) -> Timer {
+        let mut run = Run::new();
+        run.push_segment(Segment::new("Only"));
+        let mut timer = Timer::new(run).unwrap();
+        timer.set_current_timing_method(method);
+        timer.start();
+        timer.initialize_game_time();
+        timer.pause_game_time();
+        timer.set_game_time(TimeSpan::from_seconds(8.0));
+        timer.split();
+        timer
+    }
+
+    /// A timer paused right after the start, with `offset_ms` and `loading_ms`.
+    fn paused_timer(offset_ms: i64, loading_ms: i64) -> Timer {
+        let mut run = Run::new();
+        run.push_segment(Segment::new("Only"));
+        run.set_offset(TimeSpan::from_milliseconds(offset_ms as f64));
+        let mut timer = Timer::new(run).unwrap();
+        timer.start();
+        timer.pause();
+        timer.set_loading_times(TimeSpan::from_milliseconds(loading_ms as f64));
+        timer
+    }
+
+    /// `actual` is `expected` plus the few instants between start and pause,
+    /// give or take the float rounding of the clock.
+    fn assert_close(actual: Duration, expected: i64) {
+        let slack = actual - ms(expected);
+        assert!(
+            slack > -ms(1) && slack < ms(50),
+            "{actual} is not {expected}ms"
+        );
+    }
+
+    #[test]
+    fn adjusted_duration_adds_the_offset_and_drops_loads_in_game_time() {
+        let timer = paused_timer(10_000, 3_000);
+        assert_close(
+            current_adjusted_duration(&timer, RealTime, Duration::ZERO),
+            10_000,
+        );
+        assert_close(
+            current_adjusted_duration(&timer, GameTime, Duration::ZERO),
+            7_000,
+        );
+    }
+
+    #[test]
+    fn adjusted_duration_leaves_the_countdown_alone() {
+        let timer = paused_timer(-5_000, 1_500);
+        assert_close(
+            current_adjusted_duration(&timer, RealTime, Duration::ZERO),
+            -5_000,
+        );
+        assert_close(
+            current_adjusted_duration(&timer, GameTime, Duration::ZERO),
+            -5_000,
+        );
+    }
+
+    #[test]
+    fn adjusted_duration_keeps_game_time_at_zero_during_early_loads() {
+        let timer = paused_timer(1_000, 4_000);
+        assert_eq!(
+            current_adjusted_duration(&timer, GameTime, Duration::ZERO),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn the_timer_and_the_running_duration_agree() {
+        // Off a hundredths boundary, which the clock's rounding could straddle
+        let mut timer = paused_timer(-2_005, 500);
+        timer.set_current_timing_method(GameTime);
+        let tf = TimeFormat::default();
+        let running =
+            crate::utils::comparisons::current_attempt_running_duration(&timer, Duration::ZERO);
+        assert_eq!(
+            tf.format_timer(&timer, Duration::ZERO),
+            format!("-{}", tf.format_duration(&running))
+        );
+    }
+
+    #[test]
+    fn ended_timer_shows_the_final_split_time() {
+        let tf = TimeFormat {
+            show_hours: false,
+            decimal_places: 2,
+            ..TimeFormat::default()
+        };
+        let timer = finished_timer(GameTime);
+        assert_eq!(timer.current_phase(), TimerPhase::Ended);
+        assert_eq!(tf.format_timer(&timer, Duration::ZERO), "8.00");
+    }
+
+    #[test]
+    fn ended_timer_stops_updating() {
+        let tf = TimeFormat {
+            show_hours: false,
+            decimal_places: 3,
+            ..TimeFormat::default()
+        };
+        let timer = finished_timer(RealTime);
+        let final_time = timer.run().segments()[0].split_time().real_time;
+        let shown = tf.format_timer(&timer, Duration::ZERO);
+        assert_eq!(shown, tf.format_time_span_opt(final_time));
+
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert_eq!(tf.format_timer(&timer, Duration::ZERO), shown);
+    }
 }
 
 #[allow(unused_imports)]
 #[allow(clippy::identity_op)]
 mod parse_tests {
-    use super::{TimeParseError, parse_hms};
+    use super::{TimeParseError, parse_hms, parse_signed_hms};
     use time::Duration as TimeDuration;
 
     #[test]
@@ -645,9 +1546,9 @@ mod parse_tests {
 
     #[test]
     fn test_invalid_format() {
-        assert_eq!(parse_hms("1:2").err(), Some(TimeParseError));
-        assert_eq!(parse_hms("1:2:3").err(), Some(TimeParseError));
         assert_eq!(parse_hms("1:2:3.").err(), Some(TimeParseError));
+        assert_eq!(parse_hms("1:2:3:4").err(), Some(TimeParseError));
+        assert_eq!(parse_hms("1:2:.5").err(), Some(TimeParseError));
     }
 
     #[test]
@@ -674,11 +1575,75 @@ mod parse_tests {
 
     #[test]
     fn test_seconds_only_missing_fraction() {
-        assert_eq!(parse_hms("12").err(), Some(TimeParseError));
+        assert_eq!(parse_hms("12"), Ok(TimeDuration::seconds(12)));
     }
 
     #[test]
     fn test_minutes_seconds_missing_fraction() {
-        assert_eq!(parse_hms("1:44").err(), Some(TimeParseError));
+        assert_eq!(parse_hms("1:44"), Ok(TimeDuration::seconds(104)));
+        assert_eq!(parse_hms("1:2:3"), Ok(TimeDuration::seconds(3723)));
+    }
+
+    #[test]
+    fn test_days_match_the_hours() {
+        let expected = TimeDuration::seconds(29 * 3600 + 33 * 60 + 12);
+        assert_eq!(parse_hms("29:33:12.0"), Ok(expected));
+        assert_eq!(parse_hms("1d5:33:12.0"), Ok(expected));
+        assert_eq!(parse_hms("1d 05:33:12.0"), Ok(expected));
+        assert_eq!(parse_hms("0d 1:00:00.5"), parse_hms("1:00:00.5"));
+        // The fraction is optional with days too
+        assert_eq!(parse_hms("29:33:12"), Ok(expected));
+        assert_eq!(parse_hms("1d5:33:12"), Ok(expected));
+    }
+
+    #[test]
+    fn test_days_overflow() {
+        let days = format!("{}d0:00:00", u64::MAX / 86_400 + 1);
+        assert_eq!(parse_hms(&days).err(), Some(TimeParseError));
+        let days = format!("{}d0:00:00", u64::MAX / 86_400);
+        assert_eq!(parse_hms(&days).err(), Some(TimeParseError));
+    }
+
+    #[test]
+    fn test_days_invalid() {
+        assert_eq!(parse_hms("1d24:00:00.0").err(), Some(TimeParseError));
+        assert_eq!(parse_hms("1d33:12.0").err(), Some(TimeParseError));
+        assert_eq!(parse_hms("1d12.0").err(), Some(TimeParseError));
+        assert_eq!(parse_hms("xd1:00:00.0").err(), Some(TimeParseError));
+        assert_eq!(parse_hms("d1:00:00.0").err(), Some(TimeParseError));
+    }
+
+    #[test]
+    fn test_days_round_trip_across_a_day() {
+        let mut tf = super::TimeFormat::new(true, true, true, true, 3, false);
+        tf.set_show_days(true);
+        for ms in [86_399_999, 86_400_000, 90_061_001, 106_392_250] {
+            let d = TimeDuration::milliseconds(ms);
+            assert_eq!(parse_hms(&tf.format_duration(&d)), Ok(d));
+        }
+    }
+
+    #[test]
+    fn test_signed_negative() {
+        let d = parse_signed_hms("-1:02.5").unwrap();
+        assert_eq!(d, -TimeDuration::milliseconds(62_500));
+    }
+
+    #[test]
+    fn test_signed_explicit_plus_and_unsigned() {
+        assert_eq!(
+            parse_signed_hms("+0.25"),
+            Ok(TimeDuration::milliseconds(250))
+        );
+        assert_eq!(
+            parse_signed_hms(" 0.25 "),
+            Ok(TimeDuration::milliseconds(250))
+        );
+    }
+
+    #[test]
+    fn test_signed_invalid() {
+        assert_eq!(parse_signed_hms("--1.0").err(), Some(TimeParseError));
+        assert_eq!(parse_signed_hms("-").err(), Some(TimeParseError));
     }
 }
@@ -0,0 +1,215 @@
+//! Just enough of the zip format for setup bundles.
+//!
+//! Archives are written with deflated entries and UTF-8 names. Reading
+//! takes the entries listed in the central directory, stored or deflated,
+//! so a bundle repacked with another zip tool still opens. Nothing else
+//! (encryption, zip64, multiple disks) is supported.
+
+use std::io::{self, Read, Write};
+
+use flate2::Compression;
+use flate2::Crc;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+const END_OF_CENTRAL_DIRECTORY_LEN: usize = 22;
+const VERSION: u16 = 20;
+/// Entry names are UTF-8.
+const UTF8_NAMES: u16 = 1 << 11;
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+/// 1980-01-01, the earliest date zip can hold.
+const DOS_DATE: u16 = (1 << 5) | 1;
+/// Bundles hold a config and a splits file, anything bigger is refused
+/// rather than inflated.
+const MAX_ENTRY_SIZE: u64 = 64 * 1024 * 1024;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc::new();
+    crc.update(data);
+    crc.sum()
+}
+
+/// Whether `bytes` start like a zip archive.
+pub fn is_archive(bytes: &[u8]) -> bool {
+    bytes.starts_with(&LOCAL_HEADER.to_le_bytes())
+}
+
+/// A zip archive of `entries`, as name and contents.
+pub fn write_archive(entries: &[(&str, &[u8])]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut central = Vec::new();
+    for (name, contents) in entries {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(contents)?;
+        let compressed = encoder.finish()?;
+        let too_big = |_| invalid("entry too large for a bundle");
+        let sizes = (
+            u32::try_from(compressed.len()).map_err(too_big)?,
+            u32::try_from(contents.len()).map_err(too_big)?,
+        );
+        let name_len = u16::try_from(name.len()).map_err(|_| invalid("entry name too long"))?;
+        let offset = u32::try_from(out.len()).map_err(too_big)?;
+        let crc = crc32(contents);
+
+        // Fields shared by the local and the central header, from the flags on
+        let mut common = Vec::new();
+        common.extend(UTF8_NAMES.to_le_bytes());
+        common.extend(DEFLATED.to_le_bytes());
+        common.extend(0u16.to_le_bytes());
+        common.extend(DOS_DATE.to_le_bytes());
+        common.extend(crc.to_le_bytes());
+        common.extend(sizes.0.to_le_bytes());
+        common.extend(sizes.1.to_le_bytes());
+        common.extend(name_len.to_le_bytes());
+        common.extend(0u16.to_le_bytes());
+
+        out.extend(LOCAL_HEADER.to_le_bytes());
+        out.extend(VERSION.to_le_bytes());
+        out.extend(&common);
+        out.extend(name.as_bytes());
+        out.extend(&compressed);
+
+        central.extend(CENTRAL_HEADER.to_le_bytes());
+        central.extend(VERSION.to_le_bytes());
+        central.extend(VERSION.to_le_bytes());
+        central.extend(&common);
+        // Comment length, disk, internal and external attributes
+        central.extend([0; 10]);
+        central.extend(offset.to_le_bytes());
+        central.extend(name.as_bytes());
+    }
+
+    let count = u16::try_from(entries.len()).map_err(|_| invalid("too many entries"))?;
+    let central_len = u32::try_from(central.len()).map_err(|_| invalid("archive too large"))?;
+    let central_offset = u32::try_from(out.len()).map_err(|_| invalid("archive too large"))?;
+    out.extend(central);
+    out.extend(END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+    out.extend([0; 4]);
+    out.extend(count.to_le_bytes());
+    out.extend(count.to_le_bytes());
+    out.extend(central_len.to_le_bytes());
+    out.extend(central_offset.to_le_bytes());
+    out.extend(0u16.to_le_bytes());
+    Ok(out)
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> io::Result<u16> {
+    bytes
+        .get(at..at + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| invalid("truncated archive"))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> io::Result<u32> {
+    bytes
+        .get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| invalid("truncated archive"))
+}
+
+fn slice(bytes: &[u8], at: usize, len: usize) -> io::Result<&[u8]> {
+    at.checked_add(len)
+        .and_then(|end| bytes.get(at..end))
+        .ok_or_else(|| invalid("truncated archive"))
+}
+
+/// The entries of the zip archive `bytes`, as name and contents, in the
+/// order of its central directory.
+pub fn read_archive(bytes: &[u8]) -> io::Result<Vec<(String, Vec<u8>)>> {
+    // The end record sits at the end, only followed by the archive comment
+    let end = (0..=bytes.len().saturating_sub(END_OF_CENTRAL_DIRECTORY_LEN))
+        .rev()
+        .take(usize::from(u16::MAX) + 1)
+        .find(|&at| u32_at(bytes, at).ok() == Some(END_OF_CENTRAL_DIRECTORY))
+        .ok_or_else(|| invalid("not a zip archive"))?;
+    let count = u16_at(bytes, end + 10)?;
+    let mut at = u32_at(bytes, end + 16)? as usize;
+
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        if u32_at(bytes, at)? != CENTRAL_HEADER {
+            return Err(invalid("damaged central directory"));
+        }
+        let method = u16_at(bytes, at + 10)?;
+        let crc = u32_at(bytes, at + 16)?;
+        let compressed_len = u32_at(bytes, at + 20)? as usize;
+        let len = u64::from(u32_at(bytes, at + 24)?);
+        let name_len = usize::from(u16_at(bytes, at + 28)?);
+        let skipped = usize::from(u16_at(bytes, at + 30)?) + usize::from(u16_at(bytes, at + 32)?);
+        let local = u32_at(bytes, at + 42)? as usize;
+        let name = String::from_utf8_lossy(slice(bytes, at + 46, name_len)?).into_owned();
+        at += 46 + name_len + skipped;
+
+        if u32_at(bytes, local)? != LOCAL_HEADER {
+            return Err(invalid("damaged entry"));
+        }
+        let data_at = local
+            + 30
+            + usize::from(u16_at(bytes, local + 26)?)
+            + usize::from(u16_at(bytes, local + 28)?);
+        let data = slice(bytes, data_at, compressed_len)?;
+        if len > MAX_ENTRY_SIZE {
+            return Err(invalid("entry too large for a bundle"));
+        }
+        let contents = match method {
+            STORED => data.to_vec(),
+            DEFLATED => {
+                let mut contents = Vec::new();
+                DeflateDecoder::new(data)
+                    .take(len)
+                    .read_to_end(&mut contents)?;
+                contents
+            }
+            _ => return Err(invalid("unsupported compression")),
+        };
+        if contents.len() as u64 != len || crc32(&contents) != crc {
+            return Err(invalid("damaged entry"));
+        }
+        entries.push((name, contents));
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod archive_tests {
+    use super::*;
+
+    #[test]
+    fn entries_round_trip() {
+        let yaml = "version: 2\n".repeat(100);
+        let bytes = write_archive(&[
+            ("setup.yaml", yaml.as_bytes()),
+            ("theme.css", b""),
+            ("splits/Game Any%.lss", b"<Run />"),
+        ])
+        .unwrap();
+        assert!(is_archive(&bytes));
+        let entries = read_archive(&bytes).unwrap();
+        let names: Vec<_> = entries.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["setup.yaml", "theme.css", "splits/Game Any%.lss"]);
+        assert_eq!(entries[0].1, yaml.as_bytes());
+        assert!(entries[1].1.is_empty());
+        assert_eq!(entries[2].1, b"<Run />");
+    }
+
+    #[test]
+    fn damaged_archives_are_refused() {
+        let bytes = write_archive(&[("setup.yaml", b"version: 2\n")]).unwrap();
+        assert!(read_archive(&bytes[..bytes.len() - 1]).is_err());
+        assert!(read_archive(b"version: 1\n").is_err());
+        assert!(!is_archive(b"version: 1\n"));
+
+        // A flipped bit in the compressed data fails the checksum
+        let mut flipped = bytes.clone();
+        flipped[30 + "setup.yaml".len() + 3] ^= 0x10;
+        assert!(read_archive(&flipped).is_err());
+    }
+}
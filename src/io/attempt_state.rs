@@ -0,0 +1,698 @@
+//! The attempt in progress, kept across an intentional restart.
+//!
+//! "Save Attempt and Quit" writes the live attempt to a state file: the
+//! times of the splits already taken, the attempt's real and game time and
+//! the wall-clock time they were read at. On the next start the attempt is
+//! offered back. The real time has kept running while TuxSplit was closed,
+//! unless the attempt was paused; the game time resumes paused, for the
+//! game or the auto-splitter to unpause.
+//!
+//! livesplit-core's timer reads its own clock and can't be told when the
+//! earlier splits happened, so the live attempt skips them and is started
+//! at the saved attempt time through the run's start offset. The running
+//! time and the final time are exact that way. Meanwhile the split list,
+//! deltas and golds read the skipped splits at their saved times, see
+//! [`resumed_split_time`]. Once the attempt is
+//! recorded, by a reset or on saving, the saved splits are replayed into
+//! it at their recorded times, see [`finish_resumed_attempt`], so its
+//! segment history, golds and personal best hold them as they were taken
+//! rather than as skipped. Races joined late are started the same way,
+//! see `start_late`.
+
+use std::cell::RefCell;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use livesplit_core::{Run, Segment, Time, TimeSpan, Timer, TimerPhase, TimingMethod};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tracing::warn;
+
+use crate::config::data_dir;
+use crate::utils::comparisons::attempt_split_time;
+use crate::utils::trace_timing::splits_io_span;
+
+thread_local! {
+    static RESUMED: RefCell<Option<Resumed>> = const { RefCell::new(None) };
+}
+
+/// A resumed attempt until it is over.
+struct Resumed {
+    /// The run's own start offset, which the attempt borrows.
+    offset: TimeSpan,
+    /// The splits taken before the restart.
+    splits: Vec<Time>,
+    /// Golds and personal best split times when the attempt was resumed.
+    golds: Vec<Time>,
+    pb: Vec<Time>,
+    /// Highest attempt index before the attempt, the attempt is recorded
+    /// above it.
+    last_attempt: Option<i32>,
+}
+
+/// A split taken before the restart, in milliseconds. `None` when skipped.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct SavedSplit {
+    pub real_time_ms: Option<i64>,
+    pub game_time_ms: Option<i64>,
+}
+
+impl SavedSplit {
+    fn time(self) -> Time {
+        Time::new()
+            .with_real_time(self.real_time_ms.map(span))
+            .with_game_time(self.game_time_ms.map(span))
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct SavedAttempt {
+    /// Splits file the attempt belongs to.
+    pub splits: Option<PathBuf>,
+    /// Segment names, to tell the attempt was made on the same splits.
+    pub segments: Vec<String>,
+    pub comparison: String,
+    pub timing_method: TimingMethod,
+    /// Unix time in milliseconds the times below were read at.
+    pub saved_at_ms: i64,
+    pub paused: bool,
+    pub real_time_ms: i64,
+    pub game_time_ms: Option<i64>,
+    /// Real time minus game time, a load in progress included. `None`
+    /// without game time.
+    #[serde(default)]
+    pub loading_times_ms: Option<i64>,
+    /// The run's start offset, which resuming borrows.
+    pub offset_ms: i64,
+    /// The splits before the current one.
+    pub splits_done: Vec<SavedSplit>,
+}
+
+/// Times of a saved attempt once it has been resumed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResumedTimes {
+    pub real_time_ms: i64,
+    pub game_time_ms: Option<i64>,
+    pub loading_times_ms: Option<i64>,
+}
+
+/// Rounded, `from_milliseconds` doesn't always give back the exact value.
+fn millis(span: TimeSpan) -> i64 {
+    span.total_milliseconds().round() as i64
+}
+
+fn span(ms: i64) -> TimeSpan {
+    TimeSpan::from_milliseconds(ms as f64)
+}
+
+fn unix_ms(now: OffsetDateTime) -> i64 {
+    (now.unix_timestamp_nanos() / 1_000_000) as i64
+}
+
+/// `attempt.yaml` in the data directory, see [`data_dir`].
+pub fn default_attempt_path() -> PathBuf {
+    data_dir().map_or_else(
+        || std::env::temp_dir().join("tuxsplit-attempt.yaml"),
+        |dir| dir.join("attempt.yaml"),
+    )
+}
+
+impl SavedAttempt {
+    /// The attempt in progress on `timer` at `now`. `None` outside an attempt.
+    pub fn capture(timer: &Timer, splits: Option<PathBuf>, now: OffsetDateTime) -> Option<Self> {
+        let phase = timer.current_phase();
+        if !matches!(phase, TimerPhase::Running | TimerPhase::Paused) {
+            return None;
+        }
+        let current = timer.current_split_index()?;
+        let time = timer.snapshot().current_time();
+        let run = timer.run();
+        let real_time_ms = time.real_time.map_or(0, millis);
+        let game_time_ms = timer
+            .is_game_time_initialized()
+            .then(|| time.game_time.map(millis))
+            .flatten();
+        Some(Self {
+            splits,
+            segments: run
+                .segments()
+                .iter()
+                .map(|segment| segment.name().to_owned())
+                .collect(),
+            comparison: timer.current_comparison().to_owned(),
+            timing_method: timer.current_timing_method(),
+            saved_at_ms: unix_ms(now),
+            paused: phase == TimerPhase::Paused,
+            real_time_ms,
+            game_time_ms,
+            loading_times_ms: game_time_ms.map(|game| real_time_ms - game),
+            offset_ms: millis(own_offset(run)),
+            splits_done: (0..current)
+                .map(|index| {
+                    let time = attempt_split_time(timer, index);
+                    SavedSplit {
+                        real_time_ms: time.real_time.map(millis),
+                        game_time_ms: time.game_time.map(millis),
+                    }
+                })
+                .collect(),
+        })
+    }
+
+    /// Whether the attempt was made on `run`.
+    pub fn matches(&self, run: &Run) -> bool {
+        run.segments()
+            .iter()
+            .map(|segment| segment.name())
+            .eq(self.segments.iter().map(String::as_str))
+    }
+
+    /// The attempt's times at `now`. The real time kept running while
+    /// TuxSplit was closed, the game time waited, so that time counts as
+    /// loading.
+    pub fn resumed_at(&self, now: OffsetDateTime) -> ResumedTimes {
+        let closed = if self.paused {
+            0
+        } else {
+            (unix_ms(now) - self.saved_at_ms).max(0)
+        };
+        let real_time_ms = self.real_time_ms + closed;
+        // Files saved before the loading times were kept
+        let loading_times_ms = self
+            .loading_times_ms
+            .or_else(|| Some(self.real_time_ms - self.game_time_ms?))
+            .map(|loading| loading + closed);
+        ResumedTimes {
+            real_time_ms,
+            game_time_ms: loading_times_ms.map(|loading| real_time_ms - loading),
+            loading_times_ms,
+        }
+    }
+
+    /// Difference of each split taken before the restart to `run`'s
+    /// comparison, in milliseconds, in the saved timing method.
+    pub fn split_diffs(&self, run: &Run) -> Vec<Option<i64>> {
+        self.splits_done
+            .iter()
+            .zip(run.segments())
+            .map(|(split, segment)| {
+                let recorded = match self.timing_method {
+                    TimingMethod::RealTime => split.real_time_ms,
+                    TimingMethod::GameTime => split.game_time_ms,
+                }?;
+                let compared = segment.comparison(&self.comparison)[self.timing_method]?;
+                Some(recorded - millis(compared))
+            })
+            .collect()
+    }
+
+    /// Rebuilds the attempt on `timer`, which must not be running, with
+    /// the times at `now`. Returns whether the attempt could be resumed.
+    pub fn resume(&self, timer: &mut Timer, now: OffsetDateTime) -> bool {
+        if timer.current_phase() != TimerPhase::NotRunning
+            || !self.matches(timer.run())
+            || self.splits_done.len() >= self.segments.len()
+        {
+            return false;
+        }
+        let times = self.resumed_at(now);
+        let before = timer.run().clone();
+        let mut run = before.clone();
+        run.set_offset(span(times.real_time_ms));
+        if timer.set_run(run).is_err() {
+            return false;
+        }
+        RESUMED.set(Some(Resumed::new(
+            &before,
+            span(self.offset_ms),
+            self.splits_done.iter().map(|split| split.time()).collect(),
+        )));
+
+        timer.set_current_timing_method(self.timing_method);
+        let _ = timer.set_current_comparison(self.comparison.as_str());
+        timer.start();
+        // Replayed at their times once the attempt is recorded
+        for _ in &self.splits_done {
+            timer.skip_split();
+        }
+        if let Some(loading) = times.loading_times_ms {
+            timer.initialize_game_time();
+            timer.set_loading_times(span(loading));
+            timer.pause_game_time();
+        }
+        if self.paused {
+            timer.pause();
+        }
+        true
+    }
+}
+
+/// `time` as the segment's time in `id`'s attempt.
+fn set_history(segment: &mut Segment, id: i32, time: Time) {
+    let history = segment.segment_history_mut();
+    history.remove(id);
+    history.insert(id, time);
+}
+
+/// The better of `gold` and `segment`, as the timer picks golds.
+fn better(gold: Option<TimeSpan>, segment: Option<TimeSpan>) -> Option<TimeSpan> {
+    match (gold, segment) {
+        (Some(gold), Some(segment)) => Some(if segment < gold { segment } else { gold }),
+        (gold, segment) => gold.or(segment),
+    }
+}
+
+impl Resumed {
+    /// The attempt about to be started on `run`, which borrows its start
+    /// offset `offset`, with the `splits` taken before.
+    fn new(run: &Run, offset: TimeSpan, splits: Vec<Time>) -> Self {
+        Self {
+            offset,
+            splits,
+            golds: run
+                .segments()
+                .iter()
+                .map(Segment::best_segment_time)
+                .collect(),
+            pb: run
+                .segments()
+                .iter()
+                .map(Segment::personal_best_split_time)
+                .collect(),
+            last_attempt: run.max_attempt_history_index(),
+        }
+    }
+
+    /// Replays the saved splits into the attempt recorded last in `run`,
+    /// which the timer recorded with them skipped, and puts the run's own
+    /// offset back.
+    fn replay(&self, run: &mut Run) {
+        run.set_offset(self.offset);
+        let Some(id) = run
+            .max_attempt_history_index()
+            .filter(|&id| Some(id) > self.last_attempt)
+        else {
+            // Reset without updating the splits, nothing was recorded
+            return;
+        };
+        let done = self.splits.len();
+        for method in TimingMethod::all() {
+            let mut previous = TimeSpan::zero();
+            for (index, split) in self.splits.iter().enumerate() {
+                let segment = run.segment_mut(index);
+                let mut history = segment.segment_history().get(id).unwrap_or_default();
+                history[method] = split[method].map(|time| time - previous);
+                set_history(segment, id, history);
+                segment.best_segment_time_mut()[method] =
+                    better(self.golds[index][method], history[method]);
+                if let Some(time) = split[method] {
+                    previous = time;
+                }
+            }
+            // With the splits before it skipped, the first split after the
+            // restart was timed from the start of the attempt
+            for index in done..run.len() {
+                let segment = run.segment_mut(index);
+                let Some(mut history) = segment.segment_history().get(id) else {
+                    break;
+                };
+                let Some(combined) = history[method] else {
+                    continue;
+                };
+                history[method] = Some(combined - previous);
+                set_history(segment, id, history);
+                segment.best_segment_time_mut()[method] =
+                    better(self.golds[index][method], history[method]);
+                break;
+            }
+        }
+
+        // A new personal best took the skipped splits as they were
+        let last = run.len() - 1;
+        if run.segment(last).personal_best_split_time() != self.pb[last] {
+            for (index, split) in self.splits.iter().enumerate() {
+                run.segment_mut(index).set_personal_best_split_time(*split);
+            }
+        }
+        run.regenerate_comparisons();
+    }
+}
+
+/// Starts `timer`, which must not be running, as if it was started `late`
+/// ago, e.g. for a race joined after its start. The attempt borrows the
+/// run's start offset like a resumed one without splits, and gives it back
+/// through [`finish_resumed_attempt`]. Returns whether it was started.
+#[cfg(feature = "racetime")]
+pub fn start_late(timer: &mut Timer, late: TimeSpan) -> bool {
+    if timer.current_phase() != TimerPhase::NotRunning {
+        return false;
+    }
+    let before = timer.run().clone();
+    let mut run = before.clone();
+    run.set_offset(before.offset() + late);
+    if timer.set_run(run).is_err() {
+        return false;
+    }
+    RESUMED.set(Some(Resumed::new(&before, before.offset(), Vec::new())));
+    timer.start();
+    true
+}
+
+/// The saved time of split `index` while a resumed attempt is in progress,
+/// for the splits taken before the restart. The timer has them skipped.
+pub fn resumed_split_time(index: usize) -> Option<Time> {
+    RESUMED.with_borrow(|resumed| resumed.as_ref()?.splits.get(index).copied())
+}
+
+/// `run`'s own start offset, which a resumed attempt borrows until it is
+/// over, see [`finish_resumed_attempt`].
+pub fn own_offset(run: &Run) -> TimeSpan {
+    RESUMED.with_borrow(|resumed| {
+        resumed
+            .as_ref()
+            .map_or(run.offset(), |resumed| resumed.offset)
+    })
+}
+
+/// Replays the resumed attempt, if there is one, into `run`, a copy of its
+/// run that recorded it, see [`crate::io::integrity::run_to_save`]. The
+/// attempt stays resumed.
+pub fn replay_resumed_attempt(run: &mut Run) {
+    RESUMED.with_borrow(|resumed| {
+        if let Some(resumed) = resumed {
+            resumed.replay(run);
+        }
+    });
+}
+
+/// Replays the resumed attempt into `run` once the attempt is over and
+/// the timer recorded it, or dropped it. Returns whether there was one.
+pub fn finish_resumed_attempt(run: &mut Run) -> bool {
+    match RESUMED.take() {
+        Some(resumed) => {
+            resumed.replay(run);
+            true
+        }
+        None => false,
+    }
+}
+
+pub fn save_attempt(attempt: &SavedAttempt, path: &Path) -> io::Result<()> {
+    let span = splits_io_span("save_attempt", path);
+    let _entered = span.enter();
+    let yaml = serde_yaml::to_string(attempt).map_err(io::Error::other)?;
+    span.record("bytes", yaml.len());
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, yaml)
+}
+
+/// The saved attempt, if there is one. The file stays until the attempt
+/// is resumed or discarded, see [`discard_attempt`].
+pub fn load_attempt(path: &Path) -> Option<SavedAttempt> {
+    let yaml = fs::read_to_string(path).ok()?;
+    serde_yaml::from_str(&yaml).ok()
+}
+
+pub fn discard_attempt(path: &Path) {
+    if let Err(e) = fs::remove_file(path)
+        && e.kind() != io::ErrorKind::NotFound
+    {
+        warn!("Could not remove the saved attempt {}: {e}", path.display());
+    }
+}
+
+#[cfg(test)]
+mod attempt_state_tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use crate::utils::comparisons::{current_segment_elapsed, split_delta};
+
+    /// 2026-10-14 12:00:00 UTC plus `ms`, the mock clock.
+    fn at(ms: i64) -> OffsetDateTime {
+        OffsetDateTime::from_unix_timestamp(1_791_979_200).unwrap()
+            + time::Duration::milliseconds(ms)
+    }
+
+    /// PB splits at 10.000, 25.500 and 40.250.
+    fn run() -> Run {
+        let mut run = Run::new();
+        for (name, pb) in [("One", 10_000), ("Two", 25_500), ("Three", 40_250)] {
+            let mut segment = Segment::new(name);
+            segment.set_personal_best_split_time(
+                Time::new()
+                    .with_real_time(Some(span(pb)))
+                    .with_game_time(Some(span(pb - 1_000))),
+            );
+            run.push_segment(segment);
+        }
+        run
+    }
+
+    /// Two splits in, 31.337 of real time and 29.001 of game time.
+    fn saved(paused: bool) -> SavedAttempt {
+        SavedAttempt {
+            splits: Some(PathBuf::from("/splits/game.lss")),
+            segments: ["One", "Two", "Three"].map(str::to_owned).to_vec(),
+            comparison: "Personal Best".to_owned(),
+            timing_method: TimingMethod::RealTime,
+            saved_at_ms: unix_ms(at(0)),
+            paused,
+            real_time_ms: 31_337,
+            game_time_ms: Some(29_001),
+            loading_times_ms: Some(2_336),
+            offset_ms: 0,
+            splits_done: vec![
+                SavedSplit {
+                    real_time_ms: Some(9_876),
+                    game_time_ms: Some(9_000),
+                },
+                SavedSplit {
+                    real_time_ms: Some(26_123),
+                    game_time_ms: Some(24_002),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn the_state_survives_the_file() {
+        let path = temp_dir("attempt-file").join("attempt.yaml");
+        let attempt = saved(false);
+        save_attempt(&attempt, &path).unwrap();
+        assert_eq!(load_attempt(&path), Some(attempt.clone()));
+        // Still there until it's resumed or discarded
+        assert_eq!(load_attempt(&path), Some(attempt));
+        discard_attempt(&path);
+        assert!(!path.exists());
+        assert_eq!(load_attempt(&path), None);
+        discard_attempt(&path);
+    }
+
+    #[test]
+    fn files_without_loading_times_still_resume_the_game_time() {
+        let mut attempt = saved(false);
+        attempt.loading_times_ms = None;
+        let yaml = serde_yaml::to_string(&attempt)
+            .unwrap()
+            .replace("loading-times-ms: null\n", "");
+        let attempt: SavedAttempt = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(attempt.loading_times_ms, None);
+        assert_eq!(attempt.resumed_at(at(1_000)).game_time_ms, Some(29_001));
+        assert_eq!(attempt.resumed_at(at(1_000)).loading_times_ms, Some(3_336));
+    }
+
+    #[test]
+    fn real_time_keeps_running_while_closed() {
+        let attempt = saved(false);
+        assert_eq!(
+            attempt.resumed_at(at(90_250)),
+            ResumedTimes {
+                real_time_ms: 121_587,
+                game_time_ms: Some(29_001),
+                loading_times_ms: Some(92_586),
+            }
+        );
+        // A clock that went back doesn't take time off
+        assert_eq!(attempt.resumed_at(at(-5_000)).real_time_ms, 31_337);
+        // Nothing runs while paused
+        assert_eq!(saved(true).resumed_at(at(90_250)).real_time_ms, 31_337);
+    }
+
+    #[test]
+    fn diffs_are_taken_against_the_saved_comparison() {
+        let mut attempt = saved(false);
+        assert_eq!(attempt.split_diffs(&run()), vec![Some(-124), Some(623)]);
+        attempt.timing_method = TimingMethod::GameTime;
+        attempt.splits_done[1].game_time_ms = None;
+        assert_eq!(attempt.split_diffs(&run()), vec![Some(0), None]);
+    }
+
+    #[test]
+    fn resumed_timer_continues_from_the_saved_times() {
+        let attempt = saved(false);
+        let mut timer = Timer::new(run()).unwrap();
+        assert!(attempt.resume(&mut timer, at(90_250)));
+
+        assert_eq!(timer.current_phase(), TimerPhase::Running);
+        assert_eq!(timer.current_split_index(), Some(2));
+        assert_eq!(timer.current_comparison(), "Personal Best");
+        let time = timer.snapshot().current_time();
+        // The few microseconds the test takes don't reach a millisecond
+        assert_eq!(time.real_time.map(millis), Some(121_587));
+        assert_eq!(time.game_time.map(millis), Some(29_001));
+        assert!(timer.is_game_time_paused());
+        assert_eq!(millis(timer.loading_times()), 92_586);
+
+        // Saving again right away gives the same attempt back
+        let again = SavedAttempt::capture(&timer, attempt.splits.clone(), at(90_250)).unwrap();
+        assert_eq!(again.real_time_ms, 121_587);
+        assert_eq!(again.game_time_ms, Some(29_001));
+        assert_eq!(again.loading_times_ms, Some(92_586));
+        assert_eq!(again.offset_ms, attempt.offset_ms);
+        assert_eq!(again.splits_done, attempt.splits_done);
+
+        // The skipped splits are compared at their saved times meanwhile
+        assert_eq!(
+            split_delta(&timer, 0),
+            Some(time::Duration::milliseconds(-124))
+        );
+        assert_eq!(
+            split_delta(&timer, 1),
+            Some(time::Duration::milliseconds(623))
+        );
+        let elapsed = current_segment_elapsed(&timer, time::Duration::ZERO).unwrap();
+        assert_eq!(elapsed.whole_milliseconds(), 121_587 - 26_123);
+
+        timer.reset(false);
+        let mut run = timer.run().clone();
+        assert!(finish_resumed_attempt(&mut run));
+        assert_eq!(millis(run.offset()), 0);
+        // Nothing was recorded to replay into
+        assert!(run.attempt_history().is_empty());
+        assert!(!finish_resumed_attempt(&mut run));
+    }
+
+    fn history(run: &Run, index: usize, id: i32) -> Option<(i64, Option<i64>)> {
+        let time = run.segment(index).segment_history().get(id)?;
+        Some((time.real_time.map(millis)?, time.game_time.map(millis)))
+    }
+
+    #[test]
+    fn a_finished_attempt_records_the_saved_splits_at_their_times() {
+        let attempt = saved(false);
+        let mut timer = Timer::new(run()).unwrap();
+        assert!(attempt.resume(&mut timer, at(90_250)));
+        timer.set_game_time(span(35_000));
+        timer.split();
+        assert_eq!(timer.current_phase(), TimerPhase::Ended);
+        timer.reset(true);
+
+        let mut run = timer.run().clone();
+        assert!(finish_resumed_attempt(&mut run));
+        let id = run.max_attempt_history_index().unwrap();
+        assert_eq!(history(&run, 0, id), Some((9_876, Some(9_000))));
+        assert_eq!(history(&run, 1, id), Some((16_247, Some(15_002))));
+        // Timed from the last saved split, not from the start
+        assert_eq!(history(&run, 2, id), Some((95_464, Some(10_998))));
+        let golds: Vec<_> = run
+            .segments()
+            .iter()
+            .map(|segment| segment.best_segment_time().real_time.map(millis))
+            .collect();
+        // Only the first segment beats the golds taken from the PB
+        assert_eq!(golds, vec![Some(9_876), Some(15_500), Some(14_750)]);
+        // 121.587 doesn't beat the 40.250 personal best
+        assert_eq!(
+            run.segment(0)
+                .personal_best_split_time()
+                .real_time
+                .map(millis),
+            Some(10_000)
+        );
+    }
+
+    #[test]
+    fn a_new_personal_best_keeps_the_saved_splits() {
+        let attempt = saved(false);
+        let mut timer = Timer::new(run()).unwrap();
+        assert!(attempt.resume(&mut timer, at(0)));
+        timer.split();
+        timer.reset(true);
+
+        let mut run = timer.run().clone();
+        assert!(finish_resumed_attempt(&mut run));
+        let pb: Vec<_> = run
+            .segments()
+            .iter()
+            .map(|segment| segment.personal_best_split_time().real_time.map(millis))
+            .collect();
+        assert_eq!(pb, vec![Some(9_876), Some(26_123), Some(31_337)]);
+    }
+
+    #[test]
+    fn a_reset_mid_attempt_records_the_saved_splits() {
+        let attempt = saved(false);
+        let mut timer = Timer::new(run()).unwrap();
+        let golds = timer.run().segment(2).best_segment_time();
+        assert!(attempt.resume(&mut timer, at(0)));
+        timer.reset(true);
+
+        let mut run = timer.run().clone();
+        assert!(finish_resumed_attempt(&mut run));
+        let id = run.max_attempt_history_index().unwrap();
+        assert_eq!(history(&run, 0, id), Some((9_876, Some(9_000))));
+        assert_eq!(history(&run, 1, id), Some((16_247, Some(15_002))));
+        assert_eq!(history(&run, 2, id), None);
+        assert_eq!(run.segment(2).best_segment_time(), golds);
+    }
+
+    #[test]
+    fn paused_attempts_resume_paused() {
+        let attempt = saved(true);
+        let mut timer = Timer::new(run()).unwrap();
+        assert!(attempt.resume(&mut timer, at(600_000)));
+        assert_eq!(timer.current_phase(), TimerPhase::Paused);
+        assert_eq!(
+            timer.snapshot().current_time().real_time.map(millis),
+            Some(31_337)
+        );
+    }
+
+    #[test]
+    fn other_splits_or_a_running_timer_are_left_alone() {
+        let mut attempt = saved(false);
+        let mut timer = Timer::new(run()).unwrap();
+        timer.start();
+        assert!(!attempt.resume(&mut timer, at(0)));
+
+        let mut timer = Timer::new(run()).unwrap();
+        attempt.segments[2] = "Renamed".to_owned();
+        assert!(!attempt.resume(&mut timer, at(0)));
+        assert_eq!(timer.current_phase(), TimerPhase::NotRunning);
+        assert!(!finish_resumed_attempt(&mut timer.run().clone()));
+    }
+
+    #[cfg(feature = "racetime")]
+    #[test]
+    fn late_starts_count_the_time_missed() {
+        let mut timer = Timer::new(run()).unwrap();
+        assert!(start_late(&mut timer, span(2_500)));
+        assert!(!start_late(&mut timer, span(2_500)));
+        let now = timer.snapshot().current_time().real_time.map(millis);
+        assert!(now.is_some_and(|ms| ms >= 2_500), "{now:?}");
+        timer.split();
+        timer.reset(true);
+
+        let mut run = timer.run().clone();
+        assert!(finish_resumed_attempt(&mut run));
+        assert_eq!(millis(run.offset()), 0);
+        let id = run.max_attempt_history_index().unwrap();
+        let first = history(&run, 0, id).map(|(real, _)| real);
+        assert!(first.is_some_and(|ms| ms >= 2_500), "{first:?}");
+    }
+}
@@ -0,0 +1,487 @@
+//! Shareable setup bundles.
+//!
+//! A bundle is a zip archive of up to three components: `setup.yaml` with
+//! the shareable settings (see `Config::portable`) and the theme section,
+//! `theme.css` with the stylesheet of the splits, and the splits as
+//! LiveSplit XML under `splits/`. Any component may be missing, so a bundle
+//! can carry just the settings. Imports are validated as a whole before
+//! anything is written or applied. Bundles from before the archive format
+//! are a single YAML document with the splits inlined, and are still read.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use livesplit_core::run::parser::composite;
+use livesplit_core::{Run, Timer};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Config, Theme};
+use crate::io::archive::{is_archive, read_archive, write_archive};
+use crate::io::integrity::save_timer_stamped;
+use crate::utils::trace_timing::splits_io_span;
+
+/// Newest bundle layout this version can read.
+pub const BUNDLE_VERSION: u32 = 2;
+pub const BUNDLE_EXTENSION: &str = "tuxsplit";
+const BUNDLE_HEADER: &str = "# TuxSplit setup bundle\n";
+const DEFAULT_SPLITS_NAME: &str = "splits.lss";
+const MANIFEST_ENTRY: &str = "setup.yaml";
+const CSS_ENTRY: &str = "theme.css";
+const SPLITS_DIR: &str = "splits/";
+
+/// `setup.yaml` of a bundle, or a whole bundle in the old format.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Manifest {
+    version: u32,
+    #[serde(default)]
+    config: Option<Config>,
+    #[serde(default)]
+    theme: Option<Theme>,
+    /// Only in the old format, archives have an entry for them.
+    #[serde(default, skip_serializing)]
+    splits: Option<BundledSplits>,
+}
+
+#[derive(Debug)]
+pub struct SetupBundle {
+    pub config: Option<Config>,
+    pub theme: Option<Theme>,
+    /// Stylesheet of the bundled splits, or of the loaded ones if the bundle
+    /// has none.
+    pub css: Option<String>,
+    pub splits: Option<BundledSplits>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BundledSplits {
+    pub file_name: String,
+    pub contents: String,
+}
+
+#[derive(Debug)]
+pub enum BundleError {
+    Parse(String),
+    UnsupportedVersion(u32),
+    Empty,
+    InvalidSplits(String),
+    InvalidFileName(String),
+    Io(std::io::Error),
+    /// The settings were in use elsewhere, nothing was applied.
+    ConfigBusy,
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BundleError::Parse(err) => write!(f, "Not a TuxSplit setup bundle: {err}"),
+            BundleError::UnsupportedVersion(version) => write!(
+                f,
+                "This bundle was made by a newer TuxSplit (format {version}, supported up to {BUNDLE_VERSION})"
+            ),
+            BundleError::Empty => write!(f, "The bundle does not contain anything to import"),
+            BundleError::InvalidSplits(err) => write!(f, "The bundled splits are invalid: {err}"),
+            BundleError::InvalidFileName(name) => {
+                write!(f, "The bundled splits have an invalid file name: {name:?}")
+            }
+            BundleError::Io(err) => write!(f, "Could not access the file: {err}"),
+            BundleError::ConfigBusy => {
+                write!(
+                    f,
+                    "The settings were being changed elsewhere, try importing again"
+                )
+            }
+        }
+    }
+}
+
+/// A bundle whose components all passed validation.
+#[derive(Debug)]
+pub struct ValidatedBundle {
+    pub config: Option<Config>,
+    pub theme: Option<Theme>,
+    pub css: Option<String>,
+    pub splits: Option<ValidatedSplits>,
+}
+
+#[derive(Debug)]
+pub struct ValidatedSplits {
+    pub file_name: String,
+    pub contents: String,
+    pub run: Run,
+}
+
+impl ValidatedBundle {
+    /// Human readable names of the components present, for the import summary.
+    pub fn components(&self) -> Vec<&'static str> {
+        let mut components = Vec::new();
+        if self.config.is_some() {
+            components.push("Settings");
+        }
+        if self.theme.is_some() {
+            components.push("Theme");
+        }
+        if self.css.is_some() {
+            components.push("Stylesheet");
+        }
+        if self.splits.is_some() {
+            components.push("Splits");
+        }
+        components
+    }
+}
+
+/// Builds a bundle from the live config, with the timer's splits if given.
+/// The stylesheet of the loaded splits comes along either way.
+pub fn export_bundle(config: &Config, timer: Option<&Timer>) -> SetupBundle {
+    let splits = timer.and_then(|timer| {
+        let mut contents = String::new();
+        save_timer_stamped(timer, &mut contents).ok()?;
+        let file_name = config
+            .general
+            .splits
+            .as_deref()
+            .and_then(Path::file_name)
+            .map_or_else(
+                || DEFAULT_SPLITS_NAME.to_owned(),
+                |name| name.to_string_lossy().into_owned(),
+            );
+        Some(BundledSplits {
+            file_name,
+            contents,
+        })
+    });
+    let css = config
+        .splits_state()
+        .and_then(|state| state.css.clone())
+        .filter(|css| !css.trim().is_empty());
+
+    SetupBundle {
+        config: Some(config.portable()),
+        theme: Some(config.theme.clone()),
+        css,
+        splits,
+    }
+}
+
+pub fn bundle_to_bytes(bundle: &SetupBundle) -> Result<Vec<u8>, BundleError> {
+    let manifest = Manifest {
+        version: BUNDLE_VERSION,
+        config: bundle.config.clone(),
+        theme: bundle.theme.clone(),
+        splits: None,
+    };
+    let manifest = serde_yaml::to_string(&manifest)
+        .map(|yaml| format!("{BUNDLE_HEADER}{yaml}"))
+        .map_err(|err| BundleError::Parse(err.to_string()))?;
+    let splits_entry = bundle
+        .splits
+        .as_ref()
+        .map(|splits| format!("{SPLITS_DIR}{}", splits.file_name));
+
+    let mut entries = vec![(MANIFEST_ENTRY, manifest.as_bytes())];
+    if let Some(css) = &bundle.css {
+        entries.push((CSS_ENTRY, css.as_bytes()));
+    }
+    if let (Some(name), Some(splits)) = (&splits_entry, &bundle.splits) {
+        entries.push((name, splits.contents.as_bytes()));
+    }
+    write_archive(&entries).map_err(BundleError::Io)
+}
+
+/// Keeps only plain `.lss` file names, so a bundle can't write outside the
+/// splits directory.
+pub fn sanitize_file_name(name: &str) -> Option<String> {
+    let path = Path::new(name);
+    let file_name = path.file_name()?.to_str()?;
+    let is_plain = file_name == name && !file_name.starts_with('.');
+    let is_lss = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("lss"));
+    (is_plain && is_lss).then(|| file_name.to_owned())
+}
+
+fn utf8(entry: &str, bytes: Vec<u8>) -> Result<String, BundleError> {
+    String::from_utf8(bytes).map_err(|_| BundleError::Parse(format!("{entry} is not UTF-8")))
+}
+
+fn parse_manifest(text: &str) -> Result<Manifest, BundleError> {
+    serde_yaml::from_str(text).map_err(|err| BundleError::Parse(err.to_string()))
+}
+
+/// The manifest, stylesheet and splits of a bundle in either format.
+fn read_bundle(
+    bytes: &[u8],
+) -> Result<(Manifest, Option<String>, Option<BundledSplits>), BundleError> {
+    if !is_archive(bytes) {
+        let text = std::str::from_utf8(bytes)
+            .map_err(|_| BundleError::Parse("neither an archive nor text".to_owned()))?;
+        let mut manifest = parse_manifest(text)?;
+        let splits = manifest.splits.take();
+        return Ok((manifest, None, splits));
+    }
+
+    let mut manifest = None;
+    let mut css = None;
+    let mut splits = None;
+    for (name, contents) in
+        read_archive(bytes).map_err(|err| BundleError::Parse(err.to_string()))?
+    {
+        if name == MANIFEST_ENTRY {
+            manifest = Some(parse_manifest(&utf8(&name, contents)?)?);
+        } else if name == CSS_ENTRY {
+            css = Some(utf8(&name, contents)?);
+        } else if let Some(file_name) = name.strip_prefix(SPLITS_DIR) {
+            if splits.is_some() {
+                return Err(BundleError::InvalidSplits(
+                    "more than one splits file".to_owned(),
+                ));
+            }
+            splits = Some(BundledSplits {
+                file_name: file_name.to_owned(),
+                contents: utf8(&name, contents)?,
+            });
+        }
+    }
+    let manifest = manifest.ok_or_else(|| BundleError::Parse(format!("no {MANIFEST_ENTRY}")))?;
+    Ok((manifest, css, splits))
+}
+
+/// Parses and validates a bundle without applying anything.
+pub fn parse_bundle(bytes: &[u8]) -> Result<ValidatedBundle, BundleError> {
+    let (manifest, css, splits) = read_bundle(bytes)?;
+    if manifest.version > BUNDLE_VERSION {
+        return Err(BundleError::UnsupportedVersion(manifest.version));
+    }
+
+    let splits = splits
+        .map(|splits| {
+            let file_name = sanitize_file_name(&splits.file_name)
+                .ok_or_else(|| BundleError::InvalidFileName(splits.file_name.clone()))?;
+            let mut run = composite::parse(splits.contents.as_bytes(), None)
+                .map_err(|err| BundleError::InvalidSplits(err.to_string()))?
+                .run;
+            if run.segments().is_empty() {
+                return Err(BundleError::InvalidSplits("no segments".to_owned()));
+            }
+            run.fix_splits();
+            Ok(ValidatedSplits {
+                file_name,
+                contents: splits.contents,
+                run,
+            })
+        })
+        .transpose()?;
+
+    let validated = ValidatedBundle {
+        config: manifest.config,
+        theme: manifest.theme,
+        css: css.filter(|css| !css.trim().is_empty()),
+        splits,
+    };
+    if validated.components().is_empty() {
+        return Err(BundleError::Empty);
+    }
+    Ok(validated)
+}
+
+/// `dir/name`, or `dir/stem (n).ext` with the first free `n` if it is taken.
+pub fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    let candidate = dir.join(name);
+    if !candidate.exists() {
+        return candidate;
+    }
+    let path = Path::new(name);
+    let stem = path
+        .file_stem()
+        .map_or_else(String::new, |s| s.to_string_lossy().into_owned());
+    let ext = path
+        .extension()
+        .map_or_else(String::new, |e| format!(".{}", e.to_string_lossy()));
+    (1..)
+        .map(|n| dir.join(format!("{stem} ({n}){ext}")))
+        .find(|path| !path.exists())
+        .expect("unbounded range always yields a free name")
+}
+
+/// Writes the bundled splits into `dir` without overwriting existing files.
+/// Returns where they were written.
+pub fn write_splits(splits: &ValidatedSplits, dir: &Path) -> Result<PathBuf, BundleError> {
+    fs::create_dir_all(dir).map_err(BundleError::Io)?;
+    let path = unique_path(dir, &splits.file_name);
+    let span = splits_io_span("import", &path);
+    let _entered = span.enter();
+    span.record("bytes", splits.contents.len());
+    fs::write(&path, &splits.contents).map_err(BundleError::Io)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod bundle_tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use livesplit_core::Segment;
+
+    fn sample_timer() -> Timer {
+        let mut run = Run::new();
+        run.set_game_name("Game");
+        run.set_category_name("Any%");
+        run.push_segment(Segment::new("One"));
+        run.push_segment(Segment::new("Two"));
+        Timer::new(run).expect("timer")
+    }
+
+    #[test]
+    fn full_bundle_round_trips() {
+        let mut config = Config::default();
+        config.layout.show_timesave_column = true;
+        config.theme.profile = Some("stream".to_owned());
+        config.set_splits_path(PathBuf::from("/home/runner/splits/game.lss"));
+        config.splits_state_mut().unwrap().css = Some(".goldsplit { color: gold; }".to_owned());
+        let timer = sample_timer();
+
+        let bytes = bundle_to_bytes(&export_bundle(&config, Some(&timer))).unwrap();
+        let bundle = parse_bundle(&bytes).unwrap();
+
+        assert_eq!(
+            bundle.components(),
+            vec!["Settings", "Theme", "Stylesheet", "Splits"]
+        );
+        let imported = bundle.config.unwrap();
+        assert!(imported.layout.show_timesave_column);
+        // Machine-specific paths never leave this machine
+        assert!(imported.general.splits.is_none());
+        assert_eq!(bundle.theme.unwrap().profile.as_deref(), Some("stream"));
+        assert_eq!(bundle.css.as_deref(), Some(".goldsplit { color: gold; }"));
+
+        let splits = bundle.splits.unwrap();
+        assert_eq!(splits.file_name, "game.lss");
+        assert_eq!(splits.run.len(), 2);
+        assert_eq!(splits.run.game_name(), "Game");
+    }
+
+    #[test]
+    fn only_shareable_settings_travel() {
+        let mut config = Config::default();
+        config.race.racetime.room = Some("smw/cool-pika-1234".to_owned());
+        config.race.racetime.token = Some("secret".to_owned());
+        config.feedback.confirm_sound = Some(PathBuf::from("click.ogg"));
+        config.storage.trash_mb = 10;
+
+        let bytes = bundle_to_bytes(&export_bundle(&config, None)).unwrap();
+        let exported = parse_bundle(&bytes).unwrap().config.unwrap();
+        assert!(exported.race.racetime.room.is_none());
+        assert!(exported.race.racetime.token.is_none());
+        assert!(exported.feedback.confirm_sound.is_none());
+
+        // Nor does an import replace them here
+        let text = "version: 1\nconfig:\n  race:\n    racetime:\n      room: elsewhere/room\n  \
+                    storage:\n    trash-mb: 0\n  layout:\n    show-timesave-column: true\n";
+        config.apply_imported(parse_bundle(text.as_bytes()).unwrap().config.unwrap());
+        assert!(config.layout.show_timesave_column);
+        assert_eq!(
+            config.race.racetime.room.as_deref(),
+            Some("smw/cool-pika-1234")
+        );
+        assert_eq!(config.race.racetime.token.as_deref(), Some("secret"));
+        assert_eq!(config.storage.trash_mb, 10);
+    }
+
+    #[test]
+    fn config_only_bundle_is_accepted() {
+        let bytes = bundle_to_bytes(&SetupBundle {
+            config: Some(Config::default()),
+            theme: None,
+            css: None,
+            splits: None,
+        })
+        .unwrap();
+        let bundle = parse_bundle(&bytes).unwrap();
+        assert_eq!(bundle.components(), vec!["Settings"]);
+        assert!(bundle.theme.is_none());
+        assert!(bundle.css.is_none());
+        assert!(bundle.splits.is_none());
+    }
+
+    #[test]
+    fn bundles_from_before_the_archive_still_load() {
+        let text = "version: 1\nconfig:\n  layout:\n    show-timesave-column: true\n";
+        let bundle = parse_bundle(text.as_bytes()).unwrap();
+        assert_eq!(bundle.components(), vec!["Settings"]);
+        assert!(bundle.config.unwrap().layout.show_timesave_column);
+    }
+
+    #[test]
+    fn rejects_empty_newer_and_garbage_bundles() {
+        assert!(matches!(
+            parse_bundle(b"version: 1\n"),
+            Err(BundleError::Empty)
+        ));
+        assert!(matches!(
+            parse_bundle(b"version: 99\nconfig: {}\n"),
+            Err(BundleError::UnsupportedVersion(99))
+        ));
+        assert!(matches!(
+            parse_bundle(b"<Run></Run>"),
+            Err(BundleError::Parse(_))
+        ));
+        let without_manifest = write_archive(&[(CSS_ENTRY, b"".as_slice())]).unwrap();
+        assert!(matches!(
+            parse_bundle(&without_manifest),
+            Err(BundleError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_splits() {
+        let text = "version: 1\nsplits:\n  file-name: game.lss\n  contents: not splits\n";
+        assert!(matches!(
+            parse_bundle(text.as_bytes()),
+            Err(BundleError::InvalidSplits(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_splits_with_unsafe_names() {
+        let timer = sample_timer();
+        let mut bundle = export_bundle(&Config::default(), Some(&timer));
+        bundle.splits.as_mut().unwrap().file_name = "../../.bashrc.lss".to_owned();
+        let bytes = bundle_to_bytes(&bundle).unwrap();
+        assert!(matches!(
+            parse_bundle(&bytes),
+            Err(BundleError::InvalidFileName(_))
+        ));
+    }
+
+    #[test]
+    fn sanitizes_file_names() {
+        assert_eq!(sanitize_file_name("game.lss").as_deref(), Some("game.lss"));
+        assert_eq!(
+            sanitize_file_name("Game Any%.LSS").as_deref(),
+            Some("Game Any%.LSS")
+        );
+        assert_eq!(sanitize_file_name("dir/game.lss"), None);
+        assert_eq!(sanitize_file_name("/etc/game.lss"), None);
+        assert_eq!(sanitize_file_name(".hidden.lss"), None);
+        assert_eq!(sanitize_file_name("game.sh"), None);
+        assert_eq!(sanitize_file_name(".."), None);
+    }
+
+    #[test]
+    fn written_splits_never_overwrite() {
+        let dir = temp_dir("bundle-write");
+        let timer = sample_timer();
+        let bytes = bundle_to_bytes(&export_bundle(&Config::default(), Some(&timer))).unwrap();
+        let splits = parse_bundle(&bytes).unwrap().splits.unwrap();
+
+        let first = write_splits(&splits, &dir).unwrap();
+        let second = write_splits(&splits, &dir).unwrap();
+        assert_eq!(first, dir.join("splits.lss"));
+        assert_eq!(second, dir.join("splits (1).lss"));
+        assert_eq!(fs::read_to_string(&second).unwrap(), splits.contents);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
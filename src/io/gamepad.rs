@@ -0,0 +1,110 @@
+//! Listening to foot pedals and gamepads through gilrs.
+//!
+//! The listener runs on its own thread and forwards connections and button
+//! presses as [`PadEvent`]s. gilrs reports devices plugged in or unplugged
+//! while listening; when it can't start at all, e.g. without access to
+//! `/dev/input`, starting is retried every few seconds. Dropping the
+//! listener ends the thread.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use gilrs::ev::Code;
+use gilrs::{Button, Event, EventType, Gilrs};
+use tracing::{info, warn};
+
+use crate::utils::pedal::PadEvent;
+
+/// How long the thread waits for input before checking whether to stop.
+const STOP_POLL: Duration = Duration::from_millis(100);
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+pub struct GamepadListener {
+    events: Receiver<PadEvent>,
+    stop: Arc<AtomicBool>,
+}
+
+impl GamepadListener {
+    pub fn spawn() -> Self {
+        let (sender, events) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        thread::spawn(move || listen(&sender, &stopped));
+        Self { events, stop }
+    }
+
+    /// Events received since the last call.
+    pub fn poll(&self) -> Vec<PadEvent> {
+        self.events.try_iter().collect()
+    }
+}
+
+impl Drop for GamepadListener {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// How a button is stored in the config: its gamepad name, or its raw code
+/// for buttons without one, which is what most pedals send.
+fn button_name(button: Button, code: Code) -> String {
+    match button {
+        Button::Unknown => format!("code-{}", code.into_u32()),
+        known => format!("{known:?}"),
+    }
+}
+
+/// Sleeps for `duration`, or less once `stop` is set.
+fn sleep_unless_stopped(duration: Duration, stop: &AtomicBool) {
+    let until = Instant::now() + duration;
+    while !stop.load(Ordering::Relaxed) && Instant::now() < until {
+        thread::sleep(STOP_POLL);
+    }
+}
+
+fn listen(sender: &Sender<PadEvent>, stop: &AtomicBool) {
+    while !stop.load(Ordering::Relaxed) {
+        let mut gilrs = match Gilrs::new() {
+            Ok(gilrs) => gilrs,
+            Err(gilrs::Error::NotImplemented(_)) => {
+                warn!("Gamepads are not supported on this platform");
+                return;
+            }
+            Err(err) => {
+                warn!("Could not listen to gamepads, retrying: {err}");
+                sleep_unless_stopped(RETRY_INTERVAL, stop);
+                continue;
+            }
+        };
+        info!("Listening to pedals and gamepads");
+        for (_, gamepad) in gilrs.gamepads() {
+            if sender
+                .send(PadEvent::Connected(gamepad.name().to_owned()))
+                .is_err()
+            {
+                return;
+            }
+        }
+        while !stop.load(Ordering::Relaxed) {
+            let Some(Event { id, event, .. }) = gilrs.next_event_blocking(Some(STOP_POLL)) else {
+                continue;
+            };
+            let device = gilrs.gamepad(id).name().to_owned();
+            let event = match event {
+                EventType::Connected => PadEvent::Connected(device),
+                EventType::Disconnected => PadEvent::Disconnected(device),
+                EventType::ButtonPressed(button, code) => PadEvent::Pressed {
+                    device,
+                    button: button_name(button, code),
+                },
+                _ => continue,
+            };
+            if sender.send(event).is_err() {
+                return;
+            }
+        }
+    }
+}
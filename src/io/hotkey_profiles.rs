@@ -0,0 +1,219 @@
+//! Named hotkey profiles, e.g. one for a laptop without a numpad.
+//!
+//! A profile is a YAML file in the `hotkeys` directory under the config
+//! directory, holding bindings written like the `hotkeys` section of the
+//! config and read with the same key names. `hotkeys.active-profile` picks
+//! one by its file name; without it, or while its file is missing or
+//! invalid, the bindings of the config itself are the default profile.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use livesplit_core::HotkeyConfig;
+
+/// Directory of the profiles, under the config directory.
+pub const PROFILE_DIR: &str = "hotkeys";
+pub const PROFILE_EXTENSION: &str = "yaml";
+const PROFILE_HEADER: &str = "# TuxSplit hotkey profile\n";
+
+#[derive(Debug)]
+pub enum ProfileError {
+    InvalidName(String),
+    Missing(String),
+    Parse(String),
+    Io(std::io::Error),
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProfileError::InvalidName(name) => {
+                write!(f, "{name:?} is not a valid hotkey profile name")
+            }
+            ProfileError::Missing(name) => write!(f, "The hotkey profile {name:?} does not exist"),
+            ProfileError::Parse(err) => write!(f, "Not a hotkey profile: {err}"),
+            ProfileError::Io(err) => write!(f, "Could not access the profile: {err}"),
+        }
+    }
+}
+
+/// Profile names are plain file names, so a profile can't be read or
+/// written outside the profile directory.
+pub fn profile_name_is_valid(name: &str) -> bool {
+    !name.trim().is_empty()
+        && !name.starts_with('.')
+        && !name.contains(['/', '\\'])
+        && Path::new(name).file_name().and_then(|name| name.to_str()) == Some(name)
+}
+
+pub fn profile_path(dir: &Path, name: &str) -> Result<PathBuf, ProfileError> {
+    if !profile_name_is_valid(name) {
+        return Err(ProfileError::InvalidName(name.to_owned()));
+    }
+    Ok(dir.join(format!("{name}.{PROFILE_EXTENSION}")))
+}
+
+/// The name a profile file is imported under, its file name without the
+/// extension.
+pub fn profile_name_from_path(path: &Path) -> Option<String> {
+    let name = path.file_stem()?.to_str()?;
+    profile_name_is_valid(name).then(|| name.to_owned())
+}
+
+/// Names of the profiles in `dir`, sorted.
+pub fn list_profiles(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case(PROFILE_EXTENSION))
+        })
+        .filter_map(|path| profile_name_from_path(&path))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Reads bindings the way the `hotkeys` section of the config is read, so a
+/// key name the config would reject fails the whole profile.
+pub fn parse_profile(text: &str) -> Result<HotkeyConfig, ProfileError> {
+    serde_yaml::from_str(text).map_err(|err| ProfileError::Parse(err.to_string()))
+}
+
+pub fn profile_to_string(hotkeys: &HotkeyConfig) -> Result<String, ProfileError> {
+    serde_yaml::to_string(hotkeys)
+        .map(|yaml| format!("{PROFILE_HEADER}{yaml}"))
+        .map_err(|err| ProfileError::Parse(err.to_string()))
+}
+
+pub fn read_profile(dir: &Path, name: &str) -> Result<HotkeyConfig, ProfileError> {
+    let path = profile_path(dir, name)?;
+    if !path.is_file() {
+        return Err(ProfileError::Missing(name.to_owned()));
+    }
+    let text = fs::read_to_string(&path).map_err(ProfileError::Io)?;
+    parse_profile(&text)
+}
+
+/// Writes `hotkeys` as the profile `name` in `dir`, replacing it if it
+/// exists.
+pub fn write_profile(
+    dir: &Path,
+    name: &str,
+    hotkeys: &HotkeyConfig,
+) -> Result<PathBuf, ProfileError> {
+    let path = profile_path(dir, name)?;
+    let text = profile_to_string(hotkeys)?;
+    fs::create_dir_all(dir).map_err(ProfileError::Io)?;
+    fs::write(&path, text).map_err(ProfileError::Io)?;
+    Ok(path)
+}
+
+/// The bindings to use for `active`: the profile's, or `default` with the
+/// reason when the profile can't be read.
+pub fn resolve_profile(
+    dir: &Path,
+    active: Option<&str>,
+    default: HotkeyConfig,
+) -> (HotkeyConfig, Option<ProfileError>) {
+    match active.map(|name| read_profile(dir, name)) {
+        None => (default, None),
+        Some(Ok(hotkeys)) => (hotkeys, None),
+        Some(Err(err)) => (default, Some(err)),
+    }
+}
+
+#[cfg(test)]
+mod hotkey_profiles_tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use livesplit_core::hotkey::{Hotkey, KeyCode, Modifiers};
+
+    fn key(key_code: KeyCode) -> Option<Hotkey> {
+        Some(Hotkey {
+            key_code,
+            modifiers: Modifiers::empty(),
+        })
+    }
+
+    fn laptop() -> HotkeyConfig {
+        let mut hotkeys = HotkeyConfig::default();
+        hotkeys.split = key(KeyCode::Space);
+        hotkeys.reset = Some(Hotkey {
+            key_code: KeyCode::KeyR,
+            modifiers: Modifiers::CONTROL,
+        });
+        hotkeys.undo_all_pauses = None;
+        hotkeys
+    }
+
+    #[test]
+    fn profiles_round_trip() {
+        let dir = temp_dir("hotkey-profiles-round-trip");
+        let path = write_profile(&dir, "laptop", &laptop()).unwrap();
+        assert_eq!(path, dir.join("laptop.yaml"));
+        write_profile(&dir, "desktop", &HotkeyConfig::default()).unwrap();
+        fs::write(dir.join("notes.txt"), "not a profile").unwrap();
+
+        assert_eq!(list_profiles(&dir), vec!["desktop", "laptop"]);
+        assert_eq!(read_profile(&dir, "laptop").unwrap(), laptop());
+        assert_eq!(
+            read_profile(&dir, "desktop").unwrap(),
+            HotkeyConfig::default()
+        );
+
+        // Exported files import under their file name
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with(PROFILE_HEADER));
+        assert_eq!(parse_profile(&text).unwrap(), laptop());
+        assert_eq!(profile_name_from_path(&path).as_deref(), Some("laptop"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn missing_or_invalid_profiles_fall_back_to_the_default() {
+        let dir = temp_dir("hotkey-profiles-fallback");
+        let default = laptop();
+
+        let (hotkeys, err) = resolve_profile(&dir, None, default);
+        assert_eq!(hotkeys, default);
+        assert!(err.is_none());
+
+        let (hotkeys, err) = resolve_profile(&dir, Some("desktop"), default);
+        assert_eq!(hotkeys, default);
+        assert!(matches!(err, Some(ProfileError::Missing(name)) if name == "desktop"));
+
+        // Key names are checked like in the config
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("typo.yaml"), "split: Numpad11\n").unwrap();
+        let (hotkeys, err) = resolve_profile(&dir, Some("typo"), default);
+        assert_eq!(hotkeys, default);
+        assert!(matches!(err, Some(ProfileError::Parse(_))));
+
+        fs::write(dir.join("ok.yaml"), "split: Numpad1\n").unwrap();
+        let (hotkeys, err) = resolve_profile(&dir, Some("ok"), default);
+        assert_eq!(hotkeys.split, key(KeyCode::Numpad1));
+        assert!(err.is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn names_stay_inside_the_profile_directory() {
+        let dir = Path::new("/config/hotkeys");
+        assert!(profile_path(dir, "laptop").is_ok());
+        assert!(profile_path(dir, "Steam Deck (2)").is_ok());
+        for name in ["", " ", "../config", "a/b", ".hidden", "a\\b"] {
+            assert!(
+                matches!(profile_path(dir, name), Err(ProfileError::InvalidName(_))),
+                "{name:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,265 @@
+//! Integrity hash for splits files.
+//!
+//! Saving stores a SHA-256 over the attempt history, the segment histories
+//! and the personal best split times in a custom variable of the `.lss`.
+//! Loading recomputes it, so a file edited by hand (or by another tool)
+//! after it was last saved here shows up as modified. Names, icons and
+//! other metadata aren't covered, only times.
+//!
+//! The hashed text is built in a fixed order with times in whole
+//! milliseconds, so it doesn't depend on map iteration order or on how
+//! precisely the `.lss` stores times.
+
+use std::fmt::{self, Write as _};
+
+use livesplit_core::run::saver::livesplit::save_run;
+use livesplit_core::{Run, Time, TimeSpan, Timer};
+use sha2::{Digest, Sha256};
+
+use crate::io::attempt_state::replay_resumed_attempt;
+
+/// Custom variable holding the hash in the splits file.
+pub const HASH_VARIABLE: &str = "TuxSplit Integrity Hash";
+const FORMAT_VERSION: &str = "tuxsplit-integrity-v1";
+/// Length of the hash prefix shown in the UI.
+pub const SHORT_HASH_LEN: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityStatus {
+    /// The file has no hash, e.g. it was never saved by TuxSplit.
+    Unsigned,
+    Verified {
+        hash: String,
+    },
+    /// The times changed since the hash was stored.
+    Modified {
+        stored: String,
+        actual: String,
+    },
+}
+
+impl IntegrityStatus {
+    pub fn describe(&self) -> String {
+        match self {
+            IntegrityStatus::Unsigned => "Not hashed".to_owned(),
+            IntegrityStatus::Verified { hash } => format!("Verified ({})", short_hash(hash)),
+            IntegrityStatus::Modified { stored, .. } => {
+                format!("Modified since hashed ({})", short_hash(stored))
+            }
+        }
+    }
+}
+
+pub fn short_hash(hash: &str) -> &str {
+    hash.get(..SHORT_HASH_LEN).unwrap_or(hash)
+}
+
+fn millis(span: Option<TimeSpan>) -> String {
+    span.map_or_else(
+        || "-".to_owned(),
+        |span| (span.total_milliseconds().round() as i64).to_string(),
+    )
+}
+
+fn time_fields(time: Time) -> String {
+    format!("{} {}", millis(time.real_time), millis(time.game_time))
+}
+
+/// The text the hash is computed over, one line per recorded time.
+pub fn canonical_history(run: &Run) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{FORMAT_VERSION}");
+
+    let mut attempts: Vec<_> = run.attempt_history().iter().collect();
+    attempts.sort_by_key(|attempt| attempt.index());
+    for attempt in attempts {
+        let _ = writeln!(
+            out,
+            "attempt {} {} {}",
+            attempt.index(),
+            time_fields(attempt.time()),
+            millis(attempt.pause_time())
+        );
+    }
+
+    for (index, segment) in run.segments().iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "segment {index} pb {}",
+            time_fields(segment.personal_best_split_time())
+        );
+        let mut history: Vec<_> = segment.segment_history().iter().copied().collect();
+        history.sort_by_key(|(id, _)| *id);
+        for (id, time) in history {
+            let _ = writeln!(out, "segment {index} history {id} {}", time_fields(time));
+        }
+    }
+    out
+}
+
+/// Hex SHA-256 of `canonical_history`.
+pub fn integrity_hash(run: &Run) -> String {
+    let digest = Sha256::digest(canonical_history(run).as_bytes());
+    digest.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+pub fn stored_hash(run: &Run) -> Option<&str> {
+    run.metadata()
+        .custom_variable_value(HASH_VARIABLE)
+        .filter(|hash| !hash.is_empty())
+}
+
+/// Stores the current hash in the run. The variable is permanent, as the
+/// saver leaves temporary ones out of the file.
+pub fn stamp(run: &mut Run) {
+    let hash = integrity_hash(run);
+    run.metadata_mut()
+        .custom_variable_mut(HASH_VARIABLE)
+        .permanent()
+        .set_value(hash.as_str());
+}
+
+pub fn check(run: &Run) -> IntegrityStatus {
+    let Some(stored) = stored_hash(run) else {
+        return IntegrityStatus::Unsigned;
+    };
+    let actual = integrity_hash(run);
+    if actual == stored {
+        IntegrityStatus::Verified { hash: actual }
+    } else {
+        IntegrityStatus::Modified {
+            stored: stored.to_owned(),
+            actual,
+        }
+    }
+}
+
+/// The run `timer` saves. A resumed attempt is saved with its splits from
+/// before the restart and the run's own start offset.
+pub fn run_to_save(timer: &Timer) -> Run {
+    let mut run = timer.clone().into_run(true);
+    replay_resumed_attempt(&mut run);
+    run
+}
+
+/// Saves `run` with a fresh hash stored in it.
+pub fn save_run_stamped<W: fmt::Write>(mut run: Run, writer: W) -> fmt::Result {
+    stamp(&mut run);
+    save_run(&run, writer)
+}
+
+/// Like `save_timer`, with a fresh hash stored in the saved file, see
+/// [`run_to_save`].
+pub fn save_timer_stamped<W: fmt::Write>(timer: &Timer, writer: W) -> fmt::Result {
+    save_run_stamped(run_to_save(timer), writer)
+}
+
+#[cfg(test)]
+mod integrity_tests {
+    use super::*;
+    use crate::test_support::real_time;
+    use livesplit_core::Segment;
+    use livesplit_core::run::parser::composite;
+
+    fn sample_run() -> Run {
+        let mut run = Run::new();
+        run.set_game_name("Game");
+        run.set_category_name("Any%");
+        for (name, pb) in [("One", 10.25), ("Two", 31.5)] {
+            let mut segment = Segment::new(name);
+            segment.set_personal_best_split_time(real_time(pb));
+            run.push_segment(segment);
+        }
+        run.add_attempt_with_index(real_time(33.0), 1, None, None, None);
+        run.add_attempt_with_index(real_time(31.5), 2, None, None, None);
+        run.add_attempt_with_index(Time::default(), 3, None, None, None);
+        for (id, one, two) in [(1, 11.0, 22.0), (2, 10.25, 21.25)] {
+            run.segments_mut()[0]
+                .segment_history_mut()
+                .insert(id, real_time(one));
+            run.segments_mut()[1]
+                .segment_history_mut()
+                .insert(id, real_time(two));
+        }
+        run
+    }
+
+    fn save(run: &Run) -> String {
+        let mut text = String::new();
+        save_run(run, &mut text).unwrap();
+        text
+    }
+
+    fn load(text: &str) -> Run {
+        composite::parse(text.as_bytes(), None).unwrap().run
+    }
+
+    #[test]
+    fn hash_is_stable_across_load_and_save() {
+        let mut run = sample_run();
+        stamp(&mut run);
+        let hash = integrity_hash(&run);
+
+        let reloaded = load(&save(&run));
+        assert_eq!(integrity_hash(&reloaded), hash);
+        assert_eq!(
+            check(&reloaded),
+            IntegrityStatus::Verified { hash: hash.clone() }
+        );
+
+        let twice = load(&save(&reloaded));
+        assert_eq!(integrity_hash(&twice), hash);
+    }
+
+    #[test]
+    fn history_order_does_not_matter() {
+        let run = sample_run();
+        let mut reversed = sample_run();
+        for segment in reversed.segments_mut() {
+            let entries: Vec<_> = segment.segment_history().iter().copied().collect();
+            segment.segment_history_mut().clear();
+            for (id, time) in entries.into_iter().rev() {
+                segment.segment_history_mut().insert(id, time);
+            }
+        }
+        assert_eq!(integrity_hash(&run), integrity_hash(&reversed));
+    }
+
+    #[test]
+    fn single_millisecond_tamper_is_detected() {
+        let mut run = sample_run();
+        stamp(&mut run);
+        let mut tampered = load(&save(&run));
+        *tampered.segments_mut()[1]
+            .segment_history_mut()
+            .get_mut(2)
+            .unwrap() = real_time(21.249);
+        assert!(matches!(check(&tampered), IntegrityStatus::Modified { .. }));
+
+        let mut tampered = load(&save(&run));
+        tampered.segments_mut()[1].set_personal_best_split_time(real_time(31.501));
+        assert!(matches!(check(&tampered), IntegrityStatus::Modified { .. }));
+    }
+
+    #[test]
+    fn unhashed_files_are_unsigned() {
+        assert_eq!(check(&sample_run()), IntegrityStatus::Unsigned);
+        assert_eq!(IntegrityStatus::Unsigned.describe(), "Not hashed");
+    }
+
+    #[test]
+    fn timer_saves_carry_a_valid_hash() {
+        let timer = Timer::new(sample_run()).unwrap();
+        let mut text = String::new();
+        save_timer_stamped(&timer, &mut text).unwrap();
+        let status = check(&load(&text));
+        let IntegrityStatus::Verified { hash } = status else {
+            panic!("Expected a verified hash, got {status:?}");
+        };
+        assert_eq!(hash.len(), 64);
+        assert_eq!(short_hash(&hash).len(), SHORT_HASH_LEN);
+    }
+}
@@ -0,0 +1,371 @@
+//! Approximating a LiveSplit `.lsl` layout with TuxSplit's settings.
+//!
+//! The layout is read with livesplit-core's parser and each component is
+//! mapped onto its closest TuxSplit equivalent: info components become the
+//! additional info rows, the splits component sets the visible split count
+//! and the split/segment formats, and the timer sets the timer's decimals.
+//! Components without an equivalent are listed so the summary can say what
+//! was left behind. Nothing touches the config until [`LayoutImport::apply`].
+
+use livesplit_core::layout::{Component, parser};
+use livesplit_core::timing::formatter::Accuracy;
+
+use crate::config::Config;
+use crate::formatters::TimeFormat;
+
+pub const LAYOUT_EXTENSION: &str = "lsl";
+
+#[derive(Debug)]
+pub enum LayoutImportError {
+    Io(std::io::Error),
+    Parse,
+    /// The layout parsed but none of its components map to anything.
+    Empty,
+}
+
+impl std::fmt::Display for LayoutImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LayoutImportError::Io(e) => write!(f, "Could not read the layout: {e}"),
+            LayoutImportError::Parse => write!(f, "Not a LiveSplit layout file"),
+            LayoutImportError::Empty => {
+                write!(
+                    f,
+                    "None of the layout's components have a TuxSplit equivalent"
+                )
+            }
+        }
+    }
+}
+
+/// Info rows a LiveSplit layout can turn on. TuxSplit-only rows (previous
+/// segment best, gold pace, race) are left as they are.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ImportedInfos {
+    pub prev_segment_diff: bool,
+    pub best_possible_time: bool,
+    pub possible_time_save: bool,
+    pub current_pace: bool,
+    pub total_playtime: bool,
+    pub pb_chance: bool,
+    pub segment_timer: bool,
+}
+
+/// What an imported layout would change, built without touching the config.
+#[derive(Debug, Default)]
+pub struct LayoutImport {
+    pub infos: ImportedInfos,
+    pub visible_splits: Option<usize>,
+    pub timer_accuracy: Option<Accuracy>,
+    pub split_accuracy: Option<Accuracy>,
+    pub segment_accuracy: Option<Accuracy>,
+    /// Deltas drop their decimals over a minute, like the dynamic format.
+    pub split_drop_decimals: bool,
+    /// One line per component that was mapped, for the summary.
+    pub mapped: Vec<String>,
+    /// Components without an equivalent.
+    pub unmapped: Vec<String>,
+    /// Layout-only components with nothing to set, like separators.
+    pub skipped: Vec<String>,
+}
+
+fn accuracy_name(accuracy: Accuracy) -> &'static str {
+    match accuracy {
+        Accuracy::Seconds => "seconds",
+        Accuracy::Tenths => "tenths",
+        Accuracy::Hundredths => "hundredths",
+        Accuracy::Milliseconds => "milliseconds",
+    }
+}
+
+/// Sets the decimals of `format` to match a LiveSplit accuracy, keeping the
+/// rest of the format.
+fn apply_accuracy(format: &mut TimeFormat, accuracy: Accuracy) {
+    let places = match accuracy {
+        Accuracy::Seconds => None,
+        Accuracy::Tenths => Some(1),
+        Accuracy::Hundredths => Some(2),
+        Accuracy::Milliseconds => Some(3),
+    };
+    format.show_decimals = places.is_some();
+    if let Some(places) = places {
+        format.set_decimal_places(places);
+    }
+}
+
+/// Maps parsed layout components onto TuxSplit settings.
+pub fn map_components(components: &[Component]) -> LayoutImport {
+    let mut import = LayoutImport::default();
+    for component in components {
+        match component {
+            Component::Splits(splits) => {
+                let settings = splits.settings();
+                if settings.visual_split_count > 0 {
+                    import.visible_splits = Some(settings.visual_split_count);
+                }
+                import.split_accuracy = Some(settings.delta_time_accuracy);
+                import.segment_accuracy = Some(settings.split_time_accuracy);
+                import.split_drop_decimals = settings.delta_drop_decimals;
+                import.mapped.push(format!(
+                    "Splits: {} visible, deltas in {}, split times in {}",
+                    settings.visual_split_count,
+                    accuracy_name(settings.delta_time_accuracy),
+                    accuracy_name(settings.split_time_accuracy)
+                ));
+            }
+            Component::Timer(timer) => {
+                let accuracy = timer.settings().accuracy;
+                import.timer_accuracy = Some(accuracy);
+                import
+                    .mapped
+                    .push(format!("Timer: {}", accuracy_name(accuracy)));
+            }
+            Component::DetailedTimer(_) => {
+                import.infos.segment_timer = true;
+                import
+                    .mapped
+                    .push("Detailed Timer: Segment Timer row".to_owned());
+            }
+            Component::PreviousSegment(_) => {
+                import.infos.prev_segment_diff = true;
+                import
+                    .mapped
+                    .push("Previous Segment: Previous Segment row".to_owned());
+            }
+            Component::SumOfBest(_) => {
+                import.infos.best_possible_time = true;
+                import
+                    .mapped
+                    .push("Sum of Best: Best Possible Time row".to_owned());
+            }
+            Component::PossibleTimeSave(_) => {
+                import.infos.possible_time_save = true;
+                import
+                    .mapped
+                    .push("Possible Time Save: Possible Time Save row".to_owned());
+            }
+            Component::CurrentPace(_) => {
+                import.infos.current_pace = true;
+                import
+                    .mapped
+                    .push("Current Pace: Current Pace row".to_owned());
+            }
+            Component::PbChance(_) => {
+                import.infos.pb_chance = true;
+                import.mapped.push("PB Chance: PB Chance row".to_owned());
+            }
+            Component::TotalPlaytime(_) => {
+                import.infos.total_playtime = true;
+                import
+                    .mapped
+                    .push("Total Playtime: Total Playtime row".to_owned());
+            }
+            // The header always shows the title, spacing is TuxSplit's own
+            Component::Title(_) | Component::Separator(_) | Component::BlankSpace(_) => {
+                import.skipped.push(component.name().into_owned());
+            }
+            other => import.unmapped.push(other.name().into_owned()),
+        }
+    }
+    import
+}
+
+/// Parses `.lsl` text and maps its components.
+pub fn parse_layout(source: &str) -> Result<LayoutImport, LayoutImportError> {
+    let layout = parser::parse(source).map_err(|_| LayoutImportError::Parse)?;
+    let import = map_components(&layout.components);
+    if import.mapped.is_empty() {
+        return Err(LayoutImportError::Empty);
+    }
+    Ok(import)
+}
+
+pub fn read_layout(path: &std::path::Path) -> Result<LayoutImport, LayoutImportError> {
+    let source = std::fs::read_to_string(path).map_err(LayoutImportError::Io)?;
+    parse_layout(&source)
+}
+
+impl LayoutImport {
+    /// Summary shown before anything is applied.
+    pub fn summary(&self) -> String {
+        let mut summary = String::from("Will set:\n");
+        for line in &self.mapped {
+            summary.push_str(&format!("• {line}\n"));
+        }
+        if !self.unmapped.is_empty() {
+            summary.push_str(&format!(
+                "\nNo TuxSplit equivalent: {}\n",
+                self.unmapped.join(", ")
+            ));
+        }
+        if !self.skipped.is_empty() {
+            summary.push_str(&format!("\nNot needed: {}\n", self.skipped.join(", ")));
+        }
+        summary.trim_end().to_owned()
+    }
+
+    /// Writes the mapped settings into `config`. Info rows the layout has no
+    /// component for are turned off, TuxSplit-only rows are kept.
+    pub fn apply(&self, config: &mut Config) {
+        let info = &mut config.general.additional_info;
+        info.show_prev_segment_diff = self.infos.prev_segment_diff;
+        info.show_best_possible_time = self.infos.best_possible_time;
+        info.show_possible_time_save = self.infos.possible_time_save;
+        info.show_current_pace = self.infos.current_pace;
+        info.show_total_playtime = self.infos.total_playtime;
+        info.show_pb_chance = self.infos.pb_chance;
+        info.show_segment_timer = self.infos.segment_timer;
+
+        if let Some(visible) = self.visible_splits {
+            config.style.max_segments_displayed = Some(visible);
+        }
+        if let Some(accuracy) = self.timer_accuracy {
+            apply_accuracy(&mut config.format.timer, accuracy);
+        }
+        if let Some(accuracy) = self.split_accuracy {
+            apply_accuracy(&mut config.format.split, accuracy);
+            config.format.split.dynamic = self.split_drop_decimals;
+        }
+        if let Some(accuracy) = self.segment_accuracy {
+            apply_accuracy(&mut config.format.segment, accuracy);
+        }
+    }
+}
+
+#[cfg(test)]
+mod layout_import_tests {
+    use super::*;
+    use livesplit_core::component::{
+        current_pace, detailed_timer, graph, pb_chance, possible_time_save, previous_segment,
+        separator, splits, sum_of_best, text, timer, title, total_playtime,
+    };
+
+    const FIXTURE: &str = include_str!("fixtures/layout.lsl");
+
+    fn single(component: impl Into<Component>) -> LayoutImport {
+        map_components(&[component.into()])
+    }
+
+    #[test]
+    fn splits_set_the_visible_count_and_formats() {
+        let mut component = splits::Component::new();
+        let settings = component.settings_mut();
+        settings.visual_split_count = 12;
+        settings.delta_time_accuracy = Accuracy::Tenths;
+        settings.split_time_accuracy = Accuracy::Seconds;
+        settings.delta_drop_decimals = true;
+        let import = single(component);
+
+        let mut config = Config::default();
+        import.apply(&mut config);
+        assert_eq!(config.style.max_segments_displayed, Some(12));
+        assert!(config.format.split.show_decimals);
+        assert_eq!(config.format.split.decimal_places, 1);
+        assert!(config.format.split.dynamic);
+        assert!(!config.format.segment.show_decimals);
+    }
+
+    #[test]
+    fn zero_visible_splits_keeps_the_current_count() {
+        let mut component = splits::Component::new();
+        component.settings_mut().visual_split_count = 0;
+        let mut config = Config::default();
+        config.style.max_segments_displayed = Some(7);
+        single(component).apply(&mut config);
+        assert_eq!(config.style.max_segments_displayed, Some(7));
+    }
+
+    #[test]
+    fn timer_sets_the_timer_decimals() {
+        let mut component = timer::Component::new();
+        component.settings_mut().accuracy = Accuracy::Milliseconds;
+        let mut config = Config::default();
+        single(component).apply(&mut config);
+        assert!(config.format.timer.show_decimals);
+        assert_eq!(config.format.timer.decimal_places, 3);
+    }
+
+    #[test]
+    fn info_components_turn_on_their_rows() {
+        let cases: Vec<(Component, fn(&ImportedInfos) -> bool)> = vec![
+            (previous_segment::Component::new().into(), |i| {
+                i.prev_segment_diff
+            }),
+            (sum_of_best::Component::new().into(), |i| {
+                i.best_possible_time
+            }),
+            (possible_time_save::Component::new().into(), |i| {
+                i.possible_time_save
+            }),
+            (current_pace::Component::new().into(), |i| i.current_pace),
+            (pb_chance::Component::new().into(), |i| i.pb_chance),
+            (total_playtime::Component::new().into(), |i| {
+                i.total_playtime
+            }),
+            (Box::new(detailed_timer::Component::new()).into(), |i| {
+                i.segment_timer
+            }),
+        ];
+        for (component, flag) in cases {
+            let import = map_components(std::slice::from_ref(&component));
+            assert!(flag(&import.infos), "{} turns its row on", component.name());
+            assert_eq!(import.mapped.len(), 1);
+            assert!(import.unmapped.is_empty());
+        }
+    }
+
+    #[test]
+    fn rows_missing_from_the_layout_are_turned_off() {
+        let mut config = Config::default();
+        config.general.additional_info.show_best_possible_time = true;
+        config.general.additional_info.show_gold_pace = true;
+        single(pb_chance::Component::new()).apply(&mut config);
+
+        let info = &config.general.additional_info;
+        assert!(info.show_pb_chance);
+        assert!(!info.show_best_possible_time);
+        // TuxSplit-only rows have no LiveSplit component to come from
+        assert!(info.show_gold_pace);
+    }
+
+    #[test]
+    fn components_without_an_equivalent_are_reported() {
+        let import = map_components(&[
+            graph::Component::new().into(),
+            text::Component::new().into(),
+            title::Component::new().into(),
+            separator::Component::new().into(),
+        ]);
+        assert!(import.mapped.is_empty());
+        assert_eq!(import.unmapped.len(), 2);
+        assert_eq!(import.skipped.len(), 2);
+        assert!(import.summary().contains("No TuxSplit equivalent"));
+    }
+
+    #[test]
+    fn fixture_layout_maps_its_components() {
+        let import = parse_layout(FIXTURE).unwrap();
+        assert_eq!(import.visible_splits, Some(8));
+        assert_eq!(import.timer_accuracy, Some(Accuracy::Hundredths));
+        assert_eq!(import.split_accuracy, Some(Accuracy::Tenths));
+        assert!(import.infos.prev_segment_diff);
+        assert!(import.infos.best_possible_time);
+        assert!(import.infos.possible_time_save);
+        assert!(!import.infos.pb_chance);
+        // The graph has nothing to map to
+        assert_eq!(import.unmapped.len(), 1);
+
+        let mut config = Config::default();
+        import.apply(&mut config);
+        assert_eq!(config.style.max_segments_displayed, Some(8));
+        assert_eq!(config.format.split.decimal_places, 1);
+    }
+
+    #[test]
+    fn garbage_is_not_a_layout() {
+        assert!(matches!(
+            parse_layout("not xml at all"),
+            Err(LayoutImportError::Parse)
+        ));
+    }
+}
@@ -0,0 +1,12 @@
+pub mod archive;
+pub mod attempt_state;
+pub mod bundle;
+#[cfg(feature = "gamepad")]
+pub mod gamepad;
+pub mod hotkey_profiles;
+pub mod integrity;
+pub mod layout_import;
+pub mod process;
+pub mod recovery;
+pub mod save_dedup;
+pub mod trash;
@@ -0,0 +1,29 @@
+//! Finding a process by name and sampling it, for the freeze watch. Linux
+//! only: elsewhere nothing is ever found.
+
+use std::fs;
+use std::path::Path;
+
+use crate::utils::freeze_watch::{ProcSample, parse_stat};
+
+/// The kernel keeps this many bytes of a command name.
+const COMM_LEN: usize = 15;
+
+/// The pid of a process whose command name is `name`, if one is running.
+pub fn find_process(name: &str) -> Option<u32> {
+    let wanted: String = name.chars().take(COMM_LEN).collect();
+    fs::read_dir("/proc")
+        .ok()?
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .find(|pid| {
+            fs::read_to_string(Path::new("/proc").join(pid.to_string()).join("comm"))
+                .is_ok_and(|comm| comm.trim_end() == wanted)
+        })
+}
+
+/// The process's state and CPU time now, `None` once it exited.
+pub fn sample_process(pid: u32) -> Option<ProcSample> {
+    let stat = fs::read_to_string(Path::new("/proc").join(pid.to_string()).join("stat")).ok()?;
+    parse_stat(&stat)
+}
@@ -0,0 +1,230 @@
+//! Salvaging what's left of a damaged LiveSplit splits file.
+//!
+//! Used once the strict parse failed, e.g. for a file cut short by a crash
+//! mid-save. The run's header elements and every complete `<Segment>` are
+//! picked out of the text and each parsed again in a document of its own; an
+//! element the parser still rejects is left out, or replaced by an empty one
+//! where the parser requires it. The result can miss
+//! segments, attempts or times, so it's always reported as a recovery.
+
+use livesplit_core::Run;
+use livesplit_core::run::parser::composite;
+
+/// Elements of `<Run>` kept from before the segments, in saving order, with
+/// the stand-in for those the parser can't do without.
+const HEADER_ELEMENTS: [(&str, Option<&str>); 8] = [
+    ("GameIcon", Some("<GameIcon />")),
+    ("GameName", Some("<GameName />")),
+    ("CategoryName", Some("<CategoryName />")),
+    ("LayoutPath", None),
+    ("Metadata", None),
+    ("Offset", Some("<Offset>00:00:00</Offset>")),
+    ("AttemptCount", Some("<AttemptCount>0</AttemptCount>")),
+    ("AttemptHistory", None),
+];
+const DEFAULT_VERSION: &str = "1.7.0";
+
+/// A run rebuilt from a damaged file.
+#[derive(Debug)]
+pub struct RecoveredRun {
+    pub run: Run,
+    /// Complete segments the parser took.
+    pub segments: usize,
+    /// Segments or header elements left out as unreadable.
+    pub skipped: usize,
+}
+
+/// The first complete `<tag>` element of `text`, self-closing or not.
+fn element<'a>(text: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{tag}");
+    let mut from = 0;
+    loop {
+        let start = from + text[from..].find(&open)?;
+        from = start + open.len();
+        // `<Segment` also starts `<Segments>` and `<SegmentHistory>`
+        if !text[from..].starts_with(|c: char| c == '>' || c == '/' || c.is_whitespace()) {
+            continue;
+        }
+        let tag_end = from + text[from..].find('>')?;
+        if text[..tag_end].ends_with('/') {
+            return Some(&text[start..=tag_end]);
+        }
+        let close = format!("</{tag}>");
+        let end = tag_end + text[tag_end..].find(&close)? + close.len();
+        return Some(&text[start..end]);
+    }
+}
+
+/// The `version` of the root `<Run>`.
+fn run_version(text: &str) -> &str {
+    text.find("<Run")
+        .and_then(|start| {
+            let tag = &text[start..start + text[start..].find('>')?];
+            let value = &tag[tag.find("version=\"")? + "version=\"".len()..];
+            Some(&value[..value.find('"')?])
+        })
+        .unwrap_or(DEFAULT_VERSION)
+}
+
+fn document(version: &str, header: &[&str], segments: &[&str]) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?><Run version=\"{version}\">{}<Segments>{}</Segments><AutoSplitterSettings /></Run>",
+        header.concat(),
+        segments.concat()
+    )
+}
+
+fn parse(document: &str) -> Option<Run> {
+    composite::parse(document.as_bytes(), None)
+        .ok()
+        .map(|parsed| parsed.run)
+}
+
+/// Rebuilds what can be read of a damaged splits file. `None` when not even
+/// one segment survived.
+pub fn recover_run(file: &[u8]) -> Option<RecoveredRun> {
+    let text = String::from_utf8_lossy(file);
+    let version = run_version(&text);
+    let (before, after) = text.split_at(text.find("<Segments>")?);
+
+    let mut skipped = 0;
+    let mut header: Vec<_> = HEADER_ELEMENTS
+        .iter()
+        .map(|(_, stand_in)| *stand_in)
+        .collect();
+    for (index, (tag, stand_in)) in HEADER_ELEMENTS.into_iter().enumerate() {
+        let Some(element) = element(before, tag) else {
+            continue;
+        };
+        header[index] = Some(element);
+        let kept: Vec<_> = header.iter().flatten().copied().collect();
+        if parse(&document(version, &kept, &[])).is_none() {
+            header[index] = stand_in;
+            skipped += 1;
+        }
+    }
+    let header: Vec<_> = header.into_iter().flatten().collect();
+
+    // Each segment is checked on its own, so the work grows with the file
+    // rather than with its square
+    let mut segments = Vec::new();
+    let mut rest = &after["<Segments>".len()..];
+    while let Some(segment) = element(rest, "Segment") {
+        // `segment` is a slice of `rest`
+        let end = segment.as_ptr() as usize - rest.as_ptr() as usize + segment.len();
+        rest = &rest[end..];
+        if parse(&document(version, &header, &[segment])).is_some() {
+            segments.push(segment);
+        } else {
+            skipped += 1;
+        }
+    }
+    if segments.is_empty() {
+        return None;
+    }
+
+    let run = parse(&document(version, &header, &segments))?;
+    Some(RecoveredRun {
+        run,
+        segments: segments.len(),
+        skipped,
+    })
+}
+
+#[cfg(test)]
+mod recovery_tests {
+    use super::*;
+    use livesplit_core::run::saver::livesplit::save_run;
+    use livesplit_core::{Segment, Time, TimeSpan, TimingMethod};
+
+    fn saved_run() -> String {
+        let mut run = Run::new();
+        run.set_game_name("Celeste");
+        run.set_category_name("Any%");
+        for (name, pb) in [("Prologue", 20.0), ("City", 150.0), ("Site", 320.0)] {
+            let mut segment = Segment::new(name);
+            segment.set_personal_best_split_time(
+                Time::new().with_real_time(Some(TimeSpan::from_seconds(pb))),
+            );
+            run.push_segment(segment);
+        }
+        let mut text = String::new();
+        save_run(&run, &mut text).unwrap();
+        text
+    }
+
+    fn pb(run: &Run, index: usize) -> Option<f64> {
+        run.segment(index).personal_best_split_time()[TimingMethod::RealTime]
+            .map(|time| time.total_seconds())
+    }
+
+    #[test]
+    fn truncated_files_keep_the_complete_segments() {
+        let text = saved_run();
+        let cut = text.find("<Name>Site</Name>").unwrap();
+        let truncated = &text.as_bytes()[..cut];
+        assert!(composite::parse(truncated, None).is_err());
+
+        let recovered = recover_run(truncated).unwrap();
+        assert_eq!(recovered.segments, 2);
+        assert_eq!(recovered.skipped, 0);
+        let run = &recovered.run;
+        assert_eq!(run.game_name(), "Celeste");
+        assert_eq!(run.category_name(), "Any%");
+        let names: Vec<_> = run.segments().iter().map(Segment::name).collect();
+        assert_eq!(names, ["Prologue", "City"]);
+        assert_eq!(pb(run, 1), Some(150.0));
+    }
+
+    #[test]
+    fn damaged_segments_are_skipped() {
+        // City's personal best, the only time of 2:30
+        let text = saved_run().replacen("00:02:30", "xx:02:30", 1);
+        let recovered = recover_run(text.as_bytes()).unwrap();
+        assert_eq!(recovered.segments, 2);
+        assert_eq!(recovered.skipped, 1);
+        assert_eq!(recovered.run.segment(1).name(), "Site");
+        assert_eq!(pb(&recovered.run, 1), Some(320.0));
+    }
+
+    #[test]
+    fn long_runs_are_recovered_whole() {
+        let mut run = Run::new();
+        for index in 0..500 {
+            let mut segment = Segment::new(format!("S{index}"));
+            segment.set_personal_best_split_time(
+                Time::new().with_real_time(Some(TimeSpan::from_seconds(f64::from(index + 1)))),
+            );
+            run.push_segment(segment);
+        }
+        let mut text = String::new();
+        save_run(&run, &mut text).unwrap();
+        let cut = text.find("<Name>S499</Name>").unwrap();
+
+        let recovered = recover_run(&text.as_bytes()[..cut]).unwrap();
+        assert_eq!(recovered.segments, 499);
+        assert_eq!(recovered.skipped, 0);
+        assert_eq!(pb(&recovered.run, 498), Some(499.0));
+    }
+
+    #[test]
+    fn nothing_to_salvage() {
+        let text = saved_run();
+        let cut = text.find("<Name>Prologue</Name>").unwrap();
+        assert!(recover_run(&text.as_bytes()[..cut]).is_none());
+        assert!(recover_run(b"not a splits file").is_none());
+    }
+
+    #[test]
+    fn elements_are_matched_by_their_whole_name() {
+        let text = "<Segments><SegmentHistory /><Segment><Name>A</Name></Segment>";
+        assert_eq!(
+            element(text, "Segment"),
+            Some("<Segment><Name>A</Name></Segment>")
+        );
+        assert_eq!(element("<Offset />", "Offset"), Some("<Offset />"));
+        assert_eq!(element("<Segment><Name>", "Segment"), None);
+        assert_eq!(run_version("<Run version=\"1.6.0\">"), "1.6.0");
+        assert_eq!(run_version("<Run>"), DEFAULT_VERSION);
+    }
+}
@@ -0,0 +1,184 @@
+//! Skipping splits saves that would write what's already on disk.
+//!
+//! Every splits save remembers a hash of the text written to the path, with
+//! the file's size and modification time right after. A later save of an
+//! equivalent text is skipped while the file still looks the same, so a
+//! file changed or removed by something else is always written again.
+//!
+//! Texts are equivalent when they only differ in line endings and in the
+//! whitespace between elements, which the parser ignores.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::SystemTime;
+
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+static LEDGER: LazyLock<Mutex<SaveLedger>> = LazyLock::new(Mutex::default);
+
+/// `text` with line endings unified and whitespace between elements left
+/// out, the parts that change nothing once parsed.
+pub fn normalized(text: &str) -> String {
+    let text = text.replace("\r\n", "\n");
+    let mut out = String::with_capacity(text.len());
+    let mut pending = String::new();
+    for c in text.chars() {
+        if c.is_whitespace() {
+            pending.push(c);
+            continue;
+        }
+        if !(c == '<' && (out.is_empty() || out.ends_with('>'))) {
+            out.push_str(&pending);
+        }
+        pending.clear();
+        out.push(c);
+    }
+    out
+}
+
+/// Hex SHA-256 of the `normalized` text.
+pub fn content_hash(text: &str) -> String {
+    let digest = Sha256::digest(normalized(text).as_bytes());
+    digest.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+/// What a file looked like right after it was written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Written {
+    hash: String,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+fn on_disk(path: &Path) -> Option<(u64, Option<SystemTime>)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.len(), metadata.modified().ok()))
+}
+
+/// The last text written to each path.
+#[derive(Debug, Default)]
+pub struct SaveLedger {
+    written: HashMap<PathBuf, Written>,
+}
+
+impl SaveLedger {
+    /// Whether writing `text` to `path` would leave it as it is.
+    pub fn is_unchanged(&self, path: &Path, text: &str) -> bool {
+        let Some(written) = self.written.get(path) else {
+            return false;
+        };
+        on_disk(path) == Some((written.len, written.modified)) && written.hash == content_hash(text)
+    }
+
+    /// Remembers `text` as just written to `path`.
+    pub fn record(&mut self, path: &Path, text: &str) {
+        let Some((len, modified)) = on_disk(path) else {
+            self.written.remove(path);
+            return;
+        };
+        self.written.insert(
+            path.to_owned(),
+            Written {
+                hash: content_hash(text),
+                len,
+                modified,
+            },
+        );
+    }
+
+    /// Writes `text` to `path` unless it's already there. `false` when the
+    /// write was skipped.
+    pub fn write(&mut self, path: &Path, text: &str) -> io::Result<bool> {
+        if self.is_unchanged(path, text) {
+            debug!("{} is up to date, not saving it again", path.display());
+            return Ok(false);
+        }
+        fs::write(path, text)?;
+        self.record(path, text);
+        Ok(true)
+    }
+}
+
+/// Writes a splits file through the ledger shared by every save.
+pub fn write_if_changed(path: &Path, text: &str) -> io::Result<bool> {
+    LEDGER.lock().unwrap().write(path, text)
+}
+
+/// Writes a splits file even if it's up to date, e.g. to compact it.
+pub fn write_fresh(path: &Path, text: &str) -> io::Result<()> {
+    fs::write(path, text)?;
+    LEDGER.lock().unwrap().record(path, text);
+    Ok(())
+}
+
+#[cfg(test)]
+mod save_dedup_tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    const SAVED: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Run version=\"1.7.0\">\n  <GameName>Celeste</GameName>\n  <Segments>\n    <Segment>\n      <Name>Forsaken City</Name>\n    </Segment>\n  </Segments>\n</Run>\n";
+
+    fn temp_file(name: &str) -> PathBuf {
+        temp_dir(&format!("save-dedup-{name}")).join(name)
+    }
+
+    #[test]
+    fn equivalence_ignores_only_what_the_parser_ignores() {
+        // Identical
+        assert_eq!(content_hash(SAVED), content_hash(SAVED));
+        // Line endings and indentation only
+        let reformatted = SAVED.replace('\n', "\r\n").replace("  ", "\t");
+        assert_eq!(content_hash(&reformatted), content_hash(SAVED));
+        let compact: String = SAVED.lines().map(str::trim).collect();
+        assert_eq!(content_hash(&compact), content_hash(SAVED));
+        // Text inside an element is content
+        let renamed = SAVED.replace("Forsaken City", "Forsaken  City");
+        assert_ne!(content_hash(&renamed), content_hash(SAVED));
+        let another = SAVED.replace("Celeste", "Celeste 64");
+        assert_ne!(content_hash(&another), content_hash(SAVED));
+    }
+
+    #[test]
+    fn saving_the_same_run_twice_writes_once() {
+        let path = temp_file("twice.lss");
+        let mut ledger = SaveLedger::default();
+        assert!(ledger.write(&path, SAVED).unwrap());
+        assert!(!ledger.write(&path, SAVED).unwrap());
+        assert!(!ledger.write(&path, &SAVED.replace('\n', "\r\n")).unwrap());
+        // The skipped save kept the first text
+        assert_eq!(fs::read_to_string(&path).unwrap(), SAVED);
+
+        let changed = SAVED.replace("Celeste", "Celeste 64");
+        assert!(ledger.write(&path, &changed).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), changed);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn files_changed_by_something_else_are_written_again() {
+        let path = temp_file("external.lss");
+        let mut ledger = SaveLedger::default();
+        assert!(ledger.write(&path, SAVED).unwrap());
+
+        fs::write(&path, "edited by hand").unwrap();
+        assert!(ledger.write(&path, SAVED).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), SAVED);
+
+        fs::remove_file(&path).unwrap();
+        assert!(ledger.write(&path, SAVED).unwrap());
+        assert!(path.is_file());
+
+        // Other files aren't known at all
+        let other = temp_file("other.lss");
+        assert!(!ledger.is_unchanged(&other, SAVED));
+        let _ = fs::remove_file(&path);
+    }
+}
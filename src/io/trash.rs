@@ -0,0 +1,380 @@
+//! Copies of splits taken before a destructive operation.
+//!
+//! Operations confirmed from a dialog (clearing history, shifting times...)
+//! store the splits they are about to change, as loaded and with any unsaved
+//! attempts, in the trash directory, so they can be undone after the
+//! editor's Cancel is long gone. Writes over a file itself (recovering a
+//! damaged one, compacting) copy the file instead. An index file
+//! next to the copies records where each one came from and which operation
+//! produced it. The trash is capped in size; the oldest copies go first.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tracing::{debug, warn};
+
+use crate::config::data_dir;
+use crate::io::bundle::unique_path;
+
+const INDEX_FILE: &str = "index.yaml";
+
+/// One snapshot in the trash.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct TrashEntry {
+    /// File name of the copy inside the trash directory.
+    pub file: String,
+    /// Where the file was copied from, and where restoring puts it back.
+    pub original: PathBuf,
+    /// What was about to change the file, e.g. "Clear attempt history".
+    pub operation: String,
+    /// Unix timestamp of the snapshot.
+    pub created: i64,
+    pub size: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+#[serde(default)]
+struct TrashIndex {
+    /// Oldest first.
+    entries: Vec<TrashEntry>,
+}
+
+/// `trash` in the data directory, see [`data_dir`].
+pub fn default_trash_dir() -> PathBuf {
+    data_dir().map_or_else(
+        || std::env::temp_dir().join("tuxsplit-trash"),
+        |dir| dir.join("trash"),
+    )
+}
+
+pub struct Trash {
+    dir: PathBuf,
+    cap_bytes: u64,
+}
+
+impl Trash {
+    /// A `cap_bytes` of 0 turns snapshots off.
+    pub fn new(dir: impl Into<PathBuf>, cap_bytes: u64) -> Self {
+        Self {
+            dir: dir.into(),
+            cap_bytes,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.dir.join(INDEX_FILE)
+    }
+
+    /// A missing or unreadable index is an empty trash. The copies it lost
+    /// track of are left alone.
+    fn read_index(&self) -> TrashIndex {
+        let Ok(buf) = fs::read(self.index_path()) else {
+            return TrashIndex::default();
+        };
+        serde_yaml::from_slice(&buf).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable trash index: {e}");
+            TrashIndex::default()
+        })
+    }
+
+    fn write_index(&self, index: &TrashIndex) -> io::Result<()> {
+        let buf = serde_yaml::to_string(index).map_err(io::Error::other)?;
+        fs::write(self.index_path(), buf)
+    }
+
+    /// Snapshots still on disk, newest first.
+    pub fn entries(&self) -> Vec<TrashEntry> {
+        let mut entries: Vec<_> = self
+            .read_index()
+            .entries
+            .into_iter()
+            .filter(|entry| self.dir.join(&entry.file).is_file())
+            .collect();
+        entries.reverse();
+        entries
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.entries().iter().map(|entry| entry.size).sum()
+    }
+
+    /// Copies `original` into the trash before `operation` changes it.
+    /// Returns `None` when there's nothing to copy or the trash is off.
+    pub fn snapshot(&self, original: &Path, operation: &str) -> io::Result<Option<TrashEntry>> {
+        self.snapshot_at(original, operation, OffsetDateTime::now_utc())
+    }
+
+    fn snapshot_at(
+        &self,
+        original: &Path,
+        operation: &str,
+        now: OffsetDateTime,
+    ) -> io::Result<Option<TrashEntry>> {
+        if self.cap_bytes == 0 || !original.is_file() {
+            return Ok(None);
+        }
+        let contents = fs::read(original)?;
+        self.store_at(original, &contents, operation, now)
+    }
+
+    /// Stores `contents`, the splits `original` is about to be replaced
+    /// with by `operation` changing them, e.g. ones with unsaved attempts.
+    /// Returns `None` when the trash is off.
+    pub fn store(
+        &self,
+        original: &Path,
+        contents: &[u8],
+        operation: &str,
+    ) -> io::Result<Option<TrashEntry>> {
+        self.store_at(original, contents, operation, OffsetDateTime::now_utc())
+    }
+
+    fn store_at(
+        &self,
+        original: &Path,
+        contents: &[u8],
+        operation: &str,
+        now: OffsetDateTime,
+    ) -> io::Result<Option<TrashEntry>> {
+        if self.cap_bytes == 0 {
+            return Ok(None);
+        }
+        fs::create_dir_all(&self.dir)?;
+
+        let name = original
+            .file_name()
+            .map_or_else(|| "splits.lss".into(), |name| name.to_string_lossy());
+        let stamp = format!(
+            "{:04}{:02}{:02}-{:02}{:02}{:02}",
+            now.year(),
+            u8::from(now.month()),
+            now.day(),
+            now.hour(),
+            now.minute(),
+            now.second()
+        );
+        let path = unique_path(&self.dir, &format!("{stamp}-{name}"));
+        fs::write(&path, contents)?;
+
+        let entry = TrashEntry {
+            file: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            original: original.to_path_buf(),
+            operation: operation.to_owned(),
+            created: now.unix_timestamp(),
+            size: contents.len() as u64,
+        };
+        let mut index = self.read_index();
+        index
+            .entries
+            .retain(|entry| self.dir.join(&entry.file).is_file());
+        index.entries.push(entry.clone());
+        self.evict(&mut index);
+        self.write_index(&index)?;
+        debug!(
+            "Stored {} in the trash before \"{operation}\"",
+            original.display()
+        );
+        Ok(Some(entry))
+    }
+
+    /// Drops the oldest copies until the trash fits its cap. The newest one
+    /// is always kept, even when it alone is over the cap.
+    fn evict(&self, index: &mut TrashIndex) {
+        let mut total: u64 = index.entries.iter().map(|entry| entry.size).sum();
+        while total > self.cap_bytes && index.entries.len() > 1 {
+            let oldest = index.entries.remove(0);
+            total -= oldest.size;
+            if let Err(e) = fs::remove_file(self.dir.join(&oldest.file)) {
+                warn!("Could not remove {} from the trash: {e}", oldest.file);
+            }
+        }
+    }
+
+    /// Puts `entry` back where it came from. Whatever is there now is
+    /// snapshotted first, so a restore can be undone the same way.
+    pub fn restore(&self, entry: &TrashEntry) -> io::Result<()> {
+        let contents = fs::read(self.dir.join(&entry.file))?;
+        self.snapshot(&entry.original, "Restore from trash")?;
+        if let Some(parent) = entry.original.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&entry.original, contents)
+    }
+}
+
+#[cfg(test)]
+mod trash_tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    /// 2026-10-14 12:mm:00 UTC
+    fn at(minute: i64) -> OffsetDateTime {
+        OffsetDateTime::from_unix_timestamp(1_791_979_200 + minute * 60).unwrap()
+    }
+
+    #[test]
+    fn snapshot_copies_and_records_the_operation() {
+        let dir = temp_dir("trash-snapshot");
+        let original = dir.join("game.lss");
+        fs::write(&original, "before").unwrap();
+        let trash = Trash::new(dir.join("trash"), 1024);
+
+        let entry = trash
+            .snapshot_at(&original, "Clear attempt history", at(5))
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.file, "20261014-120500-game.lss");
+        assert_eq!(entry.original, original);
+        assert_eq!(entry.operation, "Clear attempt history");
+        assert_eq!(entry.size, 6);
+        assert_eq!(
+            fs::read_to_string(trash.dir().join(&entry.file)).unwrap(),
+            "before"
+        );
+        assert_eq!(trash.entries(), vec![entry]);
+    }
+
+    #[test]
+    fn stored_contents_need_no_file_on_disk() {
+        let dir = temp_dir("trash-store");
+        let original = dir.join("game.lss");
+        let trash = Trash::new(dir.join("trash"), 1024);
+
+        let entry = trash
+            .store_at(&original, b"in memory", "Clear attempt history", at(5))
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.original, original);
+        assert_eq!(entry.size, 9);
+        assert_eq!(
+            fs::read_to_string(trash.dir().join(&entry.file)).unwrap(),
+            "in memory"
+        );
+
+        let off = Trash::new(dir.join("off"), 0);
+        assert_eq!(off.store(&original, b"x", "A").unwrap(), None);
+    }
+
+    #[test]
+    fn same_second_snapshots_get_distinct_names() {
+        let dir = temp_dir("trash-names");
+        let original = dir.join("game.lss");
+        fs::write(&original, "x").unwrap();
+        let trash = Trash::new(dir.join("trash"), 1024);
+
+        let first = trash.snapshot_at(&original, "A", at(1)).unwrap().unwrap();
+        let second = trash.snapshot_at(&original, "B", at(1)).unwrap().unwrap();
+        assert_ne!(first.file, second.file);
+        // Newest first
+        assert_eq!(trash.entries(), vec![second, first]);
+    }
+
+    #[test]
+    fn nothing_to_copy_or_trash_off() {
+        let dir = temp_dir("trash-none");
+        let trash = Trash::new(dir.join("trash"), 1024);
+        assert_eq!(trash.snapshot(&dir.join("missing.lss"), "A").unwrap(), None);
+
+        let original = dir.join("game.lss");
+        fs::write(&original, "x").unwrap();
+        let off = Trash::new(dir.join("trash"), 0);
+        assert_eq!(off.snapshot(&original, "A").unwrap(), None);
+        assert!(!dir.join("trash").exists());
+    }
+
+    #[test]
+    fn oldest_snapshots_are_evicted_first() {
+        let dir = temp_dir("trash-evict");
+        let original = dir.join("game.lss");
+        fs::write(&original, "0123456789").unwrap();
+        let trash = Trash::new(dir.join("trash"), 25);
+
+        let first = trash.snapshot_at(&original, "A", at(1)).unwrap().unwrap();
+        let second = trash.snapshot_at(&original, "B", at(2)).unwrap().unwrap();
+        let third = trash.snapshot_at(&original, "C", at(3)).unwrap().unwrap();
+
+        assert_eq!(trash.entries(), vec![third, second]);
+        assert!(!trash.dir().join(&first.file).exists());
+        assert_eq!(trash.total_size(), 20);
+    }
+
+    #[test]
+    fn the_newest_snapshot_is_kept_even_over_the_cap() {
+        let dir = temp_dir("trash-oversized");
+        let original = dir.join("game.lss");
+        fs::write(&original, "small").unwrap();
+        let trash = Trash::new(dir.join("trash"), 8);
+        trash.snapshot_at(&original, "A", at(1)).unwrap();
+
+        fs::write(&original, "much larger than the cap").unwrap();
+        let big = trash.snapshot_at(&original, "B", at(2)).unwrap().unwrap();
+        assert_eq!(trash.entries(), vec![big]);
+    }
+
+    #[test]
+    fn restore_puts_the_copy_back_and_keeps_the_current_file() {
+        let dir = temp_dir("trash-restore");
+        let original = dir.join("game.lss");
+        fs::write(&original, "before").unwrap();
+        let trash = Trash::new(dir.join("trash"), 1024);
+        let entry = trash
+            .snapshot_at(&original, "Clear attempt history", at(1))
+            .unwrap()
+            .unwrap();
+        fs::write(&original, "after").unwrap();
+
+        trash.restore(&entry).unwrap();
+        assert_eq!(fs::read_to_string(&original).unwrap(), "before");
+
+        let entries = trash.entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].operation, "Restore from trash");
+        assert_eq!(
+            fs::read_to_string(trash.dir().join(&entries[0].file)).unwrap(),
+            "after"
+        );
+    }
+
+    #[test]
+    fn restore_recreates_a_deleted_original() {
+        let dir = temp_dir("trash-recreate");
+        let original = dir.join("splits").join("game.lss");
+        fs::create_dir_all(original.parent().unwrap()).unwrap();
+        fs::write(&original, "before").unwrap();
+        let trash = Trash::new(dir.join("trash"), 1024);
+        let entry = trash.snapshot_at(&original, "A", at(1)).unwrap().unwrap();
+        fs::remove_dir_all(original.parent().unwrap()).unwrap();
+
+        trash.restore(&entry).unwrap();
+        assert_eq!(fs::read_to_string(&original).unwrap(), "before");
+    }
+
+    #[test]
+    fn corrupt_index_and_missing_copies_are_tolerated() {
+        let dir = temp_dir("trash-corrupt");
+        let original = dir.join("game.lss");
+        fs::write(&original, "x").unwrap();
+        let trash = Trash::new(dir.join("trash"), 1024);
+        let entry = trash.snapshot_at(&original, "A", at(1)).unwrap().unwrap();
+
+        fs::remove_file(trash.dir().join(&entry.file)).unwrap();
+        assert!(trash.entries().is_empty());
+
+        fs::write(trash.dir().join(INDEX_FILE), "entries: [not, valid").unwrap();
+        assert!(trash.entries().is_empty());
+        let fresh = trash.snapshot_at(&original, "B", at(2)).unwrap().unwrap();
+        assert_eq!(trash.entries(), vec![fresh]);
+    }
+}
@@ -1,6 +1,10 @@
 mod config;
 mod context;
 mod formatters;
+mod io;
+mod race;
+#[cfg(test)]
+mod test_support;
 mod ui;
 mod utils;
 
@@ -8,7 +12,10 @@ use std::path::Path;
 
 use tracing::info;
 
-use crate::context::{build_ui, shutdown};
+use crate::context::{build_ui, logging_options, shutdown, window_hotkeys_requested};
+use crate::utils::crash_report::install_panic_hook;
+use crate::utils::startup;
+use crate::utils::trace_timing::init_tracing;
 use adw::Application;
 use adw::prelude::*;
 use gtk4::{
@@ -18,17 +25,30 @@ use gtk4::{
 };
 
 const RESOURCE_ICONS: &str = "/com/tunixr/tuxsplit/icons";
-const RESOURCE_CSS: &str = "/com/tunixr/tuxsplit/css/tuxsplit.css";
+pub const RESOURCE_CSS: &str = "/com/tunixr/tuxsplit/css/tuxsplit.css";
+const GRESOURCE_PATHS: [&str; 2] = [
+    "/app/share/tuxsplit.gresource",
+    "/usr/share/tuxsplit/tuxsplit.gresource",
+];
+pub const LOG_LEVEL: tracing::Level = tracing::Level::DEBUG;
+/// Writes how long each part of the startup took to the config directory.
+const PROFILE_STARTUP_FLAG: &str = "--profile-startup";
 
 fn main() {
-    unsafe {
-        std::env::set_var("GDK_BACKEND", "x11"); // Livesplit-core does not support Wayland global shortcut portal yet
+    // Before anything can panic
+    install_panic_hook();
+    let (profile_startup, args): (Vec<String>, Vec<String>) =
+        std::env::args().partition(|arg| arg == PROFILE_STARTUP_FLAG);
+    startup::begin(!profile_startup.is_empty());
+
+    if !window_hotkeys_requested() {
+        unsafe {
+            std::env::set_var("GDK_BACKEND", "x11"); // Livesplit-core does not support Wayland global shortcut portal yet
+        }
     }
 
-    // Set tracing to stdout
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::DEBUG)
-        .init();
+    // Set tracing to stdout, and the timing traces where they are asked for
+    init_tracing(LOG_LEVEL, &logging_options());
 
     register_gresource();
     info!("Starting TuxSplit");
@@ -49,7 +69,8 @@ fn main() {
             shutdown();
         });
     }
-    app.run();
+    // GApplication would refuse the flag it doesn't know
+    app.run_with_args(&args);
 }
 
 fn load_styles() {
@@ -67,20 +88,19 @@ fn load_styles() {
     );
 }
 
+/// First installed GResource bundle, in lookup order (Flatpak, then system).
+pub fn gresource_path() -> Option<&'static Path> {
+    GRESOURCE_PATHS
+        .iter()
+        .map(Path::new)
+        .find(|path| path.exists())
+}
+
 fn register_gresource() {
-    let path = Path::new("/app/share/tuxsplit.gresource");
-    if path.exists() {
-        let res = gio::Resource::load(path).expect("Failed to load resource");
-        info!("Registered GResource from {}", path.display());
-        gio::resources_register(&res);
-        return;
-    }
-    let usr_path = Path::new("/usr/share/tuxsplit/tuxsplit.gresource");
-    if usr_path.exists() {
-        let res = gio::Resource::load(usr_path).expect("Failed to load resource");
-        info!("Registered GResource from {}", usr_path.display());
-        gio::resources_register(&res);
-        return;
-    }
-    panic!("Could not load resources");
+    let Some(path) = gresource_path() else {
+        panic!("Could not load resources");
+    };
+    let res = gio::Resource::load(path).expect("Failed to load resource");
+    info!("Registered GResource from {}", path.display());
+    gio::resources_register(&res);
 }
@@ -0,0 +1,409 @@
+//! Head-to-head races between two TuxSplit instances.
+//!
+//! Both sides send their split events over a plain TCP connection using a
+//! line based protocol (see `RaceMessage`). Each side rebuilds the opponent's
+//! splits from those events and compares them against its own at the latest
+//! split both runners have reached. Everything here is independent of GTK;
+//! `net` owns the sockets. `racetime` joins racetime.gg rooms instead.
+
+pub mod net;
+#[cfg(feature = "racetime")]
+pub mod racetime;
+
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::time::SystemTime;
+
+use livesplit_core::{Timer, TimerPhase};
+
+/// Bumped whenever the line format changes incompatibly.
+pub const PROTOCOL_VERSION: u32 = 2;
+pub const DEFAULT_PORT: u16 = 7878;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RaceMessage {
+    /// `HELLO <version> <room code> <name>`, sent once after connecting.
+    Hello {
+        version: u32,
+        code: String,
+        name: String,
+    },
+    /// `START`, the attempt started.
+    Start,
+    /// `SPLIT <index> <millis>`, segment `index` was split at `millis` into the run.
+    Split { index: usize, millis: i64 },
+    /// `SKIP <index>`, segment `index` was skipped.
+    Skip { index: usize },
+    /// `UNDO <index>`, splits were undone and `index` is the current segment again.
+    Undo { index: usize },
+    /// `RESET`, the attempt was reset.
+    Reset,
+}
+
+impl RaceMessage {
+    pub fn encode(&self) -> String {
+        match self {
+            RaceMessage::Hello {
+                version,
+                code,
+                name,
+            } => {
+                // Codes are one word and names single line, the rest of the line is the name
+                let code: String = code.split_whitespace().collect();
+                format!("HELLO {version} {code} {}", name.replace(['\n', '\r'], " "))
+            }
+            RaceMessage::Start => "START".to_owned(),
+            RaceMessage::Split { index, millis } => format!("SPLIT {index} {millis}"),
+            RaceMessage::Skip { index } => format!("SKIP {index}"),
+            RaceMessage::Undo { index } => format!("UNDO {index}"),
+            RaceMessage::Reset => "RESET".to_owned(),
+        }
+    }
+
+    /// Parses one line. Unknown or malformed lines yield `None` so newer
+    /// peers can add messages without breaking older ones.
+    pub fn decode(line: &str) -> Option<Self> {
+        let line = line.trim_end_matches(['\n', '\r']);
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match command {
+            "HELLO" => {
+                let (version, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+                let (code, name) = rest.split_once(' ').unwrap_or((rest, ""));
+                Some(RaceMessage::Hello {
+                    version: version.parse().ok()?,
+                    code: code.to_owned(),
+                    name: name.trim().to_owned(),
+                })
+            }
+            "START" => Some(RaceMessage::Start),
+            "SPLIT" => {
+                let (index, millis) = rest.split_once(' ')?;
+                Some(RaceMessage::Split {
+                    index: index.parse().ok()?,
+                    millis: millis.trim().parse().ok()?,
+                })
+            }
+            "SKIP" => Some(RaceMessage::Skip {
+                index: rest.trim().parse().ok()?,
+            }),
+            "UNDO" => Some(RaceMessage::Undo {
+                index: rest.trim().parse().ok()?,
+            }),
+            "RESET" => Some(RaceMessage::Reset),
+            _ => None,
+        }
+    }
+}
+
+/// A fresh room code for hosting. Both sides have to enter the same code,
+/// so a stranger reaching the port cannot join the race.
+pub fn room_code() -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+    let mut bits = RandomState::new().hash_one(SystemTime::now());
+    (0..6)
+        .map(|_| {
+            let c = ALPHABET[(bits % ALPHABET.len() as u64) as usize] as char;
+            bits /= ALPHABET.len() as u64;
+            c
+        })
+        .collect()
+}
+
+/// What we know about the opponent's attempt.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpponentState {
+    pub name: Option<String>,
+    /// Split times in milliseconds, `None` for skipped segments. The length
+    /// is the opponent's current segment index.
+    pub splits: Vec<Option<i64>>,
+    pub running: bool,
+    /// Cleared when the connection drops. The last known splits stay frozen.
+    pub connected: bool,
+}
+
+impl OpponentState {
+    pub fn current_index(&self) -> usize {
+        self.splits.len()
+    }
+
+    /// Splits arrive one segment after the other, anything else is a
+    /// confused or hostile peer and is dropped.
+    fn set_split(&mut self, index: usize, millis: Option<i64>, segments: usize) {
+        if index == self.splits.len() && index < segments {
+            self.splits.push(millis);
+        }
+    }
+
+    /// Applies `message` for a race over a run of `segments` segments.
+    pub fn apply(&mut self, message: &RaceMessage, segments: usize) {
+        match message {
+            RaceMessage::Hello { name, .. } => {
+                self.name = (!name.is_empty()).then(|| name.clone());
+            }
+            RaceMessage::Start => {
+                self.splits.clear();
+                self.running = true;
+            }
+            RaceMessage::Split { index, millis } => {
+                self.set_split(*index, Some(*millis), segments);
+            }
+            RaceMessage::Skip { index } => self.set_split(*index, None, segments),
+            RaceMessage::Undo { index } => self.splits.truncate(*index),
+            RaceMessage::Reset => {
+                self.splits.clear();
+                self.running = false;
+            }
+        }
+    }
+}
+
+/// Our time minus the opponent's at the latest segment both have split,
+/// as `(segment index, difference in milliseconds)`.
+pub fn race_delta(mine: &[Option<i64>], theirs: &[Option<i64>]) -> Option<(usize, i64)> {
+    mine.iter()
+        .zip(theirs)
+        .enumerate()
+        .rev()
+        .find_map(|(index, pair)| match pair {
+            (Some(mine), Some(theirs)) => Some((index, mine.checked_sub(*theirs)?)),
+            _ => None,
+        })
+}
+
+/// Our own split times, in the same shape as `OpponentState::splits`.
+pub fn own_splits(timer: &Timer) -> Vec<Option<i64>> {
+    let method = timer.current_timing_method();
+    let current = match timer.current_phase() {
+        TimerPhase::NotRunning => 0,
+        _ => timer
+            .current_split_index()
+            .unwrap_or_else(|| timer.run().len()),
+    };
+    timer
+        .run()
+        .segments()
+        .iter()
+        .take(current)
+        .map(|segment| {
+            segment.split_time()[method].map(|time| time.to_duration().whole_milliseconds() as i64)
+        })
+        .collect()
+}
+
+/// Turns changes in our own timer into messages for the opponent.
+#[derive(Debug, Default)]
+pub struct RaceReporter {
+    running: bool,
+    splits: Vec<Option<i64>>,
+}
+
+impl RaceReporter {
+    /// Compares `splits` and `running` against the last call and returns the
+    /// messages describing the difference.
+    pub fn update(&mut self, running: bool, splits: Vec<Option<i64>>) -> Vec<RaceMessage> {
+        let mut messages = Vec::new();
+        if running && !self.running {
+            messages.push(RaceMessage::Start);
+            self.splits.clear();
+        } else if !running && self.running {
+            messages.push(RaceMessage::Reset);
+            self.splits.clear();
+        }
+        self.running = running;
+        if !running {
+            return messages;
+        }
+
+        let common = self
+            .splits
+            .iter()
+            .zip(&splits)
+            .take_while(|(old, new)| old == new)
+            .count();
+        if common < self.splits.len() {
+            messages.push(RaceMessage::Undo { index: common });
+        }
+        for (index, split) in splits.iter().enumerate().skip(common) {
+            messages.push(match split {
+                Some(millis) => RaceMessage::Split {
+                    index,
+                    millis: *millis,
+                },
+                None => RaceMessage::Skip { index },
+            });
+        }
+        self.splits = splits;
+        messages
+    }
+}
+
+#[cfg(test)]
+mod race_tests {
+    use super::*;
+
+    #[test]
+    fn messages_round_trip() {
+        let messages = [
+            RaceMessage::Hello {
+                version: PROTOCOL_VERSION,
+                code: "K7QM2X".to_owned(),
+                name: "Speedy Runner".to_owned(),
+            },
+            RaceMessage::Start,
+            RaceMessage::Split {
+                index: 3,
+                millis: 61_250,
+            },
+            RaceMessage::Skip { index: 4 },
+            RaceMessage::Undo { index: 2 },
+            RaceMessage::Reset,
+        ];
+        for message in messages {
+            assert_eq!(RaceMessage::decode(&message.encode()), Some(message));
+        }
+    }
+
+    #[test]
+    fn malformed_and_unknown_lines_are_ignored() {
+        assert_eq!(RaceMessage::decode("SPLIT x 10"), None);
+        assert_eq!(RaceMessage::decode("SPLIT 1"), None);
+        assert_eq!(RaceMessage::decode("CHAT hello"), None);
+        assert_eq!(RaceMessage::decode(""), None);
+        assert_eq!(RaceMessage::decode("RESET\r\n"), Some(RaceMessage::Reset));
+    }
+
+    #[test]
+    fn names_are_kept_on_one_line() {
+        let hello = RaceMessage::Hello {
+            version: 2,
+            code: "AB CD".to_owned(),
+            name: "two\nlines".to_owned(),
+        };
+        assert_eq!(hello.encode(), "HELLO 2 ABCD two lines");
+    }
+
+    #[test]
+    fn room_codes_are_short_and_unambiguous() {
+        let code = room_code();
+        assert_eq!(code.len(), 6);
+        assert!(!code.contains(['0', 'O', '1', 'I']));
+    }
+
+    #[test]
+    fn opponent_state_follows_messages() {
+        let mut opponent = OpponentState::default();
+        opponent.apply(&RaceMessage::Start, 4);
+        opponent.apply(
+            &RaceMessage::Split {
+                index: 0,
+                millis: 1000,
+            },
+            4,
+        );
+        opponent.apply(&RaceMessage::Skip { index: 1 }, 4);
+        opponent.apply(
+            &RaceMessage::Split {
+                index: 2,
+                millis: 3000,
+            },
+            4,
+        );
+        assert_eq!(opponent.splits, vec![Some(1000), None, Some(3000)]);
+        assert_eq!(opponent.current_index(), 3);
+
+        opponent.apply(&RaceMessage::Undo { index: 1 }, 4);
+        assert_eq!(opponent.splits, vec![Some(1000)]);
+
+        opponent.apply(&RaceMessage::Reset, 4);
+        assert!(opponent.splits.is_empty());
+        assert!(!opponent.running);
+    }
+
+    #[test]
+    fn out_of_place_splits_are_dropped() {
+        let mut opponent = OpponentState::default();
+        opponent.apply(&RaceMessage::Start, 2);
+        let split = |index| RaceMessage::Split {
+            index,
+            millis: 1000,
+        };
+        // Past the end of our run, or far past it
+        opponent.apply(&split(2), 2);
+        opponent.apply(&split(4_000_000_000), 2);
+        // Ahead of the opponent's current segment
+        opponent.apply(&RaceMessage::Skip { index: 1 }, 2);
+        assert!(opponent.splits.is_empty());
+
+        opponent.apply(&split(0), 2);
+        opponent.apply(&split(1), 2);
+        // A late message for an earlier segment keeps the later splits
+        opponent.apply(&RaceMessage::Skip { index: 0 }, 2);
+        assert_eq!(opponent.splits, vec![Some(1000), Some(1000)]);
+    }
+
+    #[test]
+    fn delta_uses_latest_common_split() {
+        let mine = [Some(1000), Some(2500), None];
+        let theirs = [Some(1200), Some(2000), Some(2900), Some(4000)];
+        // Segment 2 was skipped on our side, so segment 1 is compared
+        assert_eq!(race_delta(&mine, &theirs), Some((1, 500)));
+        assert_eq!(race_delta(&mine[..1], &theirs), Some((0, -200)));
+        assert_eq!(race_delta(&[], &theirs), None);
+        assert_eq!(race_delta(&[None], &[Some(1)]), None);
+        // Overflowing differences fall back to an earlier split
+        assert_eq!(
+            race_delta(&[Some(5), Some(i64::MAX)], &[Some(2), Some(-1)]),
+            Some((0, 3))
+        );
+    }
+
+    #[test]
+    fn reporter_describes_splits_skips_and_undos() {
+        let mut reporter = RaceReporter::default();
+        assert!(reporter.update(false, vec![]).is_empty());
+        assert_eq!(reporter.update(true, vec![]), vec![RaceMessage::Start]);
+        assert_eq!(
+            reporter.update(true, vec![Some(1000), None]),
+            vec![
+                RaceMessage::Split {
+                    index: 0,
+                    millis: 1000
+                },
+                RaceMessage::Skip { index: 1 },
+            ]
+        );
+        assert!(reporter.update(true, vec![Some(1000), None]).is_empty());
+        assert_eq!(
+            reporter.update(true, vec![Some(1000)]),
+            vec![RaceMessage::Undo { index: 1 }]
+        );
+        // Undo followed by a new split in the same poll
+        assert_eq!(
+            reporter.update(true, vec![Some(1000), Some(2100)]),
+            vec![RaceMessage::Split {
+                index: 1,
+                millis: 2100
+            }]
+        );
+        assert_eq!(reporter.update(false, vec![]), vec![RaceMessage::Reset]);
+    }
+
+    #[test]
+    fn reporter_replays_what_opponent_state_rebuilds() {
+        let mut reporter = RaceReporter::default();
+        let mut opponent = OpponentState::default();
+        let steps = [
+            vec![],
+            vec![Some(900)],
+            vec![Some(900), None, Some(2800)],
+            vec![Some(900)],
+            vec![Some(900), Some(2000)],
+        ];
+        for splits in steps {
+            for message in reporter.update(true, splits.clone()) {
+                opponent.apply(&message, 3);
+            }
+            assert_eq!(opponent.splits, splits);
+        }
+    }
+}
@@ -0,0 +1,403 @@
+//! Socket handling for races.
+//!
+//! A connection runs on two background threads: one reads lines from the
+//! peer and forwards them as `NetEvent`s, the other writes the messages
+//! queued with `RaceConnection::send`. Dropping the connection closes the
+//! socket and ends both threads. The first line each side reads has to be a
+//! HELLO with the same protocol version and room code; the host checks it
+//! before sending anything back.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use tracing::{info, warn};
+
+use super::{PROTOCOL_VERSION, RaceMessage};
+
+const ACCEPT_POLL: Duration = Duration::from_millis(100);
+/// How long a new peer has to send its HELLO before it is turned away.
+const HELLO_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_HELLO_LEN: usize = 512;
+/// Longest line taken from a connected peer, far more than any message needs.
+const MAX_LINE_LEN: usize = 512;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetEvent {
+    /// The socket is up, with the peer address.
+    Connected(String),
+    Message(RaceMessage),
+    /// The connection failed or was closed, with a reason for the user.
+    Disconnected(String),
+}
+
+pub struct RaceConnection {
+    outgoing: Sender<RaceMessage>,
+    incoming: Receiver<NetEvent>,
+}
+
+impl RaceConnection {
+    /// Queues a message. Messages sent before the peer connects are delivered
+    /// once it does.
+    pub fn send(&self, message: RaceMessage) {
+        // Fails only once the writer is gone, which is reported as Disconnected
+        let _ = self.outgoing.send(message);
+    }
+
+    /// Events received since the last call.
+    pub fn poll(&self) -> Vec<NetEvent> {
+        self.incoming.try_iter().collect()
+    }
+}
+
+/// Checks the first line from the peer: it has to be a HELLO for the same
+/// protocol version and room code.
+pub fn check_hello(line: &str, code: &str) -> Result<RaceMessage, String> {
+    match RaceMessage::decode(line) {
+        Some(RaceMessage::Hello { version, .. }) if version != PROTOCOL_VERSION => Err(format!(
+            "The opponent's TuxSplit speaks race protocol {version}, this one speaks {PROTOCOL_VERSION}"
+        )),
+        Some(RaceMessage::Hello { code: theirs, .. }) if theirs != code => {
+            Err("The opponent entered a different room code".to_owned())
+        }
+        Some(hello @ RaceMessage::Hello { .. }) => Ok(hello),
+        _ => Err("The other side is not a TuxSplit race".to_owned()),
+    }
+}
+
+/// Reads the peer's HELLO before anything of ours, including the room code,
+/// is sent to it. Unbuffered so nothing after the first line is lost.
+fn greet(stream: &TcpStream, code: &str) -> Result<RaceMessage, String> {
+    stream
+        .set_read_timeout(Some(HELLO_TIMEOUT))
+        .map_err(|err| err.to_string())?;
+    let mut line = Vec::new();
+    let mut byte = [0; 1];
+    while byte[0] != b'\n' {
+        match (&*stream).read(&mut byte) {
+            Ok(0) => return Err("The connection closed before saying hello".to_owned()),
+            Ok(_) if line.len() < MAX_HELLO_LEN => line.push(byte[0]),
+            Ok(_) => return Err("The hello was too long".to_owned()),
+            Err(err) => return Err(err.to_string()),
+        }
+    }
+    stream
+        .set_read_timeout(None)
+        .map_err(|err| err.to_string())?;
+    check_hello(&String::from_utf8_lossy(&line), code)
+}
+
+/// The next line from the peer, `None` once it closed the connection. A line
+/// longer than `MAX_LINE_LEN` is an error rather than buffered without end.
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = Vec::new();
+    let read = reader
+        .take(MAX_LINE_LEN as u64 + 1)
+        .read_until(b'\n', &mut line)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if line.len() > MAX_LINE_LEN && line.last() != Some(&b'\n') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "The opponent sent an overlong line",
+        ));
+    }
+    Ok(Some(String::from_utf8_lossy(&line).into_owned()))
+}
+
+/// Waits for an opponent with the same room `code` on `port`. Only this
+/// machine can connect unless `remote` is set, then every interface listens.
+/// Peers with the wrong code or version are turned away and waiting goes on.
+pub fn host(port: u16, remote: bool, code: String) -> io::Result<RaceConnection> {
+    let address = if remote { "0.0.0.0" } else { "127.0.0.1" };
+    let listener = TcpListener::bind((address, port))?;
+    listener.set_nonblocking(true)?;
+    info!("Waiting for a race opponent on {address}:{port}");
+
+    let (outgoing, outgoing_rx) = mpsc::channel();
+    let (incoming_tx, incoming) = mpsc::channel();
+    thread::spawn(move || {
+        // Messages queued while nobody is connected yet
+        let mut pending = Vec::new();
+        loop {
+            match listener.accept() {
+                Ok((stream, peer)) => {
+                    if stream.set_nonblocking(false).is_err() {
+                        continue;
+                    }
+                    match greet(&stream, &code) {
+                        Ok(hello) => {
+                            run_connection(
+                                stream,
+                                pending,
+                                Some(hello),
+                                &code,
+                                &outgoing_rx,
+                                &incoming_tx,
+                            );
+                            return;
+                        }
+                        Err(reason) => warn!("Turned away race opponent {peer}: {reason}"),
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    match outgoing_rx.recv_timeout(ACCEPT_POLL) {
+                        Ok(message) => pending.push(message),
+                        Err(mpsc::RecvTimeoutError::Timeout) => {}
+                        // Stop waiting once the session was left
+                        Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+                Err(err) => {
+                    let _ = incoming_tx.send(NetEvent::Disconnected(err.to_string()));
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(RaceConnection { outgoing, incoming })
+}
+
+/// Connects to a hosting opponent at `address` ("host:port") with the room
+/// `code` they shared.
+pub fn join(address: String, code: String) -> RaceConnection {
+    let (outgoing, outgoing_rx) = mpsc::channel();
+    let (incoming_tx, incoming) = mpsc::channel();
+    thread::spawn(move || match TcpStream::connect(&address) {
+        Ok(stream) => run_connection(stream, Vec::new(), None, &code, &outgoing_rx, &incoming_tx),
+        Err(err) => {
+            let _ = incoming_tx.send(NetEvent::Disconnected(err.to_string()));
+        }
+    });
+
+    RaceConnection { outgoing, incoming }
+}
+
+/// `hello` is the peer's already checked HELLO, if `greet` read it. Otherwise
+/// the first line read is checked and the connection dropped if it fails.
+fn run_connection(
+    stream: TcpStream,
+    pending: Vec<RaceMessage>,
+    hello: Option<RaceMessage>,
+    code: &str,
+    outgoing: &Receiver<RaceMessage>,
+    incoming: &Sender<NetEvent>,
+) {
+    let peer = stream
+        .peer_addr()
+        .map_or_else(|_| "opponent".to_owned(), |addr| addr.to_string());
+    let _ = stream.set_nodelay(true);
+
+    let reader = match stream.try_clone() {
+        Ok(reader) => reader,
+        Err(err) => {
+            let _ = incoming.send(NetEvent::Disconnected(err.to_string()));
+            return;
+        }
+    };
+    let incoming_for_reader = incoming.clone();
+    let code = code.to_owned();
+    thread::spawn(move || {
+        let mut lines = BufReader::new(&reader);
+        let hello = match hello {
+            Some(hello) => hello,
+            None => {
+                let checked = match read_line(&mut lines) {
+                    Ok(Some(line)) => check_hello(&line, &code),
+                    Err(err) => Err(err.to_string()),
+                    Ok(None) => Err("The host closed the connection. Check the room code and \
+                                 that both of you run the same TuxSplit version"
+                        .to_owned()),
+                };
+                match checked {
+                    Ok(hello) => hello,
+                    Err(reason) => {
+                        let _ = reader.shutdown(Shutdown::Both);
+                        let _ = incoming_for_reader.send(NetEvent::Disconnected(reason));
+                        return;
+                    }
+                }
+            }
+        };
+        info!("Race connection established with {peer}");
+        let _ = incoming_for_reader.send(NetEvent::Connected(peer));
+        let _ = incoming_for_reader.send(NetEvent::Message(hello));
+
+        loop {
+            match read_line(&mut lines) {
+                Ok(Some(line)) => {
+                    if let Some(message) = RaceMessage::decode(&line) {
+                        let _ = incoming_for_reader.send(NetEvent::Message(message));
+                    }
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    let _ = incoming_for_reader.send(NetEvent::Disconnected(err.to_string()));
+                    return;
+                }
+            }
+        }
+        let _ = incoming_for_reader.send(NetEvent::Disconnected("Opponent left".to_owned()));
+    });
+
+    let mut writer = stream;
+    for message in pending {
+        if writeln!(writer, "{}", message.encode()).is_err() {
+            let _ = writer.shutdown(Shutdown::Both);
+            return;
+        }
+    }
+    loop {
+        match outgoing.recv() {
+            Ok(message) => {
+                if let Err(err) = writeln!(writer, "{}", message.encode()) {
+                    warn!("Race connection lost: {err}");
+                    break;
+                }
+            }
+            // The session was left
+            Err(_) => break,
+        }
+        // Send everything queued in one go
+        loop {
+            match outgoing.try_recv() {
+                Ok(message) => {
+                    if writeln!(writer, "{}", message.encode()).is_err() {
+                        break;
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    let _ = writer.shutdown(Shutdown::Both);
+                    return;
+                }
+            }
+        }
+    }
+    let _ = writer.shutdown(Shutdown::Both);
+}
+
+#[cfg(test)]
+mod net_tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn wait_for(connection: &RaceConnection, wanted: impl Fn(&NetEvent) -> bool) -> Vec<NetEvent> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut seen = Vec::new();
+        while Instant::now() < deadline {
+            seen.extend(connection.poll());
+            if seen.iter().any(&wanted) {
+                return seen;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("timed out, got {seen:?}");
+    }
+
+    fn free_port() -> u16 {
+        // Grab a free port, then release it for the host
+        TcpListener::bind(("127.0.0.1", 0))
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port()
+    }
+
+    fn hello(code: &str) -> RaceMessage {
+        RaceMessage::Hello {
+            version: PROTOCOL_VERSION,
+            code: code.to_owned(),
+            name: String::new(),
+        }
+    }
+
+    #[test]
+    fn messages_flow_both_ways_and_leaving_disconnects() {
+        let port = free_port();
+        let host_side = host(port, false, "ROOM42".to_owned()).unwrap();
+        let join_side = join(format!("127.0.0.1:{port}"), "ROOM42".to_owned());
+
+        join_side.send(hello("ROOM42"));
+        join_side.send(RaceMessage::Start);
+        host_side.send(hello("ROOM42"));
+        host_side.send(RaceMessage::Split {
+            index: 0,
+            millis: 1234,
+        });
+
+        let on_host = wait_for(&host_side, |e| e == &NetEvent::Message(RaceMessage::Start));
+        assert!(on_host.iter().any(|e| matches!(e, NetEvent::Connected(_))));
+        wait_for(&join_side, |e| {
+            e == &NetEvent::Message(RaceMessage::Split {
+                index: 0,
+                millis: 1234,
+            })
+        });
+
+        drop(host_side);
+        wait_for(&join_side, |e| matches!(e, NetEvent::Disconnected(_)));
+    }
+
+    #[test]
+    fn wrong_room_codes_are_turned_away() {
+        let port = free_port();
+        let host_side = host(port, false, "ROOM42".to_owned()).unwrap();
+        host_side.send(hello("ROOM42"));
+
+        let stranger = join(format!("127.0.0.1:{port}"), "GUESS1".to_owned());
+        stranger.send(hello("GUESS1"));
+        let seen = wait_for(&stranger, |e| matches!(e, NetEvent::Disconnected(_)));
+        assert!(!seen.iter().any(|e| matches!(e, NetEvent::Message(_))));
+
+        // The host is still waiting for the right opponent
+        let join_side = join(format!("127.0.0.1:{port}"), "ROOM42".to_owned());
+        join_side.send(hello("ROOM42"));
+        wait_for(&join_side, |e| e == &NetEvent::Message(hello("ROOM42")));
+        assert!(
+            host_side
+                .poll()
+                .iter()
+                .all(|e| !matches!(e, NetEvent::Disconnected(_)))
+        );
+    }
+
+    #[test]
+    fn hellos_must_match_version_and_code() {
+        assert_eq!(check_hello(&hello("ABC").encode(), "ABC"), Ok(hello("ABC")));
+        let older = RaceMessage::Hello {
+            version: PROTOCOL_VERSION - 1,
+            code: "ABC".to_owned(),
+            name: String::new(),
+        };
+        assert!(
+            check_hello(&older.encode(), "ABC")
+                .unwrap_err()
+                .contains("protocol")
+        );
+        assert!(check_hello(&hello("ABD").encode(), "ABC").is_err());
+        assert!(check_hello("START", "ABC").is_err());
+    }
+
+    #[test]
+    fn overlong_lines_are_refused() {
+        let mut lines = io::Cursor::new(format!("START\n{}\n", "9".repeat(MAX_LINE_LEN + 1)));
+        assert_eq!(read_line(&mut lines).unwrap().as_deref(), Some("START\n"));
+        assert!(read_line(&mut lines).is_err());
+
+        let exact = format!("{}\n", "9".repeat(MAX_LINE_LEN));
+        let mut lines = io::Cursor::new(exact.clone());
+        assert_eq!(read_line(&mut lines).unwrap(), Some(exact));
+        assert_eq!(read_line(&mut lines).unwrap(), None);
+    }
+
+    #[test]
+    fn failed_join_reports_disconnect() {
+        let join_side = join(format!("127.0.0.1:{}", free_port()), "ROOM42".to_owned());
+        wait_for(&join_side, |e| matches!(e, NetEvent::Disconnected(_)));
+    }
+}
@@ -0,0 +1,757 @@
+//! Client for racetime.gg race rooms.
+//!
+//! The room's websocket sends `race.data` frames whenever the race changes.
+//! We only need a few fields of them: the status, the entrant counts and,
+//! once the countdown begins, `started_at`. Every frame also carries the
+//! server clock in `date`, so the time left until the start is computed on
+//! the server clock and doesn't depend on how far off the local clock is.
+//!
+//! Like `net`, the connection lives on a background thread and talks to the
+//! main loop through channels. It reconnects with a growing delay until the
+//! client is dropped.
+
+use std::io;
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+use serde_json::json;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use tracing::{info, warn};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::http::header::AUTHORIZATION;
+use tungstenite::http::{HeaderValue, Uri};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+pub const DEFAULT_HOST: &str = "racetime.gg";
+/// Joining later than this after the start doesn't start the timer.
+pub const MAX_LATE_START: Duration = Duration::from_secs(10);
+
+const READ_POLL: Duration = Duration::from_millis(100);
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RaceState {
+    Open,
+    Invitational,
+    Pending,
+    InProgress,
+    Finished,
+    Cancelled,
+    #[default]
+    #[serde(other)]
+    Unknown,
+}
+
+impl RaceState {
+    pub fn label(self) -> &'static str {
+        match self {
+            RaceState::Open => "Open",
+            RaceState::Invitational => "Invitational",
+            RaceState::Pending => "Starting",
+            RaceState::InProgress => "In progress",
+            RaceState::Finished => "Finished",
+            RaceState::Cancelled => "Cancelled",
+            RaceState::Unknown => "Unknown",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusField {
+    value: RaceState,
+}
+
+#[derive(Debug, Deserialize)]
+struct RaceData {
+    status: StatusField,
+    #[serde(default)]
+    entrants_count: u32,
+    #[serde(default)]
+    entrants_count_finished: u32,
+    started_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum ServerFrame {
+    #[serde(rename = "race.data")]
+    RaceData {
+        race: RaceData,
+        date: Option<String>,
+    },
+    #[serde(rename = "error")]
+    Error { errors: Vec<String> },
+    #[serde(other)]
+    Other,
+}
+
+/// What the header indicator shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoomStatus {
+    pub state: RaceState,
+    pub entrants: u32,
+    pub finished: u32,
+}
+
+impl RoomStatus {
+    /// "In progress · 1/4 done", "Open · 3 entrants".
+    pub fn summary(&self) -> String {
+        match self.state {
+            RaceState::InProgress | RaceState::Finished => format!(
+                "{} · {}/{} done",
+                self.state.label(),
+                self.finished,
+                self.entrants
+            ),
+            _ => format!("{} · {} entrants", self.state.label(), self.entrants),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RacetimeEvent {
+    Connected,
+    Status(RoomStatus),
+    /// The countdown started: start the timer at `at`.
+    Begin {
+        at: Instant,
+    },
+    /// The race started `by` before the frame announcing it was received
+    /// at `received`: start the timer now, as that much earlier.
+    Late {
+        by: Duration,
+        received: Instant,
+    },
+    /// The countdown was stopped before the race started.
+    StartCancelled,
+    /// The room refused an action, with its reasons.
+    Error(String),
+    /// The connection failed or was closed. A reconnect follows.
+    Disconnected(String),
+}
+
+fn parse_date(input: &str) -> Option<OffsetDateTime> {
+    OffsetDateTime::parse(input, &Rfc3339).ok()
+}
+
+/// Turns room frames into events, remembering which start was announced.
+#[derive(Debug, Default)]
+pub struct RoomTracker {
+    announced_start: Option<OffsetDateTime>,
+}
+
+impl RoomTracker {
+    /// Events for one text frame, received at `received`. Frames we don't
+    /// care about yield none.
+    pub fn handle(&mut self, frame: &str, received: Instant) -> Vec<RacetimeEvent> {
+        let frame: ServerFrame = match serde_json::from_str(frame) {
+            Ok(frame) => frame,
+            Err(err) => {
+                warn!("Ignoring racetime frame: {err}");
+                return Vec::new();
+            }
+        };
+        match frame {
+            ServerFrame::RaceData { race, date } => {
+                self.race_data(&race, date.as_deref(), received)
+            }
+            ServerFrame::Error { errors } => vec![RacetimeEvent::Error(errors.join(" "))],
+            ServerFrame::Other => Vec::new(),
+        }
+    }
+
+    fn race_data(
+        &mut self,
+        race: &RaceData,
+        date: Option<&str>,
+        received: Instant,
+    ) -> Vec<RacetimeEvent> {
+        let state = race.status.value;
+        let mut events = vec![RacetimeEvent::Status(RoomStatus {
+            state,
+            entrants: race.entrants_count,
+            finished: race.entrants_count_finished,
+        })];
+
+        let started_at = race.started_at.as_deref().and_then(parse_date);
+        match (state, started_at) {
+            (RaceState::Pending | RaceState::InProgress, Some(started_at))
+                if self.announced_start != Some(started_at) =>
+            {
+                self.announced_start = Some(started_at);
+                // The server clock when the frame was sent, or ours as a fallback
+                let now = date
+                    .and_then(parse_date)
+                    .unwrap_or_else(OffsetDateTime::now_utc);
+                let until_start = started_at - now;
+                if until_start.is_positive() {
+                    // Scheduled from when the frame came in, not when it's polled
+                    events.push(RacetimeEvent::Begin {
+                        at: received + until_start.unsigned_abs(),
+                    });
+                } else if until_start.unsigned_abs() <= MAX_LATE_START {
+                    events.push(RacetimeEvent::Late {
+                        by: until_start.unsigned_abs(),
+                        received,
+                    });
+                } else {
+                    info!(
+                        "Joined a race that started {}s ago, not starting",
+                        until_start.unsigned_abs().as_secs()
+                    );
+                }
+            }
+            (RaceState::Open | RaceState::Invitational | RaceState::Cancelled, _) => {
+                if self.announced_start.take().is_some() {
+                    events.push(RacetimeEvent::StartCancelled);
+                }
+            }
+            _ => {}
+        }
+        events
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RacetimeCommand {
+    /// Finish the race, with our final time for the room to see.
+    Done {
+        final_time: String,
+    },
+    Forfeit,
+}
+
+impl RacetimeCommand {
+    /// The chat messages sending this command.
+    pub fn messages(&self) -> Vec<String> {
+        match self {
+            RacetimeCommand::Done { final_time } => vec![
+                ".done".to_owned(),
+                format!(".comment Finished in {final_time}"),
+            ],
+            RacetimeCommand::Forfeit => vec![".forfeit".to_owned()],
+        }
+    }
+
+    /// The frames to send. Every chat message needs a unique `guid` so the
+    /// room can drop duplicates after a reconnect.
+    pub fn encode(&self, guid: &mut impl FnMut() -> String) -> Vec<String> {
+        self.messages()
+            .into_iter()
+            .map(|message| {
+                json!({
+                    "action": "message",
+                    "data": { "message": message, "guid": guid() },
+                })
+                .to_string()
+            })
+            .collect()
+    }
+}
+
+fn next_guid() -> String {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+    format!(
+        "tuxsplit-{nanos:x}-{}",
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// The websocket URL for a room given as a slug ("smw/cool-pika-1234") or a
+/// room URL. `ws://` and `wss://` URLs are used as they are, but only get
+/// the token when they point to racetime.gg over `wss://`, see
+/// [`may_carry_token`]. Rooms are joined through the OAuth endpoint when a
+/// token is configured, since chat commands need a signed in user.
+pub fn room_ws_url(room: &str, authenticated: bool) -> Option<String> {
+    let room = room.trim();
+    if room.starts_with("ws://") || room.starts_with("wss://") {
+        return Some(room.to_owned());
+    }
+    let path = room
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_start_matches(DEFAULT_HOST)
+        .trim_matches('/');
+    let mut parts = path.split('/').filter(|part| !part.is_empty());
+    let (category, slug) = (parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+    let endpoint = if authenticated {
+        "ws/o/race"
+    } else {
+        "ws/race"
+    };
+    Some(format!("wss://{DEFAULT_HOST}/{endpoint}/{category}/{slug}"))
+}
+
+/// Whether the OAuth token may be sent to `uri`. Anything but racetime.gg
+/// over TLS could be a typo or a pasted link and would leak the token.
+pub fn may_carry_token(uri: &Uri) -> bool {
+    uri.scheme_str() == Some("wss") && uri.host() == Some(DEFAULT_HOST)
+}
+
+/// Delay before the next reconnect, doubling up to a minute.
+#[derive(Debug)]
+pub struct Backoff {
+    next: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            next: INITIAL_BACKOFF,
+        }
+    }
+}
+
+impl Backoff {
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = (self.next * 2).min(MAX_BACKOFF);
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        self.next = INITIAL_BACKOFF;
+    }
+}
+
+pub struct RacetimeClient {
+    commands: Sender<RacetimeCommand>,
+    events: Receiver<RacetimeEvent>,
+}
+
+impl RacetimeClient {
+    /// Queues a command. Commands sent while reconnecting go out once the
+    /// room is back.
+    pub fn send(&self, command: RacetimeCommand) {
+        let _ = self.commands.send(command);
+    }
+
+    /// Events received since the last call.
+    pub fn poll(&self) -> Vec<RacetimeEvent> {
+        self.events.try_iter().collect()
+    }
+}
+
+enum SessionEnd {
+    /// The connection was lost, with a reason for the user.
+    Lost(String),
+    /// The client was dropped.
+    Dropped,
+}
+
+/// Connects to the room at the websocket `url` and keeps reconnecting until
+/// the client is dropped.
+pub fn connect(url: String, token: Option<String>) -> RacetimeClient {
+    let (commands, commands_rx) = mpsc::channel();
+    let (events_tx, events) = mpsc::channel();
+    thread::spawn(move || {
+        let mut backoff = Backoff::default();
+        let mut pending = Vec::new();
+        loop {
+            match run_session(
+                &url,
+                token.as_deref(),
+                &mut pending,
+                &commands_rx,
+                &events_tx,
+                &mut backoff,
+            ) {
+                SessionEnd::Dropped => return,
+                SessionEnd::Lost(reason) => {
+                    if events_tx.send(RacetimeEvent::Disconnected(reason)).is_err() {
+                        return;
+                    }
+                }
+            }
+            let delay = backoff.next_delay();
+            info!("Reconnecting to racetime.gg in {}s", delay.as_secs());
+            match commands_rx.recv_timeout(delay) {
+                Ok(command) => pending.push(command),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    });
+
+    RacetimeClient { commands, events }
+}
+
+fn set_read_timeout(socket: &WebSocket<MaybeTlsStream<TcpStream>>, timeout: Duration) {
+    let stream = match socket.get_ref() {
+        MaybeTlsStream::Plain(stream) => stream,
+        MaybeTlsStream::Rustls(stream) => stream.get_ref(),
+        _ => return,
+    };
+    let _ = stream.set_read_timeout(Some(timeout));
+}
+
+fn send_command(
+    socket: &mut WebSocket<MaybeTlsStream<TcpStream>>,
+    command: &RacetimeCommand,
+) -> tungstenite::Result<()> {
+    for frame in command.encode(&mut next_guid) {
+        socket.send(Message::text(frame))?;
+    }
+    Ok(())
+}
+
+fn run_session(
+    url: &str,
+    token: Option<&str>,
+    pending: &mut Vec<RacetimeCommand>,
+    commands: &Receiver<RacetimeCommand>,
+    events: &Sender<RacetimeEvent>,
+    backoff: &mut Backoff,
+) -> SessionEnd {
+    let mut request = match url.into_client_request() {
+        Ok(request) => request,
+        Err(err) => return SessionEnd::Lost(err.to_string()),
+    };
+    if let Some(token) = token {
+        if !may_carry_token(request.uri()) {
+            warn!("Joining {url} without the racetime.gg token, it only goes to {DEFAULT_HOST}");
+        } else if let Ok(value) = HeaderValue::from_str(&format!("Bearer {token}")) {
+            request.headers_mut().insert(AUTHORIZATION, value);
+        }
+    }
+    let mut socket = match tungstenite::connect(request) {
+        Ok((socket, _)) => socket,
+        Err(err) => return SessionEnd::Lost(err.to_string()),
+    };
+    info!("Connected to racetime.gg room {url}");
+    set_read_timeout(&socket, READ_POLL);
+    backoff.reset();
+    if events.send(RacetimeEvent::Connected).is_err() {
+        return SessionEnd::Dropped;
+    }
+
+    // A new connection gets a fresh race.data, so starts are announced again
+    let mut tracker = RoomTracker::default();
+    loop {
+        loop {
+            let command = if pending.is_empty() {
+                match commands.try_recv() {
+                    Ok(command) => command,
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        let _ = socket.close(None);
+                        return SessionEnd::Dropped;
+                    }
+                }
+            } else {
+                pending.remove(0)
+            };
+            if let Err(err) = send_command(&mut socket, &command) {
+                pending.insert(0, command);
+                return SessionEnd::Lost(err.to_string());
+            }
+        }
+
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                for event in tracker.handle(text.as_str(), Instant::now()) {
+                    if events.send(event).is_err() {
+                        let _ = socket.close(None);
+                        return SessionEnd::Dropped;
+                    }
+                }
+            }
+            Ok(Message::Close(_)) | Err(tungstenite::Error::ConnectionClosed) => {
+                return SessionEnd::Lost("The room closed the connection".to_owned());
+            }
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(err))
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) => {}
+            Err(err) => return SessionEnd::Lost(err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod racetime_tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::sync::OnceLock;
+
+    const OPEN: &str = include_str!("fixtures/racetime/race_data_open.json");
+    const PENDING: &str = include_str!("fixtures/racetime/race_data_pending.json");
+    const IN_PROGRESS: &str = include_str!("fixtures/racetime/race_data_in_progress.json");
+    const CHAT: &str = include_str!("fixtures/racetime/chat_message.json");
+    const ERROR: &str = include_str!("fixtures/racetime/error.json");
+
+    /// When the frames were received, the same for every call.
+    fn received() -> Instant {
+        static RECEIVED: OnceLock<Instant> = OnceLock::new();
+        *RECEIVED.get_or_init(Instant::now)
+    }
+
+    fn status(state: RaceState, entrants: u32, finished: u32) -> RacetimeEvent {
+        RacetimeEvent::Status(RoomStatus {
+            state,
+            entrants,
+            finished,
+        })
+    }
+
+    #[test]
+    fn open_room_reports_status_only() {
+        let mut tracker = RoomTracker::default();
+        assert_eq!(
+            tracker.handle(OPEN, received()),
+            vec![status(RaceState::Open, 3, 0)]
+        );
+    }
+
+    #[test]
+    fn countdown_begins_on_server_clock() {
+        let mut tracker = RoomTracker::default();
+        // started_at 19:15:30.000, frame sent at 19:15:15.250
+        assert_eq!(
+            tracker.handle(PENDING, received()),
+            vec![
+                status(RaceState::Pending, 4, 0),
+                RacetimeEvent::Begin {
+                    at: received() + Duration::from_millis(14_750)
+                },
+            ]
+        );
+        // The same start isn't announced twice
+        assert_eq!(
+            tracker.handle(IN_PROGRESS, received()),
+            vec![status(RaceState::InProgress, 4, 1)]
+        );
+    }
+
+    #[test]
+    fn joining_just_after_the_start_is_late() {
+        let mut tracker = RoomTracker::default();
+        assert_eq!(
+            tracker.handle(IN_PROGRESS, received()),
+            vec![
+                status(RaceState::InProgress, 4, 1),
+                RacetimeEvent::Late {
+                    by: Duration::from_millis(2_500),
+                    received: received(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn joining_long_after_the_start_does_not_start() {
+        let mut tracker = RoomTracker::default();
+        let frame = IN_PROGRESS.replace("19:15:32.500Z", "19:45:00.000Z");
+        assert_eq!(
+            tracker.handle(&frame, received()),
+            vec![status(RaceState::InProgress, 4, 1)]
+        );
+    }
+
+    #[test]
+    fn stopped_countdown_cancels_the_start() {
+        let mut tracker = RoomTracker::default();
+        assert_eq!(tracker.handle(PENDING, received()).len(), 2);
+        assert_eq!(
+            tracker.handle(OPEN, received()),
+            vec![status(RaceState::Open, 3, 0), RacetimeEvent::StartCancelled]
+        );
+        assert_eq!(
+            tracker.handle(OPEN, received()),
+            vec![status(RaceState::Open, 3, 0)]
+        );
+    }
+
+    #[test]
+    fn chat_and_garbage_are_ignored_and_errors_reported() {
+        let mut tracker = RoomTracker::default();
+        assert!(tracker.handle(CHAT, received()).is_empty());
+        assert!(tracker.handle("not json", received()).is_empty());
+        assert!(tracker.handle(r#"{"type": "pong"}"#, received()).is_empty());
+        assert_eq!(
+            tracker.handle(ERROR, received()),
+            vec![RacetimeEvent::Error(
+                "You are not an entrant in this race.".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn unknown_states_still_parse() {
+        let mut tracker = RoomTracker::default();
+        let frame = OPEN.replace(r#""value": "open""#, r#""value": "paused""#);
+        assert_eq!(
+            tracker.handle(&frame, received()),
+            vec![status(RaceState::Unknown, 3, 0)]
+        );
+    }
+
+    #[test]
+    fn commands_are_chat_messages_with_unique_guids() {
+        let mut counter = 0;
+        let mut guid = || {
+            counter += 1;
+            format!("g{counter}")
+        };
+        let frames = RacetimeCommand::Done {
+            final_time: "1:02:03.45".to_owned(),
+        }
+        .encode(&mut guid);
+        let frames: Vec<serde_json::Value> = frames
+            .iter()
+            .map(|frame| serde_json::from_str(frame).unwrap())
+            .collect();
+        assert_eq!(frames[0]["action"], "message");
+        assert_eq!(frames[0]["data"]["message"], ".done");
+        assert_eq!(frames[0]["data"]["guid"], "g1");
+        assert_eq!(
+            frames[1]["data"]["message"],
+            ".comment Finished in 1:02:03.45"
+        );
+        assert_eq!(frames[1]["data"]["guid"], "g2");
+
+        assert_eq!(RacetimeCommand::Forfeit.messages(), vec![".forfeit"]);
+    }
+
+    #[test]
+    fn room_urls_from_slugs_and_links() {
+        let expected = Some("wss://racetime.gg/ws/race/smw/cool-pika-1234".to_owned());
+        assert_eq!(room_ws_url("smw/cool-pika-1234", false), expected);
+        assert_eq!(
+            room_ws_url("https://racetime.gg/smw/cool-pika-1234/", false),
+            expected
+        );
+        assert_eq!(
+            room_ws_url("racetime.gg/smw/cool-pika-1234", false),
+            expected
+        );
+        assert_eq!(
+            room_ws_url("smw/cool-pika-1234", true).as_deref(),
+            Some("wss://racetime.gg/ws/o/race/smw/cool-pika-1234")
+        );
+        assert_eq!(
+            room_ws_url("ws://127.0.0.1:9000/room", true).as_deref(),
+            Some("ws://127.0.0.1:9000/room")
+        );
+        assert_eq!(room_ws_url("cool-pika-1234", false), None);
+        assert_eq!(room_ws_url("smw/cool-pika-1234/extra", false), None);
+    }
+
+    #[test]
+    fn token_only_goes_to_racetime_over_tls() {
+        let allowed = |url: &str| may_carry_token(&url.parse().unwrap());
+        assert!(allowed("wss://racetime.gg/ws/o/race/smw/cool-pika-1234"));
+        assert!(!allowed("ws://racetime.gg/ws/o/race/smw/cool-pika-1234"));
+        assert!(!allowed("wss://racetime.gg.example.com/ws/o/race/smw/room"));
+        assert!(!allowed("wss://example.com/racetime.gg"));
+        assert!(!allowed("ws://127.0.0.1:9000/room"));
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_a_minute() {
+        let mut backoff = Backoff::default();
+        let delays: Vec<u64> = (0..8).map(|_| backoff.next_delay().as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), INITIAL_BACKOFF);
+    }
+
+    #[test]
+    fn summary_reads_naturally() {
+        let open = RoomStatus {
+            state: RaceState::Open,
+            entrants: 3,
+            finished: 0,
+        };
+        assert_eq!(open.summary(), "Open · 3 entrants");
+        let running = RoomStatus {
+            state: RaceState::InProgress,
+            entrants: 4,
+            finished: 1,
+        };
+        assert_eq!(running.summary(), "In progress · 1/4 done");
+    }
+
+    fn wait_for(
+        client: &RacetimeClient,
+        wanted: impl Fn(&RacetimeEvent) -> bool,
+    ) -> Vec<RacetimeEvent> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut seen = Vec::new();
+        while Instant::now() < deadline {
+            seen.extend(client.poll());
+            if seen.iter().any(&wanted) {
+                return seen;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("timed out, got {seen:?}");
+    }
+
+    #[test]
+    fn mock_room_synchronizes_start_and_receives_commands() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (received_tx, received) = mpsc::channel();
+        let server = thread::spawn(move || {
+            // First connection: the countdown, then the room goes away
+            let (stream, _) = listener.accept().unwrap();
+            let mut socket = tungstenite::accept(stream).unwrap();
+            socket.send(Message::text(OPEN)).unwrap();
+            socket.send(Message::text(PENDING)).unwrap();
+            let _ = socket.close(None);
+            while socket.read().is_ok() {}
+
+            // Reconnected: commands queued meanwhile arrive
+            let (stream, _) = listener.accept().unwrap();
+            let mut socket = tungstenite::accept(stream).unwrap();
+            socket.send(Message::text(IN_PROGRESS)).unwrap();
+            loop {
+                match socket.read() {
+                    Ok(Message::Text(text)) => {
+                        let _ = received_tx.send(text.as_str().to_owned());
+                    }
+                    Ok(_) => {}
+                    Err(_) => return,
+                }
+            }
+        });
+
+        let client = connect(format!("ws://127.0.0.1:{port}/ws/race/smw/room"), None);
+        let seen = wait_for(&client, |e| matches!(e, RacetimeEvent::Begin { .. }));
+        assert_eq!(seen[0], RacetimeEvent::Connected);
+        assert!(seen.iter().any(|event| matches!(
+            event,
+            RacetimeEvent::Begin { at } if *at > Instant::now()
+        )));
+
+        wait_for(&client, |e| matches!(e, RacetimeEvent::Disconnected(_)));
+        client.send(RacetimeCommand::Forfeit);
+        // A fresh connection announces the start again, now late
+        let seen = wait_for(&client, |e| matches!(e, RacetimeEvent::Late { .. }));
+        assert!(seen.contains(&RacetimeEvent::Connected));
+
+        let frame = received.recv_timeout(Duration::from_secs(10)).unwrap();
+        let frame: serde_json::Value = serde_json::from_str(&frame).unwrap();
+        assert_eq!(frame["data"]["message"], ".forfeit");
+
+        drop(client);
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,82 @@
+//! Fixtures shared by the unit tests.
+
+use std::fs;
+use std::path::PathBuf;
+
+use livesplit_core::{Run, Segment, Time, TimeSpan, Timer, TimingMethod};
+
+/// An empty `tuxsplit-<name>-<pid>` directory in the temp directory. Tests
+/// run in parallel, so each picks its own `name`.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tuxsplit-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// `seconds` of real time.
+pub fn real_time(seconds: f64) -> Time {
+    Time::new().with_real_time(Some(TimeSpan::from_seconds(seconds)))
+}
+
+/// `seconds` of game time.
+pub fn game_time(seconds: f64) -> Time {
+    Time::new().with_game_time(Some(TimeSpan::from_seconds(seconds)))
+}
+
+/// `seconds` of both real and game time.
+pub fn both_times(seconds: f64) -> Time {
+    let span = Some(TimeSpan::from_seconds(seconds));
+    Time::new().with_real_time(span).with_game_time(span)
+}
+
+fn time(method: TimingMethod, seconds: f64) -> Time {
+    match method {
+        TimingMethod::RealTime => real_time(seconds),
+        TimingMethod::GameTime => game_time(seconds),
+    }
+}
+
+/// A run with a segment per `(name, pb, gold)`, with the personal best
+/// split and the gold in seconds of `method` where there is one.
+pub fn run_with(method: TimingMethod, segments: &[(&str, Option<f64>, Option<f64>)]) -> Run {
+    let mut run = Run::new();
+    for &(name, pb, gold) in segments {
+        let mut segment = Segment::new(name);
+        if let Some(pb) = pb {
+            segment.set_personal_best_split_time(time(method, pb));
+        }
+        if let Some(gold) = gold {
+            segment.set_best_segment_time(time(method, gold));
+        }
+        run.push_segment(segment);
+    }
+    run
+}
+
+/// A timer on [`run_with`]`(method, segments)`, timing with `method`.
+pub fn make_timer(method: TimingMethod, segments: &[(&str, Option<f64>, Option<f64>)]) -> Timer {
+    let mut timer = Timer::new(run_with(method, segments)).expect("timer");
+    timer.set_current_timing_method(method);
+    timer
+}
+
+/// A run on segments named `names`, without any times.
+pub fn run_on(names: &[&str]) -> Run {
+    let mut run = Run::new();
+    for name in names {
+        run.push_segment(Segment::new(*name));
+    }
+    run
+}
+
+/// A timer on [`run_on`]`(names)`.
+pub fn timer_on(names: &[&str]) -> Timer {
+    Timer::new(run_on(names)).expect("timer")
+}
+
+/// Splits `timer` at `seconds` of game time.
+pub fn split_at(timer: &mut Timer, seconds: f64) {
+    timer.set_game_time(TimeSpan::from_seconds(seconds));
+    timer.split();
+}
@@ -0,0 +1,203 @@
+//! "Adjust Times", correcting the attempt that just ended before it's
+//! recorded. How corrections move the other splits and how they're recorded
+//! is in [`crate::utils::attempt_edit`].
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use adw::prelude::*;
+use adw::{ActionRow, AlertDialog, ApplicationWindow};
+use gtk4::{Align, Button, Entry, ListBox, ScrolledWindow};
+use livesplit_core::comparison::personal_best;
+use tracing::info;
+
+use crate::context::TuxSplitContext;
+use crate::formatters::TimeFormat;
+use crate::formatters::time::parse_hms;
+use crate::ui::drill::drill_active;
+use crate::ui::timer::modal::show_notice;
+use crate::utils::attempt_edit::AttemptEdit;
+
+/// Split times in the dialog, every unit and to the millisecond, so they
+/// read back exactly.
+fn entry_format() -> TimeFormat {
+    TimeFormat::new(true, true, true, true, 3, false)
+}
+
+/// Shows the rows' times as they are in `edit`, with the measured time of
+/// the corrected ones.
+fn refresh(edit: &AttemptEdit, rows: &[(ActionRow, Entry)]) {
+    let format = entry_format();
+    for ((row, entry), (recorded, time)) in
+        rows.iter().zip(edit.recorded().iter().zip(edit.times()))
+    {
+        entry.remove_css_class("error");
+        entry.set_text(&time.map_or_else(String::new, |time| format.format_duration(&time)));
+        let subtitle = match recorded {
+            Some(recorded) if Some(*recorded) != *time => {
+                format!("Measured {}", format.format_duration(recorded))
+            }
+            Some(_) => String::new(),
+            None => "Skipped".to_owned(),
+        };
+        row.set_subtitle(&subtitle);
+    }
+}
+
+/// Records the corrected attempt, the way a reset with the splits updated
+/// would.
+fn apply(edit: &AttemptEdit) {
+    let changes = edit.changes();
+    if changes.is_empty() {
+        return;
+    }
+    let ctx = TuxSplitContext::get_instance();
+    let recorded = {
+        let shared = ctx.timer();
+        let timer = shared.read().unwrap();
+        edit.recorded_run(&timer)
+    };
+    let Some((run, new_pb)) = recorded else {
+        show_notice("The attempt changed, its times were left as they were");
+        return;
+    };
+    let format = entry_format();
+    for change in &changes {
+        info!(
+            "Adjusted split {} of the attempt from {} to {}",
+            change.index + 1,
+            format.format_duration(&change.before),
+            format.format_duration(&change.after)
+        );
+    }
+    let switch_to_pb = ctx.config().behavior.switch_to_pb_after_pb;
+    if let Err(err) = ctx.set_run(run) {
+        show_notice(&format!("The attempt was not recorded: {err}"));
+        return;
+    }
+    if new_pb && switch_to_pb {
+        let shared = ctx.timer();
+        let _ = shared
+            .write()
+            .unwrap()
+            .set_current_comparison(personal_best::NAME);
+    }
+    show_notice(&match changes.len() {
+        1 => "Recorded the attempt with 1 adjusted split".to_owned(),
+        count => format!("Recorded the attempt with {count} adjusted splits"),
+    });
+}
+
+/// Opens the times of the attempt that just ended for correction. Returns
+/// why it can't.
+pub fn present_attempt_editor(window: &ApplicationWindow) -> Result<(), String> {
+    if drill_active() {
+        return Err("Drill reps are not attempts and have no times to adjust.".to_owned());
+    }
+    let ctx = TuxSplitContext::get_instance();
+    let (later, step) = {
+        let config = ctx.config();
+        (
+            config.behavior.adjust_later_splits,
+            time::Duration::milliseconds(i64::from(config.behavior.adjust_step_ms.max(1))),
+        )
+    };
+    let (edit, names) = {
+        let shared = ctx.timer();
+        let timer = shared.read().unwrap();
+        let names: Vec<String> = timer
+            .run()
+            .segments()
+            .iter()
+            .map(|segment| segment.name().to_owned())
+            .collect();
+        (AttemptEdit::from_timer(&timer, later), names)
+    };
+    let edit = Rc::new(RefCell::new(edit.ok_or_else(|| {
+        "Times can only be adjusted once the attempt has ended.".to_owned()
+    })?));
+
+    let dialog = AlertDialog::builder()
+        .heading("Adjust Times")
+        .body("Applying records the attempt with these times, golds and personal best included.")
+        .default_response("apply")
+        .close_response("cancel")
+        .build();
+    dialog.add_response("cancel", "Cancel");
+    dialog.add_response("apply", "Apply");
+    dialog.set_response_appearance("apply", adw::ResponseAppearance::Suggested);
+
+    let list = ListBox::new();
+    list.add_css_class("boxed-list");
+    let rows: Rc<Vec<(ActionRow, Entry)>> = Rc::new(
+        names
+            .iter()
+            .map(|name| {
+                let row = ActionRow::builder().title(name).build();
+                let entry = Entry::builder()
+                    .width_chars(11)
+                    .valign(Align::Center)
+                    .build();
+                entry.add_css_class("monospace");
+                (row, entry)
+            })
+            .collect(),
+    );
+    let step_text = format!("{:.2}s", step.as_seconds_f64());
+    for (index, (row, entry)) in rows.iter().enumerate() {
+        let skipped = edit.borrow().times()[index].is_none();
+        entry.set_sensitive(!skipped);
+        let nudge_button = |icon: &str, delta: time::Duration, tooltip: String| {
+            let button = Button::builder()
+                .icon_name(icon)
+                .tooltip_text(tooltip)
+                .valign(Align::Center)
+                .sensitive(!skipped)
+                .build();
+            button.add_css_class("flat");
+            let edit = edit.clone();
+            let rows = rows.clone();
+            button.connect_clicked(move |_| {
+                if edit.borrow_mut().nudge(index, delta) {
+                    refresh(&edit.borrow(), &rows);
+                }
+            });
+            button
+        };
+        row.add_suffix(&nudge_button(
+            "list-remove-symbolic",
+            -step,
+            format!("−{step_text}"),
+        ));
+        row.add_suffix(entry);
+        row.add_suffix(&nudge_button(
+            "list-add-symbolic",
+            step,
+            format!("+{step_text}"),
+        ));
+
+        let edit = edit.clone();
+        let rows_for_entry = rows.clone();
+        entry.connect_activate(move |entry| match parse_hms(entry.text().trim()) {
+            Ok(time) => {
+                edit.borrow_mut().set(index, time);
+                refresh(&edit.borrow(), &rows_for_entry);
+            }
+            Err(_) => entry.add_css_class("error"),
+        });
+        list.append(row);
+    }
+    refresh(&edit.borrow(), &rows);
+
+    let scrolled = ScrolledWindow::builder()
+        .propagate_natural_height(true)
+        .max_content_height(420)
+        .min_content_width(380)
+        .child(&list)
+        .build();
+    dialog.set_extra_child(Some(&scrolled));
+
+    dialog.connect_response(Some("apply"), move |_, _| apply(&edit.borrow()));
+    dialog.present(Some(window));
+    Ok(())
+}
@@ -0,0 +1,41 @@
+//! Offering the report of the last crash, see [`crate::utils::crash_report`].
+
+use adw::prelude::*;
+use adw::{AlertDialog, ApplicationWindow};
+use gtk4::{FileLauncher, gio};
+use tracing::warn;
+
+use crate::utils::crash_report::{crash_report_dir, take_pending_report};
+
+/// Asks to open the crash report written since the last start, if any.
+pub fn offer_crash_report(window: &ApplicationWindow) {
+    let Some(path) = take_pending_report(&crash_report_dir()) else {
+        return;
+    };
+    let dialog = AlertDialog::builder()
+        .heading("TuxSplit Crashed")
+        .body(format!(
+            "TuxSplit closed unexpectedly last time. A crash report with what the timer \
+             showed was saved to {}. Attaching it to a bug report helps find the cause.",
+            path.display()
+        ))
+        .default_response("open")
+        .close_response("close")
+        .build();
+    dialog.add_response("close", "Close");
+    dialog.add_response("open", "Show Report");
+    dialog.set_response_appearance("open", adw::ResponseAppearance::Suggested);
+    dialog.connect_response(Some("open"), move |dialog, _| {
+        let window = dialog.root().and_downcast::<gtk4::Window>();
+        FileLauncher::new(Some(&gio::File::for_path(&path))).open_containing_folder(
+            window.as_ref(),
+            None::<&gio::Cancellable>,
+            |result| {
+                if let Err(err) = result {
+                    warn!("Could not show the crash report: {err}");
+                }
+            },
+        );
+    });
+    dialog.present(Some(window));
+}
@@ -0,0 +1,316 @@
+//! "Detach Timer Display": a second window with only the running timer,
+//! for the capture monitor while the splits stay on the control one.
+//!
+//! The window has no header bar and is dragged from anywhere on the timer.
+//! It draws the frames of the main window's tick through [`subscribe_tick`]
+//! instead of running a tick source of its own, so both always show the same
+//! time. It has its own font scale, closes with the main window and
+//! remembers its size on each monitor. GTK can't move a window to a
+//! position, so it only goes back to its monitor when it was left
+//! fullscreen there; F11 toggles that.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use adw::ApplicationWindow;
+use adw::prelude::*;
+use glib::{ControlFlow, Propagation};
+use gtk4::{
+    Align, Box as GtkBox, CssProvider, EventControllerKey, Orientation,
+    STYLE_PROVIDER_PRIORITY_APPLICATION, WindowHandle,
+    gdk::{self, Display, Monitor},
+};
+use tracing::info;
+
+use crate::config::{Config, DetachedGeometry, DetachedTimerOptions};
+use crate::context::TuxSplitContext;
+use crate::ui::scale::detached_timer_css;
+use crate::ui::timer::footer::RunningTimer;
+use crate::ui::timer::split_name::SplitName;
+use crate::ui::timer::tick_fanout::{SubscriptionId, subscribe_tick, unsubscribe_tick};
+
+thread_local! {
+    static DETACHED: RefCell<Option<DetachedTimer>> = const { RefCell::new(None) };
+    static PROVIDER: RefCell<Option<CssProvider>> = const { RefCell::new(None) };
+}
+
+struct DetachedTimer {
+    window: adw::Window,
+    subscription: SubscriptionId,
+    parent: ApplicationWindow,
+    parent_close: glib::SignalHandlerId,
+}
+
+/// Monitor and size to open with: the monitor the window was last closed on
+/// while it is still connected, else the first connected one it has a size
+/// for, and the size it had there.
+pub fn restore_geometry(
+    options: &DetachedTimerOptions,
+    connected: &[String],
+) -> (Option<String>, DetachedGeometry) {
+    let monitor = options
+        .last_monitor
+        .iter()
+        .chain(connected)
+        .find(|name| connected.contains(name) && options.geometry.contains_key(*name))
+        .cloned();
+    let geometry = monitor
+        .as_ref()
+        .and_then(|name| options.geometry.get(name))
+        .copied()
+        .unwrap_or_default();
+    (monitor, geometry)
+}
+
+/// Records the window's size on `monitor` as it closes. Nothing is kept when
+/// the monitor can't be told.
+pub fn remember_geometry(
+    options: &mut DetachedTimerOptions,
+    monitor: Option<String>,
+    geometry: DetachedGeometry,
+) {
+    let Some(monitor) = monitor else {
+        return;
+    };
+    options.geometry.insert(monitor.clone(), geometry);
+    options.last_monitor = Some(monitor);
+}
+
+/// The window's own setting, or the layout's when it has none.
+fn shows_split_name(config: &Config) -> bool {
+    config
+        .detached_timer
+        .show_current_split_name
+        .unwrap_or(config.layout.show_current_split_name)
+}
+
+fn connector(monitor: &Monitor) -> Option<String> {
+    monitor.connector().map(|name| name.to_string())
+}
+
+fn load_font_css(font_scale: f64) {
+    let Some(display) = Display::default() else {
+        return;
+    };
+    PROVIDER.with_borrow_mut(|provider| {
+        provider
+            .get_or_insert_with(|| {
+                let provider = CssProvider::new();
+                gtk4::style_context_add_provider_for_display(
+                    &display,
+                    &provider,
+                    STYLE_PROVIDER_PRIORITY_APPLICATION + 1,
+                );
+                provider
+            })
+            .load_from_string(&detached_timer_css(font_scale));
+    });
+}
+
+fn remember_window(window: &adw::Window) {
+    let monitor = window
+        .surface()
+        .and_then(|surface| WidgetExt::display(window).monitor_at_surface(&surface))
+        .and_then(|monitor| connector(&monitor));
+    let (width, height) = window.default_size();
+    let geometry = DetachedGeometry {
+        width,
+        height,
+        fullscreen: window.is_fullscreen(),
+    };
+    if let Ok(mut config) = TuxSplitContext::get_instance().config_mut() {
+        remember_geometry(&mut config.detached_timer, monitor, geometry);
+    }
+}
+
+fn restore_window(window: &adw::Window, options: &DetachedTimerOptions) {
+    let monitors: Vec<Monitor> = WidgetExt::display(window)
+        .monitors()
+        .iter::<Monitor>()
+        .filter_map(Result::ok)
+        .collect();
+    let connected: Vec<String> = monitors.iter().filter_map(connector).collect();
+    let (monitor, geometry) = restore_geometry(options, &connected);
+    window.set_default_size(geometry.width, geometry.height);
+    if geometry.fullscreen
+        && let Some(name) = monitor
+        && let Some(monitor) = monitors
+            .iter()
+            .find(|candidate| connector(candidate).as_ref() == Some(&name))
+    {
+        window.fullscreen_on_monitor(monitor);
+    }
+}
+
+/// Opens the detached timer, or brings it to the front when it's open.
+pub fn present_detached_timer(parent: &ApplicationWindow) {
+    if let Some(window) = DETACHED.with_borrow(|open| open.as_ref().map(|d| d.window.clone())) {
+        window.present();
+        return;
+    }
+
+    let ctx = TuxSplitContext::get_instance();
+    let timer = {
+        let shared = ctx.timer();
+        shared.read().unwrap().clone()
+    };
+    let (running, split_name, options) = {
+        let config = ctx.config();
+        let mut split_name = SplitName::new();
+        split_name.update(&timer, &config, shows_split_name(&config));
+        (
+            RunningTimer::new(&timer, &config),
+            split_name,
+            config.detached_timer.clone(),
+        )
+    };
+    let timer_widget = GtkBox::new(Orientation::Vertical, 0);
+    timer_widget.append(split_name.container());
+    timer_widget.append(running.container());
+    timer_widget.set_halign(Align::Center);
+    timer_widget.set_valign(Align::Center);
+    timer_widget.set_hexpand(true);
+    timer_widget.set_vexpand(true);
+    timer_widget.set_margin_start(12);
+    timer_widget.set_margin_end(12);
+
+    let handle = WindowHandle::builder().child(&timer_widget).build();
+    let window = adw::Window::builder()
+        .title("TuxSplit Timer")
+        .content(&handle)
+        .build();
+    window.add_css_class("detached-timer");
+    if let Some(app) = parent.application() {
+        window.set_application(Some(&app));
+    }
+    load_font_css(options.font_scale);
+    restore_window(&window, &options);
+
+    let keys = EventControllerKey::new();
+    keys.connect_key_pressed(|controller, keyval, _, _| {
+        if keyval != gdk::Key::F11 {
+            return Propagation::Proceed;
+        }
+        if let Some(window) = controller.widget().and_downcast::<adw::Window>() {
+            if window.is_fullscreen() {
+                window.unfullscreen();
+            } else {
+                window.fullscreen();
+            }
+        }
+        Propagation::Stop
+    });
+    window.add_controller(keys);
+
+    let running = Rc::new(RefCell::new(running));
+    let mut split_name = split_name;
+    let weak_window = window.downgrade();
+    let mut font_scale = options.font_scale;
+    let subscription = subscribe_tick(move |timer, config| {
+        if weak_window.upgrade().is_none() {
+            return ControlFlow::Break;
+        }
+        if (config.detached_timer.font_scale - font_scale).abs() > f64::EPSILON {
+            font_scale = config.detached_timer.font_scale;
+            load_font_css(font_scale);
+        }
+        split_name.update(timer, config, shows_split_name(config));
+        running.borrow_mut().update(timer, config);
+        ControlFlow::Continue
+    });
+
+    let parent_close = parent.connect_close_request(|_| {
+        close_detached_timer();
+        Propagation::Proceed
+    });
+    window.connect_close_request(|window| {
+        remember_window(window);
+        if let Some(detached) = DETACHED.take() {
+            unsubscribe_tick(detached.subscription);
+            detached.parent.disconnect(detached.parent_close);
+        }
+        Propagation::Proceed
+    });
+
+    info!("Detached the timer display");
+    DETACHED.replace(Some(DetachedTimer {
+        window: window.clone(),
+        subscription,
+        parent: parent.clone(),
+        parent_close,
+    }));
+    window.present();
+}
+
+/// Closes the detached timer if it's open, remembering where it was.
+pub fn close_detached_timer() {
+    if let Some(window) = DETACHED.with_borrow(|open| open.as_ref().map(|d| d.window.clone())) {
+        window.close();
+    }
+}
+
+#[cfg(test)]
+mod detached_timer_tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| (*name).to_owned()).collect()
+    }
+
+    fn size(width: i32, height: i32) -> DetachedGeometry {
+        DetachedGeometry {
+            width,
+            height,
+            fullscreen: false,
+        }
+    }
+
+    #[test]
+    fn reopening_restores_the_last_monitor() {
+        let mut options = DetachedTimerOptions::default();
+        assert_eq!(
+            restore_geometry(&options, &names(&["DP-1"])),
+            (None, DetachedGeometry::default())
+        );
+
+        remember_geometry(&mut options, Some("HDMI-1".to_owned()), size(800, 200));
+        remember_geometry(&mut options, Some("DP-1".to_owned()), size(400, 100));
+        let connected = names(&["DP-1", "HDMI-1"]);
+        assert_eq!(
+            restore_geometry(&options, &connected),
+            (Some("DP-1".to_owned()), size(400, 100))
+        );
+
+        // Each monitor keeps its own size
+        remember_geometry(&mut options, Some("HDMI-1".to_owned()), size(900, 250));
+        assert_eq!(
+            restore_geometry(&options, &connected),
+            (Some("HDMI-1".to_owned()), size(900, 250))
+        );
+        assert_eq!(options.geometry["DP-1"], size(400, 100));
+    }
+
+    #[test]
+    fn a_disconnected_monitor_falls_back_to_a_known_one() {
+        let mut options = DetachedTimerOptions::default();
+        remember_geometry(&mut options, Some("HDMI-1".to_owned()), size(800, 200));
+        remember_geometry(&mut options, Some("DP-2".to_owned()), size(400, 100));
+
+        assert_eq!(
+            restore_geometry(&options, &names(&["eDP-1", "HDMI-1"])),
+            (Some("HDMI-1".to_owned()), size(800, 200))
+        );
+        assert_eq!(
+            restore_geometry(&options, &names(&["eDP-1"])),
+            (None, DetachedGeometry::default())
+        );
+    }
+
+    #[test]
+    fn unknown_monitors_are_not_remembered() {
+        let mut options = DetachedTimerOptions::default();
+        remember_geometry(&mut options, None, size(800, 200));
+        assert!(options.geometry.is_empty());
+        assert_eq!(options.last_monitor, None);
+    }
+}
@@ -0,0 +1,416 @@
+//! "Drill Segment": repeating one segment and summing up the reps.
+//!
+//! A drill is started from a split's context menu, or with D on a focused
+//! split, and runs on the normal timer with a compact HUD in place of the
+//! split list. The pure rep tracking is [`DrillSession`]; this module feeds
+//! it the frames of the main tick through [`subscribe_tick`] and acts on
+//! what it asks for outside the tick. Starting the timer skips ahead to the
+//! drilled segment, and a finished rep resets the timer after
+//! `drill.pause-ms`.
+//!
+//! Reps never reach the splits file: the run is put back as it was before
+//! the drill when it ends, attempt count and history included. Loading or
+//! editing splits meanwhile ends the drill without putting anything back.
+
+use std::cell::RefCell;
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use adw::AlertDialog;
+use adw::prelude::*;
+use glib::ControlFlow;
+use gtk4::{Align, Box as GtkBox, Button, Label, Orientation};
+use livesplit_core::{Run, TimerPhase};
+use tracing::info;
+
+use crate::config::Config;
+use crate::context::TuxSplitContext;
+use crate::ui::timer::modal::show_notice;
+use crate::ui::timer::tick_fanout::{SubscriptionId, subscribe_tick, unsubscribe_tick};
+use crate::utils::comparisons::{
+    format_signed, gold_segment_duration, segment_comparison_duration,
+};
+use crate::utils::drill::{DrillSession, DrillStep, DrillSummary};
+
+thread_local! {
+    static HUD: RefCell<Option<DrillHud>> = const { RefCell::new(None) };
+    static DRILL: RefCell<Option<ActiveDrill>> = const { RefCell::new(None) };
+}
+
+struct ActiveDrill {
+    session: DrillSession,
+    /// The run as it was before the first rep.
+    run: Run,
+    gold: Option<time::Duration>,
+    comparison: Option<time::Duration>,
+    comparison_name: String,
+    pause: Duration,
+    subscription: SubscriptionId,
+    /// Taken when the drill ends.
+    run_changed: Option<glib::SignalHandlerId>,
+    /// Shown under the reps until the next one starts.
+    notice: Option<&'static str>,
+}
+
+/// Shown instead of the split list while drilling.
+#[derive(Clone)]
+pub struct DrillHud {
+    container: GtkBox,
+    splits: GtkBox,
+    title: Label,
+    progress: Label,
+    last: Label,
+    stats: Label,
+    hint: Label,
+}
+
+impl DrillHud {
+    /// `splits` is the split list container, hidden during drills.
+    pub fn new(splits: &GtkBox) -> Self {
+        let container = GtkBox::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(6)
+            .visible(false)
+            .css_classes(["drill-hud"])
+            .build();
+        let label = |classes: &[&str]| {
+            let label = Label::builder().halign(Align::Center).build();
+            for class in classes {
+                label.add_css_class(class);
+            }
+            label
+        };
+        let title = label(&["title-3"]);
+        let progress = label(&["heading"]);
+        let last = label(&["monospace"]);
+        let stats = label(&["monospace", "dim-label"]);
+        let hint = label(&["caption", "dim-label"]);
+        let stop = Button::builder()
+            .label("Stop Drill")
+            .halign(Align::Center)
+            .margin_top(6)
+            .css_classes(["pill"])
+            .build();
+        stop.connect_clicked(|_| end_drill(true));
+
+        container.append(&title);
+        container.append(&progress);
+        container.append(&last);
+        container.append(&stats);
+        container.append(&hint);
+        container.append(&stop);
+
+        Self {
+            container,
+            splits: splits.clone(),
+            title,
+            progress,
+            last,
+            stats,
+            hint,
+        }
+    }
+
+    pub fn container(&self) -> &GtkBox {
+        &self.container
+    }
+
+    fn show(&self, drill: Option<&ActiveDrill>, config: &Config) {
+        self.container.set_visible(drill.is_some());
+        self.splits.set_visible(drill.is_none());
+        let Some(drill) = drill else {
+            return;
+        };
+        let session = &drill.session;
+        let format = &config.format.segment;
+        let reps = session.reps();
+
+        self.title
+            .set_label(&format!("Drilling {}", session.name()));
+        self.progress.set_label(&match session.target_reps() {
+            0 => format!("{} reps", reps.len()),
+            target => format!("Rep {} of {target}", (reps.len() + 1).min(target)),
+        });
+        match reps.last() {
+            Some(last) => {
+                let mut text = format!("Last {}", format.format_duration(last));
+                if let Some(delta) = DrillSummary::versus(*last, drill.gold) {
+                    let _ = write!(text, " ({} vs gold)", format_signed(delta, config));
+                }
+                self.last.set_label(&text);
+            }
+            None => self.last.set_label("No reps yet"),
+        }
+        match session.summary(drill.gold, drill.comparison) {
+            Some(summary) => self.stats.set_label(&format!(
+                "Best {} · Average {}",
+                format.format_duration(&summary.best),
+                format.format_duration(&summary.average)
+            )),
+            None => self.stats.set_label(""),
+        }
+        self.stats.set_visible(!reps.is_empty());
+        self.hint.set_label(
+            drill
+                .notice
+                .unwrap_or("Start the timer to begin the rep, split at the end of the segment"),
+        );
+    }
+}
+
+/// Where the drill HUD is shown.
+pub fn set_drill_hud(hud: &DrillHud) {
+    HUD.replace(Some(hud.clone()));
+}
+
+/// Whether a drill is running.
+pub fn drill_active() -> bool {
+    DRILL.with_borrow(Option::is_some)
+}
+
+fn refresh_hud() {
+    let ctx = TuxSplitContext::get_instance();
+    let Ok(config) = ctx.try_config() else {
+        return;
+    };
+    HUD.with_borrow(|hud| {
+        if let Some(hud) = hud {
+            DRILL.with_borrow(|drill| hud.show(drill.as_ref(), &config));
+        }
+    });
+}
+
+/// Starts drilling the segment at `index`. Only outside an attempt.
+pub fn start_drill(index: usize) {
+    end_drill(true);
+    let ctx = TuxSplitContext::get_instance();
+    let (session, run, gold, comparison, comparison_name, pause) = {
+        let shared = ctx.timer();
+        let timer = shared.read().unwrap();
+        let config = ctx.config();
+        let Some(segment) = timer.run().segments().get(index) else {
+            return;
+        };
+        if timer.current_phase() != TimerPhase::NotRunning {
+            return;
+        }
+        let gold =
+            Some(gold_segment_duration(segment, &timer, &config)).filter(|gold| !gold.is_zero());
+        (
+            DrillSession::new(index, segment.name(), config.drill.reps as usize),
+            timer.run().clone(),
+            gold,
+            segment_comparison_duration(&timer, index),
+            timer.current_comparison().to_owned(),
+            Duration::from_millis(u64::from(config.drill.pause_ms)),
+        )
+    };
+
+    let subscription = subscribe_tick(|timer, _| {
+        let Some(step) =
+            DRILL.with_borrow_mut(|drill| drill.as_mut().map(|drill| drill.session.observe(timer)))
+        else {
+            return ControlFlow::Break;
+        };
+        if step != DrillStep::Continue {
+            // The timer and the dialogs can't be touched from inside the tick
+            glib::idle_add_local_once(move || apply_step(step));
+        }
+        ControlFlow::Continue
+    });
+    let run_changed = ctx.connect_local("run-changed", false, |_| {
+        info!("Splits changed, drill ended");
+        end_drill(false);
+        None
+    });
+
+    info!("Drilling segment {index} ({})", session.name());
+    DRILL.replace(Some(ActiveDrill {
+        session,
+        run,
+        gold,
+        comparison,
+        comparison_name,
+        pause,
+        subscription,
+        run_changed: Some(run_changed),
+        notice: None,
+    }));
+    refresh_hud();
+}
+
+fn set_notice(notice: Option<&'static str>) {
+    DRILL.with_borrow_mut(|drill| {
+        if let Some(drill) = drill.as_mut() {
+            drill.notice = notice;
+        }
+    });
+}
+
+fn apply_step(step: DrillStep) {
+    let Some((segment, pause)) = DRILL.with_borrow(|drill| {
+        drill
+            .as_ref()
+            .map(|drill| (drill.session.segment(), drill.pause))
+    }) else {
+        return;
+    };
+    match step {
+        DrillStep::Continue => {}
+        DrillStep::SkipSplits(skips) => {
+            let ctx = TuxSplitContext::get_instance();
+            let shared = ctx.timer();
+            let mut timer = shared.write().unwrap();
+            for _ in 0..skips {
+                timer.skip_split();
+            }
+            drop(timer);
+            set_notice(Some("Split at the end of the segment"));
+        }
+        DrillStep::Recorded(time) | DrillStep::Finished(time) => {
+            info!("Drill rep of segment {segment}: {time}");
+            set_notice(Some("Rep recorded"));
+            if matches!(step, DrillStep::Finished(_)) {
+                end_drill(true);
+                return;
+            }
+            reset_after(pause, segment);
+        }
+        DrillStep::Missed => {
+            set_notice(Some("The segment was skipped, rep not recorded"));
+            reset_after(pause, segment);
+        }
+        DrillStep::Abandoned => set_notice(Some("Rep abandoned")),
+    }
+    refresh_hud();
+}
+
+/// Resets the timer after `pause`, unless the runner already did and
+/// started the next rep.
+fn reset_after(pause: Duration, segment: usize) {
+    glib::timeout_add_local_once(pause, move || {
+        if !drill_active() {
+            return;
+        }
+        let ctx = TuxSplitContext::get_instance();
+        let shared = ctx.timer();
+        let mut timer = shared.write().unwrap();
+        if timer.current_phase() != TimerPhase::NotRunning
+            && timer
+                .current_split_index()
+                .is_none_or(|index| index > segment)
+        {
+            timer.reset(false);
+        }
+        drop(timer);
+        set_notice(None);
+        refresh_hud();
+    });
+}
+
+/// Ends the drill, if any. With `restore`, the run is put back as it was
+/// and the summary is shown.
+pub fn end_drill(restore: bool) {
+    let Some(mut drill) = DRILL.take() else {
+        return;
+    };
+    unsubscribe_tick(drill.subscription);
+    let ctx = TuxSplitContext::get_instance();
+    if let Some(run_changed) = drill.run_changed.take() {
+        ctx.disconnect(run_changed);
+    }
+    if restore {
+        {
+            let shared = ctx.timer();
+            shared.write().unwrap().reset(false);
+        }
+        if let Err(err) = ctx.set_run(drill.run.clone()) {
+            show_notice(&format!(
+                "The splits from before the drill were not restored: {err}"
+            ));
+        }
+    }
+    refresh_hud();
+    info!(
+        "Drill of segment {} ended after {} reps",
+        drill.session.segment(),
+        drill.session.reps().len()
+    );
+    if restore {
+        present_summary(&drill);
+    }
+}
+
+fn reps_text(drill: &ActiveDrill, config: &Config) -> String {
+    let mut text = format!("{}\n", drill.session.name());
+    for (rep, time) in drill.session.reps().iter().enumerate() {
+        let _ = writeln!(
+            text,
+            "Rep {}\t{}",
+            rep + 1,
+            config.format.segment.format_duration(time)
+        );
+    }
+    text
+}
+
+fn summary_body(drill: &ActiveDrill, summary: &DrillSummary, config: &Config) -> String {
+    let format = &config.format.segment;
+    let against = |time: time::Duration| {
+        let mut text = String::new();
+        if let Some(delta) = DrillSummary::versus(time, summary.gold) {
+            let _ = write!(text, "  {} vs gold", format_signed(delta, config));
+        }
+        if let Some(delta) = DrillSummary::versus(time, summary.comparison) {
+            let _ = write!(
+                text,
+                "  {} vs {}",
+                format_signed(delta, config),
+                drill.comparison_name
+            );
+        }
+        text
+    };
+    let mut body = format!(
+        "{} reps\nBest {}{}\nAverage {}{}",
+        summary.reps,
+        format.format_duration(&summary.best),
+        against(summary.best),
+        format.format_duration(&summary.average),
+        against(summary.average)
+    );
+    if let Some(std_dev) = summary.std_dev {
+        let _ = write!(
+            body,
+            "\nStandard deviation {}",
+            format.format_duration(&std_dev)
+        );
+    }
+    body
+}
+
+fn present_summary(drill: &ActiveDrill) {
+    let Some(summary) = drill.session.summary(drill.gold, drill.comparison) else {
+        return;
+    };
+    let Some(parent) = HUD.with_borrow(|hud| hud.as_ref().map(|hud| hud.container.clone())) else {
+        return;
+    };
+    let ctx = TuxSplitContext::get_instance();
+    let config = ctx.config();
+    let reps = reps_text(drill, &config);
+    info!("Drill reps:\n{reps}");
+
+    let dialog = AlertDialog::builder()
+        .heading(format!("Drill: {}", drill.session.name()))
+        .body(summary_body(drill, &summary, &config))
+        .default_response("ok")
+        .close_response("ok")
+        .build();
+    dialog.add_response("copy", "Copy Reps");
+    dialog.add_response("ok", "Close");
+    dialog.connect_response(Some("copy"), move |dialog, _| {
+        dialog.clipboard().set_text(&reps);
+    });
+    dialog.present(Some(&parent));
+}
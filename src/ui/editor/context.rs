@@ -5,9 +5,11 @@ use glib::prelude::*;
 use glib::subclass::prelude::*;
 
 use glib::{Properties, subclass::signal::Signal};
-use livesplit_core::{RunEditor, TimeSpan, Timer, TimingMethod};
+use livesplit_core::{Run, RunEditor, TimeSpan, Timer, TimingMethod};
 
 use crate::context::TuxSplitContext;
+use crate::ui::editor::reorder;
+use crate::ui::timer::modal::show_notice;
 
 pub enum SegmentMoveDirection {
     Up,
@@ -116,6 +118,15 @@ impl EditorContext {
         ctx.emit_run_changed();
     }
 
+    /// Puts the edited run on the timer, and only tells listeners about the
+    /// edit once the timer took it.
+    fn commit(&self, run: Run) {
+        match TuxSplitContext::get_instance().set_run(run) {
+            Ok(()) => self.emit_run_changed(),
+            Err(err) => show_notice(&format!("The edit was not applied: {err}")),
+        }
+    }
+
     /// Sets the segment name at `index`. Returns true if the operation succeeded.
     ///
     /// Mirrors the existing behavior in table.rs: clones the run, mutates it,
@@ -129,9 +140,7 @@ impl EditorContext {
         }
 
         run.segments_mut()[index].set_name(name);
-        ctx.set_run(run);
-
-        self.emit_run_changed();
+        self.commit(run);
     }
 
     /// Sets the split time at `index` in milliseconds for the current timing method.
@@ -158,9 +167,7 @@ impl EditorContext {
         );
         run_editor.unselect(index);
 
-        ctx.set_run(run_editor.close());
-
-        self.emit_run_changed();
+        self.commit(run_editor.close());
     }
 
     /// Sets the segment time at `index` in milliseconds for the current timing method.
@@ -186,9 +193,7 @@ impl EditorContext {
             .set_segment_time(Some(TimeSpan::from_milliseconds(ms as f64)));
         run_editor.unselect(index);
 
-        ctx.set_run(run_editor.close());
-
-        self.emit_run_changed();
+        self.commit(run_editor.close());
     }
 
     /// Sets the best segment time at `index` in milliseconds for the current timing method.
@@ -213,9 +218,7 @@ impl EditorContext {
             .best_segment_time_mut()
             .with_timing_method(method, Some(TimeSpan::from_milliseconds(ms as f64)));
 
-        ctx.set_run(run);
-
-        self.emit_run_changed();
+        self.commit(run);
     }
 
     /// Moves a given segment up/down by one position.
@@ -242,9 +245,32 @@ impl EditorContext {
             }
         }
 
-        ctx.set_run(run_editor.close());
+        self.commit(run_editor.close());
+    }
 
-        self.emit_run_changed();
+    /// Moves the segment at `from` to `to`, e.g. for a drag and drop. It moves
+    /// one step at a time like the buttons do, so its times, history and
+    /// icon stay with it.
+    pub fn move_segment_to(&self, from: usize, to: usize) {
+        let ctx = TuxSplitContext::get_instance();
+
+        let mut run_editor = RunEditor::new(ctx.get_run()).ok().unwrap();
+        if from >= run_editor.run().len() || to >= run_editor.run().len() {
+            return;
+        }
+        let Some((direction, steps)) = reorder::move_steps(from, to) else {
+            return;
+        };
+
+        run_editor.select_only(from);
+        for _ in 0..steps {
+            match direction {
+                SegmentMoveDirection::Up => run_editor.move_segments_up(),
+                SegmentMoveDirection::Down => run_editor.move_segments_down(),
+            }
+        }
+
+        self.commit(run_editor.close());
     }
 
     pub fn add_segment(&self, index: usize, direction: SegmentMoveDirection) {
@@ -262,9 +288,13 @@ impl EditorContext {
             }
         }
 
-        ctx.set_run(run_editor.close());
+        self.commit(run_editor.close());
+    }
 
-        self.emit_run_changed();
+    /// Replaces the whole run with `run`, e.g. the result of a bulk operation
+    /// that was previewed on a copy.
+    pub fn replace_run(&self, run: Run) {
+        self.commit(run);
     }
 
     pub fn remove_segment(&self, index: usize) {
@@ -279,9 +309,7 @@ impl EditorContext {
             return;
         }
 
-        ctx.set_run(run_editor.close());
-
-        self.emit_run_changed();
+        self.commit(run_editor.close());
     }
 }
 
@@ -306,7 +334,7 @@ mod tests {
         {
             let mut run = Run::new();
             run.push_segment(Segment::new("A"));
-            TuxSplitContext::get_instance().set_run(run);
+            TuxSplitContext::get_instance().set_run(run).unwrap();
         }
         let ctx = EditorContext::new();
 
@@ -340,7 +368,7 @@ mod tests {
         {
             let mut run = Run::new();
             run.push_segment(Segment::new("A"));
-            TuxSplitContext::get_instance().set_run(run);
+            TuxSplitContext::get_instance().set_run(run).unwrap();
         }
         let ctx = EditorContext::new();
 
@@ -373,7 +401,7 @@ mod tests {
         {
             let mut run = Run::new();
             run.push_segment(Segment::new("A"));
-            TuxSplitContext::get_instance().set_run(run);
+            TuxSplitContext::get_instance().set_run(run).unwrap();
         }
         let ctx = EditorContext::new();
 
@@ -430,7 +458,7 @@ mod tests {
         {
             let mut run = Run::new();
             run.push_segment(Segment::new("A"));
-            TuxSplitContext::get_instance().set_run(run);
+            TuxSplitContext::get_instance().set_run(run).unwrap();
         }
         let ctx = EditorContext::new();
 
@@ -456,7 +484,7 @@ mod tests {
         {
             let mut run = Run::new();
             run.push_segment(Segment::new("A"));
-            TuxSplitContext::get_instance().set_run(run);
+            TuxSplitContext::get_instance().set_run(run).unwrap();
         }
         let ctx = EditorContext::new();
 
@@ -501,7 +529,7 @@ mod tests {
         {
             let mut run = Run::new();
             run.push_segment(Segment::new("A"));
-            TuxSplitContext::get_instance().set_run(run);
+            TuxSplitContext::get_instance().set_run(run).unwrap();
         }
         let ctx = EditorContext::new();
 
@@ -524,4 +552,51 @@ mod tests {
         ctx.set_split_time_ms(10, 100);
         assert_eq!(count.get(), 1);
     }
+
+    #[test]
+    fn move_segment_to_keeps_segment_data_attached() {
+        {
+            let mut run = Run::new();
+            for name in ["A", "B", "C", "D"] {
+                run.push_segment(Segment::new(name));
+            }
+            *run.segment_mut(0).best_segment_time_mut() =
+                livesplit_core::Time::new().with_real_time(Some(TimeSpan::from_seconds(7.0)));
+            TuxSplitContext::get_instance().set_run(run).unwrap();
+        }
+        let ctx = EditorContext::new();
+
+        let count = Rc::new(Cell::new(0));
+        let c2 = count.clone();
+        ctx.connect_local("run-changed", false, move |_v| {
+            c2.set(c2.get() + 1);
+            None
+        });
+
+        let names = || {
+            let run = TuxSplitContext::get_instance().get_run();
+            run.segments()
+                .iter()
+                .map(|s| s.name().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        ctx.move_segment_to(0, 2);
+        assert_eq!(names(), ["B", "C", "A", "D"]);
+        {
+            let run = TuxSplitContext::get_instance().get_run();
+            let best = run.segments()[2].best_segment_time().real_time;
+            assert_eq!(best, Some(TimeSpan::from_seconds(7.0)));
+        }
+
+        ctx.move_segment_to(3, 0);
+        assert_eq!(names(), ["D", "B", "C", "A"]);
+        assert_eq!(count.get(), 2);
+
+        // No-op and out of range moves do nothing
+        ctx.move_segment_to(1, 1);
+        ctx.move_segment_to(4, 0);
+        assert_eq!(names(), ["D", "B", "C", "A"]);
+        assert_eq!(count.get(), 2);
+    }
 }
@@ -1,27 +1,32 @@
 mod action_bar;
 mod context;
 mod model;
+mod reorder;
 mod row;
 mod table;
 pub use context::EditorContext;
 pub use model::SegmentsModel;
 
 use crate::context::TuxSplitContext;
+use crate::formatters::time::parse_signed_hms;
 use crate::ui::editor::table::SegmentsEditor;
-use gtk4::{ActionBar, StringList};
+use crate::ui::timer::modal::show_notice;
+use crate::utils::bulk_edit;
+use gtk4::{ActionBar, CheckButton, Entry, MenuButton, Orientation, StringList, gio};
 use livesplit_core::{Run, TimeSpan};
 use std::sync::{Arc, RwLock};
 
 use adw::prelude::*;
 use adw::{
-    ComboRow, EntryRow, HeaderBar, PreferencesGroup, PreferencesPage, ToolbarView, ViewStack,
-    ViewSwitcher, Window,
+    AlertDialog, ComboRow, EntryRow, HeaderBar, PreferencesGroup, PreferencesPage,
+    ResponseAppearance, ToolbarView, ViewStack, ViewSwitcher, Window,
 };
 
 #[derive(Clone)]
 pub struct SplitEditor {
     dialog: ToolbarView,
     run_snapshot: Arc<RwLock<Run>>,
+    editor_ctx: EditorContext,
 }
 
 impl SplitEditor {
@@ -38,6 +43,7 @@ impl SplitEditor {
         let this = Self {
             dialog,
             run_snapshot,
+            editor_ctx: EditorContext::new(),
         };
 
         let run_info = this.build_run_info_page();
@@ -57,6 +63,7 @@ impl SplitEditor {
             .policy(adw::ViewSwitcherPolicy::Wide)
             .build();
         headerbar.set_title_widget(Some(&switcher));
+        headerbar.pack_end(&this.build_bulk_actions_menu());
 
         let action_bar = this.build_cancel_banner();
 
@@ -126,7 +133,10 @@ impl SplitEditor {
         let snapshot_binding = Arc::clone(&self.run_snapshot);
         let action_bar_binding = action_bar.clone();
         cancel_button.connect_clicked(move |_| {
-            TuxSplitContext::get_instance().set_run(snapshot_binding.read().unwrap().clone());
+            let snapshot = snapshot_binding.read().unwrap().clone();
+            if let Err(err) = TuxSplitContext::get_instance().set_run(snapshot) {
+                show_notice(&format!("The edits were not undone: {err}"));
+            }
             action_bar_binding.set_revealed(false);
         });
 
@@ -136,6 +146,134 @@ impl SplitEditor {
         action_bar
     }
 
+    fn build_bulk_actions_menu(&self) -> MenuButton {
+        let menu = gio::Menu::new();
+        menu.append(
+            Some("Clear All Split Times"),
+            Some("bulk.clear-split-times"),
+        );
+        menu.append(
+            Some("Clear Best Segments"),
+            Some("bulk.clear-best-segments"),
+        );
+        menu.append(Some("Clear Attempt History"), Some("bulk.clear-history"));
+        menu.append(Some("Shift All Times…"), Some("bulk.shift-times"));
+
+        let actions = gio::SimpleActionGroup::new();
+        actions.add_action(&self.bulk_clear_action(
+            "clear-split-times",
+            "Clear all split times?",
+            "split times",
+            bulk_edit::clear_split_times,
+        ));
+        actions.add_action(&self.bulk_clear_action(
+            "clear-best-segments",
+            "Clear best segments?",
+            "best segment times",
+            bulk_edit::clear_best_segments,
+        ));
+        actions.add_action(&self.bulk_clear_action(
+            "clear-history",
+            "Clear attempt history?",
+            "attempts",
+            bulk_edit::clear_attempt_history,
+        ));
+        actions.add_action(&self.bulk_shift_action());
+
+        let button = MenuButton::builder()
+            .icon_name("view-more-symbolic")
+            .tooltip_text("Bulk Actions")
+            .menu_model(&menu)
+            .build();
+        button.insert_action_group("bulk", Some(&actions));
+        button
+    }
+
+    /// Action that previews `op` on a copy of the run and asks for confirmation
+    /// before applying it. Everything stays revertible through Cancel.
+    fn bulk_clear_action(
+        &self,
+        name: &str,
+        heading: &'static str,
+        unit: &'static str,
+        op: fn(&mut Run) -> usize,
+    ) -> gio::SimpleAction {
+        let action = gio::SimpleAction::new(name, None);
+        let parent = self.dialog.clone();
+        let editor_ctx = self.editor_ctx.clone();
+
+        action.connect_activate(move |_, _| {
+            let mut preview = TuxSplitContext::get_instance().get_run();
+            let affected = op(&mut preview);
+            confirm_bulk_edit(
+                &parent,
+                &editor_ctx,
+                heading,
+                &format!("This will clear {affected} {unit}."),
+                preview,
+            );
+        });
+
+        action
+    }
+
+    fn bulk_shift_action(&self) -> gio::SimpleAction {
+        let action = gio::SimpleAction::new("shift-times", None);
+        let parent = self.dialog.clone();
+        let editor_ctx = self.editor_ctx.clone();
+
+        action.connect_activate(move |_, _| {
+            let offset_entry = Entry::builder()
+                .placeholder_text("-1.500 or +0:02.000")
+                .activates_default(true)
+                .build();
+            let include_best = CheckButton::builder()
+                .label("Also shift the first best segment")
+                .build();
+            let content = gtk4::Box::builder()
+                .orientation(Orientation::Vertical)
+                .spacing(12)
+                .build();
+            content.append(&offset_entry);
+            content.append(&include_best);
+
+            let dialog = AlertDialog::builder()
+                .heading("Shift all times")
+                .body("Moves every split time by the given offset, as if the start had been re-timed.")
+                .extra_child(&content)
+                .build();
+            dialog.add_responses(&[("cancel", "Cancel"), ("shift", "Shift")]);
+            dialog.set_response_appearance("shift", ResponseAppearance::Suggested);
+            dialog.set_default_response(Some("shift"));
+            dialog.set_close_response("cancel");
+
+            let parent_binding = parent.clone();
+            let editor_ctx = editor_ctx.clone();
+            dialog.connect_response(Some("shift"), move |_, _| {
+                let Ok(offset) = parse_signed_hms(&offset_entry.text()) else {
+                    show_bulk_error(&parent_binding, "Offset must look like [-]h:m:s.ms");
+                    return;
+                };
+
+                let mut preview = TuxSplitContext::get_instance().get_run();
+                match bulk_edit::shift_times(&mut preview, offset, include_best.is_active()) {
+                    Ok(affected) => confirm_bulk_edit(
+                        &parent_binding,
+                        &editor_ctx,
+                        "Shift all times?",
+                        &format!("This will shift {affected} values."),
+                        preview,
+                    ),
+                    Err(e) => show_bulk_error(&parent_binding, &e.to_string()),
+                }
+            });
+
+            dialog.present(Some(&parent));
+        });
+
+        action
+    }
+
     fn build_run_info_page(&self) -> PreferencesPage {
         let page = PreferencesPage::builder().title("General").build();
 
@@ -175,7 +313,9 @@ impl SplitEditor {
 
                 run.set_game_name(new_name);
 
-                ctx.set_run(run);
+                if let Err(err) = ctx.set_run(run) {
+                    show_notice(&format!("The edit was not applied: {err}"));
+                }
             });
         }
         {
@@ -187,7 +327,9 @@ impl SplitEditor {
 
                 run.set_category_name(new_category);
 
-                ctx.set_run(run);
+                if let Err(err) = ctx.set_run(run) {
+                    show_notice(&format!("The edit was not applied: {err}"));
+                }
             });
         }
 
@@ -239,7 +381,9 @@ impl SplitEditor {
 
                 run.set_offset(TimeSpan::from_seconds(new_offset));
 
-                ctx.set_run(run);
+                if let Err(err) = ctx.set_run(run) {
+                    show_notice(&format!("The edit was not applied: {err}"));
+                }
             } else {
                 entry.set_title("Start at (entry must be a valid number)");
                 entry.add_css_class("error");
@@ -249,14 +393,17 @@ impl SplitEditor {
         timing_method.connect_selected_notify(move |r| {
             let ctx = TuxSplitContext::get_instance();
 
-            let idx = r.selected();
+            let method = match r.selected() {
+                0 => livesplit_core::TimingMethod::RealTime,
+                1 => livesplit_core::TimingMethod::GameTime,
+                _ => return,
+            };
             if let Ok(mut t) = ctx.timer().try_write() {
-                match idx {
-                    0 => t.set_current_timing_method(livesplit_core::TimingMethod::RealTime),
-                    1 => t.set_current_timing_method(livesplit_core::TimingMethod::GameTime),
-                    _ => (),
-                }
+                t.set_current_timing_method(method);
                 drop(t);
+                if let Ok(mut cfg) = ctx.config_mut() {
+                    cfg.set_timing_method_override(method);
+                }
                 ctx.emit_by_name::<()>("run-changed", &[]);
             }
         });
@@ -280,8 +427,7 @@ impl SplitEditor {
             .description("Edit your run segments")
             .build();
 
-        let editor_ctx = EditorContext::new();
-        let segment_editor = SegmentsEditor::new(editor_ctx);
+        let segment_editor = SegmentsEditor::new(self.editor_ctx.clone());
         group.add(segment_editor.container());
 
         page.add(&group);
@@ -289,3 +435,40 @@ impl SplitEditor {
         page
     }
 }
+
+fn confirm_bulk_edit(
+    parent: &ToolbarView,
+    editor_ctx: &EditorContext,
+    heading: &str,
+    body: &str,
+    preview: Run,
+) {
+    let dialog = AlertDialog::builder()
+        .heading(heading)
+        .body(format!("{body} You can still undo it with Cancel."))
+        .build();
+    dialog.add_responses(&[("cancel", "Cancel"), ("apply", "Apply")]);
+    dialog.set_response_appearance("apply", ResponseAppearance::Destructive);
+    dialog.set_close_response("cancel");
+
+    let editor_ctx = editor_ctx.clone();
+    // The heading names the operation, e.g. "Clear attempt history?"
+    let operation = heading.trim_end_matches('?').to_owned();
+    dialog.connect_response(Some("apply"), move |_, _| {
+        let ctx = TuxSplitContext::get_instance();
+        ctx.config()
+            .trash_splits(&ctx.timer().read().unwrap(), &operation);
+        editor_ctx.replace_run(preview.clone());
+    });
+
+    dialog.present(Some(parent));
+}
+
+fn show_bulk_error(parent: &ToolbarView, message: &str) {
+    let dialog = AlertDialog::builder()
+        .heading("Cannot apply")
+        .body(message)
+        .build();
+    dialog.add_response("ok", "OK");
+    dialog.present(Some(parent));
+}
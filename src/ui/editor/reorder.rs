@@ -0,0 +1,110 @@
+//! Position bookkeeping for dragging segments to a new place in the editor.
+
+use std::ops::RangeInclusive;
+
+use crate::ui::editor::context::SegmentMoveDirection;
+
+/// Distance from the list's top or bottom edge where a drag scrolls it.
+const EDGE_SIZE: f64 = 32.0;
+/// Pixels scrolled per tick with the pointer right at the edge.
+const MAX_EDGE_SCROLL: f64 = 12.0;
+
+/// How a segment at `from` reaches `to` with single-step moves, or `None`
+/// when it doesn't move.
+pub fn move_steps(from: usize, to: usize) -> Option<(SegmentMoveDirection, usize)> {
+    match from.cmp(&to) {
+        std::cmp::Ordering::Equal => None,
+        std::cmp::Ordering::Greater => Some((SegmentMoveDirection::Up, from - to)),
+        std::cmp::Ordering::Less => Some((SegmentMoveDirection::Down, to - from)),
+    }
+}
+
+/// Positions whose item changes when the item at `from` moves to `to`.
+pub fn affected_range(from: usize, to: usize) -> RangeInclusive<usize> {
+    from.min(to)..=from.max(to)
+}
+
+/// Moves the item at `from` to `to`, shifting the ones in between by one.
+/// Returns false, leaving `items` untouched, for a no-op or an index out of
+/// range.
+pub fn move_item<T>(items: &mut Vec<T>, from: usize, to: usize) -> bool {
+    if from == to || from >= items.len() || to >= items.len() {
+        return false;
+    }
+    let item = items.remove(from);
+    items.insert(to, item);
+    true
+}
+
+/// Pixels to scroll per tick with the pointer `y` pixels into a list that is
+/// `height` pixels tall. Negative scrolls up.
+pub fn edge_scroll_step(y: f64, height: f64) -> f64 {
+    let edge = EDGE_SIZE.min(height / 2.0);
+    if edge <= 0.0 {
+        0.0
+    } else if y < edge {
+        -MAX_EDGE_SCROLL * (edge - y.max(0.0)) / edge
+    } else if y > height - edge {
+        MAX_EDGE_SCROLL * (y.min(height) - (height - edge)) / edge
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod reorder_tests {
+    use super::*;
+
+    fn moved(from: usize, to: usize) -> Vec<char> {
+        let mut items = vec!['a', 'b', 'c', 'd', 'e'];
+        move_item(&mut items, from, to);
+        items
+    }
+
+    #[test]
+    fn move_up_shifts_the_rows_in_between_down() {
+        assert_eq!(moved(3, 1), ['a', 'd', 'b', 'c', 'e']);
+        assert_eq!(affected_range(3, 1), 1..=3);
+        assert!(matches!(
+            move_steps(3, 1),
+            Some((SegmentMoveDirection::Up, 2))
+        ));
+    }
+
+    #[test]
+    fn move_down_shifts_the_rows_in_between_up() {
+        assert_eq!(moved(1, 3), ['a', 'c', 'd', 'b', 'e']);
+        assert_eq!(affected_range(1, 3), 1..=3);
+        assert!(matches!(
+            move_steps(1, 3),
+            Some((SegmentMoveDirection::Down, 2))
+        ));
+    }
+
+    #[test]
+    fn move_to_either_end() {
+        assert_eq!(moved(2, 0), ['c', 'a', 'b', 'd', 'e']);
+        assert_eq!(moved(2, 4), ['a', 'b', 'd', 'e', 'c']);
+        assert_eq!(moved(4, 0), ['e', 'a', 'b', 'c', 'd']);
+        assert_eq!(affected_range(4, 0), 0..=4);
+    }
+
+    #[test]
+    fn no_op_and_out_of_range_moves_change_nothing() {
+        let mut items = vec!['a', 'b', 'c'];
+        assert!(!move_item(&mut items, 1, 1));
+        assert!(!move_item(&mut items, 3, 0));
+        assert!(!move_item(&mut items, 0, 3));
+        assert_eq!(items, ['a', 'b', 'c']);
+        assert!(move_steps(2, 2).is_none());
+    }
+
+    #[test]
+    fn scrolls_only_near_the_edges() {
+        assert_eq!(edge_scroll_step(0.0, 400.0), -MAX_EDGE_SCROLL);
+        assert_eq!(edge_scroll_step(16.0, 400.0), -MAX_EDGE_SCROLL / 2.0);
+        assert_eq!(edge_scroll_step(200.0, 400.0), 0.0);
+        assert_eq!(edge_scroll_step(400.0, 400.0), MAX_EDGE_SCROLL);
+        assert_eq!(edge_scroll_step(10.0, 0.0), 0.0);
+    }
+}
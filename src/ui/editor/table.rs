@@ -1,15 +1,24 @@
 use livesplit_core::{Run, TimingMethod};
+use std::cell::Cell;
 use std::rc::Rc;
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
-use gtk4::{Box as GtkBox, ColumnView, ColumnViewColumn, ScrolledWindow, prelude::*};
+use gtk4::{
+    Box as GtkBox, ColumnView, ColumnViewColumn, DragSource, DropControllerMotion, DropTarget,
+    Image, ScrolledWindow, gdk, gio::ListStore, prelude::*,
+};
 
 use crate::context::TuxSplitContext;
 use crate::formatters::time::parse_hms;
 use crate::ui::editor::context::SegmentMoveDirection;
+use crate::ui::editor::reorder::{affected_range, edge_scroll_step, move_item};
 use crate::ui::editor::row::SegmentRow;
 use crate::ui::editor::{EditorContext, SegmentsModel};
 
+/// How often the list scrolls while a dragged row is held near its edge.
+const EDGE_SCROLL_INTERVAL: Duration = Duration::from_millis(30);
+
 pub struct SegmentsEditor {
     container: GtkBox,
     table: ColumnView,
@@ -49,6 +58,7 @@ impl SegmentsEditor {
             .hscrollbar_policy(gtk4::PolicyType::Never)
             .build();
         scroller.set_child(Some(&table));
+        Self::setup_edge_scroll(&scroller);
 
         let container = GtkBox::builder()
             .orientation(gtk4::Orientation::Horizontal)
@@ -83,11 +93,13 @@ impl SegmentsEditor {
     }
 
     fn setup_columns(self: &Rc<SegmentsEditor>) {
+        let handle_column = self.clone().make_drag_handle_column();
         let name_column = self.make_name_column();
         let split_time_column = self.clone().make_split_time_column();
         let segment_time_column = self.clone().make_segment_time_column();
         let best_column = self.clone().make_best_segment_column();
 
+        self.table.append_column(&handle_column);
         self.table.append_column(&name_column);
         self.table.append_column(&split_time_column);
         self.table.append_column(&segment_time_column);
@@ -133,6 +145,7 @@ impl SegmentsEditor {
 
         let context = self.context.clone();
         let model = self.model.clone();
+        let store = self.segments_model.store();
 
         factory.connect_setup(move |_, list_item| {
             let cell = list_item.downcast_ref::<gtk4::ColumnViewCell>().unwrap();
@@ -140,6 +153,7 @@ impl SegmentsEditor {
             cell.set_child(Some(&entry));
 
             SegmentsEditor::setup_name_cell_common(cell, &entry, &model, &context);
+            SegmentsEditor::setup_drop_target(cell, &entry, &context, &model, &store);
         });
         factory.connect_bind(|_, list_item| {
             let cell = list_item.downcast_ref::<gtk4::ColumnViewCell>().unwrap();
@@ -266,6 +280,140 @@ impl SegmentsEditor {
         col
     }
 
+    fn make_drag_handle_column(self: Rc<Self>) -> ColumnViewColumn {
+        let col = ColumnViewColumn::builder().build();
+        let factory = gtk4::SignalListItemFactory::new();
+
+        let self_shared = Rc::clone(&self);
+
+        factory.connect_setup(move |_, list_item| {
+            let cell = list_item.downcast_ref::<gtk4::ColumnViewCell>().unwrap();
+            let handle = Image::builder()
+                .icon_name("list-drag-handle-symbolic")
+                .tooltip_text("Drag to reorder")
+                .css_classes(["dim-label"])
+                .build();
+            handle.set_cursor_from_name(Some("grab"));
+            cell.set_child(Some(&handle));
+
+            // The dragged value is the row's index at the time the drag starts
+            let drag_source = DragSource::builder().actions(gdk::DragAction::MOVE).build();
+            let cell_binding = cell.clone();
+            drag_source.connect_prepare(move |_, _, _| {
+                let row = cell_binding.item()?.downcast::<SegmentRow>().ok()?;
+                Some(gdk::ContentProvider::for_value(&row.index().to_value()))
+            });
+            handle.add_controller(drag_source);
+
+            SegmentsEditor::setup_drop_target(
+                cell,
+                &handle,
+                &self_shared.context,
+                &self_shared.model,
+                &self_shared.segments_model.store(),
+            );
+        });
+        col.set_factory(Some(&factory));
+        col
+    }
+
+    // Accepts rows dragged by their handle and moves them onto this cell's row
+    fn setup_drop_target(
+        cell: &gtk4::ColumnViewCell,
+        widget: &impl IsA<gtk4::Widget>,
+        context: &EditorContext,
+        model: &gtk4::SingleSelection,
+        store: &ListStore,
+    ) {
+        let drop_target = DropTarget::new(u32::static_type(), gdk::DragAction::MOVE);
+        let cell_binding = cell.clone();
+        let context_binding = context.clone();
+        let model_binding = model.clone();
+        let store_binding = store.clone();
+        drop_target.connect_drop(move |_, value, _, _| {
+            let (Ok(from), Some(item)) = (value.get::<u32>(), cell_binding.item()) else {
+                return false;
+            };
+            let Ok(row) = item.downcast::<SegmentRow>() else {
+                return false;
+            };
+            SegmentsEditor::move_row(
+                &context_binding,
+                &model_binding,
+                &store_binding,
+                from as usize,
+                row.index() as usize,
+            )
+        });
+        widget.add_controller(drop_target);
+    }
+
+    /// Moves the row objects first so each keeps its widgets, then the
+    /// segment itself. The refresh that follows rewrites values in place.
+    fn move_row(
+        context: &EditorContext,
+        model: &gtk4::SingleSelection,
+        store: &ListStore,
+        from: usize,
+        to: usize,
+    ) -> bool {
+        let mut rows: Vec<SegmentRow> = store.iter::<SegmentRow>().filter_map(Result::ok).collect();
+        if !move_item(&mut rows, from, to) {
+            return false;
+        }
+        let affected = affected_range(from, to);
+        for index in affected.clone() {
+            rows[index].set_index(index as u32);
+        }
+        store.splice(
+            *affected.start() as u32,
+            (affected.end() - affected.start() + 1) as u32,
+            &rows[affected],
+        );
+
+        context.move_segment_to(from, to);
+        model.set_selected(to as u32);
+        true
+    }
+
+    // Scrolls the list while a dragged row is held close to its top or bottom edge
+    fn setup_edge_scroll(scroller: &ScrolledWindow) {
+        let pointer_y: Rc<Cell<Option<f64>>> = Rc::new(Cell::new(None));
+        let scrolling = Rc::new(Cell::new(false));
+        let motion = DropControllerMotion::new();
+        {
+            let pointer_y = pointer_y.clone();
+            let scroller = scroller.clone();
+            motion.connect_enter(move |_, _, y| {
+                pointer_y.set(Some(y));
+                if scrolling.replace(true) {
+                    return;
+                }
+                let pointer_y = pointer_y.clone();
+                let scrolling = scrolling.clone();
+                let scroller = scroller.clone();
+                glib::timeout_add_local(EDGE_SCROLL_INTERVAL, move || {
+                    let Some(y) = pointer_y.get() else {
+                        scrolling.set(false);
+                        return glib::ControlFlow::Break;
+                    };
+                    let step = edge_scroll_step(y, f64::from(scroller.height()));
+                    if step != 0.0 {
+                        let adjustment = scroller.vadjustment();
+                        adjustment.set_value(adjustment.value() + step);
+                    }
+                    glib::ControlFlow::Continue
+                });
+            });
+        }
+        {
+            let pointer_y = pointer_y.clone();
+            motion.connect_motion(move |_, _, y| pointer_y.set(Some(y)));
+        }
+        motion.connect_leave(move |_| pointer_y.set(None));
+        scroller.add_controller(motion);
+    }
+
     // Set standardized handlers for the name column
     fn setup_name_cell_common(
         cell: &gtk4::ColumnViewCell,
@@ -357,6 +505,14 @@ impl SegmentsEditor {
                 }
             }
         });
+
+        SegmentsEditor::setup_drop_target(
+            cell,
+            entry,
+            &editor.context,
+            &editor.model,
+            &editor.segments_model.store(),
+        );
     }
 
     // Small helpers to bridge into EditorContext
@@ -543,7 +699,9 @@ mod tests {
         s1.set_personal_best_split_time(time_both(10, 20));
         run.push_segment(s1);
 
-        crate::context::TuxSplitContext::get_instance().set_run(run);
+        crate::context::TuxSplitContext::get_instance()
+            .set_run(run)
+            .unwrap();
 
         let context = EditorContext::new();
         let editor = SegmentsEditor::new(context);
@@ -0,0 +1,192 @@
+//! Saving the timer layout as a PNG, for sharing a run's splits.
+//!
+//! The layout is drawn through a [`WidgetPaintable`] of the widget set with
+//! [`set_export_source`], so the image has the theme, profile colors and
+//! fonts of the window. The window background the timer sits on is painted
+//! first, since the layout itself is transparent.
+//!
+//! A layout that wasn't allocated yet is drawn at its natural size, and a
+//! widget outside of a realized window is rendered with a Cairo renderer of
+//! its own.
+
+use std::cell::RefCell;
+use std::path::Path;
+
+use gtk4::prelude::*;
+use gtk4::{
+    FileChooserAction, FileChooserDialog, FileFilter, Orientation, ResponseType, Snapshot, Widget,
+    WidgetPaintable, gdk, graphene, gsk,
+};
+use tracing::{info, warn};
+
+use crate::context::TuxSplitContext;
+use crate::ui::theme::{DARK_BACKGROUND, LIGHT_BACKGROUND};
+use crate::ui::timer::modal::show_notice;
+
+thread_local! {
+    static SOURCE: RefCell<Option<Widget>> = const { RefCell::new(None) };
+}
+
+/// Sets the widget drawn by the export, the main timer layout.
+pub fn set_export_source(widget: &impl IsA<Widget>) {
+    SOURCE.replace(Some(widget.clone().upcast()));
+}
+
+/// A file name for the image of `game` and `category`, with the characters
+/// file managers choke on left out.
+pub fn export_file_name(game: &str, category: &str) -> String {
+    let name = [game, category]
+        .iter()
+        .map(|part| {
+            part.split(|c: char| !c.is_alphanumeric())
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<_>>()
+                .join("-")
+        })
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if name.is_empty() {
+        "tuxsplit-splits.png".to_owned()
+    } else {
+        format!("{name}.png")
+    }
+}
+
+/// The size to draw at: the allocated one, or `natural` for a widget that
+/// wasn't laid out yet. `None` when there is nothing to draw.
+pub fn export_size(allocated: (i32, i32), natural: (i32, i32)) -> Option<(i32, i32)> {
+    let (width, height) = if allocated.0 > 0 && allocated.1 > 0 {
+        allocated
+    } else {
+        natural
+    };
+    (width > 0 && height > 0).then_some((width, height))
+}
+
+fn natural_size(widget: &Widget) -> (i32, i32) {
+    let (_, width, _, _) = widget.measure(Orientation::Horizontal, -1);
+    let (_, height, _, _) = widget.measure(Orientation::Vertical, width);
+    (width, height)
+}
+
+fn render_texture(widget: &Widget) -> Result<gdk::Texture, String> {
+    let (width, height) = export_size((widget.width(), widget.height()), natural_size(widget))
+        .ok_or("The timer has no size to draw at")?;
+
+    let background = if adw::StyleManager::default().is_dark() {
+        DARK_BACKGROUND
+    } else {
+        LIGHT_BACKGROUND
+    };
+    let snapshot = Snapshot::new();
+    if let Ok(color) = gdk::RGBA::parse(background) {
+        snapshot.append_color(
+            &color,
+            &graphene::Rect::new(0.0, 0.0, width as f32, height as f32),
+        );
+    }
+    WidgetPaintable::new(Some(widget)).snapshot(&snapshot, f64::from(width), f64::from(height));
+    let node = snapshot.to_node().ok_or("The timer drew nothing")?;
+
+    if let Some(renderer) = widget.native().and_then(|native| native.renderer()) {
+        return Ok(renderer.render_texture(&node, None));
+    }
+    let renderer = gsk::CairoRenderer::new();
+    renderer
+        .realize(None::<&gdk::Surface>)
+        .map_err(|err| err.to_string())?;
+    let texture = renderer.render_texture(&node, None);
+    renderer.unrealize();
+    Ok(texture)
+}
+
+/// Draws the export source and writes it to `path` as a PNG.
+pub fn export_image(path: &Path) -> Result<(), String> {
+    let widget = SOURCE
+        .with_borrow(Clone::clone)
+        .ok_or("There is no timer to export")?;
+    let texture = render_texture(&widget)?;
+    texture
+        .save_to_png(path)
+        .map_err(|err| format!("Could not write {}: {err}", path.display()))?;
+    info!(
+        "Exported the layout to {} ({}x{})",
+        path.display(),
+        texture.width(),
+        texture.height()
+    );
+    Ok(())
+}
+
+fn png_filter() -> FileFilter {
+    let filter = FileFilter::new();
+    filter.set_name(Some("PNG Image (*.png)"));
+    filter.add_mime_type("image/png");
+    filter.add_pattern("*.png");
+    filter
+}
+
+/// Asks where to save the image of the layout and writes it there.
+pub fn present_image_export(parent: &adw::ApplicationWindow) {
+    let file_chooser = FileChooserDialog::new(
+        Some("Export as Image"),
+        Some(parent),
+        FileChooserAction::Save,
+        &[("Save", ResponseType::Ok), ("Cancel", ResponseType::Cancel)],
+    );
+    file_chooser.add_filter(&png_filter());
+    let name = {
+        let ctx = TuxSplitContext::get_instance();
+        let shared = ctx.timer();
+        let timer = shared.read().unwrap();
+        export_file_name(timer.run().game_name(), timer.run().category_name())
+    };
+    file_chooser.set_current_name(&name);
+
+    file_chooser.connect_response(move |dialog, response| {
+        if response == ResponseType::Ok
+            && let Some(file) = dialog.file()
+            && let Some(path) = file.path()
+        {
+            match export_image(&path) {
+                Ok(()) => show_notice("Layout exported as an image"),
+                Err(err) => {
+                    warn!("Image export failed: {err}");
+                    show_notice(&format!("Image export failed: {err}"));
+                }
+            }
+        }
+        dialog.destroy();
+    });
+
+    file_chooser.set_modal(true);
+    file_chooser.present();
+}
+
+#[cfg(test)]
+mod export_image_tests {
+    use super::*;
+
+    #[test]
+    fn file_name_joins_game_and_category() {
+        assert_eq!(
+            export_file_name("Super Mario 64", "120 Star"),
+            "Super-Mario-64-120-Star.png"
+        );
+        assert_eq!(
+            export_file_name("Celeste", "Any% / No DLC"),
+            "Celeste-Any-No-DLC.png"
+        );
+        assert_eq!(export_file_name("Hollow Knight", ""), "Hollow-Knight.png");
+        assert_eq!(export_file_name("", " "), "tuxsplit-splits.png");
+    }
+
+    #[test]
+    fn unallocated_layouts_draw_at_their_natural_size() {
+        assert_eq!(export_size((420, 600), (300, 500)), Some((420, 600)));
+        assert_eq!(export_size((0, 0), (300, 500)), Some((300, 500)));
+        assert_eq!(export_size((420, 0), (300, 500)), Some((300, 500)));
+        assert_eq!(export_size((0, 0), (0, 0)), None);
+    }
+}
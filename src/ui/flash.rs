@@ -0,0 +1,422 @@
+//! Brief tint of the window when a split is taken.
+//!
+//! With `feedback.flash-on-split`, taking a split puts a class on the window
+//! content for [`FLASH_DURATION`], tinting its border or background gold,
+//! green or red like the split's delta. `feedback.flash-on-start` flashes
+//! the start of an attempt as well, in the accent color. The timer is
+//! followed on every refresh tick rather than the hotkeys, so splits from
+//! global hotkeys or the auto splitter flash too. Only one flash is shown at a time: a new split
+//! restarts it and undoing or resetting drops it. Nothing flashes while
+//! animations are turned off in the desktop settings.
+//!
+//! `feedback.confirm-sound` plays a sound on every start and split, whether
+//! anything flashes or not. A split taken while it's still playing starts it
+//! over.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use adw::prelude::*;
+use glib::ControlFlow;
+use gtk4::{MediaFile, Widget};
+use tracing::warn;
+
+use crate::config::SplitFlashMode;
+use crate::context::TuxSplitContext;
+use crate::ui::timer::tick_fanout::subscribe_tick;
+use crate::utils::comparisons::{color_class, split_delta_class};
+
+pub const FLASH_DURATION: Duration = Duration::from_millis(200);
+
+/// Semantic state of the split that was just taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlashTint {
+    Gold,
+    Ahead,
+    Behind,
+    /// The attempt just started.
+    Start,
+}
+
+const TINT_CLASSES: [&str; 4] = ["flash-gold", "flash-ahead", "flash-behind", "flash-start"];
+const MODE_CLASSES: [&str; 2] = ["split-flash-border", "split-flash-background"];
+
+impl FlashTint {
+    /// Tint for a delta class from `classify_split_label`.
+    pub fn from_split_class(class: &str) -> Option<Self> {
+        match class {
+            "goldsplit" => Some(Self::Gold),
+            "greensplit" | "lostgreensplit" => Some(Self::Ahead),
+            "gainedredsplit" | "redsplit" => Some(Self::Behind),
+            _ => None,
+        }
+    }
+
+    pub fn css_class(self) -> &'static str {
+        match self {
+            Self::Gold => TINT_CLASSES[0],
+            Self::Ahead => TINT_CLASSES[1],
+            Self::Behind => TINT_CLASSES[2],
+            Self::Start => TINT_CLASSES[3],
+        }
+    }
+}
+
+impl SplitFlashMode {
+    fn css_class(self) -> Option<&'static str> {
+        match self {
+            SplitFlashMode::Off => None,
+            SplitFlashMode::Border => Some(MODE_CLASSES[0]),
+            SplitFlashMode::Background => Some(MODE_CLASSES[1]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlashCommand {
+    /// Replace whatever flash is on with this one.
+    Show(FlashTint),
+    Clear,
+}
+
+/// Decides when to flash from the observed split index, independently of GTK.
+#[derive(Debug, Default)]
+pub struct SplitFlash {
+    last_index: Option<usize>,
+    shown_at: Option<Instant>,
+    /// Whether starting an attempt flashes too.
+    on_start: bool,
+    /// Whether the last update saw a start or a split.
+    registered: bool,
+}
+
+impl SplitFlash {
+    pub fn is_shown(&self) -> bool {
+        self.shown_at.is_some()
+    }
+
+    pub fn set_flash_on_start(&mut self, on_start: bool) {
+        self.on_start = on_start;
+    }
+
+    /// Whether the last update saw the timer start or split, flashed or not.
+    pub fn registered(&self) -> bool {
+        self.registered
+    }
+
+    /// Feeds the timer's current split index. `tint_of` is asked for the
+    /// segment that was just split, and returns `None` for a skipped one.
+    pub fn update(
+        &mut self,
+        split_index: Option<usize>,
+        enabled: bool,
+        now: Instant,
+        tint_of: impl FnOnce(usize) -> Option<FlashTint>,
+    ) -> Option<FlashCommand> {
+        let previous = std::mem::replace(&mut self.last_index, split_index);
+        let (split_taken, moved_back) = match (previous, split_index) {
+            (Some(previous), Some(current)) => (current > previous, current < previous),
+            (Some(_), None) => (false, true),
+            _ => (false, false),
+        };
+        let started = previous.is_none() && split_index.is_some();
+        self.registered = split_taken || started;
+
+        if enabled && started && self.on_start {
+            self.shown_at = Some(now);
+            return Some(FlashCommand::Show(FlashTint::Start));
+        }
+        if enabled
+            && split_taken
+            && let Some(tint) = split_index.and_then(|index| tint_of(index - 1))
+        {
+            self.shown_at = Some(now);
+            return Some(FlashCommand::Show(tint));
+        }
+        if split_taken || moved_back || !enabled {
+            return self.clear();
+        }
+        match self.shown_at {
+            Some(at) if now.duration_since(at) >= FLASH_DURATION => self.clear(),
+            _ => None,
+        }
+    }
+
+    fn clear(&mut self) -> Option<FlashCommand> {
+        self.shown_at.take().map(|_| FlashCommand::Clear)
+    }
+}
+
+fn clear_classes(target: &Widget) {
+    for class in TINT_CLASSES.iter().chain(&MODE_CLASSES) {
+        target.remove_css_class(class);
+    }
+}
+
+/// Plays `feedback.confirm-sound`. One stream is kept and rewound, so a
+/// quick split restarts the sound instead of layering it over the last one.
+#[derive(Default)]
+struct ConfirmSound {
+    path: Option<PathBuf>,
+    media: Option<MediaFile>,
+}
+
+impl ConfirmSound {
+    fn play(&mut self, path: &Path) {
+        if self.path.as_deref() != Some(path) {
+            if !path.is_file() {
+                warn!("Confirmation sound {} not found", path.display());
+            }
+            self.path = Some(path.to_owned());
+            self.media = Some(MediaFile::for_filename(path));
+        }
+        if let Some(media) = &self.media {
+            media.seek(0);
+            media.play();
+        }
+    }
+}
+
+/// Follows the refresh ticks and flashes `target` on splits while enabled in
+/// the config. Nothing is subscribed when neither a flash nor a sound is
+/// configured.
+pub fn start_split_flash(target: &impl IsA<Widget>) {
+    let ctx = TuxSplitContext::get_instance();
+    let feedback = &ctx.config().feedback;
+    if feedback.flash_on_split == SplitFlashMode::Off && feedback.confirm_sound.is_none() {
+        return;
+    }
+    let target = target.clone().upcast::<Widget>();
+    let mut flash = SplitFlash::default();
+    let mut sound = ConfirmSound::default();
+
+    subscribe_tick(move |timer, config| {
+        let mode = config.feedback.flash_on_split;
+        let animations = target.settings().is_gtk_enable_animations();
+
+        flash.set_flash_on_start(config.feedback.flash_on_start);
+        let command = flash.update(
+            timer.current_split_index(),
+            mode != SplitFlashMode::Off && animations,
+            Instant::now(),
+            |index| {
+                split_delta_class(timer, config, index)
+                    .and_then(|class| color_class(config, class))
+                    .and_then(FlashTint::from_split_class)
+            },
+        );
+        match command {
+            Some(FlashCommand::Show(tint)) => {
+                clear_classes(&target);
+                if let Some(class) = mode.css_class() {
+                    target.add_css_class(class);
+                }
+                target.add_css_class(tint.css_class());
+            }
+            Some(FlashCommand::Clear) => clear_classes(&target),
+            None => {}
+        }
+        if flash.registered()
+            && let Some(path) = &config.feedback.confirm_sound
+        {
+            sound.play(&config.resolve(path));
+        }
+
+        ControlFlow::Continue
+    });
+}
+
+#[cfg(test)]
+mod flash_tests {
+    use super::*;
+
+    /// Runs `indices` through the state machine, one poll every `step`.
+    fn run(
+        flash: &mut SplitFlash,
+        start: Instant,
+        step: Duration,
+        indices: &[Option<usize>],
+    ) -> Vec<Option<FlashCommand>> {
+        indices
+            .iter()
+            .enumerate()
+            .map(|(tick, &index)| {
+                let now = start + step * tick as u32;
+                flash.update(index, true, now, |_| Some(FlashTint::Ahead))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn split_flashes_for_the_flash_duration() {
+        let mut flash = SplitFlash::default();
+        let start = Instant::now();
+        let commands = run(
+            &mut flash,
+            start,
+            Duration::from_millis(100),
+            &[None, Some(0), Some(1), Some(1), Some(1), Some(1)],
+        );
+        assert_eq!(
+            commands,
+            vec![
+                None,
+                // Starting the timer is not a split
+                None,
+                Some(FlashCommand::Show(FlashTint::Ahead)),
+                None,
+                Some(FlashCommand::Clear),
+                None,
+            ]
+        );
+        assert!(!flash.is_shown());
+    }
+
+    #[test]
+    fn rapid_splits_restart_instead_of_stacking() {
+        let mut flash = SplitFlash::default();
+        let start = Instant::now();
+        let step = Duration::from_millis(150);
+        let commands = run(
+            &mut flash,
+            start,
+            step,
+            &[Some(0), Some(1), Some(2), Some(2), Some(2)],
+        );
+        assert_eq!(
+            commands,
+            vec![
+                None,
+                Some(FlashCommand::Show(FlashTint::Ahead)),
+                Some(FlashCommand::Show(FlashTint::Ahead)),
+                // 150ms after the second split, still within its own flash
+                None,
+                Some(FlashCommand::Clear),
+            ]
+        );
+    }
+
+    #[test]
+    fn undo_and_reset_drop_the_flash() {
+        let mut flash = SplitFlash::default();
+        let start = Instant::now();
+        let step = Duration::from_millis(10);
+        let commands = run(
+            &mut flash,
+            start,
+            step,
+            &[Some(0), Some(1), Some(0), Some(1), None, None],
+        );
+        assert_eq!(
+            commands,
+            vec![
+                None,
+                Some(FlashCommand::Show(FlashTint::Ahead)),
+                Some(FlashCommand::Clear),
+                Some(FlashCommand::Show(FlashTint::Ahead)),
+                Some(FlashCommand::Clear),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn the_tint_comes_from_the_segment_just_split() {
+        let mut flash = SplitFlash::default();
+        let now = Instant::now();
+        let tint_of = |index| match index {
+            0 => Some(FlashTint::Gold),
+            _ => Some(FlashTint::Behind),
+        };
+        flash.update(Some(0), true, now, tint_of);
+        assert_eq!(
+            flash.update(Some(1), true, now, tint_of),
+            Some(FlashCommand::Show(FlashTint::Gold))
+        );
+        assert_eq!(
+            flash.update(Some(2), true, now, tint_of),
+            Some(FlashCommand::Show(FlashTint::Behind))
+        );
+    }
+
+    #[test]
+    fn skips_do_not_flash_and_cut_the_previous_one() {
+        let mut flash = SplitFlash::default();
+        let now = Instant::now();
+        flash.update(Some(0), true, now, |_| Some(FlashTint::Gold));
+        flash.update(Some(1), true, now, |_| Some(FlashTint::Gold));
+        assert_eq!(
+            flash.update(Some(2), true, now, |_| None),
+            Some(FlashCommand::Clear)
+        );
+        assert_eq!(flash.update(Some(3), true, now, |_| None), None);
+    }
+
+    #[test]
+    fn disabled_never_flashes_and_disabling_clears() {
+        let mut flash = SplitFlash::default();
+        let now = Instant::now();
+        flash.update(Some(0), false, now, |_| Some(FlashTint::Gold));
+        assert_eq!(
+            flash.update(Some(1), false, now, |_| Some(FlashTint::Gold)),
+            None
+        );
+
+        flash.update(Some(2), true, now, |_| Some(FlashTint::Gold));
+        assert!(flash.is_shown());
+        // e.g. reduce motion turned on mid-flash
+        assert_eq!(
+            flash.update(Some(2), false, now, |_| Some(FlashTint::Gold)),
+            Some(FlashCommand::Clear)
+        );
+    }
+
+    #[test]
+    fn starting_flashes_only_when_asked() {
+        let mut flash = SplitFlash::default();
+        let now = Instant::now();
+        flash.update(None, true, now, |_| Some(FlashTint::Gold));
+        assert_eq!(
+            flash.update(Some(0), true, now, |_| Some(FlashTint::Gold)),
+            None
+        );
+        assert!(flash.registered());
+
+        flash.update(None, true, now, |_| Some(FlashTint::Gold));
+        flash.set_flash_on_start(true);
+        assert_eq!(
+            flash.update(Some(0), true, now, |_| Some(FlashTint::Gold)),
+            Some(FlashCommand::Show(FlashTint::Start))
+        );
+        // A quick first split replaces it
+        assert_eq!(
+            flash.update(Some(1), true, now, |_| Some(FlashTint::Gold)),
+            Some(FlashCommand::Show(FlashTint::Gold))
+        );
+        assert!(flash.registered());
+        assert_eq!(flash.update(Some(1), true, now, |_| None), None);
+        assert!(!flash.registered());
+
+        // Restarting is registered even with the flash off
+        flash.update(None, false, now, |_| None);
+        assert_eq!(flash.update(Some(0), false, now, |_| None), None);
+        assert!(flash.registered());
+    }
+
+    #[test]
+    fn split_classes_map_to_tints() {
+        assert_eq!(
+            FlashTint::from_split_class("goldsplit"),
+            Some(FlashTint::Gold)
+        );
+        assert_eq!(
+            FlashTint::from_split_class("lostgreensplit"),
+            Some(FlashTint::Ahead)
+        );
+        assert_eq!(
+            FlashTint::from_split_class("gainedredsplit"),
+            Some(FlashTint::Behind)
+        );
+        assert_eq!(FlashTint::from_split_class(""), None);
+    }
+}
@@ -0,0 +1,154 @@
+//! Pausing the timer when the game in `freeze-watch.process` hard-freezes.
+//!
+//! While an attempt runs, the process is sampled once a second and fed to a
+//! [`FreezeDetector`]. A freeze pauses the timer with `action: pause`, and
+//! the watch resumes it once the game responds again if it was the one that
+//! paused it. Every automatic pause and resume is shown in a toast and
+//! logged, so it's never mistaken for the runner's own.
+
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+use adw::{Toast, ToastOverlay};
+use glib::ControlFlow;
+use livesplit_core::TimerPhase;
+use tracing::info;
+
+use crate::config::{FreezeAction, FreezeWatchOptions};
+use crate::context::TuxSplitContext;
+use crate::io::process::{find_process, sample_process};
+use crate::utils::freeze_watch::{FreezeChange, FreezeDetector};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+thread_local! {
+    static WATCH: RefCell<Option<FreezeWatch>> = const { RefCell::new(None) };
+}
+
+struct FreezeWatch {
+    process: String,
+    idle_secs: u32,
+    pid: Option<u32>,
+    detector: FreezeDetector,
+    /// The timer was paused by the watch and not resumed since.
+    paused_by_watch: bool,
+}
+
+impl FreezeWatch {
+    fn new(process: &str, idle_secs: u32) -> Self {
+        Self {
+            process: process.to_owned(),
+            idle_secs,
+            pid: None,
+            detector: FreezeDetector::new(Duration::from_secs(u64::from(idle_secs))),
+            paused_by_watch: false,
+        }
+    }
+}
+
+fn show_toast(toasts: &ToastOverlay, title: &str) {
+    toasts.add_toast(Toast::builder().title(title).timeout(8).build());
+}
+
+fn sample(options: &FreezeWatchOptions, phase: TimerPhase) -> Option<FreezeChange> {
+    WATCH.with_borrow_mut(|watch| {
+        let Some(process) = options.process.as_deref() else {
+            *watch = None;
+            return None;
+        };
+        if watch
+            .as_ref()
+            .is_none_or(|watch| watch.process != process || watch.idle_secs != options.idle_secs)
+        {
+            *watch = Some(FreezeWatch::new(process, options.idle_secs));
+        }
+        let watch = watch.as_mut()?;
+        if phase != TimerPhase::Paused {
+            watch.paused_by_watch = false;
+        }
+        // Only an attempt in progress is worth the /proc reads
+        if matches!(phase, TimerPhase::NotRunning | TimerPhase::Ended) {
+            *watch = FreezeWatch::new(process, options.idle_secs);
+            return None;
+        }
+        if watch.pid.is_none() {
+            watch.pid = find_process(process);
+        }
+        let sample = watch.pid.and_then(sample_process);
+        if sample.is_none() {
+            watch.pid = None;
+        }
+        watch.detector.observe(sample, Instant::now())
+    })
+}
+
+fn apply(change: FreezeChange, options: &FreezeWatchOptions, toasts: &ToastOverlay) {
+    let process = options.process.as_deref().unwrap_or_default();
+    let ctx = TuxSplitContext::get_instance();
+    let shared = ctx.timer();
+    let mut timer = shared.write().unwrap();
+    match change {
+        FreezeChange::Froze(reason) => {
+            if options.action == FreezeAction::Pause && timer.current_phase() == TimerPhase::Running
+            {
+                timer.pause();
+                WATCH.with_borrow_mut(|watch| {
+                    if let Some(watch) = watch {
+                        watch.paused_by_watch = true;
+                    }
+                });
+                info!("Paused the timer automatically: {process} {reason}");
+                show_toast(
+                    toasts,
+                    &format!("Timer paused automatically: {process} {reason}"),
+                );
+            } else {
+                info!("The game looks frozen: {process} {reason}");
+                show_toast(toasts, &format!("{process} {reason}"));
+            }
+        }
+        FreezeChange::Recovered => {
+            let paused_by_watch = WATCH.with_borrow_mut(|watch| {
+                watch
+                    .as_mut()
+                    .is_some_and(|watch| std::mem::take(&mut watch.paused_by_watch))
+            });
+            if !paused_by_watch {
+                info!("{process} responds again");
+            } else if options.resume_on_recovery && timer.current_phase() == TimerPhase::Paused {
+                timer.resume();
+                info!("Resumed the timer automatically: {process} responds again");
+                show_toast(
+                    toasts,
+                    &format!("Timer resumed automatically: {process} responds again"),
+                );
+            } else {
+                info!("{process} responds again, the timer stays paused");
+                show_toast(
+                    toasts,
+                    &format!("{process} responds again, the timer stays paused"),
+                );
+            }
+        }
+    }
+}
+
+/// Starts watching the configured game process, with the automatic pauses
+/// reported on `toasts`.
+pub fn start_freeze_watch(toasts: &ToastOverlay) {
+    let toasts = toasts.clone();
+    glib::timeout_add_local(SAMPLE_INTERVAL, move || {
+        let ctx = TuxSplitContext::get_instance();
+        let Ok(options) = ctx.try_config().map(|config| config.freeze_watch.clone()) else {
+            return ControlFlow::Continue;
+        };
+        let phase = {
+            let shared = ctx.timer();
+            shared.read().unwrap().current_phase()
+        };
+        if let Some(change) = sample(&options, phase) {
+            apply(change, &options, &toasts);
+        }
+        ControlFlow::Continue
+    });
+}
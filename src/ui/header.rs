@@ -1,19 +1,48 @@
 use adw::{self, AboutDialog, AlertDialog};
 use adw::{PreferencesDialog, prelude::*};
 use gtk4::{
-    Align, Box as GtkBox, FileChooserDialog, FileFilter, Label, ListBox, MenuButton,
-    Orientation::Vertical, gio,
+    Align, Box as GtkBox, Button, FileChooserDialog, FileFilter, Image, Label, ListBox, MenuButton,
+    Orientation::Vertical, gdk, gio,
 };
 
-use crate::context::TuxSplitContext;
+use livesplit_core::TimerPhase;
+
+use crate::context::{TuxSplitContext, config_file_path, config_file_status};
+use crate::io::bundle::{
+    BUNDLE_EXTENSION, BundleError, ValidatedBundle, bundle_to_bytes, export_bundle, parse_bundle,
+    write_splits,
+};
+use crate::io::layout_import::{LAYOUT_EXTENSION, LayoutImport, read_layout};
+use crate::io::trash::TrashEntry;
+use crate::ui::attempt_edit::present_attempt_editor;
+use crate::ui::detached_timer::present_detached_timer;
 use crate::ui::editor::SplitEditor;
+use crate::ui::export_image::present_image_export;
+use crate::ui::hotkeys::{hotkeys_registered, register_hotkeys, set_hotkeys_suspended};
+use crate::ui::integrity::refresh_integrity_badge;
+use crate::ui::keybindings::present_keybindings;
 use crate::ui::menu::TimerPreferencesDialog;
+use crate::ui::race::present_race_dialog;
+#[cfg(feature = "racetime")]
+use crate::ui::racetime::{send_done, send_forfeit};
+use crate::ui::run_style::present_run_style_editor;
+use crate::ui::saved_attempt::save_attempt_and_quit;
+use crate::ui::theme::apply_theme;
+use crate::ui::timer::font::apply_timer_font;
+use crate::ui::timer::modal::show_notice;
+use crate::utils::stats::{format_consistency, least_consistent, segment_consistency};
+use crate::utils::trace_timing::splits_io_span;
 
 /// `TuxSplitHeader`
 /// A top bar that renders the application title and a hamburger menu.
 pub struct TuxSplitHeader {
     header: adw::HeaderBar,
     menu: TuxSplitMenu,
+    inhibit_indicator: Image,
+    hotkeys_paused_indicator: Image,
+    racetime_indicator: Label,
+    video_offset_indicator: Label,
+    integrity_badge: MenuButton,
 }
 
 impl TuxSplitHeader {
@@ -25,12 +54,77 @@ impl TuxSplitHeader {
         let menu = TuxSplitMenu::new(parent);
         header.pack_start(menu.button());
 
-        Self { header, menu }
+        let inhibit_indicator = Image::builder()
+            .icon_name("security-high-symbolic")
+            .tooltip_text("Sleep is blocked while the run is in progress")
+            .visible(false)
+            .build();
+        header.pack_end(&inhibit_indicator);
+
+        let hotkeys_paused_indicator = Image::builder()
+            .icon_name("input-keyboard-symbolic")
+            .tooltip_text("Global hotkeys are paused")
+            .css_classes(["dim-label"])
+            .visible(false)
+            .build();
+        header.pack_end(&hotkeys_paused_indicator);
+
+        let racetime_indicator = Label::builder()
+            .css_classes(["caption"])
+            .visible(false)
+            .build();
+        header.pack_end(&racetime_indicator);
+
+        let video_offset_indicator = Label::builder()
+            .css_classes(["caption", "dim-label"])
+            .visible(false)
+            .build();
+        header.pack_end(&video_offset_indicator);
+
+        let integrity_badge = MenuButton::builder()
+            .icon_name("dialog-warning-symbolic")
+            .css_classes(["flat", "warning"])
+            .visible(false)
+            .build();
+        header.pack_end(&integrity_badge);
+
+        Self {
+            header,
+            menu,
+            inhibit_indicator,
+            hotkeys_paused_indicator,
+            racetime_indicator,
+            video_offset_indicator,
+            integrity_badge,
+        }
     }
 
     pub fn header(&self) -> &adw::HeaderBar {
         &self.header
     }
+
+    pub fn inhibit_indicator(&self) -> &Image {
+        &self.inhibit_indicator
+    }
+
+    pub fn hotkeys_paused_indicator(&self) -> &Image {
+        &self.hotkeys_paused_indicator
+    }
+
+    /// racetime.gg room status, hidden unless a room is joined.
+    pub fn racetime_indicator(&self) -> &Label {
+        &self.racetime_indicator
+    }
+
+    /// The video offset, hidden while it is zero.
+    pub fn video_offset_indicator(&self) -> &Label {
+        &self.video_offset_indicator
+    }
+
+    /// Shown while the loaded splits' times differ from their stored hash.
+    pub fn integrity_badge(&self) -> &MenuButton {
+        &self.integrity_badge
+    }
 }
 
 pub struct TuxSplitMenu {
@@ -50,12 +144,30 @@ impl TuxSplitMenu {
         splits_section.append(Some("Load Splits"), Some("app.load-splits"));
         splits_section.append(Some("Save Splits"), Some("app.save-splits"));
         splits_section.append(Some("Edit Splits"), Some("app.edit-splits"));
+        splits_section.append(Some("Segment Statistics"), Some("app.segment-stats"));
+        splits_section.append(Some("Recently Changed Files"), Some("app.splits-trash"));
+        splits_section.append(Some("Compact Splits File"), Some("app.compact-splits"));
+        splits_section.append(Some("Export as Image"), Some("app.export-image"));
+        splits_section.append(Some("Run Style"), Some("app.run-style"));
+        splits_section.append(Some("Race"), Some("app.race"));
+        splits_section.append(Some("Save Attempt and Quit"), Some("app.save-attempt-quit"));
+        splits_section.append(Some("Adjust Times…"), Some("app.adjust-times"));
+        #[cfg(feature = "racetime")]
+        {
+            splits_section.append(Some("Race Done"), Some("app.racetime-done"));
+            splits_section.append(Some("Forfeit Race"), Some("app.racetime-forfeit"));
+        }
 
         let settings_section = gio::Menu::new();
+        settings_section.append(Some("Detach Timer Display"), Some("app.detach-timer"));
         settings_section.append(Some("Settings"), Some("app.settings"));
         settings_section.append(Some("Keybindings"), Some("app.keybindings"));
+        settings_section.append(Some("Export Setup"), Some("app.export-setup"));
+        settings_section.append(Some("Import Setup"), Some("app.import-setup"));
+        settings_section.append(Some("Import LiveSplit Layout"), Some("app.import-layout"));
 
         let about_section = gio::Menu::new();
+        about_section.append(Some("Diagnostics"), Some("app.diagnostics"));
         about_section.append(Some("About"), Some("app.about"));
 
         menu.append_section(None, &splits_section);
@@ -68,8 +180,26 @@ impl TuxSplitMenu {
         group.add_action(&Self::get_load_action(parent));
         group.add_action(&Self::get_save_action());
         group.add_action(&Self::get_edit_action());
+        group.add_action(&Self::get_segment_stats_action(parent));
+        group.add_action(&Self::get_splits_trash_action(parent));
+        group.add_action(&Self::get_compact_splits_action());
+        group.add_action(&Self::get_export_image_action(parent));
+        group.add_action(&Self::get_run_style_action(parent));
+        group.add_action(&Self::get_race_action(parent));
+        group.add_action(&Self::get_save_attempt_action(parent));
+        group.add_action(&Self::get_adjust_times_action(parent));
+        #[cfg(feature = "racetime")]
+        {
+            group.add_action(&Self::get_racetime_done_action(parent));
+            group.add_action(&Self::get_racetime_forfeit_action(parent));
+        }
+        group.add_action(&Self::get_detach_timer_action(parent));
         group.add_action(&Self::get_settings_action(parent));
         group.add_action(&Self::get_keybinds_action(parent));
+        group.add_action(&Self::get_export_setup_action(parent));
+        group.add_action(&Self::get_import_setup_action(parent));
+        group.add_action(&Self::get_import_layout_action(parent));
+        group.add_action(&Self::get_diagnostics_action(parent));
         group.add_action(&Self::get_about_action(parent));
         button.insert_action_group("app", Some(&group));
 
@@ -105,6 +235,245 @@ impl TuxSplitMenu {
         action
     }
 
+    fn get_segment_stats_action(parent: &adw::ApplicationWindow) -> gio::SimpleAction {
+        let parent_binding = parent.clone();
+        let action = gio::SimpleAction::new("segment-stats", None);
+        action.connect_activate(move |_, _| {
+            let ctx = TuxSplitContext::get_instance();
+            let (outlier_z, show_least_consistent) = {
+                let config = ctx.config();
+                (
+                    config.stats.outlier_z_score,
+                    config.stats.show_least_consistent,
+                )
+            };
+            let shared = ctx.timer();
+            let timer = shared.read().unwrap();
+            let run = timer.run();
+            let method = timer.current_timing_method();
+
+            let dialog = AlertDialog::builder()
+                .heading("Segment Statistics")
+                .body(format!(
+                    "Spread of each segment over its history. Skipped attempts and times \
+                     more than {outlier_z} standard deviations from the mean are left out."
+                ))
+                .default_response("ok")
+                .build();
+
+            let content = GtkBox::new(Vertical, 12);
+            if show_least_consistent {
+                let ranked = least_consistent(run, method, outlier_z, 5);
+                if !ranked.is_empty() {
+                    let title = Label::builder()
+                        .label("Least Consistent")
+                        .halign(Align::Start)
+                        .css_classes(["heading"])
+                        .build();
+                    let ranked_list = ListBox::new();
+                    ranked_list.add_css_class("boxed-list");
+                    for (index, stats) in &ranked {
+                        let row = adw::ActionRow::builder()
+                            .title(run.segment(*index).name())
+                            .subtitle(format_consistency(Some(stats)))
+                            .build();
+                        ranked_list.append(&row);
+                    }
+                    content.append(&title);
+                    content.append(&ranked_list);
+                }
+            }
+
+            let segments_list = ListBox::new();
+            segments_list.add_css_class("boxed-list");
+            for (index, segment) in run.segments().iter().enumerate() {
+                let stats = segment_consistency(run, index, method, outlier_z);
+                let row = adw::ActionRow::builder()
+                    .title(segment.name())
+                    .subtitle(format_consistency(stats.as_ref()))
+                    .build();
+                segments_list.append(&row);
+            }
+            content.append(&segments_list);
+
+            let scrolled = gtk4::ScrolledWindow::builder()
+                .hscrollbar_policy(gtk4::PolicyType::Never)
+                .propagate_natural_height(true)
+                .max_content_height(400)
+                .child(&content)
+                .build();
+            dialog.set_extra_child(Some(&scrolled));
+
+            dialog.add_response("ok", "Okay");
+            dialog.present(Some(&parent_binding));
+        });
+        action
+    }
+
+    fn get_compact_splits_action() -> gio::SimpleAction {
+        let action = gio::SimpleAction::new("compact-splits", None);
+        action.connect_activate(move |_, _| {
+            let ctx = TuxSplitContext::get_instance();
+            let result = {
+                let shared = ctx.timer();
+                let t = shared.read().unwrap();
+                ctx.config().compact_splits(&t)
+            };
+            match result {
+                Ok((before, after)) => {
+                    let saved = before.saturating_sub(after);
+                    show_notice(&format!(
+                        "Splits file compacted from {} to {} ({} saved)",
+                        glib::format_size(before),
+                        glib::format_size(after),
+                        glib::format_size(saved)
+                    ));
+                }
+                Err(err) => show_notice(&format!("Could not compact the splits file: {err}")),
+            }
+        });
+        action
+    }
+
+    fn get_splits_trash_action(parent: &adw::ApplicationWindow) -> gio::SimpleAction {
+        let parent_binding = parent.clone();
+        let action = gio::SimpleAction::new("splits-trash", None);
+        action.connect_activate(move |_, _| {
+            let trash = TuxSplitContext::get_instance().config().trash();
+            let entries = trash.entries();
+
+            let dialog = AlertDialog::builder()
+                .heading("Recently Changed Files")
+                .body(if entries.is_empty() {
+                    "Splits files are copied here before a destructive edit. Nothing yet."
+                } else {
+                    "Splits files as they were before a destructive edit."
+                })
+                .default_response("ok")
+                .build();
+            dialog.add_response("ok", "Okay");
+
+            if !entries.is_empty() {
+                let entries_list = ListBox::new();
+                entries_list.add_css_class("boxed-list");
+                for entry in entries {
+                    let name = entry.original.file_name().map_or_else(
+                        || entry.original.display().to_string(),
+                        |name| name.to_string_lossy().into_owned(),
+                    );
+                    let when = glib::DateTime::from_unix_local(entry.created)
+                        .and_then(|date| date.format("%Y-%m-%d %H:%M"))
+                        .map_or_else(|_| entry.created.to_string(), |date| date.to_string());
+                    let row = adw::ActionRow::builder()
+                        .title(name)
+                        .subtitle(format!("{} · {when}", entry.operation))
+                        .tooltip_text(entry.original.display().to_string())
+                        .build();
+
+                    let restore_button = Button::builder()
+                        .label("Restore")
+                        .valign(Align::Center)
+                        .build();
+                    let dialog_binding = dialog.clone();
+                    let parent_for_restore = parent_binding.clone();
+                    restore_button.connect_clicked(move |_| {
+                        dialog_binding.close();
+                        restore_from_trash(&parent_for_restore, &entry);
+                    });
+                    row.add_suffix(&restore_button);
+                    entries_list.append(&row);
+                }
+
+                let scrolled = gtk4::ScrolledWindow::builder()
+                    .hscrollbar_policy(gtk4::PolicyType::Never)
+                    .propagate_natural_height(true)
+                    .max_content_height(400)
+                    .child(&entries_list)
+                    .build();
+                dialog.set_extra_child(Some(&scrolled));
+            }
+
+            dialog.present(Some(&parent_binding));
+        });
+        action
+    }
+
+    fn get_export_image_action(parent: &adw::ApplicationWindow) -> gio::SimpleAction {
+        let parent_binding = parent.clone();
+        let action = gio::SimpleAction::new("export-image", None);
+        action.connect_activate(move |_, _| present_image_export(&parent_binding));
+        action
+    }
+
+    fn get_run_style_action(parent: &adw::ApplicationWindow) -> gio::SimpleAction {
+        let parent_binding = parent.clone();
+        let action = gio::SimpleAction::new("run-style", None);
+        action.connect_activate(move |_, _| {
+            present_run_style_editor(&parent_binding);
+        });
+        action
+    }
+
+    fn get_detach_timer_action(parent: &adw::ApplicationWindow) -> gio::SimpleAction {
+        let parent_binding = parent.clone();
+        let action = gio::SimpleAction::new("detach-timer", None);
+        action.connect_activate(move |_, _| {
+            present_detached_timer(&parent_binding);
+        });
+        action
+    }
+
+    fn get_race_action(parent: &adw::ApplicationWindow) -> gio::SimpleAction {
+        let parent_binding = parent.clone();
+        let action = gio::SimpleAction::new("race", None);
+        action.connect_activate(move |_, _| {
+            present_race_dialog(&parent_binding);
+        });
+        action
+    }
+
+    fn get_save_attempt_action(parent: &adw::ApplicationWindow) -> gio::SimpleAction {
+        let parent_binding = parent.clone();
+        let action = gio::SimpleAction::new("save-attempt-quit", None);
+        action.connect_activate(move |_, _| {
+            if let Err(reason) = save_attempt_and_quit(&parent_binding) {
+                show_message(&parent_binding, "Could Not Save Attempt", &reason);
+            }
+        });
+        action
+    }
+
+    fn get_adjust_times_action(parent: &adw::ApplicationWindow) -> gio::SimpleAction {
+        let parent_binding = parent.clone();
+        let action = gio::SimpleAction::new("adjust-times", None);
+        action.connect_activate(move |_, _| {
+            if let Err(reason) = present_attempt_editor(&parent_binding) {
+                show_message(&parent_binding, "Could Not Adjust Times", &reason);
+            }
+        });
+        action
+    }
+
+    #[cfg(feature = "racetime")]
+    fn get_racetime_done_action(parent: &adw::ApplicationWindow) -> gio::SimpleAction {
+        let parent_binding = parent.clone();
+        let action = gio::SimpleAction::new("racetime-done", None);
+        action.connect_activate(move |_, _| {
+            send_done(&parent_binding);
+        });
+        action
+    }
+
+    #[cfg(feature = "racetime")]
+    fn get_racetime_forfeit_action(parent: &adw::ApplicationWindow) -> gio::SimpleAction {
+        let parent_binding = parent.clone();
+        let action = gio::SimpleAction::new("racetime-forfeit", None);
+        action.connect_activate(move |_, _| {
+            send_forfeit(&parent_binding);
+        });
+        action
+    }
+
     fn get_load_action(parent: &adw::ApplicationWindow) -> gio::SimpleAction {
         let parent_binding = parent.clone();
         let action = gio::SimpleAction::new("load-splits", None);
@@ -136,9 +505,16 @@ impl TuxSplitMenu {
                     let ctx = TuxSplitContext::get_instance();
                     if let Ok(mut c) = ctx.config_mut() {
                         c.set_splits_path(path);
-                        if let Some(run) = c.parse_run() {
+                        if let Some(run) = c.load_run() {
+                            c.begin_session(&run);
+                            let recovery = c.splits_recovery();
+                            refresh_integrity_badge(c.splits_integrity());
                             drop(c); // Set run needs write access to config
-                            ctx.set_run(run);
+                            if let Err(err) = ctx.set_run(run) {
+                                show_notice(&format!("The splits were not opened: {err}"));
+                            } else if let Some(recovery) = recovery {
+                                show_notice(&recovery.describe());
+                            }
                         }
                     }
                 }
@@ -154,35 +530,125 @@ impl TuxSplitMenu {
     fn get_keybinds_action(parent: &adw::ApplicationWindow) -> gio::SimpleAction {
         let parent_for_keybinds = parent.clone();
         let action = gio::SimpleAction::new("keybindings", None);
+        action.connect_activate(move |_, _| {
+            present_keybindings(&parent_for_keybinds);
+        });
+        action
+    }
+
+    fn get_export_setup_action(parent: &adw::ApplicationWindow) -> gio::SimpleAction {
+        let parent_binding = parent.clone();
+        let action = gio::SimpleAction::new("export-setup", None);
         action.connect_activate(move |_, _| {
             let dialog = AlertDialog::builder()
-                .heading("Keybindings")
-                .body("Current keybinds are not modifiable yet.")
-                .default_response("ok")
+                .heading("Export Setup")
+                .body(
+                    "Bundle your layout settings, theme and stylesheet into a single \
+                     archive to share or back up. The current splits can be included too.",
+                )
+                .default_response("splits")
+                .close_response("cancel")
                 .build();
+            dialog.add_response("cancel", "Cancel");
+            dialog.add_response("settings", "Settings Only");
+            dialog.add_response("splits", "Include Splits");
+            dialog.set_response_appearance("splits", adw::ResponseAppearance::Suggested);
+
+            let parent_for_chooser = parent_binding.clone();
+            dialog.connect_response(None, move |_, response| {
+                if response != "cancel" {
+                    choose_export_path(&parent_for_chooser, response == "splits");
+                }
+            });
+            dialog.present(Some(&parent_binding));
+        });
+        action
+    }
 
-            let keybinds_list = ListBox::new();
-            keybinds_list.add_css_class("boxed-list");
-            let keybinds = vec![
-                ("Start / Split", "Numpad 1"),
-                ("Skip Split", "Numpad 2"),
-                ("Reset", "Numpad 3"),
-                ("Previous Comparison", "Numpad 4"),
-                ("Pause", "Numpad 5"),
-                ("Next Comparison", "Numpad 6"),
-                ("Undo", "Numpad 8"),
-            ];
-            for (action, key) in keybinds {
-                let key_label = Label::new(Some(key));
-                let row = adw::ActionRow::builder().title(action).build();
-                row.add_suffix(&key_label);
-                keybinds_list.append(&row);
-            }
+    fn get_import_setup_action(parent: &adw::ApplicationWindow) -> gio::SimpleAction {
+        let parent_binding = parent.clone();
+        let action = gio::SimpleAction::new("import-setup", None);
+        action.connect_activate(move |_, _| {
+            let file_chooser = FileChooserDialog::new(
+                Some("Import Setup"),
+                Some(&parent_binding),
+                gtk4::FileChooserAction::Open,
+                &[
+                    ("Open", gtk4::ResponseType::Ok),
+                    ("Cancel", gtk4::ResponseType::Cancel),
+                ],
+            );
+            file_chooser.add_filter(&bundle_filter());
 
-            dialog.set_extra_child(Some(&keybinds_list));
+            let parent_for_result = parent_binding.clone();
+            file_chooser.connect_response(move |dialog, response| {
+                if response == gtk4::ResponseType::Ok
+                    && let Some(file) = dialog.file()
+                    && let Some(path) = file.path()
+                {
+                    let result = std::fs::read(&path)
+                        .map_err(BundleError::Io)
+                        .and_then(|bytes| parse_bundle(&bytes))
+                        .and_then(|bundle| {
+                            let components = bundle.components().join(", ");
+                            apply_setup_bundle(bundle).map(|()| components)
+                        });
+                    match result {
+                        Ok(components) => show_message(
+                            &parent_for_result,
+                            "Setup Imported",
+                            &format!("Imported: {components}"),
+                        ),
+                        Err(err) => {
+                            show_message(&parent_for_result, "Import Failed", &err.to_string());
+                        }
+                    }
+                }
+                dialog.destroy();
+            });
 
-            dialog.add_response("ok", "Okay");
-            dialog.present(Some(&parent_for_keybinds));
+            file_chooser.set_modal(true);
+            file_chooser.present();
+        });
+        action
+    }
+
+    fn get_import_layout_action(parent: &adw::ApplicationWindow) -> gio::SimpleAction {
+        let parent_binding = parent.clone();
+        let action = gio::SimpleAction::new("import-layout", None);
+        action.connect_activate(move |_, _| {
+            let file_chooser = FileChooserDialog::new(
+                Some("Import LiveSplit Layout"),
+                Some(&parent_binding),
+                gtk4::FileChooserAction::Open,
+                &[
+                    ("Open", gtk4::ResponseType::Ok),
+                    ("Cancel", gtk4::ResponseType::Cancel),
+                ],
+            );
+            let filter = FileFilter::new();
+            filter.set_name(Some("LiveSplit Layout (*.lsl)"));
+            filter.add_pattern(&format!("*.{LAYOUT_EXTENSION}"));
+            file_chooser.add_filter(&filter);
+
+            let parent_for_result = parent_binding.clone();
+            file_chooser.connect_response(move |dialog, response| {
+                if response == gtk4::ResponseType::Ok
+                    && let Some(file) = dialog.file()
+                    && let Some(path) = file.path()
+                {
+                    match read_layout(&path) {
+                        Ok(import) => confirm_layout_import(&parent_for_result, import),
+                        Err(err) => {
+                            show_message(&parent_for_result, "Import Failed", &err.to_string());
+                        }
+                    }
+                }
+                dialog.destroy();
+            });
+
+            file_chooser.set_modal(true);
+            file_chooser.present();
         });
         action
     }
@@ -210,25 +676,312 @@ impl TuxSplitMenu {
                 .comments("A GTK-based LiveSplit timer application.")
                 .license_type(gtk4::License::MitX11)
                 .website("https://github.com/AntonioRodriguezRuiz/tuxsplit")
+                .debug_info(diagnostics_text())
+                .debug_info_filename("tuxsplit-diagnostics.txt")
                 .build();
             about_dialog.present(Some(&parent_for_about));
         });
         action
     }
+
+    fn get_diagnostics_action(parent: &adw::ApplicationWindow) -> gio::SimpleAction {
+        let parent_for_diagnostics = parent.clone();
+        let action = gio::SimpleAction::new("diagnostics", None);
+        action.connect_activate(move |_, _| {
+            let dialog = AlertDialog::builder()
+                .heading("Diagnostics")
+                .body("Where TuxSplit is reading its setup from.")
+                .default_response("ok")
+                .build();
+
+            let entries_list = ListBox::new();
+            entries_list.add_css_class("boxed-list");
+            for (name, value) in diagnostics_entries() {
+                let row = adw::ActionRow::builder()
+                    .title(name)
+                    .subtitle(&value)
+                    .subtitle_selectable(true)
+                    .build();
+                row.add_css_class("property");
+
+                let copy_button = Button::builder()
+                    .icon_name("edit-copy-symbolic")
+                    .tooltip_text("Copy")
+                    .valign(Align::Center)
+                    .css_classes(["flat"])
+                    .build();
+                copy_button.connect_clicked(move |button| {
+                    button.clipboard().set_text(&value);
+                });
+                row.add_suffix(&copy_button);
+                entries_list.append(&row);
+            }
+
+            dialog.set_extra_child(Some(&entries_list));
+
+            dialog.add_response("ok", "Okay");
+            dialog.present(Some(&parent_for_diagnostics));
+        });
+        action
+    }
 }
 
-fn temporary_keybinds_disable(widget: &PreferencesDialog) {
-    // Disable and enable keybinds while settings are open
+/// Resolved paths and runtime state that are otherwise opaque when a setup
+/// does not behave as expected. Read from the live config.
+fn diagnostics_entries() -> Vec<(&'static str, String)> {
+    let ctx = TuxSplitContext::get_instance();
+    let config = ctx.config();
+
+    let config_path = config_file_path();
+    let splits_path = config
+        .splits_path()
+        .map_or_else(|| "None".to_owned(), |p| p.display().to_string());
+    let auto_splitter_path = config.general.auto_splitter.as_ref().map_or_else(
+        || "None".to_owned(),
+        |p| config.resolve(p).display().to_string(),
+    );
+    let theme = match crate::gresource_path() {
+        Some(bundle) => format!("{} (in {})", crate::RESOURCE_CSS, bundle.display()),
+        None => crate::RESOURCE_CSS.to_owned(),
+    };
+    let hotkeys = if hotkeys_registered() {
+        "Registered"
+    } else {
+        "Unavailable"
+    };
+    let integrity = if let Some(recovery) = config.splits_recovery() {
+        recovery.describe()
+    } else if let Some(status) = config.splits_integrity() {
+        status.describe()
+    } else if config.general.splits.is_some() {
+        "Not checked".to_owned()
+    } else {
+        "None".to_owned()
+    };
+    let backend = gdk::Display::default()
+        .map_or_else(|| "No display".to_owned(), |d| d.type_().name().to_owned());
+
+    vec![
+        (
+            "Config File",
+            format!("{} ({})", config_path.display(), config_file_status()),
+        ),
+        ("Splits File", splits_path),
+        ("Splits Integrity", integrity),
+        ("Auto Splitter", auto_splitter_path),
+        ("Theme", theme),
+        ("Hotkeys", hotkeys.to_owned()),
+        ("Log Level", crate::LOG_LEVEL.to_string()),
+        ("Display Backend", backend),
+    ]
+}
+
+fn diagnostics_text() -> String {
+    diagnostics_entries()
+        .into_iter()
+        .map(|(name, value)| format!("{name}: {value}\n"))
+        .collect()
+}
+
+fn bundle_filter() -> FileFilter {
+    let filter = FileFilter::new();
+    filter.set_name(Some("TuxSplit Setup (*.tuxsplit)"));
+    filter.add_pattern(&format!("*.{BUNDLE_EXTENSION}"));
+    filter
+}
+
+fn choose_export_path(parent: &adw::ApplicationWindow, include_splits: bool) {
+    let file_chooser = FileChooserDialog::new(
+        Some("Export Setup"),
+        Some(parent),
+        gtk4::FileChooserAction::Save,
+        &[
+            ("Save", gtk4::ResponseType::Ok),
+            ("Cancel", gtk4::ResponseType::Cancel),
+        ],
+    );
+    file_chooser.add_filter(&bundle_filter());
+    file_chooser.set_current_name(&format!("tuxsplit-setup.{BUNDLE_EXTENSION}"));
+
+    let parent_binding = parent.clone();
+    file_chooser.connect_response(move |dialog, response| {
+        if response == gtk4::ResponseType::Ok
+            && let Some(file) = dialog.file()
+            && let Some(path) = file.path()
+        {
+            let ctx = TuxSplitContext::get_instance();
+            let bytes = {
+                let shared_timer = ctx.timer();
+                let timer = shared_timer.read().unwrap();
+                bundle_to_bytes(&export_bundle(
+                    &ctx.config(),
+                    include_splits.then_some(&*timer),
+                ))
+            };
+            let result = bytes.and_then(|bytes| {
+                let span = splits_io_span("export", &path);
+                let _entered = span.enter();
+                span.record("bytes", bytes.len());
+                std::fs::write(&path, bytes).map_err(BundleError::Io)
+            });
+            if let Err(err) = result {
+                show_message(&parent_binding, "Export Failed", &err.to_string());
+            }
+        }
+        dialog.destroy();
+    });
+
+    file_chooser.set_modal(true);
+    file_chooser.present();
+}
+
+/// Applies an already validated bundle. The settings are claimed and the
+/// splits written next to the config first, so a failure there leaves the
+/// current setup untouched.
+fn apply_setup_bundle(bundle: ValidatedBundle) -> Result<(), BundleError> {
     let ctx = TuxSplitContext::get_instance();
+    let splits_dir = config_file_path().parent().map_or_else(
+        || std::path::PathBuf::from("splits"),
+        |dir| dir.join("splits"),
+    );
+    let mut c = ctx.config_mut().map_err(|_| BundleError::ConfigBusy)?;
+    let splits = bundle
+        .splits
+        .map(|splits| write_splits(&splits, &splits_dir).map(|path| (path, splits.run)))
+        .transpose()?;
+
+    if let Some(imported) = bundle.config {
+        c.apply_imported(imported);
+        register_hotkeys(&c);
+    }
+    if let Some(theme) = bundle.theme {
+        c.theme = theme;
+    }
+    if let Some((path, run)) = &splits {
+        c.set_splits_path(path.clone());
+        c.begin_session(run);
+        refresh_integrity_badge(c.splits_integrity());
+    }
+    // For the imported splits, or the loaded ones when the bundle has none
+    if let Some(css) = bundle.css
+        && let Some(state) = c.splits_state_mut()
     {
+        state.css = Some(css);
+    }
+    drop(c);
+
+    apply_theme(&ctx.config().theme);
+    apply_timer_font(&ctx.config());
+    if let Some((_, run)) = splits {
+        ctx.set_run(run).map_err(BundleError::InvalidSplits)?;
+    } else {
+        ctx.config()
+            .configure_timer(&mut ctx.timer().write().unwrap());
+        ctx.emit_run_changed();
+    }
+    Ok(())
+}
+
+/// Shows what a layout import would change, and only writes the config once
+/// the user agrees.
+fn confirm_layout_import(parent: &adw::ApplicationWindow, import: LayoutImport) {
+    let dialog = AlertDialog::builder()
+        .heading("Import LiveSplit Layout?")
+        .body(import.summary())
+        .default_response("cancel")
+        .close_response("cancel")
+        .build();
+    dialog.add_response("cancel", "Cancel");
+    dialog.add_response("apply", "Apply");
+    dialog.set_response_appearance("apply", adw::ResponseAppearance::Suggested);
+
+    dialog.connect_response(None, move |_, response| {
+        if response != "apply" {
+            return;
+        }
+        let ctx = TuxSplitContext::get_instance();
         if let Ok(mut c) = ctx.config_mut() {
-            c.disable_hotkey_system();
+            import.apply(&mut c);
+        }
+        ctx.emit_run_changed();
+    });
+    dialog.present(Some(parent));
+}
+
+/// Puts a trashed copy back in place, reloading it when it's the loaded
+/// splits file. Refused mid-attempt, like loading other splits would be.
+fn restore_from_trash(parent: &adw::ApplicationWindow, entry: &TrashEntry) {
+    let ctx = TuxSplitContext::get_instance();
+    let running = {
+        let shared = ctx.timer();
+        let timer = shared.read().unwrap();
+        timer.current_phase() != TimerPhase::NotRunning
+    };
+    if running {
+        show_message(
+            parent,
+            "Attempt in Progress",
+            "Reset or finish the current attempt before restoring splits.",
+        );
+        return;
+    }
+
+    let result = ctx.config().trash().restore(entry);
+    if let Err(err) = result {
+        show_message(parent, "Restore Failed", &err.to_string());
+        return;
+    }
+
+    let reloaded = {
+        let Ok(mut c) = ctx.config_mut() else {
+            return;
+        };
+        let run = (c.splits_path().as_ref() == Some(&entry.original))
+            .then(|| c.load_run())
+            .flatten();
+        if let Some(run) = &run {
+            c.begin_session(run);
+            refresh_integrity_badge(c.splits_integrity());
         }
+        run
+    };
+    if let Some(run) = reloaded
+        && let Err(err) = ctx.set_run(run)
+    {
+        show_message(parent, "Restore Failed", &err);
+        return;
+    }
+    show_message(
+        parent,
+        "Splits Restored",
+        &format!(
+            "{} is back as it was before \"{}\".",
+            entry.original.display(),
+            entry.operation
+        ),
+    );
+}
+
+fn show_message(parent: &adw::ApplicationWindow, heading: &str, body: &str) {
+    let dialog = AlertDialog::builder()
+        .heading(heading)
+        .body(body)
+        .default_response("ok")
+        .build();
+    dialog.add_response("ok", "Okay");
+    dialog.present(Some(parent));
+}
+
+fn temporary_keybinds_disable(widget: &PreferencesDialog) {
+    // Disable and enable keybinds while settings are open
+    let ctx = TuxSplitContext::get_instance();
+    if let Ok(c) = ctx.try_config() {
+        set_hotkeys_suspended(&c, true);
     }
     widget.connect_closed(move |_| {
         let ctx = TuxSplitContext::get_instance();
-        if let Ok(mut c) = ctx.config_mut() {
-            c.enable_hotkey_system();
+        if let Ok(c) = ctx.try_config() {
+            set_hotkeys_suspended(&c, false);
         }
     });
 }
@@ -0,0 +1,878 @@
+//! Focus-aware hotkey handling.
+//!
+//! With `behavior.hotkeys-only-unfocused`, the global `HotkeySystem` is
+//! switched off while any TuxSplit window has focus and the same bindings are
+//! handled as regular key presses instead, so typing in the app can't split
+//! by accident. With `behavior.suppress-hotkeys-while-typing` (the default),
+//! global hotkeys are also paused whenever a text field has keyboard focus.
+//! Focus is tracked per window rather than through the application's active
+//! window, which isn't cleared when focus moves to another application (e.g.
+//! a game on a second monitor).
+
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use adw::ApplicationWindow;
+use adw::prelude::*;
+use glib::Propagation;
+use gtk4::{EventControllerKey, Image, gdk};
+use livesplit_core::hotkey::{Hook, Hotkey, KeyCode, Modifiers};
+use livesplit_core::{HotkeyConfig, HotkeySystem, SharedTimer};
+use livesplit_core::{TimerPhase, TimingMethod};
+use tracing::error;
+
+pub use crate::config::HotkeyAction;
+use crate::config::{Behavior, Config};
+use crate::context::TuxSplitContext;
+use crate::ui::keybindings::toggle_keybindings;
+use crate::ui::offset_conflict::note_start_issued;
+use crate::ui::setting_undo::{request_setting_undo, undo_setting_change};
+use crate::ui::timer::modal::offer_action;
+use crate::utils::comparisons::switch_comparison;
+use crate::utils::trace_timing::{command_span, record_command};
+
+thread_local! {
+    static TYPING: RefCell<TypingSuppression> = RefCell::new(TypingSuppression::default());
+    static TRACKED_WINDOWS: RefCell<HashSet<usize>> = RefCell::new(HashSet::new());
+    static PAUSED_INDICATOR: RefCell<Option<Image>> = const { RefCell::new(None) };
+    static GLOBAL_HOTKEYS: RefCell<GlobalHotkeys> = RefCell::new(GlobalHotkeys::default());
+}
+
+/// What triggered a [`HotkeyAction`], for the timing traces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionSource {
+    /// A key press handled by a focused TuxSplit window.
+    Keyboard,
+    Pedal,
+    SplitButton,
+    /// Held back while a dialog was open and run once it closed.
+    HeldDuringDialog,
+    /// A global hotkey or the auto splitter, whichever it was, noticed by
+    /// the refresh tick.
+    Outside,
+    /// The auto splitter, while global hotkeys are off.
+    AutoSplitter,
+}
+
+/// Whether the global hotkey system may be active.
+pub fn global_hotkeys_allowed(only_unfocused: bool, window_focused: bool) -> bool {
+    !(only_unfocused && window_focused)
+}
+
+/// What currently decides which hotkeys listen. Never saved with the config.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct HotkeyState {
+    /// Hotkeys are off, e.g. while the preferences are open.
+    pub suspended: bool,
+    /// Any TuxSplit window has focus.
+    pub window_focused: bool,
+    /// A text field in TuxSplit has keyboard focus.
+    pub typing: bool,
+    /// A dialog freezing the display is open. The global hotkey system can't
+    /// hold actions back, so it is paused meanwhile.
+    pub modal_pending: bool,
+}
+
+impl HotkeyState {
+    /// Whether the configured hotkeys should be handled by the focused window
+    /// instead of the global hotkey system.
+    pub fn local_active(&self, behavior: &Behavior) -> bool {
+        !self.suspended
+            && !self.typing
+            && (behavior.hotkeys_only_unfocused || behavior.window_hotkeys)
+            && self.window_focused
+    }
+
+    /// Whether the global hotkey system should currently be listening.
+    pub fn global_active(&self, behavior: &Behavior) -> bool {
+        !self.suspended
+            && !self.typing
+            && !self.modal_pending
+            && !behavior.window_hotkeys
+            && global_hotkeys_allowed(behavior.hotkeys_only_unfocused, self.window_focused)
+    }
+}
+
+/// The registered global hotkeys and the state deciding whether they listen.
+#[derive(Default)]
+struct GlobalHotkeys {
+    system: Option<HotkeySystem>,
+    comparisons: Option<ComparisonHotkeys>,
+    state: HotkeyState,
+}
+
+impl GlobalHotkeys {
+    /// (De)activates the global hotkeys according to the state and the hotkey
+    /// behavior options of `config`.
+    fn refresh(&mut self, config: &Config) {
+        let active = self.state.global_active(&config.behavior);
+        if let Some(comparisons) = &self.comparisons {
+            comparisons.set_active(active);
+            comparisons.set_min_attempts(config.general.min_attempts_for_generated);
+        }
+        let Some(system) = self.system.as_mut() else {
+            return;
+        };
+        if active {
+            system.activate();
+        } else {
+            system.deactivate();
+        }
+    }
+}
+
+/// Registers the global hotkeys of `config` for `timer`. Returns `None` when
+/// the hotkey system can't be created.
+pub fn create_hotkey_system(config: &Config, timer: SharedTimer) -> Option<()> {
+    if config.behavior.window_hotkeys {
+        // Handled by the window, no global hotkeys to register
+        return Some(());
+    }
+    let hotkeys = config.hotkeys.active();
+    let comparisons = ComparisonHotkeys::new(
+        timer.clone(),
+        hotkeys,
+        config.behavior.undo_setting_hotkey,
+        config.general.min_attempts_for_generated,
+    );
+    let system = HotkeySystem::with_config(timer, without_comparison_hotkeys(*hotkeys)).ok()?;
+    GLOBAL_HOTKEYS.with_borrow_mut(|global| {
+        global.system = Some(system);
+        global.comparisons = comparisons;
+        global.refresh(config);
+    });
+    Some(())
+}
+
+/// Hands the active bindings of `config` over to the global hotkeys.
+pub fn register_hotkeys(config: &Config) {
+    let hotkeys = *config.hotkeys.active();
+    GLOBAL_HOTKEYS.with_borrow_mut(|global| {
+        if let Some(system) = global.system.as_mut()
+            && system
+                .set_config(without_comparison_hotkeys(hotkeys))
+                .is_err()
+        {
+            error!("Could not register the hotkeys");
+        }
+        if let Some(comparisons) = global.comparisons.as_mut() {
+            comparisons.set_config(&hotkeys, config.behavior.undo_setting_hotkey);
+        }
+        global.refresh(config);
+    });
+}
+
+/// Re-applies the hotkey behavior options of `config`.
+pub fn refresh_hotkey_activation(config: &Config) {
+    GLOBAL_HOTKEYS.with_borrow_mut(|global| global.refresh(config));
+}
+
+/// Changes the hotkey state with `update`, (de)activating the global hotkeys
+/// if it changed.
+fn update_hotkey_state(config: &Config, update: impl FnOnce(&mut HotkeyState)) {
+    GLOBAL_HOTKEYS.with_borrow_mut(|global| {
+        let before = global.state;
+        update(&mut global.state);
+        if global.state != before {
+            global.refresh(config);
+        }
+    });
+}
+
+/// Turns every hotkey off, or back on.
+pub fn set_hotkeys_suspended(config: &Config, suspended: bool) {
+    update_hotkey_state(config, |state| state.suspended = suspended);
+}
+
+/// Records whether a dialog freezing the display is open.
+pub fn set_modal_pending(config: &Config, pending: bool) {
+    update_hotkey_state(config, |state| state.modal_pending = pending);
+}
+
+pub fn hotkey_state() -> HotkeyState {
+    GLOBAL_HOTKEYS.with_borrow(|global| global.state)
+}
+
+pub fn hotkeys_registered() -> bool {
+    GLOBAL_HOTKEYS.with_borrow(|global| global.system.is_some())
+}
+
+/// Translates a GDK key name into the name of the matching livesplit `KeyCode`.
+pub fn key_code_name(gdk_name: &str) -> Option<String> {
+    if let Some(rest) = gdk_name.strip_prefix("KP_") {
+        let name = match rest {
+            "0" | "Insert" => "Numpad0",
+            "1" | "End" => "Numpad1",
+            "2" | "Down" => "Numpad2",
+            "3" | "Next" | "Page_Down" => "Numpad3",
+            "4" | "Left" => "Numpad4",
+            "5" | "Begin" => "Numpad5",
+            "6" | "Right" => "Numpad6",
+            "7" | "Home" => "Numpad7",
+            "8" | "Up" => "Numpad8",
+            "9" | "Prior" | "Page_Up" => "Numpad9",
+            "Decimal" | "Delete" => "NumpadDecimal",
+            "Add" => "NumpadAdd",
+            "Subtract" => "NumpadSubtract",
+            "Multiply" => "NumpadMultiply",
+            "Divide" => "NumpadDivide",
+            "Enter" => "NumpadEnter",
+            _ => return None,
+        };
+        return Some(name.to_owned());
+    }
+
+    let mut chars = gdk_name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return if c.is_ascii_alphabetic() {
+            Some(format!("Key{}", c.to_ascii_uppercase()))
+        } else if c.is_ascii_digit() {
+            Some(format!("Digit{c}"))
+        } else {
+            None
+        };
+    }
+
+    if let Some(n) = gdk_name.strip_prefix('F')
+        && n.parse::<u8>().is_ok_and(|n| (1..=24).contains(&n))
+    {
+        return Some(gdk_name.to_owned());
+    }
+
+    let name = match gdk_name {
+        "space" => "Space",
+        "Return" => "Enter",
+        "Escape" => "Escape",
+        "Tab" => "Tab",
+        "BackSpace" => "Backspace",
+        "Up" => "ArrowUp",
+        "Down" => "ArrowDown",
+        "Left" => "ArrowLeft",
+        "Right" => "ArrowRight",
+        "Home" => "Home",
+        "End" => "End",
+        "Page_Up" => "PageUp",
+        "Page_Down" => "PageDown",
+        "Insert" => "Insert",
+        "Delete" => "Delete",
+        _ => return None,
+    };
+    Some(name.to_owned())
+}
+
+fn modifiers_from_gdk(state: gdk::ModifierType) -> Modifiers {
+    let mut modifiers = Modifiers::empty();
+    if state.contains(gdk::ModifierType::SHIFT_MASK) {
+        modifiers |= Modifiers::SHIFT;
+    }
+    if state.contains(gdk::ModifierType::CONTROL_MASK) {
+        modifiers |= Modifiers::CONTROL;
+    }
+    if state.contains(gdk::ModifierType::ALT_MASK) {
+        modifiers |= Modifiers::ALT;
+    }
+    if state.intersects(gdk::ModifierType::SUPER_MASK | gdk::ModifierType::META_MASK) {
+        modifiers |= Modifiers::META;
+    }
+    modifiers
+}
+
+/// Every action with the hotkey `config` binds it to.
+pub fn bindings(config: &HotkeyConfig) -> [(HotkeyAction, Option<Hotkey>); 9] {
+    [
+        (HotkeyAction::Split, config.split),
+        (HotkeyAction::Reset, config.reset),
+        (HotkeyAction::Undo, config.undo),
+        (HotkeyAction::Skip, config.skip),
+        (HotkeyAction::Pause, config.pause),
+        (HotkeyAction::UndoAllPauses, config.undo_all_pauses),
+        (HotkeyAction::PreviousComparison, config.previous_comparison),
+        (HotkeyAction::NextComparison, config.next_comparison),
+        (
+            HotkeyAction::ToggleTimingMethod,
+            config.toggle_timing_method,
+        ),
+    ]
+}
+
+/// The configured action bound to `pressed`, if any.
+pub fn action_for(config: &HotkeyConfig, pressed: Hotkey) -> Option<HotkeyAction> {
+    bindings(config)
+        .into_iter()
+        .find_map(|(action, hotkey)| (hotkey == Some(pressed)).then_some(action))
+}
+
+/// `config` without the comparison hotkeys, for livesplit-core's
+/// `HotkeySystem`. Those are registered by `ComparisonHotkeys` instead.
+pub fn without_comparison_hotkeys(mut config: HotkeyConfig) -> HotkeyConfig {
+    config.previous_comparison = None;
+    config.next_comparison = None;
+    config
+}
+
+/// Global previous/next comparison hotkeys. They are registered outside the
+/// `HotkeySystem` so the cycle can skip generated comparisons without enough
+/// history, the same way the local hotkeys do. The hotkey undoing a setting
+/// switch, which isn't one of livesplit-core's, is registered here as well.
+pub struct ComparisonHotkeys {
+    hook: Hook,
+    registered: Vec<Hotkey>,
+    timer: SharedTimer,
+    active: Arc<AtomicBool>,
+    min_attempts: Arc<AtomicU32>,
+}
+
+impl ComparisonHotkeys {
+    pub fn new(
+        timer: SharedTimer,
+        config: &HotkeyConfig,
+        undo_setting: Option<Hotkey>,
+        min_attempts: u32,
+    ) -> Option<Self> {
+        let hook = Hook::new().ok()?;
+        let mut this = Self {
+            hook,
+            registered: Vec::new(),
+            timer,
+            active: Arc::new(AtomicBool::new(true)),
+            min_attempts: Arc::new(AtomicU32::new(min_attempts)),
+        };
+        this.set_config(config, undo_setting);
+        Some(this)
+    }
+
+    /// Re-registers the comparison hotkeys from `config`, and `undo_setting`.
+    pub fn set_config(&mut self, config: &HotkeyConfig, undo_setting: Option<Hotkey>) {
+        for hotkey in self.registered.drain(..) {
+            let _ = self.hook.unregister(hotkey);
+        }
+        for (hotkey, forward) in [
+            (config.previous_comparison, false),
+            (config.next_comparison, true),
+        ] {
+            let Some(hotkey) = hotkey else {
+                continue;
+            };
+            let timer = self.timer.clone();
+            let active = self.active.clone();
+            let min_attempts = self.min_attempts.clone();
+            let registered = self.hook.register(hotkey, move || {
+                if active.load(Ordering::Relaxed)
+                    && let Ok(mut timer) = timer.write()
+                {
+                    switch_comparison(&mut timer, forward, min_attempts.load(Ordering::Relaxed));
+                }
+            });
+            match registered {
+                Ok(()) => self.registered.push(hotkey),
+                Err(err) => error!("Could not register comparison hotkey {hotkey}: {err}"),
+            }
+        }
+        if let Some(hotkey) = undo_setting {
+            let active = self.active.clone();
+            let registered = self.hook.register(hotkey, move || {
+                if active.load(Ordering::Relaxed) {
+                    request_setting_undo();
+                }
+            });
+            match registered {
+                Ok(()) => self.registered.push(hotkey),
+                Err(err) => error!("Could not register the undo setting hotkey {hotkey}: {err}"),
+            }
+        }
+    }
+
+    pub fn set_active(&self, active: bool) {
+        self.active.store(active, Ordering::Relaxed);
+    }
+
+    pub fn set_min_attempts(&self, min_attempts: u32) {
+        self.min_attempts.store(min_attempts, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusEvent {
+    /// A text field in this window gained keyboard focus.
+    Editable(usize),
+    /// This window lost focus or moved it to something that isn't a text field.
+    NonEditable(usize),
+    /// This window is gone.
+    Closed(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuppressionChange {
+    Suppress,
+    Resume,
+}
+
+/// Decides when typing should pause the global hotkeys, independently of GTK.
+///
+/// Windows are tracked by id, so focus moving from one text field to another
+/// in a different window (focus-in arriving before the focus-out) keeps a
+/// single suppression, and a closed window can never leave it behind.
+#[derive(Debug, Default)]
+pub struct TypingSuppression {
+    typing_in: BTreeSet<usize>,
+    enabled: bool,
+    suppressed: bool,
+}
+
+impl TypingSuppression {
+    pub fn is_suppressed(&self) -> bool {
+        self.suppressed
+    }
+
+    pub fn handle(&mut self, event: FocusEvent) -> Option<SuppressionChange> {
+        match event {
+            FocusEvent::Editable(window) => {
+                self.typing_in.insert(window);
+            }
+            FocusEvent::NonEditable(window) | FocusEvent::Closed(window) => {
+                self.typing_in.remove(&window);
+            }
+        }
+        self.settle()
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) -> Option<SuppressionChange> {
+        self.enabled = enabled;
+        self.settle()
+    }
+
+    fn settle(&mut self) -> Option<SuppressionChange> {
+        let wanted = self.enabled && !self.typing_in.is_empty();
+        match (self.suppressed, wanted) {
+            (false, true) => {
+                self.suppressed = true;
+                Some(SuppressionChange::Suppress)
+            }
+            (true, false) => {
+                self.suppressed = false;
+                Some(SuppressionChange::Resume)
+            }
+            _ => None,
+        }
+    }
+}
+
+pub fn run_action(action: HotkeyAction, source: ActionSource) {
+    let ctx = TuxSplitContext::get_instance();
+    let min_attempts = ctx.config().general.min_attempts_for_generated;
+    let shared = ctx.timer();
+    let mut timer = shared.write().unwrap();
+    let span = command_span(action, source, timer.current_phase());
+    let _entered = span.enter();
+    match action {
+        HotkeyAction::Split => {
+            note_start_issued();
+            timer.split_or_start();
+        }
+        HotkeyAction::Reset => {
+            if timer.current_phase() != TimerPhase::NotRunning {
+                timer.reset(true);
+            }
+        }
+        HotkeyAction::Undo => {
+            timer.undo_split();
+        }
+        HotkeyAction::Skip => {
+            timer.skip_split();
+        }
+        HotkeyAction::Pause => {
+            note_start_issued();
+            timer.toggle_pause_or_start();
+        }
+        HotkeyAction::UndoAllPauses => {
+            timer.undo_all_pauses();
+        }
+        HotkeyAction::PreviousComparison => switch_comparison(&mut timer, false, min_attempts),
+        HotkeyAction::NextComparison => switch_comparison(&mut timer, true, min_attempts),
+        HotkeyAction::ToggleTimingMethod => {
+            let method = match timer.current_timing_method() {
+                TimingMethod::RealTime => TimingMethod::GameTime,
+                TimingMethod::GameTime => TimingMethod::RealTime,
+            };
+            timer.set_current_timing_method(method);
+        }
+    }
+    record_command(&span, timer.current_phase(), timer.current_split_index());
+}
+
+fn window_id(window: &gtk4::Window) -> usize {
+    window.as_ptr() as usize
+}
+
+fn toplevel_windows() -> Vec<gtk4::Window> {
+    let model = gtk4::Window::toplevels();
+    (0..model.n_items())
+        .filter_map(|i| model.item(i).and_downcast::<gtk4::Window>())
+        .collect()
+}
+
+fn focus_is_editable(window: &gtk4::Window) -> bool {
+    GtkWindowExt::focus(window)
+        .is_some_and(|widget| widget.is::<gtk4::Editable>() || widget.is::<gtk4::TextView>())
+}
+
+/// Pushes the tracked focus state into the hotkey state. The state is always
+/// re-derived from the live windows, so a dialog that goes away without the
+/// usual focus-out still ends the suppression.
+fn sync_hotkey_state() {
+    let focused = toplevel_windows().iter().any(GtkWindowExt::is_active);
+    let typing = TYPING.with_borrow(TypingSuppression::is_suppressed);
+
+    let ctx = TuxSplitContext::get_instance();
+    let Ok(cfg) = ctx.try_config() else {
+        // Someone is changing the config, try again once they are done
+        glib::idle_add_local_once(sync_hotkey_state);
+        return;
+    };
+    update_hotkey_state(&cfg, |state| {
+        state.window_focused = focused;
+        state.typing = typing;
+    });
+    let paused = hotkeys_registered() && !hotkey_state().global_active(&cfg.behavior);
+    drop(cfg);
+
+    PAUSED_INDICATOR.with_borrow(|indicator| {
+        if let Some(indicator) = indicator {
+            indicator.set_visible(paused);
+        }
+    });
+}
+
+fn on_focus_changed(window: &gtk4::Window) {
+    let id = window_id(window);
+    let event = if window.is_active() && focus_is_editable(window) {
+        FocusEvent::Editable(id)
+    } else {
+        FocusEvent::NonEditable(id)
+    };
+    TYPING.with_borrow_mut(|typing| typing.handle(event));
+    sync_hotkey_state();
+}
+
+/// Starts tracking windows that appeared and forgets the ones that are gone.
+fn sync_tracked_windows() {
+    let windows = toplevel_windows();
+    let present: HashSet<usize> = windows.iter().map(window_id).collect();
+
+    TRACKED_WINDOWS.with_borrow_mut(|tracked| {
+        let closed: Vec<usize> = tracked.difference(&present).copied().collect();
+        for id in closed {
+            tracked.remove(&id);
+            TYPING.with_borrow_mut(|typing| typing.handle(FocusEvent::Closed(id)));
+        }
+        for window in &windows {
+            if tracked.insert(window_id(window)) {
+                window.connect_is_active_notify(on_focus_changed);
+                window.connect_focus_widget_notify(on_focus_changed);
+            }
+        }
+    });
+    sync_hotkey_state();
+}
+
+/// Enables or disables pausing global hotkeys while typing.
+pub fn set_typing_suppression_enabled(enabled: bool) {
+    TYPING.with_borrow_mut(|typing| typing.set_enabled(enabled));
+    sync_hotkey_state();
+}
+
+/// Tracks focus across every TuxSplit window (dialogs and file choosers
+/// included), pausing global hotkeys while typing and handling them locally
+/// on `window` when they are off because it has focus. `paused_indicator` is
+/// shown while global hotkeys are paused.
+pub fn attach_focus_aware_hotkeys(window: &ApplicationWindow, paused_indicator: &Image) {
+    let enabled = TuxSplitContext::get_instance()
+        .config()
+        .behavior
+        .suppress_hotkeys_while_typing;
+    TYPING.with_borrow_mut(|typing| typing.set_enabled(enabled));
+    PAUSED_INDICATOR.replace(Some(paused_indicator.clone()));
+
+    gtk4::Window::toplevels().connect_items_changed(|_, _, _, _| sync_tracked_windows());
+    sync_tracked_windows();
+
+    let controller = EventControllerKey::new();
+    let window_for_help = window.clone();
+    controller.connect_key_pressed(move |_, keyval, _, state| {
+        let ctx = TuxSplitContext::get_instance();
+        if keyval == gdk::Key::question
+            && !focus_is_editable(window_for_help.upcast_ref())
+            && ctx.try_config().is_ok_and(|cfg| {
+                let help = Hotkey {
+                    key_code: KeyCode::Slash,
+                    modifiers: Modifiers::SHIFT,
+                };
+                action_for(cfg.hotkeys.active(), help).is_none()
+            })
+        {
+            toggle_keybindings(&window_for_help);
+            return Propagation::Stop;
+        }
+        let action = {
+            let Ok(cfg) = ctx.try_config() else {
+                return Propagation::Proceed;
+            };
+            if !hotkey_state().local_active(&cfg.behavior) {
+                return Propagation::Proceed;
+            }
+            let Some(key_code) = keyval
+                .name()
+                .and_then(|name| key_code_name(&name))
+                .and_then(|name| KeyCode::from_str(&name).ok())
+            else {
+                return Propagation::Proceed;
+            };
+            let pressed = Hotkey {
+                key_code,
+                modifiers: modifiers_from_gdk(state),
+            };
+            if cfg.behavior.undo_setting_hotkey == Some(pressed) {
+                drop(cfg);
+                undo_setting_change();
+                return Propagation::Stop;
+            }
+            action_for(cfg.hotkeys.active(), pressed)
+        };
+
+        match action {
+            Some(action) => {
+                offer_action(action, ActionSource::Keyboard);
+                Propagation::Stop
+            }
+            None => Propagation::Proceed,
+        }
+    });
+    window.add_controller(controller);
+}
+
+#[cfg(test)]
+mod hotkeys_tests {
+    use super::*;
+
+    #[test]
+    fn comparison_hotkeys_are_left_out_of_the_hotkey_system() {
+        let config = HotkeyConfig::default();
+        assert!(config.next_comparison.is_some());
+        let stripped = without_comparison_hotkeys(config);
+        assert_eq!(stripped.previous_comparison, None);
+        assert_eq!(stripped.next_comparison, None);
+        assert_eq!(stripped.split, config.split);
+        assert_eq!(stripped.reset, config.reset);
+    }
+
+    #[test]
+    fn window_hotkeys_never_use_global_hotkeys() {
+        let behavior = Behavior {
+            window_hotkeys: true,
+            ..Behavior::default()
+        };
+        let mut state = HotkeyState::default();
+        assert!(!state.global_active(&behavior));
+        assert!(!state.local_active(&behavior));
+
+        state.window_focused = true;
+        assert!(!state.global_active(&behavior));
+        assert!(state.local_active(&behavior));
+
+        state.typing = true;
+        assert!(!state.local_active(&behavior));
+    }
+
+    #[test]
+    fn global_hotkeys_stay_on_by_default() {
+        let behavior = Behavior::default();
+        let state = HotkeyState {
+            window_focused: true,
+            ..HotkeyState::default()
+        };
+        assert!(state.global_active(&behavior));
+        assert!(!state.local_active(&behavior));
+    }
+
+    #[test]
+    fn pending_dialog_pauses_only_global_hotkeys() {
+        let mut behavior = Behavior::default();
+        let mut state = HotkeyState {
+            modal_pending: true,
+            ..HotkeyState::default()
+        };
+        assert!(!state.global_active(&behavior));
+        behavior.hotkeys_only_unfocused = true;
+        state.window_focused = true;
+        // Local ones still reach the dialog policy
+        assert!(state.local_active(&behavior));
+        state.modal_pending = false;
+        state.window_focused = false;
+        assert!(state.global_active(&behavior));
+    }
+
+    #[test]
+    fn globals_off_only_when_focused_and_enabled() {
+        assert!(global_hotkeys_allowed(false, false));
+        assert!(global_hotkeys_allowed(false, true));
+        assert!(global_hotkeys_allowed(true, false));
+        assert!(!global_hotkeys_allowed(true, true));
+    }
+
+    /// Feeds `events` and checks suppress/resume always alternate, starting
+    /// with a suppress, and end in `expected_suppressed`.
+    fn assert_paired(
+        tracker: &mut TypingSuppression,
+        events: &[FocusEvent],
+        expected_suppressed: bool,
+    ) -> Vec<SuppressionChange> {
+        let changes: Vec<SuppressionChange> =
+            events.iter().filter_map(|e| tracker.handle(*e)).collect();
+        for (i, change) in changes.iter().enumerate() {
+            let expected = if i % 2 == 0 {
+                SuppressionChange::Suppress
+            } else {
+                SuppressionChange::Resume
+            };
+            assert_eq!(*change, expected, "unpaired transition in {changes:?}");
+        }
+        assert_eq!(tracker.is_suppressed(), expected_suppressed);
+        changes
+    }
+
+    fn enabled_tracker() -> TypingSuppression {
+        let mut tracker = TypingSuppression::default();
+        assert_eq!(tracker.set_enabled(true), None);
+        tracker
+    }
+
+    #[test]
+    fn typing_suppresses_until_focus_leaves() {
+        let mut tracker = enabled_tracker();
+        let changes = assert_paired(
+            &mut tracker,
+            &[
+                FocusEvent::Editable(1),
+                FocusEvent::Editable(1),
+                FocusEvent::NonEditable(1),
+                FocusEvent::NonEditable(1),
+            ],
+            false,
+        );
+        assert_eq!(
+            changes,
+            vec![SuppressionChange::Suppress, SuppressionChange::Resume]
+        );
+    }
+
+    #[test]
+    fn moving_between_windows_keeps_one_suppression() {
+        let mut tracker = enabled_tracker();
+        // The dialog's focus-in arrives before the main window's focus-out
+        let changes = assert_paired(
+            &mut tracker,
+            &[
+                FocusEvent::Editable(1),
+                FocusEvent::Editable(2),
+                FocusEvent::NonEditable(1),
+                FocusEvent::NonEditable(2),
+            ],
+            false,
+        );
+        assert_eq!(changes.len(), 2);
+    }
+
+    #[test]
+    fn closing_a_dialog_without_focus_out_resumes() {
+        let mut tracker = enabled_tracker();
+        assert_paired(
+            &mut tracker,
+            &[FocusEvent::Editable(7), FocusEvent::Closed(7)],
+            false,
+        );
+        // Stray events for the closed window are harmless
+        assert_paired(
+            &mut tracker,
+            &[FocusEvent::NonEditable(7), FocusEvent::Closed(7)],
+            false,
+        );
+    }
+
+    #[test]
+    fn long_random_sequences_never_leak() {
+        let mut tracker = enabled_tracker();
+        let mut events = Vec::new();
+        let mut seed: u32 = 0x2545_f491;
+        for _ in 0..500 {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            let window = (seed % 3) as usize;
+            events.push(match (seed >> 8) % 3 {
+                0 => FocusEvent::Editable(window),
+                1 => FocusEvent::NonEditable(window),
+                _ => FocusEvent::Closed(window),
+            });
+        }
+        events.extend((0..3).map(FocusEvent::Closed));
+        assert_paired(&mut tracker, &events, false);
+    }
+
+    #[test]
+    fn disabling_resumes_and_disabled_never_suppresses() {
+        let mut tracker = enabled_tracker();
+        assert_eq!(
+            tracker.handle(FocusEvent::Editable(1)),
+            Some(SuppressionChange::Suppress)
+        );
+        assert_eq!(tracker.set_enabled(false), Some(SuppressionChange::Resume));
+        assert_eq!(tracker.handle(FocusEvent::Editable(2)), None);
+        // Re-enabling while still typing suppresses again
+        assert_eq!(tracker.set_enabled(true), Some(SuppressionChange::Suppress));
+    }
+
+    #[test]
+    fn numpad_keys_map_with_and_without_numlock() {
+        assert_eq!(key_code_name("KP_1").as_deref(), Some("Numpad1"));
+        assert_eq!(key_code_name("KP_End").as_deref(), Some("Numpad1"));
+        assert_eq!(key_code_name("KP_Begin").as_deref(), Some("Numpad5"));
+        assert_eq!(key_code_name("KP_Add").as_deref(), Some("NumpadAdd"));
+        assert_eq!(key_code_name("KP_Space"), None);
+    }
+
+    #[test]
+    fn letters_digits_and_named_keys_map() {
+        assert_eq!(key_code_name("a").as_deref(), Some("KeyA"));
+        assert_eq!(key_code_name("Q").as_deref(), Some("KeyQ"));
+        assert_eq!(key_code_name("7").as_deref(), Some("Digit7"));
+        assert_eq!(key_code_name("F12").as_deref(), Some("F12"));
+        assert_eq!(key_code_name("space").as_deref(), Some("Space"));
+        assert_eq!(key_code_name("Page_Up").as_deref(), Some("PageUp"));
+        assert_eq!(key_code_name("F30"), None);
+        assert_eq!(key_code_name("dead_acute"), None);
+    }
+
+    #[test]
+    fn mapped_names_are_valid_key_codes() {
+        for name in ["KP_1", "KP_Enter", "a", "0", "F1", "Return", "Left"] {
+            let code = key_code_name(name).unwrap();
+            assert!(KeyCode::from_str(&code).is_ok(), "{code} is not a KeyCode");
+        }
+    }
+
+    #[test]
+    fn default_split_key_is_found() {
+        let config = HotkeyConfig::default();
+        let split = config.split.expect("default split hotkey");
+        assert_eq!(action_for(&config, split), Some(HotkeyAction::Split));
+
+        let unbound = Hotkey {
+            key_code: KeyCode::KeyZ,
+            modifiers: Modifiers::CONTROL | Modifiers::ALT,
+        };
+        assert_eq!(action_for(&config, unbound), None);
+    }
+}
@@ -1,10 +1,15 @@
 use crate::config::Config;
 use crate::utils::comparisons::{
-    best_comparison_values, best_segment_duration, classify_split_label,
-    current_attempt_running_duration, format_signed, previous_comparison_values,
-    previous_split_combined_gold_and_prev_comparison, real_time_sob, segment_best_time,
-    segment_comparison_time, segment_split_time,
+    DeltaWidget, GoldPace, RemainingBest, analytics_comparison, best_comparison_values,
+    classify_split_label, color_class, current_attempt_running_duration, current_gold_pace,
+    current_segment_elapsed, format_delta, gold_segment_duration, live_possible_time_save,
+    previous_comparison_values, previous_comparison_values_for, segment_best_time,
+    segment_comparison_time_for, segment_split_time, segments_since_last_split, sum_of_best,
 };
+use crate::utils::video_offset::video_offset;
+
+use crate::race::{own_splits, race_delta};
+use crate::ui::race::{RaceStatus, race_snapshot};
 
 use gtk4::{CenterBox, Label, Orientation::Horizontal, prelude::WidgetExt};
 
@@ -19,9 +24,13 @@ pub enum AdditionalInfoKind {
     CurrentPace,
     TotalPlaytime,
     PbChance,
+    GoldPace,
+    SegmentTimer,
+    RemainingBest,
+    Race,
 }
 
-pub static ALL_ADDITIONAL_INFOS: [AdditionalInfoKind; 7] = [
+pub static ALL_ADDITIONAL_INFOS: [AdditionalInfoKind; 11] = [
     AdditionalInfoKind::PrevSegmentDiff,
     AdditionalInfoKind::PrevSegmentBest,
     AdditionalInfoKind::BestPossibleTime,
@@ -29,6 +38,10 @@ pub static ALL_ADDITIONAL_INFOS: [AdditionalInfoKind; 7] = [
     AdditionalInfoKind::CurrentPace,
     AdditionalInfoKind::TotalPlaytime,
     AdditionalInfoKind::PbChance,
+    AdditionalInfoKind::GoldPace,
+    AdditionalInfoKind::SegmentTimer,
+    AdditionalInfoKind::RemainingBest,
+    AdditionalInfoKind::Race,
 ];
 
 pub trait AdditionalInfo {
@@ -74,6 +87,34 @@ pub struct TotalPlaytimeInfo {
     value: Label,
 }
 
+pub struct GoldPaceInfo {
+    container: CenterBox,
+    label: Label,
+    value: Label,
+}
+
+pub struct SegmentTimerInfo {
+    container: CenterBox,
+    value: Label,
+}
+
+/// What the sums were computed for: the split index, timing method and
+/// comparison.
+type RemainingKey = (usize, livesplit_core::TimingMethod, String);
+
+pub struct RemainingBestInfo {
+    container: CenterBox,
+    value: Label,
+    /// Summed again only when the key changes, not on every tick.
+    sums: Option<(RemainingKey, RemainingBest)>,
+}
+
+pub struct RaceInfo {
+    container: CenterBox,
+    label: Label,
+    value: Label,
+}
+
 impl AdditionalInfo for PrevSegmentDiffInfo {
     fn new(timer: &Timer, config: &Config) -> Self {
         let container = CenterBox::builder().orientation(Horizontal).build();
@@ -103,16 +144,17 @@ impl AdditionalInfo for PrevSegmentDiffInfo {
             index -= 1; // Previous segment index
 
             let segment = &timer.run().segments()[index];
+            let comparison = analytics_comparison(timer, config);
 
-            let segment_comparison_time = segment_comparison_time(segment, timer);
+            let segment_comparison_time = segment_comparison_time_for(segment, timer, comparison);
             let (previous_comparison_duration, previous_split_time) =
-                previous_comparison_values(timer, index);
+                previous_comparison_values_for(timer, index, comparison);
             let segment_comparison_duration = segment_comparison_time
                 .checked_sub(previous_comparison_duration)
                 .unwrap_or_default()
                 .abs();
 
-            let split_time = segment_split_time(segment, timer);
+            let split_time = segment_split_time(timer, index);
 
             if split_time == time::Duration::ZERO {
                 self.value.set_label("");
@@ -124,20 +166,24 @@ impl AdditionalInfo for PrevSegmentDiffInfo {
                     .unwrap_or_default();
 
                 if segment_comparison_time != time::Duration::ZERO {
-                    self.value.set_label(format_signed(diff, config).as_str());
+                    self.value
+                        .set_label(format_delta(diff, config, DeltaWidget::Info).as_str());
 
-                    let gold_duration = best_segment_duration(segment, timer);
+                    let gold_duration = gold_segment_duration(segment, timer, config);
                     let split_duration = split_time
                         .checked_sub(previous_split_time)
                         .unwrap_or_default();
 
-                    self.value.add_css_class(classify_split_label(
+                    let class = classify_split_label(
                         segment_comparison_duration,
                         split_duration,
                         diff,
                         gold_duration,
                         false,
-                    ));
+                    );
+                    if let Some(class) = color_class(config, class) {
+                        self.value.add_css_class(class);
+                    }
                 }
             }
         } else {
@@ -188,7 +234,7 @@ impl AdditionalInfo for PrevSegmentBestInfo {
                 .unwrap_or_default()
                 .abs();
 
-            let split_time = segment_split_time(segment, timer);
+            let split_time = segment_split_time(timer, index);
 
             if split_time == time::Duration::ZERO {
                 self.value.set_label("");
@@ -200,20 +246,24 @@ impl AdditionalInfo for PrevSegmentBestInfo {
                     .unwrap_or_default();
 
                 if segment_best_time != time::Duration::ZERO {
-                    self.value.set_label(format_signed(diff, config).as_str());
+                    self.value
+                        .set_label(format_delta(diff, config, DeltaWidget::Info).as_str());
 
-                    let gold_duration = best_segment_duration(segment, timer);
+                    let gold_duration = gold_segment_duration(segment, timer, config);
                     let split_duration = split_time
                         .checked_sub(previous_best_time)
                         .unwrap_or_default();
 
-                    self.value.add_css_class(classify_split_label(
+                    let class = classify_split_label(
                         segment_best_duration,
                         split_duration,
                         diff,
                         gold_duration,
                         false,
-                    ));
+                    );
+                    if let Some(class) = color_class(config, class) {
+                        self.value.add_css_class(class);
+                    }
                 }
             }
         } else {
@@ -255,7 +305,7 @@ impl AdditionalInfo for BestPossibleTimeInfo {
             let segment_best_duration = segment_best_time(segment, timer);
 
             // Diff to SOB
-            let diff = current_attempt_running_duration(timer)
+            let diff = current_attempt_running_duration(timer, video_offset(config))
                 .checked_sub(segment_best_duration)
                 .unwrap_or_default();
 
@@ -266,9 +316,13 @@ impl AdditionalInfo for BestPossibleTimeInfo {
                 time::Duration::ZERO
             };
 
-            let best_possible_time = real_time_sob(timer)
-                .checked_add(live_addition)
-                .unwrap_or_default();
+            let Some(best_segments) = sum_of_best(timer, config.general.skipped_in_sum_of_best)
+            else {
+                // A segment without a best voids the total
+                self.value.set_label("--");
+                return;
+            };
+            let best_possible_time = best_segments.checked_add(live_addition).unwrap_or_default();
             if best_possible_time == time::Duration::ZERO {
                 self.value.set_label("");
             } else {
@@ -285,7 +339,10 @@ impl AdditionalInfo for BestPossibleTimeInfo {
                 config
                     .format
                     .segment
-                    .format_duration(&current_attempt_running_duration(timer))
+                    .format_duration(&current_attempt_running_duration(
+                        timer,
+                        video_offset(config),
+                    ))
                     .as_str(),
             );
         }
@@ -320,21 +377,13 @@ impl AdditionalInfo for PossibleTimeSaveInfo {
         if timer.current_phase().is_not_running() {
             self.value.set_label("");
         } else if timer.current_phase().is_running() || timer.current_phase().is_paused() {
-            let index = timer.current_split_index().unwrap_or(0);
-
-            let (_, combined_gold, previous_comparion_time) =
-                previous_split_combined_gold_and_prev_comparison(timer, index);
-            let current_comparison_time = segment_comparison_time(
-                timer.current_split().unwrap_or(timer.run().segment(0)),
-                timer,
-            );
-
-            // Diff from gold to comp. This is the possible time save
-            let gold_diff = current_comparison_time
-                .checked_sub(previous_comparion_time)
-                .unwrap_or_default()
-                .checked_sub(combined_gold)
-                .unwrap_or_default();
+            let Some(gold_diff) =
+                live_possible_time_save(timer, analytics_comparison(timer, config))
+            else {
+                // Nothing to save against, e.g. a PB comparison without a completed run
+                self.value.set_label("--");
+                return;
+            };
 
             self.value.set_label(
                 config
@@ -378,9 +427,10 @@ impl AdditionalInfo for CurrentPaceInfo {
             self.value.set_label("");
         } else {
             let timer_snaptshot = timer.snapshot();
-            let pace = current_pace::calculate(&timer_snaptshot, timer.current_comparison())
-                .0
-                .unwrap_or_default();
+            let pace =
+                current_pace::calculate(&timer_snaptshot, analytics_comparison(timer, config))
+                    .0
+                    .unwrap_or_default();
             let pace = config.format.timer.format_time_span(&pace);
             self.value.set_label(&pace);
         }
@@ -456,3 +506,246 @@ impl AdditionalInfo for TotalPlaytimeInfo {
         &self.container
     }
 }
+
+impl AdditionalInfo for GoldPaceInfo {
+    fn new(timer: &Timer, config: &Config) -> Self {
+        let container = CenterBox::builder().orientation(Horizontal).build();
+
+        let label = Label::builder()
+            .label("Gold In:")
+            .css_classes(["heading"])
+            .build();
+        let value = Label::builder().label("").css_classes(["timer"]).build();
+
+        container.set_start_widget(Some(&label));
+        container.set_end_widget(Some(&value));
+
+        let mut res = Self {
+            container,
+            label,
+            value,
+        };
+
+        res.update(timer, config); // Initialize with default timer state
+
+        res
+    }
+
+    fn update(&mut self, timer: &Timer, config: &Config) {
+        match current_gold_pace(timer, config) {
+            Some(pace) => {
+                self.value
+                    .set_label(&format_delta(pace.diff(), config, DeltaWidget::Info));
+                match pace {
+                    GoldPace::Ahead(_) => {
+                        self.label.set_label("Gold In:");
+                        match color_class(config, "goldsplit") {
+                            Some(class) => self.value.set_css_classes(&["timer", class]),
+                            None => self.value.set_css_classes(&["timer"]),
+                        }
+                    }
+                    GoldPace::Over(_) => {
+                        self.label.set_label("Over Gold:");
+                        self.value.set_css_classes(&["timer", "dim-label"]);
+                    }
+                }
+            }
+            None => {
+                self.label.set_label("Gold In:");
+                self.value.set_css_classes(&["timer"]);
+                self.value.set_label("");
+            }
+        }
+    }
+
+    fn container(&self) -> &CenterBox {
+        &self.container
+    }
+}
+
+impl AdditionalInfo for SegmentTimerInfo {
+    fn new(timer: &Timer, config: &Config) -> Self {
+        let container = CenterBox::builder().orientation(Horizontal).build();
+
+        let label = Label::builder()
+            .label("Segment Time:")
+            .css_classes(["heading"])
+            .build();
+        let value = Label::builder().label("").css_classes(["timer"]).build();
+
+        container.set_start_widget(Some(&label));
+        container.set_end_widget(Some(&value));
+
+        let mut res = Self { container, value };
+
+        res.update(timer, config); // Initialize with default timer state
+
+        res
+    }
+
+    fn update(&mut self, timer: &Timer, config: &Config) {
+        match current_segment_elapsed(timer, video_offset(config)) {
+            Some(elapsed) => self
+                .value
+                .set_label(&config.format.segment.format_segment_time(&elapsed)),
+            None => self.value.set_label(""),
+        }
+    }
+
+    fn container(&self) -> &CenterBox {
+        &self.container
+    }
+}
+
+impl AdditionalInfo for RemainingBestInfo {
+    fn new(timer: &Timer, config: &Config) -> Self {
+        let container = CenterBox::builder().orientation(Horizontal).build();
+
+        let label = Label::builder()
+            .label("Remaining at Gold Pace:")
+            .css_classes(["heading"])
+            .build();
+        let value = Label::builder().label("").css_classes(["timer"]).build();
+
+        container.set_start_widget(Some(&label));
+        container.set_end_widget(Some(&value));
+
+        let mut res = Self {
+            container,
+            value,
+            sums: None,
+        };
+
+        res.update(timer, config); // Initialize with default timer state
+
+        res
+    }
+
+    fn update(&mut self, timer: &Timer, config: &Config) {
+        let phase = timer.current_phase();
+        if phase.is_ended() {
+            self.sums = None;
+            self.value
+                .set_label(&config.format.timer.format_duration(&time::Duration::ZERO));
+            return;
+        }
+        let (Some(current), Some(elapsed)) = (
+            timer.current_split_index(),
+            current_segment_elapsed(timer, video_offset(config)),
+        ) else {
+            self.sums = None;
+            self.value.set_label("");
+            return;
+        };
+
+        let comparison = analytics_comparison(timer, config);
+        let key = (
+            current,
+            timer.current_timing_method(),
+            comparison.to_owned(),
+        );
+        if self.sums.as_ref().is_none_or(|(cached, _)| *cached != key) {
+            let sums = RemainingBest::new(timer, comparison, config.general.remaining_without_best);
+            self.sums = Some((key, sums));
+        }
+        let Some((_, sums)) = &self.sums else {
+            return;
+        };
+
+        let start = segments_since_last_split(timer, current);
+        if sums.is_incomplete(start) {
+            self.value.set_label("--");
+            return;
+        }
+        let remaining = config
+            .format
+            .timer
+            .format_duration(&sums.remaining(start, current, elapsed));
+        if sums.is_estimated(start) {
+            self.value.set_label(&format!("~{remaining}"));
+        } else {
+            self.value.set_label(&remaining);
+        }
+    }
+
+    fn container(&self) -> &CenterBox {
+        &self.container
+    }
+}
+
+impl AdditionalInfo for RaceInfo {
+    fn new(timer: &Timer, config: &Config) -> Self {
+        let container = CenterBox::builder().orientation(Horizontal).build();
+
+        let label = Label::builder()
+            .label("Race:")
+            .css_classes(["heading"])
+            .build();
+        let value = Label::builder().label("").css_classes(["timer"]).build();
+
+        container.set_start_widget(Some(&label));
+        container.set_end_widget(Some(&value));
+
+        let mut res = Self {
+            container,
+            label,
+            value,
+        };
+
+        res.update(timer, config); // Initialize with default timer state
+
+        res
+    }
+
+    fn update(&mut self, timer: &Timer, config: &Config) {
+        let Some(race) = race_snapshot() else {
+            self.label.set_label("Race:");
+            self.value.set_label("");
+            return;
+        };
+        if race.status == RaceStatus::Waiting {
+            self.label.set_label("Race:");
+            self.value.set_css_classes(&["timer", "dim-label"]);
+            self.value.set_label("Waiting for opponent");
+            return;
+        }
+
+        let name = race.opponent.name.as_deref().unwrap_or("Opponent");
+        if race.opponent.connected {
+            self.label.set_label(&format!("vs {name}:"));
+        } else {
+            self.label.set_label(&format!("vs {name} (offline):"));
+        }
+
+        match race_delta(&own_splits(timer), &race.opponent.splits) {
+            Some((_, millis)) => {
+                let diff = time::Duration::milliseconds(millis);
+                self.value
+                    .set_label(&format_delta(diff, config, DeltaWidget::Info));
+                let class = if !race.opponent.connected {
+                    Some("dim-label")
+                } else if diff.is_positive() {
+                    color_class(config, "redsplit")
+                } else {
+                    color_class(config, "greensplit")
+                };
+                match class {
+                    Some(class) => self.value.set_css_classes(&["timer", class]),
+                    None => self.value.set_css_classes(&["timer"]),
+                }
+            }
+            None => {
+                self.value.set_css_classes(&["timer", "dim-label"]);
+                self.value.set_label(&format!(
+                    "Split {}/{}",
+                    race.opponent.current_index().min(timer.run().len()),
+                    timer.run().len()
+                ));
+            }
+        }
+    }
+
+    fn container(&self) -> &CenterBox {
+        &self.container
+    }
+}
@@ -0,0 +1,202 @@
+//! Keeps the session from blanking or suspending while an attempt is running.
+//!
+//! The inhibitor follows the timer phase: it is taken when a run starts and
+//! dropped on reset or end. Undoing the last split after the end puts the
+//! timer back into a running phase, which takes it again.
+
+use std::time::Duration;
+
+use adw::prelude::*;
+use adw::{Application, ApplicationWindow, Toast, ToastOverlay};
+use gtk4::{ApplicationInhibitFlags, Image};
+use livesplit_core::TimerPhase;
+use tracing::{info, warn};
+
+use crate::context::TuxSplitContext;
+
+const INHIBIT_REASON: &str = "Speedrun in progress";
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InhibitAction {
+    Acquire,
+    Release,
+}
+
+/// Decides when the inhibitor should be held, independently of GTK.
+#[derive(Debug, Default)]
+pub struct InhibitTracker {
+    held: bool,
+}
+
+impl InhibitTracker {
+    pub fn is_held(&self) -> bool {
+        self.held
+    }
+
+    /// Feeds the current phase and whether the feature is enabled. Returns the
+    /// transition to perform, if any.
+    pub fn update(&mut self, phase: TimerPhase, enabled: bool) -> Option<InhibitAction> {
+        let wanted = enabled && matches!(phase, TimerPhase::Running | TimerPhase::Paused);
+        match (self.held, wanted) {
+            (false, true) => {
+                self.held = true;
+                Some(InhibitAction::Acquire)
+            }
+            (true, false) => {
+                self.held = false;
+                Some(InhibitAction::Release)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Polls the timer phase and holds an application inhibit while a run is in progress.
+/// `indicator` is shown while the inhibitor is held.
+pub fn start_idle_inhibitor(
+    app: &Application,
+    window: &ApplicationWindow,
+    toasts: &ToastOverlay,
+    indicator: &Image,
+) {
+    let app = app.clone();
+    let window = window.clone();
+    let toasts = toasts.clone();
+    let indicator = indicator.clone();
+    let mut tracker = InhibitTracker::default();
+    // `None` while held means the inhibit call was denied
+    let mut cookie: Option<u32> = None;
+
+    glib::timeout_add_local(POLL_INTERVAL, move || {
+        let ctx = TuxSplitContext::get_instance();
+        let Ok(config) = ctx.try_config() else {
+            return glib::ControlFlow::Continue;
+        };
+        let enabled = config.behavior.inhibit_idle;
+        drop(config);
+        let phase = ctx.timer().read().unwrap().current_phase();
+
+        match tracker.update(phase, enabled) {
+            Some(InhibitAction::Acquire) => {
+                let id = app.inhibit(
+                    Some(&window),
+                    ApplicationInhibitFlags::SUSPEND | ApplicationInhibitFlags::IDLE,
+                    Some(INHIBIT_REASON),
+                );
+                if id == 0 {
+                    warn!("Idle inhibit was denied by the session");
+                    toasts.add_toast(Toast::new(
+                        "Could not prevent sleep, screen blanking may interrupt this run",
+                    ));
+                } else {
+                    info!("Idle inhibited for the current run");
+                    cookie = Some(id);
+                    indicator.set_visible(true);
+                }
+            }
+            Some(InhibitAction::Release) => {
+                if let Some(id) = cookie.take() {
+                    app.uninhibit(id);
+                    info!("Idle inhibit released");
+                }
+                indicator.set_visible(false);
+            }
+            None => {}
+        }
+
+        glib::ControlFlow::Continue
+    });
+}
+
+#[cfg(test)]
+mod inhibit_tests {
+    use super::*;
+
+    fn run(tracker: &mut InhibitTracker, phases: &[TimerPhase]) -> Vec<Option<InhibitAction>> {
+        phases.iter().map(|p| tracker.update(*p, true)).collect()
+    }
+
+    #[test]
+    fn acquires_on_start_and_releases_on_reset() {
+        let mut tracker = InhibitTracker::default();
+        let actions = run(
+            &mut tracker,
+            &[
+                TimerPhase::NotRunning,
+                TimerPhase::Running,
+                TimerPhase::Running,
+                TimerPhase::NotRunning,
+            ],
+        );
+        assert_eq!(
+            actions,
+            vec![
+                None,
+                Some(InhibitAction::Acquire),
+                None,
+                Some(InhibitAction::Release)
+            ]
+        );
+        assert!(!tracker.is_held());
+    }
+
+    #[test]
+    fn stays_held_while_paused() {
+        let mut tracker = InhibitTracker::default();
+        let actions = run(
+            &mut tracker,
+            &[TimerPhase::Running, TimerPhase::Paused, TimerPhase::Running],
+        );
+        assert_eq!(actions, vec![Some(InhibitAction::Acquire), None, None]);
+        assert!(tracker.is_held());
+    }
+
+    #[test]
+    fn undo_after_end_reacquires_and_ends_again() {
+        let mut tracker = InhibitTracker::default();
+        let actions = run(
+            &mut tracker,
+            &[
+                TimerPhase::Running,
+                TimerPhase::Ended,
+                TimerPhase::Running, // undo split
+                TimerPhase::Ended,
+                TimerPhase::NotRunning, // reset
+            ],
+        );
+        assert_eq!(
+            actions,
+            vec![
+                Some(InhibitAction::Acquire),
+                Some(InhibitAction::Release),
+                Some(InhibitAction::Acquire),
+                Some(InhibitAction::Release),
+                None,
+            ]
+        );
+        assert!(!tracker.is_held());
+    }
+
+    #[test]
+    fn disabling_mid_run_releases() {
+        let mut tracker = InhibitTracker::default();
+        assert_eq!(
+            tracker.update(TimerPhase::Running, true),
+            Some(InhibitAction::Acquire)
+        );
+        assert_eq!(
+            tracker.update(TimerPhase::Running, false),
+            Some(InhibitAction::Release)
+        );
+        assert_eq!(tracker.update(TimerPhase::Running, false), None);
+    }
+
+    #[test]
+    fn never_acquires_when_disabled() {
+        let mut tracker = InhibitTracker::default();
+        for phase in [TimerPhase::Running, TimerPhase::Paused, TimerPhase::Ended] {
+            assert_eq!(tracker.update(phase, false), None);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Header badge for splits whose times changed since TuxSplit hashed them.
+//!
+//! The badge stays until other splits are loaded, and its popover shows the
+//! stored and the current hash so either can be copied and compared.
+
+use std::cell::RefCell;
+
+use adw::prelude::*;
+use gtk4::{Align, Box as GtkBox, Button, Label, MenuButton, Orientation, Popover};
+
+use crate::io::integrity::{IntegrityStatus, short_hash};
+
+thread_local! {
+    static BADGE: RefCell<Option<MenuButton>> = const { RefCell::new(None) };
+}
+
+/// The stored and the current short hash of splits edited elsewhere.
+pub fn modified_hashes(status: &IntegrityStatus) -> Option<(&str, &str)> {
+    match status {
+        IntegrityStatus::Modified { stored, actual } => {
+            Some((short_hash(stored), short_hash(actual)))
+        }
+        _ => None,
+    }
+}
+
+/// Where a change to the loaded times is shown.
+pub fn set_integrity_badge(badge: &MenuButton) {
+    BADGE.replace(Some(badge.clone()));
+}
+
+fn hash_row(title: &str, hash: &str) -> GtkBox {
+    let row = GtkBox::new(Orientation::Horizontal, 6);
+    let label = Label::builder()
+        .label(format!("{title}: {hash}"))
+        .selectable(true)
+        .hexpand(true)
+        .xalign(0.0)
+        .css_classes(["monospace"])
+        .build();
+    let copy = Button::builder()
+        .icon_name("edit-copy-symbolic")
+        .tooltip_text("Copy")
+        .valign(Align::Center)
+        .css_classes(["flat"])
+        .build();
+    let hash = hash.to_owned();
+    copy.connect_clicked(move |button| button.clipboard().set_text(&hash));
+    row.append(&label);
+    row.append(&copy);
+    row
+}
+
+/// Shows the badge while `status` says the times were modified.
+pub fn refresh_integrity_badge(status: Option<&IntegrityStatus>) {
+    BADGE.with_borrow(|badge| {
+        let Some(badge) = badge else {
+            return;
+        };
+        let Some((stored, actual)) = status.and_then(modified_hashes) else {
+            badge.set_visible(false);
+            return;
+        };
+        let content = GtkBox::new(Orientation::Vertical, 6);
+        let explanation = Label::builder()
+            .label("The times in these splits changed since TuxSplit last saved them.")
+            .wrap(true)
+            .max_width_chars(36)
+            .xalign(0.0)
+            .build();
+        content.append(&explanation);
+        content.append(&hash_row("Stored", stored));
+        content.append(&hash_row("Now", actual));
+        badge.set_popover(Some(&Popover::builder().child(&content).build()));
+        badge.set_tooltip_text(Some(&format!("Splits times modified ({stored})")));
+        badge.set_visible(true);
+    });
+}
+
+#[cfg(test)]
+mod integrity_badge_tests {
+    use super::*;
+
+    #[test]
+    fn only_modified_times_show_the_badge() {
+        let modified = IntegrityStatus::Modified {
+            stored: "0123456789abcdef".to_owned(),
+            actual: "fedcba9876543210".to_owned(),
+        };
+        assert_eq!(
+            modified_hashes(&modified),
+            Some(("0123456789ab", "fedcba987654"))
+        );
+        let verified = IntegrityStatus::Verified {
+            hash: "0123456789abcdef".to_owned(),
+        };
+        assert_eq!(modified_hashes(&verified), None);
+        assert_eq!(modified_hashes(&IntegrityStatus::Unsigned), None);
+    }
+}
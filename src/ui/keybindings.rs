@@ -0,0 +1,419 @@
+//! Overlay listing the hotkeys that are actually bound.
+//!
+//! The list is read from the config each time the overlay opens, so it
+//! follows imported setups and edits to the config file. Pressing `?` in the
+//! main window toggles it. The hotkey profile is picked, imported and
+//! exported here too; a profile that couldn't be read is shown in a banner.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use adw::prelude::*;
+use adw::{AlertDialog, Banner, ComboRow};
+use gtk4::{
+    Button, FileChooserAction, FileChooserDialog, FileFilter, Label, ListBox, ResponseType,
+    StringList,
+};
+use livesplit_core::HotkeyConfig;
+use livesplit_core::hotkey::{Hotkey, Modifiers};
+
+use crate::context::{TuxSplitContext, hotkey_profiles_dir};
+use crate::io::bundle::unique_path;
+use crate::io::hotkey_profiles::{
+    PROFILE_EXTENSION, ProfileError, list_profiles, parse_profile, profile_name_from_path,
+    profile_to_string, write_profile,
+};
+use crate::ui::hotkeys::{HotkeyAction, bindings, register_hotkeys};
+use crate::ui::timer::modal::show_notice;
+
+/// Selector entry of the bindings in the config itself.
+const DEFAULT_PROFILE_LABEL: &str = "Default";
+
+thread_local! {
+    static OPEN_OVERLAY: RefCell<Option<AlertDialog>> = const { RefCell::new(None) };
+}
+
+impl HotkeyAction {
+    pub fn label(self) -> &'static str {
+        match self {
+            HotkeyAction::Split => "Start / Split",
+            HotkeyAction::Reset => "Reset",
+            HotkeyAction::Undo => "Undo Split",
+            HotkeyAction::Skip => "Skip Split",
+            HotkeyAction::Pause => "Pause",
+            HotkeyAction::UndoAllPauses => "Undo All Pauses",
+            HotkeyAction::PreviousComparison => "Previous Comparison",
+            HotkeyAction::NextComparison => "Next Comparison",
+            HotkeyAction::ToggleTimingMethod => "Toggle Timing Method",
+        }
+    }
+}
+
+/// Readable name of a livesplit key code, e.g. "Numpad 1" for `Numpad1`.
+fn key_label(name: &str) -> String {
+    if let Some(key) = name
+        .strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+    {
+        return key.to_owned();
+    }
+    if let Some(key) = name.strip_prefix("Numpad")
+        && !key.is_empty()
+    {
+        return format!("Numpad {key}");
+    }
+    if let Some(key) = name.strip_prefix("Arrow") {
+        return key.to_owned();
+    }
+    name.to_owned()
+}
+
+pub fn hotkey_label(hotkey: Option<Hotkey>) -> String {
+    let Some(hotkey) = hotkey else {
+        return "Unbound".to_owned();
+    };
+    let mut parts: Vec<String> = [
+        (Modifiers::CONTROL, "Ctrl"),
+        (Modifiers::ALT, "Alt"),
+        (Modifiers::SHIFT, "Shift"),
+        (Modifiers::META, "Super"),
+    ]
+    .into_iter()
+    .filter(|(modifier, _)| hotkey.modifiers.contains(*modifier))
+    .map(|(_, name)| name.to_owned())
+    .collect();
+    parts.push(key_label(hotkey.key_code.name()));
+    parts.join(" + ")
+}
+
+/// The rows of the overlay: bound actions first, in the usual order, then
+/// the unbound ones.
+pub fn binding_rows(config: &HotkeyConfig) -> Vec<(&'static str, String)> {
+    let (bound, unbound): (Vec<_>, Vec<_>) = bindings(config)
+        .into_iter()
+        .partition(|(_, hotkey)| hotkey.is_some());
+    bound
+        .into_iter()
+        .chain(unbound)
+        .map(|(action, hotkey)| (action.label(), hotkey_label(hotkey)))
+        .collect()
+}
+
+/// Refills `list` with the rows of the active bindings.
+fn fill_binding_rows(list: &ListBox) {
+    let rows = binding_rows(TuxSplitContext::get_instance().config().hotkeys.active());
+    list.remove_all();
+    for (action, key) in rows {
+        let key_label = Label::new(Some(&key));
+        if key == "Unbound" {
+            key_label.add_css_class("dim-label");
+        }
+        let row = adw::ActionRow::builder().title(action).build();
+        row.add_suffix(&key_label);
+        list.append(&row);
+    }
+}
+
+fn refresh_profile_banner(banner: &Banner) {
+    let ctx = TuxSplitContext::get_instance();
+    let config = ctx.config();
+    match config.hotkeys.profile_error() {
+        Some(err) => {
+            banner.set_title(&format!("{err}, using the default bindings"));
+            banner.set_revealed(true);
+        }
+        None => banner.set_revealed(false),
+    }
+}
+
+/// Entries of the profile selector: the default bindings, then the
+/// profiles in the profile directory. An active profile whose file is gone
+/// stays listed so the selector doesn't jump.
+fn profile_choices() -> (Vec<String>, u32) {
+    let active = TuxSplitContext::get_instance()
+        .config()
+        .hotkeys
+        .active_profile
+        .clone();
+    let mut names = list_profiles(&hotkey_profiles_dir());
+    if let Some(active) = &active
+        && !names.contains(active)
+    {
+        names.push(active.clone());
+    }
+    let selected = active
+        .and_then(|active| names.iter().position(|name| *name == active))
+        .map_or(0, |index| index as u32 + 1);
+    let mut choices = vec![DEFAULT_PROFILE_LABEL.to_owned()];
+    choices.extend(names);
+    (choices, selected)
+}
+
+/// The profile selector with its import and export buttons. Switching
+/// applies right away and refills `list`.
+struct ProfileSection {
+    row: ComboRow,
+    model: StringList,
+    banner: Banner,
+    list: ListBox,
+    /// Set while the choices are refilled, so that doesn't switch profiles.
+    syncing: Cell<bool>,
+}
+
+impl ProfileSection {
+    fn new(banner: &Banner, list: &ListBox) -> Rc<Self> {
+        let model = StringList::new(&[]);
+        let row = ComboRow::builder()
+            .title("Profile")
+            .subtitle("Bindings stored in the hotkeys folder of the config")
+            .model(&model)
+            .build();
+        let section = Rc::new(Self {
+            row,
+            model,
+            banner: banner.clone(),
+            list: list.clone(),
+            syncing: Cell::new(false),
+        });
+        section.refill();
+
+        let weak = Rc::downgrade(&section);
+        section.row.connect_selected_notify(move |row| {
+            if let Some(section) = weak.upgrade()
+                && !section.syncing.get()
+            {
+                let name = (row.selected() > 0)
+                    .then(|| section.model.string(row.selected()))
+                    .flatten()
+                    .map(Into::into);
+                section.switch_to(name);
+            }
+        });
+        section
+    }
+
+    fn refill(&self) {
+        let (choices, selected) = profile_choices();
+        let choices: Vec<&str> = choices.iter().map(String::as_str).collect();
+        self.syncing.set(true);
+        self.model.splice(0, self.model.n_items(), &choices);
+        self.row.set_selected(selected);
+        self.syncing.set(false);
+    }
+
+    fn switch_to(&self, name: Option<String>) {
+        let ctx = TuxSplitContext::get_instance();
+        if let Ok(mut config) = ctx.config_mut() {
+            config.set_hotkey_profile(&hotkey_profiles_dir(), name);
+            register_hotkeys(&config);
+        }
+        self.refill();
+        refresh_profile_banner(&self.banner);
+        fill_binding_rows(&self.list);
+    }
+
+    fn buttons(self: &Rc<Self>, parent: Option<gtk4::Window>) -> gtk4::Box {
+        let import = Button::with_label("Import Profile…");
+        let export = Button::with_label("Export Profile…");
+        let section = self.clone();
+        let import_parent = parent.clone();
+        import.connect_clicked(move |_| section.import(import_parent.as_ref()));
+        export.connect_clicked(move |_| export_profile(parent.as_ref()));
+
+        let buttons = gtk4::Box::builder()
+            .spacing(6)
+            .halign(gtk4::Align::End)
+            .build();
+        buttons.append(&import);
+        buttons.append(&export);
+        buttons
+    }
+
+    /// Copies a profile file into the profile directory, under its own
+    /// name or the next free one, and switches to it.
+    fn import(self: &Rc<Self>, parent: Option<&gtk4::Window>) {
+        let file_chooser = FileChooserDialog::new(
+            Some("Import Hotkey Profile"),
+            parent,
+            FileChooserAction::Open,
+            &[("Open", ResponseType::Ok), ("Cancel", ResponseType::Cancel)],
+        );
+        file_chooser.add_filter(&profile_filter());
+        let section = self.clone();
+        file_chooser.connect_response(move |dialog, response| {
+            if response == ResponseType::Ok
+                && let Some(file) = dialog.file()
+                && let Some(path) = file.path()
+            {
+                let dir = hotkey_profiles_dir();
+                let result = std::fs::read_to_string(&path)
+                    .map_err(ProfileError::Io)
+                    .and_then(|text| parse_profile(&text))
+                    .and_then(|hotkeys| {
+                        let name = profile_name_from_path(&path)
+                            .ok_or_else(|| ProfileError::InvalidName(path.display().to_string()))?;
+                        let file_name = format!("{name}.{PROFILE_EXTENSION}");
+                        let name =
+                            profile_name_from_path(&unique_path(&dir, &file_name)).unwrap_or(name);
+                        write_profile(&dir, &name, &hotkeys).map(|_| name)
+                    });
+                match result {
+                    Ok(name) => {
+                        show_notice(&format!("Imported the hotkey profile {name}"));
+                        section.switch_to(Some(name));
+                    }
+                    Err(err) => show_notice(&err.to_string()),
+                }
+            }
+            dialog.destroy();
+        });
+        file_chooser.set_modal(true);
+        file_chooser.present();
+    }
+}
+
+fn profile_filter() -> FileFilter {
+    let filter = FileFilter::new();
+    filter.set_name(Some("Hotkey Profile (*.yaml)"));
+    filter.add_pattern("*.yaml");
+    filter.add_pattern("*.yml");
+    filter
+}
+
+/// Saves the active bindings as a profile file to share.
+fn export_profile(parent: Option<&gtk4::Window>) {
+    let file_chooser = FileChooserDialog::new(
+        Some("Export Hotkey Profile"),
+        parent,
+        FileChooserAction::Save,
+        &[("Save", ResponseType::Ok), ("Cancel", ResponseType::Cancel)],
+    );
+    file_chooser.add_filter(&profile_filter());
+    let name = TuxSplitContext::get_instance()
+        .config()
+        .hotkeys
+        .active_profile
+        .clone()
+        .unwrap_or_else(|| "default".to_owned());
+    file_chooser.set_current_name(&format!("{name}.{PROFILE_EXTENSION}"));
+
+    file_chooser.connect_response(|dialog, response| {
+        if response == ResponseType::Ok
+            && let Some(file) = dialog.file()
+            && let Some(path) = file.path()
+        {
+            let hotkeys = *TuxSplitContext::get_instance().config().hotkeys.active();
+            let result = profile_to_string(&hotkeys)
+                .and_then(|text| std::fs::write(&path, text).map_err(ProfileError::Io));
+            match result {
+                Ok(()) => show_notice("Hotkey profile exported"),
+                Err(err) => show_notice(&err.to_string()),
+            }
+        }
+        dialog.destroy();
+    });
+    file_chooser.set_modal(true);
+    file_chooser.present();
+}
+
+pub fn present_keybindings(parent: &impl IsA<gtk4::Widget>) {
+    let dialog = AlertDialog::builder()
+        .heading("Keybindings")
+        .body("Hotkeys as currently bound. Press ? to show or hide this list.")
+        .default_response("ok")
+        .build();
+    dialog.add_response("ok", "Okay");
+
+    let banner = Banner::builder().use_markup(false).build();
+    refresh_profile_banner(&banner);
+
+    let keybinds_list = ListBox::new();
+    keybinds_list.add_css_class("boxed-list");
+    fill_binding_rows(&keybinds_list);
+
+    let profiles = ProfileSection::new(&banner, &keybinds_list);
+    let profile_list = ListBox::new();
+    profile_list.add_css_class("boxed-list");
+    profile_list.append(&profiles.row);
+
+    let container = gtk4::Box::new(gtk4::Orientation::Vertical, 12);
+    container.append(&banner);
+    container.append(&profile_list);
+    container.append(&profiles.buttons(parent.root().and_downcast::<gtk4::Window>()));
+    container.append(&keybinds_list);
+    #[cfg(feature = "gamepad")]
+    container.append(&crate::ui::pedal::pedal_section());
+    dialog.set_extra_child(Some(&container));
+
+    dialog.connect_closed(|_| {
+        OPEN_OVERLAY.replace(None);
+        #[cfg(feature = "gamepad")]
+        crate::ui::pedal::close_pedal_section();
+    });
+    if let Some(previous) = OPEN_OVERLAY.replace(Some(dialog.clone())) {
+        previous.close();
+    }
+    dialog.present(Some(parent));
+}
+
+/// Opens the keybindings overlay, or closes it when it's already open.
+pub fn toggle_keybindings(parent: &impl IsA<gtk4::Widget>) {
+    match OPEN_OVERLAY.with_borrow(Clone::clone) {
+        Some(open) => {
+            open.close();
+        }
+        None => present_keybindings(parent),
+    }
+}
+
+#[cfg(test)]
+mod keybindings_tests {
+    use super::*;
+    use livesplit_core::hotkey::KeyCode;
+
+    #[test]
+    fn key_codes_read_like_keys() {
+        assert_eq!(key_label("Numpad1"), "Numpad 1");
+        assert_eq!(key_label("KeyA"), "A");
+        assert_eq!(key_label("Digit7"), "7");
+        assert_eq!(key_label("ArrowUp"), "Up");
+        assert_eq!(key_label("F5"), "F5");
+    }
+
+    #[test]
+    fn modifiers_come_before_the_key() {
+        let hotkey = Hotkey {
+            key_code: KeyCode::KeyS,
+            modifiers: Modifiers::SHIFT | Modifiers::CONTROL,
+        };
+        assert_eq!(hotkey_label(Some(hotkey)), "Ctrl + Shift + S");
+        assert_eq!(hotkey_label(None), "Unbound");
+    }
+
+    fn key(key_code: KeyCode) -> Option<Hotkey> {
+        Some(Hotkey {
+            key_code,
+            modifiers: Modifiers::empty(),
+        })
+    }
+
+    #[test]
+    fn rows_follow_the_config() {
+        let mut config = HotkeyConfig::default();
+        config.split = key(KeyCode::Space);
+        config.toggle_timing_method = key(KeyCode::KeyT);
+        config.undo_all_pauses = None;
+        let rows = binding_rows(&config);
+
+        assert_eq!(rows.len(), 9);
+        assert_eq!(rows[0], ("Start / Split", "Space".to_owned()));
+        assert_eq!(
+            rows.last(),
+            Some(&("Undo All Pauses", "Unbound".to_owned()))
+        );
+
+        // Rebinding shows up the next time the rows are read
+        config.split = key(KeyCode::Numpad1);
+        assert_eq!(binding_rows(&config)[0].1, "Numpad 1");
+    }
+}
@@ -5,6 +5,11 @@ use adw::{
 use gtk4::{self as gtk, StringList};
 use livesplit_core::TimingMethod;
 
+use crate::config::{GoldSource, SkippedInSumOfBest};
+use crate::formatters::Rounding;
+use crate::ui::theme;
+use crate::ui::video_offset::{present_calibration, refresh_video_offset_indicator};
+
 #[derive(Clone, Copy)]
 enum FormatTarget {
     Timer,
@@ -56,8 +61,152 @@ impl TimerPreferencesDialog {
         let timing_group = PreferencesGroup::builder().title("Timing").build();
         let timing_row = self.build_timing_method_row();
         timing_group.add(&timing_row);
+        let gold_source_row = self.build_gold_source_row();
+        timing_group.add(&gold_source_row);
+        timing_group.add(&self.build_sum_of_best_row());
         page.add(&timing_group);
 
+        // Behavior Group
+        let behavior_group = PreferencesGroup::builder().title("Behavior").build();
+        let inhibit_row = SwitchRow::builder()
+            .title("Prevent Sleep During Runs")
+            .subtitle("Block screen blanking and suspend while the timer is running")
+            .active(
+                crate::context::TuxSplitContext::get_instance()
+                    .config()
+                    .behavior
+                    .inhibit_idle,
+            )
+            .build();
+        inhibit_row.connect_active_notify(|r| {
+            if let Ok(mut cfg) = crate::context::TuxSplitContext::get_instance().config_mut() {
+                cfg.behavior.inhibit_idle = r.is_active();
+            }
+        });
+        behavior_group.add(&inhibit_row);
+
+        let live_gold_row = SwitchRow::builder()
+            .title("Live Gold Indicator")
+            .subtitle("Mark the current split while its gold is still reachable")
+            .active(
+                crate::context::TuxSplitContext::get_instance()
+                    .config()
+                    .behavior
+                    .live_gold_indicator,
+            )
+            .build();
+        live_gold_row.connect_active_notify(|r| {
+            if let Ok(mut cfg) = crate::context::TuxSplitContext::get_instance().config_mut() {
+                cfg.behavior.live_gold_indicator = r.is_active();
+            }
+        });
+        behavior_group.add(&live_gold_row);
+
+        let switch_to_pb_row = SwitchRow::builder()
+            .title("Compare Against New PBs")
+            .subtitle("Switch to Personal Best after a run that sets one")
+            .active(
+                crate::context::TuxSplitContext::get_instance()
+                    .config()
+                    .behavior
+                    .switch_to_pb_after_pb,
+            )
+            .build();
+        switch_to_pb_row.connect_active_notify(|r| {
+            if let Ok(mut cfg) = crate::context::TuxSplitContext::get_instance().config_mut() {
+                cfg.behavior.switch_to_pb_after_pb = r.is_active();
+            }
+        });
+        behavior_group.add(&switch_to_pb_row);
+
+        let unfocused_hotkeys_row = SwitchRow::builder()
+            .title("Global Hotkeys Only When Unfocused")
+            .subtitle("While TuxSplit has focus, hotkeys only work as regular key presses")
+            .active(
+                crate::context::TuxSplitContext::get_instance()
+                    .config()
+                    .behavior
+                    .hotkeys_only_unfocused,
+            )
+            .build();
+        unfocused_hotkeys_row.connect_active_notify(|r| {
+            if let Ok(mut cfg) = crate::context::TuxSplitContext::get_instance().config_mut() {
+                cfg.behavior.hotkeys_only_unfocused = r.is_active();
+                crate::ui::hotkeys::refresh_hotkey_activation(&cfg);
+            }
+        });
+        behavior_group.add(&unfocused_hotkeys_row);
+
+        let typing_row = SwitchRow::builder()
+            .title("Pause Hotkeys While Typing")
+            .subtitle("Global hotkeys are ignored while a text field has focus")
+            .active(
+                crate::context::TuxSplitContext::get_instance()
+                    .config()
+                    .behavior
+                    .suppress_hotkeys_while_typing,
+            )
+            .build();
+        typing_row.connect_active_notify(|r| {
+            if let Ok(mut cfg) = crate::context::TuxSplitContext::get_instance().config_mut() {
+                cfg.behavior.suppress_hotkeys_while_typing = r.is_active();
+            }
+            crate::ui::hotkeys::set_typing_suppression_enabled(r.is_active());
+        });
+        behavior_group.add(&typing_row);
+
+        let window_hotkeys_row = SwitchRow::builder()
+            .title("Window Hotkeys")
+            .subtitle("Only handle hotkeys while TuxSplit has focus. Runs natively on Wayland. Takes effect after a restart")
+            .active(
+                crate::context::TuxSplitContext::get_instance()
+                    .config()
+                    .behavior
+                    .window_hotkeys,
+            )
+            .build();
+        window_hotkeys_row.connect_active_notify(|r| {
+            if let Ok(mut cfg) = crate::context::TuxSplitContext::get_instance().config_mut() {
+                cfg.behavior.window_hotkeys = r.is_active();
+            }
+        });
+        behavior_group.add(&window_hotkeys_row);
+
+        let video_offset_row = SpinRow::with_range(0.0, 500.0, 1.0);
+        video_offset_row.set_title("Video Offset (ms)");
+        video_offset_row.set_subtitle(
+            "Show live times earlier to match a delayed capture. Recorded times are not affected",
+        );
+        video_offset_row.set_value(f64::from(
+            crate::context::TuxSplitContext::get_instance()
+                .config()
+                .behavior
+                .video_offset_ms,
+        ));
+        video_offset_row.connect_value_notify(|r| {
+            let value = r.value().round().clamp(0.0, 500.0) as u32;
+            if let Ok(mut cfg) = crate::context::TuxSplitContext::get_instance().config_mut() {
+                cfg.behavior.video_offset_ms = value;
+            }
+            refresh_video_offset_indicator(value);
+        });
+        let calibrate_button = gtk::Button::builder()
+            .label("Calibrate")
+            .valign(gtk::Align::Center)
+            .css_classes(["flat"])
+            .build();
+        let row_for_calibration = video_offset_row.clone();
+        let dialog_for_calibration = self.dialog.clone();
+        calibrate_button.connect_clicked(move |_| {
+            let row = row_for_calibration.clone();
+            present_calibration(&dialog_for_calibration, move |ms| {
+                row.set_value(f64::from(ms.min(500)));
+            });
+        });
+        video_offset_row.add_suffix(&calibrate_button);
+        behavior_group.add(&video_offset_row);
+        page.add(&behavior_group);
+
         // Additional Info Visibility Group
         let additional_info_group = PreferencesGroup::builder().title("Additional Info").build();
 
@@ -128,8 +277,33 @@ impl TimerPreferencesDialog {
                 "Toggle visibility of the probability of achieving a Personal Best",
                 show_pb_chance
             );
+            add_switch!(
+                gold_pace_row,
+                "Show Gold Pace",
+                "Toggle visibility of the live time left before losing the current segment's gold",
+                show_gold_pace
+            );
+            add_switch!(
+                segment_timer_row,
+                "Show Segment Time",
+                "Toggle visibility of the time spent in the current segment so far",
+                show_segment_timer
+            );
+            add_switch!(
+                remaining_best_row,
+                "Show Remaining at Gold Pace",
+                "Toggle visibility of the time the rest of the run takes at gold pace",
+                show_remaining_best
+            );
+            add_switch!(
+                race_row,
+                "Show Race Opponent",
+                "Toggle visibility of the delta against the opponent during a race",
+                show_race
+            );
         }
 
+        additional_info_group.add(&self.build_analytics_comparison_row());
         page.add(&additional_info_group);
 
         page
@@ -201,10 +375,242 @@ impl TimerPreferencesDialog {
             }
         });
 
+        let show_target_row = SwitchRow::builder()
+            .title("Show Target Time")
+            .subtitle("Show the comparison's final time above the timer")
+            .build();
+        show_target_row.set_active(
+            crate::context::TuxSplitContext::get_instance()
+                .config()
+                .style
+                .show_target_time
+                .unwrap_or(true),
+        );
+        show_target_row.connect_active_notify(|r| {
+            if let Ok(mut cfg) = crate::context::TuxSplitContext::get_instance().config_mut() {
+                cfg.style.show_target_time = Some(r.is_active());
+            }
+        });
+
+        let show_running_row = SwitchRow::builder()
+            .title("Show Segment Running Time")
+            .subtitle("Show the raw time of the current segment under the current split info")
+            .build();
+        show_running_row.set_active(
+            crate::context::TuxSplitContext::get_instance()
+                .config()
+                .style
+                .show_segment_running_time
+                .unwrap_or(false),
+        );
+        show_running_row.connect_active_notify(|r| {
+            if let Ok(mut cfg) = crate::context::TuxSplitContext::get_instance().config_mut() {
+                cfg.style.show_segment_running_time = Some(r.is_active());
+            }
+        });
+
+        let show_breakdown_row = SwitchRow::builder()
+            .title("Show Segment Breakdown")
+            .subtitle("Show the best, PB and current time of the selected segment together")
+            .build();
+        show_breakdown_row.set_active(
+            crate::context::TuxSplitContext::get_instance()
+                .config()
+                .style
+                .show_segment_breakdown
+                .unwrap_or(false),
+        );
+        show_breakdown_row.connect_active_notify(|r| {
+            if let Ok(mut cfg) = crate::context::TuxSplitContext::get_instance().config_mut() {
+                cfg.style.show_segment_breakdown = Some(r.is_active());
+            }
+        });
+
+        let reverse_row = SwitchRow::builder()
+            .title("Reverse Split Order")
+            .subtitle("List the splits from the last one up, with the final split on top")
+            .build();
+        reverse_row.set_active(
+            crate::context::TuxSplitContext::get_instance()
+                .config()
+                .style
+                .reverse_segments
+                .unwrap_or(false),
+        );
+        reverse_row.connect_active_notify(|r| {
+            let ctx = crate::context::TuxSplitContext::get_instance();
+            if let Ok(mut cfg) = ctx.config_mut() {
+                cfg.style.reverse_segments = Some(r.is_active());
+                drop(cfg);
+                ctx.emit_by_name::<()>("run-changed", &[]);
+            }
+        });
+
+        let show_time_save_row = SwitchRow::builder()
+            .title("Show Time Save Column")
+            .subtitle(
+                "Time that can be saved on upcoming splits, and saved or lost on completed ones",
+            )
+            .build();
+        show_time_save_row.set_active(
+            crate::context::TuxSplitContext::get_instance()
+                .config()
+                .layout
+                .show_timesave_column,
+        );
+        show_time_save_row.connect_active_notify(|r| {
+            let ctx = crate::context::TuxSplitContext::get_instance();
+            if let Ok(mut cfg) = ctx.config_mut() {
+                cfg.layout.show_timesave_column = r.is_active();
+                drop(cfg);
+                ctx.emit_by_name::<()>("run-changed", &[]);
+            }
+        });
+
+        let delta_bars_row = SwitchRow::builder()
+            .title("Show Delta Bars")
+            .subtitle("A bar next to each completed split, as long as its delta")
+            .build();
+        delta_bars_row.set_active(
+            crate::context::TuxSplitContext::get_instance()
+                .config()
+                .layout
+                .show_delta_bars,
+        );
+        delta_bars_row.connect_active_notify(|r| {
+            let ctx = crate::context::TuxSplitContext::get_instance();
+            if let Ok(mut cfg) = ctx.config_mut() {
+                cfg.layout.show_delta_bars = r.is_active();
+                drop(cfg);
+                ctx.emit_by_name::<()>("run-changed", &[]);
+            }
+        });
+
+        // The split name picks these up on the next frame
+        let split_name_row = SwitchRow::builder()
+            .title("Show Current Split Name")
+            .subtitle("The segment being run, above the timer")
+            .build();
+        split_name_row.set_active(
+            crate::context::TuxSplitContext::get_instance()
+                .config()
+                .layout
+                .show_current_split_name,
+        );
+        split_name_row.connect_active_notify(|r| {
+            let ctx = crate::context::TuxSplitContext::get_instance();
+            if let Ok(mut cfg) = ctx.config_mut() {
+                cfg.layout.show_current_split_name = r.is_active();
+            }
+        });
+
+        let next_split_name_row = SwitchRow::builder()
+            .title("Show Next Split Name")
+            .subtitle("The segment after it, under the current split name")
+            .build();
+        next_split_name_row.set_active(
+            crate::context::TuxSplitContext::get_instance()
+                .config()
+                .layout
+                .show_next_split_name,
+        );
+        next_split_name_row.connect_active_notify(|r| {
+            let ctx = crate::context::TuxSplitContext::get_instance();
+            if let Ok(mut cfg) = ctx.config_mut() {
+                cfg.layout.show_next_split_name = r.is_active();
+            }
+        });
+
         segments_group.add(&max_segments_row);
         segments_group.add(&follow_from_row);
         segments_group.add(&show_icons_row);
+        segments_group.add(&reverse_row);
+        segments_group.add(&show_target_row);
+        segments_group.add(&show_running_row);
+        segments_group.add(&show_breakdown_row);
+        segments_group.add(&show_time_save_row);
+        segments_group.add(&delta_bars_row);
+        segments_group.add(&split_name_row);
+        segments_group.add(&next_split_name_row);
+
+        let split_button_row = SwitchRow::builder()
+            .title("Show Split Button")
+            .subtitle("A large button under the timer for touchscreens")
+            .build();
+        split_button_row.set_active(
+            crate::context::TuxSplitContext::get_instance()
+                .config()
+                .layout
+                .show_split_button,
+        );
+        split_button_row.connect_active_notify(|r| {
+            let ctx = crate::context::TuxSplitContext::get_instance();
+            if let Ok(mut cfg) = ctx.config_mut() {
+                cfg.layout.show_split_button = r.is_active();
+            }
+        });
+        segments_group.add(&split_button_row);
+
+        let split_button_height_row = SpinRow::with_range(48.0, 400.0, 8.0);
+        split_button_height_row.set_title("Split Button Height");
+        split_button_height_row.set_subtitle("In pixels");
+        split_button_height_row.set_value(f64::from(
+            crate::context::TuxSplitContext::get_instance()
+                .config()
+                .layout
+                .split_button_height,
+        ));
+        split_button_height_row.connect_value_notify(|r| {
+            let ctx = crate::context::TuxSplitContext::get_instance();
+            if let Ok(mut cfg) = ctx.config_mut() {
+                cfg.layout.split_button_height = r.value().round() as i32;
+            }
+        });
+        segments_group.add(&split_button_height_row);
+
+        let value_width_row = SpinRow::with_range(0.0, 300.0, 10.0);
+        value_width_row.set_title("Time Column Width");
+        value_width_row.set_subtitle("Pixels reserved for each time so they line up, 0 to fit");
+        value_width_row.set_value(f64::from(
+            crate::context::TuxSplitContext::get_instance()
+                .config()
+                .layout
+                .value_min_width,
+        ));
+        value_width_row.connect_value_notify(|r| {
+            let ctx = crate::context::TuxSplitContext::get_instance();
+            if let Ok(mut cfg) = ctx.config_mut() {
+                cfg.layout.value_min_width = r.value().round() as i32;
+                drop(cfg);
+                ctx.emit_by_name::<()>("run-changed", &[]);
+            }
+        });
+        segments_group.add(&value_width_row);
 
+        let align_column_row = SwitchRow::builder()
+            .title("Align Time Column")
+            .subtitle("Line up split times with the target time and additional info")
+            .build();
+        align_column_row.set_active(
+            crate::context::TuxSplitContext::get_instance()
+                .config()
+                .layout
+                .align_value_column,
+        );
+        align_column_row.connect_active_notify(|r| {
+            let ctx = crate::context::TuxSplitContext::get_instance();
+            if let Ok(mut cfg) = ctx.config_mut() {
+                cfg.layout.align_value_column = r.is_active();
+                drop(cfg);
+                ctx.emit_by_name::<()>("run-changed", &[]);
+            }
+        });
+        segments_group.add(&align_column_row);
+
+        let theme_group = PreferencesGroup::builder().title("Theme").build();
+        theme_group.add(&self.build_theme_profile_row());
+
+        page.add(&theme_group);
         page.add(&segments_group);
         page
     }
@@ -245,12 +651,93 @@ impl TimerPreferencesDialog {
         );
         formats_group.add(&comparison_expander);
 
+        let adaptive_row = SwitchRow::builder()
+            .title("Adaptive Timer Precision")
+            .subtitle(
+                "Show more decimals on the timer while a gold or the comparison is within reach",
+            )
+            .active(
+                crate::context::TuxSplitContext::get_instance()
+                    .config()
+                    .format
+                    .adaptive_precision,
+            )
+            .build();
+        adaptive_row.connect_active_notify(|r| {
+            if let Ok(mut cfg) = crate::context::TuxSplitContext::get_instance().config_mut() {
+                cfg.format.adaptive_precision = r.is_active();
+            }
+        });
+        formats_group.add(&adaptive_row);
+
+        let round_deltas_row = SwitchRow::builder()
+            .title("Round Deltas")
+            .subtitle("Round deltas to the nearest shown digit instead of cutting them off. Split times stay truncated")
+            .active(
+                crate::context::TuxSplitContext::get_instance()
+                    .config()
+                    .format
+                    .delta_rounding
+                    != Rounding::Truncate,
+            )
+            .build();
+        round_deltas_row.connect_active_notify(|r| {
+            let ctx = crate::context::TuxSplitContext::get_instance();
+            if let Ok(mut cfg) = ctx.config_mut() {
+                cfg.format.delta_rounding = if r.is_active() {
+                    Rounding::Nearest
+                } else {
+                    Rounding::Truncate
+                };
+                drop(cfg);
+                ctx.emit_by_name::<()>("run-changed", &[]);
+            }
+        });
+        formats_group.add(&round_deltas_row);
+
         page.add(&formats_group);
         page
     }
 
     // ------------- Rows -------------
 
+    fn build_theme_profile_row(&self) -> ComboRow {
+        let (names, selected) = {
+            let ctx = crate::context::TuxSplitContext::get_instance();
+            let c = ctx.config();
+            let names = theme::profile_names(&c.theme);
+            let selected = c
+                .theme
+                .profile
+                .as_ref()
+                .and_then(|p| names.iter().position(|n| n == p))
+                .unwrap_or(0);
+            (names, selected)
+        };
+
+        let model = StringList::new(&names.iter().map(String::as_str).collect::<Vec<_>>());
+        let row = ComboRow::builder()
+            .title("Color Profile")
+            .subtitle(format!("Active: {}", theme::active_profile()))
+            .model(&model)
+            .selected(selected as u32)
+            .build();
+
+        row.connect_selected_notify(move |r| {
+            let Some(name) = names.get(r.selected() as usize) else {
+                return;
+            };
+            let ctx = crate::context::TuxSplitContext::get_instance();
+            if let Ok(mut cfg) = ctx.config_mut() {
+                cfg.theme.profile = Some(name.clone());
+            }
+            theme::apply_theme(&ctx.config().theme);
+            r.set_subtitle(&format!("Active: {}", theme::active_profile()));
+        });
+
+        row
+    }
+
     fn build_timing_method_row(&self) -> ComboRow {
         let model = StringList::new(&["Real Time", "Game Time"]);
         let row = ComboRow::builder()
@@ -259,12 +746,13 @@ impl TimerPreferencesDialog {
             .build();
         row.set_model(Some(&model));
 
+        // Reflect the method actually in use, which may come from the splits file
         let initial_selected = {
             let ctx = crate::context::TuxSplitContext::get_instance();
             let c = ctx.config();
-            match c.general.timing_method {
-                Some(TimingMethod::GameTime) => 1,
-                _ => 0, // default Real Time
+            match c.timing_method_for(&ctx.get_run()) {
+                TimingMethod::GameTime => 1,
+                TimingMethod::RealTime => 0,
             }
         };
         row.set_selected(initial_selected);
@@ -278,7 +766,7 @@ impl TimerPreferencesDialog {
             };
 
             if let Ok(mut cfg) = crate::context::TuxSplitContext::get_instance().config_mut() {
-                cfg.general.timing_method = Some(method);
+                cfg.set_timing_method_override(method);
             }
 
             if let Ok(mut t) = crate::context::TuxSplitContext::get_instance()
@@ -292,13 +780,125 @@ impl TimerPreferencesDialog {
         row
     }
 
+    fn build_gold_source_row(&self) -> ComboRow {
+        let model = StringList::new(&["Stored best segments", "This session only"]);
+        let row = ComboRow::builder()
+            .title("Gold Splits")
+            .subtitle("Choose what a segment has to beat to count as gold")
+            .build();
+        row.set_model(Some(&model));
+
+        let initial_selected = {
+            let ctx = crate::context::TuxSplitContext::get_instance();
+            let c = ctx.config();
+            match c.general.gold_source {
+                GoldSource::StoredBest => 0,
+                GoldSource::SessionBest => 1,
+            }
+        };
+        row.set_selected(initial_selected);
+
+        row.connect_selected_notify(move |r| {
+            let source = if r.selected() == 1 {
+                GoldSource::SessionBest
+            } else {
+                GoldSource::StoredBest
+            };
+
+            let ctx = crate::context::TuxSplitContext::get_instance();
+            if let Ok(mut cfg) = ctx.config_mut() {
+                cfg.general.gold_source = source;
+                drop(cfg);
+                ctx.emit_run_changed();
+            }
+        });
+
+        row
+    }
+
+    fn build_sum_of_best_row(&self) -> ComboRow {
+        let model = StringList::new(&["Unknown total", "Left out"]);
+        let row = ComboRow::builder()
+            .title("Segments Without a Best")
+            .subtitle("How always skipped segments count toward the best possible time")
+            .build();
+        row.set_model(Some(&model));
+        row.set_selected(
+            match crate::context::TuxSplitContext::get_instance()
+                .config()
+                .general
+                .skipped_in_sum_of_best
+            {
+                SkippedInSumOfBest::Void => 0,
+                SkippedInSumOfBest::Exclude => 1,
+            },
+        );
+
+        row.connect_selected_notify(move |r| {
+            let policy = if r.selected() == 1 {
+                SkippedInSumOfBest::Exclude
+            } else {
+                SkippedInSumOfBest::Void
+            };
+
+            let ctx = crate::context::TuxSplitContext::get_instance();
+            if let Ok(mut cfg) = ctx.config_mut() {
+                cfg.general.skipped_in_sum_of_best = policy;
+                drop(cfg);
+                ctx.emit_run_changed();
+            }
+        });
+
+        row
+    }
+
+    fn build_analytics_comparison_row(&self) -> ComboRow {
+        let (comparisons, selected) = {
+            let ctx = crate::context::TuxSplitContext::get_instance();
+            let c = ctx.config();
+            let comparisons: Vec<String> = ctx.get_run().comparisons().map(str::to_owned).collect();
+            let selected = c
+                .general
+                .analytics_comparison
+                .as_ref()
+                .and_then(|name| comparisons.iter().position(|n| n == name))
+                .map_or(0, |index| index + 1);
+            (comparisons, selected)
+        };
+
+        let labels: Vec<&str> = std::iter::once("Current Comparison")
+            .chain(comparisons.iter().map(String::as_str))
+            .collect();
+        let row = ComboRow::builder()
+            .title("Analytics Comparison")
+            .subtitle("Comparison used by the previous segment, possible time save and pace")
+            .model(&StringList::new(&labels))
+            .selected(selected as u32)
+            .build();
+
+        row.connect_selected_notify(move |r| {
+            let comparison = (r.selected() as usize)
+                .checked_sub(1)
+                .and_then(|index| comparisons.get(index))
+                .cloned();
+            let ctx = crate::context::TuxSplitContext::get_instance();
+            if let Ok(mut cfg) = ctx.config_mut() {
+                cfg.general.analytics_comparison = comparison;
+                drop(cfg);
+                ctx.emit_run_changed();
+            }
+        });
+
+        row
+    }
+
     fn build_format_expander(
         &self,
         title: &str,
         subtitle: &str,
         target: FormatTarget,
     ) -> ExpanderRow {
-        let (initial_mode_index, initial_decimals) = {
+        let (initial_mode_index, initial_decimals, initial_days) = {
             let ctx = crate::context::TuxSplitContext::get_instance();
             let cfg = ctx.config();
             let tf = match target {
@@ -312,7 +912,7 @@ impl TimerPreferencesDialog {
             } else {
                 2
             };
-            (mode, tf.decimal_places)
+            (mode, tf.decimal_places, tf.show_days)
         };
 
         let expander = ExpanderRow::builder()
@@ -376,8 +976,26 @@ impl TimerPreferencesDialog {
             }
         });
 
+        let days_row = SwitchRow::builder()
+            .title("Show days")
+            .subtitle("29:33:12 shows as 1d 05:33:12")
+            .active(initial_days)
+            .build();
+        days_row.connect_active_notify(move |row| {
+            if let Ok(mut cfg) = crate::context::TuxSplitContext::get_instance().config_mut() {
+                let tf = match target {
+                    FormatTarget::Timer => &mut cfg.format.timer,
+                    FormatTarget::Split => &mut cfg.format.split,
+                    FormatTarget::Segment => &mut cfg.format.segment,
+                    FormatTarget::Comparison => &mut cfg.format.comparison,
+                };
+                tf.set_show_days(row.is_active());
+            }
+        });
+
         expander.add_row(&mode_row);
         expander.add_row(&decimals_row);
+        expander.add_row(&days_row);
 
         expander
     }
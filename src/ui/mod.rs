@@ -1,7 +1,31 @@
+pub mod attempt_edit;
+pub mod crash_report;
+pub mod detached_timer;
+pub mod drill;
 pub mod editor;
+pub mod export_image;
+pub mod flash;
+pub mod freeze_watch;
 pub mod header;
+pub mod hotkeys;
 pub mod info;
+pub mod inhibit;
+pub mod integrity;
+pub mod keybindings;
 pub mod menu;
+pub mod offset_conflict;
+pub mod pb_switch;
+#[cfg(feature = "gamepad")]
+pub mod pedal;
+pub mod race;
+#[cfg(feature = "racetime")]
+pub mod racetime;
+pub mod run_style;
+pub mod saved_attempt;
+pub mod scale;
+pub mod setting_undo;
+pub mod theme;
 pub mod timer;
+pub mod video_offset;
 
 pub use header::TuxSplitHeader;
@@ -0,0 +1,232 @@
+//! The banner for a start offset the auto splitter's starts put in the
+//! times, with the two ways out of it. The decisions are in
+//! [`crate::utils::offset_conflict`].
+//!
+//! Starts TuxSplit makes itself are noted with [`note_start_issued`], so
+//! the watch can tell the others apart on the tick that sees the attempt
+//! begin.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use adw::prelude::*;
+use adw::{AlertDialog, Banner, ToolbarView};
+use glib::ControlFlow;
+use gtk4::Align;
+use livesplit_core::{TimeSpan, Timer, TimerPhase};
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::context::TuxSplitContext;
+use crate::io::attempt_state::own_offset;
+use crate::ui::hotkeys::hotkey_state;
+use crate::ui::timer::modal::show_notice;
+use crate::ui::timer::tick_fanout::subscribe_tick;
+use crate::utils::offset_conflict::{
+    StartResponse, describe_conflict, offset_conflicts, respond_to_start, start_source,
+};
+
+thread_local! {
+    static START_ISSUED: Cell<bool> = const { Cell::new(false) };
+    static BANNER: RefCell<Option<Banner>> = const { RefCell::new(None) };
+    /// Files the conflict was kept for, until TuxSplit is closed.
+    static KEPT: RefCell<HashSet<PathBuf>> = RefCell::new(HashSet::new());
+}
+
+/// Records that TuxSplit may have just started the timer itself.
+pub fn note_start_issued() {
+    START_ISSUED.set(true);
+}
+
+/// Whether the loaded file's runs get the countdown in their times, and
+/// the file isn't one the conflict was kept for.
+fn conflict(config: &Config, offset: TimeSpan) -> bool {
+    let state = config.splits_state();
+    offset_conflicts(
+        offset,
+        config.general.auto_splitter.is_some(),
+        state.is_some_and(|state| state.auto_splitter_starts),
+        state.is_some_and(|state| state.ignore_auto_splitter_start),
+    ) && config
+        .general
+        .splits
+        .as_ref()
+        .is_none_or(|path| !KEPT.with_borrow(|kept| kept.contains(path)))
+}
+
+fn refresh_banner() {
+    let ctx = TuxSplitContext::get_instance();
+    let offset = {
+        let shared = ctx.timer();
+        own_offset(shared.read().unwrap().run())
+    };
+    let Ok(config) = ctx.try_config() else {
+        return;
+    };
+    let show = conflict(&config, offset);
+    BANNER.with_borrow(|banner| {
+        if let Some(banner) = banner {
+            banner.set_title(&describe_conflict(offset));
+            banner.set_revealed(show);
+        }
+    });
+}
+
+/// Zeroes the offset once no attempt is running, so the current one is
+/// left as it is.
+fn zero_offset() {
+    let apply = || {
+        let ctx = TuxSplitContext::get_instance();
+        let mut run = ctx.get_run();
+        run.set_offset(TimeSpan::zero());
+        match ctx.set_run(run) {
+            Ok(()) => info!("Zeroed the start offset for the auto splitter"),
+            Err(err) => show_notice(&format!("The offset was not zeroed: {err}")),
+        }
+    };
+    let running = {
+        let shared = TuxSplitContext::get_instance().timer();
+        shared.read().unwrap().current_phase() != TimerPhase::NotRunning
+    };
+    if !running {
+        apply();
+        return;
+    }
+    show_notice("The offset is zeroed once this attempt is over");
+    subscribe_tick(move |timer, _| {
+        if timer.current_phase() != TimerPhase::NotRunning {
+            return ControlFlow::Continue;
+        }
+        glib::idle_add_local_once(apply);
+        ControlFlow::Break
+    });
+}
+
+fn ignore_auto_start() {
+    let ctx = TuxSplitContext::get_instance();
+    if let Ok(mut config) = ctx.config_mut()
+        && let Some(state) = config.splits_state_mut()
+    {
+        state.ignore_auto_splitter_start = true;
+        info!("Ignoring the auto splitter's starts for this file");
+    }
+    show_notice("The auto splitter's starts are ignored for this file");
+}
+
+fn present_choices(banner: &Banner) {
+    let dialog = AlertDialog::builder()
+        .heading("Start Offset and Auto Splitter")
+        .body(
+            "The offset is meant for starting by hand ahead of the run, but the auto \
+             splitter starts the timer when the run begins, so the offset ends up in \
+             every time. Zero the offset to let the auto splitter start the runs, or \
+             ignore its starts to start them yourself. Starts can only be told apart \
+             from global hotkeys while those are off, e.g. with the window focused.",
+        )
+        .default_response("zero")
+        .close_response("keep")
+        .build();
+    dialog.add_response("keep", "Keep Both");
+    dialog.add_response("ignore", "Ignore Auto-Start");
+    dialog.add_response("zero", "Zero the Offset");
+    dialog.set_response_appearance("zero", adw::ResponseAppearance::Suggested);
+    dialog.connect_response(None, |_, response| {
+        match response {
+            "zero" => zero_offset(),
+            "ignore" => ignore_auto_start(),
+            _ => {
+                let path = TuxSplitContext::get_instance()
+                    .config()
+                    .general
+                    .splits
+                    .clone();
+                if let Some(path) = path {
+                    KEPT.with_borrow_mut(|kept| kept.insert(path));
+                }
+            }
+        }
+        refresh_banner();
+    });
+    dialog.present(Some(banner));
+}
+
+/// Learns or undoes the start `timer` just made.
+fn on_start(timer: &Timer, config: &Config) {
+    let source = start_source(
+        START_ISSUED.get(),
+        config.general.auto_splitter.is_some(),
+        hotkey_state().global_active(&config.behavior),
+    );
+    let state = config.splits_state();
+    let response = respond_to_start(
+        source,
+        state.is_some_and(|state| state.auto_splitter_starts),
+        state.is_some_and(|state| state.ignore_auto_splitter_start),
+    );
+    if response == StartResponse::Keep {
+        return;
+    }
+    let offset = timer.run().offset();
+    glib::idle_add_local_once(move || {
+        let ctx = TuxSplitContext::get_instance();
+        match response {
+            StartResponse::Undo => {
+                {
+                    let shared = ctx.timer();
+                    shared.write().unwrap().reset(false);
+                }
+                // The start counted an attempt that never happened
+                let mut run = ctx.get_run();
+                run.set_attempt_count(run.attempt_count().saturating_sub(1));
+                if let Err(err) = ctx.set_run(run) {
+                    warn!("The attempt count of the undone start was kept: {err}");
+                }
+                info!("Undid a start made by the auto splitter");
+                show_notice("Ignored the auto splitter's start, start the timer yourself");
+            }
+            StartResponse::Remember => {
+                if let Ok(mut config) = ctx.config_mut()
+                    && let Some(state) = config.splits_state_mut()
+                {
+                    state.auto_splitter_starts = true;
+                }
+                if offset != TimeSpan::zero() {
+                    info!("The auto splitter started a run that has a start offset");
+                }
+                refresh_banner();
+            }
+            StartResponse::Keep => {}
+        }
+    });
+}
+
+/// Puts the banner under the header bar of `toolbar`, checked whenever a
+/// run is loaded and on the first start the auto splitter is seen making.
+pub fn start_offset_conflict_watch(toolbar: &ToolbarView) {
+    let banner = Banner::builder()
+        .button_label("Fix…")
+        .valign(Align::Start)
+        .revealed(false)
+        .build();
+    banner.connect_button_clicked(present_choices);
+    toolbar.add_top_bar(&banner);
+    BANNER.with_borrow_mut(|slot| *slot = Some(banner));
+    refresh_banner();
+
+    TuxSplitContext::get_instance().connect_local("run-changed", false, |_| {
+        refresh_banner();
+        None
+    });
+
+    let mut last_phase = TimerPhase::NotRunning;
+    subscribe_tick(move |timer, config| {
+        let phase = timer.current_phase();
+        if last_phase == TimerPhase::NotRunning && phase != TimerPhase::NotRunning {
+            on_start(timer, config);
+        }
+        last_phase = phase;
+        START_ISSUED.set(false);
+        ControlFlow::Continue
+    });
+}
@@ -0,0 +1,33 @@
+//! "Compare Against New PBs": the current comparison moves to Personal Best
+//! once a reset saved a new one, whichever way the reset came.
+
+use glib::ControlFlow;
+use livesplit_core::comparison::personal_best;
+use tracing::info;
+
+use crate::context::TuxSplitContext;
+use crate::ui::timer::tick_fanout::subscribe_tick;
+use crate::utils::comparisons::NewPbWatch;
+
+fn switch_to_pb() {
+    let ctx = TuxSplitContext::get_instance();
+    let shared = ctx.timer();
+    let mut timer = shared.write().unwrap();
+    if timer.set_current_comparison(personal_best::NAME).is_ok() {
+        info!("New personal best, comparing against it");
+    }
+}
+
+pub fn start_pb_switch() {
+    let mut watch = NewPbWatch::default();
+    subscribe_tick(move |timer, config| {
+        if watch.observe(timer)
+            && config.behavior.switch_to_pb_after_pb
+            && timer.current_comparison() != personal_best::NAME
+        {
+            // The timer can't be written from inside the tick
+            glib::idle_add_local_once(switch_to_pb);
+        }
+        ControlFlow::Continue
+    });
+}
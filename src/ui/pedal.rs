@@ -0,0 +1,244 @@
+//! Timer actions from foot pedals and gamepad buttons.
+//!
+//! The listener only runs while a device is configured or a button is being
+//! captured, and its events are polled on the GTK thread. Actions go through
+//! [`offer_action`] like the hotkeys handled as key presses, so an open
+//! dialog holds them back the same way. The keybindings overlay shows the
+//! device picker and the buttons, see [`pedal_section`].
+
+use std::cell::{Cell, RefCell};
+use std::time::Duration;
+
+use adw::prelude::*;
+use adw::{ActionRow, ComboRow};
+use glib::ControlFlow;
+use gtk4::{Align, Box as GtkBox, Button, Label, ListBox, Orientation, StringList};
+use tracing::info;
+
+use crate::context::TuxSplitContext;
+use crate::io::gamepad::GamepadListener;
+use crate::ui::hotkeys::{ActionSource, HotkeyAction, bindings};
+use crate::ui::timer::modal::{offer_action, show_notice};
+use crate::utils::pedal::{PadEvent, PadOutcome, PedalInput};
+
+thread_local! {
+    static INPUT: RefCell<PedalInput> = RefCell::new(PedalInput::default());
+    static LISTENER: RefCell<Option<GamepadListener>> = const { RefCell::new(None) };
+    /// Bumped for every listener, so the poll of a dropped one stops.
+    static GENERATION: Cell<u32> = const { Cell::new(0) };
+    /// Redraws the overlay's section while it is open.
+    static ON_CHANGE: RefCell<Option<Box<dyn Fn()>>> = const { RefCell::new(None) };
+}
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Starts or stops the listener for what the config and the capture need.
+pub fn sync_pedal_listener() {
+    let wanted = INPUT.with_borrow(|input| input.capturing().is_some())
+        || TuxSplitContext::get_instance()
+            .config()
+            .gamepad
+            .device
+            .is_some();
+    if wanted == LISTENER.with_borrow(Option::is_some) {
+        return;
+    }
+    let generation = GENERATION.get().wrapping_add(1);
+    GENERATION.set(generation);
+    if !wanted {
+        info!("No pedal or gamepad configured, stopped listening");
+        LISTENER.take();
+        return;
+    }
+    LISTENER.replace(Some(GamepadListener::spawn()));
+    glib::timeout_add_local(POLL_INTERVAL, move || {
+        if GENERATION.get() != generation {
+            return ControlFlow::Break;
+        }
+        let events = LISTENER.with_borrow(|listener| {
+            listener
+                .as_ref()
+                .map(GamepadListener::poll)
+                .unwrap_or_default()
+        });
+        for event in events {
+            handle(event);
+        }
+        ControlFlow::Continue
+    });
+}
+
+fn handle(event: PadEvent) {
+    let ctx = TuxSplitContext::get_instance();
+    let (notice, outcome) = {
+        let Ok(config) = ctx.try_config() else {
+            return;
+        };
+        INPUT.with_borrow_mut(|input| {
+            let notice = input.hotplug_notice(&config.gamepad, &event);
+            (notice, input.handle(&config.gamepad, event))
+        })
+    };
+    if let Some(notice) = notice {
+        info!("{notice}");
+        show_notice(&notice);
+    }
+    match outcome {
+        PadOutcome::Nothing => {}
+        PadOutcome::Run(action) => offer_action(action, ActionSource::Pedal),
+        PadOutcome::Bound { device, binding } => {
+            info!(
+                "Bound {} of {device} to {}",
+                binding.button,
+                binding.action.label()
+            );
+            if let Ok(mut config) = ctx.config_mut() {
+                config.gamepad.bind(device, binding);
+            }
+            notify_change();
+        }
+        PadOutcome::DevicesChanged => notify_change(),
+    }
+}
+
+fn notify_change() {
+    ON_CHANGE.with_borrow(|on_change| {
+        if let Some(on_change) = on_change {
+            on_change();
+        }
+    });
+}
+
+fn start_capture(action: HotkeyAction) {
+    INPUT.with_borrow_mut(|input| input.start_capture(action));
+    sync_pedal_listener();
+    notify_change();
+}
+
+fn cancel_capture() {
+    INPUT.with_borrow_mut(PedalInput::cancel_capture);
+    sync_pedal_listener();
+    notify_change();
+}
+
+fn select_device(device: Option<String>) {
+    let ctx = TuxSplitContext::get_instance();
+    if let Ok(mut config) = ctx.config_mut() {
+        config.gamepad.select_device(device);
+    }
+    sync_pedal_listener();
+    notify_change();
+}
+
+/// The device picker and the button of each action, each with a button to
+/// bind it by pressing it.
+pub fn pedal_section() -> GtkBox {
+    let container = GtkBox::builder()
+        .orientation(Orientation::Vertical)
+        .spacing(6)
+        .margin_top(12)
+        .build();
+    let heading = Label::builder()
+        .label("Pedals and Gamepads")
+        .halign(Align::Start)
+        .css_classes(["heading"])
+        .build();
+    let list = ListBox::new();
+    list.add_css_class("boxed-list");
+    container.append(&heading);
+    container.append(&list);
+
+    // Devices connected before listening show up once listening
+    sync_pedal_listener();
+    fill_pedal_list(&list);
+    let list_weak = list.downgrade();
+    ON_CHANGE.replace(Some(Box::new(move || {
+        if let Some(list) = list_weak.upgrade() {
+            fill_pedal_list(&list);
+        }
+    })));
+    container
+}
+
+/// Ends a capture left open and stops redrawing the section, for when the
+/// overlay closes.
+pub fn close_pedal_section() {
+    ON_CHANGE.take();
+    if INPUT.with_borrow(|input| input.capturing().is_some()) {
+        cancel_capture();
+    }
+}
+
+fn fill_pedal_list(list: &ListBox) {
+    list.remove_all();
+    let ctx = TuxSplitContext::get_instance();
+    let (options, actions) = {
+        let config = ctx.config();
+        (
+            config.gamepad.clone(),
+            bindings(config.hotkeys.active()).map(|(action, _)| action),
+        )
+    };
+    let (mut devices, capturing) = INPUT.with_borrow(|input| (input.devices(), input.capturing()));
+    if let Some(device) = &options.device
+        && !devices.contains(device)
+    {
+        devices.push(device.clone());
+    }
+
+    let names: Vec<&str> = std::iter::once("None")
+        .chain(devices.iter().map(String::as_str))
+        .collect();
+    let device_row = ComboRow::builder()
+        .title("Device")
+        .model(&StringList::new(&names))
+        .build();
+    let selected = options
+        .device
+        .as_ref()
+        .and_then(|device| devices.iter().position(|known| known == device))
+        .map_or(0, |position| position + 1);
+    device_row.set_selected(selected as u32);
+    device_row.connect_selected_notify(move |row| {
+        let device = (row.selected() as usize)
+            .checked_sub(1)
+            .and_then(|index| devices.get(index).cloned());
+        // The row is rebuilt, not from within its own handler
+        glib::idle_add_local_once(move || select_device(device));
+    });
+    list.append(&device_row);
+
+    for action in actions {
+        let bound = options
+            .buttons
+            .iter()
+            .find(|binding| binding.action == action)
+            .map(|binding| binding.button.as_str());
+        let waiting = capturing == Some(action);
+        let button_label = Label::new(Some(match (waiting, bound) {
+            (true, _) => "Press a button…",
+            (false, Some(button)) => button,
+            (false, None) => "Unbound",
+        }));
+        if bound.is_none() && !waiting {
+            button_label.add_css_class("dim-label");
+        }
+        let bind = Button::builder()
+            .label(if waiting { "Cancel" } else { "Bind" })
+            .valign(Align::Center)
+            .build();
+        bind.connect_clicked(move |_| {
+            glib::idle_add_local_once(move || {
+                if waiting {
+                    cancel_capture();
+                } else {
+                    start_capture(action);
+                }
+            });
+        });
+        let row = ActionRow::builder().title(action.label()).build();
+        row.add_suffix(&button_label);
+        row.add_suffix(&bind);
+        list.append(&row);
+    }
+}
@@ -0,0 +1,241 @@
+//! The race session shown in the footer and managed from the header menu.
+//!
+//! There is at most one session. It is polled on the main loop: opponent
+//! events update the ghost state and changes in our own timer are sent out.
+//! When the connection drops, the opponent's last known splits stay on
+//! screen, marked as disconnected, until the session is left.
+
+use std::cell::RefCell;
+use std::time::Duration;
+
+use adw::prelude::*;
+use adw::{AlertDialog, ApplicationWindow, EntryRow};
+use gtk4::ListBox;
+use livesplit_core::TimerPhase;
+use tracing::{info, warn};
+
+use crate::context::TuxSplitContext;
+use crate::race::net::{self, NetEvent, RaceConnection};
+use crate::race::{
+    OpponentState, PROTOCOL_VERSION, RaceMessage, RaceReporter, own_splits, room_code,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+thread_local! {
+    static SESSION: RefCell<Option<RaceSession>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RaceStatus {
+    Waiting,
+    Connected,
+    Disconnected(String),
+}
+
+struct RaceSession {
+    connection: RaceConnection,
+    reporter: RaceReporter,
+    opponent: OpponentState,
+    status: RaceStatus,
+    source: Option<glib::SourceId>,
+}
+
+/// Copy of the session state for display.
+pub struct RaceSnapshot {
+    pub opponent: OpponentState,
+    pub status: RaceStatus,
+}
+
+pub fn race_snapshot() -> Option<RaceSnapshot> {
+    SESSION.with_borrow(|session| {
+        session.as_ref().map(|session| RaceSnapshot {
+            opponent: session.opponent.clone(),
+            status: session.status.clone(),
+        })
+    })
+}
+
+fn poll_session() -> glib::ControlFlow {
+    let ctx = TuxSplitContext::get_instance();
+    let (running, splits, segments) = {
+        let shared = ctx.timer();
+        let timer = shared.read().unwrap();
+        (
+            timer.current_phase() != TimerPhase::NotRunning,
+            own_splits(&timer),
+            timer.run().len(),
+        )
+    };
+
+    SESSION.with_borrow_mut(|session| {
+        let Some(session) = session.as_mut() else {
+            return glib::ControlFlow::Break;
+        };
+        for event in session.connection.poll() {
+            match event {
+                NetEvent::Connected(peer) => {
+                    info!("Racing against {peer}");
+                    session.status = RaceStatus::Connected;
+                    session.opponent.connected = true;
+                }
+                NetEvent::Message(message) => session.opponent.apply(&message, segments),
+                NetEvent::Disconnected(reason) => {
+                    warn!("Race connection closed: {reason}");
+                    session.status = RaceStatus::Disconnected(reason);
+                    session.opponent.connected = false;
+                }
+            }
+        }
+        if session.status != RaceStatus::Waiting && !session.opponent.connected {
+            // Frozen: keep showing the last known state
+            return glib::ControlFlow::Continue;
+        }
+        for message in session.reporter.update(running, splits) {
+            session.connection.send(message);
+        }
+        glib::ControlFlow::Continue
+    })
+}
+
+fn start_session(connection: RaceConnection, code: String) {
+    leave_session();
+    let name = {
+        let ctx = TuxSplitContext::get_instance();
+        let config = ctx.config();
+        config.race.name.clone().unwrap_or_default()
+    };
+    connection.send(RaceMessage::Hello {
+        version: PROTOCOL_VERSION,
+        code,
+        name,
+    });
+
+    SESSION.replace(Some(RaceSession {
+        connection,
+        reporter: RaceReporter::default(),
+        opponent: OpponentState::default(),
+        status: RaceStatus::Waiting,
+        source: None,
+    }));
+    let source = glib::timeout_add_local(POLL_INTERVAL, poll_session);
+    SESSION.with_borrow_mut(|session| {
+        if let Some(session) = session.as_mut() {
+            session.source = Some(source);
+        }
+    });
+}
+
+/// Ends the current session, if any. Dropping the connection closes the socket.
+pub fn leave_session() {
+    if let Some(mut session) = SESSION.take()
+        && let Some(source) = session.source.take()
+    {
+        source.remove();
+        info!("Left the race");
+    }
+}
+
+fn show_error(parent: &ApplicationWindow, body: &str) {
+    let dialog = AlertDialog::builder()
+        .heading("Race")
+        .body(body)
+        .default_response("ok")
+        .build();
+    dialog.add_response("ok", "Okay");
+    dialog.present(Some(parent));
+}
+
+fn status_text(status: &RaceStatus, opponent: &OpponentState) -> String {
+    let name = opponent.name.as_deref().unwrap_or("your opponent");
+    match status {
+        RaceStatus::Waiting => "Waiting for the opponent to connect.".to_owned(),
+        RaceStatus::Connected => format!("Racing against {name}."),
+        RaceStatus::Disconnected(reason) => {
+            format!("The connection to {name} was lost ({reason}). Their last splits stay visible.")
+        }
+    }
+}
+
+/// Host, join or leave a race.
+pub fn present_race_dialog(parent: &ApplicationWindow) {
+    if let Some(snapshot) = race_snapshot() {
+        let dialog = AlertDialog::builder()
+            .heading("Race")
+            .body(status_text(&snapshot.status, &snapshot.opponent))
+            .default_response("close")
+            .close_response("close")
+            .build();
+        dialog.add_response("close", "Close");
+        dialog.add_response("leave", "Leave Race");
+        dialog.set_response_appearance("leave", adw::ResponseAppearance::Destructive);
+        dialog.connect_response(Some("leave"), |_, _| leave_session());
+        dialog.present(Some(parent));
+        return;
+    }
+
+    let (port, remote) = {
+        let ctx = TuxSplitContext::get_instance();
+        let config = ctx.config();
+        (config.race.port, config.race.allow_remote)
+    };
+    let reach = if remote {
+        "from other computers"
+    } else {
+        "from this computer only"
+    };
+    let dialog = AlertDialog::builder()
+        .heading("Race")
+        .body(format!(
+            "Race another TuxSplit live. Host to wait for an opponent on port {port} \
+             ({reach}), or join one by address. Both sides need the same room code."
+        ))
+        .default_response("join")
+        .close_response("cancel")
+        .build();
+
+    let address_row = EntryRow::builder().title("Opponent Address").build();
+    address_row.set_text(&format!("localhost:{port}"));
+    let list = ListBox::new();
+    list.add_css_class("boxed-list");
+    let code_row = EntryRow::builder().title("Room Code").build();
+    code_row.set_text(&room_code());
+    list.append(&address_row);
+    list.append(&code_row);
+    dialog.set_extra_child(Some(&list));
+
+    dialog.add_response("cancel", "Cancel");
+    dialog.add_response("host", "Host");
+    dialog.add_response("join", "Join");
+    dialog.set_response_appearance("join", adw::ResponseAppearance::Suggested);
+
+    let parent_binding = parent.clone();
+    dialog.connect_response(None, move |_, response| {
+        let code = code_row.text().trim().to_owned();
+        match response {
+            "host" | "join" if code.is_empty() => {
+                show_error(
+                    &parent_binding,
+                    "Enter the room code shared with your opponent.",
+                );
+            }
+            "host" => match net::host(port, remote, code.clone()) {
+                Ok(connection) => start_session(connection, code),
+                Err(err) => show_error(
+                    &parent_binding,
+                    &format!("Could not host on port {port}: {err}"),
+                ),
+            },
+            "join" => {
+                let address = address_row.text().trim().to_owned();
+                if address.is_empty() {
+                    show_error(&parent_binding, "Enter the opponent's address to join.");
+                } else {
+                    start_session(net::join(address, code.clone()), code);
+                }
+            }
+            _ => {}
+        }
+    });
+    dialog.present(Some(parent));
+}
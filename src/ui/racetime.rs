@@ -0,0 +1,210 @@
+//! The racetime.gg room shown in the header and finished from the menu.
+//!
+//! The room is joined at startup when `race.racetime.enabled` is set and a
+//! room is configured, and polled on the main loop like a race session.
+//! When the countdown begins the start is scheduled for the moment the room
+//! starts, counted from when the announcement came in. Joining within a few
+//! seconds after the start still starts the timer right away, with the time
+//! already missed on it.
+
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+use adw::prelude::*;
+use adw::{AlertDialog, ApplicationWindow};
+use gtk4::Label;
+use livesplit_core::{TimeSpan, TimerPhase};
+use tracing::{info, warn};
+
+use crate::context::TuxSplitContext;
+use crate::io::attempt_state::start_late;
+use crate::race::racetime::{self, RacetimeClient, RacetimeCommand, RacetimeEvent};
+use crate::ui::offset_conflict::note_start_issued;
+use crate::ui::saved_attempt::finish_after_attempt;
+use crate::ui::timer::modal::present_modal;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+thread_local! {
+    static ROOM: RefCell<Option<RacetimeRoom>> = const { RefCell::new(None) };
+}
+
+struct RacetimeRoom {
+    client: RacetimeClient,
+    indicator: Label,
+    pending_start: Option<glib::SourceId>,
+    /// Whether the token allows sending chat commands.
+    authenticated: bool,
+}
+
+/// Starts the timer, as `late` earlier when the race began before.
+fn start_timer(late: Duration) {
+    let ctx = TuxSplitContext::get_instance();
+    let shared = ctx.timer();
+    let mut timer = shared.write().unwrap();
+    if timer.current_phase() != TimerPhase::NotRunning {
+        return;
+    }
+    info!("racetime.gg race started");
+    note_start_issued();
+    if late.is_zero() {
+        timer.start();
+    } else if start_late(&mut timer, TimeSpan::from_seconds(late.as_secs_f64())) {
+        // The run's own start offset is given back once the attempt is over
+        finish_after_attempt();
+    }
+}
+
+fn cancel_pending_start(room: &mut RacetimeRoom) {
+    if let Some(source) = room.pending_start.take() {
+        source.remove();
+    }
+}
+
+fn handle_event(room: &mut RacetimeRoom, event: RacetimeEvent) {
+    match event {
+        RacetimeEvent::Connected => {
+            room.indicator.remove_css_class("dim-label");
+        }
+        RacetimeEvent::Status(status) => {
+            room.indicator.set_label(&status.summary());
+        }
+        RacetimeEvent::Begin { at } => {
+            let delay = at.saturating_duration_since(Instant::now());
+            info!("racetime.gg countdown: starting in {}ms", delay.as_millis());
+            cancel_pending_start(room);
+            room.pending_start = Some(glib::timeout_add_local_once(delay, || {
+                ROOM.with_borrow_mut(|room| {
+                    if let Some(room) = room.as_mut() {
+                        room.pending_start = None;
+                    }
+                });
+                start_timer(Duration::ZERO);
+            }));
+        }
+        RacetimeEvent::Late { by, received } => {
+            let late = by + received.elapsed();
+            warn!("Joined the racetime.gg race {}ms late", late.as_millis());
+            cancel_pending_start(room);
+            start_timer(late);
+        }
+        RacetimeEvent::StartCancelled => {
+            info!("racetime.gg countdown stopped");
+            cancel_pending_start(room);
+        }
+        RacetimeEvent::Error(reason) => warn!("racetime.gg: {reason}"),
+        RacetimeEvent::Disconnected(reason) => {
+            warn!("racetime.gg connection lost: {reason}");
+            room.indicator.add_css_class("dim-label");
+        }
+    }
+}
+
+fn poll_room() -> glib::ControlFlow {
+    ROOM.with_borrow_mut(|room| {
+        let Some(room) = room.as_mut() else {
+            return glib::ControlFlow::Break;
+        };
+        for event in room.client.poll() {
+            handle_event(room, event);
+        }
+        glib::ControlFlow::Continue
+    })
+}
+
+/// Joins the configured room, if any. Does nothing unless enabled.
+pub fn start_racetime(indicator: &Label) {
+    let options = TuxSplitContext::get_instance()
+        .config()
+        .race
+        .racetime
+        .clone();
+    if !options.enabled {
+        return;
+    }
+    let token = options.token.filter(|token| !token.trim().is_empty());
+    let Some(url) = options
+        .room
+        .as_deref()
+        .and_then(|room| racetime::room_ws_url(room, token.is_some()))
+    else {
+        warn!("racetime.gg is enabled but no valid room is configured");
+        return;
+    };
+
+    indicator.set_label("Connecting…");
+    indicator.add_css_class("dim-label");
+    indicator.set_tooltip_text(Some(&url));
+    indicator.set_visible(true);
+
+    ROOM.replace(Some(RacetimeRoom {
+        authenticated: token.is_some(),
+        client: racetime::connect(url, token),
+        indicator: indicator.clone(),
+        pending_start: None,
+    }));
+    glib::timeout_add_local(POLL_INTERVAL, poll_room);
+}
+
+fn show_message(parent: &ApplicationWindow, body: &str) {
+    let dialog = AlertDialog::builder()
+        .heading("racetime.gg")
+        .body(body)
+        .default_response("ok")
+        .build();
+    dialog.add_response("ok", "Okay");
+    dialog.present(Some(parent));
+}
+
+fn send(parent: &ApplicationWindow, command: RacetimeCommand) {
+    let sent = ROOM.with_borrow(|room| match room.as_ref() {
+        Some(room) if room.authenticated => {
+            room.client.send(command);
+            Ok(())
+        }
+        Some(_) => Err("Set race.racetime.token to finish or forfeit from TuxSplit."),
+        None => Err("No racetime.gg room is joined. Enable race.racetime and set a room."),
+    });
+    if let Err(reason) = sent {
+        show_message(parent, reason);
+    }
+}
+
+/// Sends `.done` with the final time of the finished attempt.
+pub fn send_done(parent: &ApplicationWindow) {
+    let final_time = {
+        let ctx = TuxSplitContext::get_instance();
+        let shared = ctx.timer();
+        let timer = shared.read().unwrap();
+        (timer.current_phase() == TimerPhase::Ended).then(|| {
+            let method = timer.current_timing_method();
+            let time = timer
+                .run()
+                .segments()
+                .last()
+                .and_then(|segment| segment.split_time()[method]);
+            ctx.config().format.timer.format_time_span_opt(time)
+        })
+    };
+    match final_time {
+        Some(final_time) => send(parent, RacetimeCommand::Done { final_time }),
+        None => show_message(parent, "Finish the run before marking it done."),
+    }
+}
+
+pub fn send_forfeit(parent: &ApplicationWindow) {
+    let dialog = AlertDialog::builder()
+        .heading("Forfeit Race?")
+        .body("The room will list you as forfeited.")
+        .default_response("cancel")
+        .close_response("cancel")
+        .build();
+    dialog.add_response("cancel", "Cancel");
+    dialog.add_response("forfeit", "Forfeit");
+    dialog.set_response_appearance("forfeit", adw::ResponseAppearance::Destructive);
+    let parent_binding = parent.clone();
+    dialog.connect_response(Some("forfeit"), move |_, _| {
+        send(&parent_binding, RacetimeCommand::Forfeit);
+    });
+    present_modal(&dialog, parent);
+}
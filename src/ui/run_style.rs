@@ -0,0 +1,333 @@
+//! CSS that belongs to a single splits file.
+//!
+//! A run can carry its own stylesheet in its per-file state, e.g. gold
+//! accents for one game. It is loaded into a provider of its own while that
+//! run is active, above the theme profile and below the user's GTK
+//! stylesheet, and taken off the display as soon as another run (or none)
+//! is loaded. The decisions live in [`RunStyleLayer`], which only tells the
+//! provider what to do, so they can be checked without a display.
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+
+use adw::AlertDialog;
+use adw::prelude::*;
+use gtk4::{
+    CssProvider, Label, Orientation, STYLE_PROVIDER_PRIORITY_APPLICATION, TextView, gdk::Display,
+};
+use tracing::{debug, info};
+
+use crate::config::Config;
+use crate::context::TuxSplitContext;
+
+/// Above the theme and the other runtime providers.
+const RUN_STYLE_PRIORITY: u32 = STYLE_PROVIDER_PRIORITY_APPLICATION + 2;
+
+thread_local! {
+    static RUN_STYLE: RefCell<Option<RunStyle>> = const { RefCell::new(None) };
+}
+
+/// What to do with the run's provider, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProviderOp {
+    /// Take the provider off the display.
+    Detach,
+    Load(String),
+    /// Put the provider on the display.
+    Attach,
+}
+
+/// Which run's CSS is on the display, independently of GTK.
+#[derive(Debug, Default)]
+pub struct RunStyleLayer {
+    active: Option<(PathBuf, String)>,
+}
+
+impl RunStyleLayer {
+    pub fn is_attached(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// Moves to the CSS of `wanted`, the run that is active now. Another
+    /// run's CSS is always detached before anything new is loaded, so it
+    /// can't stay behind even for a frame.
+    pub fn update(&mut self, wanted: Option<(PathBuf, String)>) -> Vec<ProviderOp> {
+        if self.active == wanted {
+            return Vec::new();
+        }
+        let mut ops = Vec::new();
+        let same_run = matches!(
+            (&self.active, &wanted),
+            (Some((active, _)), Some((path, _))) if active == path
+        );
+        if self.active.is_some() && !same_run {
+            ops.push(ProviderOp::Detach);
+        }
+        if let Some((_, css)) = &wanted {
+            ops.push(ProviderOp::Load(css.clone()));
+            if !same_run {
+                ops.push(ProviderOp::Attach);
+            }
+        }
+        self.active = wanted;
+        ops
+    }
+}
+
+/// The loaded splits file and its CSS, when it has any.
+pub fn resolve_run_css(config: &Config) -> Option<(PathBuf, String)> {
+    let path = config.general.splits.clone()?;
+    let css = config.splits_state()?.css.as_deref()?.trim();
+    (!css.is_empty()).then(|| (path, css.to_owned()))
+}
+
+struct RunStyle {
+    provider: CssProvider,
+    layer: RunStyleLayer,
+}
+
+fn run_ops(provider: &CssProvider, ops: Vec<ProviderOp>) {
+    let Some(display) = Display::default() else {
+        return;
+    };
+    for op in ops {
+        match op {
+            ProviderOp::Detach => {
+                debug!("Removing the previous run's CSS");
+                gtk4::style_context_remove_provider_for_display(&display, provider);
+            }
+            ProviderOp::Load(css) => provider.load_from_string(&css),
+            ProviderOp::Attach => {
+                gtk4::style_context_add_provider_for_display(
+                    &display,
+                    provider,
+                    RUN_STYLE_PRIORITY,
+                );
+            }
+        }
+    }
+}
+
+fn with_run_style<R>(f: impl FnOnce(&mut RunStyle) -> R) -> R {
+    RUN_STYLE.with_borrow_mut(|style| {
+        f(style.get_or_insert_with(|| RunStyle {
+            provider: CssProvider::new(),
+            layer: RunStyleLayer::default(),
+        }))
+    })
+}
+
+fn show(wanted: Option<(PathBuf, String)>) {
+    with_run_style(|style| {
+        let ops = style.layer.update(wanted);
+        run_ops(&style.provider, ops);
+    });
+}
+
+/// Loads the CSS of the active run, dropping the previous run's.
+pub fn apply_run_style(config: &Config) {
+    show(resolve_run_css(config));
+}
+
+/// Applies the active run's CSS now and again whenever the run changes.
+pub fn start_run_style() {
+    let ctx = TuxSplitContext::get_instance();
+    apply_run_style(&ctx.config());
+    ctx.connect_local("run-changed", false, |_| {
+        if let Ok(config) = TuxSplitContext::get_instance().try_config() {
+            apply_run_style(&config);
+        }
+        None
+    });
+}
+
+/// Editor for the loaded run's CSS. Edits apply as they are typed, parse
+/// errors are shown under the text, and Cancel puts the saved CSS back.
+pub fn present_run_style_editor(parent: &adw::ApplicationWindow) {
+    let ctx = TuxSplitContext::get_instance();
+    let (path, saved) = {
+        let config = ctx.config();
+        let Some(path) = config.general.splits.clone() else {
+            let dialog = AlertDialog::builder()
+                .heading("Run Style")
+                .body("Load a splits file to give it its own style.")
+                .default_response("ok")
+                .build();
+            dialog.add_response("ok", "Okay");
+            dialog.present(Some(parent));
+            return;
+        };
+        let saved = config
+            .splits_state()
+            .and_then(|state| state.css.clone())
+            .unwrap_or_default();
+        (path, saved)
+    };
+
+    let dialog = AlertDialog::builder()
+        .heading("Run Style")
+        .body("CSS applied on top of the theme while this splits file is loaded.")
+        .default_response("save")
+        .close_response("cancel")
+        .build();
+    dialog.add_response("cancel", "Cancel");
+    dialog.add_response("save", "Save");
+    dialog.set_response_appearance("save", adw::ResponseAppearance::Suggested);
+
+    let text_view = TextView::builder()
+        .monospace(true)
+        .wrap_mode(gtk4::WrapMode::WordChar)
+        .top_margin(6)
+        .bottom_margin(6)
+        .left_margin(6)
+        .right_margin(6)
+        .build();
+    text_view.buffer().set_text(&saved);
+    let scrolled = gtk4::ScrolledWindow::builder()
+        .min_content_height(200)
+        .min_content_width(360)
+        .child(&text_view)
+        .build();
+    scrolled.add_css_class("card");
+
+    let error_label = Label::builder()
+        .wrap(true)
+        .xalign(0.0)
+        .visible(false)
+        .build();
+    error_label.add_css_class("error");
+
+    let content = gtk4::Box::new(Orientation::Vertical, 6);
+    content.append(&scrolled);
+    content.append(&error_label);
+    dialog.set_extra_child(Some(&content));
+
+    let error_for_signal = error_label.clone();
+    let error_handler = with_run_style(|style| {
+        style
+            .provider
+            .connect_parsing_error(move |_, section, error| {
+                let line = section.start_location().lines() + 1;
+                error_for_signal.set_label(&format!("Line {line}: {}", error.message()));
+                error_for_signal.set_visible(true);
+            })
+    });
+
+    let path_for_preview = path.clone();
+    let error_for_preview = error_label.clone();
+    text_view.buffer().connect_changed(move |buffer| {
+        error_for_preview.set_visible(false);
+        let css = buffer
+            .text(&buffer.start_iter(), &buffer.end_iter(), false)
+            .to_string();
+        show((!css.trim().is_empty()).then(|| (path_for_preview.clone(), css)));
+    });
+
+    let buffer = text_view.buffer();
+    dialog.connect_response(None, move |_, response| {
+        let ctx = TuxSplitContext::get_instance();
+        if response == "save"
+            && let Ok(mut config) = ctx.config_mut()
+            && config.general.splits.as_ref() == Some(&path)
+            && let Some(state) = config.splits_state_mut()
+        {
+            let css = buffer
+                .text(&buffer.start_iter(), &buffer.end_iter(), false)
+                .to_string();
+            state.css = (!css.trim().is_empty()).then_some(css);
+            info!("Saved the run style for {}", path.display());
+        }
+        apply_run_style(&ctx.config());
+    });
+
+    let error_handler = RefCell::new(Some(error_handler));
+    dialog.connect_closed(move |_| {
+        if let Some(handler) = error_handler.take() {
+            with_run_style(|style| style.provider.disconnect(handler));
+        }
+    });
+
+    dialog.present(Some(parent));
+}
+
+#[cfg(test)]
+mod run_style_tests {
+    use super::*;
+
+    fn run(path: &str, css: &str) -> Option<(PathBuf, String)> {
+        Some((PathBuf::from(path), css.to_owned()))
+    }
+
+    fn load(css: &str) -> ProviderOp {
+        ProviderOp::Load(css.to_owned())
+    }
+
+    #[test]
+    fn a_styled_run_attaches_after_loading() {
+        let mut layer = RunStyleLayer::default();
+        assert_eq!(
+            layer.update(run("mario.lss", ".goldsplit {}")),
+            vec![load(".goldsplit {}"), ProviderOp::Attach]
+        );
+        assert!(layer.is_attached());
+        // Nothing changed, nothing to do
+        assert!(layer.update(run("mario.lss", ".goldsplit {}")).is_empty());
+    }
+
+    #[test]
+    fn switching_runs_detaches_the_previous_one_first() {
+        let mut layer = RunStyleLayer::default();
+        layer.update(run("mario.lss", "a {}"));
+        assert_eq!(
+            layer.update(run("zelda.lss", "b {}")),
+            vec![ProviderOp::Detach, load("b {}"), ProviderOp::Attach]
+        );
+    }
+
+    #[test]
+    fn switching_to_an_unstyled_run_only_detaches() {
+        let mut layer = RunStyleLayer::default();
+        layer.update(run("mario.lss", "a {}"));
+        assert_eq!(layer.update(None), vec![ProviderOp::Detach]);
+        assert!(!layer.is_attached());
+        assert!(layer.update(None).is_empty());
+    }
+
+    #[test]
+    fn editing_the_same_run_reloads_in_place() {
+        let mut layer = RunStyleLayer::default();
+        layer.update(run("mario.lss", "a {}"));
+        assert_eq!(layer.update(run("mario.lss", "b {}")), vec![load("b {}")]);
+    }
+
+    #[test]
+    fn resolver_reads_the_loaded_runs_css() {
+        let mut config = Config::default();
+        assert_eq!(resolve_run_css(&config), None);
+
+        config.general.splits = Some(PathBuf::from("mario.lss"));
+        assert_eq!(resolve_run_css(&config), None);
+        config.splits_state_mut().unwrap().css = Some("  \n".to_owned());
+        assert_eq!(resolve_run_css(&config), None);
+        config.splits_state_mut().unwrap().css = Some(" a {} \n".to_owned());
+        assert_eq!(resolve_run_css(&config), run("mario.lss", "a {}"));
+
+        // Another run's CSS is never picked up
+        config.general.splits = Some(PathBuf::from("zelda.lss"));
+        assert_eq!(resolve_run_css(&config), None);
+    }
+
+    #[test]
+    fn resolver_and_layer_never_leave_the_previous_run_attached() {
+        let mut config = Config::default();
+        let mut layer = RunStyleLayer::default();
+        config.general.splits = Some(PathBuf::from("mario.lss"));
+        config.splits_state_mut().unwrap().css = Some("a {}".to_owned());
+        layer.update(resolve_run_css(&config));
+
+        config.general.splits = Some(PathBuf::from("zelda.lss"));
+        let ops = layer.update(resolve_run_css(&config));
+        assert_eq!(ops, vec![ProviderOp::Detach]);
+        assert!(!layer.is_attached());
+    }
+}
@@ -0,0 +1,180 @@
+//! "Save Attempt and Quit", and offering the attempt back on the next start.
+//! What is kept and how the attempt is rebuilt is in [`crate::io::attempt_state`].
+
+use adw::prelude::*;
+use adw::{ActionRow, AlertDialog, ApplicationWindow};
+use glib::ControlFlow;
+use gtk4::{Label, ListBox};
+use livesplit_core::TimerPhase;
+use time::OffsetDateTime;
+use tracing::{info, warn};
+
+use crate::context::TuxSplitContext;
+use crate::io::attempt_state::{
+    SavedAttempt, default_attempt_path, discard_attempt, finish_resumed_attempt, load_attempt,
+    save_attempt,
+};
+use crate::ui::drill::drill_active;
+use crate::ui::offset_conflict::note_start_issued;
+use crate::ui::timer::modal::show_notice;
+use crate::ui::timer::tick_fanout::subscribe_tick;
+use crate::utils::comparisons::format_signed;
+
+/// Writes the attempt in progress to the state file and quits. Returns why
+/// it couldn't.
+pub fn save_attempt_and_quit(window: &ApplicationWindow) -> Result<(), String> {
+    if drill_active() {
+        return Err("Drill reps are not attempts and can't be saved.".to_owned());
+    }
+    let ctx = TuxSplitContext::get_instance();
+    let attempt = {
+        let shared = ctx.timer();
+        let timer = shared.read().unwrap();
+        SavedAttempt::capture(
+            &timer,
+            ctx.config().splits_path(),
+            OffsetDateTime::now_utc(),
+        )
+    }
+    .ok_or_else(|| "No attempt is in progress.".to_owned())?;
+    save_attempt(&attempt, &default_attempt_path())
+        .map_err(|e| format!("Could not save the attempt: {e}"))?;
+    info!(
+        "Saved the attempt at split {} to resume it on the next start",
+        attempt.splits_done.len() + 1
+    );
+    if let Some(app) = window.application() {
+        app.quit();
+    }
+    Ok(())
+}
+
+/// Records the splits from before the restart into the attempt and puts
+/// the run's own start offset back once the resumed attempt is over.
+pub fn finish_after_attempt() {
+    subscribe_tick(|timer, _| {
+        if timer.current_phase() != TimerPhase::NotRunning {
+            return ControlFlow::Continue;
+        }
+        glib::idle_add_local_once(|| {
+            let ctx = TuxSplitContext::get_instance();
+            let mut run = ctx.get_run();
+            if finish_resumed_attempt(&mut run)
+                && let Err(err) = ctx.set_run(run)
+            {
+                show_notice(&format!("The resumed splits were not recorded: {err}"));
+            }
+        });
+        ControlFlow::Break
+    });
+}
+
+fn resume(attempt: &SavedAttempt) -> bool {
+    let ctx = TuxSplitContext::get_instance();
+    let resumed = {
+        let shared = ctx.timer();
+        let mut timer = shared.write().unwrap();
+        note_start_issued();
+        attempt.resume(&mut timer, OffsetDateTime::now_utc())
+    };
+    if resumed {
+        info!("Resumed the saved attempt");
+        discard_attempt(&default_attempt_path());
+        ctx.emit_run_changed();
+        finish_after_attempt();
+    }
+    resumed
+}
+
+fn splits_list(attempt: &SavedAttempt) -> ListBox {
+    let ctx = TuxSplitContext::get_instance();
+    let config = ctx.config();
+    let diffs = attempt.split_diffs(&ctx.get_run());
+    let list = ListBox::new();
+    list.add_css_class("boxed-list");
+    for ((name, split), diff) in attempt.segments.iter().zip(&attempt.splits_done).zip(diffs) {
+        let recorded = match attempt.timing_method {
+            livesplit_core::TimingMethod::RealTime => split.real_time_ms,
+            livesplit_core::TimingMethod::GameTime => split.game_time_ms,
+        };
+        let row = ActionRow::builder().title(name).build();
+        if let Some(diff) = diff {
+            let diff_label = Label::new(Some(&format_signed(
+                time::Duration::milliseconds(diff),
+                &config,
+            )));
+            diff_label.add_css_class("monospace");
+            row.add_suffix(&diff_label);
+        }
+        let time_label = Label::new(Some(&recorded.map_or_else(
+            || "Skipped".to_owned(),
+            |ms| {
+                config
+                    .format
+                    .segment
+                    .format_duration(&time::Duration::milliseconds(ms))
+            },
+        )));
+        time_label.add_css_class("monospace");
+        row.add_suffix(&time_label);
+        list.append(&row);
+    }
+    list
+}
+
+/// Offers the attempt saved by "Save Attempt and Quit", if there is one.
+/// It is kept until it is resumed or discarded.
+pub fn offer_saved_attempt(window: &ApplicationWindow) {
+    let Some(attempt) = load_attempt(&default_attempt_path()) else {
+        return;
+    };
+    let ctx = TuxSplitContext::get_instance();
+    let same_splits =
+        attempt.matches(&ctx.get_run()) && attempt.splits == ctx.config().splits_path();
+    if !same_splits {
+        warn!("The saved attempt was made on other splits, keeping it for them");
+        return;
+    }
+
+    let dialog = AlertDialog::builder()
+        .heading("Resume Attempt?")
+        .body(format!(
+            "An attempt was saved at split {} of {}. The real time kept running while \
+             TuxSplit was closed, the game time resumes paused. The earlier splits keep \
+             their saved times.",
+            attempt.splits_done.len() + 1,
+            attempt.segments.len()
+        ))
+        .default_response("resume")
+        .close_response("discard")
+        .build();
+    dialog.add_response("discard", "Discard");
+    dialog.add_response("resume", "Resume Attempt");
+    dialog.set_response_appearance("resume", adw::ResponseAppearance::Suggested);
+    if !attempt.splits_done.is_empty() {
+        let scrolled = gtk4::ScrolledWindow::builder()
+            .min_content_height(160)
+            .propagate_natural_height(true)
+            .child(&splits_list(&attempt))
+            .build();
+        dialog.set_extra_child(Some(&scrolled));
+    }
+    dialog.connect_response(Some("discard"), |_, _| {
+        discard_attempt(&default_attempt_path());
+    });
+    dialog.connect_response(Some("resume"), move |dialog, _| {
+        if !resume(&attempt) {
+            warn!("The saved attempt could not be resumed");
+            if let Some(window) = dialog.root().and_downcast::<ApplicationWindow>() {
+                let notice = AlertDialog::builder()
+                    .heading("Could Not Resume")
+                    .body("An attempt was started meanwhile.")
+                    .default_response("ok")
+                    .build();
+                notice.add_response("ok", "Okay");
+                notice.present(Some(&window));
+            }
+        }
+    });
+    dialog.present(Some(window));
+}
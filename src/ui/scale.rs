@@ -0,0 +1,199 @@
+//! Keeps the timer fonts sized for the monitor the window is on.
+//!
+//! With the X11 backend GDK reports one scale for every monitor, so moving
+//! the window from a HiDPI screen to a normal one leaves fonts sized for the
+//! wrong density. We estimate the scale of each monitor from its physical
+//! size and regenerate the font sizes when the window enters another one.
+//! On native backends GTK already scales per monitor and this does nothing
+//! beyond applying the configured font scales.
+
+use std::cell::RefCell;
+
+use adw::ApplicationWindow;
+use gtk4::{
+    CssProvider, STYLE_PROVIDER_PRIORITY_APPLICATION,
+    gdk::{Display, Monitor},
+    prelude::*,
+};
+use tracing::info;
+
+use crate::config::LayoutOptions;
+use crate::context::TuxSplitContext;
+
+/// Font sizes from the application stylesheet, in pixels at scale 1.
+const BIG_TIMER_FONT_PX: f64 = 36.0;
+const SMALL_TIMER_FONT_PX: f64 = 18.0;
+const BASE_DPI: f64 = 96.0;
+const MM_PER_INCH: f64 = 25.4;
+/// Monitors report odd physical sizes; scales are snapped to quarter steps.
+const SCALE_STEP: f64 = 0.25;
+const MAX_SCALE: f64 = 4.0;
+
+thread_local! {
+    static PROVIDER: RefCell<Option<CssProvider>> = const { RefCell::new(None) };
+    static CURRENT_SCALE: RefCell<f64> = const { RefCell::new(1.0) };
+}
+
+/// Scale of a monitor `width_px` logical pixels wide and `width_mm`
+/// millimeters wide, relative to 96 DPI. Unknown physical sizes count as 1.
+pub fn monitor_scale(width_px: i32, width_mm: i32) -> f64 {
+    if width_px <= 0 || width_mm <= 0 {
+        return 1.0;
+    }
+    let dpi = f64::from(width_px) / (f64::from(width_mm) / MM_PER_INCH);
+    ((dpi / BASE_DPI / SCALE_STEP).round() * SCALE_STEP).clamp(1.0, MAX_SCALE)
+}
+
+/// The scale dependent part of the runtime stylesheet.
+pub fn scaled_font_css(layout: &LayoutOptions, scale: f64) -> String {
+    let px = |base: f64, factor: f64| (base * factor * scale * 10.0).round() / 10.0;
+    format!(
+        ".bigtimer {{ font-size: {}px; }}\n.smalltimer {{ font-size: {}px; }}\n",
+        px(BIG_TIMER_FONT_PX, layout.timer_font_scale),
+        px(SMALL_TIMER_FONT_PX, layout.small_timer_font_scale),
+    )
+}
+
+/// Font sizes for the detached timer window, which has a scale of its own.
+pub fn detached_timer_css(font_scale: f64) -> String {
+    let px = |base: f64| (base * font_scale * 10.0).round() / 10.0;
+    format!(
+        ".detached-timer .bigtimer {{ font-size: {}px; }}\n\
+         .detached-timer .smalltimer {{ font-size: {}px; }}\n",
+        px(BIG_TIMER_FONT_PX),
+        px(SMALL_TIMER_FONT_PX),
+    )
+}
+
+/// Whether GTK runs on X11, where per monitor scaling is up to us.
+fn needs_manual_scaling(display: &Display) -> bool {
+    display.type_().name() == "GdkX11Display"
+}
+
+fn apply_scale(layout: &LayoutOptions, scale: f64) {
+    let Some(display) = Display::default() else {
+        return;
+    };
+    PROVIDER.with_borrow_mut(|provider| {
+        let provider = provider.get_or_insert_with(|| {
+            let provider = CssProvider::new();
+            gtk4::style_context_add_provider_for_display(
+                &display,
+                &provider,
+                STYLE_PROVIDER_PRIORITY_APPLICATION + 1,
+            );
+            provider
+        });
+        provider.load_from_string(&scaled_font_css(layout, scale));
+    });
+    CURRENT_SCALE.set(scale);
+}
+
+/// Keeps the window within the monitor after a scale change.
+fn clamp_to_monitor(window: &ApplicationWindow, monitor: &Monitor) {
+    let geometry = monitor.geometry();
+    let (width, height) = window.default_size();
+    let clamped = (width.min(geometry.width()), height.min(geometry.height()));
+    if clamped != (width, height) {
+        window.set_default_size(clamped.0, clamped.1);
+    }
+}
+
+fn on_enter_monitor(window: &ApplicationWindow, monitor: &Monitor) {
+    let scale = monitor_scale(monitor.geometry().width(), monitor.width_mm());
+    let previous = CURRENT_SCALE.with_borrow(|current| *current);
+    if (scale - previous).abs() < f64::EPSILON {
+        return;
+    }
+    info!(
+        "Window moved to {} (scale {previous} -> {scale})",
+        monitor.model().as_deref().unwrap_or("a monitor")
+    );
+    let layout = TuxSplitContext::get_instance().config().layout.clone();
+    apply_scale(&layout, scale);
+    clamp_to_monitor(window, monitor);
+}
+
+/// Applies the configured font scales and, on X11, follows the window
+/// across monitors.
+pub fn attach_monitor_scaling(window: &ApplicationWindow) {
+    let layout = TuxSplitContext::get_instance().config().layout.clone();
+    apply_scale(&layout, 1.0);
+
+    let Some(display) = Display::default() else {
+        return;
+    };
+    if !needs_manual_scaling(&display) {
+        return;
+    }
+    window.connect_realize(|window| {
+        let Some(surface) = window.surface() else {
+            return;
+        };
+        let window = window.clone();
+        surface.connect_enter_monitor(move |_, monitor| on_enter_monitor(&window, monitor));
+    });
+}
+
+#[cfg(test)]
+mod scale_tests {
+    use super::*;
+
+    #[test]
+    fn css_at_scale_one_matches_stylesheet() {
+        let css = scaled_font_css(&LayoutOptions::default(), 1.0);
+        assert_eq!(
+            css,
+            ".bigtimer { font-size: 36px; }\n.smalltimer { font-size: 18px; }\n"
+        );
+    }
+
+    #[test]
+    fn css_at_scale_two_doubles_sizes() {
+        let layout = LayoutOptions {
+            timer_font_scale: 1.5,
+            small_timer_font_scale: 1.0,
+            ..LayoutOptions::default()
+        };
+        assert_eq!(
+            scaled_font_css(&layout, 2.0),
+            ".bigtimer { font-size: 108px; }\n.smalltimer { font-size: 36px; }\n"
+        );
+    }
+
+    #[test]
+    fn sizes_are_rounded_to_a_tenth() {
+        let layout = LayoutOptions {
+            timer_font_scale: 1.0,
+            small_timer_font_scale: 1.0,
+            ..LayoutOptions::default()
+        };
+        assert!(scaled_font_css(&layout, 1.25).contains("font-size: 22.5px"));
+    }
+
+    #[test]
+    fn detached_timer_sizes_only_apply_to_its_window() {
+        assert_eq!(
+            detached_timer_css(2.5),
+            ".detached-timer .bigtimer { font-size: 90px; }\n\
+             .detached-timer .smalltimer { font-size: 45px; }\n"
+        );
+    }
+
+    #[test]
+    fn scale_from_physical_size() {
+        // 1920px over 508mm is 96 DPI
+        assert!((monitor_scale(1920, 508) - 1.0).abs() < f64::EPSILON);
+        // 3840px over the same width is 192 DPI
+        assert!((monitor_scale(3840, 508) - 2.0).abs() < f64::EPSILON);
+        // 2560px over 344mm (a 14" laptop) is about 189 DPI
+        assert!((monitor_scale(2560, 344) - 2.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn unknown_or_low_density_monitors_are_scale_one() {
+        assert!((monitor_scale(1920, 0) - 1.0).abs() < f64::EPSILON);
+        assert!((monitor_scale(0, 500) - 1.0).abs() < f64::EPSILON);
+        assert!((monitor_scale(1024, 600) - 1.0).abs() < f64::EPSILON);
+    }
+}
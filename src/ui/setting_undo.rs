@@ -0,0 +1,106 @@
+//! Taking back an accidental comparison or timing method switch.
+//!
+//! Every frame of the main tick goes through a [`SettingHistory`]. A switch
+//! made while the timer runs shows a toast with an "Undo" button, and
+//! `behavior.undo-setting-hotkey` takes back the latest switch at any time.
+//! Undoing sets the previous value on the timer the same way the switches
+//! do. Loading other splits forgets the history.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use adw::prelude::*;
+use adw::{Toast, ToastOverlay};
+use glib::ControlFlow;
+use livesplit_core::{TimerPhase, TimingMethod};
+use tracing::info;
+
+use crate::context::TuxSplitContext;
+use crate::ui::timer::tick_fanout::subscribe_tick;
+use crate::utils::setting_history::{DisplaySettings, Setting, SettingHistory};
+
+thread_local! {
+    static HISTORY: RefCell<SettingHistory> = RefCell::new(SettingHistory::default());
+    static TOAST: RefCell<Option<Toast>> = const { RefCell::new(None) };
+}
+
+/// Set by the global hotkey, which doesn't run on the GTK thread.
+static UNDO_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+const TOAST_TIMEOUT_SECS: u32 = 5;
+
+/// Asks for the latest switch to be undone on the next frame. Can be called
+/// from any thread.
+pub fn request_setting_undo() {
+    UNDO_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+fn describe(setting: &Setting) -> String {
+    match setting {
+        Setting::Comparison(name) => format!("Comparison: {name}"),
+        Setting::TimingMethod(TimingMethod::RealTime) => "Timing method: Real Time".to_owned(),
+        Setting::TimingMethod(TimingMethod::GameTime) => "Timing method: Game Time".to_owned(),
+    }
+}
+
+fn dismiss_toast() {
+    if let Some(toast) = TOAST.take() {
+        toast.dismiss();
+    }
+}
+
+/// Takes back the latest switch, if there is one.
+pub fn undo_setting_change() {
+    let Some(previous) = HISTORY.with_borrow_mut(SettingHistory::undo) else {
+        return;
+    };
+    {
+        let ctx = TuxSplitContext::get_instance();
+        let shared = ctx.timer();
+        let mut timer = shared.write().unwrap();
+        match &previous {
+            Setting::Comparison(name) => {
+                let _ = timer.set_current_comparison(name.as_str());
+            }
+            Setting::TimingMethod(method) => timer.set_current_timing_method(*method),
+        }
+    }
+    info!("Undid a setting switch, back to {}", describe(&previous));
+    dismiss_toast();
+}
+
+fn show_toast(toasts: &ToastOverlay, value: &Setting) {
+    dismiss_toast();
+    let toast = Toast::builder()
+        .title(describe(value))
+        .button_label("Undo")
+        .timeout(TOAST_TIMEOUT_SECS)
+        .build();
+    toast.connect_button_clicked(|_| undo_setting_change());
+    toasts.add_toast(toast.clone());
+    TOAST.replace(Some(toast));
+}
+
+/// Starts following the switches, with the undo toasts shown on `toasts`.
+pub fn start_setting_undo(toasts: &ToastOverlay) {
+    let toasts = toasts.clone();
+    subscribe_tick(move |timer, _| {
+        let current = DisplaySettings::of(timer);
+        let changed = HISTORY.with_borrow_mut(|history| history.observe(&current, Instant::now()));
+        if let Some(value) = changed
+            && timer.current_phase() == TimerPhase::Running
+        {
+            show_toast(&toasts, &value);
+        }
+        if UNDO_REQUESTED.swap(false, Ordering::Relaxed) {
+            // The timer can't be written from inside the tick
+            glib::idle_add_local_once(undo_setting_change);
+        }
+        ControlFlow::Continue
+    });
+    TuxSplitContext::get_instance().connect_local("run-changed", false, |_| {
+        HISTORY.with_borrow_mut(SettingHistory::clear);
+        None
+    });
+}
@@ -0,0 +1,633 @@
+//! Named color profiles layered over the bundled stylesheet.
+//!
+//! A profile maps style classes (`goldsplit`, `redsplit`, ...) to CSS colors
+//! and inherits whatever it leaves out from the `default` profile. The active
+//! profile is the one picked in Settings, unless `theme.auto` schedules the
+//! stream profile for the current time of day. The schedule is re-evaluated
+//! every minute and only the runtime `CssProvider` is reloaded, so switching
+//! never touches the running attempt.
+//!
+//! Profile colors are the ones for the light style. While the desktop prefers
+//! a dark style, `theme.dark-profiles` is used instead, and hex colors it
+//! doesn't set are derived from the light ones so they stay readable on the
+//! dark background. The provider is reloaded as soon as the style changes.
+//!
+//! Below every profile sit the bundled split colors, made readable for the
+//! current style the same way: the gold and greens are too light for the
+//! light background as written.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use gtk4::{CssProvider, STYLE_PROVIDER_PRIORITY_APPLICATION, gdk::Display};
+use tracing::{info, warn};
+
+use crate::config::Theme;
+use crate::context::TuxSplitContext;
+
+pub const DEFAULT_PROFILE: &str = "default";
+pub const STREAM_PROFILE: &str = "stream";
+
+/// Window backgrounds of the default Adwaita styles, which the split colors
+/// are read against.
+pub const LIGHT_BACKGROUND: &str = "#fafafb";
+pub const DARK_BACKGROUND: &str = "#222226";
+/// WCAG AA contrast for normal text.
+pub const MIN_CONTRAST: f64 = 4.5;
+
+/// Split colors the app ships with, by class. They're written for the dark
+/// style and only reach the window through the runtime provider, made
+/// readable for the current style, so the stylesheet doesn't set them.
+const BUNDLED_SPLIT_COLORS: [(&str, &str); 5] = [
+    ("goldsplit", "#e5a50a"),
+    ("greensplit", "#33d17a"),
+    ("lostgreensplit", "#26a269"),
+    ("gainedredsplit", "#f66151"),
+    ("redsplit", "#ed333b"),
+];
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+const SCHEDULE_INTERVAL_SECS: u32 = 60;
+
+thread_local! {
+    static RUNTIME_THEME: RefCell<Option<RuntimeTheme>> = const { RefCell::new(None) };
+}
+
+struct RuntimeTheme {
+    provider: CssProvider,
+    profile: String,
+    css: String,
+}
+
+/// Parses a "HH:MM" clock time into minutes since midnight.
+fn parse_clock(input: &str) -> Option<u32> {
+    let (hours, minutes) = input.trim().split_once(':')?;
+    let hours: u32 = hours.parse().ok()?;
+    let minutes: u32 = minutes.parse().ok()?;
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+/// Parses a "HH:MM-HH:MM" range into (start, end) minutes since midnight.
+/// The end is exclusive; an end before the start wraps around midnight.
+pub fn parse_time_range(input: &str) -> Option<(u32, u32)> {
+    let (start, end) = input.split_once('-')?;
+    Some((parse_clock(start)?, parse_clock(end)?))
+}
+
+fn range_contains((start, end): (u32, u32), minute: u32) -> bool {
+    match start.cmp(&end) {
+        std::cmp::Ordering::Less => (start..end).contains(&minute),
+        std::cmp::Ordering::Greater => minute >= start || minute < end,
+        // "19:00-19:00" covers the whole day
+        std::cmp::Ordering::Equal => true,
+    }
+}
+
+/// Whether `minute` (since midnight) falls in any of the scheduled ranges.
+/// Invalid ranges are ignored.
+pub fn in_schedule(ranges: &[String], minute: u32) -> bool {
+    let minute = minute % MINUTES_PER_DAY;
+    ranges.iter().any(|range| match parse_time_range(range) {
+        Some(parsed) => range_contains(parsed, minute),
+        None => {
+            warn!("Ignoring invalid theme schedule range {range:?}");
+            false
+        }
+    })
+}
+
+/// Name of the profile that should be active at `minute` (since midnight).
+pub fn active_profile_name(theme: &Theme, minute: u32) -> &str {
+    if in_schedule(&theme.auto.stream_profile_during, minute) {
+        STREAM_PROFILE
+    } else {
+        theme.profile.as_deref().unwrap_or(DEFAULT_PROFILE)
+    }
+}
+
+/// Colors of `name` with the ones it doesn't set inherited from the default profile.
+pub fn merged_colors(theme: &Theme, name: &str) -> BTreeMap<String, String> {
+    let mut colors = theme
+        .profiles
+        .get(DEFAULT_PROFILE)
+        .cloned()
+        .unwrap_or_default();
+    if name != DEFAULT_PROFILE
+        && let Some(profile) = theme.profiles.get(name)
+    {
+        colors.extend(profile.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+    colors
+}
+
+/// "#rgb" or "#rrggbb" as RGB channels between 0 and 1.
+fn parse_hex(color: &str) -> Option<[f64; 3]> {
+    let hex = color.trim().strip_prefix('#')?;
+    if !hex.is_ascii() {
+        return None;
+    }
+    let channel = |digits: &str| u8::from_str_radix(digits, 16).ok();
+    let channels = match hex.len() {
+        3 => [
+            channel(&hex[0..1])? * 17,
+            channel(&hex[1..2])? * 17,
+            channel(&hex[2..3])? * 17,
+        ],
+        6 => [
+            channel(&hex[0..2])?,
+            channel(&hex[2..4])?,
+            channel(&hex[4..6])?,
+        ],
+        _ => return None,
+    };
+    Some(channels.map(|c| f64::from(c) / 255.0))
+}
+
+fn to_hex(rgb: [f64; 3]) -> String {
+    let [r, g, b] = rgb.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// WCAG relative luminance.
+fn relative_luminance(rgb: [f64; 3]) -> f64 {
+    let [r, g, b] = rgb.map(|c| {
+        if c <= 0.040_45 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    });
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+fn luminance_contrast(a: f64, b: f64) -> f64 {
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// WCAG contrast ratio of two hex colors, from 1 (same) to 21 (black on white).
+pub fn contrast_ratio(a: &str, b: &str) -> Option<f64> {
+    Some(luminance_contrast(
+        relative_luminance(parse_hex(a)?),
+        relative_luminance(parse_hex(b)?),
+    ))
+}
+
+fn rgb_to_hsl([r, g, b]: [f64; 3]) -> (f64, f64, f64) {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let lightness = (max + min) / 2.0;
+    let delta = max - min;
+    if delta == 0.0 {
+        return (0.0, 0.0, lightness);
+    }
+    let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+    let hue = if max == r {
+        ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / delta + 2.0
+    } else {
+        (r - g) / delta + 4.0
+    };
+    (hue * 60.0, saturation, lightness)
+}
+
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> [f64; 3] {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue / 60.0;
+    let x = chroma * (1.0 - (sector.rem_euclid(2.0) - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    [r + m, g + m, b + m]
+}
+
+/// `color` made readable on `background`: unchanged when it already is,
+/// otherwise with its lightness moved away from the background's until it
+/// reaches [`MIN_CONTRAST`], keeping its hue and saturation. `None` for
+/// colors that aren't hex, which can't be adjusted.
+pub fn readable_variant(color: &str, background: &str) -> Option<String> {
+    let rgb = parse_hex(color)?;
+    let background = relative_luminance(parse_hex(background)?);
+    if luminance_contrast(relative_luminance(rgb), background) >= MIN_CONTRAST {
+        return Some(color.to_owned());
+    }
+
+    let (hue, saturation, mut lightness) = rgb_to_hsl(rgb);
+    // Black on white and white on black both clear the bar, so this ends
+    let step = if background < 0.18 { 0.01 } else { -0.01 };
+    loop {
+        lightness = (lightness + step).clamp(0.0, 1.0);
+        let candidate = hsl_to_rgb(hue, saturation, lightness);
+        if luminance_contrast(relative_luminance(candidate), background) >= MIN_CONTRAST
+            || lightness == 0.0
+            || lightness == 1.0
+        {
+            return Some(to_hex(candidate));
+        }
+    }
+}
+
+/// Dark colors of one profile, without inheritance: its `dark-profiles`
+/// entry, and its light colors derived for the dark background.
+fn dark_layer(theme: &Theme, name: &str) -> BTreeMap<String, String> {
+    let explicit = theme.dark_profiles.get(name);
+    let mut layer: BTreeMap<String, String> = theme
+        .profiles
+        .get(name)
+        .into_iter()
+        .flatten()
+        .map(|(class, color)| {
+            let dark = explicit
+                .and_then(|explicit| explicit.get(class))
+                .cloned()
+                .or_else(|| readable_variant(color, DARK_BACKGROUND))
+                .unwrap_or_else(|| color.clone());
+            (class.clone(), dark)
+        })
+        .collect();
+    if let Some(explicit) = explicit {
+        layer.extend(explicit.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+    layer
+}
+
+/// [`BUNDLED_SPLIT_COLORS`] as written.
+fn bundled_split_colors() -> BTreeMap<String, String> {
+    BUNDLED_SPLIT_COLORS
+        .iter()
+        .map(|(class, color)| ((*class).to_owned(), (*color).to_owned()))
+        .collect()
+}
+
+/// The bundled split colors, each made readable on the style's background.
+fn bundled_layer(dark: bool) -> BTreeMap<String, String> {
+    let background = if dark {
+        DARK_BACKGROUND
+    } else {
+        LIGHT_BACKGROUND
+    };
+    bundled_split_colors()
+        .into_iter()
+        .map(|(class, color)| {
+            let readable = readable_variant(&color, background).unwrap_or(color);
+            (class, readable)
+        })
+        .collect()
+}
+
+/// Colors of `name` for the light or dark style, inheriting from `default`
+/// like [`merged_colors`], over the bundled split colors for that style. A
+/// light color set by the profile itself is derived for the dark style
+/// rather than taking the default's dark color.
+pub fn scheme_colors(theme: &Theme, name: &str, dark: bool) -> BTreeMap<String, String> {
+    let mut colors = bundled_layer(dark);
+    if !dark {
+        colors.extend(merged_colors(theme, name));
+        return colors;
+    }
+    colors.extend(dark_layer(theme, DEFAULT_PROFILE));
+    if name != DEFAULT_PROFILE {
+        colors.extend(dark_layer(theme, name));
+    }
+    colors
+}
+
+/// Every selectable profile name, `default` first.
+pub fn profile_names(theme: &Theme) -> Vec<String> {
+    let mut names = vec![DEFAULT_PROFILE.to_owned()];
+    names.extend(
+        theme
+            .profiles
+            .keys()
+            .filter(|name| name.as_str() != DEFAULT_PROFILE)
+            .cloned(),
+    );
+    names
+}
+
+fn profile_css(colors: &BTreeMap<String, String>) -> String {
+    colors
+        .iter()
+        .map(|(class, color)| format!(".{class} {{ color: {color}; }}\n"))
+        .collect()
+}
+
+fn current_minute() -> u32 {
+    glib::DateTime::now_local().map_or(0, |now| {
+        (now.hour() * 60 + now.minute()).clamp(0, MINUTES_PER_DAY as i32 - 1) as u32
+    })
+}
+
+/// Name of the profile currently loaded in the runtime provider.
+pub fn active_profile() -> String {
+    RUNTIME_THEME.with_borrow(|theme| {
+        theme
+            .as_ref()
+            .map_or_else(|| DEFAULT_PROFILE.to_owned(), |t| t.profile.clone())
+    })
+}
+
+/// Resolves the active profile and reloads the runtime provider if its CSS changed.
+pub fn apply_theme(theme: &Theme) {
+    let profile = active_profile_name(theme, current_minute()).to_owned();
+    let dark = adw::StyleManager::default().is_dark();
+    let css = profile_css(&scheme_colors(theme, &profile, dark));
+
+    RUNTIME_THEME.with_borrow_mut(|runtime| {
+        if runtime.is_none() {
+            let Some(display) = Display::default() else {
+                return;
+            };
+            let provider = CssProvider::new();
+            gtk4::style_context_add_provider_for_display(
+                &display,
+                &provider,
+                STYLE_PROVIDER_PRIORITY_APPLICATION + 1,
+            );
+            *runtime = Some(RuntimeTheme {
+                provider,
+                profile: String::new(),
+                css: String::new(),
+            });
+        }
+        let Some(runtime) = runtime.as_mut() else {
+            return;
+        };
+
+        if runtime.profile != profile || runtime.css != css {
+            let style = if dark { "dark" } else { "light" };
+            info!("Applying theme profile {profile} ({style})");
+            runtime.provider.load_from_string(&css);
+            runtime.profile = profile;
+            runtime.css = css;
+        }
+    });
+}
+
+/// Applies the theme now, re-evaluates the schedule every minute and
+/// follows the desktop switching between light and dark.
+pub fn start_theme_schedule() {
+    apply_theme(&TuxSplitContext::get_instance().config().theme);
+    adw::StyleManager::default().connect_dark_notify(|_| {
+        if let Ok(config) = TuxSplitContext::get_instance().try_config() {
+            apply_theme(&config.theme);
+        }
+    });
+    glib::timeout_add_seconds_local(SCHEDULE_INTERVAL_SECS, || {
+        apply_theme(&TuxSplitContext::get_instance().config().theme);
+        glib::ControlFlow::Continue
+    });
+}
+
+#[cfg(test)]
+mod theme_tests {
+    use super::*;
+
+    fn ranges(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| (*s).to_owned()).collect()
+    }
+
+    fn colors(items: &[(&str, &str)]) -> BTreeMap<String, String> {
+        items
+            .iter()
+            .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+            .collect()
+    }
+
+    fn at(hours: u32, minutes: u32) -> u32 {
+        hours * 60 + minutes
+    }
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(
+            parse_time_range("19:00-23:00"),
+            Some((at(19, 0), at(23, 0)))
+        );
+        assert_eq!(
+            parse_time_range(" 7:05 - 8:30 "),
+            Some((at(7, 5), at(8, 30)))
+        );
+        assert_eq!(parse_time_range("24:00-01:00"), None);
+        assert_eq!(parse_time_range("19:60-20:00"), None);
+        assert_eq!(parse_time_range("19:00"), None);
+        assert_eq!(parse_time_range("evening"), None);
+    }
+
+    #[test]
+    fn simple_range_is_end_exclusive() {
+        let schedule = ranges(&["19:00-23:00"]);
+        assert!(!in_schedule(&schedule, at(18, 59)));
+        assert!(in_schedule(&schedule, at(19, 0)));
+        assert!(in_schedule(&schedule, at(22, 59)));
+        assert!(!in_schedule(&schedule, at(23, 0)));
+    }
+
+    #[test]
+    fn range_wraps_around_midnight() {
+        let schedule = ranges(&["22:00-02:00"]);
+        assert!(in_schedule(&schedule, at(23, 30)));
+        assert!(in_schedule(&schedule, at(0, 0)));
+        assert!(in_schedule(&schedule, at(1, 59)));
+        assert!(!in_schedule(&schedule, at(2, 0)));
+        assert!(!in_schedule(&schedule, at(12, 0)));
+    }
+
+    #[test]
+    fn overlapping_ranges_union() {
+        let schedule = ranges(&["18:00-21:00", "20:00-23:00"]);
+        assert!(in_schedule(&schedule, at(18, 30)));
+        assert!(in_schedule(&schedule, at(20, 30)));
+        assert!(in_schedule(&schedule, at(22, 30)));
+        assert!(!in_schedule(&schedule, at(23, 30)));
+    }
+
+    #[test]
+    fn equal_bounds_cover_whole_day() {
+        let schedule = ranges(&["05:00-05:00"]);
+        assert!(in_schedule(&schedule, at(4, 59)));
+        assert!(in_schedule(&schedule, at(17, 0)));
+    }
+
+    #[test]
+    fn invalid_ranges_are_ignored() {
+        let schedule = ranges(&["nope", "10:00-11:00"]);
+        assert!(in_schedule(&schedule, at(10, 30)));
+        assert!(!in_schedule(&schedule, at(12, 0)));
+        assert!(!in_schedule(&[], at(12, 0)));
+    }
+
+    #[test]
+    fn schedule_overrides_selected_profile() {
+        let mut theme = Theme::default();
+        theme.profile = Some("night".to_owned());
+        theme.auto.stream_profile_during = ranges(&["19:00-23:00"]);
+        assert_eq!(active_profile_name(&theme, at(20, 0)), STREAM_PROFILE);
+        assert_eq!(active_profile_name(&theme, at(10, 0)), "night");
+
+        theme.profile = None;
+        assert_eq!(active_profile_name(&theme, at(10, 0)), DEFAULT_PROFILE);
+    }
+
+    #[test]
+    fn profiles_inherit_from_default() {
+        let mut theme = Theme::default();
+        theme.profiles.insert(
+            DEFAULT_PROFILE.to_owned(),
+            colors(&[("goldsplit", "#e5a50a"), ("redsplit", "#ed333b")]),
+        );
+        theme.profiles.insert(
+            STREAM_PROFILE.to_owned(),
+            colors(&[("redsplit", "#ff0000")]),
+        );
+
+        assert_eq!(
+            merged_colors(&theme, STREAM_PROFILE),
+            colors(&[("goldsplit", "#e5a50a"), ("redsplit", "#ff0000")])
+        );
+        assert_eq!(
+            merged_colors(&theme, DEFAULT_PROFILE),
+            colors(&[("goldsplit", "#e5a50a"), ("redsplit", "#ed333b")])
+        );
+        // Unknown profiles fall back to the default colors
+        assert_eq!(
+            merged_colors(&theme, "missing"),
+            merged_colors(&theme, DEFAULT_PROFILE)
+        );
+    }
+
+    #[test]
+    fn profile_names_list_default_first() {
+        let mut theme = Theme::default();
+        theme.profiles.insert("stream".to_owned(), BTreeMap::new());
+        theme.profiles.insert("default".to_owned(), BTreeMap::new());
+        theme.profiles.insert("alpha".to_owned(), BTreeMap::new());
+        assert_eq!(profile_names(&theme), vec!["default", "alpha", "stream"]);
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn contrast_ratios_match_wcag() {
+        assert!(close(contrast_ratio("#000000", "#ffffff").unwrap(), 21.0));
+        assert!(close(contrast_ratio("#fff", "#ffffff").unwrap(), 1.0));
+        // Order doesn't matter
+        assert_eq!(
+            contrast_ratio("#ed333b", DARK_BACKGROUND),
+            contrast_ratio(DARK_BACKGROUND, "#ed333b")
+        );
+        assert_eq!(contrast_ratio("gold", DARK_BACKGROUND), None);
+        assert_eq!(contrast_ratio("#12345", DARK_BACKGROUND), None);
+    }
+
+    #[test]
+    fn derived_colors_are_readable_on_the_adwaita_backgrounds() {
+        for color in ["#ed333b", "#1c71d8", "#613583", "#000000"] {
+            assert!(contrast_ratio(color, DARK_BACKGROUND).unwrap() < MIN_CONTRAST);
+            let dark = readable_variant(color, DARK_BACKGROUND).unwrap();
+            let ratio = contrast_ratio(&dark, DARK_BACKGROUND).unwrap();
+            assert!(ratio >= MIN_CONTRAST, "{color} -> {dark}: {ratio}");
+        }
+        for color in ["#f8e45c", "#ffffff", "#e5a50a"] {
+            let light = readable_variant(color, LIGHT_BACKGROUND).unwrap();
+            let ratio = contrast_ratio(&light, LIGHT_BACKGROUND).unwrap();
+            assert!(ratio >= MIN_CONTRAST, "{color} -> {light}: {ratio}");
+        }
+    }
+
+    #[test]
+    fn derivation_keeps_the_hue_and_readable_colors() {
+        assert_eq!(
+            readable_variant("#33d17a", DARK_BACKGROUND).as_deref(),
+            Some("#33d17a")
+        );
+        let red = readable_variant("#ed333b", DARK_BACKGROUND).unwrap();
+        let (hue, _, lightness) = rgb_to_hsl(parse_hex(&red).unwrap());
+        let (original_hue, _, original_lightness) = rgb_to_hsl(parse_hex("#ed333b").unwrap());
+        assert!((hue - original_hue).abs() < 2.0);
+        assert!(lightness > original_lightness);
+        assert_eq!(readable_variant("gold", DARK_BACKGROUND), None);
+    }
+
+    #[test]
+    fn dark_colors_prefer_explicit_values() {
+        let mut theme = Theme::default();
+        theme.profiles.insert(
+            DEFAULT_PROFILE.to_owned(),
+            colors(&[
+                ("goldsplit", "#e5a50a"),
+                ("redsplit", "#ed333b"),
+                ("blue", "navy"),
+            ]),
+        );
+        theme.dark_profiles.insert(
+            DEFAULT_PROFILE.to_owned(),
+            colors(&[("goldsplit", "#f8e45c")]),
+        );
+        theme.profiles.insert(
+            STREAM_PROFILE.to_owned(),
+            colors(&[("goldsplit", "#c64600")]),
+        );
+
+        // Light is the profiles as written
+        let light = scheme_colors(&theme, DEFAULT_PROFILE, false);
+        for (class, color) in merged_colors(&theme, DEFAULT_PROFILE) {
+            assert_eq!(light[&class], color);
+        }
+
+        let dark = scheme_colors(&theme, DEFAULT_PROFILE, true);
+        assert_eq!(dark["goldsplit"], "#f8e45c");
+        assert_ne!(dark["redsplit"], "#ed333b");
+        assert!(contrast_ratio(&dark["redsplit"], DARK_BACKGROUND).unwrap() >= MIN_CONTRAST);
+        // Named colors can't be adjusted and are kept
+        assert_eq!(dark["blue"], "navy");
+
+        // The stream profile's own gold wins over the default's dark gold
+        let stream = scheme_colors(&theme, STREAM_PROFILE, true);
+        assert_eq!(
+            stream["goldsplit"],
+            readable_variant("#c64600", DARK_BACKGROUND).unwrap()
+        );
+        assert_eq!(stream["redsplit"], dark["redsplit"]);
+    }
+
+    #[test]
+    fn bundled_split_colors_are_readable_in_both_styles() {
+        let bundled = bundled_split_colors();
+        assert_eq!(bundled.len(), BUNDLED_SPLIT_COLORS.len());
+        assert_eq!(bundled["goldsplit"], "#e5a50a");
+
+        let theme = Theme::default();
+        for (dark, background) in [(false, LIGHT_BACKGROUND), (true, DARK_BACKGROUND)] {
+            let colors = scheme_colors(&theme, DEFAULT_PROFILE, dark);
+            let css = profile_css(&colors);
+            for (class, _) in BUNDLED_SPLIT_COLORS {
+                assert!(css.contains(&format!(".{class} {{ color: #")));
+                let ratio = contrast_ratio(&colors[class], background).unwrap();
+                assert!(ratio >= MIN_CONTRAST, "{class} on {background}: {ratio}");
+            }
+        }
+        // The bundled gold is too light for the light background as written
+        assert_ne!(
+            scheme_colors(&theme, DEFAULT_PROFILE, false)["goldsplit"],
+            "#e5a50a"
+        );
+    }
+
+    #[test]
+    fn css_lists_every_class() {
+        let css = profile_css(&colors(&[("goldsplit", "gold"), ("redsplit", "red")]));
+        assert_eq!(
+            css,
+            ".goldsplit { color: gold; }\n.redsplit { color: red; }\n"
+        );
+    }
+}
@@ -1,20 +1,39 @@
 use crate::config::Config;
+use crate::ui::timer::delta_bars::{BAR_WIDTH, DeltaBar, bar_widths};
+use crate::ui::timer::dual_comparison::{
+    DualColumns, DualHeader, dual_row_data, resolve_secondary,
+};
+use crate::ui::timer::frame_budget::{AppliedSplits, RowMemo, RowRefreshQueue, visible_positions};
+use crate::ui::timer::modal::show_notice;
+use crate::ui::timer::segment_style::{apply_segment_styles, segment_class};
+use crate::ui::timer::split_menu::{SplitMenu, TimerSplitActions};
+use crate::ui::timer::value_column::ValueColumn;
 use crate::utils::comparisons::{
-    classify_split_label, current_attempt_running_duration, format_signed,
-    previous_split_combined_gold_and_prev_comparison, segment_comparison_time, segment_split_time,
+    DeltaWidget, MethodClasses, TimeSave, attempt_split_time, color_class,
+    current_attempt_running_duration, current_gold_pace, current_segment_elapsed, format_delta,
+    format_time_save, live_gold_row_class, previous_split_combined_gold_for_config,
+    segment_comparison_time, segment_split_time, segment_time_save, split_delta, split_delta_class,
+};
+use crate::utils::stats::{
+    conditional_expected_time, format_reset_share, reset_shares, segment_samples,
 };
+use crate::utils::video_offset::video_offset;
 
 use adw::ActionRow;
 use adw::prelude::ActionRowExt;
 use glib::Propagation;
 use gtk4::ffi::GTK_ICON_LOOKUP_FORCE_REGULAR;
 use gtk4::{
-    Align, Box as GtkBox, EventControllerKey, Label, ListBox, Orientation, ScrolledWindow,
-    SelectionMode, gdk,
+    Align, Box as GtkBox, EventControllerKey, Label, ListBox, ListBoxRow, Orientation,
+    ScrolledWindow, SelectionMode, Widget, gdk,
 };
 use gtk4::{CenterBox, prelude::*};
 
-use livesplit_core::{Timer, TimerPhase};
+use livesplit_core::{Time, Timer, TimerPhase, TimingMethod};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use tracing::warn;
 
 /// The body of the Timer UI:
 ///
@@ -52,20 +71,74 @@ impl TimerBody {
         self.segment_list.last_segment_list()
     }
 
+    pub fn value_column(&self) -> &ValueColumn {
+        self.segment_list.value_column()
+    }
+
+    pub fn placed_rows(&self) -> &PlacedRows {
+        self.segment_list.placed_rows()
+    }
+
     pub fn refresh(&mut self, timer: &Timer, config: &Config, force_rebuild: bool) {
         self.segment_list.update(timer, config, force_rebuild);
     }
+
+    /// Whether the rows show a secondary comparison.
+    pub fn dual_comparison(&self) -> bool {
+        self.segment_list.secondary.is_some()
+    }
 }
 
+/// Start and end of the list positions whose rows are placed in the
+/// scrolled list, and how many positions it has. The rows out of view have
+/// no widgets, so the first placed row is at `start`.
+pub type PlacedRows = Rc<Cell<(usize, usize, usize)>>;
+
 /// Component responsible of rendering, managing, and updating the list of segments/splits.
 pub struct SegmentList {
     container: GtkBox,
     scroller: ScrolledWindow,
     list: ListBox,
     last_segment_list: ListBox,
-    rows: Vec<SegmentRow>,
+    /// Stand in for the rows above and below the ones placed in `list`.
+    top_spacer: GtkBox,
+    bottom_spacer: GtkBox,
+    /// One per segment, in segment order. Only the final split and the rows
+    /// placed in `list` have widgets.
+    rows: Vec<Option<SegmentRow>>,
+    /// Height of a row, measured when the rows are built.
+    row_height: i32,
+    /// Shared with the keyboard navigation and the footer.
+    placed: PlacedRows,
+    /// Segment to select, and whether to focus it, once its row is placed.
+    pending_selection: Rc<Cell<Option<(usize, bool)>>>,
+    pending_rows: RowRefreshQueue,
+    applied_splits: AppliedSplits,
+    /// Which rows have their values computed, only the ones in view get them.
+    row_memo: RowMemo,
+    /// Bumped on every phase change, each attempt computes its rows anew.
+    attempt_generation: u64,
     last_phase: TimerPhase,
     last_comparison: String,
+    /// Whether the bars are on, the split index and timing method the delta
+    /// bars were last drawn for.
+    delta_bars_for: Option<(bool, Option<usize>, TimingMethod)>,
+    /// Whether the danger markers are on, their threshold and the number of
+    /// attempts they were last placed for.
+    danger_markers_for: Option<(bool, f64, usize)>,
+    /// Whether the rows are laid out with the final split on top. Shared with
+    /// the keyboard navigation between both lists.
+    reversed: Rc<Cell<bool>>,
+    split_menu: Rc<SplitMenu>,
+    projection: SegmentProjection,
+    /// Shared with the footer's readouts, see [`ValueColumn`].
+    value_column: ValueColumn,
+    /// The secondary comparison of the dual comparison layout.
+    secondary: Option<String>,
+    dual_header: DualHeader,
+    /// Why the dual comparison layout was last left out, so it's only
+    /// reported once.
+    dual_fallback: Option<String>,
 }
 
 impl SegmentList {
@@ -78,12 +151,13 @@ impl SegmentList {
             .css_classes(["splits-container", "no-background"])
             .build();
 
-        let height_request = SegmentList::compute_scroller_height(timer, config);
+        let row_height = SegmentRow::get_natural_height().max(1);
+        let height_request = SegmentList::compute_scroller_height(row_height, timer, config);
 
         let scroller = ScrolledWindow::builder()
             .hexpand(true)
             .vexpand(false)
-            .min_content_height(SegmentRow::get_natural_height())
+            .min_content_height(row_height)
             .height_request(height_request)
             .css_classes(["no-background"])
             .kinetic_scrolling(true)
@@ -100,18 +174,47 @@ impl SegmentList {
             .css_classes(["last-split-boxed-list", "no-background"])
             .build();
 
+        let top_spacer = GtkBox::new(Orientation::Vertical, 0);
+        let bottom_spacer = GtkBox::new(Orientation::Vertical, 0);
+        let scrolled_content = GtkBox::new(Orientation::Vertical, 0);
+        scrolled_content.append(&top_spacer);
+        scrolled_content.append(&list);
+        scrolled_content.append(&bottom_spacer);
+
+        let dual_header = DualHeader::new();
+        container.append(dual_header.container());
         container.append(&scroller);
         container.append(&last_segment_list);
-        scroller.set_child(Some(&list));
+        scroller.set_child(Some(&scrolled_content));
+
+        let split_menu = SplitMenu::new(&container, TimerSplitActions::new(&container));
 
         let mut this = Self {
             container,
             scroller,
             list,
             last_segment_list,
+            top_spacer,
+            bottom_spacer,
             rows: Vec::new(),
+            row_height,
+            placed: Rc::new(Cell::new((0, 0, 0))),
+            pending_selection: Rc::new(Cell::new(None)),
+            pending_rows: RowRefreshQueue::default(),
+            applied_splits: AppliedSplits::default(),
+            row_memo: RowMemo::default(),
+            attempt_generation: 0,
             last_phase: timer.current_phase(),
             last_comparison: timer.current_comparison().to_owned(),
+            delta_bars_for: None,
+            danger_markers_for: None,
+            reversed: Rc::new(Cell::new(false)),
+            split_menu,
+            projection: SegmentProjection::default(),
+            value_column: ValueColumn::new(config),
+            secondary: None,
+            dual_header,
+            dual_fallback: None,
         };
         this.build_rows(timer, config);
         this.list.unselect_all();
@@ -131,8 +234,16 @@ impl SegmentList {
         &self.last_segment_list
     }
 
+    pub fn value_column(&self) -> &ValueColumn {
+        &self.value_column
+    }
+
+    pub fn placed_rows(&self) -> &PlacedRows {
+        &self.placed
+    }
+
     pub fn update(&mut self, timer: &Timer, config: &Config, force_rebuild: bool) {
-        // Detect structural changes or comparison/splits changes that force a full rebuild.
+        // Structural changes force a full rebuild, comparison/phase changes refresh every row.
         let phase = timer.current_phase();
         let comp_changed = self.last_comparison.as_str() != timer.current_comparison();
         let splits_key_current = config
@@ -142,83 +253,359 @@ impl SegmentList {
             .map(|p| p.to_string_lossy().to_string());
         let phase_changed = self.last_phase != phase;
 
-        let selected_index = self.get_selected_row_index();
+        let selected_index = self.selected_segment_index();
 
-        if comp_changed || phase_changed || force_rebuild {
+        let rebuilt = force_rebuild
+            || self.rows.len() != timer.run().len()
+            || self.reversed.get() != config.style.reverse_segments.unwrap_or(false);
+        if rebuilt {
             self.rebuild_rows(timer, config);
-        } else if phase.is_running() {
-            self.update_scroll_position(timer, config);
-            self.update_rows_minimal(timer, config);
+        } else if comp_changed || phase_changed {
+            if comp_changed {
+                self.show_dual_header(timer, config);
+            }
+            if phase_changed {
+                self.attempt_generation += 1;
+            }
+            self.mark_splits_applied(timer);
         }
-
-        if comp_changed
-            && let Some(index) = selected_index
-            && let Some(row) = self.list.row_at_index(index)
+        // Same rows, new contents: refresh the ones in view over the next ticks
+        if self
+            .row_memo
+            .set_key(self.attempt_generation, timer.current_comparison())
         {
-            self.list.grab_focus();
-            self.list.select_row(Some(&row));
+            self.pending_rows.clear();
+        }
+        self.place_window(timer, config);
+        let window = self.visible_rows(timer);
+        self.pending_rows.extend(self.row_memo.due(window));
+        self.refresh_pending_rows(timer, config);
+
+        if !(comp_changed || phase_changed) {
+            if phase.is_running() {
+                self.update_scroll_position(timer, config);
+            }
+            if !phase.is_not_running() {
+                self.update_rows_minimal(timer, config);
+            }
         }
+        self.update_delta_bars(timer, config, rebuilt || comp_changed || phase_changed);
+        self.update_danger_markers(timer, config, rebuilt);
+        self.update_projection(timer, config);
 
         if phase_changed {
             if phase.is_not_running() {
                 // Go to the beggining of the split list after a reset
                 self.update_scroll_position(timer, config);
-            } else if phase.is_ended() {
-                self.last_segment_list.grab_focus();
-                self.last_segment_list
-                    .select_row(Some(&self.last_segment_list.row_at_index(0).unwrap()));
             }
             self.update_selection_policy(phase);
         }
 
+        if rebuilt || comp_changed || phase_changed {
+            let target =
+                selection_after_update(selected_index, self.rows.len(), phase, phase_changed);
+            if let Some(index) = target {
+                self.select_segment(index, comp_changed || phase_changed);
+            }
+        }
+
         self.last_phase = phase;
         self.last_comparison = timer.current_comparison().to_string();
 
         // Update scroller height request
-        let height_request = SegmentList::compute_scroller_height(timer, config);
+        let height_request = SegmentList::compute_scroller_height(self.row_height, timer, config);
         self.scroller.set_height_request(height_request);
     }
 
     fn update_scroll_position(&mut self, timer: &Timer, config: &Config) {
         let adjustment = self.scroller.vadjustment();
 
-        if let Some(cur) = timer.current_split_index() {
+        // Offset from the first segment's end of the list
+        let offset = if let Some(cur) = timer.current_split_index() {
             let follow_from = config.style.segments_scroll_follow_from.unwrap_or(7);
-            let y = SegmentRow::get_natural_height() * (cur as i32 + 1 - follow_from as i32);
+            let y = self.row_height * (cur as i32 + 1 - follow_from as i32);
 
-            if self.list.row_at_index(cur as i32).is_some() {
-                adjustment.set_value(if cur >= follow_from {
+            // The final split isn't in the scrolled list
+            (cur + 1 < self.rows.len()).then(|| {
+                if cur >= follow_from {
                     f64::from(y)
                 } else {
                     0.0
-                });
-            }
+                }
+            })
         } else {
-            adjustment.set_value(0.0);
+            Some(0.0)
+        };
+        if let Some(offset) = offset {
+            adjustment.set_value(if self.reversed.get() {
+                (adjustment.upper() - adjustment.page_size() - offset).max(0.0)
+            } else {
+                offset
+            });
         }
 
         self.scroller.set_vadjustment(Some(&adjustment));
     }
 
-    fn get_selected_row_index(&mut self) -> Option<i32> {
-        self.list.selected_row().map(|row| row.index())
+    /// Index of the selected segment, whichever list its row is in.
+    pub fn selected_segment_index(&self) -> Option<usize> {
+        let selected = self
+            .list
+            .selected_row()
+            .or_else(|| self.last_segment_list.selected_row())?;
+        self.rows.iter().position(|row| {
+            row.as_ref()
+                .is_some_and(|row| row.row().upcast_ref::<ListBoxRow>() == &selected)
+        })
+    }
+
+    /// Selects the row of segment `index` in the list that holds it. Rows
+    /// not placed in the list are scrolled to, and selected once placed.
+    pub fn select_segment(&self, index: usize, focus: bool) {
+        let Some(slot) = self.rows.get(index) else {
+            return;
+        };
+        let Some(row) = slot.as_ref().map(SegmentRow::row) else {
+            let position = list_index(index, self.rows.len(), self.reversed.get());
+            self.scroller
+                .vadjustment()
+                .set_value(f64::from(self.row_height) * position as f64);
+            self.pending_selection.set(Some((index, focus)));
+            return;
+        };
+        let Some(list) = row.parent().and_then(|p| p.downcast::<ListBox>().ok()) else {
+            return;
+        };
+        if focus {
+            list.grab_focus();
+        }
+        list.select_row(Some(row));
+    }
+
+    /// Segment indices of the rows in view, or about to be: the current
+    /// split's first, then the ones placed in the scrolled list from its top
+    /// and the final split.
+    fn visible_rows(&self, timer: &Timer) -> Vec<usize> {
+        let segment_count = self.rows.len();
+        let (start, end, _) = self.placed.get();
+        let reversed = self.reversed.get();
+        let current = timer
+            .current_split_index()
+            .filter(|c| self.rows.get(*c).is_some_and(Option::is_some));
+        current
+            .into_iter()
+            .chain((start..end).map(|position| list_index(position, segment_count, reversed)))
+            .chain(segment_count.checked_sub(1))
+            .collect()
+    }
+
+    /// How many rows the scrolled list shows at once, from its allocated
+    /// height, or the configured count until it has one.
+    fn page_rows(&self, config: &Config) -> usize {
+        let page_size = self.scroller.vadjustment().page_size();
+        if page_size > 0.0 {
+            (page_size / f64::from(self.row_height)).ceil() as usize
+        } else {
+            config.style.max_segments_displayed.unwrap_or(10)
+        }
+    }
+
+    /// Places the rows of the scrolled list in view, or about to be, in the
+    /// list, the spacers stand in for the others. Rows leaving it are
+    /// dropped and computed again once they're back.
+    fn place_window(&mut self, timer: &Timer, config: &Config) {
+        let segment_count = self.rows.len();
+        let scrolled = segment_count.saturating_sub(1);
+        let first = (self.scroller.vadjustment().value() / f64::from(self.row_height)) as usize;
+        let window = visible_positions(first, self.page_rows(config), scrolled);
+        let (start, end, _) = self.placed.get();
+        let placed = start..end;
+        if window == placed {
+            return;
+        }
+        let reversed = self.reversed.get();
+        for position in placed.clone() {
+            let index = list_index(position, segment_count, reversed);
+            if !window.contains(&position)
+                && let Some(row) = self.rows[index].take()
+            {
+                self.list.remove(row.row());
+                self.row_memo.forget(index);
+            }
+        }
+        for position in window.clone() {
+            if placed.contains(&position) {
+                continue;
+            }
+            let index = list_index(position, segment_count, reversed);
+            let row = self.materialize(timer, config, index);
+            self.list.insert(&row, (position - window.start) as i32);
+        }
+        self.top_spacer
+            .set_height_request(self.row_height * window.start as i32);
+        self.bottom_spacer
+            .set_height_request(self.row_height * (scrolled - window.end) as i32);
+        self.placed.set((window.start, window.end, scrolled));
+        // The new rows get their bars and markers on this update
+        self.delta_bars_for = None;
+        self.danger_markers_for = None;
+
+        if let Some((index, focus)) = self.pending_selection.get()
+            && self.rows.get(index).is_some_and(Option::is_some)
+        {
+            self.pending_selection.set(None);
+            self.select_segment(index, focus);
+        }
+    }
+
+    /// The widget of segment `index`'s row, built if it has none yet. Its
+    /// values are computed by the next refresh.
+    fn materialize(&mut self, timer: &Timer, config: &Config, index: usize) -> ActionRow {
+        let current = timer.current_split_index();
+        let segment = &timer.run().segments()[index];
+        if self.rows[index].is_none() {
+            self.row_memo.forget(index);
+        }
+        let row = self.rows[index].get_or_insert_with(|| {
+            let row = SegmentRow::unrendered(timer, config, current, index, segment);
+            // The row keeps its segment index whatever list and position it ends up in
+            self.split_menu.attach(row.row(), index);
+            self.value_column.add(row.value_label());
+            row
+        });
+        row.row().clone()
     }
 
+    /// Refreshes the rows around the current split, and every row split or
+    /// undone since the last tick. Those out of view are computed again once
+    /// they're scrolled to.
     fn update_rows_minimal(&mut self, timer: &Timer, config: &Config) {
-        if let Some(cur) = timer.current_split_index() {
-            let len = timer.run().segments().len();
-
-            // Avoid rerendering twice
-            let mut indices_vec = vec![cur.saturating_sub(1), cur, cur.saturating_add(1)];
-            indices_vec.sort_unstable();
-            indices_vec.dedup();
-            for i in indices_vec {
-                if i < len
-                    && let Some(row) = self.rows.get_mut(i)
-                {
-                    let seg = &timer.run().segments()[i];
-                    row.refresh(timer, config, Some(cur), i, seg);
-                }
+        let current = timer.current_split_index();
+        let segments = timer.run().segments();
+        let dirty = self
+            .applied_splits
+            .dirty_rows(current, segments.iter().map(|seg| seg.split_time()));
+        let window = self.visible_rows(timer);
+        let secondary = self.secondary.as_deref();
+        for i in dirty {
+            if !window.contains(&i) {
+                self.row_memo.forget(i);
+                continue;
+            }
+            if let (Some(Some(row)), Some(seg)) = (self.rows.get_mut(i), segments.get(i)) {
+                row.refresh(timer, config, current, i, seg);
+                row.refresh_dual(timer, config, secondary, i);
+            }
+        }
+    }
+
+    fn mark_splits_applied(&mut self, timer: &Timer) {
+        let segments = timer.run().segments();
+        self.applied_splits.mark_applied(
+            timer.current_split_index(),
+            segments.iter().map(|seg| seg.split_time()),
+        );
+    }
+
+    /// Redraws the delta bars when a split may have changed what they scale
+    /// against, or with `force`.
+    fn update_delta_bars(&mut self, timer: &Timer, config: &Config, force: bool) {
+        let enabled = config.layout.show_delta_bars;
+        let key = (
+            enabled,
+            timer.current_split_index(),
+            timer.current_timing_method(),
+        );
+        if !force && self.delta_bars_for == Some(key) {
+            return;
+        }
+        self.delta_bars_for = Some(key);
+        for row in self.rows.iter().flatten() {
+            row.delta_bar.set_enabled(enabled);
+        }
+        if !enabled {
+            return;
+        }
+        let deltas: Vec<_> = (0..self.rows.len())
+            .map(|index| split_delta(timer, index))
+            .collect();
+        for (index, (row, width)) in self.rows.iter_mut().zip(bar_widths(&deltas)).enumerate() {
+            let Some(row) = row else {
+                continue;
+            };
+            let class = if width > 0 {
+                split_delta_class(timer, config, index).and_then(|class| color_class(config, class))
+            } else {
+                None
+            };
+            row.delta_bar.show(width, class);
+        }
+    }
+
+    /// Marks the segments with a large share of the resets. Placed again
+    /// once an attempt is recorded, or with `force`.
+    fn update_danger_markers(&mut self, timer: &Timer, config: &Config, force: bool) {
+        let stats = &config.stats;
+        let key = (
+            stats.show_danger_markers,
+            stats.danger_marker_threshold,
+            timer.run().attempt_history().len(),
+        );
+        if !force && self.danger_markers_for == Some(key) {
+            return;
+        }
+        self.danger_markers_for = Some(key);
+        let shares = stats
+            .show_danger_markers
+            .then(|| reset_shares(timer.run()))
+            .flatten()
+            .unwrap_or_default();
+        for (index, row) in self.rows.iter().enumerate() {
+            let Some(row) = row else {
+                continue;
+            };
+            let tooltip = shares
+                .get(index)
+                .filter(|share| **share > 0.0 && **share * 100.0 >= stats.danger_marker_threshold)
+                .map(|share| format_reset_share(*share));
+            row.show_danger(tooltip.as_deref());
+        }
+    }
+
+    /// Puts the running segment's projection on its row, on top of what the
+    /// row refresh just rendered.
+    fn update_projection(&mut self, timer: &Timer, config: &Config) {
+        let phase = timer.current_phase();
+        if !config.stats.show_projection || !(phase.is_running() || phase.is_paused()) {
+            self.projection.clear();
+            return;
+        }
+        let Some(current) = timer.current_split_index() else {
+            return;
+        };
+        let text = self.projection.update(Instant::now(), current, || {
+            projection_text(timer, config, current)
+        });
+        if let Some(Some(row)) = self.rows.get_mut(current) {
+            row.show_projection(text);
+        }
+    }
+
+    /// Refreshes the next chunk of rows queued by a comparison or phase change.
+    fn refresh_pending_rows(&mut self, timer: &Timer, config: &Config) {
+        if self.pending_rows.is_empty() {
+            return;
+        }
+        let current = timer.current_split_index();
+        let segments = timer.run().segments();
+        let secondary = self.secondary.as_deref();
+        for i in self
+            .pending_rows
+            .next_chunk(config.debug.rebuild_chunk_size)
+        {
+            if let (Some(Some(row)), Some(seg)) = (self.rows.get_mut(i), segments.get(i)) {
+                row.refresh(timer, config, current, i, seg);
+                row.refresh_dual(timer, config, secondary, i);
             }
         }
     }
@@ -245,14 +632,27 @@ impl SegmentList {
             }
         });
 
-        // Keyboard navigation
+        // Keyboard navigation, towards the final split's list and back
         let list_for_down = self.list.clone();
         let last_list_for_down = self.last_segment_list.clone();
+        let reversed = self.reversed.clone();
+        let placed = self.placed.clone();
         let down_ctrl = EventControllerKey::new();
         down_ctrl.connect_key_pressed(move |_, keyval, _, _| {
-            if keyval == gdk::Key::Down
+            let reversed = reversed.get();
+            let key = if reversed {
+                gdk::Key::Up
+            } else {
+                gdk::Key::Down
+            };
+            let (start, end, scrolled) = placed.get();
+            if keyval == key
                 && let Some(selected) = list_for_down.selected_row()
-                && selected.next_sibling().is_none()
+                && if reversed {
+                    start == 0 && selected.prev_sibling().is_none()
+                } else {
+                    end == scrolled && selected.next_sibling().is_none()
+                }
                 && let Some(row) = last_list_for_down.row_at_index(0)
             {
                 last_list_for_down.grab_focus();
@@ -266,22 +666,46 @@ impl SegmentList {
         let list_for_up = self.list.clone();
         let last_list_for_up = self.last_segment_list.clone();
         let scroller_for_up = self.scroller.clone();
+        let reversed = self.reversed.clone();
+        let placed = self.placed.clone();
+        let pending_selection = self.pending_selection.clone();
         let up_ctrl = EventControllerKey::new();
         up_ctrl.connect_key_pressed(move |_, keyval, _, _| {
-            if keyval == gdk::Key::Up
-                && let Some(selected) = last_list_for_up.selected_row()
-                && selected.index() == 0
-                && let Some(last) = list_for_up.last_child()
-                && let Ok(row) = last.downcast::<gtk4::ListBoxRow>()
-            {
+            let reversed = reversed.get();
+            let key = if reversed {
+                gdk::Key::Down
+            } else {
+                gdk::Key::Up
+            };
+            let (start, end, scrolled) = placed.get();
+            let on_final_split = last_list_for_up
+                .selected_row()
+                .is_some_and(|selected| selected.index() == 0);
+            if keyval != key || scrolled == 0 || !on_final_split {
+                return Propagation::Proceed;
+            }
+            let adjustment = scroller_for_up.vadjustment();
+            adjustment.set_value(if reversed {
+                adjustment.lower()
+            } else {
+                adjustment.upper()
+            });
+            let edge = if reversed {
+                (start == 0).then(|| list_for_up.first_child()).flatten()
+            } else {
+                (end == scrolled)
+                    .then(|| list_for_up.last_child())
+                    .flatten()
+            };
+            if let Some(row) = edge.and_downcast::<gtk4::ListBoxRow>() {
                 list_for_up.grab_focus();
                 list_for_up.select_row(Some(&row));
-                scroller_for_up
-                    .vadjustment()
-                    .set_value(scroller_for_up.vadjustment().upper());
-                return Propagation::Stop;
+            } else {
+                // The second to last segment is next to the final split
+                // either way, it's selected once scrolled to
+                pending_selection.set(Some((scrolled - 1, true)));
             }
-            Propagation::Proceed
+            Propagation::Stop
         });
         self.last_segment_list.add_controller(up_ctrl);
     }
@@ -299,9 +723,6 @@ impl SegmentList {
                 self.list.set_selection_mode(SelectionMode::Single);
                 self.last_segment_list
                     .set_selection_mode(SelectionMode::Single);
-                if let Some(row) = self.last_segment_list.row_at_index(0) {
-                    self.last_segment_list.select_row(Some(&row));
-                }
             }
             _ => {
                 self.list.set_selection_mode(SelectionMode::Single);
@@ -325,40 +746,222 @@ impl SegmentList {
             self.last_segment_list.remove(&child);
         }
         self.rows.clear();
+        self.pending_rows.clear();
+
+        apply_segment_styles(&config.style.segment_classes);
+        self.value_column.update(config);
+        self.resolve_dual_comparison(timer, config);
+
+        // `rows` stays in segment order whatever the layout, only the rows
+        // in view get widgets. Their values are computed as they come into view.
+        self.row_height = SegmentRow::get_natural_height().max(1);
+        let segment_count = timer.run().len();
+        self.rows.resize_with(segment_count, || None);
+        self.placed.set((0, 0, segment_count.saturating_sub(1)));
+        self.pending_selection.set(None);
+
+        // Last segment will always be visible, so we render it separately
+        let reversed = config.style.reverse_segments.unwrap_or(false);
+        if let Some(last) = segment_count.checked_sub(1) {
+            let row = self.materialize(timer, config, last);
+            self.last_segment_list.append(&row);
+        }
+        self.place_last_segment_list(reversed);
 
-        // Create new rows once and append references to the ListBox
+        self.row_memo.reset(segment_count);
+        self.row_memo
+            .set_key(self.attempt_generation, timer.current_comparison());
+        self.place_window(timer, config);
         let opt_current_segment_index = timer.current_split_index();
-        for (index, segment) in timer.run().segments().iter().enumerate() {
-            let row = SegmentRow::new(timer, config, opt_current_segment_index, index, segment);
-            // Last segment will always be visible, so we render it separately
-            if index < timer.run().len() - 1 {
-                self.list.append(row.row());
-            } else {
-                self.last_segment_list.append(row.row());
-            }
-            self.rows.push(row);
+        let segments = timer.run().segments();
+        let secondary = self.secondary.as_deref();
+        let window = self.visible_rows(timer);
+        for index in self.row_memo.due(window) {
+            let Some(row) = &mut self.rows[index] else {
+                continue;
+            };
+            row.refresh(
+                timer,
+                config,
+                opt_current_segment_index,
+                index,
+                &segments[index],
+            );
+            row.refresh_dual(timer, config, secondary, index);
         }
 
         // Refresh caches
+        self.mark_splits_applied(timer);
         self.last_phase = timer.current_phase();
         self.last_comparison = timer.current_comparison().to_string();
     }
 
-    fn compute_scroller_height(timer: &Timer, config: &Config) -> i32 {
-        let segments_requested = config.style.max_segments_displayed.unwrap_or(10);
+    /// Picks the secondary comparison for the rows about to be built. When
+    /// the splits can't have one, the single comparison layout is used and
+    /// the reason shown once.
+    fn resolve_dual_comparison(&mut self, timer: &Timer, config: &Config) {
+        let fallback = match resolve_secondary(timer, &config.layout) {
+            Ok(secondary) => {
+                self.secondary = secondary;
+                None
+            }
+            Err(reason) => {
+                self.secondary = None;
+                Some(reason)
+            }
+        };
+        if let Some(reason) = &fallback
+            && self.dual_fallback.as_ref() != Some(reason)
+        {
+            warn!("Dual comparison layout left out: {reason}");
+            // The toast overlay may not be up yet while the window is built
+            let notice = format!("{reason}, showing a single comparison");
+            glib::idle_add_local_once(move || show_notice(&notice));
+        }
+        self.dual_fallback = fallback;
+        self.show_dual_header(timer, config);
+    }
+
+    fn show_dual_header(&self, timer: &Timer, config: &Config) {
+        let mut delta_width = suffix_width(config);
+        if config.layout.show_delta_bars {
+            delta_width += BAR_WIDTH;
+        }
+        self.dual_header.show(
+            timer.current_comparison(),
+            self.secondary.as_deref(),
+            delta_width,
+        );
+    }
 
-        if segments_requested < timer.run().len() - 1 {
-            SegmentRow::get_natural_height() * segments_requested as i32
+    /// Puts the final split's list under the others, or on top of them when
+    /// the order is reversed.
+    fn place_last_segment_list(&self, reversed: bool) {
+        self.reversed.set(reversed);
+        if reversed {
+            self.container
+                .reorder_child_after(&self.last_segment_list, None::<&Widget>);
+            self.container.add_css_class("reversed");
         } else {
-            SegmentRow::get_natural_height() * (timer.run().len() as i32 - 1)
+            self.container
+                .reorder_child_after(&self.last_segment_list, Some(&self.scroller));
+            self.container.remove_css_class("reversed");
         }
+        // The column headers stay on top either way
+        self.container
+            .reorder_child_after(self.dual_header.container(), None::<&Widget>);
+    }
+
+    fn compute_scroller_height(row_height: i32, timer: &Timer, config: &Config) -> i32 {
+        let segments_requested = config.style.max_segments_displayed.unwrap_or(10);
+
+        // Every segment but the final one is in the scrolled list
+        let scrolled = timer.run().len().saturating_sub(1);
+        row_height * segments_requested.min(scrolled) as i32
     }
 }
 
+/// Position of segment `index` in the scrolled list, which holds every
+/// segment but the final one. With `reversed` the list runs from the second
+/// to last segment up to the first. The mapping is its own inverse, so it
+/// also gives the segment shown at a position.
+pub fn list_index(index: usize, segment_count: usize, reversed: bool) -> usize {
+    if reversed {
+        segment_count.saturating_sub(2).saturating_sub(index)
+    } else {
+        index
+    }
+}
+
+/// Segment to select once rows were rebuilt, the comparison changed or the
+/// phase changed. Ending an attempt selects the final split; other phase
+/// changes clear the selection. Otherwise the previous segment stays
+/// selected, as long as it still exists.
+pub fn selection_after_update(
+    previous: Option<usize>,
+    segment_count: usize,
+    phase: TimerPhase,
+    phase_changed: bool,
+) -> Option<usize> {
+    let last = segment_count.checked_sub(1)?;
+    if phase.is_ended() && (phase_changed || previous.is_none()) {
+        return Some(last);
+    }
+    if phase_changed {
+        return None;
+    }
+    previous.filter(|&index| index <= last)
+}
+
+/// How often the running segment's projection is recomputed.
+pub const PROJECTION_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The running segment's projection, recomputed once a second rather than
+/// on every tick.
+#[derive(Debug, Default)]
+pub struct SegmentProjection {
+    computed: Option<(Instant, usize)>,
+    text: Option<String>,
+}
+
+impl SegmentProjection {
+    /// Whether the projection of segment `index` is due at `now`: a second
+    /// after the last one, or right away for another segment.
+    pub fn is_due(&self, now: Instant, index: usize) -> bool {
+        self.computed.is_none_or(|(at, computed)| {
+            computed != index || now.duration_since(at) >= PROJECTION_INTERVAL
+        })
+    }
+
+    /// The projection of segment `index`, computing it with `compute` when due.
+    pub fn update(
+        &mut self,
+        now: Instant,
+        index: usize,
+        compute: impl FnOnce() -> Option<String>,
+    ) -> Option<&str> {
+        if self.is_due(now, index) {
+            self.text = compute();
+            self.computed = Some((now, index));
+        }
+        self.text.as_deref()
+    }
+
+    pub fn clear(&mut self) {
+        self.computed = None;
+        self.text = None;
+    }
+}
+
+/// "proj: 1:12" for the running segment at `index`, from its history and
+/// the time spent in it so far.
+fn projection_text(timer: &Timer, config: &Config, index: usize) -> Option<String> {
+    let elapsed = current_segment_elapsed(timer, video_offset(config))?;
+    let expected = conditional_expected_time(
+        &segment_samples(timer.run(), index, timer.current_timing_method()),
+        elapsed.as_seconds_f64(),
+        config.stats.outlier_z_score,
+        config.stats.projection_min_samples,
+    )?;
+    let expected = time::Duration::seconds_f64(expected);
+    Some(format!(
+        "proj: {}",
+        config.format.segment.format_duration(&expected)
+    ))
+}
+
 // SegmentRow: wraps a row widget and its value label so we can refresh without touching the ListBox
 pub struct SegmentRow {
     row: ActionRow,
     suffix: SegmentSuffix,
+    /// Warning sign on a segment many attempts are reset on.
+    danger: gtk4::Image,
+    delta_bar: DeltaBar,
+    dual: DualColumns,
+    /// Class from `style.segment-classes` currently on the row.
+    override_class: Option<String>,
+    /// Whether the row is on `current-segment`, and its live gold class.
+    rendered_classes: (bool, Option<&'static str>),
 }
 
 impl SegmentRow {
@@ -366,12 +969,31 @@ impl SegmentRow {
         &self.row
     }
 
+    /// The comparison time, the row's entry in the value column.
+    pub fn value_label(&self) -> &Label {
+        &self.suffix.comparison_label
+    }
+
     pub fn new(
         timer: &Timer,
         config: &Config,
         opt_current_segment_index: Option<usize>,
         index: usize,
         segment: &livesplit_core::Segment,
+    ) -> Self {
+        let mut row = Self::unrendered(timer, config, opt_current_segment_index, index, segment);
+        row.suffix
+            .compute_segment(timer, config, opt_current_segment_index, index, segment);
+        row
+    }
+
+    /// The row without its values, which the next `refresh` computes.
+    pub fn unrendered(
+        timer: &Timer,
+        config: &Config,
+        opt_current_segment_index: Option<usize>,
+        index: usize,
+        segment: &livesplit_core::Segment,
     ) -> Self {
         let row = ActionRow::builder()
             .title(segment.name())
@@ -395,19 +1017,51 @@ impl SegmentRow {
             row.add_prefix(&image);
         }
 
-        if Some(index) == opt_current_segment_index {
+        let is_current = Some(index) == opt_current_segment_index;
+        let gold_class = live_gold_class(timer, config, is_current);
+        if is_current {
             row.add_css_class("current-segment");
         }
-        let suffix = SegmentSuffix::new(timer, config, opt_current_segment_index, index, segment);
+        if let Some(class) = gold_class {
+            row.add_css_class(class);
+        }
+        let suffix = SegmentSuffix::new(config);
 
+        let danger = gtk4::Image::builder()
+            .icon_name("dialog-warning-symbolic")
+            .valign(Align::Center)
+            .visible(false)
+            .css_classes(["dim-label"])
+            .build();
+        row.add_suffix(&danger);
+        let delta_bar = DeltaBar::new();
+        delta_bar.set_enabled(config.layout.show_delta_bars);
+        row.add_suffix(delta_bar.container());
         row.add_suffix(suffix.container());
+        let dual = DualColumns::new();
+        row.add_suffix(dual.container());
 
         // Add no transition for more responsive updates
         row.add_css_class("no-transition");
 
-        Self { row, suffix }
+        let override_class = segment_class(&config.style.segment_classes, index, segment.name());
+        if let Some(class) = &override_class {
+            row.add_css_class(class);
+        }
+
+        Self {
+            row,
+            suffix,
+            danger,
+            delta_bar,
+            dual,
+            override_class,
+            rendered_classes: (is_current, gold_class),
+        }
     }
 
+    /// Re-renders the row, only touching the widgets whose label, class or
+    /// visibility changed since the last render. Returns how many were.
     pub fn refresh(
         &mut self,
         timer: &Timer,
@@ -415,15 +1069,69 @@ impl SegmentRow {
         opt_current_segment_index: Option<usize>,
         index: usize,
         segment: &livesplit_core::Segment,
-    ) {
-        // Reset dynamic classes
-        self.row.remove_css_class("current-segment");
-        if Some(index) == opt_current_segment_index {
-            self.row.add_css_class("current-segment");
+    ) -> usize {
+        let is_current = Some(index) == opt_current_segment_index;
+        let gold_class = live_gold_class(timer, config, is_current);
+        let mut updates = 0;
+        if (is_current, gold_class) != self.rendered_classes {
+            self.row.remove_css_class("current-segment");
+            self.row.remove_css_class("on-gold-pace");
+            self.row.remove_css_class("gold-lost");
+            if is_current {
+                self.row.add_css_class("current-segment");
+            }
+            if let Some(class) = gold_class {
+                self.row.add_css_class(class);
+            }
+            self.rendered_classes = (is_current, gold_class);
+            updates += 1;
+        }
+
+        let override_class = segment_class(&config.style.segment_classes, index, segment.name());
+        if override_class != self.override_class {
+            if let Some(class) = &self.override_class {
+                self.row.remove_css_class(class);
+            }
+            if let Some(class) = &override_class {
+                self.row.add_css_class(class);
+            }
+            self.override_class = override_class;
+            updates += 1;
         }
 
+        updates
+            + self
+                .suffix
+                .compute_segment(timer, config, opt_current_segment_index, index, segment)
+    }
+
+    /// Fills the dual comparison columns against `secondary`, or hides them
+    /// and gives the comparison time its place back with `None`.
+    pub fn refresh_dual(
+        &mut self,
+        timer: &Timer,
+        config: &Config,
+        secondary: Option<&str>,
+        index: usize,
+    ) {
+        // The actual time has a column of its own
         self.suffix
-            .compute_segment(timer, config, opt_current_segment_index, index, segment);
+            .comparison_label
+            .set_visible(secondary.is_none());
+        self.dual
+            .show(secondary.map(|secondary| dual_row_data(timer, config, secondary, index)))
+    }
+
+    /// Shows the warning sign with `tooltip`, or hides it with `None`.
+    pub fn show_danger(&self, tooltip: Option<&str>) {
+        self.danger.set_visible(tooltip.is_some());
+        self.danger.set_tooltip_text(tooltip);
+    }
+
+    /// Shows `text` in the row's caption, where upcoming rows show their
+    /// possible time save, or puts the time save back with `None`.
+    pub fn show_projection(&mut self, text: Option<&str>) {
+        self.suffix.show_projection(text);
     }
 
     fn get_natural_height() -> i32 {
@@ -438,95 +1146,265 @@ impl SegmentRow {
     }
 }
 
+/// The live gold class of the current row, if the indicator is on.
+fn live_gold_class(timer: &Timer, config: &Config, is_current: bool) -> Option<&'static str> {
+    if is_current && config.behavior.live_gold_indicator {
+        live_gold_row_class(current_gold_pace(timer, config))
+            .and_then(|class| color_class(config, class))
+    } else {
+        None
+    }
+}
+
+/// Gives `labels` the width of `layout.value-min-width`, right-aligning
+/// their text so times of any length end on the same edge.
+fn reserve_value_width(labels: &[&Label], config: &Config) {
+    let width = config.layout.value_min_width;
+    if width <= 0 {
+        return;
+    }
+    for label in labels {
+        label.set_width_request(width);
+        label.set_xalign(1.0);
+    }
+}
+
+/// Width of a row's delta, time save and comparison part.
+fn suffix_width(config: &Config) -> i32 {
+    if config.layout.show_timesave_column {
+        220
+    } else {
+        150
+    }
+}
+
+/// Classes `classify_split_label` can put on a delta label.
+const SPLIT_DELTA_CLASSES: [&str; 5] = [
+    "goldsplit",
+    "greensplit",
+    "lostgreensplit",
+    "gainedredsplit",
+    "redsplit",
+];
+
+/// What a suffix shows. The last render is kept so a tick that renders the
+/// same thing again, like a running split whose delta isn't shown yet,
+/// doesn't touch the labels at all.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct SuffixRender {
+    comparison: String,
+    delta: String,
+    delta_class: Option<&'static str>,
+    caption: String,
+    caption_visible: bool,
+    tooltip: Option<String>,
+}
+
 // A segment suffix contains both the delta and the comparison labels, and renders them in a box, that is meant to be attached to a SegmentRow
 pub struct SegmentSuffix {
     container: CenterBox,
     delta_label: Label,
+    time_save_label: Label,
     comparison_label: Label,
+    rendered: Option<SuffixRender>,
+    /// The running segment's projection, shown instead of its time save.
+    projection: Option<String>,
+    /// Classes of the split segment under both timing methods, with the
+    /// attempt count, comparison and split time they were worked out for.
+    classes: Option<(u32, String, Time, MethodClasses)>,
 }
 
 impl SegmentSuffix {
-    pub fn new(
-        timer: &Timer,
-        config: &Config,
-        opt_current_segment_index: Option<usize>,
-        index: usize,
-        segment: &livesplit_core::Segment,
-    ) -> Self {
+    /// Empty labels, filled by `compute_segment`.
+    pub fn new(config: &Config) -> Self {
         let container = CenterBox::builder()
             .orientation(Orientation::Horizontal)
-            .width_request(150)
+            .width_request(suffix_width(config))
             .build();
         let delta_label = Label::builder()
             .halign(Align::Center)
             .valign(Align::Center)
             .css_classes(["timer", "monospace"])
             .build();
+        let time_save_label = Label::builder()
+            .halign(Align::Center)
+            .valign(Align::Center)
+            .css_classes(["caption", "dim-label", "monospace"])
+            .visible(false)
+            .build();
         let comparison_label = Label::builder()
             .halign(Align::Center)
             .valign(Align::Center)
             .css_classes(["timer", "monospace", "comparison"])
             .build();
         container.set_start_widget(Some(&delta_label));
+        container.set_center_widget(Some(&time_save_label));
         container.set_end_widget(Some(&comparison_label));
+        reserve_value_width(&[&delta_label, &comparison_label], config);
 
-        let suffix = Self {
+        Self {
             container,
             delta_label,
+            time_save_label,
             comparison_label,
-        };
-        suffix.compute_segment(timer, config, opt_current_segment_index, index, segment);
-
-        suffix
+            rendered: None,
+            projection: None,
+            classes: None,
+        }
     }
 
     pub fn container(&self) -> &CenterBox {
         &self.container
     }
 
-    #[allow(clippy::too_many_arguments)]
+    /// Without a projection the time save comes back with the next render.
+    fn show_projection(&mut self, text: Option<&str>) {
+        self.projection = text.map(str::to_owned);
+        if let (Some(text), Some(last)) = (text, &self.rendered) {
+            let render = SuffixRender {
+                caption: text.to_owned(),
+                caption_visible: true,
+                ..last.clone()
+            };
+            self.apply(render);
+        }
+    }
+
+    /// Renders the segment, returning how many widget updates it took.
     fn compute_segment(
+        &mut self,
+        timer: &Timer,
+        config: &Config,
+        opt_current_segment_index: Option<usize>,
+        index: usize,
+        segment: &livesplit_core::Segment,
+    ) -> usize {
+        let phase = timer.current_phase();
+        if opt_current_segment_index != Some(index)
+            || !config.stats.show_projection
+            || !(phase.is_running() || phase.is_paused())
+        {
+            self.projection = None;
+        }
+        let classes = opt_current_segment_index
+            .is_some_and(|current| current > index)
+            .then(|| self.method_classes(timer, config, index, segment));
+        let render = self.render(
+            timer,
+            config,
+            opt_current_segment_index,
+            index,
+            segment,
+            classes,
+        );
+        self.apply(render)
+    }
+
+    /// Classes of the split segment, worked out again only when it was split
+    /// again or compared against something else.
+    fn method_classes(
+        &mut self,
+        timer: &Timer,
+        config: &Config,
+        index: usize,
+        segment: &livesplit_core::Segment,
+    ) -> MethodClasses {
+        let attempts = timer.run().attempt_count();
+        let comparison = timer.current_comparison();
+        let split_time = segment.split_time();
+        match &self.classes {
+            Some((cached_attempts, cached_comparison, cached_time, classes))
+                if *cached_attempts == attempts
+                    && cached_comparison == comparison
+                    && *cached_time == split_time =>
+            {
+                *classes
+            }
+            _ => {
+                let classes = MethodClasses::of(timer, config, index);
+                self.classes = Some((attempts, comparison.to_owned(), split_time, classes));
+                classes
+            }
+        }
+    }
+
+    /// Puts `render` on the widgets, skipping whatever the last render
+    /// already shows.
+    fn apply(&mut self, render: SuffixRender) -> usize {
+        let last = self.rendered.take();
+        let differs = |same: fn(&SuffixRender, &SuffixRender) -> bool| {
+            last.as_ref().is_none_or(|last| !same(last, &render))
+        };
+        let mut updates = 0;
+        if differs(|a, b| a.comparison == b.comparison) {
+            self.comparison_label.set_label(&render.comparison);
+            updates += 1;
+        }
+        if differs(|a, b| a.delta == b.delta) {
+            self.delta_label.set_label(&render.delta);
+            updates += 1;
+        }
+        if differs(|a, b| a.delta_class == b.delta_class) {
+            for class in SPLIT_DELTA_CLASSES {
+                self.delta_label.remove_css_class(class);
+            }
+            if let Some(class) = render.delta_class {
+                self.delta_label.add_css_class(class);
+            }
+            updates += 1;
+        }
+        if differs(|a, b| a.caption == b.caption) {
+            self.time_save_label.set_label(&render.caption);
+            updates += 1;
+        }
+        if differs(|a, b| a.caption_visible == b.caption_visible) {
+            self.time_save_label.set_visible(render.caption_visible);
+            updates += 1;
+        }
+        if differs(|a, b| a.tooltip == b.tooltip) {
+            self.container.set_tooltip_text(render.tooltip.as_deref());
+            updates += 1;
+        }
+        self.rendered = Some(render);
+        updates
+    }
+
+    fn render(
         &self,
         timer: &Timer,
         config: &Config,
         opt_current_segment_index: Option<usize>,
         index: usize,
         segment: &livesplit_core::Segment,
-    ) {
+        classes: Option<MethodClasses>,
+    ) -> SuffixRender {
         let segment_comparison_time = segment_comparison_time(segment, timer);
-        let (previous_split_time, gold_duration, previous_comparison_duration) =
-            previous_split_combined_gold_and_prev_comparison(timer, index);
-        let segment_comparison_duration = segment_comparison_time
-            .checked_sub(previous_comparison_duration)
-            .unwrap_or_default()
-            .abs();
-
-        self.comparison_label.set_label(
-            config
-                .format
-                .segment
-                .format_split_time(
-                    &segment.comparison(timer.current_comparison()),
-                    timer.current_timing_method(),
-                )
-                .as_str(),
-        );
-        self.delta_label.set_label("");
+        let (previous_split_time, gold_duration, _) =
+            previous_split_combined_gold_for_config(timer, index, config);
+
+        let mut render = SuffixRender {
+            comparison: config.format.segment.format_split_time(
+                &segment.comparison(timer.current_comparison()),
+                timer.current_timing_method(),
+            ),
+            ..SuffixRender::default()
+        };
+        self.render_time_save(&mut render, timer, config, opt_current_segment_index, index);
         if let Some(current_segment_index) = opt_current_segment_index {
             if current_segment_index > index {
-                self.compute_passed_segment(
+                Self::render_passed_segment(
+                    &mut render,
                     timer,
                     config,
-                    segment,
+                    index,
                     segment_comparison_time,
-                    previous_split_time,
-                    segment_comparison_duration,
-                    gold_duration,
+                    classes,
                 );
             }
 
             if current_segment_index == index {
-                self.compute_current_segment(
+                Self::render_current_segment(
+                    &mut render,
                     timer,
                     config,
                     index,
@@ -536,58 +1414,74 @@ impl SegmentSuffix {
                 );
             }
         }
+        render
     }
 
-    #[allow(clippy::too_many_arguments)]
-    fn compute_passed_segment(
+    /// Shows the segment's time save in the optional column, and what could
+    /// be saved on upcoming segments in the tooltip.
+    fn render_time_save(
         &self,
+        render: &mut SuffixRender,
         timer: &Timer,
         config: &Config,
-        segment: &livesplit_core::Segment,
+        opt_current_segment_index: Option<usize>,
+        index: usize,
+    ) {
+        let time_save = segment_time_save(timer, config, opt_current_segment_index, index);
+        let text = time_save.map(|time_save| format_time_save(time_save, config));
+
+        render.tooltip = match (time_save, &text) {
+            (Some(TimeSave::Possible(_)), Some(text)) => {
+                Some(format!("Possible time save: {text}"))
+            }
+            _ => None,
+        };
+        render.caption_visible = config.layout.show_timesave_column && text.is_some();
+        render.caption = text.unwrap_or_default();
+        if let Some(projection) = &self.projection {
+            projection.clone_into(&mut render.caption);
+            render.caption_visible = true;
+        }
+    }
+
+    /// The class of the active timing method goes on the delta, and the
+    /// tooltip tells when the other one disagrees on the gold.
+    fn render_passed_segment(
+        render: &mut SuffixRender,
+        timer: &Timer,
+        config: &Config,
+        index: usize,
         segment_comparison_time: time::Duration,
-        previous_split_time: time::Duration,
-        segment_comparison_duration: time::Duration,
-        gold_duration: time::Duration,
+        classes: Option<MethodClasses>,
     ) {
-        let split_time = segment_split_time(segment, timer);
+        let split_time = segment_split_time(timer, index);
 
         if split_time == time::Duration::ZERO {
-            self.comparison_label.set_label("--");
-            self.delta_label.set_label("");
+            render.comparison = "--".to_owned();
         } else {
             let diff = split_time
                 .checked_sub(segment_comparison_time)
                 .unwrap_or_default();
 
-            self.comparison_label.set_label(
-                config
-                    .format
-                    .segment
-                    .format_split_time(&segment.split_time(), timer.current_timing_method())
-                    .as_str(),
+            render.comparison = config.format.segment.format_split_time(
+                &attempt_split_time(timer, index),
+                timer.current_timing_method(),
             );
             if segment_comparison_time != time::Duration::ZERO {
-                self.delta_label
-                    .set_label(format_signed(diff, config).as_str());
-
-                let split_duration = split_time
-                    .checked_sub(previous_split_time)
-                    .unwrap_or_default();
-
-                self.delta_label.add_css_class(classify_split_label(
-                    segment_comparison_duration,
-                    split_duration,
-                    diff,
-                    gold_duration,
-                    false,
-                ));
+                render.delta = format_delta(diff, config, DeltaWidget::Splits);
+                render.delta_class = classes
+                    .and_then(|classes| classes.get(timer.current_timing_method()))
+                    .and_then(|class| color_class(config, class));
+                if let Some(note) = classes.and_then(MethodClasses::gold_note) {
+                    render.tooltip = Some(note.to_owned());
+                }
             }
         }
     }
 
     #[allow(clippy::too_many_arguments)]
-    fn compute_current_segment(
-        &self,
+    fn render_current_segment(
+        render: &mut SuffixRender,
         timer: &Timer,
         config: &Config,
         index: usize,
@@ -595,7 +1489,7 @@ impl SegmentSuffix {
         previous_split_time: time::Duration,
         gold_duration: time::Duration,
     ) {
-        let current_duration = current_attempt_running_duration(timer);
+        let current_duration = current_attempt_running_duration(timer, video_offset(config));
         let diff = current_duration
             .checked_sub(segment_comparison_time)
             .unwrap_or_default();
@@ -613,8 +1507,7 @@ impl SegmentSuffix {
             && (diff.is_positive()
                 || (gold_duration != time::Duration::ZERO && split_running_time >= gold_duration))
         {
-            self.delta_label
-                .set_label(format_signed(diff, config).as_str());
+            render.delta = format_delta(diff, config, DeltaWidget::Splits);
         }
     }
 }
@@ -676,4 +1569,594 @@ mod segment_row_ui_tests {
             "Expected current-segment class"
         );
     }
+
+    #[gtk4::test]
+    fn static_ticks_do_not_touch_the_widgets() {
+        gtk_test_init();
+
+        let mut run = livesplit_core::Run::new();
+        for (name, seconds) in [("Split A", 10.0), ("Split B", 25.0)] {
+            let mut segment = livesplit_core::Segment::new(name);
+            segment.set_personal_best_split_time(
+                livesplit_core::Time::new()
+                    .with_real_time(Some(livesplit_core::TimeSpan::from_seconds(seconds))),
+            );
+            run.push_segment(segment);
+        }
+        let mut timer = livesplit_core::Timer::new(run).expect("timer");
+        timer.start();
+        timer.split();
+        timer.pause();
+        let mut config = Config::default();
+
+        let segments = timer.run().segments();
+        let mut passed = SegmentRow::new(&timer, &config, Some(1), 0, &segments[0]);
+        let mut current = SegmentRow::new(&timer, &config, Some(1), 1, &segments[1]);
+        for _ in 0..5 {
+            assert_eq!(passed.refresh(&timer, &config, Some(1), 0, &segments[0]), 0);
+            assert_eq!(
+                current.refresh(&timer, &config, Some(1), 1, &segments[1]),
+                0
+            );
+        }
+
+        // A change is rendered once, then the row is quiet again
+        assert!(passed.refresh(&timer, &config, None, 0, &segments[0]) > 0);
+        assert_eq!(passed.refresh(&timer, &config, None, 0, &segments[0]), 0);
+
+        config.stats.show_projection = true;
+        current.show_projection(Some("proj: 15.00"));
+        assert_eq!(
+            current.suffix.time_save_label.label().as_str(),
+            "proj: 15.00"
+        );
+        current.show_projection(Some("proj: 15.00"));
+        assert_eq!(
+            current.refresh(&timer, &config, Some(1), 1, &segments[1]),
+            0
+        );
+    }
+
+    #[gtk4::test]
+    fn segment_row_applies_configured_override_class() {
+        gtk_test_init();
+
+        let mut run = livesplit_core::Run::new();
+        run.set_game_name("Game");
+        run.set_category_name("Any%");
+        run.push_segment(livesplit_core::Segment::new("Split A"));
+        run.push_segment(livesplit_core::Segment::new("Boss"));
+        let timer = livesplit_core::Timer::new(run).expect("timer");
+        let mut config = Config::default();
+        config
+            .style
+            .segment_classes
+            .insert("Boss".to_owned(), "boss-split".to_owned());
+        config
+            .style
+            .segment_classes
+            .insert("1".to_owned(), "by-index".to_owned());
+
+        let segments = timer.run().segments();
+        let plain = SegmentRow::new(&timer, &config, None, 0, &segments[0]);
+        let boss = SegmentRow::new(&timer, &config, None, 1, &segments[1]);
+
+        assert!(!plain.row().has_css_class("boss-split"));
+        assert!(boss.row().has_css_class("boss-split"));
+        assert!(
+            !boss.row().has_css_class("by-index"),
+            "Name keys take precedence over index keys"
+        );
+    }
+
+    #[gtk4::test]
+    fn reserved_width_lines_up_values_of_any_length() {
+        gtk_test_init();
+
+        let mut run = livesplit_core::Run::new();
+        for (name, seconds) in [("Short", 5.0), ("Long", 5025.67)] {
+            let mut segment = livesplit_core::Segment::new(name);
+            segment.set_personal_best_split_time(
+                livesplit_core::Time::new()
+                    .with_real_time(Some(livesplit_core::TimeSpan::from_seconds(seconds))),
+            );
+            run.push_segment(segment);
+        }
+        let timer = livesplit_core::Timer::new(run).expect("timer");
+        let width = |config: &Config, index: usize| {
+            let row = SegmentRow::new(&timer, config, None, index, &timer.run().segments()[index]);
+            let label = &row.suffix.comparison_label;
+            (label.measure(Orientation::Horizontal, -1).1, label.xalign())
+        };
+
+        let mut config = Config::default();
+        assert_ne!(width(&config, 0).0, width(&config, 1).0);
+
+        config.layout.value_min_width = 240;
+        let (short, short_align) = width(&config, 0);
+        let (long, long_align) = width(&config, 1);
+        assert_eq!(short, long);
+        assert!(short >= 240);
+        assert_eq!((short_align, long_align), (1.0, 1.0));
+    }
+
+    fn three_segment_timer() -> livesplit_core::Timer {
+        let mut run = livesplit_core::Run::new();
+        run.set_game_name("Game");
+        run.set_category_name("Any%");
+        for name in ["Split A", "Split B", "Split C"] {
+            run.push_segment(livesplit_core::Segment::new(name));
+        }
+        livesplit_core::Timer::new(run).expect("timer")
+    }
+
+    #[gtk4::test]
+    fn selection_survives_a_rebuild_when_not_ended() {
+        gtk_test_init();
+
+        let timer = three_segment_timer();
+        let config = Config::default();
+        let mut list = SegmentList::new(&timer, &config);
+
+        list.select_segment(1, false);
+        assert_eq!(list.selected_segment_index(), Some(1));
+        list.update(&timer, &config, true);
+        assert_eq!(list.selected_segment_index(), Some(1));
+
+        // The final split lives in its own list and is found there too
+        list.select_segment(2, false);
+        list.update(&timer, &config, true);
+        assert_eq!(list.selected_segment_index(), Some(2));
+    }
+
+    #[gtk4::test]
+    fn ending_selects_the_final_split() {
+        gtk_test_init();
+
+        let mut timer = three_segment_timer();
+        let config = Config::default();
+        let mut list = SegmentList::new(&timer, &config);
+        list.select_segment(0, false);
+
+        timer.start();
+        list.update(&timer, &config, false);
+        for _ in 0..3 {
+            timer.split();
+        }
+        list.update(&timer, &config, false);
+        assert_eq!(list.selected_segment_index(), Some(2));
+
+        // Browsing after the end is kept across rebuilds
+        list.select_segment(0, false);
+        list.update(&timer, &config, true);
+        assert_eq!(list.selected_segment_index(), Some(0));
+    }
+
+    /// Three segments with PB game times 10/20/30s, split once at 8s of game
+    /// time, so the first segment has a delta and the second is current.
+    fn split_once_on_game_time() -> livesplit_core::Timer {
+        use livesplit_core::{Time, TimeSpan, TimingMethod};
+
+        let mut run = livesplit_core::Run::new();
+        for (name, pb) in [("Split A", 10.0), ("Split B", 20.0), ("Split C", 30.0)] {
+            let mut segment = livesplit_core::Segment::new(name);
+            segment.set_personal_best_split_time(
+                Time::new().with_game_time(Some(TimeSpan::from_seconds(pb))),
+            );
+            run.push_segment(segment);
+        }
+        let mut timer = livesplit_core::Timer::new(run).expect("timer");
+        timer.set_current_timing_method(TimingMethod::GameTime);
+        timer.start();
+        timer.initialize_game_time();
+        timer.pause_game_time();
+        timer.set_game_time(TimeSpan::from_seconds(8.0));
+        timer.split();
+        timer
+    }
+
+    /// The row of segment `index`, which has to be in view.
+    fn placed(list: &SegmentList, index: usize) -> &SegmentRow {
+        list.rows[index].as_ref().expect("row in view")
+    }
+
+    fn title_at(list: &ListBox, position: i32) -> String {
+        list.row_at_index(position)
+            .and_downcast::<ActionRow>()
+            .expect("row")
+            .title()
+            .to_string()
+    }
+
+    #[gtk4::test]
+    fn reversed_rows_keep_their_segment_deltas_and_classes() {
+        gtk_test_init();
+
+        let timer = split_once_on_game_time();
+        let forward = SegmentList::new(&timer, &Config::default());
+        let mut config = Config::default();
+        config.style.reverse_segments = Some(true);
+        let reversed = SegmentList::new(&timer, &config);
+
+        assert_eq!(title_at(&reversed.list, 0), "Split B");
+        assert_eq!(title_at(&reversed.list, 1), "Split A");
+        assert_eq!(title_at(&reversed.last_segment_list, 0), "Split C");
+        // The final split is pinned above the others
+        assert_eq!(
+            reversed.container.first_child().as_ref(),
+            Some(reversed.last_segment_list.upcast_ref::<Widget>())
+        );
+
+        assert!(
+            !placed(&forward, 0).suffix.delta_label.label().is_empty(),
+            "Completed segment has a delta"
+        );
+        for (index, (ahead, behind)) in forward
+            .rows
+            .iter()
+            .flatten()
+            .zip(reversed.rows.iter().flatten())
+            .enumerate()
+        {
+            assert_eq!(
+                ahead.suffix.delta_label.label(),
+                behind.suffix.delta_label.label(),
+                "Delta of segment {index}"
+            );
+            assert_eq!(
+                ahead.suffix.delta_label.css_classes(),
+                behind.suffix.delta_label.css_classes(),
+                "Delta classes of segment {index}"
+            );
+            assert_eq!(
+                ahead.suffix.comparison_label.label(),
+                behind.suffix.comparison_label.label(),
+                "Comparison of segment {index}"
+            );
+            assert_eq!(
+                ahead.row().has_css_class("current-segment"),
+                index == 1,
+                "Current split of segment {index}"
+            );
+            assert_eq!(
+                behind.row().has_css_class("current-segment"),
+                index == 1,
+                "Current split of segment {index} when reversed"
+            );
+        }
+    }
+
+    #[gtk4::test]
+    fn splits_between_two_ticks_all_reach_their_rows() {
+        use livesplit_core::{Time, TimeSpan, TimingMethod};
+
+        gtk_test_init();
+
+        let mut run = livesplit_core::Run::new();
+        for (index, pb) in [10.0, 20.0, 30.0, 40.0, 50.0].into_iter().enumerate() {
+            let mut segment = livesplit_core::Segment::new(format!("Split {index}"));
+            segment.set_personal_best_split_time(
+                Time::new().with_game_time(Some(TimeSpan::from_seconds(pb))),
+            );
+            run.push_segment(segment);
+        }
+        let mut timer = livesplit_core::Timer::new(run).expect("timer");
+        timer.set_current_timing_method(TimingMethod::GameTime);
+        timer.start();
+        timer.initialize_game_time();
+        timer.pause_game_time();
+        let config = Config::default();
+        let mut list = SegmentList::new(&timer, &config);
+        list.update(&timer, &config, false);
+
+        // An auto splitter splitting three times before the next tick
+        for seconds in [8.0, 21.0, 29.0] {
+            timer.set_game_time(TimeSpan::from_seconds(seconds));
+            timer.split();
+        }
+        list.update(&timer, &config, false);
+
+        let expected = SegmentList::new(&timer, &config);
+        for (index, (row, fresh)) in list
+            .rows
+            .iter()
+            .flatten()
+            .zip(expected.rows.iter().flatten())
+            .enumerate()
+        {
+            assert_eq!(
+                row.suffix.delta_label.label(),
+                fresh.suffix.delta_label.label(),
+                "Delta of segment {index}"
+            );
+            assert_eq!(
+                row.suffix.delta_label.css_classes(),
+                fresh.suffix.delta_label.css_classes(),
+                "Delta classes of segment {index}"
+            );
+            assert_eq!(
+                row.suffix.comparison_label.label(),
+                fresh.suffix.comparison_label.label(),
+                "Comparison of segment {index}"
+            );
+            assert_eq!(
+                row.row().has_css_class("current-segment"),
+                index == 3,
+                "Current split of segment {index}"
+            );
+        }
+        for row in list.rows[..3].iter().flatten() {
+            assert!(!row.suffix.delta_label.label().is_empty());
+        }
+    }
+
+    #[gtk4::test]
+    fn delta_bars_show_on_completed_splits_when_enabled() {
+        gtk_test_init();
+
+        let timer = split_once_on_game_time();
+        let bar_width = |list: &SegmentList, index: usize| {
+            let slot = placed(list, index).delta_bar.container();
+            let bar = slot.first_child().expect("bar");
+            (slot.is_visible(), bar.is_visible(), bar.width_request())
+        };
+
+        let mut list = SegmentList::new(&timer, &Config::default());
+        assert_eq!(bar_width(&list, 0), (false, false, 0));
+
+        let mut config = Config::default();
+        config.layout.show_delta_bars = true;
+        list.update(&timer, &config, false);
+        assert_eq!(
+            bar_width(&list, 0),
+            (true, true, crate::ui::timer::delta_bars::BAR_WIDTH)
+        );
+        let bar = placed(&list, 0)
+            .delta_bar
+            .container()
+            .first_child()
+            .unwrap();
+        assert!(bar.has_css_class("greensplit") || bar.has_css_class("goldsplit"));
+        // Upcoming splits keep the space without a bar
+        assert_eq!(bar_width(&list, 1), (true, false, 0));
+        assert_eq!(bar_width(&list, 2), (true, false, 0));
+
+        list.update(&timer, &Config::default(), false);
+        assert!(!placed(&list, 0).delta_bar.container().is_visible());
+    }
+
+    #[gtk4::test]
+    fn reversed_selection_maps_back_to_segments() {
+        gtk_test_init();
+
+        let timer = three_segment_timer();
+        let mut config = Config::default();
+        config.style.reverse_segments = Some(true);
+        let mut list = SegmentList::new(&timer, &config);
+
+        list.select_segment(0, false);
+        assert_eq!(list.list.selected_row().map(|row| row.index()), Some(1));
+        assert_eq!(list.selected_segment_index(), Some(0));
+
+        // Turning it off again rebuilds in segment order
+        list.update(&timer, &Config::default(), false);
+        assert_eq!(title_at(&list.list, 0), "Split A");
+        assert_eq!(
+            list.container.first_child().as_ref(),
+            Some(list.scroller.upcast_ref::<Widget>())
+        );
+        assert_eq!(list.selected_segment_index(), Some(0));
+    }
+
+    #[gtk4::test]
+    fn huge_runs_only_compute_the_rows_in_view() {
+        gtk_test_init();
+
+        let mut run = livesplit_core::Run::new();
+        for index in 0..1000 {
+            run.push_segment(livesplit_core::Segment::new(format!("Split {index}")));
+        }
+        let mut timer = livesplit_core::Timer::new(run).expect("timer");
+        let config = Config::default();
+        let computed = |list: &SegmentList| {
+            list.rows
+                .iter()
+                .flatten()
+                .filter(|row| row.suffix.rendered.is_some())
+                .count()
+        };
+
+        // Two pages from the top, and the final split pinned under them
+        let mut list = SegmentList::new(&timer, &config);
+        assert_eq!(computed(&list), 21);
+        // Only those have widgets
+        assert_eq!(list.rows.iter().flatten().count(), 21);
+        assert_eq!(list.list.observe_children().n_items(), 20);
+        assert!(placed(&list, 999).suffix.rendered.is_some());
+
+        timer.start();
+        timer.split();
+        list.update(&timer, &config, false);
+        let _ = timer.set_current_comparison(livesplit_core::comparison::best_segments::NAME);
+        list.update(&timer, &config, false);
+        assert_eq!(computed(&list), 21);
+        assert!(placed(&list, 1).row().has_css_class("current-segment"));
+    }
+
+    #[gtk4::test]
+    fn danger_markers_follow_the_resets() {
+        gtk_test_init();
+
+        let mut run = livesplit_core::Run::new();
+        for name in ["Split 1", "Split 2", "Split 3"] {
+            run.push_segment(livesplit_core::Segment::new(name));
+        }
+        let mut timer = livesplit_core::Timer::new(run).expect("timer");
+        let mut config = Config::default();
+        config.stats.show_danger_markers = true;
+        let reset_on = |timer: &mut Timer, segment| {
+            timer.start();
+            for _ in 0..segment {
+                timer.split();
+            }
+            timer.reset(true);
+        };
+        for segment in [0, 0, 1, 1, 1, 1, 1, 1, 1, 1] {
+            reset_on(&mut timer, segment);
+        }
+        let marked = |list: &SegmentList| -> Vec<Option<String>> {
+            list.rows
+                .iter()
+                .flatten()
+                .map(|row| {
+                    row.danger
+                        .is_visible()
+                        .then(|| row.danger.tooltip_text().unwrap_or_default().to_string())
+                })
+                .collect()
+        };
+
+        let mut list = SegmentList::new(&timer, &config);
+        list.update(&timer, &config, false);
+        let second = Some("80% of resets happen here".to_owned());
+        assert_eq!(marked(&list), [None, second, None]);
+
+        // 3 of 11 now, over the threshold
+        reset_on(&mut timer, 0);
+        list.update(&timer, &config, false);
+        assert_eq!(
+            marked(&list)[0].as_deref(),
+            Some("27% of resets happen here")
+        );
+
+        config.stats.show_danger_markers = false;
+        list.update(&timer, &config, false);
+        assert_eq!(marked(&list), [None, None, None]);
+    }
+}
+
+#[cfg(test)]
+mod list_index_tests {
+    use super::*;
+
+    #[test]
+    fn forward_positions_are_segment_indices() {
+        assert_eq!(list_index(0, 4, false), 0);
+        assert_eq!(list_index(2, 4, false), 2);
+    }
+
+    #[test]
+    fn reversed_positions_run_from_the_second_to_last_segment() {
+        // Segments 0..=2 are scrolled, segment 3 is pinned
+        assert_eq!(list_index(0, 4, true), 2);
+        assert_eq!(list_index(2, 4, true), 0);
+        for index in 0..3 {
+            assert_eq!(list_index(list_index(index, 4, true), 4, true), index);
+        }
+        assert_eq!(list_index(0, 1, true), 0);
+    }
+}
+
+#[cfg(test)]
+mod projection_tests {
+    use super::*;
+
+    #[test]
+    fn projection_is_recomputed_once_a_second() {
+        let start = Instant::now();
+        let mut projection = SegmentProjection::default();
+        let mut computed = 0;
+        let mut tick = |projection: &mut SegmentProjection, at: Duration, index: usize| {
+            projection
+                .update(start + at, index, || {
+                    computed += 1;
+                    Some(format!("proj {computed}"))
+                })
+                .map(str::to_owned)
+        };
+
+        assert_eq!(
+            tick(&mut projection, Duration::ZERO, 0).as_deref(),
+            Some("proj 1")
+        );
+        // Every tick in between reuses it
+        for ms in [16, 500, 999] {
+            let text = tick(&mut projection, Duration::from_millis(ms), 0);
+            assert_eq!(text.as_deref(), Some("proj 1"));
+        }
+        assert_eq!(
+            tick(&mut projection, Duration::from_secs(1), 0).as_deref(),
+            Some("proj 2")
+        );
+        // Splitting moves on to the next segment right away
+        let next = tick(&mut projection, Duration::from_millis(1_100), 1);
+        assert_eq!(next.as_deref(), Some("proj 3"));
+    }
+
+    #[test]
+    fn clearing_forgets_the_projection() {
+        let start = Instant::now();
+        let mut projection = SegmentProjection::default();
+        projection.update(start, 0, || Some("proj".to_owned()));
+        projection.clear();
+        assert!(projection.is_due(start, 0));
+        assert_eq!(projection.update(start, 0, || None), None);
+    }
+}
+
+#[cfg(test)]
+mod selection_tests {
+    use super::*;
+
+    #[test]
+    fn previous_selection_is_kept() {
+        assert_eq!(
+            selection_after_update(Some(1), 3, TimerPhase::NotRunning, false),
+            Some(1)
+        );
+        assert_eq!(
+            selection_after_update(None, 3, TimerPhase::NotRunning, false),
+            None
+        );
+    }
+
+    #[test]
+    fn removed_segments_drop_the_selection() {
+        assert_eq!(
+            selection_after_update(Some(4), 3, TimerPhase::NotRunning, false),
+            None
+        );
+        assert_eq!(
+            selection_after_update(Some(0), 0, TimerPhase::NotRunning, false),
+            None
+        );
+    }
+
+    #[test]
+    fn ending_selects_the_last_segment() {
+        assert_eq!(
+            selection_after_update(Some(0), 3, TimerPhase::Ended, true),
+            Some(2)
+        );
+        assert_eq!(
+            selection_after_update(None, 3, TimerPhase::Ended, false),
+            Some(2)
+        );
+        assert_eq!(
+            selection_after_update(Some(0), 3, TimerPhase::Ended, false),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn other_phase_changes_clear_the_selection() {
+        assert_eq!(
+            selection_after_update(Some(1), 3, TimerPhase::NotRunning, true),
+            None
+        );
+        assert_eq!(
+            selection_after_update(Some(1), 3, TimerPhase::Running, true),
+            None
+        );
+    }
 }
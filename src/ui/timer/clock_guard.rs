@@ -0,0 +1,223 @@
+//! Keeps clock glitches off the display.
+//!
+//! Around a system sleep and resume, the attempt duration read by one tick
+//! can be wildly off the one before it, then settle again. While running, a
+//! tick whose attempt duration strays from the previous one by more than
+//! [`JUMP_TOLERANCE`] beyond the time that passed between them is not drawn,
+//! so the display holds its last frame. A jump that lasts for
+//! [`PERSISTENT_TICKS`] ticks is taken as the timer's actual time and drawn.
+//!
+//! Starting, resetting or pausing an attempt moves the duration on purpose,
+//! so the comparison starts over with each phase or attempt change.
+
+use std::time::Instant;
+
+use livesplit_core::{Timer, TimerPhase};
+
+/// How far the attempt duration may stray from the time between two ticks.
+pub const JUMP_TOLERANCE: time::Duration = time::Duration::seconds(2);
+/// Ticks a jump has to last for before it's drawn, about half a second.
+pub const PERSISTENT_TICKS: u32 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockCheck {
+    Draw,
+    /// The attempt duration strayed by `jump`, keep the previous frame.
+    /// `first` on the first tick of the jump.
+    Skip {
+        jump: time::Duration,
+        first: bool,
+    },
+    /// A jump of `jump` lasted, it's drawn from now on.
+    Accept {
+        jump: time::Duration,
+    },
+}
+
+/// The last attempt duration drawn, read at `at`.
+#[derive(Debug, Clone, Copy)]
+struct Reading {
+    attempt: u32,
+    duration: time::Duration,
+    at: Instant,
+}
+
+#[derive(Debug, Default)]
+pub struct ClockJumpGuard {
+    last: Option<Reading>,
+    skipped: u32,
+}
+
+impl ClockJumpGuard {
+    /// Looks at `timer`'s attempt duration at time `now`.
+    pub fn check(&mut self, timer: &Timer, now: Instant) -> ClockCheck {
+        self.observe(
+            timer.current_phase(),
+            timer.run().attempt_count(),
+            timer.current_attempt_duration().to_duration(),
+            now,
+        )
+    }
+
+    pub fn observe(
+        &mut self,
+        phase: TimerPhase,
+        attempt: u32,
+        duration: time::Duration,
+        now: Instant,
+    ) -> ClockCheck {
+        let reading = Reading {
+            attempt,
+            duration,
+            at: now,
+        };
+        let previous = match self.last {
+            Some(last) if phase.is_running() && last.attempt == attempt => last,
+            _ => {
+                // Not running, or a new attempt: nothing to compare against
+                self.last = phase.is_running().then_some(reading);
+                self.skipped = 0;
+                return ClockCheck::Draw;
+            }
+        };
+
+        let passed = time::Duration::try_from(now.saturating_duration_since(previous.at))
+            .unwrap_or(time::Duration::MAX);
+        let jump = duration - previous.duration - passed;
+        if jump.abs() <= JUMP_TOLERANCE {
+            self.last = Some(reading);
+            self.skipped = 0;
+            return ClockCheck::Draw;
+        }
+
+        self.skipped += 1;
+        if self.skipped >= PERSISTENT_TICKS {
+            self.last = Some(reading);
+            self.skipped = 0;
+            return ClockCheck::Accept { jump };
+        }
+        ClockCheck::Skip {
+            jump,
+            first: self.skipped == 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod clock_guard_tests {
+    use super::*;
+    use std::time::Duration;
+
+    const TICK: Duration = Duration::from_millis(16);
+
+    /// Runs `durations` through the guard, one tick apart, and returns the
+    /// attempt duration on screen after each of them.
+    fn shown(durations: &[time::Duration]) -> Vec<time::Duration> {
+        let mut guard = ClockJumpGuard::default();
+        let start = Instant::now();
+        let mut on_screen = time::Duration::ZERO;
+        durations
+            .iter()
+            .enumerate()
+            .map(|(index, duration)| {
+                let now = start + TICK * index as u32;
+                if !matches!(
+                    guard.observe(TimerPhase::Running, 1, *duration, now),
+                    ClockCheck::Skip { .. }
+                ) {
+                    on_screen = *duration;
+                }
+                on_screen
+            })
+            .collect()
+    }
+
+    fn ticks(from: usize, count: usize) -> impl Iterator<Item = time::Duration> {
+        (from..from + count).map(|index| time::Duration::milliseconds(16 * index as i64))
+    }
+
+    #[test]
+    fn a_momentary_jump_is_never_drawn() {
+        let bogus = time::Duration::hours(5000);
+        let mut durations: Vec<_> = ticks(0, 10).collect();
+        durations.push(bogus);
+        durations.push(-bogus);
+        durations.extend(ticks(12, 10));
+
+        let shown = shown(&durations);
+        assert!(!shown.contains(&bogus) && !shown.contains(&-bogus));
+        // The frame before the jump stays up, then the display carries on
+        assert_eq!(shown[10], durations[9]);
+        assert_eq!(shown[11], durations[9]);
+        assert_eq!(shown[12], durations[12]);
+        assert_eq!(shown.last(), durations.last());
+    }
+
+    #[test]
+    fn a_lasting_jump_is_drawn_after_a_while() {
+        let mut guard = ClockJumpGuard::default();
+        let start = Instant::now();
+        let at = |index: u32| start + TICK * index;
+        let minute = time::Duration::minutes(1);
+        assert_eq!(
+            guard.observe(TimerPhase::Running, 1, minute, at(0)),
+            ClockCheck::Draw
+        );
+
+        let resumed = minute + time::Duration::hours(1);
+        let mut checks = (1..=PERSISTENT_TICKS)
+            .map(|index| guard.observe(TimerPhase::Running, 1, resumed, at(index)));
+        assert!(matches!(
+            checks.next(),
+            Some(ClockCheck::Skip { first: true, .. })
+        ));
+        let checks: Vec<_> = checks.collect();
+        assert!(matches!(
+            checks.last(),
+            Some(ClockCheck::Accept { jump }) if *jump > time::Duration::minutes(59)
+        ));
+        let index = PERSISTENT_TICKS + 1;
+        assert_eq!(
+            guard.observe(
+                TimerPhase::Running,
+                1,
+                resumed + time::Duration::milliseconds(16),
+                at(index)
+            ),
+            ClockCheck::Draw
+        );
+    }
+
+    #[test]
+    fn phase_and_attempt_changes_are_not_jumps() {
+        let mut guard = ClockJumpGuard::default();
+        let start = Instant::now();
+        let long = time::Duration::hours(2);
+        let mut check = |phase, attempt, duration, tick: u32| {
+            guard.observe(phase, attempt, duration, start + TICK * tick)
+        };
+        assert_eq!(check(TimerPhase::Running, 1, long, 0), ClockCheck::Draw);
+        // Reset and started again within a tick
+        assert_eq!(
+            check(TimerPhase::Running, 2, time::Duration::ZERO, 1),
+            ClockCheck::Draw
+        );
+        assert_eq!(check(TimerPhase::Paused, 2, long, 2), ClockCheck::Draw);
+        assert_eq!(check(TimerPhase::Ended, 2, long, 3), ClockCheck::Draw);
+        // A slow tick is no jump either, both clocks moved on
+        let late = Duration::from_secs(10);
+        assert_eq!(
+            guard.observe(TimerPhase::Running, 3, long, start),
+            ClockCheck::Draw
+        );
+        assert_eq!(
+            guard.observe(
+                TimerPhase::Running,
+                3,
+                long + time::Duration::seconds(10),
+                start + late
+            ),
+            ClockCheck::Draw
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! Thin colored bars next to the split deltas, with `layout.show-delta-bars`.
+//!
+//! The bar of a completed split is as long as its delta relative to the
+//! largest delta of the attempt so far, and has the color of the delta
+//! label. Splits without a delta, skipped ones and splits right on the
+//! comparison have no bar, but keep the space so the columns line up.
+
+use gtk4::prelude::*;
+use gtk4::{Align, Box as GtkBox};
+
+/// Width of the longest bar.
+pub const BAR_WIDTH: i32 = 48;
+/// Shortest bar drawn, so a small delta beside a large one still shows.
+const MIN_BAR_WIDTH: i32 = 2;
+const BAR_HEIGHT: i32 = 4;
+
+/// The width of the bar of each delta, the largest absolute delta taking
+/// [`BAR_WIDTH`]. 0 is no bar.
+pub fn bar_widths(deltas: &[Option<time::Duration>]) -> Vec<i32> {
+    let largest = deltas
+        .iter()
+        .flatten()
+        .map(|delta| delta.unsigned_abs())
+        .max()
+        .unwrap_or_default();
+    deltas
+        .iter()
+        .map(|delta| match delta {
+            Some(delta) if !delta.is_zero() => {
+                let fraction = delta.unsigned_abs().as_secs_f64() / largest.as_secs_f64();
+                ((fraction * f64::from(BAR_WIDTH)).round() as i32).clamp(MIN_BAR_WIDTH, BAR_WIDTH)
+            }
+            _ => 0,
+        })
+        .collect()
+}
+
+/// A split row's bar, in a slot of [`BAR_WIDTH`].
+pub struct DeltaBar {
+    slot: GtkBox,
+    bar: GtkBox,
+    /// Width and color class last shown, the slot's visibility aside.
+    rendered: Option<(i32, Option<&'static str>)>,
+}
+
+impl DeltaBar {
+    pub fn new() -> Self {
+        let slot = GtkBox::builder()
+            .width_request(BAR_WIDTH)
+            .valign(Align::Center)
+            .visible(false)
+            .build();
+        let bar = GtkBox::builder()
+            .halign(Align::End)
+            .hexpand(true)
+            .height_request(BAR_HEIGHT)
+            .css_classes(["delta-bar"])
+            .visible(false)
+            .build();
+        slot.append(&bar);
+        Self {
+            slot,
+            bar,
+            rendered: None,
+        }
+    }
+
+    pub fn container(&self) -> &GtkBox {
+        &self.slot
+    }
+
+    /// Shows or hides the slot, for when the option is toggled.
+    pub fn set_enabled(&self, enabled: bool) {
+        if self.slot.is_visible() != enabled {
+            self.slot.set_visible(enabled);
+        }
+    }
+
+    /// Shows a bar `width` wide in the color of `class`, none for 0.
+    pub fn show(&mut self, width: i32, class: Option<&'static str>) {
+        if self.rendered == Some((width, class)) {
+            return;
+        }
+        if let Some((_, Some(previous))) = self.rendered {
+            self.bar.remove_css_class(previous);
+        }
+        if let Some(class) = class {
+            self.bar.add_css_class(class);
+        }
+        self.bar.set_width_request(width);
+        self.bar.set_visible(width > 0);
+        self.rendered = Some((width, class));
+    }
+}
+
+#[cfg(test)]
+mod delta_bar_tests {
+    use super::*;
+
+    fn ms(n: i64) -> Option<time::Duration> {
+        Some(time::Duration::milliseconds(n))
+    }
+
+    #[test]
+    fn bars_scale_to_the_largest_delta_whatever_its_sign() {
+        assert_eq!(
+            bar_widths(&[ms(-4_000), ms(2_000), ms(1_000)]),
+            vec![BAR_WIDTH, BAR_WIDTH / 2, BAR_WIDTH / 4]
+        );
+    }
+
+    #[test]
+    fn zero_and_absent_deltas_have_no_bar() {
+        assert_eq!(
+            bar_widths(&[None, ms(0), ms(500), None]),
+            vec![0, 0, BAR_WIDTH, 0]
+        );
+        assert_eq!(bar_widths(&[ms(0)]), vec![0]);
+        assert!(bar_widths(&[]).is_empty());
+    }
+
+    #[test]
+    fn tiny_deltas_still_show() {
+        assert_eq!(
+            bar_widths(&[ms(60_000), ms(10)]),
+            vec![BAR_WIDTH, MIN_BAR_WIDTH]
+        );
+    }
+}
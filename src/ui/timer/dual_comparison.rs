@@ -0,0 +1,413 @@
+//! `layout.mode: dual-comparison`: the split rows show the current
+//! comparison and `layout.secondary-comparison` side by side, then this
+//! attempt's time.
+//!
+//! The delta and its color stay against the current comparison, the
+//! primary one. A dot after the time tells whether the split also beat the
+//! secondary comparison. Splits without the secondary comparison fall back
+//! to the single comparison layout, see [`resolve_secondary`].
+
+use gtk4::prelude::*;
+use gtk4::{Align, Box as GtkBox, Label, Orientation};
+use livesplit_core::{Timer, TimerPhase};
+
+use crate::config::{Config, LayoutMode, LayoutOptions};
+use crate::utils::comparisons::{
+    attempt_split_time, current_attempt_running_duration, segment_split_time,
+};
+use crate::utils::video_offset::video_offset;
+
+/// Width of each time column.
+pub const COLUMN_WIDTH: i32 = 90;
+/// How much wider the timer gets to fit the extra columns.
+pub const EXTRA_WIDTH: i32 = 2 * COLUMN_WIDTH + 20;
+
+/// The secondary comparison to show, `None` in the single comparison
+/// layout. Errors tell why the dual layout can't be used with these splits.
+pub fn resolve_secondary(timer: &Timer, layout: &LayoutOptions) -> Result<Option<String>, String> {
+    if layout.mode != LayoutMode::DualComparison {
+        return Ok(None);
+    }
+    let Some(name) = layout.secondary_comparison.as_deref() else {
+        return Err("No secondary comparison is set".to_owned());
+    };
+    if !timer
+        .run()
+        .comparisons()
+        .any(|comparison| comparison == name)
+    {
+        return Err(format!("The splits have no “{name}” comparison"));
+    }
+    Ok(Some(name.to_owned()))
+}
+
+/// What the extra columns of a row show.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DualRowData {
+    pub primary: String,
+    pub secondary: String,
+    /// This attempt's split time, the running time on the current segment,
+    /// empty on upcoming ones.
+    pub actual: String,
+    /// Whether the split beat the secondary comparison, once it's done.
+    pub beats_secondary: Option<bool>,
+    /// `style.monochrome`: the dot is hollow rather than red when the split
+    /// didn't beat the secondary comparison.
+    pub monochrome: bool,
+}
+
+pub fn dual_row_data(timer: &Timer, config: &Config, secondary: &str, index: usize) -> DualRowData {
+    let method = timer.current_timing_method();
+    let format = &config.format.segment;
+    let Some(segment) = timer.run().segments().get(index) else {
+        return DualRowData::default();
+    };
+    let mut data = DualRowData {
+        primary: format.format_split_time(&segment.comparison(timer.current_comparison()), method),
+        secondary: format.format_split_time(&segment.comparison(secondary), method),
+        monochrome: config.style.monochrome.unwrap_or(false),
+        ..DualRowData::default()
+    };
+
+    let phase = timer.current_phase();
+    let current = match phase {
+        TimerPhase::NotRunning => return data,
+        TimerPhase::Ended => timer.run().len(),
+        _ => timer.current_split_index().unwrap_or_default(),
+    };
+    if index == current {
+        data.actual = format.format_duration(&current_attempt_running_duration(
+            timer,
+            video_offset(config),
+        ));
+    } else if index < current {
+        let split_time = segment_split_time(timer, index);
+        if split_time.is_zero() {
+            data.actual = "--".to_owned();
+        } else {
+            data.actual = format.format_split_time(&attempt_split_time(timer, index), method);
+            data.beats_secondary =
+                segment.comparison(secondary)[method].map(|time| split_time < time.to_duration());
+        }
+    }
+    data
+}
+
+/// The extra columns on a row, hidden in the single comparison layout.
+pub struct DualColumns {
+    container: GtkBox,
+    primary: Label,
+    secondary: Label,
+    actual: Label,
+    indicator: Label,
+    rendered: Option<DualRowData>,
+}
+
+impl DualColumns {
+    pub fn new() -> Self {
+        let container = GtkBox::builder()
+            .orientation(Orientation::Horizontal)
+            .visible(false)
+            .build();
+        let column = |classes: &[&str]| {
+            let label = Label::builder()
+                .width_request(COLUMN_WIDTH)
+                .xalign(1.0)
+                .valign(Align::Center)
+                .build();
+            for class in classes {
+                label.add_css_class(class);
+            }
+            container.append(&label);
+            label
+        };
+        let primary = column(&["timer", "monospace", "comparison"]);
+        let secondary = column(&["timer", "monospace", "comparison", "dim-label"]);
+        let actual = column(&["timer", "monospace"]);
+        let indicator = Label::builder()
+            .label("●")
+            .width_request(16)
+            .valign(Align::Center)
+            .css_classes(["caption"])
+            .build();
+        container.append(&indicator);
+        Self {
+            container,
+            primary,
+            secondary,
+            actual,
+            indicator,
+            rendered: None,
+        }
+    }
+
+    pub fn container(&self) -> &GtkBox {
+        &self.container
+    }
+
+    /// Shows `data`, or hides the columns with `None`.
+    pub fn show(&mut self, data: Option<DualRowData>) {
+        self.container.set_visible(data.is_some());
+        let Some(data) = data else {
+            self.rendered = None;
+            return;
+        };
+        if self.rendered.as_ref() == Some(&data) {
+            return;
+        }
+        self.primary.set_label(&data.primary);
+        self.secondary.set_label(&data.secondary);
+        self.actual.set_label(&data.actual);
+        self.indicator
+            .set_opacity(if data.beats_secondary.is_some() {
+                1.0
+            } else {
+                0.0
+            });
+        self.indicator.remove_css_class("greensplit");
+        self.indicator.remove_css_class("redsplit");
+        let beaten = data.beats_secondary;
+        self.indicator
+            .set_label(if data.monochrome && beaten == Some(false) {
+                "○"
+            } else {
+                "●"
+            });
+        match beaten.filter(|_| !data.monochrome) {
+            Some(true) => self.indicator.add_css_class("greensplit"),
+            Some(false) => self.indicator.add_css_class("redsplit"),
+            None => {}
+        }
+        self.rendered = Some(data);
+    }
+}
+
+/// Column headers above the split list in the dual comparison layout.
+pub struct DualHeader {
+    container: GtkBox,
+    delta: Label,
+    primary: Label,
+    secondary: Label,
+}
+
+impl DualHeader {
+    pub fn new() -> Self {
+        let container = GtkBox::builder()
+            .orientation(Orientation::Horizontal)
+            .visible(false)
+            .margin_start(12)
+            .margin_end(12)
+            .css_classes(["dual-comparison-header"])
+            .build();
+        let heading = |text: &str, width: i32| {
+            let label = Label::builder()
+                .label(text)
+                .xalign(1.0)
+                .ellipsize(gtk4::pango::EllipsizeMode::End)
+                .css_classes(["caption-heading", "dim-label"])
+                .build();
+            label.set_width_request(width);
+            label
+        };
+        let segment = heading("Segment", -1);
+        segment.set_xalign(0.0);
+        segment.set_hexpand(true);
+        let delta = heading("Delta", -1);
+        delta.set_xalign(0.0);
+        let primary = heading("", COLUMN_WIDTH);
+        let secondary = heading("", COLUMN_WIDTH);
+        let actual = heading("Time", COLUMN_WIDTH);
+        container.append(&segment);
+        container.append(&delta);
+        container.append(&primary);
+        container.append(&secondary);
+        container.append(&actual);
+        // Room for the indicator dot
+        container.append(&heading("", 16));
+        Self {
+            container,
+            delta,
+            primary,
+            secondary,
+        }
+    }
+
+    pub fn container(&self) -> &GtkBox {
+        &self.container
+    }
+
+    /// Names the columns, or hides the header without a secondary
+    /// comparison. `delta_width` is how wide the rows' delta part is.
+    pub fn show(&self, primary: &str, secondary: Option<&str>, delta_width: i32) {
+        self.container.set_visible(secondary.is_some());
+        if let Some(secondary) = secondary {
+            self.delta.set_width_request(delta_width);
+            self.primary.set_label(primary);
+            self.secondary.set_label(secondary);
+        }
+    }
+}
+
+#[cfg(test)]
+mod dual_comparison_tests {
+    use super::*;
+    use crate::test_support::{both_times, run_on, split_at};
+    use livesplit_core::TimingMethod;
+    use livesplit_core::comparison::personal_best;
+
+    const FRIEND: &str = "Friend's PB";
+
+    /// PB splits at 10/20/30, the friend's at 12/18/33.
+    fn friend_timer() -> Timer {
+        let mut run = run_on(&["A", "B", "C"]);
+        run.add_custom_comparison(FRIEND).unwrap();
+        for (index, (pb, friend)) in [(10.0, 12.0), (20.0, 18.0), (30.0, 33.0)]
+            .into_iter()
+            .enumerate()
+        {
+            let segment = run.segment_mut(index);
+            segment.set_personal_best_split_time(both_times(pb));
+            *segment.comparison_mut(FRIEND) = both_times(friend);
+        }
+        Timer::new(run).expect("timer")
+    }
+
+    /// A started timer on game time, so the splits land on exact times.
+    fn game_time_timer() -> Timer {
+        let mut timer = friend_timer();
+        timer.set_current_timing_method(TimingMethod::GameTime);
+        timer.start();
+        timer.initialize_game_time();
+        timer.pause_game_time();
+        timer
+    }
+
+    fn dual_layout(secondary: Option<&str>) -> LayoutOptions {
+        LayoutOptions {
+            mode: LayoutMode::DualComparison,
+            secondary_comparison: secondary.map(str::to_owned),
+            ..LayoutOptions::default()
+        }
+    }
+
+    #[test]
+    fn single_layout_needs_no_secondary() {
+        let timer = friend_timer();
+        assert_eq!(
+            resolve_secondary(&timer, &LayoutOptions::default()),
+            Ok(None)
+        );
+        assert_eq!(
+            resolve_secondary(&timer, &dual_layout(Some(FRIEND))),
+            Ok(Some(FRIEND.to_owned()))
+        );
+    }
+
+    #[test]
+    fn missing_secondary_falls_back() {
+        let timer = friend_timer();
+        assert!(resolve_secondary(&timer, &dual_layout(None)).is_err());
+        assert!(resolve_secondary(&timer, &dual_layout(Some("Rival"))).is_err());
+        // Any of the run's comparisons can be the secondary one
+        assert!(resolve_secondary(&timer, &dual_layout(Some(personal_best::NAME))).is_ok());
+    }
+
+    #[test]
+    fn before_the_start_only_comparisons_are_shown() {
+        let timer = friend_timer();
+        let config = Config::default();
+        let row = dual_row_data(&timer, &config, FRIEND, 1);
+        let format = &config.format.segment;
+        assert_eq!(
+            row.primary,
+            format.format_split_time(&both_times(20.0), TimingMethod::RealTime)
+        );
+        assert_eq!(
+            row.secondary,
+            format.format_split_time(&both_times(18.0), TimingMethod::RealTime)
+        );
+        assert_eq!(row.actual, "");
+        assert_eq!(row.beats_secondary, None);
+    }
+
+    #[test]
+    fn running_rows_show_split_live_and_upcoming_values() {
+        let mut timer = game_time_timer();
+        split_at(&mut timer, 11.0);
+        let config = Config::default();
+        let format = &config.format.segment;
+
+        // Lost to the PB but beat the friend
+        let passed = dual_row_data(&timer, &config, FRIEND, 0);
+        assert_eq!(
+            passed.actual,
+            format.format_duration(&time::Duration::seconds(11))
+        );
+        assert_eq!(passed.beats_secondary, Some(true));
+
+        let current = dual_row_data(&timer, &config, FRIEND, 1);
+        assert_eq!(
+            current.actual,
+            format.format_duration(&current_attempt_running_duration(
+                &timer,
+                video_offset(&config)
+            ))
+        );
+        assert_eq!(current.beats_secondary, None);
+
+        let upcoming = dual_row_data(&timer, &config, FRIEND, 2);
+        assert_eq!(upcoming.actual, "");
+        assert_eq!(upcoming.beats_secondary, None);
+    }
+
+    #[test]
+    fn paused_rows_keep_their_values() {
+        let mut timer = game_time_timer();
+        split_at(&mut timer, 11.0);
+        let config = Config::default();
+        let running = dual_row_data(&timer, &config, FRIEND, 0);
+        timer.pause();
+        assert_eq!(timer.current_phase(), TimerPhase::Paused);
+        assert_eq!(dual_row_data(&timer, &config, FRIEND, 0), running);
+        assert_eq!(dual_row_data(&timer, &config, FRIEND, 2).actual, "");
+    }
+
+    #[test]
+    fn ended_rows_all_compare_against_the_secondary() {
+        let mut timer = game_time_timer();
+        split_at(&mut timer, 11.0);
+        timer.skip_split();
+        split_at(&mut timer, 32.0);
+        assert_eq!(timer.current_phase(), TimerPhase::Ended);
+        let config = Config::default();
+
+        assert_eq!(
+            dual_row_data(&timer, &config, FRIEND, 0).beats_secondary,
+            Some(true)
+        );
+        let skipped = dual_row_data(&timer, &config, FRIEND, 1);
+        assert_eq!(skipped.actual, "--");
+        assert_eq!(skipped.beats_secondary, None);
+        // 32 beats the friend's 33 even though the PB is 30
+        assert_eq!(
+            dual_row_data(&timer, &config, FRIEND, 2).beats_secondary,
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn slower_than_the_secondary_is_flagged() {
+        let mut timer = game_time_timer();
+        split_at(&mut timer, 9.0);
+        split_at(&mut timer, 19.0);
+        let config = Config::default();
+        assert_eq!(
+            dual_row_data(&timer, &config, FRIEND, 0).beats_secondary,
+            Some(true)
+        );
+        assert_eq!(
+            dual_row_data(&timer, &config, FRIEND, 1).beats_secondary,
+            Some(false)
+        );
+    }
+}
@@ -0,0 +1,233 @@
+//! Keeps the running timer from changing width every centisecond.
+//!
+//! Digits are requested as tabular through CSS, but not every font has them.
+//! At startup we measure the timer font and, if its digits still differ in
+//! width, force a monospace family on the timer labels. That family ships
+//! in the app's GResource, so it looks the same whatever fonts the system has.
+
+use std::cell::OnceCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use gtk4::{
+    CssProvider, Label, STYLE_PROVIDER_PRIORITY_APPLICATION, gdk::Display, gio, prelude::*,
+};
+use tracing::{info, warn};
+
+use crate::config::{Config, data_dir};
+
+/// Used when the timer font renders digits with different widths.
+const FALLBACK_TIMER_FONT: &str = "DejaVu Sans Mono";
+/// The fallback font in the GResource. Pango only loads fonts from files,
+/// so it's copied out next to the other data.
+const FALLBACK_FONT_RESOURCE: &str = "/com/tunixr/tuxsplit/fonts/DejaVuSansMono.ttf";
+const FALLBACK_FONT_NAME: &str = "DejaVuSansMono.ttf";
+/// Rounding to whole pixels can leave equal-width digits one pixel apart.
+const WIDTH_TOLERANCE_PX: i32 = 1;
+
+/// Whether two runs of the same number of digits ("111111" and "999999")
+/// measured this close mean the font has equal-width digits.
+pub fn digits_are_tabular(narrow_width: i32, wide_width: i32) -> bool {
+    (narrow_width - wide_width).abs() <= WIDTH_TOLERANCE_PX
+}
+
+/// Font family to force on the timer labels, if any. An explicit
+/// `theme.timer-font` always wins over the measurement.
+pub fn timer_font_override(configured: Option<&str>, tabular: bool) -> Option<&str> {
+    match configured.map(str::trim) {
+        Some(font) if !font.is_empty() => Some(font),
+        _ if tabular => None,
+        _ => Some(FALLBACK_TIMER_FONT),
+    }
+}
+
+thread_local! {
+    /// The timer font override. Its contents are replaced on every apply.
+    static PROVIDER: OnceCell<CssProvider> = const { OnceCell::new() };
+    static FALLBACK_REGISTERED: OnceCell<bool> = const { OnceCell::new() };
+}
+
+fn fallback_font_path() -> PathBuf {
+    data_dir()
+        .unwrap_or_else(|| std::env::temp_dir().join("tuxsplit"))
+        .join("fonts")
+        .join(FALLBACK_FONT_NAME)
+}
+
+/// Writes `contents` to `path` unless it already holds them.
+fn write_if_changed(path: &Path, contents: &[u8]) -> bool {
+    let unchanged = fs::metadata(path).is_ok_and(|meta| meta.len() == contents.len() as u64)
+        && fs::read(path).is_ok_and(|existing| existing == contents);
+    unchanged
+        || path
+            .parent()
+            .is_some_and(|dir| fs::create_dir_all(dir).is_ok())
+            && fs::write(path, contents).is_ok()
+}
+
+/// Copies the fallback font out of the GResource and hands it to Pango.
+fn register_fallback_font(label: &Label) -> bool {
+    let font =
+        match gio::resources_lookup_data(FALLBACK_FONT_RESOURCE, gio::ResourceLookupFlags::NONE) {
+            Ok(font) => font,
+            Err(err) => {
+                warn!("The fallback timer font is missing from the resources: {err}");
+                return false;
+            }
+        };
+    let path = fallback_font_path();
+    if !write_if_changed(&path, &font) {
+        warn!(
+            "Could not write the fallback timer font to {}",
+            path.display()
+        );
+        return false;
+    }
+    let Some(font_map) = label.pango_context().font_map() else {
+        return false;
+    };
+    if let Err(err) = font_map.add_font_file(&path) {
+        warn!(
+            "Could not load the fallback timer font from {}: {err}",
+            path.display()
+        );
+        return false;
+    }
+    // Fonts Pango already looked up are cached
+    font_map.changed();
+    true
+}
+
+fn measure_digit_widths() -> (i32, i32) {
+    let probe = Label::builder().css_classes(["timer", "bigtimer"]).build();
+    let width = |text: &str| probe.create_pango_layout(Some(text)).pixel_size().0;
+    (width("111111"), width("999999"))
+}
+
+fn timer_font_css(font: &str) -> String {
+    format!(
+        ".timer, .bigtimer {{ font-family: \"{}\"; }}",
+        font.replace('"', "\\\"")
+    )
+}
+
+/// The provider for the timer font override, installed on first use.
+fn timer_font_provider(display: &Display) -> CssProvider {
+    PROVIDER.with(|provider| {
+        provider
+            .get_or_init(|| {
+                let provider = CssProvider::new();
+                gtk4::style_context_add_provider_for_display(
+                    display,
+                    &provider,
+                    STYLE_PROVIDER_PRIORITY_APPLICATION + 1,
+                );
+                provider
+            })
+            .clone()
+    })
+}
+
+/// Measures the timer font and overrides it when needed, replacing the
+/// previous override. Must run after the application stylesheet is loaded.
+pub fn apply_timer_font(config: &Config) {
+    let Some(display) = Display::default() else {
+        return;
+    };
+    let provider = timer_font_provider(&display);
+    // Measure the stylesheet's font, not the previous override
+    provider.load_from_string("");
+
+    let (narrow, wide) = measure_digit_widths();
+    let tabular = digits_are_tabular(narrow, wide);
+
+    match timer_font_override(config.theme.timer_font.as_deref(), tabular) {
+        Some(font) => {
+            if font == FALLBACK_TIMER_FONT
+                && !FALLBACK_REGISTERED.with(|registered| {
+                    *registered.get_or_init(|| register_fallback_font(&Label::new(None)))
+                })
+            {
+                warn!("Timer font: the fallback font is unavailable, digits may shift");
+            }
+            info!("Timer font: {font} (default font digits measured {narrow}px / {wide}px)");
+            provider.load_from_string(&timer_font_css(font));
+        }
+        None => info!("Timer font: default, digits are tabular ({narrow}px)"),
+    }
+}
+
+#[cfg(test)]
+mod font_tests {
+    use super::*;
+
+    #[test]
+    fn equal_widths_are_tabular() {
+        assert!(digits_are_tabular(60, 60));
+        assert!(digits_are_tabular(60, 61));
+        assert!(digits_are_tabular(61, 60));
+    }
+
+    #[test]
+    fn different_widths_are_not_tabular() {
+        assert!(!digits_are_tabular(42, 60));
+        assert!(!digits_are_tabular(60, 58));
+    }
+
+    #[test]
+    fn tabular_font_is_kept() {
+        assert_eq!(timer_font_override(None, true), None);
+    }
+
+    #[test]
+    fn proportional_font_falls_back() {
+        assert_eq!(timer_font_override(None, false), Some(FALLBACK_TIMER_FONT));
+        assert_eq!(
+            timer_font_override(Some("  "), false),
+            Some(FALLBACK_TIMER_FONT)
+        );
+    }
+
+    #[test]
+    fn configured_font_always_wins() {
+        assert_eq!(timer_font_override(Some("Iosevka"), true), Some("Iosevka"));
+        assert_eq!(
+            timer_font_override(Some(" Iosevka "), false),
+            Some("Iosevka")
+        );
+    }
+
+    #[test]
+    fn fallback_font_is_bundled() {
+        let resources = include_str!("../../../data/tuxsplit.xml");
+        let file = FALLBACK_FONT_RESOURCE
+            .strip_prefix("/com/tunixr/tuxsplit/")
+            .unwrap();
+        assert!(resources.contains(&format!("<file>{file}</file>")));
+        assert!(fallback_font_path().ends_with("fonts/DejaVuSansMono.ttf"));
+    }
+
+    #[test]
+    fn the_font_is_only_written_when_changed() {
+        let path = std::env::temp_dir()
+            .join(format!("tuxsplit-font-{}", std::process::id()))
+            .join("fonts")
+            .join(FALLBACK_FONT_NAME);
+        assert!(write_if_changed(&path, b"font"));
+        let written = fs::metadata(&path).unwrap().modified().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert!(write_if_changed(&path, b"font"));
+        assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), written);
+        assert!(write_if_changed(&path, b"other"));
+        assert_eq!(fs::read(&path).unwrap(), b"other");
+        fs::remove_dir_all(path.parent().unwrap().parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn css_quotes_font_name() {
+        assert_eq!(
+            timer_font_css("Fira \"Mono\""),
+            ".timer, .bigtimer { font-family: \"Fira \\\"Mono\\\"\"; }"
+        );
+    }
+}
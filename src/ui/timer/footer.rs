@@ -1,10 +1,22 @@
-use crate::config::Config;
+use crate::config::{Config, SegmentInfoFollows};
 use crate::formatters::label::format_label;
+use crate::formatters::time::{TimeFormat, adaptive_decimal_places};
 use crate::ui::info::{
     ALL_ADDITIONAL_INFOS, AdditionalInfo, AdditionalInfoKind, BestPossibleTimeInfo,
-    CurrentPaceInfo, PbChanceInfo, PossibleTimeSaveInfo, PrevSegmentBestInfo, PrevSegmentDiffInfo,
-    TotalPlaytimeInfo,
+    CurrentPaceInfo, GoldPaceInfo, PbChanceInfo, PossibleTimeSaveInfo, PrevSegmentBestInfo,
+    PrevSegmentDiffInfo, RaceInfo, RemainingBestInfo, SegmentTimerInfo, TotalPlaytimeInfo,
 };
+use crate::ui::race::race_snapshot;
+use crate::ui::timer::body::{PlacedRows, list_index};
+use crate::ui::timer::split_button::SplitButton;
+use crate::ui::timer::split_name::SplitName;
+use crate::ui::timer::value_column::ValueColumn;
+use crate::utils::comparisons::{
+    attempt_segment_duration, comparison_line_duration, current_segment_elapsed,
+    current_segment_has_gold, pb_segment_duration, signed_segment_comparison_duration,
+    target_final_time, time_left_to_threshold,
+};
+use crate::utils::video_offset::video_offset;
 
 use glib;
 use gtk4::prelude::{BoxExt as _, WidgetExt as _, *};
@@ -12,14 +24,20 @@ use gtk4::{
     Align, Box as GtkBox, CenterBox, Label, ListBox, Orientation::Horizontal, Orientation::Vertical,
 };
 
+use livesplit_core::comparison::personal_best;
 use livesplit_core::{Timer, TimerPhase};
+use std::borrow::Cow;
 
 pub struct TimerFooter {
     container: GtkBox,
     timer_container: CenterBox,
     segment_comparison: SegmentComparison,
+    timer_column: GtkBox,
+    target_time: TargetTime,
+    split_name: SplitName,
     running_timer: RunningTimer,
     additional_info: AdditionalInfoFooter,
+    split_button: SplitButton,
 }
 
 impl TimerFooter {
@@ -28,6 +46,8 @@ impl TimerFooter {
         config: &Config,
         primary_list: &ListBox,
         last_segment_list: &ListBox,
+        placed_rows: &PlacedRows,
+        value_column: &ValueColumn,
     ) -> Self {
         let container = GtkBox::builder()
             .orientation(Vertical)
@@ -41,26 +61,50 @@ impl TimerFooter {
             .hexpand(true)
             .build();
 
-        let segment_comparison =
+        let mut segment_comparison =
             SegmentComparison::new(timer, config, primary_list, last_segment_list);
+        segment_comparison.follow_placed_rows(placed_rows);
         let running_timer = RunningTimer::new(timer, config);
+        let target_time = TargetTime::new(timer, config);
+        let mut split_name = SplitName::new();
+        split_name.update(timer, config, config.layout.show_current_split_name);
+        value_column.add(target_time.value());
+
+        let timer_column = GtkBox::builder()
+            .orientation(Vertical)
+            .halign(Align::End)
+            .build();
+        timer_column.append(target_time.container());
+        timer_column.append(split_name.container());
+        timer_column.append(running_timer.container());
 
         timer_container.set_start_widget(Some(segment_comparison.container()));
-        timer_container.set_end_widget(Some(running_timer.container()));
+        timer_container.set_end_widget(Some(&timer_column));
 
         let additional_info = AdditionalInfoFooter::new(timer, config);
+        for value in additional_info.values() {
+            value_column.add(&value);
+        }
 
         let separator = gtk4::Separator::builder().build();
         container.append(&timer_container);
         container.append(&separator);
         container.append(&additional_info.container());
 
+        let mut split_button = SplitButton::new();
+        split_button.update(timer, config);
+        container.append(split_button.widget());
+
         Self {
             container,
             timer_container,
             segment_comparison,
+            timer_column,
+            target_time,
+            split_name,
             running_timer,
             additional_info,
+            split_button,
         }
     }
 
@@ -70,13 +114,22 @@ impl TimerFooter {
 
     pub fn refresh(&mut self, timer: &Timer, config: &Config) {
         self.segment_comparison.update(timer, config);
+        self.target_time.update(timer, config);
+        self.split_name
+            .update(timer, config, config.layout.show_current_split_name);
         self.running_timer.update(timer, config);
         self.additional_info.update(timer, config);
+        self.split_button.update(timer, config);
 
         self.timer_container
             .set_start_widget(Some(self.segment_comparison.container()));
         self.timer_container
-            .set_end_widget(Some(self.running_timer.container()));
+            .set_end_widget(Some(&self.timer_column));
+    }
+
+    /// Sets the split name again on the next refresh, for edited splits.
+    pub fn invalidate_split_name(&mut self) {
+        self.split_name.invalidate();
     }
 }
 
@@ -94,6 +147,10 @@ impl AdditionalInfoFooter {
             Box::new(CurrentPaceInfo::new(timer, config)),
             Box::new(TotalPlaytimeInfo::new(timer, config)),
             Box::new(PbChanceInfo::new(timer, config)),
+            Box::new(GoldPaceInfo::new(timer, config)),
+            Box::new(SegmentTimerInfo::new(timer, config)),
+            Box::new(RemainingBestInfo::new(timer, config)),
+            Box::new(RaceInfo::new(timer, config)),
         ];
 
         // Initialize visibility based on config at creation time.
@@ -114,11 +171,25 @@ impl AdditionalInfoFooter {
                 AdditionalInfoKind::CurrentPace => vis_cfg.show_current_pace,
                 AdditionalInfoKind::TotalPlaytime => vis_cfg.show_total_playtime,
                 AdditionalInfoKind::PbChance => vis_cfg.show_pb_chance,
+                AdditionalInfoKind::GoldPace => {
+                    vis_cfg.show_gold_pace && current_segment_has_gold(timer, config)
+                }
+                AdditionalInfoKind::SegmentTimer => vis_cfg.show_segment_timer,
+                AdditionalInfoKind::RemainingBest => vis_cfg.show_remaining_best,
+                AdditionalInfoKind::Race => vis_cfg.show_race && race_snapshot().is_some(),
             };
             info.container().set_visible(visible);
         }
     }
 
+    /// The readouts' values, the end widget of each info.
+    pub fn values(&self) -> Vec<gtk4::Widget> {
+        self.additional_info
+            .iter()
+            .filter_map(|info| info.container().end_widget())
+            .collect()
+    }
+
     pub fn container(&self) -> GtkBox {
         let container = GtkBox::builder()
             .orientation(Vertical)
@@ -137,14 +208,24 @@ impl AdditionalInfoFooter {
 
 /// Left pane in the footer:
 /// - Best: <best split value>
-/// - <Comparison Label>: <per-segment comparison value>
+/// - <Comparison Label>: <per-segment comparison value>, or its split time
+///   with `layout.cumulative-segment-comparison`
+/// - PB Segment: <PB segment time>, with the breakdown enabled and another
+///   comparison selected
+/// - Current: <running time of the current segment>, when enabled. With the
+///   breakdown, this attempt's time for the selected segment instead
 pub struct SegmentComparison {
     wrapper: GtkBox,
     primary_list_ref: glib::WeakRef<ListBox>, // Weak ref to main segments list
     last_list_ref: glib::WeakRef<ListBox>,    // Weak ref to last-segment list
+    placed_rows: PlacedRows,
     best_value: Label,
+    pb_box: GtkBox,
+    pb_value: Label,
     comparison_label: Label,
     comparison_value: Label,
+    running_box: GtkBox,
+    running_value: Label,
 }
 
 impl SegmentComparison {
@@ -160,27 +241,42 @@ impl SegmentComparison {
         let vbox = GtkBox::builder().orientation(Vertical).build();
 
         let (best_box, best_value) = SegmentComparison::build_best();
+        let (pb_box, pb_value) = SegmentComparison::build_pb_segment();
 
         let (comparison_box, comparison_label, comparison_value) =
             SegmentComparison::build_comparison();
 
+        let (running_box, running_value) = SegmentComparison::build_running();
+
         vbox.append(&best_box);
         vbox.append(&comparison_box);
+        vbox.append(&pb_box);
+        vbox.append(&running_box);
         wrapper.append(&vbox);
 
         let mut this = Self {
             wrapper,
             primary_list_ref: glib::WeakRef::new(),
             last_list_ref: glib::WeakRef::new(),
+            placed_rows: PlacedRows::default(),
             best_value,
+            pb_box,
+            pb_value,
             comparison_label,
             comparison_value,
+            running_box,
+            running_value,
         };
         this.primary_list_ref.set(Some(primary_list));
         this.last_list_ref.set(Some(last_list));
         this.rebuild(timer, config);
         this
     }
+
+    /// Maps the rows of the primary list from the positions placed in it.
+    pub fn follow_placed_rows(&mut self, placed_rows: &PlacedRows) {
+        self.placed_rows = placed_rows.clone();
+    }
     pub fn container(&self) -> &GtkBox {
         &self.wrapper
     }
@@ -190,36 +286,17 @@ impl SegmentComparison {
     }
 
     fn rebuild(&mut self, timer: &Timer, config: &Config) {
-        // Compute which segment to display
         let segments = timer.run().segments();
-        let selected_index = if timer.current_phase().is_running() {
-            timer.current_split_index().unwrap_or(0)
-        } else {
-            let mut idx = self
-                .primary_list_ref
-                .upgrade()
-                .and_then(|l| l.selected_row())
-                .map(|row| row.index() as usize);
-            if idx.is_none()
-                && let Some(last_list) = self.last_list_ref.upgrade()
-                && last_list.selected_row().is_some()
-            {
-                idx = Some(segments.len().saturating_sub(1));
-            }
-            idx.unwrap_or(0)
-        }
-        .min(segments.len().saturating_sub(1));
-
-        let segment = &segments[selected_index];
+        let selected_index = info_segment_index(
+            config.layout.segment_info_follows,
+            timer.current_phase().is_running(),
+            timer.current_split_index(),
+            || self.selected_row_segment(segments.len(), config),
+            segments.len(),
+        );
 
-        // Previous segment's comparison time (under current timing method)
-        let previous_comparison_time = if selected_index > 0 {
-            segments[selected_index - 1]
-                .comparison_timing_method(timer.current_comparison(), timer.current_timing_method())
-                .unwrap_or_default()
-                .to_duration()
-        } else {
-            time::Duration::ZERO
+        let Some(segment) = segments.get(selected_index) else {
+            return;
         };
 
         // Build values
@@ -230,17 +307,21 @@ impl SegmentComparison {
 
         let comparison_label_text = format!("{}:", format_label(timer.current_comparison()));
 
-        let comparison_value_text = {
-            let segment_comparison_time = segment
-                .comparison_timing_method(timer.current_comparison(), timer.current_timing_method())
-                .unwrap_or_default()
-                .to_duration();
-
-            let per_segment_time = segment_comparison_time
-                .checked_sub(previous_comparison_time)
-                .unwrap_or_default()
-                .abs();
-            config.format.comparison.format_duration(&per_segment_time)
+        // "--" when the comparison has no time here (e.g. no completed run for PB)
+        let cumulative = config.layout.cumulative_segment_comparison;
+        let signed = (config.layout.signed_segment_comparison && !cumulative)
+            .then(|| signed_segment_comparison_duration(timer, selected_index))
+            .flatten();
+        let out_of_order = signed.is_some_and(|duration| duration.is_negative());
+        let comparison_value_text = match signed {
+            Some(duration) if out_of_order => format!(
+                "-{}",
+                config.format.comparison.format_duration(&duration.abs())
+            ),
+            _ => config
+                .format
+                .comparison
+                .format_duration_opt(comparison_line_duration(timer, selected_index, cumulative)),
         };
 
         // Update stored labels in place
@@ -253,6 +334,92 @@ impl SegmentComparison {
         if self.comparison_value.label().as_str() != comparison_value_text {
             self.comparison_value.set_label(&comparison_value_text);
         }
+        if self.comparison_value.has_css_class("warning") != out_of_order {
+            if out_of_order {
+                self.comparison_value.add_css_class("warning");
+                self.comparison_value.set_tooltip_text(Some(
+                    "The comparison splits earlier here than on the segment before",
+                ));
+            } else {
+                self.comparison_value.remove_css_class("warning");
+                self.comparison_value.set_tooltip_text(None);
+            }
+        }
+
+        let breakdown = config.style.show_segment_breakdown.unwrap_or(false);
+
+        // The comparison line already shows the PB when it's selected
+        let show_pb = breakdown && timer.current_comparison() != personal_best::NAME;
+        self.pb_box.set_visible(show_pb);
+        if show_pb {
+            let pb_value_text = config
+                .format
+                .comparison
+                .format_duration_opt(pb_segment_duration(timer, selected_index));
+            if self.pb_value.label().as_str() != pb_value_text {
+                self.pb_value.set_label(&pb_value_text);
+            }
+        }
+
+        if breakdown {
+            // Kept visible with "--" so the box doesn't change height mid-run
+            self.running_box.set_visible(true);
+            let current_text =
+                attempt_segment_duration(timer, selected_index, video_offset(config)).map_or_else(
+                    || "--".to_owned(),
+                    |time| config.format.segment.format_segment_time(&time),
+                );
+            self.running_value.set_label(&current_text);
+            return;
+        }
+
+        // Raw time in the running segment, next to the live delta on its row
+        let running_time = current_segment_elapsed(timer, video_offset(config))
+            .filter(|_| config.style.show_segment_running_time.unwrap_or(false));
+        self.running_box.set_visible(running_time.is_some());
+        if let Some(running_time) = running_time {
+            self.running_value
+                .set_label(&config.format.segment.format_segment_time(&running_time));
+        }
+    }
+
+    /// Segment of the selected row, in either list.
+    fn selected_row_segment(&self, segment_count: usize, config: &Config) -> Option<usize> {
+        if let Some(row) = self
+            .primary_list_ref
+            .upgrade()
+            .and_then(|list| list.selected_row())
+        {
+            let (first_placed, _, _) = self.placed_rows.get();
+            return Some(list_index(
+                first_placed + row.index() as usize,
+                segment_count,
+                config.style.reverse_segments.unwrap_or(false),
+            ));
+        }
+        self.last_list_ref
+            .upgrade()
+            .and_then(|list| list.selected_row())
+            .map(|_| segment_count.saturating_sub(1))
+    }
+
+    fn build_pb_segment() -> (GtkBox, Label) {
+        let pb_box = GtkBox::builder()
+            .orientation(Horizontal)
+            .spacing(2)
+            .halign(Align::Start)
+            .visible(false)
+            .build();
+        let pb_label = Label::builder().label("PB Segment:").build();
+        pb_label.add_css_class("caption-heading");
+
+        let pb_value = Label::builder().label("--").build();
+        pb_value.add_css_class("caption");
+        pb_value.add_css_class("timer");
+
+        pb_box.append(&pb_label);
+        pb_box.append(&pb_value);
+        (pb_box, pb_value)
     }
 
     fn build_comparison() -> (GtkBox, Label, Label) {
@@ -274,6 +441,25 @@ impl SegmentComparison {
         (comparison_box, comparison_label, comparison_value)
     }
 
+    fn build_running() -> (GtkBox, Label) {
+        let running_box = GtkBox::builder()
+            .orientation(Horizontal)
+            .spacing(2)
+            .halign(Align::Start)
+            .visible(false)
+            .build();
+        let running_label = Label::builder().label("Current:").build();
+        running_label.add_css_class("caption-heading");
+
+        let running_value = Label::builder().label("").build();
+        running_value.add_css_class("caption");
+        running_value.add_css_class("timer");
+
+        running_box.append(&running_label);
+        running_box.append(&running_value);
+        (running_box, running_value)
+    }
+
     fn build_best() -> (GtkBox, Label) {
         let best_box = GtkBox::builder()
             .orientation(Horizontal)
@@ -294,6 +480,71 @@ impl SegmentComparison {
     }
 }
 
+/// Above the running timer: the comparison's final time, the number to beat.
+/// Hidden when disabled or when the comparison has no final time.
+pub struct TargetTime {
+    wrapper: GtkBox,
+    label: Label,
+    value: Label,
+}
+
+impl TargetTime {
+    pub fn new(timer: &Timer, config: &Config) -> Self {
+        let wrapper = GtkBox::builder()
+            .orientation(Horizontal)
+            .spacing(4)
+            .halign(Align::End)
+            .build();
+
+        let label = Label::builder().label("PB:").build();
+        label.add_css_class("heading");
+        label.add_css_class("dim-label");
+
+        let value = Label::builder().label("").build();
+        value.add_css_class("title-4");
+        value.add_css_class("timer");
+
+        wrapper.append(&label);
+        wrapper.append(&value);
+
+        let this = Self {
+            wrapper,
+            label,
+            value,
+        };
+        this.update(timer, config);
+        this
+    }
+
+    pub fn container(&self) -> &GtkBox {
+        &self.wrapper
+    }
+
+    pub fn value(&self) -> &Label {
+        &self.value
+    }
+
+    pub fn update(&self, timer: &Timer, config: &Config) {
+        let target =
+            target_final_time(timer).filter(|_| config.style.show_target_time.unwrap_or(true));
+        let Some(target) = target else {
+            self.value.set_label("");
+            self.wrapper.set_visible(false);
+            return;
+        };
+
+        let label_text = format!("{}:", format_label(timer.current_comparison()));
+        if self.label.label().as_str() != label_text {
+            self.label.set_label(&label_text);
+        }
+        let value_text = config.format.comparison.format_duration(&target);
+        if self.value.label().as_str() != value_text {
+            self.value.set_label(&value_text);
+        }
+        self.wrapper.set_visible(true);
+    }
+}
+
 /// Right pane in the footer: the running timer display.
 pub struct RunningTimer {
     wrapper: GtkBox,
@@ -317,7 +568,10 @@ impl RunningTimer {
             timer_box.add_css_class("inactive-timer");
         }
 
-        let formatted = config.format.timer.format_timer(timer);
+        let formatted = config
+            .format
+            .timer
+            .format_timer(timer, video_offset(config));
         let (left, right) = if let Some((l, r)) = formatted.rsplit_once('.') {
             (format!("{l}."), r.to_owned())
         } else {
@@ -357,7 +611,8 @@ impl RunningTimer {
         });
 
         // Update labels only if changed
-        let formatted = config.format.timer.format_timer(timer);
+        let formatted =
+            running_timer_format(timer, config).format_timer(timer, video_offset(config));
         let (left, right) = if let Some((l, r)) = formatted.rsplit_once('.') {
             (format!("{l}."), r.to_owned())
         } else {
@@ -373,6 +628,44 @@ impl RunningTimer {
     }
 }
 
+/// Segment the info is for: the current split during an attempt, or always
+/// with [`SegmentInfoFollows::CurrentSplit`]. Otherwise the selected one,
+/// asked for only then. The first segment when there's neither.
+fn info_segment_index(
+    follows: SegmentInfoFollows,
+    running: bool,
+    current_split: Option<usize>,
+    selected: impl FnOnce() -> Option<usize>,
+    segment_count: usize,
+) -> usize {
+    let index = if running || follows == SegmentInfoFollows::CurrentSplit {
+        current_split
+    } else {
+        selected()
+    };
+    index.unwrap_or(0).min(segment_count.saturating_sub(1))
+}
+
+/// Timer format for the current frame. With adaptive precision, decimals grow
+/// while a gold or the comparison is within reach and drop back once the
+/// split is done, since the next segment starts far from its threshold.
+fn running_timer_format<'a>(timer: &Timer, config: &'a Config) -> Cow<'a, TimeFormat> {
+    let base = &config.format.timer;
+    if !config.format.adaptive_precision || !base.show_decimals {
+        return Cow::Borrowed(base);
+    }
+
+    let places =
+        adaptive_decimal_places(base.decimal_places, time_left_to_threshold(timer, config));
+    if places == base.decimal_places {
+        Cow::Borrowed(base)
+    } else {
+        let mut format = base.clone();
+        format.set_decimal_places(places);
+        Cow::Owned(format)
+    }
+}
+
 #[cfg(test)]
 mod footer_ui_tests {
     use super::*;
@@ -513,6 +806,44 @@ mod footer_ui_tests {
         );
     }
 
+    #[gtk4::test]
+    fn target_time_shows_comparison_final_time() {
+        gtk_test_init();
+
+        let mut run = livesplit_core::Run::new();
+        let mut segment = livesplit_core::Segment::new("Split 1");
+        segment.set_personal_best_split_time(
+            livesplit_core::Time::new()
+                .with_real_time(Some(livesplit_core::TimeSpan::from_seconds(83.25))),
+        );
+        run.push_segment(segment);
+        let timer = livesplit_core::Timer::new(run).expect("timer");
+        let mut config = Config::default();
+
+        let target = TargetTime::new(&timer, &config);
+        assert!(target.container().is_visible());
+        assert_eq!(target.label.label().as_str(), "PB:");
+        assert_eq!(target.value.label().as_str(), "1:23.25");
+
+        config.style.show_target_time = Some(false);
+        target.update(&timer, &config);
+        assert!(!target.container().is_visible());
+    }
+
+    #[gtk4::test]
+    fn target_time_is_blank_without_final_time() {
+        gtk_test_init();
+
+        let mut run = livesplit_core::Run::new();
+        run.push_segment(livesplit_core::Segment::new("Split 1"));
+        let timer = livesplit_core::Timer::new(run).expect("timer");
+        let config = Config::default();
+
+        let target = TargetTime::new(&timer, &config);
+        assert!(!target.container().is_visible());
+        assert_eq!(target.value.label().as_str(), "");
+    }
+
     #[gtk4::test]
     fn segment_comparison_structure_and_texts() {
         gtk_test_init();
@@ -583,4 +914,174 @@ mod footer_ui_tests {
         // Ensure update works without panics and keeps structure
         sc.update(&timer, &config);
     }
+
+    #[gtk4::test]
+    fn segment_comparison_value_uses_comparison_precision() {
+        gtk_test_init();
+
+        let list = ListBox::new();
+        let last_list = ListBox::new();
+        let mut run = livesplit_core::Run::new();
+        let mut segment = livesplit_core::Segment::new("Split 1");
+        segment.set_personal_best_split_time(
+            livesplit_core::Time::new()
+                .with_real_time(Some(livesplit_core::TimeSpan::from_seconds(83.256))),
+        );
+        run.push_segment(segment);
+        let timer = livesplit_core::Timer::new(run).expect("timer");
+        let mut config = Config::default();
+        config.format.segment = TimeFormat::new(true, true, true, true, 3, false);
+        config.format.comparison = TimeFormat::new(true, true, true, true, 1, false);
+
+        let sc = SegmentComparison::new(&timer, &config, &list, &last_list);
+        assert_eq!(sc.comparison_value.label().as_str(), "1:23.2");
+    }
+
+    #[gtk4::test]
+    fn segment_breakdown_shows_pb_segment_for_other_comparisons() {
+        gtk_test_init();
+
+        let list = ListBox::new();
+        let last_list = ListBox::new();
+        let mut run = livesplit_core::Run::new();
+        for (name, pb) in [("Split 1", 10.0), ("Split 2", 25.5)] {
+            let mut segment = livesplit_core::Segment::new(name);
+            segment.set_personal_best_split_time(
+                livesplit_core::Time::new()
+                    .with_real_time(Some(livesplit_core::TimeSpan::from_seconds(pb))),
+            );
+            run.push_segment(segment);
+        }
+        let mut timer = livesplit_core::Timer::new(run).expect("timer");
+        let mut config = Config::default();
+        config.style.show_segment_breakdown = Some(true);
+
+        let mut sc = SegmentComparison::new(&timer, &config, &list, &last_list);
+        // PB is the comparison, so its line already has the PB segment
+        assert!(!sc.pb_box.is_visible());
+        assert!(sc.running_box.is_visible());
+        assert_eq!(sc.running_value.label().as_str(), "--");
+
+        timer.set_current_comparison("Best Segments").unwrap();
+        sc.update(&timer, &config);
+        assert!(sc.pb_box.is_visible());
+        assert_eq!(
+            sc.pb_value.label().as_str(),
+            config
+                .format
+                .comparison
+                .format_duration(&time::Duration::seconds(10))
+        );
+
+        config.style.show_segment_breakdown = Some(false);
+        sc.update(&timer, &config);
+        assert!(!sc.pb_box.is_visible());
+        assert!(!sc.running_box.is_visible());
+    }
+
+    #[test]
+    fn segment_info_follows_the_selection_only_when_idle() {
+        use SegmentInfoFollows::*;
+        // Row 2 selected in a run of 4
+        let index = |follows, running, current| {
+            info_segment_index(follows, running, current, || Some(2), 4)
+        };
+        assert_eq!(index(SelectionWhenIdle, false, None), 2);
+        assert_eq!(index(SelectionWhenIdle, true, Some(1)), 1);
+        assert_eq!(index(CurrentSplit, false, None), 0);
+        assert_eq!(index(CurrentSplit, true, Some(1)), 1);
+        // Ended, past the last split
+        assert_eq!(index(CurrentSplit, false, Some(4)), 3);
+        assert_eq!(
+            info_segment_index(SelectionWhenIdle, false, None, || None, 4),
+            0
+        );
+    }
+
+    #[gtk4::test]
+    fn segment_comparison_is_for_the_selected_row_until_the_attempt_starts() {
+        gtk_test_init();
+
+        let mut run = livesplit_core::Run::new();
+        for (name, best) in [("Split 1", 10.0), ("Split 2", 20.0), ("Split 3", 30.0)] {
+            let mut segment = livesplit_core::Segment::new(name);
+            segment.set_best_segment_time(
+                livesplit_core::Time::new()
+                    .with_real_time(Some(livesplit_core::TimeSpan::from_seconds(best))),
+            );
+            run.push_segment(segment);
+        }
+        let mut timer = livesplit_core::Timer::new(run).expect("timer");
+        let mut config = Config::default();
+        // The last segment has a list of its own, as in the timer
+        let list = ListBox::new();
+        let last_list = ListBox::new();
+        list.append(&Label::new(Some("Split 1")));
+        list.append(&Label::new(Some("Split 2")));
+        last_list.append(&Label::new(Some("Split 3")));
+        let best = |seconds| {
+            config
+                .format
+                .comparison
+                .format_duration(&time::Duration::seconds(seconds))
+        };
+        let (first, second, third) = (best(10), best(20), best(30));
+
+        let mut sc = SegmentComparison::new(&timer, &config, &list, &last_list);
+        assert_eq!(sc.best_value.label().as_str(), first, "Nothing selected");
+
+        list.select_row(list.row_at_index(1).as_ref());
+        sc.update(&timer, &config);
+        assert_eq!(sc.best_value.label().as_str(), second);
+
+        list.unselect_all();
+        last_list.select_row(last_list.row_at_index(0).as_ref());
+        sc.update(&timer, &config);
+        assert_eq!(sc.best_value.label().as_str(), third);
+
+        timer.start();
+        sc.update(&timer, &config);
+        assert_eq!(sc.best_value.label().as_str(), first, "The current split");
+
+        timer.reset(false);
+        config.layout.segment_info_follows = SegmentInfoFollows::CurrentSplit;
+        sc.update(&timer, &config);
+        assert_eq!(sc.best_value.label().as_str(), first, "Selection ignored");
+    }
+
+    #[gtk4::test]
+    fn segment_comparison_shows_running_segment_time_mid_split() {
+        gtk_test_init();
+
+        let list = ListBox::new();
+        let last_list = ListBox::new();
+        let mut run = livesplit_core::Run::new();
+        run.set_game_name("Game");
+        run.set_category_name("Any%");
+        run.push_segment(livesplit_core::Segment::new("Split 1"));
+        run.push_segment(livesplit_core::Segment::new("Split 2"));
+        // Start twelve seconds in so the running time is well defined
+        run.set_offset(livesplit_core::TimeSpan::from_seconds(12.0));
+        let mut timer = livesplit_core::Timer::new(run).expect("timer");
+        let mut config = Config::default();
+
+        let mut sc = SegmentComparison::new(&timer, &config, &list, &last_list);
+        assert!(!sc.running_box.is_visible(), "Hidden unless enabled");
+
+        config.style.show_segment_running_time = Some(true);
+        sc.update(&timer, &config);
+        assert!(!sc.running_box.is_visible(), "Hidden outside an attempt");
+
+        timer.start();
+        // Paused so the value holds still while we compare
+        timer.pause();
+        sc.update(&timer, &config);
+        assert!(sc.running_box.is_visible());
+        let elapsed = current_segment_elapsed(&timer, video_offset(&config)).expect("in progress");
+        assert!(elapsed >= time::Duration::seconds(12));
+        assert_eq!(
+            sc.running_value.label().as_str(),
+            config.format.segment.format_segment_time(&elapsed)
+        );
+    }
 }
@@ -0,0 +1,497 @@
+//! Frame budget instrumentation for the refresh loop.
+//!
+//! Every tick records how long each phase took (timer snapshot, header, body
+//! and footer). The recent samples are kept for percentiles shown in the
+//! `debug.frame-stats` overlay, and ticks over `debug.frame-budget-ms` are
+//! logged with their breakdown. `RowRefreshQueue` spreads large row updates
+//! (comparison switches, phase changes) over consecutive ticks, and
+//! `AppliedSplits` tells which rows the splits since the last tick touched.
+//! `RowMemo` keeps huge runs to computing the rows in view.
+
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use livesplit_core::Time;
+use tracing::warn;
+
+/// Number of ticks kept for percentiles, about four seconds at 60 Hz.
+const SAMPLE_WINDOW: usize = 240;
+/// Minimum time between two over-budget warnings.
+const WARN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Time spent in each phase of a single tick.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TickTimings {
+    pub snapshot: Duration,
+    pub header: Duration,
+    pub body: Duration,
+    pub footer: Duration,
+}
+
+impl TickTimings {
+    pub fn total(&self) -> Duration {
+        self.snapshot + self.header + self.body + self.footer
+    }
+}
+
+/// Measures consecutive phases of a tick: each `lap` returns the time since
+/// the previous one.
+pub struct PhaseClock(Instant);
+
+impl PhaseClock {
+    pub fn start() -> Self {
+        Self(Instant::now())
+    }
+
+    pub fn lap(&mut self) -> Duration {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.0);
+        self.0 = now;
+        elapsed
+    }
+}
+
+/// Nearest-rank percentile of `samples`, `p` in 0..=100.
+pub fn percentile(samples: impl IntoIterator<Item = Duration>, p: f64) -> Option<Duration> {
+    let mut sorted: Vec<Duration> = samples.into_iter().collect();
+    if sorted.is_empty() {
+        return None;
+    }
+    sorted.sort_unstable();
+    let rank = ((p.clamp(0.0, 100.0) / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.saturating_sub(1).min(sorted.len() - 1)])
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Rolling window of recent tick timings.
+#[derive(Debug, Default)]
+pub struct FrameStats {
+    samples: VecDeque<TickTimings>,
+    last_warning: Option<Instant>,
+}
+
+impl FrameStats {
+    pub fn record(&mut self, timings: TickTimings) {
+        if self.samples.len() == SAMPLE_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(timings);
+    }
+
+    /// Logs `timings` with its breakdown if it exceeded `budget`. Warnings are
+    /// rate limited so a slow machine doesn't flood the log.
+    pub fn warn_if_over_budget(&mut self, timings: &TickTimings, budget: Duration) {
+        let total = timings.total();
+        if total <= budget
+            || self
+                .last_warning
+                .is_some_and(|last| last.elapsed() < WARN_INTERVAL)
+        {
+            return;
+        }
+        self.last_warning = Some(Instant::now());
+        warn!(
+            "Refresh tick took {:.1}ms (budget {:.1}ms): snapshot {:.1}ms, header {:.1}ms, body {:.1}ms, footer {:.1}ms",
+            millis(total),
+            millis(budget),
+            millis(timings.snapshot),
+            millis(timings.header),
+            millis(timings.body),
+            millis(timings.footer),
+        );
+    }
+
+    pub fn total_percentile(&self, p: f64) -> Option<Duration> {
+        percentile(self.samples.iter().map(TickTimings::total), p)
+    }
+
+    /// Short summary for the overlay, e.g. "p50 1.2 / p95 3.4 / p99 9.8 ms".
+    pub fn summary(&self) -> String {
+        match (
+            self.total_percentile(50.0),
+            self.total_percentile(95.0),
+            self.total_percentile(99.0),
+        ) {
+            (Some(p50), Some(p95), Some(p99)) => format!(
+                "p50 {:.1} / p95 {:.1} / p99 {:.1} ms",
+                millis(p50),
+                millis(p95),
+                millis(p99)
+            ),
+            _ => "no samples".to_owned(),
+        }
+    }
+}
+
+/// Rows waiting to be refreshed, applied a chunk per tick.
+#[derive(Debug, Default)]
+pub struct RowRefreshQueue {
+    pending: VecDeque<usize>,
+}
+
+impl RowRefreshQueue {
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Queues `rows` after the pending ones. The current split's row should
+    /// come first so the visible change lands immediately.
+    pub fn extend(&mut self, rows: impl IntoIterator<Item = usize>) {
+        self.pending.extend(rows);
+    }
+
+    /// Takes the next `chunk_size` rows (at least one) in scheduling order.
+    pub fn next_chunk(&mut self, chunk_size: usize) -> Vec<usize> {
+        let count = chunk_size.max(1).min(self.pending.len());
+        self.pending.drain(..count).collect()
+    }
+}
+
+/// The split index and split times the rows were last refreshed with.
+///
+/// An auto splitter can split several times between two ticks, so the rows
+/// to refresh are worked out from what changed since then rather than from
+/// the current split alone.
+#[derive(Debug, Default)]
+pub struct AppliedSplits {
+    index: Option<usize>,
+    times: Vec<Time>,
+}
+
+impl AppliedSplits {
+    /// Records that every row shows `current` and `times`.
+    pub fn mark_applied(&mut self, current: Option<usize>, times: impl IntoIterator<Item = Time>) {
+        self.index = current;
+        self.times.clear();
+        self.times.extend(times);
+    }
+
+    /// Rows to refresh to go from the applied snapshot to `current` and
+    /// `times`, in order, and records them as applied. That is every row
+    /// between the previous and the current split with their neighbours,
+    /// along with any row whose split time changed, e.g. after an undo and
+    /// a new split within the same tick.
+    pub fn dirty_rows(
+        &mut self,
+        current: Option<usize>,
+        times: impl IntoIterator<Item = Time>,
+    ) -> Vec<usize> {
+        let mut dirty = Vec::new();
+        let mut len = 0;
+        for (index, time) in times.into_iter().enumerate() {
+            len = index + 1;
+            match self.times.get_mut(index) {
+                Some(applied) if *applied == time => {}
+                Some(applied) => {
+                    *applied = time;
+                    dirty.push(index);
+                }
+                None => {
+                    self.times.push(time);
+                    dirty.push(index);
+                }
+            }
+        }
+        self.times.truncate(len);
+
+        if let Some(current) = current {
+            let previous = self.index.unwrap_or(current);
+            let first = previous.min(current).saturating_sub(1);
+            let last = previous.max(current).saturating_add(1);
+            dirty.extend((first..=last).filter(|index| *index < len));
+        }
+        self.index = current;
+        dirty.sort_unstable();
+        dirty.dedup();
+        dirty
+    }
+}
+
+/// Positions of the scrolled list in view with the one at `first` on top,
+/// `page` rows of them, and `page` more on each side so rows are computed
+/// before they're scrolled to.
+pub fn visible_positions(first: usize, page: usize, len: usize) -> Range<usize> {
+    let page = page.max(1);
+    let start = first.saturating_sub(page).min(len);
+    start..first.saturating_add(page * 2).min(len).max(start)
+}
+
+/// Which rows show values computed for the current attempt and comparison.
+///
+/// Randomizer splits can have well over a thousand segments but only a
+/// screenful of rows is seen at a time, so rows are computed as they come
+/// into view and left alone while scrolling back and forth. A new attempt
+/// generation or comparison forgets every row, splits and undos the rows
+/// they touched.
+#[derive(Debug, Default)]
+pub struct RowMemo {
+    key: Option<(u64, String)>,
+    fresh: Vec<bool>,
+}
+
+impl RowMemo {
+    /// Forgets everything for `len` rows just built.
+    pub fn reset(&mut self, len: usize) {
+        self.key = None;
+        self.fresh.clear();
+        self.fresh.resize(len, false);
+    }
+
+    /// Keys the rows to `generation` and `comparison`. Returns whether every
+    /// row was forgotten because the key changed.
+    pub fn set_key(&mut self, generation: u64, comparison: &str) -> bool {
+        if self
+            .key
+            .as_ref()
+            .is_some_and(|(g, c)| *g == generation && c == comparison)
+        {
+            return false;
+        }
+        self.key = Some((generation, comparison.to_owned()));
+        self.fresh.fill(false);
+        true
+    }
+
+    /// Forgets `index`, e.g. a row split or undone out of view.
+    pub fn forget(&mut self, index: usize) {
+        if let Some(fresh) = self.fresh.get_mut(index) {
+            *fresh = false;
+        }
+    }
+
+    /// The rows of `window` that need computing, in order without repeats,
+    /// counted as computed from now on.
+    pub fn due(&mut self, window: impl IntoIterator<Item = usize>) -> Vec<usize> {
+        window
+            .into_iter()
+            .filter(|index| match self.fresh.get_mut(*index) {
+                Some(fresh) if !*fresh => {
+                    *fresh = true;
+                    true
+                }
+                _ => false,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod frame_budget_tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    fn drain(queue: &mut RowRefreshQueue, chunk_size: usize) -> Vec<Vec<usize>> {
+        let mut chunks = Vec::new();
+        while !queue.is_empty() {
+            chunks.push(queue.next_chunk(chunk_size));
+        }
+        chunks
+    }
+
+    #[test]
+    fn rows_are_applied_once_in_order() {
+        let mut queue = RowRefreshQueue::default();
+        queue.extend([4, 0, 1, 2, 3, 5, 6]);
+        let chunks = drain(&mut queue, 3);
+        assert_eq!(chunks, vec![vec![4, 0, 1], vec![2, 3, 5], vec![6]]);
+
+        let mut applied: Vec<usize> = chunks.concat();
+        applied.sort_unstable();
+        assert_eq!(applied, (0..7).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn small_updates_fit_in_one_chunk() {
+        let mut queue = RowRefreshQueue::default();
+        queue.extend(0..3);
+        assert_eq!(drain(&mut queue, 8), vec![vec![0, 1, 2]]);
+    }
+
+    #[test]
+    fn clearing_drops_pending_rows() {
+        let mut queue = RowRefreshQueue::default();
+        queue.extend(0..10);
+        assert_eq!(queue.next_chunk(4), vec![0, 1, 2, 3]);
+        queue.clear();
+        queue.extend([2, 0, 1, 3, 4]);
+        assert_eq!(drain(&mut queue, 4), vec![vec![2, 0, 1, 3], vec![4]]);
+    }
+
+    #[test]
+    fn empty_queue_and_zero_chunk_size() {
+        let mut queue = RowRefreshQueue::default();
+        queue.extend(0..2);
+        assert_eq!(drain(&mut queue, 0), vec![vec![0], vec![1]]);
+
+        queue.extend(std::iter::empty());
+        assert!(queue.is_empty());
+        assert!(queue.next_chunk(4).is_empty());
+    }
+
+    fn splits(seconds: &[Option<f64>]) -> Vec<Time> {
+        seconds
+            .iter()
+            .map(|s| Time::new().with_real_time(s.map(livesplit_core::TimeSpan::from_seconds)))
+            .collect()
+    }
+
+    #[test]
+    fn a_single_split_touches_the_rows_around_it() {
+        let mut applied = AppliedSplits::default();
+        applied.mark_applied(
+            Some(2),
+            splits(&[Some(1.0), Some(2.0), None, None, None, None]),
+        );
+        let times = splits(&[Some(1.0), Some(2.0), Some(3.0), None, None, None]);
+        assert_eq!(applied.dirty_rows(Some(3), times.clone()), vec![1, 2, 3, 4]);
+        // Nothing changed on the next tick but the live rows
+        assert_eq!(applied.dirty_rows(Some(3), times), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn every_split_between_two_ticks_is_refreshed() {
+        let mut applied = AppliedSplits::default();
+        applied.mark_applied(Some(1), splits(&[Some(1.0), None, None, None, None, None]));
+        let times = splits(&[Some(1.0), Some(2.0), Some(3.0), Some(4.0), None, None]);
+        assert_eq!(applied.dirty_rows(Some(4), times), vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn a_changed_time_is_refreshed_even_when_the_index_is_back() {
+        let mut applied = AppliedSplits::default();
+        applied.mark_applied(
+            Some(4),
+            splits(&[Some(1.0), Some(2.0), Some(3.0), Some(4.0), None]),
+        );
+        // Undone and split again in between, the index is where it was
+        let times = splits(&[Some(1.0), Some(2.5), Some(3.0), Some(4.0), None]);
+        assert_eq!(applied.dirty_rows(Some(4), times), vec![1, 3, 4]);
+
+        // Undoing moves back over the rows too
+        let times = splits(&[Some(1.0), Some(2.5), None, None, None]);
+        assert_eq!(applied.dirty_rows(Some(2), times), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn the_end_of_the_run_stays_in_range() {
+        let mut applied = AppliedSplits::default();
+        applied.mark_applied(Some(1), splits(&[Some(1.0), None]));
+        let times = splits(&[Some(1.0), Some(2.0)]);
+        assert_eq!(applied.dirty_rows(Some(2), times), vec![0, 1]);
+        assert!(applied.dirty_rows(None, Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn a_run_without_segments_has_nothing_to_refresh() {
+        let mut memo = RowMemo::default();
+        memo.reset(0);
+        assert!(memo.set_key(1, "Personal Best"));
+        memo.forget(0);
+        assert!(memo.due(visible_positions(0, 10, 0)).is_empty());
+
+        let mut applied = AppliedSplits::default();
+        applied.mark_applied(None, Vec::new());
+        // The timer reports a split with no row to show it on
+        assert!(applied.dirty_rows(Some(0), Vec::new()).is_empty());
+        assert!(applied.dirty_rows(None, Vec::new()).is_empty());
+
+        let mut queue = RowRefreshQueue::default();
+        queue.extend(applied.dirty_rows(Some(1), Vec::new()));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn visible_positions_stay_in_range() {
+        assert_eq!(visible_positions(0, 10, 1000), 0..20);
+        assert_eq!(visible_positions(500, 10, 1000), 490..520);
+        assert_eq!(visible_positions(995, 10, 1000), 985..1000);
+        assert_eq!(visible_positions(0, 10, 4), 0..4);
+        assert_eq!(visible_positions(3, 0, 0), 0..0);
+    }
+
+    #[test]
+    fn huge_runs_only_compute_the_rows_scrolled_to() {
+        const SEGMENTS: usize = 1000;
+        const PAGE: usize = 10;
+        let mut memo = RowMemo::default();
+        let mut computed = 0;
+        let show = |memo: &mut RowMemo, first: usize| {
+            // The final split is always in view
+            let window = visible_positions(first, PAGE, SEGMENTS - 1).chain([SEGMENTS - 1]);
+            memo.due(window).len()
+        };
+
+        memo.reset(SEGMENTS);
+        assert!(memo.set_key(0, "Personal Best"));
+        computed += show(&mut memo, 0);
+        assert_eq!(computed, 2 * PAGE + 1);
+
+        // Scrolling down a few pages and back up again
+        for first in (0..=5 * PAGE).chain((0..5 * PAGE).rev()) {
+            assert!(!memo.set_key(0, "Personal Best"));
+            computed += show(&mut memo, first);
+        }
+        assert_eq!(computed, 7 * PAGE + 1);
+
+        // A split forgets its row, a new comparison every row
+        memo.forget(3);
+        computed += show(&mut memo, 0);
+        assert_eq!(computed, 7 * PAGE + 2);
+        assert!(memo.set_key(0, "Best Segments"));
+        computed += show(&mut memo, 0);
+        assert_eq!(computed, 9 * PAGE + 3);
+        assert!(memo.set_key(1, "Best Segments"));
+        assert_eq!(memo.due([0, SEGMENTS - 1, 0]), vec![0, SEGMENTS - 1]);
+    }
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let samples: Vec<Duration> = (1..=100).map(ms).collect();
+        assert_eq!(percentile(samples.iter().copied(), 50.0), Some(ms(50)));
+        assert_eq!(percentile(samples.iter().copied(), 95.0), Some(ms(95)));
+        assert_eq!(percentile(samples.iter().copied(), 100.0), Some(ms(100)));
+        assert_eq!(percentile(samples.iter().copied(), 0.0), Some(ms(1)));
+        assert_eq!(percentile([ms(7)], 99.0), Some(ms(7)));
+        assert_eq!(percentile(std::iter::empty(), 50.0), None);
+    }
+
+    #[test]
+    fn window_keeps_only_recent_ticks() {
+        let mut stats = FrameStats::default();
+        for _ in 0..SAMPLE_WINDOW {
+            stats.record(TickTimings {
+                body: ms(30),
+                ..TickTimings::default()
+            });
+        }
+        for _ in 0..SAMPLE_WINDOW {
+            stats.record(TickTimings {
+                body: ms(2),
+                ..TickTimings::default()
+            });
+        }
+        assert_eq!(stats.total_percentile(99.0), Some(ms(2)));
+    }
+
+    #[test]
+    fn total_sums_phases() {
+        let timings = TickTimings {
+            snapshot: ms(1),
+            header: ms(2),
+            body: ms(3),
+            footer: ms(4),
+        };
+        assert_eq!(timings.total(), ms(10));
+    }
+}
@@ -1,19 +1,32 @@
 use crate::config::Config;
 
 use adw::prelude::*;
-use gtk4::{Align, Box as GtkBox, Label, Orientation::Vertical};
+use gtk4::{
+    Align, Box as GtkBox, DropDown, EventControllerFocus, EventControllerMotion,
+    INVALID_LIST_POSITION, Label, ListItem, Orientation::Horizontal, Orientation::Vertical,
+    SignalListItemFactory, StringList, StringObject,
+};
 
-use livesplit_core::Timer;
+use livesplit_core::comparison::shorten;
+use livesplit_core::{Run, Timer, TimingMethod};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::context::TuxSplitContext;
+use crate::utils::comparisons::{DeltaWidget, comparison_summary, format_delta};
 
 /// `TimerHeader`
 /// Renders the top section of the timer UI:
 /// - Game name (styled as `title-2`)
-/// - Category (styled as `heading`)
+/// - Category (styled as `heading`) and the active timing method
+/// - The run metadata picked in `layout.run-metadata`
+/// - A picker for the current comparison
 ///
 /// This component owns a stable container widget that can be appended to the main layout.
 pub struct TimerHeader {
     container: GtkBox,
     run_info: RunInfo,
+    comparison_picker: ComparisonPicker,
 }
 
 impl TimerHeader {
@@ -27,12 +40,15 @@ impl TimerHeader {
 
         // Run info (game + category)
         let run_info = RunInfo::new(timer);
+        let comparison_picker = ComparisonPicker::new(timer);
 
         container.append(run_info.container());
+        container.append(comparison_picker.dropdown());
 
         Self {
             container,
             run_info,
+            comparison_picker,
         }
     }
 
@@ -42,9 +58,9 @@ impl TimerHeader {
     }
 
     /// Update the header from the current timer/config state.
-    /// Currently only the timer is used (to update game/category labels).
-    pub fn refresh(&mut self, timer: &Timer) {
-        self.run_info.update(timer);
+    pub fn refresh(&mut self, timer: &Timer, config: &Config) {
+        self.run_info.update(timer, config);
+        self.comparison_picker.update(timer);
     }
 }
 
@@ -53,10 +69,15 @@ impl TimerHeader {
 /// Holds and renders:
 /// - Game name (Label with CSS class `title-2`)
 /// - Category (Label with CSS class `heading`)
+/// - Timing method, "(RTA)" or "(IGT)", so IGT runs aren't mistaken for RTA
+/// - Run metadata (Label with CSS classes `caption` and `dim-label`)
 pub struct RunInfo {
     container: GtkBox,
     run_name: Label,
     category: Label,
+    timing_method: Label,
+    metadata: Label,
+    shown_metadata: Option<String>,
 }
 
 impl RunInfo {
@@ -73,13 +94,35 @@ impl RunInfo {
         let category = Label::builder().label(timer.run().category_name()).build();
         category.add_css_class("heading");
 
+        let timing_method = Label::builder()
+            .label(timing_method_text(timer.current_timing_method()))
+            .css_classes(["caption", "dim-label"])
+            .build();
+
+        let category_box = GtkBox::builder()
+            .orientation(Horizontal)
+            .spacing(4)
+            .halign(Align::Center)
+            .build();
+        category_box.append(&category);
+        category_box.append(&timing_method);
+
+        let metadata = Label::builder()
+            .css_classes(["caption", "dim-label"])
+            .visible(false)
+            .build();
+
         container.append(&run_name);
-        container.append(&category);
+        container.append(&category_box);
+        container.append(&metadata);
 
         Self {
             container,
             run_name,
             category,
+            timing_method,
+            metadata,
+            shown_metadata: None,
         }
     }
 
@@ -89,8 +132,370 @@ impl RunInfo {
     }
 
     /// Update labels using the current timer state.
-    pub fn update(&self, timer: &Timer) {
+    pub fn update(&mut self, timer: &Timer, config: &Config) {
         self.run_name.set_label(timer.run().game_name());
         self.category.set_label(timer.run().category_name());
+        let timing_method = timing_method_text(timer.current_timing_method());
+        if self.timing_method.label().as_str() != timing_method {
+            self.timing_method.set_label(timing_method);
+        }
+
+        let metadata = metadata_line(timer.run(), &config.layout.run_metadata);
+        if metadata != self.shown_metadata {
+            self.metadata
+                .set_label(metadata.as_deref().unwrap_or_default());
+            self.metadata.set_visible(metadata.is_some());
+            self.shown_metadata = metadata;
+        }
+    }
+}
+
+fn timing_method_text(method: TimingMethod) -> &'static str {
+    match method {
+        TimingMethod::RealTime => "(RTA)",
+        TimingMethod::GameTime => "(IGT)",
+    }
+}
+
+/// The `fields` of `run`'s metadata joined into one line, `None` when they
+/// are all empty. A field is `platform`, `region`, `emulator`, or the name
+/// of a speedrun.com variable, then of a custom variable.
+pub fn metadata_line(run: &Run, fields: &[String]) -> Option<String> {
+    let metadata = run.metadata();
+    let values: Vec<&str> = fields
+        .iter()
+        .filter_map(|field| match field.as_str() {
+            "platform" => Some(metadata.platform_name()),
+            "region" => Some(metadata.region_name()),
+            "emulator" => metadata.uses_emulator().then_some("Emulator"),
+            name => metadata
+                .speedrun_com_variables()
+                .find(|(variable, _)| *variable == name)
+                .map(|(_, value)| value.as_str())
+                .or_else(|| metadata.custom_variable_value(name)),
+        })
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .collect();
+    (!values.is_empty()).then(|| values.join(" · "))
+}
+
+/// The comparisons offered by the picker, in the run's order.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ComparisonChoices {
+    names: Vec<String>,
+}
+
+impl ComparisonChoices {
+    /// Follows the run's comparisons. Returns whether the list changed, e.g.
+    /// after loading other splits or adding a custom comparison.
+    pub fn sync<'a>(&mut self, comparisons: impl Iterator<Item = &'a str>) -> bool {
+        let mut changed = false;
+        let mut len = 0;
+        for (index, name) in comparisons.enumerate() {
+            len = index + 1;
+            match self.names.get_mut(index) {
+                Some(known) if known == name => {}
+                Some(known) => {
+                    name.clone_into(known);
+                    changed = true;
+                }
+                None => {
+                    self.names.push(name.to_owned());
+                    changed = true;
+                }
+            }
+        }
+        if self.names.len() != len {
+            self.names.truncate(len);
+            changed = true;
+        }
+        changed
+    }
+
+    pub fn name(&self, position: u32) -> Option<&str> {
+        self.names.get(position as usize).map(String::as_str)
+    }
+
+    /// Position of `comparison`, `INVALID_LIST_POSITION` when it isn't offered.
+    pub fn position(&self, comparison: &str) -> u32 {
+        self.names
+            .iter()
+            .position(|name| name == comparison)
+            .map_or(INVALID_LIST_POSITION, |position| position as u32)
+    }
+
+    /// Short names as shown in the dropdown, e.g. "PB" for Personal Best.
+    pub fn labels(&self) -> Vec<&str> {
+        self.names.iter().map(|name| shorten(name)).collect()
+    }
+}
+
+/// Switches the current comparison from the header, as an alternative to
+/// the cycling hotkeys. It follows the comparison the timer is rendered
+/// with, so switching by hotkey moves the selection too.
+pub struct ComparisonPicker {
+    dropdown: DropDown,
+    model: StringList,
+    choices: Rc<RefCell<ComparisonChoices>>,
+    rendered_comparison: String,
+    /// Set while the selection is moved to match the timer, so that doesn't
+    /// switch the comparison again.
+    syncing: Rc<Cell<bool>>,
+}
+
+impl ComparisonPicker {
+    pub fn new(timer: &Timer) -> Self {
+        let model = StringList::new(&[]);
+        let dropdown = DropDown::builder()
+            .model(&model)
+            .halign(Align::Center)
+            .tooltip_text("Comparison")
+            .css_classes(["flat"])
+            .build();
+        dropdown.update_property(&[gtk4::accessible::Property::Label("Comparison")]);
+
+        let choices = Rc::new(RefCell::new(ComparisonChoices::default()));
+        let syncing = Rc::new(Cell::new(false));
+        dropdown.set_list_factory(Some(&preview_factory(&choices)));
+
+        let choices_for_change = choices.clone();
+        let syncing_for_change = syncing.clone();
+        dropdown.connect_selected_notify(move |dropdown| {
+            if syncing_for_change.get() {
+                return;
+            }
+            let choices = choices_for_change.borrow();
+            let Some(name) = choices.name(dropdown.selected()) else {
+                return;
+            };
+            let ctx = TuxSplitContext::get_instance();
+            if let Ok(mut timer) = ctx.timer().write() {
+                let _ = timer.set_current_comparison(name);
+            }
+        });
+
+        let mut picker = Self {
+            dropdown,
+            model,
+            choices,
+            rendered_comparison: String::new(),
+            syncing,
+        };
+        picker.update(timer);
+        picker
+    }
+
+    pub fn dropdown(&self) -> &DropDown {
+        &self.dropdown
+    }
+
+    pub fn update(&mut self, timer: &Timer) {
+        let list_changed = self.choices.borrow_mut().sync(timer.run().comparisons());
+        let current = timer.current_comparison();
+        if !list_changed && self.rendered_comparison == current {
+            return;
+        }
+
+        self.syncing.set(true);
+        let choices = self.choices.borrow();
+        if list_changed {
+            self.model
+                .splice(0, self.model.n_items(), &choices.labels());
+        }
+        self.dropdown.set_selected(choices.position(current));
+        self.syncing.set(false);
+        current.clone_into(&mut self.rendered_comparison);
+    }
+}
+
+/// The line previewed under a comparison in the open picker.
+fn preview_text(name: &str) -> String {
+    let ctx = TuxSplitContext::get_instance();
+    let summary = {
+        let shared = ctx.timer();
+        let timer = shared.read().unwrap();
+        comparison_summary(&timer, name)
+    };
+    let config = ctx.config();
+    let final_time = summary.final_time.map_or_else(
+        || "No final time".to_owned(),
+        |time| format!("Final {}", config.format.comparison.format_duration(&time)),
+    );
+    match summary.delta_at_last_split {
+        Some(delta) => format!(
+            "{final_time} · {} at the last split",
+            format_delta(delta, &config, DeltaWidget::Info)
+        ),
+        None => final_time,
+    }
+}
+
+/// Rows of the open picker, which preview the comparison's final time and
+/// delta while hovered or focused with the arrow keys. Picking one still goes
+/// through the selection, so nothing switches until then.
+fn preview_factory(choices: &Rc<RefCell<ComparisonChoices>>) -> SignalListItemFactory {
+    let factory = SignalListItemFactory::new();
+    let choices = choices.clone();
+    factory.connect_setup(move |_, item| {
+        let item = item.downcast_ref::<ListItem>().unwrap();
+        let name = Label::builder().xalign(0.0).build();
+        let preview = Label::builder()
+            .xalign(0.0)
+            .visible(false)
+            .css_classes(["caption", "dim-label"])
+            .build();
+        let row = GtkBox::builder()
+            .orientation(Vertical)
+            .focusable(true)
+            .build();
+        row.append(&name);
+        row.append(&preview);
+        // The row takes the focus itself, so moving it shows the preview
+        item.set_focusable(false);
+        item.set_child(Some(&row));
+
+        let show = {
+            let item = item.downgrade();
+            let preview = preview.clone();
+            let choices = choices.clone();
+            move || {
+                let Some(item) = item.upgrade() else {
+                    return;
+                };
+                if let Some(name) = choices.borrow().name(item.position()) {
+                    preview.set_label(&preview_text(name));
+                    preview.set_visible(true);
+                }
+            }
+        };
+        let motion = EventControllerMotion::new();
+        let focus = EventControllerFocus::new();
+        {
+            let show = show.clone();
+            motion.connect_enter(move |_, _, _| show());
+        }
+        focus.connect_enter(move |_| show());
+        {
+            let preview = preview.clone();
+            motion.connect_leave(move |_| preview.set_visible(false));
+        }
+        focus.connect_leave(move |_| preview.set_visible(false));
+        row.add_controller(motion);
+        row.add_controller(focus);
+    });
+    factory.connect_bind(|_, item| {
+        let item = item.downcast_ref::<ListItem>().unwrap();
+        let Some(row) = item.child().and_downcast::<GtkBox>() else {
+            return;
+        };
+        if let Some(name) = row.first_child().and_downcast::<Label>()
+            && let Some(label) = item.item().and_downcast::<StringObject>()
+        {
+            name.set_label(&label.string());
+        }
+        if let Some(preview) = row.last_child() {
+            preview.set_visible(false);
+        }
+    });
+    factory
+}
+
+#[cfg(test)]
+mod comparison_picker_tests {
+    use super::*;
+    use livesplit_core::{Run, Segment};
+
+    fn run() -> Run {
+        let mut run = Run::new();
+        run.push_segment(Segment::new("One"));
+        run
+    }
+
+    #[test]
+    fn choices_follow_the_runs_comparisons() {
+        let mut run = run();
+        let mut choices = ComparisonChoices::default();
+        assert!(choices.sync(run.comparisons()));
+        assert!(!choices.sync(run.comparisons()));
+        assert_eq!(choices.position("Personal Best"), 0);
+        assert_eq!(choices.labels()[0], "PB");
+
+        run.add_custom_comparison("Race").unwrap();
+        assert!(choices.sync(run.comparisons()));
+        assert_ne!(choices.position("Race"), INVALID_LIST_POSITION);
+        let race = choices.position("Race");
+        assert_eq!(choices.name(race), Some("Race"));
+    }
+
+    #[test]
+    fn removed_comparisons_are_dropped() {
+        let mut run = run();
+        run.add_custom_comparison("Race").unwrap();
+        let mut choices = ComparisonChoices::default();
+        choices.sync(run.comparisons());
+
+        let mut other = self::run();
+        other.set_game_name("Other");
+        assert!(choices.sync(other.comparisons()));
+        assert_eq!(choices.position("Race"), INVALID_LIST_POSITION);
+        assert_eq!(choices.labels().len(), other.comparisons().count());
+    }
+
+    #[test]
+    fn selection_tracks_the_timers_comparison() {
+        let mut timer = Timer::new(run()).unwrap();
+        let mut choices = ComparisonChoices::default();
+        choices.sync(timer.run().comparisons());
+        let pb = choices.position(timer.current_comparison());
+
+        // As a comparison hotkey would
+        timer.switch_to_next_comparison();
+        let next = choices.position(timer.current_comparison());
+        assert_ne!(next, pb);
+        assert_eq!(choices.name(next), Some(timer.current_comparison()));
+    }
+}
+
+#[cfg(test)]
+mod run_metadata_tests {
+    use super::*;
+
+    fn fields(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| (*name).to_owned()).collect()
+    }
+
+    fn run() -> Run {
+        let mut run = Run::new();
+        let metadata = run.metadata_mut();
+        metadata.set_platform_name("Nintendo 64");
+        metadata.set_region_name("");
+        metadata.set_emulator_usage(true);
+        metadata.set_speedrun_com_variable("Difficulty", "Hard");
+        metadata.custom_variable_mut("Seed").set_value("1234");
+        run
+    }
+
+    #[test]
+    fn fields_are_joined_in_the_configured_order() {
+        assert_eq!(
+            metadata_line(&run(), &fields(&["Seed", "platform", "Difficulty"])),
+            Some("1234 · Nintendo 64 · Hard".to_owned())
+        );
+        assert_eq!(
+            metadata_line(&run(), &fields(&["emulator"])),
+            Some("Emulator".to_owned())
+        );
+    }
+
+    #[test]
+    fn empty_and_unknown_fields_are_left_out() {
+        assert_eq!(
+            metadata_line(&run(), &fields(&["region", "platform", "Route"])),
+            Some("Nintendo 64".to_owned())
+        );
+        assert_eq!(metadata_line(&run(), &fields(&["region", "Route"])), None);
+        assert_eq!(metadata_line(&run(), &[]), None);
+        assert_eq!(metadata_line(&Run::new(), &fields(&["emulator"])), None);
     }
 }
@@ -1,34 +1,138 @@
 pub mod body;
+pub mod clock_guard;
+pub mod delta_bars;
+pub mod dual_comparison;
+pub mod font;
 pub mod footer;
+pub mod frame_budget;
 pub mod header;
+pub mod modal;
+pub mod segment_style;
+pub mod split_button;
+pub mod split_menu;
+pub mod split_name;
+pub mod tick_align;
+pub mod tick_fanout;
+pub mod value_column;
+pub mod watchdog;
 
+use crate::config::Config;
+use crate::ui::hotkeys::{ActionSource, hotkey_state};
 use crate::ui::timer::body::TimerBody;
+use crate::ui::timer::clock_guard::{ClockCheck, ClockJumpGuard};
+use crate::ui::timer::dual_comparison::EXTRA_WIDTH;
 use crate::ui::timer::footer::TimerFooter;
+use crate::ui::timer::frame_budget::{FrameStats, PhaseClock, TickTimings};
 use crate::ui::timer::header::TimerHeader;
+use crate::ui::timer::tick_align::{TICK_INTERVAL, TickAligner};
+use crate::ui::timer::tick_fanout::publish_tick;
+use crate::ui::timer::watchdog::{StallDetector, WATCHDOG_INTERVAL, WatchdogAction};
+use crate::utils::trace_timing::{record_tick, tick_span, trace_outside_change};
 
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex, RwLock, TryLockError};
+use std::thread;
+use std::time::Instant;
 
 use core::time::Duration;
 
-use adw::Clamp;
 use adw::prelude::*;
-use gtk4::{Align, Box as GtkBox, Orientation::Vertical};
+use adw::{AlertDialog, Banner, Clamp};
+use gtk4::{Align, Box as GtkBox, Label, Orientation::Vertical, Overlay};
+use livesplit_core::{Timer, TimerPhase};
+use tracing::{error, info, warn};
 
 use crate::context::TuxSplitContext;
+use crate::ui::drill::{DrillHud, set_drill_hud};
 
-pub struct TuxSplitTimer {
+/// Ticks between two updates of the frame stats overlay.
+const STATS_LABEL_INTERVAL: u32 = 30;
+/// Widest the timer gets in the standard layout.
+const MAX_WIDTH: i32 = 900;
+
+/// Last tick that got through to the widgets.
+#[derive(Debug, Clone, Copy, Default)]
+struct Heartbeat {
+    at: Option<Instant>,
+    phase: Option<TimerPhase>,
+}
+
+impl Heartbeat {
+    fn now(phase: TimerPhase) -> Self {
+        Self {
+            at: Some(Instant::now()),
+            phase: Some(phase),
+        }
+    }
+}
+
+/// The heartbeat, shared with the watchdog's thread.
+type SharedHeartbeat = Arc<Mutex<Heartbeat>>;
+
+/// The watchdog's thread, and the main loop source acting on what it saw.
+struct Watchdog {
+    stop: Arc<AtomicBool>,
+    source: glib::SourceId,
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        remove_source(&self.source);
+    }
+}
+
+/// The widgets refreshed on every tick.
+#[derive(Clone)]
+struct TimerParts {
     clamp: Clamp,
+    stats_label: Label,
     header: Rc<RefCell<TimerHeader>>,
     body: Rc<RefCell<TimerBody>>,
     footer: Rc<RefCell<TimerFooter>>,
-    refresh_source: Option<glib::SourceId>,
+}
+
+impl TimerParts {
+    /// Rebuilds every row and label from scratch, dropping cached render state.
+    fn full_resync(&self, timer: &Timer, config: &Config) {
+        self.header.borrow_mut().refresh(timer, config);
+        self.body.borrow_mut().refresh(timer, config, true);
+        let mut footer = self.footer.borrow_mut();
+        footer.invalidate_split_name();
+        footer.refresh(timer, config);
+        drop(footer);
+        self.fit_width();
+    }
+
+    /// Widens the timer for the extra columns of the dual comparison layout.
+    fn fit_width(&self) {
+        let extra = if self.body.borrow().dual_comparison() {
+            EXTRA_WIDTH
+        } else {
+            0
+        };
+        self.clamp.set_maximum_size(MAX_WIDTH + extra);
+    }
+}
+
+pub struct TuxSplitTimer {
+    overlay: Overlay,
+    stall_banner: Banner,
+    parts: TimerParts,
+    heartbeat: SharedHeartbeat,
+    /// Whether the timer is on screen, for the watchdog's thread.
+    mapped: Arc<AtomicBool>,
+    refresh_source: Rc<RefCell<Option<glib::SourceId>>>,
+    watchdog: Option<Watchdog>,
 }
 
 impl TuxSplitTimer {
     /// Create the timer widget (header/body/footer composed) but does NOT start refresh loop.
     pub fn new() -> Self {
-        let clamp = Clamp::builder().maximum_size(900).build();
+        let clamp = Clamp::builder().maximum_size(MAX_WIDTH).build();
 
         let container = GtkBox::builder()
             .orientation(Vertical)
@@ -54,19 +158,53 @@ impl TuxSplitTimer {
             &cfg,
             body.borrow().list(),
             body.borrow().last_segment_list(),
+            body.borrow().placed_rows(),
+            body.borrow().value_column(),
         )));
         drop(timer_read);
 
         container.append(header.borrow().container());
+        let drill_hud = DrillHud::new(body.borrow().container());
+        set_drill_hud(&drill_hud);
+        container.append(drill_hud.container());
         container.append(body.borrow().container());
         container.append(footer.borrow().container());
 
         clamp.set_child(Some(&container));
 
+        let stats_label = Label::builder()
+            .halign(Align::End)
+            .valign(Align::Start)
+            .margin_top(6)
+            .margin_end(6)
+            .css_classes(["caption", "dim-label", "monospace"])
+            .visible(false)
+            .can_target(false)
+            .build();
+        let stall_banner = Banner::builder()
+            .title("Display stalled and was recovered — please report logs")
+            .button_label("Dismiss")
+            .valign(Align::Start)
+            .revealed(false)
+            .build();
+        stall_banner.connect_button_clicked(|banner| banner.set_revealed(false));
+
+        let overlay = Overlay::builder().child(&clamp).build();
+        overlay.add_overlay(&stats_label);
+        overlay.add_overlay(&stall_banner);
+
+        let parts = TimerParts {
+            clamp: clamp.clone(),
+            stats_label,
+            header,
+            body,
+            footer,
+        };
+        parts.fit_width();
+
         {
             // Connect global run-changed to force a rebuild of timer UI.
-            let body_binding = body.clone();
-            let footer_binding = footer.clone();
+            let parts = parts.clone();
             TuxSplitContext::get_instance().connect_local("run-changed", false, move |_| {
                 let ctx = TuxSplitContext::get_instance();
                 let t = {
@@ -74,55 +212,297 @@ impl TuxSplitTimer {
                     shared.read().unwrap().clone()
                 };
                 let c = ctx.config();
-                body_binding.borrow_mut().refresh(&t, &c, true);
-                footer_binding.borrow_mut().refresh(&t, &c);
+                parts.full_resync(&t, &c);
                 None
             });
         }
 
+        let mapped = Arc::new(AtomicBool::new(false));
+        {
+            let on_map = mapped.clone();
+            overlay.connect_map(move |_| on_map.store(true, Ordering::Relaxed));
+            let on_unmap = mapped.clone();
+            overlay.connect_unmap(move |_| on_unmap.store(false, Ordering::Relaxed));
+        }
+
         Self {
-            clamp,
-            header,
-            body,
-            footer,
-            refresh_source: None,
+            overlay,
+            stall_banner,
+            parts,
+            heartbeat: Arc::new(Mutex::new(Heartbeat::default())),
+            mapped,
+            refresh_source: Rc::new(RefCell::new(None)),
+            watchdog: None,
         }
     }
 
-    pub fn clamped(&self) -> &Clamp {
-        &self.clamp
+    /// The clamped timer with the debug overlay on top, to be used as window content.
+    pub fn widget(&self) -> &Overlay {
+        &self.overlay
     }
 
     pub fn start_refresh_loop(&mut self) {
-        if self.refresh_source.is_some() {
+        if self.refresh_source.borrow().is_some() {
             return; // Already running
         }
+        spawn_tick(&self.parts, &self.heartbeat, &self.refresh_source);
+        self.start_watchdog();
+    }
 
-        let header_binding = self.header.clone();
-        let body_binding = self.body.clone();
-        let footer_binding = self.footer.clone();
+    pub fn stop_refresh_loop(&mut self) {
+        self.watchdog = None;
+        if let Some(id) = self.refresh_source.take() {
+            remove_source(&id);
+        }
+    }
 
-        let source_id = glib::timeout_add_local(Duration::from_millis(16), move || {
-            let ctx = TuxSplitContext::get_instance();
-            let t = {
-                let shared = ctx.timer();
-                shared.read().unwrap().clone()
-            };
+    /// Watches the heartbeat from a thread of its own, which still sees the
+    /// ticks stop when the main loop is the one stuck. What it finds is acted
+    /// on from the main loop.
+    fn start_watchdog(&mut self) {
+        if self.watchdog.is_some() {
+            return;
+        }
+        let ctx = TuxSplitContext::get_instance();
+        let threshold = Duration::from_secs(ctx.config().debug.stall_threshold_secs);
+        let (actions, found) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let spawned = {
+            let heartbeat = self.heartbeat.clone();
+            let mapped = self.mapped.clone();
+            let stop = stop.clone();
+            let timer = ctx.timer();
+            thread::Builder::new()
+                .name("display-watchdog".to_owned())
+                .spawn(move || {
+                    let mut detector = StallDetector::new(threshold);
+                    while !stop.load(Ordering::Relaxed) {
+                        thread::sleep(WATCHDOG_INTERVAL);
+                        let beat = *heartbeat.lock().unwrap();
+                        let observed = detector.observe(
+                            Instant::now(),
+                            beat.at,
+                            mapped.load(Ordering::Relaxed),
+                        );
+                        let Some(action) = observed else {
+                            continue;
+                        };
+                        report_stall(action, beat, &timer);
+                        if actions.send(action).is_err() {
+                            break;
+                        }
+                    }
+                })
+        };
+        if let Err(err) = spawned {
+            warn!("Could not start the display watchdog: {err}");
+            return;
+        }
+        let source = act_on_stalls(
+            found,
+            self.parts.clone(),
+            self.heartbeat.clone(),
+            self.refresh_source.clone(),
+            self.overlay.clone(),
+            self.stall_banner.clone(),
+        );
+        self.watchdog = Some(Watchdog { stop, source });
+    }
+}
 
-            let c = ctx.config();
-            header_binding.borrow_mut().refresh(&t);
-            body_binding.borrow_mut().refresh(&t, &c, false);
-            footer_binding.borrow_mut().refresh(&t, &c);
+/// Logs what the watchdog found, from its thread, so stalls of the main
+/// loop are reported while they last.
+fn report_stall(action: WatchdogAction, beat: Heartbeat, timer: &RwLock<Timer>) {
+    match action {
+        WatchdogAction::Recover { behind } => error!(
+            "Display stalled for {behind:.1?} (last phase {:?}, {}), restarting the refresh loop",
+            beat.phase,
+            lock_report(timer)
+        ),
+        WatchdogAction::Recovered => info!("Display recovered after a stall"),
+        WatchdogAction::GiveUp { behind } => error!(
+            "Display still stalled {behind:.1?} after recovery ({})",
+            lock_report(timer)
+        ),
+    }
+}
 
-            glib::ControlFlow::Continue
-        });
+/// Restarts the refresh loop, or tells the user it can't be, as the
+/// watchdog's thread finds stalls.
+fn act_on_stalls(
+    found: Receiver<WatchdogAction>,
+    parts: TimerParts,
+    heartbeat: SharedHeartbeat,
+    refresh_source: Rc<RefCell<Option<glib::SourceId>>>,
+    overlay: Overlay,
+    banner: Banner,
+) -> glib::SourceId {
+    glib::timeout_add_local(WATCHDOG_INTERVAL, move || {
+        while let Ok(action) = found.try_recv() {
+            match action {
+                WatchdogAction::Recover { .. } => {
+                    if let Some(id) = refresh_source.take() {
+                        remove_source(&id);
+                    }
+                    let ctx = TuxSplitContext::get_instance();
+                    let shared = ctx.timer();
+                    // Whoever holds the timer may be what stalled, don't wait on it
+                    let timer = shared.try_read().ok().map(|timer| timer.clone());
+                    if let Ok(config) = ctx.try_config()
+                        && let Some(timer) = timer
+                    {
+                        parts.full_resync(&timer, &config);
+                    }
+                    spawn_tick(&parts, &heartbeat, &refresh_source);
+                }
+                WatchdogAction::Recovered => banner.set_revealed(true),
+                WatchdogAction::GiveUp { .. } => {
+                    let dialog = AlertDialog::builder()
+                        .heading("Display Stalled")
+                        .body(
+                            "The timer display stopped updating and could not be recovered. \
+                             The timer itself keeps running. Please restart TuxSplit and \
+                             report the logs.",
+                        )
+                        .default_response("ok")
+                        .build();
+                    dialog.add_response("ok", "Okay");
+                    dialog.present(Some(&overlay));
+                }
+            }
+        }
+        glib::ControlFlow::Continue
+    })
+}
 
-        self.refresh_source = Some(source_id);
+/// Removes a source that may already be gone, which `SourceId::remove` would panic on.
+fn remove_source(id: &glib::SourceId) {
+    if let Some(source) = glib::MainContext::default().find_source_by_id(id) {
+        source.destroy();
     }
+}
 
-    pub fn stop_refresh_loop(&mut self) {
-        if let Some(id) = self.refresh_source.take() {
-            id.remove();
+/// Who is holding the timer, for stall reports.
+fn lock_report(timer: &RwLock<Timer>) -> String {
+    let state = match timer.try_read() {
+        Ok(_) => "free",
+        Err(TryLockError::WouldBlock) => "write locked",
+        Err(TryLockError::Poisoned(_)) => "poisoned",
+    };
+    format!("timer {state}")
+}
+
+/// Starts the refresh ticks, keeping the pending source in `source`.
+fn spawn_tick(
+    parts: &TimerParts,
+    heartbeat: &SharedHeartbeat,
+    source: &Rc<RefCell<Option<glib::SourceId>>>,
+) {
+    let header_binding = parts.header.clone();
+    let body_binding = parts.body.clone();
+    let footer_binding = parts.footer.clone();
+    let stats_label = parts.stats_label.clone();
+    let heartbeat = heartbeat.clone();
+    let mut stats = FrameStats::default();
+    let mut ticks_since_label = 0;
+    let mut clock_guard = ClockJumpGuard::default();
+
+    let frame = move || {
+        let ctx = TuxSplitContext::get_instance();
+        // Formatting only needs a shared borrow. If a settings callback is
+        // holding the config mutably, skip this frame instead of panicking.
+        let Ok(c) = ctx.try_config() else {
+            return;
+        };
+        // A dialog is asking about what's on screen, keep showing that
+        if let Some(snapshot) = modal::frozen_snapshot() {
+            *heartbeat.lock().unwrap() = Heartbeat::now(snapshot.phase);
+            return;
+        }
+        let span = tick_span();
+        let _entered = span.enter();
+        let mut clock = PhaseClock::start();
+        let t = {
+            let shared = ctx.timer();
+            shared.read().unwrap().clone()
+        };
+        let snapshot = clock.lap();
+        let source =
+            if c.general.auto_splitter.is_some() && !hotkey_state().global_active(&c.behavior) {
+                ActionSource::AutoSplitter
+            } else {
+                ActionSource::Outside
+            };
+        trace_outside_change(&t, source);
+        match clock_guard.check(&t, Instant::now()) {
+            ClockCheck::Draw => {}
+            ClockCheck::Skip { jump, first } => {
+                if first {
+                    warn!("The attempt duration jumped by {jump}, holding the last frame");
+                }
+                *heartbeat.lock().unwrap() = Heartbeat::now(t.current_phase());
+                return;
+            }
+            ClockCheck::Accept { jump } => {
+                info!("The attempt duration kept a jump of {jump}, showing it");
+            }
+        }
+
+        header_binding.borrow_mut().refresh(&t, &c);
+        let header = clock.lap();
+        body_binding.borrow_mut().refresh(&t, &c, false);
+        let body = clock.lap();
+        footer_binding.borrow_mut().refresh(&t, &c);
+        let footer = clock.lap();
+        // Other windows draw the same frame
+        publish_tick(&t, &c);
+
+        let timings = TickTimings {
+            snapshot,
+            header,
+            body,
+            footer,
+        };
+        stats.record(timings);
+        record_tick(&span, t.current_phase(), &timings);
+        stats.warn_if_over_budget(&timings, Duration::from_millis(c.debug.frame_budget_ms));
+
+        stats_label.set_visible(c.debug.frame_stats);
+        ticks_since_label += 1;
+        if c.debug.frame_stats && ticks_since_label >= STATS_LABEL_INTERVAL {
+            ticks_since_label = 0;
+            stats_label.set_label(&stats.summary());
         }
+
+        *heartbeat.lock().unwrap() = Heartbeat::now(t.current_phase());
+    };
+
+    let aligned = TuxSplitContext::get_instance()
+        .try_config()
+        .is_ok_and(|c| c.debug.align_ticks);
+    if aligned {
+        let aligner = TickAligner::new(Instant::now(), TICK_INTERVAL);
+        schedule_aligned(Rc::new(RefCell::new(frame)), aligner, source.clone());
+    } else {
+        let mut frame = frame;
+        source.replace(Some(glib::timeout_add_local(TICK_INTERVAL, move || {
+            frame();
+            glib::ControlFlow::Continue
+        })));
     }
 }
+
+/// Runs `frame` on the next boundary of `aligner`, then schedules the one
+/// after that.
+fn schedule_aligned(
+    frame: Rc<RefCell<dyn FnMut()>>,
+    aligner: TickAligner,
+    source: Rc<RefCell<Option<glib::SourceId>>>,
+) {
+    let pending = source.clone();
+    let id = glib::timeout_add_local_once(aligner.next_delay(Instant::now()), move || {
+        (frame.borrow_mut())();
+        schedule_aligned(frame, aligner, pending);
+    });
+    source.replace(Some(id));
+}
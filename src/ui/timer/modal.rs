@@ -0,0 +1,297 @@
+//! Freezing the display while a dialog waits for an answer.
+//!
+//! A dialog presented with [`present_modal`] captures a [`DisplaySnapshot`]
+//! of the timer, and the refresh loop leaves the widgets as they were until
+//! the dialog closes, so numbers don't keep moving under a question about
+//! them.
+//! Hotkeys pressed meanwhile are queued or rejected depending on
+//! `behavior.actions-during-dialog`. The global hotkey system can't hold an
+//! action back, so it is paused while the dialog is open.
+
+use std::cell::RefCell;
+
+use adw::prelude::*;
+use adw::{Toast, ToastOverlay};
+use livesplit_core::{Time, TimeSpan, Timer, TimerPhase};
+use time::Duration as TimeDuration;
+use tracing::{debug, info};
+
+use crate::config::DialogActionPolicy;
+use crate::context::TuxSplitContext;
+use crate::ui::hotkeys::{self, ActionSource, HotkeyAction, run_action};
+use crate::utils::video_offset::{
+    displayed_attempt_duration, shift_time_for_display, video_offset,
+};
+
+thread_local! {
+    static FREEZE: RefCell<ModalFreeze> = RefCell::new(ModalFreeze::default());
+    static TOASTS: RefCell<Option<ToastOverlay>> = const { RefCell::new(None) };
+}
+
+/// What the display showed when a dialog opened. A cloned `Timer` would
+/// keep running, so the values are copied out instead, with the live times
+/// shifted back by the video offset like the rest of the display.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplaySnapshot {
+    pub phase: TimerPhase,
+    pub split_index: Option<usize>,
+    pub attempt_duration: TimeSpan,
+    pub current_time: Time,
+    /// The segment being run, the first one before the start and the last
+    /// one once the run is over.
+    pub current_split_name: Option<String>,
+    /// The segment after it, none once the run is over.
+    pub next_split_name: Option<String>,
+}
+
+impl DisplaySnapshot {
+    pub fn capture(timer: &Timer, video_offset: TimeDuration) -> Self {
+        let phase = timer.current_phase();
+        let segments = timer.run().segments();
+        let current = match phase {
+            TimerPhase::NotRunning => Some(0),
+            TimerPhase::Ended => segments.len().checked_sub(1),
+            _ => timer.current_split_index(),
+        };
+        let name = |index: usize| segments.get(index).map(|segment| segment.name().to_owned());
+        Self {
+            phase,
+            split_index: timer.current_split_index(),
+            attempt_duration: displayed_attempt_duration(timer, video_offset).into(),
+            current_time: shift_time_for_display(
+                timer.snapshot().current_time(),
+                phase,
+                video_offset,
+            ),
+            current_split_name: current.and_then(name),
+            next_split_name: current
+                .filter(|_| phase != TimerPhase::Ended)
+                .and_then(|index| name(index + 1)),
+        }
+    }
+}
+
+/// What happened to a hotkey action offered while the display may be frozen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionDisposition {
+    /// Nothing is pending, run it now.
+    Run,
+    Queued,
+    Rejected,
+}
+
+/// Pending dialogs and the snapshot they share. Dialogs can stack, the
+/// snapshot from the first one is kept until the last one closes.
+#[derive(Default)]
+pub struct ModalFreeze {
+    depth: usize,
+    snapshot: Option<DisplaySnapshot>,
+    queued: Vec<HotkeyAction>,
+}
+
+impl ModalFreeze {
+    /// Returns true when this starts the freeze.
+    pub fn begin(&mut self, snapshot: DisplaySnapshot) -> bool {
+        self.depth += 1;
+        if self.snapshot.is_some() {
+            return false;
+        }
+        self.snapshot = Some(snapshot);
+        true
+    }
+
+    /// Returns the queued actions once the last dialog closed, or `None`
+    /// while the display stays frozen.
+    pub fn end(&mut self) -> Option<Vec<HotkeyAction>> {
+        if self.depth == 0 {
+            return None;
+        }
+        self.depth -= 1;
+        if self.depth > 0 {
+            return None;
+        }
+        self.snapshot = None;
+        Some(std::mem::take(&mut self.queued))
+    }
+
+    pub fn snapshot(&self) -> Option<&DisplaySnapshot> {
+        self.snapshot.as_ref()
+    }
+
+    pub fn offer(&mut self, action: HotkeyAction, policy: DialogActionPolicy) -> ActionDisposition {
+        if self.snapshot.is_none() {
+            return ActionDisposition::Run;
+        }
+        match policy {
+            DialogActionPolicy::Queue => {
+                self.queued.push(action);
+                ActionDisposition::Queued
+            }
+            DialogActionPolicy::Reject => ActionDisposition::Rejected,
+        }
+    }
+}
+
+/// Where rejected actions and [`show_notice`] toasts are reported.
+pub fn set_toast_overlay(toasts: &ToastOverlay) {
+    TOASTS.replace(Some(toasts.clone()));
+}
+
+/// Shows `title` in a toast on the main window, for the parts of the timer
+/// that aren't handed the overlay.
+pub fn show_notice(title: &str) {
+    TOASTS.with_borrow(|toasts| {
+        if let Some(toasts) = toasts {
+            toasts.add_toast(Toast::new(title));
+        }
+    });
+}
+
+/// The snapshot the display is frozen at while a dialog is pending.
+pub fn frozen_snapshot() -> Option<DisplaySnapshot> {
+    FREEZE.with_borrow(|freeze| freeze.snapshot().cloned())
+}
+
+fn set_modal_pending(pending: bool) {
+    let ctx = TuxSplitContext::get_instance();
+    let Ok(config) = ctx.try_config() else {
+        // Someone is changing the config, try again once they are done
+        glib::idle_add_local_once(move || set_modal_pending(pending));
+        return;
+    };
+    hotkeys::set_modal_pending(&config, pending);
+}
+
+pub fn begin_modal() {
+    let snapshot = {
+        let ctx = TuxSplitContext::get_instance();
+        let offset = video_offset(&ctx.config());
+        let shared = ctx.timer();
+        let timer = shared.read().unwrap();
+        DisplaySnapshot::capture(&timer, offset)
+    };
+    if FREEZE.with_borrow_mut(|freeze| freeze.begin(snapshot)) {
+        debug!("Display frozen while a dialog is open");
+        set_modal_pending(true);
+    }
+}
+
+pub fn end_modal() {
+    let Some(queued) = FREEZE.with_borrow_mut(ModalFreeze::end) else {
+        return;
+    };
+    debug!("Display resumed");
+    set_modal_pending(false);
+    if !queued.is_empty() {
+        info!(
+            "Running {} hotkey action(s) held during a dialog",
+            queued.len()
+        );
+    }
+    for action in queued {
+        run_action(action, ActionSource::HeldDuringDialog);
+    }
+}
+
+/// Runs `action` unless a dialog is pending, in which case it's queued or
+/// rejected with a toast.
+pub fn offer_action(action: HotkeyAction, source: ActionSource) {
+    let policy = TuxSplitContext::get_instance()
+        .config()
+        .behavior
+        .actions_during_dialog;
+    match FREEZE.with_borrow_mut(|freeze| freeze.offer(action, policy)) {
+        ActionDisposition::Run => run_action(action, source),
+        ActionDisposition::Queued => {}
+        ActionDisposition::Rejected => show_notice("Answer the open dialog first"),
+    }
+}
+
+/// Presents `dialog` with the display frozen until it closes.
+pub fn present_modal(dialog: &impl IsA<adw::Dialog>, parent: &impl IsA<gtk4::Widget>) {
+    begin_modal();
+    dialog.connect_closed(|_| end_modal());
+    dialog.present(Some(parent));
+}
+
+#[cfg(test)]
+mod modal_tests {
+    use super::*;
+    use livesplit_core::{Run, Segment};
+
+    fn snapshot() -> DisplaySnapshot {
+        let mut run = Run::new();
+        run.push_segment(Segment::new("One"));
+        DisplaySnapshot::capture(&Timer::new(run).unwrap(), TimeDuration::ZERO)
+    }
+
+    #[test]
+    fn freezes_until_the_dialog_closes() {
+        let mut freeze = ModalFreeze::default();
+        assert!(freeze.snapshot().is_none());
+        assert!(freeze.begin(snapshot()));
+        assert_eq!(
+            freeze.snapshot().map(|snapshot| snapshot.phase),
+            Some(TimerPhase::NotRunning)
+        );
+        assert_eq!(freeze.end(), Some(vec![]));
+        assert!(freeze.snapshot().is_none());
+        // A stray close doesn't underflow
+        assert_eq!(freeze.end(), None);
+    }
+
+    #[test]
+    fn stacked_dialogs_keep_the_first_snapshot() {
+        let mut freeze = ModalFreeze::default();
+        assert!(freeze.begin(snapshot()));
+        assert!(!freeze.begin(snapshot()));
+        assert_eq!(freeze.end(), None);
+        assert!(freeze.snapshot().is_some());
+        assert_eq!(freeze.end(), Some(vec![]));
+        assert!(freeze.snapshot().is_none());
+    }
+
+    #[test]
+    fn actions_run_directly_without_a_dialog() {
+        let mut freeze = ModalFreeze::default();
+        for policy in [DialogActionPolicy::Queue, DialogActionPolicy::Reject] {
+            assert_eq!(
+                freeze.offer(HotkeyAction::Split, policy),
+                ActionDisposition::Run
+            );
+        }
+    }
+
+    #[test]
+    fn queued_actions_come_back_in_order_on_close() {
+        let mut freeze = ModalFreeze::default();
+        freeze.begin(snapshot());
+        let policy = DialogActionPolicy::Queue;
+        assert_eq!(
+            freeze.offer(HotkeyAction::Split, policy),
+            ActionDisposition::Queued
+        );
+        assert_eq!(
+            freeze.offer(HotkeyAction::Pause, policy),
+            ActionDisposition::Queued
+        );
+        assert_eq!(
+            freeze.end(),
+            Some(vec![HotkeyAction::Split, HotkeyAction::Pause])
+        );
+        // Nothing left over for the next dialog
+        freeze.begin(snapshot());
+        assert_eq!(freeze.end(), Some(vec![]));
+    }
+
+    #[test]
+    fn rejected_actions_are_not_kept() {
+        let mut freeze = ModalFreeze::default();
+        freeze.begin(snapshot());
+        assert_eq!(
+            freeze.offer(HotkeyAction::Reset, DialogActionPolicy::Reject),
+            ActionDisposition::Rejected
+        );
+        assert_eq!(freeze.end(), Some(vec![]));
+    }
+}
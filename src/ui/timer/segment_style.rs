@@ -0,0 +1,148 @@
+//! Per-segment style overrides from `style.segment-classes`.
+//!
+//! Keys are segment names or 0-based segment indices. Names win over indices,
+//! so an override keyed by name follows its segment when splits are added or
+//! reordered. Values are either a CSS class from a user stylesheet or a hex
+//! color, which becomes a generated `segment-color-*` class tinting the row.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use gtk4::{CssProvider, STYLE_PROVIDER_PRIORITY_APPLICATION, gdk::Display};
+
+thread_local! {
+    static PROVIDER: RefCell<Option<(CssProvider, String)>> = const { RefCell::new(None) };
+}
+
+/// Prefix of the classes generated for color overrides.
+const COLOR_CLASS_PREFIX: &str = "segment-color-";
+
+/// The configured value for a segment, preferring its name over its index.
+pub fn override_value<'a>(
+    overrides: &'a BTreeMap<String, String>,
+    index: usize,
+    name: &str,
+) -> Option<&'a str> {
+    overrides
+        .get(name)
+        .or_else(|| overrides.get(&index.to_string()))
+        .map(String::as_str)
+}
+
+/// The hex digits of a `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa` color.
+fn hex_color(value: &str) -> Option<&str> {
+    let digits = value.strip_prefix('#')?;
+    (matches!(digits.len(), 3 | 4 | 6 | 8) && digits.chars().all(|c| c.is_ascii_hexdigit()))
+        .then_some(digits)
+}
+
+/// The class to put on a row for an override value. Characters that can't
+/// appear in a class name are dropped.
+pub fn override_class(value: &str) -> Option<String> {
+    if let Some(digits) = hex_color(value.trim()) {
+        return Some(format!(
+            "{COLOR_CLASS_PREFIX}{}",
+            digits.to_ascii_lowercase()
+        ));
+    }
+    let class: String = value
+        .trim()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        .collect();
+    (!class.is_empty()).then_some(class)
+}
+
+/// The class for the segment at `index` named `name`, if any.
+pub fn segment_class(
+    overrides: &BTreeMap<String, String>,
+    index: usize,
+    name: &str,
+) -> Option<String> {
+    override_value(overrides, index, name).and_then(override_class)
+}
+
+/// Rules for the color overrides. Plain classes are left to the user's
+/// stylesheet.
+pub fn override_css(overrides: &BTreeMap<String, String>) -> String {
+    let mut colors: Vec<String> = overrides
+        .values()
+        .filter_map(|value| hex_color(value.trim()))
+        .map(str::to_ascii_lowercase)
+        .collect();
+    colors.sort();
+    colors.dedup();
+    colors
+        .iter()
+        .map(|digits| {
+            format!(
+                "row.{COLOR_CLASS_PREFIX}{digits} {{ background-color: alpha(#{digits}, 0.25); }}\n"
+            )
+        })
+        .collect()
+}
+
+/// Loads the color override rules, reloading only when they changed.
+pub fn apply_segment_styles(overrides: &BTreeMap<String, String>) {
+    let css = override_css(overrides);
+    PROVIDER.with_borrow_mut(|provider| {
+        if provider.is_none() {
+            let Some(display) = Display::default() else {
+                return;
+            };
+            let css_provider = CssProvider::new();
+            gtk4::style_context_add_provider_for_display(
+                &display,
+                &css_provider,
+                STYLE_PROVIDER_PRIORITY_APPLICATION + 1,
+            );
+            *provider = Some((css_provider, String::new()));
+        }
+        if let Some((css_provider, loaded)) = provider.as_mut()
+            && *loaded != css
+        {
+            css_provider.load_from_string(&css);
+            *loaded = css;
+        }
+    });
+}
+
+#[cfg(test)]
+mod segment_style_tests {
+    use super::*;
+
+    fn overrides(items: &[(&str, &str)]) -> BTreeMap<String, String> {
+        items
+            .iter()
+            .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn name_is_preferred_over_index() {
+        let map = overrides(&[("Boss", "boss-split"), ("2", "by-index")]);
+        assert_eq!(override_value(&map, 2, "Boss"), Some("boss-split"));
+        assert_eq!(override_value(&map, 2, "Hallway"), Some("by-index"));
+        assert_eq!(override_value(&map, 3, "Hallway"), None);
+    }
+
+    #[test]
+    fn colors_map_to_generated_classes() {
+        assert_eq!(
+            override_class("#E5A50A").as_deref(),
+            Some("segment-color-e5a50a")
+        );
+        assert_eq!(override_class("#f00").as_deref(), Some("segment-color-f00"));
+        assert_eq!(override_class("boss split!").as_deref(), Some("bosssplit"));
+        assert_eq!(override_class("  "), None);
+    }
+
+    #[test]
+    fn css_only_covers_colors_once() {
+        let map = overrides(&[("A", "#E5A50A"), ("B", "#e5a50a"), ("C", "boss")]);
+        assert_eq!(
+            override_css(&map),
+            "row.segment-color-e5a50a { background-color: alpha(#e5a50a, 0.25); }\n"
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! A large split button under the timer for touchscreens and handhelds,
+//! with `layout.show-split-button`.
+//!
+//! Pressing it is the split hotkey, offered through [`offer_action`] so an
+//! open dialog holds it back the same way. The button is left out of the
+//! layout entirely while the option is off.
+
+use gtk4::prelude::*;
+use gtk4::{Align, Button};
+use livesplit_core::{Timer, TimerPhase};
+
+use crate::config::Config;
+use crate::ui::hotkeys::{ActionSource, HotkeyAction};
+use crate::ui::timer::modal::offer_action;
+
+/// The button's label, and whether pressing it does anything, in `phase`.
+pub fn split_button_label(
+    phase: TimerPhase,
+    split_index: Option<usize>,
+    segment_count: usize,
+) -> (&'static str, bool) {
+    match phase {
+        TimerPhase::NotRunning => ("Start", true),
+        TimerPhase::Running if split_index.is_some_and(|index| index + 1 >= segment_count) => {
+            ("Finish", true)
+        }
+        TimerPhase::Running => ("Split", true),
+        // Splitting is ignored while paused or after the run
+        TimerPhase::Paused => ("Paused", false),
+        TimerPhase::Ended => ("Finished", false),
+    }
+}
+
+pub struct SplitButton {
+    button: Button,
+    shown_for: Option<(bool, i32, &'static str, bool)>,
+}
+
+impl SplitButton {
+    pub fn new() -> Self {
+        let button = Button::builder()
+            .hexpand(true)
+            .halign(Align::Fill)
+            .visible(false)
+            .focusable(false)
+            .css_classes(["split-button", "suggested-action"])
+            .build();
+        button.connect_clicked(|_| offer_action(HotkeyAction::Split, ActionSource::SplitButton));
+        Self {
+            button,
+            shown_for: None,
+        }
+    }
+
+    pub fn widget(&self) -> &Button {
+        &self.button
+    }
+
+    pub fn update(&mut self, timer: &Timer, config: &Config) {
+        let (label, sensitive) = split_button_label(
+            timer.current_phase(),
+            timer.current_split_index(),
+            timer.run().len(),
+        );
+        let shown_for = (
+            config.layout.show_split_button,
+            config.layout.split_button_height,
+            label,
+            sensitive,
+        );
+        if self.shown_for == Some(shown_for) {
+            return;
+        }
+        self.shown_for = Some(shown_for);
+        self.button.set_visible(config.layout.show_split_button);
+        self.button
+            .set_height_request(config.layout.split_button_height.max(1));
+        self.button.set_label(label);
+        self.button.set_sensitive(sensitive);
+    }
+}
+
+#[cfg(test)]
+mod split_button_tests {
+    use super::*;
+
+    #[test]
+    fn label_follows_the_phase() {
+        assert_eq!(
+            split_button_label(TimerPhase::NotRunning, None, 3),
+            ("Start", true)
+        );
+        assert_eq!(
+            split_button_label(TimerPhase::Running, Some(0), 3),
+            ("Split", true)
+        );
+        assert_eq!(
+            split_button_label(TimerPhase::Paused, Some(1), 3),
+            ("Paused", false)
+        );
+        assert_eq!(
+            split_button_label(TimerPhase::Ended, Some(3), 3),
+            ("Finished", false)
+        );
+    }
+
+    #[test]
+    fn last_segment_finishes_the_run() {
+        assert_eq!(
+            split_button_label(TimerPhase::Running, Some(2), 3),
+            ("Finish", true)
+        );
+        assert_eq!(
+            split_button_label(TimerPhase::Running, Some(0), 1),
+            ("Finish", true)
+        );
+    }
+}
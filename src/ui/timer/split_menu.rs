@@ -0,0 +1,555 @@
+//! Context menu on the split rows.
+//!
+//! Right-clicking a row, or pressing Menu / Shift+F10 on a focused one,
+//! opens a menu of actions on that segment while no attempt is in progress.
+//! D on a focused row drills the segment straight away.
+//! The menu model and its `split.*` actions are built once per list; the
+//! rows only tell the [`SplitMenuDispatcher`] which segment the menu was
+//! opened on, using the segment index they were built for rather than their
+//! position in the list, which differs when the order is reversed and for
+//! the final split's own list. What each action does is up to a
+//! [`SplitMenuHandler`].
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use adw::ActionRow;
+use adw::AlertDialog;
+use adw::prelude::*;
+use glib::Propagation;
+use gtk4::gio::{Menu, SimpleAction, SimpleActionGroup};
+use gtk4::{
+    Box as GtkBox, EventControllerKey, EventSequenceState, GestureClick, Label, ListBox,
+    PopoverMenu, gdk,
+};
+use livesplit_core::hotkey::{Hotkey, KeyCode, Modifiers};
+use livesplit_core::{Segment, Timer, TimerPhase, TimingMethod};
+
+use crate::context::TuxSplitContext;
+use crate::formatters::TimeFormat;
+use crate::ui::drill::start_drill;
+use crate::ui::hotkeys::action_for;
+use crate::utils::comparisons::{attempt_segment_duration, segment_comparison_duration};
+
+/// Name of the action group the menu's actions are installed under.
+const ACTION_GROUP: &str = "split";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitMenuAction {
+    History,
+    Drill,
+    CopySplitTime,
+    CopySegmentTime,
+}
+
+pub static ALL_SPLIT_MENU_ACTIONS: [SplitMenuAction; 4] = [
+    SplitMenuAction::History,
+    SplitMenuAction::Drill,
+    SplitMenuAction::CopySplitTime,
+    SplitMenuAction::CopySegmentTime,
+];
+
+impl SplitMenuAction {
+    pub fn name(self) -> &'static str {
+        match self {
+            SplitMenuAction::History => "history",
+            SplitMenuAction::Drill => "drill",
+            SplitMenuAction::CopySplitTime => "copy-split-time",
+            SplitMenuAction::CopySegmentTime => "copy-segment-time",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SplitMenuAction::History => "Segment History",
+            SplitMenuAction::Drill => "Drill Segment",
+            SplitMenuAction::CopySplitTime => "Copy Split Time",
+            SplitMenuAction::CopySegmentTime => "Copy Segment Time",
+        }
+    }
+}
+
+/// The menu is only offered outside an attempt, so it can't get in the way of splitting.
+pub fn menu_available(phase: TimerPhase) -> bool {
+    matches!(phase, TimerPhase::NotRunning | TimerPhase::Ended)
+}
+
+/// Sections of the menu: looking at the segment, copying from it.
+pub fn menu_model() -> Menu {
+    let menu = Menu::new();
+    for section_actions in [&ALL_SPLIT_MENU_ACTIONS[..2], &ALL_SPLIT_MENU_ACTIONS[2..]] {
+        let section = Menu::new();
+        for action in section_actions {
+            section.append(
+                Some(action.label()),
+                Some(&format!("{ACTION_GROUP}.{}", action.name())),
+            );
+        }
+        menu.append_section(None, &section);
+    }
+    menu
+}
+
+/// What the menu's actions do, for the segment at `index`.
+pub trait SplitMenuHandler {
+    fn is_enabled(&self, action: SplitMenuAction, index: usize) -> bool;
+    fn activate(&self, action: SplitMenuAction, index: usize);
+}
+
+/// Remembers which segment the menu was opened on and hands the chosen
+/// action to the handler with it.
+pub struct SplitMenuDispatcher {
+    handler: Box<dyn SplitMenuHandler>,
+    target: Cell<Option<usize>>,
+}
+
+impl SplitMenuDispatcher {
+    pub fn new(handler: impl SplitMenuHandler + 'static) -> Self {
+        Self {
+            handler: Box::new(handler),
+            target: Cell::new(None),
+        }
+    }
+
+    /// Points the menu at the segment `index`. Returns `false`, leaving the
+    /// menu closed, while an attempt is in progress.
+    pub fn open(&self, index: usize, phase: TimerPhase) -> bool {
+        if !menu_available(phase) {
+            self.target.set(None);
+            return false;
+        }
+        self.target.set(Some(index));
+        true
+    }
+
+    pub fn is_enabled(&self, action: SplitMenuAction) -> bool {
+        self.target
+            .get()
+            .is_some_and(|index| self.handler.is_enabled(action, index))
+    }
+
+    /// Runs `action` on the segment the menu was opened on. Returns whether
+    /// the handler was called.
+    pub fn dispatch(&self, action: SplitMenuAction) -> bool {
+        match self.target.get() {
+            Some(index) if self.handler.is_enabled(action, index) => {
+                self.handler.activate(action, index);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The split time copied for the row at `index`: the one recorded in this
+/// attempt, else the current comparison's.
+pub fn split_time_to_copy(timer: &Timer, index: usize) -> Option<time::Duration> {
+    let segment = timer.run().segments().get(index)?;
+    let method = timer.current_timing_method();
+    segment.split_time()[method]
+        .or_else(|| segment.comparison(timer.current_comparison())[method])
+        .map(|time| time.to_duration())
+}
+
+/// The segment time copied for the row at `index`, recorded or compared
+/// against like [`split_time_to_copy`]. Copies are the times as measured,
+/// without the video offset.
+pub fn segment_time_to_copy(timer: &Timer, index: usize) -> Option<time::Duration> {
+    attempt_segment_duration(timer, index, time::Duration::ZERO)
+        .or_else(|| segment_comparison_duration(timer, index))
+}
+
+/// Rows of the history dialog, newest attempt first.
+pub fn history_rows(
+    segment: &Segment,
+    method: TimingMethod,
+    format: &TimeFormat,
+) -> Vec<(String, String)> {
+    let mut history: Vec<_> = segment.segment_history().iter().copied().collect();
+    history.sort_by_key(|(id, _)| std::cmp::Reverse(*id));
+    history
+        .into_iter()
+        .map(|(id, time)| {
+            let value = time[method].map_or_else(
+                || "Skipped".to_owned(),
+                |time| format.format_duration(&time.to_duration()),
+            );
+            (format!("Attempt {id}"), value)
+        })
+        .collect()
+}
+
+/// The actions as they work on the loaded run.
+pub struct TimerSplitActions {
+    anchor: glib::WeakRef<GtkBox>,
+}
+
+impl TimerSplitActions {
+    pub fn new(anchor: &GtkBox) -> Self {
+        Self {
+            anchor: anchor.downgrade(),
+        }
+    }
+}
+
+impl SplitMenuHandler for TimerSplitActions {
+    fn is_enabled(&self, action: SplitMenuAction, index: usize) -> bool {
+        let ctx = TuxSplitContext::get_instance();
+        let shared = ctx.timer();
+        let Ok(timer) = shared.read() else {
+            return false;
+        };
+        match action {
+            SplitMenuAction::History => timer
+                .run()
+                .segments()
+                .get(index)
+                .is_some_and(|segment| segment.segment_history().iter().next().is_some()),
+            SplitMenuAction::CopySplitTime => split_time_to_copy(&timer, index).is_some(),
+            SplitMenuAction::CopySegmentTime => segment_time_to_copy(&timer, index).is_some(),
+            SplitMenuAction::Drill => {
+                timer.current_phase() == TimerPhase::NotRunning
+                    && index < timer.run().segments().len()
+            }
+        }
+    }
+
+    fn activate(&self, action: SplitMenuAction, index: usize) {
+        let Some(anchor) = self.anchor.upgrade() else {
+            return;
+        };
+        if action == SplitMenuAction::Drill {
+            start_drill(index);
+            return;
+        }
+        let ctx = TuxSplitContext::get_instance();
+        let config = ctx.config();
+        let shared = ctx.timer();
+        let Ok(timer) = shared.read() else {
+            return;
+        };
+        match action {
+            SplitMenuAction::History => {
+                if let Some(segment) = timer.run().segments().get(index) {
+                    let rows = history_rows(
+                        segment,
+                        timer.current_timing_method(),
+                        &config.format.segment,
+                    );
+                    present_history(&anchor, segment.name(), rows);
+                }
+            }
+            SplitMenuAction::CopySplitTime => {
+                if let Some(time) = split_time_to_copy(&timer, index) {
+                    anchor
+                        .clipboard()
+                        .set_text(&config.format.segment.format_duration(&time));
+                }
+            }
+            SplitMenuAction::CopySegmentTime => {
+                if let Some(time) = segment_time_to_copy(&timer, index) {
+                    anchor
+                        .clipboard()
+                        .set_text(&config.format.segment.format_duration(&time));
+                }
+            }
+            SplitMenuAction::Drill => {}
+        }
+    }
+}
+
+fn present_history(parent: &GtkBox, name: &str, rows: Vec<(String, String)>) {
+    let dialog = AlertDialog::builder()
+        .heading(name)
+        .body("Times of this segment, newest attempt first.")
+        .default_response("ok")
+        .build();
+    dialog.add_response("ok", "Okay");
+
+    let history_list = ListBox::new();
+    history_list.add_css_class("boxed-list");
+    for (attempt, time) in rows {
+        let time_label = Label::new(Some(&time));
+        time_label.add_css_class("monospace");
+        if time == "Skipped" {
+            time_label.add_css_class("dim-label");
+        }
+        let row = adw::ActionRow::builder().title(attempt).build();
+        row.add_suffix(&time_label);
+        history_list.append(&row);
+    }
+    let scrolled = gtk4::ScrolledWindow::builder()
+        .min_content_height(240)
+        .propagate_natural_height(true)
+        .child(&history_list)
+        .build();
+    dialog.set_extra_child(Some(&scrolled));
+
+    dialog.present(Some(parent));
+}
+
+fn current_phase() -> Option<TimerPhase> {
+    let ctx = TuxSplitContext::get_instance();
+    let shared = ctx.timer();
+    let timer = shared.read().ok()?;
+    Some(timer.current_phase())
+}
+
+/// D drills the focused split, unless it's bound as a hotkey.
+fn drill_key_free() -> bool {
+    let drill = Hotkey {
+        key_code: KeyCode::KeyD,
+        modifiers: Modifiers::empty(),
+    };
+    TuxSplitContext::get_instance()
+        .try_config()
+        .is_ok_and(|config| action_for(config.hotkeys.active(), drill).is_none())
+}
+
+/// The menu of one split list, shared by all of its rows.
+pub struct SplitMenu {
+    model: Menu,
+    dispatcher: Rc<SplitMenuDispatcher>,
+    actions: Vec<(SplitMenuAction, SimpleAction)>,
+}
+
+impl SplitMenu {
+    /// Builds the menu and installs its actions on `container`, which holds
+    /// the rows it will be attached to.
+    pub fn new(container: &GtkBox, handler: impl SplitMenuHandler + 'static) -> Rc<Self> {
+        let dispatcher = Rc::new(SplitMenuDispatcher::new(handler));
+        let group = SimpleActionGroup::new();
+        let actions = ALL_SPLIT_MENU_ACTIONS
+            .iter()
+            .map(|&action| {
+                let simple = SimpleAction::new(action.name(), None);
+                let dispatcher = dispatcher.clone();
+                simple.connect_activate(move |_, _| {
+                    dispatcher.dispatch(action);
+                });
+                group.add_action(&simple);
+                (action, simple)
+            })
+            .collect();
+        container.insert_action_group(ACTION_GROUP, Some(&group));
+
+        Rc::new(Self {
+            model: menu_model(),
+            dispatcher,
+            actions,
+        })
+    }
+
+    /// Lets `row`, built for segment `index`, open the menu with the
+    /// secondary button or the keyboard.
+    pub fn attach(self: &Rc<Self>, row: &ActionRow, index: usize) {
+        let click = GestureClick::builder()
+            .button(gdk::BUTTON_SECONDARY)
+            .build();
+        let menu = Rc::downgrade(self);
+        click.connect_pressed(move |gesture, _, x, y| {
+            if let Some(menu) = menu.upgrade()
+                && let Some(row) = gesture.widget().and_downcast::<ActionRow>()
+                && menu.popup(&row, index, x, y)
+            {
+                gesture.set_state(EventSequenceState::Claimed);
+            }
+        });
+        row.add_controller(click);
+
+        let keys = EventControllerKey::new();
+        let menu = Rc::downgrade(self);
+        keys.connect_key_pressed(move |controller, keyval, _, state| {
+            let shift_f10 =
+                keyval == gdk::Key::F10 && state.contains(gdk::ModifierType::SHIFT_MASK);
+            if (keyval == gdk::Key::Menu || shift_f10)
+                && let Some(menu) = menu.upgrade()
+                && let Some(row) = controller.widget().and_downcast::<ActionRow>()
+                && menu.popup(
+                    &row,
+                    index,
+                    f64::from(row.width()) / 2.0,
+                    f64::from(row.height()) / 2.0,
+                )
+            {
+                return Propagation::Stop;
+            }
+            if keyval.to_lower() == gdk::Key::d
+                && !state.intersects(
+                    gdk::ModifierType::SHIFT_MASK
+                        | gdk::ModifierType::CONTROL_MASK
+                        | gdk::ModifierType::ALT_MASK
+                        | gdk::ModifierType::SUPER_MASK,
+                )
+                && drill_key_free()
+                && let Some(menu) = menu.upgrade()
+                && menu.run(SplitMenuAction::Drill, index)
+            {
+                return Propagation::Stop;
+            }
+            Propagation::Proceed
+        });
+        row.add_controller(keys);
+    }
+
+    /// Runs `action` on segment `index` without opening the menu.
+    fn run(&self, action: SplitMenuAction, index: usize) -> bool {
+        current_phase().is_some_and(|phase| self.dispatcher.open(index, phase))
+            && self.dispatcher.dispatch(action)
+    }
+
+    fn popup(&self, row: &ActionRow, index: usize, x: f64, y: f64) -> bool {
+        let Some(phase) = current_phase() else {
+            return false;
+        };
+        if !self.dispatcher.open(index, phase) {
+            return false;
+        }
+        for (action, simple) in &self.actions {
+            simple.set_enabled(self.dispatcher.is_enabled(*action));
+        }
+
+        let popover = PopoverMenu::from_model(Some(&self.model));
+        popover.set_parent(row);
+        popover.set_has_arrow(false);
+        popover.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+        popover.connect_closed(|popover| {
+            // Unparenting inside the signal would drop it before the chosen action runs
+            let popover = popover.clone();
+            glib::idle_add_local_once(move || popover.unparent());
+        });
+        popover.popup();
+        true
+    }
+}
+
+#[cfg(test)]
+mod split_menu_tests {
+    use super::*;
+    use crate::test_support::real_time;
+    use livesplit_core::{Run, Time};
+    use std::cell::RefCell;
+
+    /// Records what it was asked to do.
+    #[derive(Default)]
+    struct FakeHandler {
+        calls: Rc<RefCell<Vec<(SplitMenuAction, usize)>>>,
+    }
+
+    impl SplitMenuHandler for FakeHandler {
+        fn is_enabled(&self, action: SplitMenuAction, _index: usize) -> bool {
+            action != SplitMenuAction::Drill
+        }
+
+        fn activate(&self, action: SplitMenuAction, index: usize) {
+            self.calls.borrow_mut().push((action, index));
+        }
+    }
+
+    fn dispatcher() -> (
+        SplitMenuDispatcher,
+        Rc<RefCell<Vec<(SplitMenuAction, usize)>>>,
+    ) {
+        let handler = FakeHandler::default();
+        let calls = handler.calls.clone();
+        (SplitMenuDispatcher::new(handler), calls)
+    }
+
+    #[test]
+    fn actions_reach_the_handler_with_the_rows_segment_index() {
+        let (dispatcher, calls) = dispatcher();
+        assert!(dispatcher.open(4, TimerPhase::NotRunning));
+        assert!(dispatcher.dispatch(SplitMenuAction::CopySplitTime));
+        assert!(dispatcher.open(0, TimerPhase::Ended));
+        assert!(dispatcher.dispatch(SplitMenuAction::History));
+        assert_eq!(
+            *calls.borrow(),
+            vec![
+                (SplitMenuAction::CopySplitTime, 4),
+                (SplitMenuAction::History, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn nothing_is_dispatched_during_an_attempt() {
+        let (dispatcher, calls) = dispatcher();
+        assert!(!dispatcher.dispatch(SplitMenuAction::History));
+        dispatcher.open(2, TimerPhase::NotRunning);
+        for phase in [TimerPhase::Running, TimerPhase::Paused] {
+            // Opening during an attempt forgets the previous row too
+            assert!(!dispatcher.open(2, phase));
+            assert!(!dispatcher.is_enabled(SplitMenuAction::History));
+            assert!(!dispatcher.dispatch(SplitMenuAction::History));
+        }
+        assert!(calls.borrow().is_empty());
+    }
+
+    #[test]
+    fn disabled_actions_are_not_dispatched() {
+        let (dispatcher, calls) = dispatcher();
+        dispatcher.open(1, TimerPhase::NotRunning);
+        assert!(!dispatcher.is_enabled(SplitMenuAction::Drill));
+        assert!(!dispatcher.dispatch(SplitMenuAction::Drill));
+        assert!(calls.borrow().is_empty());
+    }
+
+    #[test]
+    fn model_lists_every_action_once() {
+        let model = menu_model();
+        let items: i32 = (0..model.n_items())
+            .filter_map(|i| model.item_link(i, gtk4::gio::MENU_LINK_SECTION))
+            .map(|section| section.n_items())
+            .sum();
+        assert_eq!(items, ALL_SPLIT_MENU_ACTIONS.len() as i32);
+
+        let mut names: Vec<_> = ALL_SPLIT_MENU_ACTIONS.iter().map(|a| a.name()).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), ALL_SPLIT_MENU_ACTIONS.len());
+    }
+
+    #[test]
+    fn copied_times_prefer_the_attempt_over_the_comparison() {
+        let mut run = Run::new();
+        for (name, pb) in [("A", 10.0), ("B", 25.0)] {
+            let mut segment = Segment::new(name);
+            segment.set_personal_best_split_time(real_time(pb));
+            run.push_segment(segment);
+        }
+        let mut timer = Timer::new(run).unwrap();
+        assert_eq!(
+            split_time_to_copy(&timer, 1),
+            Some(time::Duration::seconds(25))
+        );
+        assert_eq!(
+            segment_time_to_copy(&timer, 1),
+            Some(time::Duration::seconds(15))
+        );
+        assert_eq!(split_time_to_copy(&timer, 2), None);
+
+        timer.start();
+        timer.split();
+        timer.split();
+        assert_eq!(timer.current_phase(), TimerPhase::Ended);
+        let recorded = timer.run().segments()[1].split_time()[TimingMethod::RealTime]
+            .map(|time| time.to_duration());
+        assert_eq!(split_time_to_copy(&timer, 1), recorded);
+        assert_ne!(
+            split_time_to_copy(&timer, 1),
+            Some(time::Duration::seconds(25))
+        );
+    }
+
+    #[test]
+    fn history_is_listed_newest_first() {
+        let mut segment = Segment::new("A");
+        segment.segment_history_mut().insert(1, real_time(12.0));
+        segment.segment_history_mut().insert(3, real_time(10.0));
+        segment.segment_history_mut().insert(2, Time::default());
+        let rows = history_rows(&segment, TimingMethod::RealTime, &TimeFormat::default());
+        let attempts: Vec<_> = rows.iter().map(|(attempt, _)| attempt.as_str()).collect();
+        assert_eq!(attempts, ["Attempt 3", "Attempt 2", "Attempt 1"]);
+        assert_eq!(rows[1].1, "Skipped");
+    }
+}
@@ -0,0 +1,228 @@
+//! The name of the segment being run, shown above the running timer when
+//! `layout.show-current-split-name` is on. The detached timer has its own
+//! override in `detached-timer.show-current-split-name`.
+//!
+//! The text comes from the [`DisplaySnapshot`] of the frame, and the labels
+//! only change when the phase or the split index does.
+
+use gtk4::pango::EllipsizeMode;
+use gtk4::prelude::*;
+use gtk4::{Align, Box as GtkBox, Label, Orientation};
+use livesplit_core::{Timer, TimerPhase};
+
+use crate::config::{Config, LayoutOptions, SplitNameBeforeStart};
+use crate::ui::timer::modal::DisplaySnapshot;
+use crate::utils::video_offset::video_offset;
+
+/// The name line and the optional next segment line for `snapshot`, `None`
+/// when nothing is shown.
+pub fn split_name_lines(
+    snapshot: &DisplaySnapshot,
+    layout: &LayoutOptions,
+) -> Option<(String, Option<String>)> {
+    let current = snapshot.current_split_name.as_deref()?;
+    match snapshot.phase {
+        TimerPhase::Ended => return Some((format!("Done — {current}"), None)),
+        TimerPhase::NotRunning
+            if layout.split_name_before_start == SplitNameBeforeStart::Hidden =>
+        {
+            return None;
+        }
+        _ => {}
+    }
+    let next = snapshot
+        .next_split_name
+        .clone()
+        .filter(|_| layout.show_next_split_name);
+    Some((current.to_owned(), next))
+}
+
+/// What the labels were last set for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ShownFor {
+    enabled: bool,
+    show_next: bool,
+    before_start: SplitNameBeforeStart,
+    phase: TimerPhase,
+    split_index: Option<usize>,
+}
+
+pub struct SplitName {
+    container: GtkBox,
+    current: Label,
+    next: Label,
+    shown_for: Option<ShownFor>,
+}
+
+impl SplitName {
+    pub fn new() -> Self {
+        let container = GtkBox::builder()
+            .orientation(Orientation::Vertical)
+            .halign(Align::End)
+            .visible(false)
+            .css_classes(["split-name"])
+            .build();
+        let label = |classes: &[&str]| {
+            let label = Label::builder()
+                .halign(Align::End)
+                .ellipsize(EllipsizeMode::End)
+                .max_width_chars(32)
+                .build();
+            for class in classes {
+                label.add_css_class(class);
+            }
+            label
+        };
+        let current = label(&["title-4"]);
+        let next = label(&["caption", "dim-label"]);
+        container.append(&current);
+        container.append(&next);
+        Self {
+            container,
+            current,
+            next,
+            shown_for: None,
+        }
+    }
+
+    pub fn container(&self) -> &GtkBox {
+        &self.container
+    }
+
+    /// Shows the split name for this frame when `enabled`.
+    pub fn update(&mut self, timer: &Timer, config: &Config, enabled: bool) {
+        let shown_for = ShownFor {
+            enabled,
+            show_next: config.layout.show_next_split_name,
+            before_start: config.layout.split_name_before_start,
+            phase: timer.current_phase(),
+            split_index: timer.current_split_index(),
+        };
+        if self.shown_for == Some(shown_for) {
+            return;
+        }
+        self.shown_for = Some(shown_for);
+
+        let lines = enabled
+            .then(|| {
+                split_name_lines(
+                    &DisplaySnapshot::capture(timer, video_offset(config)),
+                    &config.layout,
+                )
+            })
+            .flatten();
+        self.container.set_visible(lines.is_some());
+        let Some((current, next)) = lines else {
+            return;
+        };
+        self.current.set_label(&current);
+        self.next.set_label(next.as_deref().unwrap_or_default());
+        self.next.set_visible(next.is_some());
+    }
+
+    /// Sets the labels again on the next update, for renamed segments.
+    pub fn invalidate(&mut self) {
+        self.shown_for = None;
+    }
+}
+
+#[cfg(test)]
+mod split_name_tests {
+    use super::*;
+    use crate::test_support::timer_on;
+    use livesplit_core::TimeSpan;
+
+    fn timer() -> Timer {
+        timer_on(&["Forest", "Caves", "Castle"])
+    }
+
+    fn layout(show_next: bool, before_start: SplitNameBeforeStart) -> LayoutOptions {
+        LayoutOptions {
+            show_current_split_name: true,
+            show_next_split_name: show_next,
+            split_name_before_start: before_start,
+            ..LayoutOptions::default()
+        }
+    }
+
+    #[test]
+    fn snapshot_names_follow_the_split_index() {
+        let mut timer = timer();
+        let snapshot = DisplaySnapshot::capture(&timer, time::Duration::ZERO);
+        assert_eq!(snapshot.current_split_name.as_deref(), Some("Forest"));
+        assert_eq!(snapshot.next_split_name.as_deref(), Some("Caves"));
+
+        timer.start();
+        timer.split();
+        timer.split();
+        let snapshot = DisplaySnapshot::capture(&timer, time::Duration::ZERO);
+        assert_eq!(snapshot.current_split_name.as_deref(), Some("Castle"));
+        assert_eq!(snapshot.next_split_name, None);
+    }
+
+    #[test]
+    fn running_shows_the_current_and_optionally_the_next_name() {
+        let mut timer = timer();
+        timer.start();
+        timer.split();
+        let snapshot = DisplaySnapshot::capture(&timer, time::Duration::ZERO);
+        assert_eq!(
+            split_name_lines(
+                &snapshot,
+                &layout(false, SplitNameBeforeStart::FirstSegment)
+            ),
+            Some(("Caves".to_owned(), None))
+        );
+        assert_eq!(
+            split_name_lines(&snapshot, &layout(true, SplitNameBeforeStart::FirstSegment)),
+            Some(("Caves".to_owned(), Some("Castle".to_owned())))
+        );
+    }
+
+    #[test]
+    fn before_the_start_shows_the_first_segment_or_nothing() {
+        let snapshot = DisplaySnapshot::capture(&timer(), time::Duration::ZERO);
+        assert_eq!(
+            split_name_lines(
+                &snapshot,
+                &layout(false, SplitNameBeforeStart::FirstSegment)
+            ),
+            Some(("Forest".to_owned(), None))
+        );
+        assert_eq!(
+            split_name_lines(&snapshot, &layout(true, SplitNameBeforeStart::Hidden)),
+            None
+        );
+    }
+
+    #[test]
+    fn a_finished_run_shows_done_with_the_final_split() {
+        let mut timer = timer();
+        timer.start();
+        for _ in 0..3 {
+            timer.split();
+        }
+        let snapshot = DisplaySnapshot::capture(&timer, time::Duration::ZERO);
+        assert_eq!(snapshot.phase, TimerPhase::Ended);
+        assert_eq!(
+            split_name_lines(&snapshot, &layout(true, SplitNameBeforeStart::Hidden)),
+            Some(("Done — Castle".to_owned(), None))
+        );
+    }
+
+    #[test]
+    fn no_segments_show_nothing() {
+        let snapshot = DisplaySnapshot {
+            phase: TimerPhase::NotRunning,
+            split_index: None,
+            attempt_duration: TimeSpan::zero(),
+            current_time: Default::default(),
+            current_split_name: None,
+            next_split_name: None,
+        };
+        assert_eq!(
+            split_name_lines(&snapshot, &layout(true, SplitNameBeforeStart::FirstSegment)),
+            None
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! Spacing the refresh ticks on the monotonic clock, with `debug.align-ticks`.
+//!
+//! A repeating glib timeout is scheduled again from when its callback ran,
+//! so every late tick pushes all the later ones back and the milliseconds on
+//! screen advance unevenly. Aligned ticks are each scheduled for the next
+//! multiple of the interval since the first one instead, so lateness never
+//! adds up. Every tick still reads the attempt duration when it runs.
+
+use std::time::{Duration, Instant};
+
+/// Time between two refresh ticks.
+pub const TICK_INTERVAL: Duration = Duration::from_millis(16);
+
+#[derive(Debug, Clone, Copy)]
+pub struct TickAligner {
+    anchor: Instant,
+    interval: Duration,
+}
+
+impl TickAligner {
+    pub fn new(anchor: Instant, interval: Duration) -> Self {
+        Self { anchor, interval }
+    }
+
+    /// How long to wait from `now` until the next boundary. glib counts
+    /// timeouts in whole milliseconds, so this is rounded up: waking early
+    /// would land just before the boundary and tick twice.
+    pub fn next_delay(&self, now: Instant) -> Duration {
+        let interval = self.interval.as_nanos().max(1);
+        let into = now.saturating_duration_since(self.anchor).as_nanos() % interval;
+        let nanos = (interval - into).div_ceil(1_000_000) * 1_000_000;
+        Duration::from_nanos(nanos as u64)
+    }
+}
+
+#[cfg(test)]
+mod tick_align_tests {
+    use super::*;
+
+    /// How late each tick runs, in milliseconds.
+    const JITTER: [u64; 7] = [0, 3, 1, 5, 2, 0, 4];
+
+    /// When each of `count` ticks runs, from the first one.
+    fn tick_times(aligned: bool, count: usize) -> Vec<Duration> {
+        let start = Instant::now();
+        let aligner = TickAligner::new(start, TICK_INTERVAL);
+        let mut now = start;
+        let mut times = vec![Duration::ZERO];
+        for late in JITTER.iter().cycle().take(count - 1) {
+            let delay = if aligned {
+                aligner.next_delay(now)
+            } else {
+                TICK_INTERVAL
+            };
+            now += delay + Duration::from_millis(*late);
+            times.push(now - start);
+        }
+        times
+    }
+
+    #[test]
+    fn waits_for_the_next_boundary() {
+        let start = Instant::now();
+        let aligner = TickAligner::new(start, TICK_INTERVAL);
+        let at = |ms| start + Duration::from_millis(ms);
+        assert_eq!(aligner.next_delay(start), TICK_INTERVAL);
+        assert_eq!(aligner.next_delay(at(3)), Duration::from_millis(13));
+        assert_eq!(aligner.next_delay(at(16)), TICK_INTERVAL);
+        // A tick over an interval late skips the boundary it missed
+        assert_eq!(aligner.next_delay(at(37)), Duration::from_millis(11));
+        // Part of a millisecond is waited out whole
+        assert_eq!(
+            aligner.next_delay(start + Duration::from_micros(15_600)),
+            Duration::from_millis(1)
+        );
+    }
+
+    #[test]
+    fn aligned_ticks_do_not_drift() {
+        let max_late = Duration::from_millis(*JITTER.iter().max().unwrap());
+        let times = tick_times(true, 600);
+        for (index, time) in times.iter().enumerate() {
+            let boundary = TICK_INTERVAL * index as u32;
+            assert!(*time >= boundary && *time - boundary <= max_late);
+        }
+
+        // Whereas a fixed cadence falls behind by every late tick
+        let fixed = tick_times(false, 600);
+        assert!(*fixed.last().unwrap() - *times.last().unwrap() > Duration::from_secs(1));
+    }
+
+    #[test]
+    fn shown_centiseconds_advance_every_tick() {
+        let max_late = *JITTER.iter().max().unwrap() as u128;
+        let shown: Vec<u128> = tick_times(true, 600)
+            .iter()
+            .map(|time| time.as_millis() / 10)
+            .collect();
+        for pair in shown.windows(2) {
+            let step = pair[1] - pair[0];
+            // Never the same value twice, never more than a late tick's jump
+            assert!(step >= 1);
+            assert!(step * 10 <= TICK_INTERVAL.as_millis() + max_late + 10);
+        }
+    }
+}
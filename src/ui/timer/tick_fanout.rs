@@ -0,0 +1,165 @@
+//! Hands the refresh loop's frame to other displays.
+//!
+//! The main window's tick takes one snapshot of the timer per frame. A view
+//! living outside the main window (the detached timer) subscribes here
+//! rather than running a tick source of its own, so every window shows the
+//! same frame at the same rate, with the same video offset applied.
+//!
+//! Consumers run inside the tick and must not subscribe or unsubscribe from
+//! there; returning `ControlFlow::Break` drops them instead.
+
+use std::cell::RefCell;
+
+use glib::ControlFlow;
+use livesplit_core::Timer;
+
+use crate::config::Config;
+
+type TickConsumer = Box<dyn FnMut(&Timer, &Config) -> ControlFlow>;
+
+thread_local! {
+    static TICK_FANOUT: RefCell<TickFanout> = RefCell::new(TickFanout::default());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriptionId(u64);
+
+/// The consumers of each tick, in subscription order.
+#[derive(Default)]
+pub struct TickFanout {
+    next_id: u64,
+    consumers: Vec<(SubscriptionId, TickConsumer)>,
+}
+
+impl TickFanout {
+    pub fn subscribe(
+        &mut self,
+        consumer: impl FnMut(&Timer, &Config) -> ControlFlow + 'static,
+    ) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.consumers.push((id, Box::new(consumer)));
+        id
+    }
+
+    /// Returns whether `id` was still subscribed.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let before = self.consumers.len();
+        self.consumers.retain(|(subscribed, _)| *subscribed != id);
+        self.consumers.len() != before
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.consumers.is_empty()
+    }
+
+    /// Gives the frame to every consumer, dropping those that are done.
+    pub fn publish(&mut self, timer: &Timer, config: &Config) {
+        self.consumers
+            .retain_mut(|(_, consumer)| consumer(timer, config) == ControlFlow::Continue);
+    }
+}
+
+pub fn subscribe_tick(
+    consumer: impl FnMut(&Timer, &Config) -> ControlFlow + 'static,
+) -> SubscriptionId {
+    TICK_FANOUT.with_borrow_mut(|fanout| fanout.subscribe(consumer))
+}
+
+pub fn unsubscribe_tick(id: SubscriptionId) {
+    TICK_FANOUT.with_borrow_mut(|fanout| fanout.unsubscribe(id));
+}
+
+/// Called by the refresh loop once the main window has drawn the frame.
+pub fn publish_tick(timer: &Timer, config: &Config) {
+    TICK_FANOUT.with_borrow_mut(|fanout| {
+        if !fanout.is_empty() {
+            fanout.publish(timer, config);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tick_fanout_tests {
+    use super::*;
+    use crate::test_support::timer_on;
+    use std::rc::Rc;
+
+    use livesplit_core::TimerPhase;
+
+    /// A consumer that records the phase of every frame it gets.
+    fn recorder(
+        fanout: &mut TickFanout,
+        frames_before_done: Option<usize>,
+    ) -> (SubscriptionId, Rc<RefCell<Vec<TimerPhase>>>) {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let sink = seen.clone();
+        let id = fanout.subscribe(move |timer, _| {
+            sink.borrow_mut().push(timer.current_phase());
+            match frames_before_done {
+                Some(frames) if sink.borrow().len() >= frames => ControlFlow::Break,
+                _ => ControlFlow::Continue,
+            }
+        });
+        (id, seen)
+    }
+
+    #[test]
+    fn every_consumer_gets_the_same_frame() {
+        let mut fanout = TickFanout::default();
+        let (_, main) = recorder(&mut fanout, None);
+        let (_, detached) = recorder(&mut fanout, None);
+        let config = Config::default();
+        let mut timer = timer_on(&["One"]);
+
+        fanout.publish(&timer, &config);
+        timer.start();
+        fanout.publish(&timer, &config);
+
+        let expected = vec![TimerPhase::NotRunning, TimerPhase::Running];
+        assert_eq!(*main.borrow(), expected);
+        assert_eq!(*detached.borrow(), expected);
+    }
+
+    #[test]
+    fn unsubscribing_one_consumer_keeps_the_other() {
+        let mut fanout = TickFanout::default();
+        let (main_id, main) = recorder(&mut fanout, None);
+        let (detached_id, detached) = recorder(&mut fanout, None);
+        assert_ne!(main_id, detached_id);
+        let config = Config::default();
+        let timer = timer_on(&["One"]);
+
+        fanout.publish(&timer, &config);
+        assert!(fanout.unsubscribe(detached_id));
+        assert!(!fanout.unsubscribe(detached_id));
+        fanout.publish(&timer, &config);
+
+        assert_eq!(main.borrow().len(), 2);
+        assert_eq!(detached.borrow().len(), 1);
+        assert_eq!(fanout.consumers.len(), 1);
+    }
+
+    #[test]
+    fn finished_consumers_are_dropped() {
+        let mut fanout = TickFanout::default();
+        let (_, main) = recorder(&mut fanout, None);
+        let (detached_id, detached) = recorder(&mut fanout, Some(2));
+        let config = Config::default();
+        let timer = timer_on(&["One"]);
+
+        for _ in 0..4 {
+            fanout.publish(&timer, &config);
+        }
+        assert_eq!(main.borrow().len(), 4);
+        assert_eq!(detached.borrow().len(), 2);
+        assert_eq!(fanout.consumers.len(), 1);
+
+        // Ids are never reused, a stale one can't drop a newer consumer
+        let (late_id, _) = recorder(&mut fanout, None);
+        assert!(!fanout.unsubscribe(detached_id));
+        assert_eq!(fanout.consumers.len(), 2);
+        assert!(fanout.unsubscribe(late_id));
+        assert!(!fanout.is_empty());
+    }
+}
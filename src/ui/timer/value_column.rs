@@ -0,0 +1,102 @@
+//! One width for every value on the right edge of the timer.
+//!
+//! With `layout.align-value-column`, the split rows' comparison times, the
+//! target time above the running timer and the additional info readouts share
+//! a horizontal `SizeGroup`, so their text ends on the same edge and the
+//! column reads as one. The big running timer is left out: it is far wider
+//! than a split time and would push the rows past the clamped width.
+//!
+//! Labels stay in the group when the option is off, the group just stops
+//! applying, so toggling it takes effect on the next resync.
+
+use gtk4::prelude::*;
+use gtk4::{Label, SizeGroup, SizeGroupMode, Widget};
+
+use crate::config::Config;
+
+pub fn value_column_mode(config: &Config) -> SizeGroupMode {
+    if config.layout.align_value_column {
+        SizeGroupMode::Horizontal
+    } else {
+        SizeGroupMode::None
+    }
+}
+
+#[derive(Clone)]
+pub struct ValueColumn {
+    group: SizeGroup,
+}
+
+impl ValueColumn {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            group: SizeGroup::new(value_column_mode(config)),
+        }
+    }
+
+    /// Adds a value to the column. Labels are right-aligned so their text
+    /// ends where the widest one does; other widgets only share the width.
+    pub fn add(&self, widget: &impl IsA<Widget>) {
+        if let Some(label) = widget.dynamic_cast_ref::<Label>() {
+            label.set_xalign(1.0);
+        }
+        self.group.add_widget(widget);
+    }
+
+    pub fn update(&self, config: &Config) {
+        let mode = value_column_mode(config);
+        if self.group.mode() != mode {
+            self.group.set_mode(mode);
+        }
+    }
+}
+
+#[cfg(test)]
+mod value_column_tests {
+    use super::*;
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    fn gtk_test_init() {
+        INIT.call_once(|| {
+            gtk4::init().expect("Failed to init GTK");
+            let _ = adw::init();
+        });
+    }
+
+    fn width(label: &Label) -> i32 {
+        label.measure(gtk4::Orientation::Horizontal, -1).1
+    }
+
+    #[gtk4::test]
+    fn aligned_values_share_the_widest_width() {
+        gtk_test_init();
+        let mut config = Config::default();
+        let column = ValueColumn::new(&config);
+        let split = Label::new(Some("1:02.34"));
+        let readout = Label::new(Some("1:23:45.67"));
+        column.add(&split);
+        column.add(&readout);
+        assert_eq!(split.xalign(), 1.0);
+
+        // Off by default, each value keeps its own width
+        assert_ne!(width(&split), width(&readout));
+
+        config.layout.align_value_column = true;
+        column.update(&config);
+        assert_eq!(width(&split), width(&readout));
+
+        config.layout.align_value_column = false;
+        column.update(&config);
+        assert_ne!(width(&split), width(&readout));
+    }
+
+    #[test]
+    fn mode_follows_the_config() {
+        let mut config = Config::default();
+        assert_eq!(value_column_mode(&config), SizeGroupMode::None);
+        config.layout.align_value_column = true;
+        assert_eq!(value_column_mode(&config), SizeGroupMode::Horizontal);
+    }
+}
@@ -0,0 +1,193 @@
+//! Detects a refresh loop that stopped updating the display.
+//!
+//! The refresh loop records a heartbeat after every tick that got through to
+//! the widgets. A slower watchdog on a thread of its own compares it
+//! against the clock, so it also notices the main loop stalling: when the
+//! heartbeat falls behind by more than the threshold while the window is
+//! mapped, one recovery is attempted. If the display stalls again before a
+//! tick got through, the watchdog gives up instead of looping.
+
+use std::time::{Duration, Instant};
+
+/// How often the watchdog looks at the heartbeat.
+pub const WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogAction {
+    /// No tick for `behind`; restart the refresh loop.
+    Recover { behind: Duration },
+    /// Ticks resumed after a recovery.
+    Recovered,
+    /// Still no tick after a recovery.
+    GiveUp { behind: Duration },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Healthy,
+    Recovering { since: Instant },
+    Failed,
+}
+
+#[derive(Debug)]
+pub struct StallDetector {
+    threshold: Duration,
+    state: State,
+    /// Heartbeats older than this don't count, e.g. while unmapped.
+    baseline: Option<Instant>,
+}
+
+impl StallDetector {
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            state: State::Healthy,
+            baseline: None,
+        }
+    }
+
+    fn latest(&self, last_tick: Option<Instant>) -> Option<Instant> {
+        match (last_tick, self.baseline) {
+            (Some(tick), Some(baseline)) => Some(tick.max(baseline)),
+            (tick, baseline) => tick.or(baseline),
+        }
+    }
+
+    /// Looks at the heartbeat `last_tick` at time `now`.
+    pub fn observe(
+        &mut self,
+        now: Instant,
+        last_tick: Option<Instant>,
+        mapped: bool,
+    ) -> Option<WatchdogAction> {
+        if !mapped {
+            // Nothing is drawn while unmapped, don't hold that against the loop
+            self.baseline = Some(now);
+            return None;
+        }
+        let Some(latest) = self.latest(last_tick) else {
+            self.baseline = Some(now);
+            return None;
+        };
+        let behind = now.saturating_duration_since(latest);
+
+        match self.state {
+            State::Healthy if behind > self.threshold => {
+                self.state = State::Recovering { since: now };
+                Some(WatchdogAction::Recover { behind })
+            }
+            State::Recovering { since } => {
+                if last_tick.is_some_and(|tick| tick > since) {
+                    self.state = State::Healthy;
+                    Some(WatchdogAction::Recovered)
+                } else if now.saturating_duration_since(since) > self.threshold {
+                    self.state = State::Failed;
+                    Some(WatchdogAction::GiveUp { behind })
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod watchdog_tests {
+    use super::*;
+
+    const THRESHOLD: Duration = Duration::from_secs(3);
+
+    fn secs(start: Instant, secs: u64) -> Instant {
+        start + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn regular_ticks_are_healthy() {
+        let start = Instant::now();
+        let mut detector = StallDetector::new(THRESHOLD);
+        for t in 0..10 {
+            assert_eq!(
+                detector.observe(secs(start, t), Some(secs(start, t)), true),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn stall_past_threshold_triggers_one_recovery() {
+        let start = Instant::now();
+        let mut detector = StallDetector::new(THRESHOLD);
+        let tick = Some(start);
+        assert_eq!(detector.observe(secs(start, 3), tick, true), None);
+        assert_eq!(
+            detector.observe(secs(start, 4), tick, true),
+            Some(WatchdogAction::Recover {
+                behind: Duration::from_secs(4)
+            })
+        );
+        // Waiting on the recovery, not asking for another one
+        assert_eq!(detector.observe(secs(start, 5), tick, true), None);
+    }
+
+    #[test]
+    fn ticks_after_recovery_report_success() {
+        let start = Instant::now();
+        let mut detector = StallDetector::new(THRESHOLD);
+        assert!(
+            detector
+                .observe(secs(start, 4), Some(start), true)
+                .is_some()
+        );
+        assert_eq!(
+            detector.observe(secs(start, 5), Some(secs(start, 5)), true),
+            Some(WatchdogAction::Recovered)
+        );
+        // Healthy again, so a later stall is recovered once more
+        assert_eq!(
+            detector.observe(secs(start, 10), Some(secs(start, 5)), true),
+            Some(WatchdogAction::Recover {
+                behind: Duration::from_secs(5)
+            })
+        );
+    }
+
+    #[test]
+    fn no_ticks_after_recovery_gives_up_once() {
+        let start = Instant::now();
+        let mut detector = StallDetector::new(THRESHOLD);
+        let tick = Some(start);
+        assert!(detector.observe(secs(start, 4), tick, true).is_some());
+        assert_eq!(detector.observe(secs(start, 7), tick, true), None);
+        assert_eq!(
+            detector.observe(secs(start, 8), tick, true),
+            Some(WatchdogAction::GiveUp {
+                behind: Duration::from_secs(8)
+            })
+        );
+        assert_eq!(detector.observe(secs(start, 20), tick, true), None);
+    }
+
+    #[test]
+    fn unmapped_time_is_not_a_stall() {
+        let start = Instant::now();
+        let mut detector = StallDetector::new(THRESHOLD);
+        let tick = Some(start);
+        assert_eq!(detector.observe(secs(start, 60), tick, false), None);
+        // Mapped again: counted from when it was last seen unmapped
+        assert_eq!(detector.observe(secs(start, 62), tick, true), None);
+        assert!(matches!(
+            detector.observe(secs(start, 64), tick, true),
+            Some(WatchdogAction::Recover { .. })
+        ));
+    }
+
+    #[test]
+    fn waits_for_a_first_tick_from_startup() {
+        let start = Instant::now();
+        let mut detector = StallDetector::new(THRESHOLD);
+        assert_eq!(detector.observe(start, None, true), None);
+        assert_eq!(detector.observe(secs(start, 3), None, true), None);
+        assert!(detector.observe(secs(start, 4), None, true).is_some());
+    }
+}
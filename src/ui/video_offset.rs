@@ -0,0 +1,276 @@
+//! Header indicator and calibration for `behavior.video-offset-ms`.
+//!
+//! Calibration flashes a target a few times while the runner presses Space
+//! as soon as they see it: first looking at TuxSplit itself, which measures
+//! their reaction time, then through the capture preview, which adds the
+//! capture delay. The difference of the two medians is the suggested offset.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use adw::AlertDialog;
+use adw::prelude::*;
+use glib::Propagation;
+use gtk4::{EventControllerKey, GestureClick, Label, PropagationPhase, gdk};
+
+use crate::ui::timer::modal::present_modal;
+
+pub const ROUNDS_PER_PHASE: usize = 3;
+/// Presses faster than this were anticipated rather than seen.
+const MIN_REACTION: Duration = Duration::from_millis(100);
+const MAX_REACTION: Duration = Duration::from_secs(2);
+const FLASH_LENGTH: Duration = Duration::from_millis(150);
+
+thread_local! {
+    static INDICATOR: RefCell<Option<Label>> = const { RefCell::new(None) };
+}
+
+/// Where the offset is shown while it is not zero.
+pub fn set_video_offset_indicator(indicator: &Label) {
+    INDICATOR.replace(Some(indicator.clone()));
+}
+
+pub fn refresh_video_offset_indicator(offset_ms: u32) {
+    INDICATOR.with_borrow(|indicator| {
+        if let Some(indicator) = indicator {
+            indicator.set_label(&format!("Video −{offset_ms} ms"));
+            indicator.set_tooltip_text(Some(&format!(
+                "Live times are shown {offset_ms} ms earlier to match the capture. \
+                 Recorded and saved times are not affected."
+            )));
+            indicator.set_visible(offset_ms > 0);
+        }
+    });
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationPhase {
+    /// Reacting to the flash on TuxSplit itself.
+    Direct,
+    /// Reacting to the flash as the capture preview shows it.
+    Capture,
+    Done,
+}
+
+/// Reaction times collected during calibration, independently of GTK.
+#[derive(Debug, Default)]
+pub struct Calibration {
+    direct: Vec<Duration>,
+    capture: Vec<Duration>,
+}
+
+fn median(samples: &[Duration]) -> Option<Duration> {
+    let mut sorted = samples.to_vec();
+    sorted.sort();
+    sorted.get(sorted.len() / 2).copied()
+}
+
+impl Calibration {
+    pub fn phase(&self) -> CalibrationPhase {
+        if self.direct.len() < ROUNDS_PER_PHASE {
+            CalibrationPhase::Direct
+        } else if self.capture.len() < ROUNDS_PER_PHASE {
+            CalibrationPhase::Capture
+        } else {
+            CalibrationPhase::Done
+        }
+    }
+
+    /// Records one reaction. Implausible ones are dropped and the round is
+    /// repeated; returns whether it counted.
+    pub fn record(&mut self, reaction: Duration) -> bool {
+        if !(MIN_REACTION..=MAX_REACTION).contains(&reaction) {
+            return false;
+        }
+        match self.phase() {
+            CalibrationPhase::Direct => self.direct.push(reaction),
+            CalibrationPhase::Capture => self.capture.push(reaction),
+            CalibrationPhase::Done => return false,
+        }
+        true
+    }
+
+    /// Suggested offset once both phases are complete.
+    pub fn estimate_ms(&self) -> Option<u32> {
+        if self.phase() != CalibrationPhase::Done {
+            return None;
+        }
+        let delay = median(&self.capture)?.saturating_sub(median(&self.direct)?);
+        Some(u32::try_from(delay.as_millis()).unwrap_or(u32::MAX))
+    }
+
+    fn instructions(&self) -> String {
+        match self.phase() {
+            CalibrationPhase::Direct => format!(
+                "Look at the box below and press Space as soon as it flashes ({}/{ROUNDS_PER_PHASE}).",
+                self.direct.len() + 1
+            ),
+            CalibrationPhase::Capture => format!(
+                "Now watch this window through your capture preview and press Space when the \
+                 flash shows up there ({}/{ROUNDS_PER_PHASE}).",
+                self.capture.len() + 1
+            ),
+            CalibrationPhase::Done => match self.estimate_ms() {
+                Some(ms) => format!("Your capture runs about {ms} ms behind."),
+                None => "Could not estimate the delay.".to_owned(),
+            },
+        }
+    }
+}
+
+struct CalibrationState {
+    calibration: Calibration,
+    flashed_at: Option<Instant>,
+}
+
+fn schedule_flash(
+    target: &gtk4::Box,
+    state: &Rc<RefCell<CalibrationState>>,
+    closed: &Rc<Cell<bool>>,
+) {
+    let delay = Duration::from_millis(glib::random_int_range(1200, 2600) as u64);
+    let target = target.clone();
+    let state = state.clone();
+    let closed = closed.clone();
+    glib::timeout_add_local_once(delay, move || {
+        if closed.get() {
+            return;
+        }
+        target.add_css_class("flashing");
+        state.borrow_mut().flashed_at = Some(Instant::now());
+        glib::timeout_add_local_once(FLASH_LENGTH, move || {
+            target.remove_css_class("flashing");
+        });
+    });
+}
+
+/// Runs the calibration and passes the estimate to `on_done` if the user
+/// keeps it.
+pub fn present_calibration(parent: &impl IsA<gtk4::Widget>, on_done: impl Fn(u32) + 'static) {
+    let state = Rc::new(RefCell::new(CalibrationState {
+        calibration: Calibration::default(),
+        flashed_at: None,
+    }));
+    let closed = Rc::new(Cell::new(false));
+
+    let dialog = AlertDialog::builder()
+        .heading("Calibrate Video Offset")
+        .body(state.borrow().calibration.instructions())
+        .close_response("cancel")
+        .build();
+    dialog.add_response("cancel", "Cancel");
+    dialog.add_response("use", "Use Estimate");
+    dialog.set_response_appearance("use", adw::ResponseAppearance::Suggested);
+    dialog.set_response_enabled("use", false);
+
+    let target = gtk4::Box::builder().height_request(120).build();
+    target.add_css_class("calibration-target");
+    dialog.set_extra_child(Some(&target));
+
+    let on_press = {
+        let dialog = dialog.clone();
+        let target = target.clone();
+        let state = state.clone();
+        let closed = closed.clone();
+        move || {
+            let mut current = state.borrow_mut();
+            let Some(flashed_at) = current.flashed_at.take() else {
+                // Too early, wait for the flash
+                return;
+            };
+            if !current.calibration.record(flashed_at.elapsed()) {
+                dialog.set_body(&format!(
+                    "That one didn't count. {}",
+                    current.calibration.instructions()
+                ));
+            } else {
+                dialog.set_body(&current.calibration.instructions());
+            }
+            if current.calibration.phase() == CalibrationPhase::Done {
+                dialog.set_response_enabled("use", current.calibration.estimate_ms().is_some());
+            } else {
+                drop(current);
+                schedule_flash(&target, &state, &closed);
+            }
+        }
+    };
+
+    // Space would otherwise activate the focused response button
+    let keys = EventControllerKey::new();
+    keys.set_propagation_phase(PropagationPhase::Capture);
+    let on_key = on_press.clone();
+    keys.connect_key_pressed(move |_, keyval, _, _| {
+        if keyval == gdk::Key::space {
+            on_key();
+            Propagation::Stop
+        } else {
+            Propagation::Proceed
+        }
+    });
+    dialog.add_controller(keys);
+    let click = GestureClick::new();
+    click.connect_pressed(move |_, _, _, _| on_press());
+    target.add_controller(click);
+
+    let state_for_response = state.clone();
+    dialog.connect_response(None, move |_, response| {
+        if response == "use"
+            && let Some(ms) = state_for_response.borrow().calibration.estimate_ms()
+        {
+            on_done(ms);
+        }
+    });
+    let closed_for_close = closed.clone();
+    dialog.connect_closed(move |_| closed_for_close.set(true));
+
+    schedule_flash(&target, &state, &closed);
+    // Hotkeys are held back meanwhile, Space is often the split key
+    present_modal(&dialog, parent);
+}
+
+#[cfg(test)]
+mod video_offset_tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn estimate_is_the_difference_of_the_medians() {
+        let mut calibration = Calibration::default();
+        for reaction in [250, 230, 900] {
+            assert_eq!(calibration.phase(), CalibrationPhase::Direct);
+            assert!(calibration.record(ms(reaction)));
+        }
+        assert_eq!(calibration.estimate_ms(), None);
+        for reaction in [320, 340, 330] {
+            assert_eq!(calibration.phase(), CalibrationPhase::Capture);
+            assert!(calibration.record(ms(reaction)));
+        }
+        assert_eq!(calibration.phase(), CalibrationPhase::Done);
+        // 330 - 250, the slow outlier doesn't count
+        assert_eq!(calibration.estimate_ms(), Some(80));
+    }
+
+    #[test]
+    fn implausible_reactions_repeat_the_round() {
+        let mut calibration = Calibration::default();
+        assert!(!calibration.record(ms(40)));
+        assert!(!calibration.record(ms(5_000)));
+        assert_eq!(calibration.phase(), CalibrationPhase::Direct);
+        assert!(calibration.record(ms(200)));
+    }
+
+    #[test]
+    fn a_faster_capture_never_gives_a_negative_offset() {
+        let mut calibration = Calibration::default();
+        for reaction in [300, 300, 300, 250, 250, 250] {
+            calibration.record(ms(reaction));
+        }
+        assert_eq!(calibration.estimate_ms(), Some(0));
+        // Nothing more is recorded once done
+        assert!(!calibration.record(ms(300)));
+    }
+}
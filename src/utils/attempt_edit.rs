@@ -0,0 +1,432 @@
+//! Correcting the split times of an attempt that just ended.
+//!
+//! A split registered a beat late can be nudged before the attempt is
+//! recorded. livesplit-core's timer doesn't let go of the times it measured,
+//! so applying a correction records the attempt the way a reset with the
+//! splits updated would, from the corrected times of the current timing
+//! method: its segment history, golds, personal best and the attempt's own
+//! time. The other timing method keeps what was measured.
+//!
+//! Split times stay in order. A split can't be moved before the one ahead
+//! of it, and the later splits either move along or only make way, see
+//! [`LaterSplits`]. Skipped splits have no time to correct.
+
+use livesplit_core::run::Attempt;
+use livesplit_core::{Run, Time, TimeSpan, Timer, TimerPhase, TimingMethod};
+use serde::{Deserialize, Serialize};
+use time::Duration;
+
+use crate::io::integrity::run_to_save;
+
+/// What correcting a split does to the splits after it.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LaterSplits {
+    /// They move by as much, so their segment times stay.
+    #[default]
+    Shift,
+    /// They stay, unless the corrected split would pass them.
+    Clamp,
+}
+
+/// A split whose time was corrected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeChange {
+    pub index: usize,
+    pub before: Duration,
+    pub after: Duration,
+}
+
+/// The split times of an ended attempt, as measured and as corrected.
+#[derive(Debug, Clone)]
+pub struct AttemptEdit {
+    method: TimingMethod,
+    recorded: Vec<Option<Duration>>,
+    times: Vec<Option<Duration>>,
+    later: LaterSplits,
+}
+
+fn span(duration: Duration) -> TimeSpan {
+    TimeSpan::from_milliseconds(duration.whole_nanoseconds() as f64 / 1_000_000.0)
+}
+
+impl AttemptEdit {
+    pub fn new(method: TimingMethod, recorded: Vec<Option<Duration>>, later: LaterSplits) -> Self {
+        Self {
+            method,
+            times: recorded.clone(),
+            recorded,
+            later,
+        }
+    }
+
+    /// The attempt `timer` just ended, in its current timing method. `None`
+    /// while no attempt has ended.
+    pub fn from_timer(timer: &Timer, later: LaterSplits) -> Option<Self> {
+        if timer.current_phase() != TimerPhase::Ended {
+            return None;
+        }
+        let method = timer.current_timing_method();
+        let recorded = timer
+            .run()
+            .segments()
+            .iter()
+            .map(|segment| segment.split_time()[method].map(|time| time.to_duration()))
+            .collect();
+        Some(Self::new(method, recorded, later))
+    }
+
+    pub fn method(&self) -> TimingMethod {
+        self.method
+    }
+
+    pub fn recorded(&self) -> &[Option<Duration>] {
+        &self.recorded
+    }
+
+    pub fn times(&self) -> &[Option<Duration>] {
+        &self.times
+    }
+
+    /// The earliest split `index` can be moved to, the split before it.
+    fn earliest(&self, index: usize) -> Duration {
+        self.times[..index]
+            .iter()
+            .rev()
+            .flatten()
+            .next()
+            .copied()
+            .unwrap_or(Duration::ZERO)
+    }
+
+    /// Moves split `index` to `time`, or as close as the split before it
+    /// allows. Whether anything changed.
+    pub fn set(&mut self, index: usize, time: Duration) -> bool {
+        let Some(Some(current)) = self.times.get(index).copied() else {
+            return false;
+        };
+        let time = time.max(self.earliest(index));
+        let delta = time - current;
+        if delta.is_zero() {
+            return false;
+        }
+        self.times[index] = Some(time);
+        let later = self.later;
+        for split in self.times[index + 1..].iter_mut().flatten() {
+            *split = match later {
+                LaterSplits::Shift => *split + delta,
+                LaterSplits::Clamp => (*split).max(time),
+            };
+        }
+        true
+    }
+
+    /// Moves split `index` by `delta`, see [`Self::set`].
+    pub fn nudge(&mut self, index: usize, delta: Duration) -> bool {
+        match self.times.get(index).copied().flatten() {
+            Some(current) => self.set(index, current + delta),
+            None => false,
+        }
+    }
+
+    /// Puts every split back to its measured time.
+    pub fn revert(&mut self) {
+        self.times.clone_from(&self.recorded);
+    }
+
+    /// The corrected splits, in order.
+    pub fn changes(&self) -> Vec<TimeChange> {
+        self.recorded
+            .iter()
+            .zip(&self.times)
+            .enumerate()
+            .filter_map(|(index, (before, after))| match (before, after) {
+                (Some(before), Some(after)) if before != after => Some(TimeChange {
+                    index,
+                    before: *before,
+                    after: *after,
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Whether the corrected attempt beats `run`'s personal best.
+    fn is_new_pb(&self, run: &Run) -> bool {
+        let Some(Some(last)) = self.times.last().copied() else {
+            return false;
+        };
+        run.segments()
+            .last()
+            .and_then(|segment| segment.personal_best_split_time()[self.method])
+            .is_none_or(|pb| last < pb.to_duration())
+    }
+
+    /// Segment times of the corrected splits. A skipped split's segment
+    /// counts towards the next one, as the timer records them.
+    fn segment_times(&self) -> Vec<Option<Duration>> {
+        let mut previous = Duration::ZERO;
+        self.times
+            .iter()
+            .map(|time| {
+                let time = (*time)?;
+                let segment = time - previous;
+                previous = time;
+                Some(segment)
+            })
+            .collect()
+    }
+
+    /// The run `timer` records its ended attempt into, with the corrected
+    /// times, and whether that's a new personal best. `None` when `timer`
+    /// isn't on the attempt this was made from anymore.
+    pub fn recorded_run(&self, timer: &Timer) -> Option<(Run, bool)> {
+        let current = Self::from_timer(timer, self.later)?;
+        if current.method != self.method || current.recorded != self.recorded {
+            return None;
+        }
+        let before = timer.run();
+        let new_pb = self.is_new_pb(before);
+        let method = self.method;
+        let mut run = run_to_save(timer);
+        let id = run.attempt_history().last().map(Attempt::index)?;
+
+        // The attempt's own time is the last split's
+        let attempts = run.attempt_history().to_vec();
+        let histories: Vec<Vec<(i32, Time)>> = run
+            .segments()
+            .iter()
+            .map(|segment| segment.segment_history().iter().copied().collect())
+            .collect();
+        run.clear_history();
+        for attempt in attempts {
+            let mut time = attempt.time();
+            if attempt.index() == id {
+                time[method] = self.times.last().copied().flatten().map(span);
+            }
+            run.add_attempt_with_index(
+                time,
+                attempt.index(),
+                attempt.started(),
+                attempt.ended(),
+                attempt.pause_time(),
+            );
+        }
+
+        let segment_times = self.segment_times();
+        for (index, (segment, history)) in run.segments_mut().iter_mut().zip(histories).enumerate()
+        {
+            let mut history_time = Time::default();
+            for (history_id, time) in history {
+                // Inserting keeps a time already there, so this one waits for its correction
+                if history_id == id {
+                    history_time = time;
+                } else {
+                    segment.segment_history_mut().insert(history_id, time);
+                }
+            }
+            let segment_time = segment_times[index];
+            history_time[method] = segment_time.map(span);
+            segment.segment_history_mut().insert(id, history_time);
+
+            let gold = before.segment(index).best_segment_time()[method];
+            segment.best_segment_time_mut()[method] = match (gold, segment_time) {
+                (Some(gold), Some(time)) => Some(span(time.min(gold.to_duration()))),
+                (gold, None) => gold,
+                (None, Some(time)) => Some(span(time)),
+            };
+
+            let pb = if new_pb {
+                let mut pb = before.segment(index).split_time();
+                pb[method] = self.times[index].map(span);
+                pb
+            } else {
+                before.segment(index).personal_best_split_time()
+            };
+            segment.set_personal_best_split_time(pb);
+        }
+        Some((run, new_pb))
+    }
+}
+
+#[cfg(test)]
+mod attempt_edit_tests {
+    use super::*;
+    use livesplit_core::Segment;
+
+    fn s(seconds: i64) -> Option<Duration> {
+        Some(Duration::seconds(seconds))
+    }
+
+    fn edit(times: &[Option<Duration>], later: LaterSplits) -> AttemptEdit {
+        AttemptEdit::new(TimingMethod::RealTime, times.to_vec(), later)
+    }
+
+    #[test]
+    fn shifting_keeps_the_later_segment_times() {
+        let mut edit = edit(&[s(10), s(20), None, s(40)], LaterSplits::Shift);
+        assert!(edit.nudge(1, Duration::milliseconds(-300)));
+        let shifted = Duration::milliseconds(19_700);
+        assert_eq!(
+            edit.times(),
+            [
+                s(10),
+                Some(shifted),
+                None,
+                Some(Duration::milliseconds(39_700))
+            ]
+        );
+        assert!(edit.nudge(0, Duration::seconds(1)));
+        assert_eq!(edit.times()[3], Some(Duration::milliseconds(40_700)));
+        assert_eq!(edit.changes().len(), 3);
+    }
+
+    #[test]
+    fn clamping_only_moves_the_splits_it_passes() {
+        let mut edit = edit(&[s(10), s(20), s(30), s(40)], LaterSplits::Clamp);
+        assert!(edit.set(1, Duration::seconds(35)));
+        assert_eq!(edit.times(), [s(10), s(35), s(35), s(40)]);
+        // Moving back leaves the later ones where they are
+        assert!(edit.set(1, Duration::seconds(15)));
+        assert_eq!(edit.times(), [s(10), s(15), s(35), s(40)]);
+        assert_eq!(
+            edit.changes(),
+            [
+                TimeChange {
+                    index: 1,
+                    before: Duration::seconds(20),
+                    after: Duration::seconds(15)
+                },
+                TimeChange {
+                    index: 2,
+                    before: Duration::seconds(30),
+                    after: Duration::seconds(35)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_stay_in_order() {
+        for later in [LaterSplits::Shift, LaterSplits::Clamp] {
+            let mut edit = edit(&[s(10), None, s(30), s(40)], later);
+            // Not before the split ahead, skipped ones don't count
+            assert!(edit.set(2, Duration::seconds(5)));
+            assert_eq!(edit.times()[2], s(10));
+            // Nor before the start
+            assert!(edit.nudge(0, Duration::seconds(-60)));
+            assert_eq!(edit.times()[0], s(0));
+            for window in edit.times().iter().flatten().collect::<Vec<_>>().windows(2) {
+                assert!(window[0] <= window[1], "{later:?}: {:?}", edit.times());
+            }
+            // At the limit already
+            assert!(!edit.nudge(0, Duration::seconds(-1)));
+        }
+    }
+
+    #[test]
+    fn skipped_or_missing_splits_are_not_edited() {
+        let mut edit = edit(&[s(10), None, s(30)], LaterSplits::Shift);
+        assert!(!edit.nudge(1, Duration::seconds(1)));
+        assert!(!edit.set(3, Duration::seconds(50)));
+        assert!(!edit.set(0, Duration::seconds(10)));
+        assert!(edit.changes().is_empty());
+        assert!(edit.nudge(2, Duration::seconds(1)));
+        edit.revert();
+        assert_eq!(edit.times(), edit.recorded());
+    }
+
+    #[test]
+    fn skipped_segments_count_towards_the_next() {
+        let edit = edit(&[s(10), None, s(30)], LaterSplits::Shift);
+        assert_eq!(edit.segment_times(), [s(10), None, s(20)]);
+    }
+
+    /// A timer that ended with game time splits at `splits` seconds, against
+    /// a personal best of 10s per segment with 9s golds.
+    fn ended_timer(splits: &[f64]) -> Timer {
+        let mut run = Run::new();
+        for index in 0..splits.len() {
+            let mut segment = Segment::new(format!("S{index}"));
+            let pb = 10.0 * (index + 1) as f64;
+            segment.set_personal_best_split_time(
+                Time::new().with_game_time(Some(TimeSpan::from_seconds(pb))),
+            );
+            segment.set_best_segment_time(
+                Time::new().with_game_time(Some(TimeSpan::from_seconds(9.0))),
+            );
+            run.push_segment(segment);
+        }
+        let mut timer = Timer::new(run).unwrap();
+        timer.set_current_timing_method(TimingMethod::GameTime);
+        timer.start();
+        timer.initialize_game_time();
+        timer.pause_game_time();
+        for split in splits {
+            timer.set_game_time(TimeSpan::from_seconds(*split));
+            timer.split();
+        }
+        timer
+    }
+
+    fn game(time: Time) -> Option<f64> {
+        time.game_time.map(|time| time.total_seconds())
+    }
+
+    #[test]
+    fn corrections_reach_the_recorded_run() {
+        // The second split was half a second late
+        let timer = ended_timer(&[8.0, 17.5, 29.0]);
+        let mut edit = AttemptEdit::from_timer(&timer, LaterSplits::Shift).unwrap();
+        assert!(edit.nudge(1, Duration::milliseconds(-500)));
+
+        let (run, new_pb) = edit.recorded_run(&timer).unwrap();
+        assert!(new_pb);
+        let pb: Vec<_> = run
+            .segments()
+            .iter()
+            .map(|segment| game(segment.personal_best_split_time()))
+            .collect();
+        assert_eq!(pb, [Some(8.0), Some(17.0), Some(28.5)]);
+        let golds: Vec<_> = run
+            .segments()
+            .iter()
+            .map(|segment| game(segment.best_segment_time()))
+            .collect();
+        assert_eq!(golds, [Some(8.0), Some(9.0), Some(9.0)]);
+        let id = run.attempt_history().last().unwrap().index();
+        let history = run.segment(1).segment_history().iter().copied();
+        let corrected = history
+            .filter(|(index, _)| *index == id)
+            .map(|(_, time)| game(time));
+        assert_eq!(corrected.collect::<Vec<_>>(), [Some(9.0)]);
+        assert_eq!(
+            game(run.attempt_history().last().unwrap().time()),
+            Some(28.5)
+        );
+    }
+
+    #[test]
+    fn corrections_can_lose_the_personal_best() {
+        // An 8.5s gold on the last segment, and a PB
+        let timer = ended_timer(&[9.5, 19.5, 28.0]);
+        let mut edit = AttemptEdit::from_timer(&timer, LaterSplits::Clamp).unwrap();
+        assert!(edit.nudge(2, Duration::milliseconds(2_500)));
+        let (run, new_pb) = edit.recorded_run(&timer).unwrap();
+        assert!(!new_pb);
+        assert_eq!(game(run.segment(2).personal_best_split_time()), Some(30.0));
+        // A gold the correction took back is the old one again
+        assert_eq!(game(run.segment(2).best_segment_time()), Some(9.0));
+    }
+
+    #[test]
+    fn only_the_attempt_it_was_made_from() {
+        let timer = ended_timer(&[8.0, 18.0]);
+        let edit = AttemptEdit::from_timer(&timer, LaterSplits::Shift).unwrap();
+        let mut other = ended_timer(&[8.5, 18.0]);
+        assert!(edit.recorded_run(&other).is_none());
+        other.reset(true);
+        assert!(AttemptEdit::from_timer(&other, LaterSplits::Shift).is_none());
+        assert!(edit.recorded_run(&other).is_none());
+    }
+}
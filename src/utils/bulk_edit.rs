@@ -0,0 +1,252 @@
+//! Bulk maintenance operations over a whole `Run`, used by the split editor.
+//!
+//! They only mutate the run they are given, so the editor can preview them on
+//! a copy (to report how many values change) and roll them back with Cancel
+//! like any other edit.
+
+use livesplit_core::{Run, TimeSpan, TimingMethod};
+
+const TIMING_METHODS: [TimingMethod; 2] = [TimingMethod::RealTime, TimingMethod::GameTime];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShiftError {
+    /// Applying the shift would leave a negative time on this segment.
+    NegativeSegment { index: usize, name: String },
+}
+
+impl std::fmt::Display for ShiftError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ShiftError::NegativeSegment { index, name } => write!(
+                f,
+                "Shifting would make segment {} (\"{name}\") negative",
+                index + 1
+            ),
+        }
+    }
+}
+
+/// Clears the personal best split times of every segment, for both timing methods.
+/// Returns how many values were removed.
+pub fn clear_split_times(run: &mut Run) -> usize {
+    let mut cleared = 0;
+    for segment in run.segments_mut() {
+        let time = segment.personal_best_split_time_mut();
+        for method in TIMING_METHODS {
+            if time[method].take().is_some() {
+                cleared += 1;
+            }
+        }
+    }
+    cleared
+}
+
+/// Clears the best segment times of every segment, for both timing methods.
+/// Returns how many values were removed.
+pub fn clear_best_segments(run: &mut Run) -> usize {
+    let mut cleared = 0;
+    for segment in run.segments_mut() {
+        let time = segment.best_segment_time_mut();
+        for method in TIMING_METHODS {
+            if time[method].take().is_some() {
+                cleared += 1;
+            }
+        }
+    }
+    cleared
+}
+
+/// Clears the attempt history together with every segment history.
+/// Returns how many attempts were removed.
+pub fn clear_attempt_history(run: &mut Run) -> usize {
+    let cleared = run.attempt_history().len();
+    run.clear_history();
+    cleared
+}
+
+/// Shifts every split time of the custom comparisons (Personal Best included)
+/// by `offset`, as if the start of the run had been re-timed.
+///
+/// Split times are cumulative, so only the first segment's duration changes.
+/// With `include_best_segments` its best segment is shifted too, since it
+/// starts at the same point. Returns how many values were changed.
+///
+/// The run is left untouched if any shifted value would be negative; the
+/// error names the first offending segment.
+pub fn shift_times(
+    run: &mut Run,
+    offset: time::Duration,
+    include_best_segments: bool,
+) -> Result<usize, ShiftError> {
+    let offset_span = TimeSpan::from_milliseconds(offset.whole_milliseconds() as f64);
+    let comparisons: Vec<String> = run.custom_comparisons().to_vec();
+
+    let mut shifted = run.clone();
+    let mut changed = 0;
+
+    for (index, segment) in shifted.segments_mut().iter_mut().enumerate() {
+        let mut segment_negative = false;
+
+        for comparison in &comparisons {
+            let time = segment.comparison_mut(comparison);
+            for method in TIMING_METHODS {
+                if let Some(span) = time[method] {
+                    let new_span = span + offset_span;
+                    segment_negative |= new_span.to_duration().is_negative();
+                    time[method] = Some(new_span);
+                    changed += 1;
+                }
+            }
+        }
+
+        if include_best_segments && index == 0 {
+            let time = segment.best_segment_time_mut();
+            for method in TIMING_METHODS {
+                if let Some(span) = time[method] {
+                    let new_span = span + offset_span;
+                    segment_negative |= new_span.to_duration().is_negative();
+                    time[method] = Some(new_span);
+                    changed += 1;
+                }
+            }
+        }
+
+        if segment_negative {
+            return Err(ShiftError::NegativeSegment {
+                index,
+                name: segment.name().to_owned(),
+            });
+        }
+    }
+
+    *run = shifted;
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod bulk_edit_tests {
+    use super::*;
+    use crate::test_support::real_time;
+    use livesplit_core::{Segment, Time};
+
+    fn time_both(rt_secs: f64, gt_secs: f64) -> Time {
+        Time::new()
+            .with_real_time(Some(TimeSpan::from_seconds(rt_secs)))
+            .with_game_time(Some(TimeSpan::from_seconds(gt_secs)))
+    }
+
+    /// Three segments: PB splits at 10/20/30s (RT) and 9/19 (GT on the first two),
+    /// golds of 8/9/9s (RT only) and two attempts in the history.
+    fn make_run() -> Run {
+        let mut run = Run::new();
+        let splits = [time_both(10.0, 9.0), time_both(20.0, 19.0), real_time(30.0)];
+        let golds = [8.0, 9.0, 9.0];
+        for (i, (split, gold)) in splits.into_iter().zip(golds).enumerate() {
+            let mut segment = Segment::new(format!("S{i}"));
+            segment.set_personal_best_split_time(split);
+            segment.set_best_segment_time(real_time(gold));
+            run.push_segment(segment);
+        }
+        run.add_attempt_with_index(Time::default(), 1, None, None, None);
+        run.add_attempt_with_index(real_time(30.0), 2, None, None, None);
+        run
+    }
+
+    fn pb_rt(run: &Run, index: usize) -> Option<i128> {
+        run.segments()[index]
+            .personal_best_split_time()
+            .real_time
+            .map(|t| t.to_duration().whole_milliseconds())
+    }
+
+    #[test]
+    fn clear_split_times_counts_every_method() {
+        let mut run = make_run();
+        assert_eq!(clear_split_times(&mut run), 5);
+        assert!(
+            run.segments()
+                .iter()
+                .all(|s| s.personal_best_split_time() == Time::default())
+        );
+        // Golds are untouched
+        assert!(run.segments()[0].best_segment_time().real_time.is_some());
+    }
+
+    #[test]
+    fn clear_best_segments_keeps_splits() {
+        let mut run = make_run();
+        assert_eq!(clear_best_segments(&mut run), 3);
+        assert!(
+            run.segments()
+                .iter()
+                .all(|s| s.best_segment_time() == Time::default())
+        );
+        assert_eq!(pb_rt(&run, 2), Some(30_000));
+    }
+
+    #[test]
+    fn clear_attempt_history_removes_attempts() {
+        let mut run = make_run();
+        assert_eq!(clear_attempt_history(&mut run), 2);
+        assert!(run.attempt_history().is_empty());
+        assert_eq!(clear_attempt_history(&mut run), 0);
+    }
+
+    #[test]
+    fn shift_forward_moves_all_splits() {
+        let mut run = make_run();
+        let changed = shift_times(&mut run, time::Duration::milliseconds(1_500), false).unwrap();
+        assert_eq!(changed, 5);
+        assert_eq!(pb_rt(&run, 0), Some(11_500));
+        assert_eq!(pb_rt(&run, 2), Some(31_500));
+        // Best segment not included
+        assert_eq!(
+            run.segments()[0].best_segment_time().real_time,
+            Some(TimeSpan::from_seconds(8.0))
+        );
+    }
+
+    #[test]
+    fn shift_with_best_segments_only_moves_first_gold() {
+        let mut run = make_run();
+        let changed = shift_times(&mut run, time::Duration::seconds(-2), true).unwrap();
+        assert_eq!(changed, 6);
+        assert_eq!(pb_rt(&run, 0), Some(8_000));
+        assert_eq!(
+            run.segments()[0]
+                .best_segment_time()
+                .real_time
+                .map(|t| t.to_duration().whole_milliseconds()),
+            Some(6_000)
+        );
+        assert_eq!(
+            run.segments()[1].best_segment_time().real_time,
+            Some(TimeSpan::from_seconds(9.0))
+        );
+    }
+
+    #[test]
+    fn shift_aborts_on_negative_and_leaves_run_untouched() {
+        let mut run = make_run();
+        let result = shift_times(&mut run, time::Duration::milliseconds(-9_500), false);
+        assert_eq!(
+            result,
+            Err(ShiftError::NegativeSegment {
+                index: 0,
+                name: "S0".to_owned()
+            })
+        );
+        assert_eq!(pb_rt(&run, 0), Some(10_000));
+    }
+
+    #[test]
+    fn shift_aborts_when_only_best_segment_goes_negative() {
+        let mut run = make_run();
+        let result = shift_times(&mut run, time::Duration::milliseconds(-8_500), true);
+        assert!(matches!(
+            result,
+            Err(ShiftError::NegativeSegment { index: 0, .. })
+        ));
+        assert_eq!(pb_rt(&run, 0), Some(10_000));
+    }
+}
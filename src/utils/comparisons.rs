@@ -1,41 +1,49 @@
-use crate::config::Config;
-use livesplit_core::{Timer, analysis::sum_of_segments::best::calculate as calculate_sob};
+use crate::config::{
+    Config, GoldSource, PaddedGoldComparison, RemainingWithoutBest, SkippedInSumOfBest,
+};
+use crate::formatters::current_adjusted_duration;
+use crate::io::attempt_state::resumed_split_time;
+use crate::utils::video_offset::video_offset;
+use livesplit_core::comparison::{
+    self, ComparisonGenerator, best_segments, latest_run, personal_best,
+};
+use livesplit_core::{
+    Attempt, Run, Segment, Time, TimeSpan, Timer, TimerPhase, TimingMethod,
+    analysis::sum_of_segments::best::calculate as calculate_sob,
+};
 
-pub fn current_attempt_running_duration(timer: &Timer) -> time::Duration {
-    use livesplit_core::TimingMethod;
-    let current_dur = timer
-        .current_attempt_duration()
-        .to_duration()
-        .checked_add(timer.run().offset().to_duration())
-        .unwrap_or_default();
-
-    let paused_time = timer.get_pause_time().unwrap_or_default().to_duration();
-
-    let loading_times = if timer.current_timing_method() == TimingMethod::GameTime {
-        timer.loading_times().to_duration()
-    } else {
-        time::Duration::ZERO
-    };
-
-    current_dur
-        .checked_sub(paused_time)
-        .unwrap_or_default()
-        .checked_sub(loading_times)
-        .unwrap_or_default()
+/// The attempt's running time, as shown by the main timer with
+/// `video_offset`. See [`current_adjusted_duration`].
+pub fn current_attempt_running_duration(
+    timer: &Timer,
+    video_offset: time::Duration,
+) -> time::Duration {
+    current_adjusted_duration(timer, timer.current_timing_method(), video_offset)
 }
 
-pub fn real_time_sob(timer: &Timer) -> time::Duration {
-    let mut predictions = vec![None; timer.run().len() + 1];
-    let predictions = &mut predictions[..];
-    calculate_sob(
-        timer.run().segments(),
-        predictions,
-        false,
-        true,
-        timer.current_timing_method(),
-    )
-    .unwrap_or_default()
-    .to_duration()
+/// Sum of best segments under the current timing method, with the
+/// segments that have no best time treated as `policy` says. `None` when
+/// the total is voided or can't be built.
+pub fn sum_of_best(timer: &Timer, policy: SkippedInSumOfBest) -> Option<time::Duration> {
+    let method = timer.current_timing_method();
+    let segments = timer.run().segments();
+    let missing = segments
+        .iter()
+        .any(|segment| segment.best_segment_time()[method].is_none());
+    match policy {
+        SkippedInSumOfBest::Void if missing => None,
+        SkippedInSumOfBest::Exclude if missing => Some(
+            segments
+                .iter()
+                .filter_map(|segment| segment.best_segment_time()[method])
+                .fold(time::Duration::ZERO, |sum, best| sum + best.to_duration()),
+        ),
+        _ => {
+            let mut predictions = vec![None; segments.len() + 1];
+            calculate_sob(segments, &mut predictions, false, true, method)
+                .map(|sum| sum.to_duration())
+        }
+    }
 }
 
 pub fn best_segment_duration(segment: &livesplit_core::Segment, timer: &Timer) -> time::Duration {
@@ -55,23 +63,87 @@ pub fn best_segment_duration(segment: &livesplit_core::Segment, timer: &Timer) -
     }
 }
 
-pub fn segment_split_time(segment: &livesplit_core::Segment, timer: &Timer) -> time::Duration {
-    use livesplit_core::TimingMethod;
-    if timer.current_timing_method() == TimingMethod::GameTime {
-        segment
-            .split_time()
-            .game_time
-            .unwrap_or_default()
-            .to_duration()
-    } else {
-        segment
-            .split_time()
-            .real_time
+/// Best duration of `segment` among the attempts newer than `session_start_attempt`,
+/// under the timer's current timing method. `None` if none of them reached it.
+pub fn session_best_segment_duration(
+    segment: &livesplit_core::Segment,
+    timer: &Timer,
+    session_start_attempt: i32,
+) -> Option<time::Duration> {
+    session_best_segment_duration_for(
+        segment,
+        timer.current_timing_method(),
+        session_start_attempt,
+    )
+}
+
+fn session_best_segment_duration_for(
+    segment: &livesplit_core::Segment,
+    method: TimingMethod,
+    session_start_attempt: i32,
+) -> Option<time::Duration> {
+    segment
+        .segment_history()
+        .iter()
+        .filter(|(attempt, _)| *attempt > session_start_attempt)
+        .filter_map(|(_, time)| time[method])
+        .map(|span| span.to_duration())
+        .min()
+}
+
+/// Gold threshold for `segment` according to the configured `GoldSource`.
+/// A ZERO result means there is nothing to beat, so any split counts as gold.
+pub fn gold_segment_duration(
+    segment: &livesplit_core::Segment,
+    timer: &Timer,
+    config: &Config,
+) -> time::Duration {
+    gold_segment_duration_for(segment, timer.current_timing_method(), config)
+}
+
+/// Same as `gold_segment_duration`, under `method` instead of the timer's.
+fn gold_segment_duration_for(
+    segment: &livesplit_core::Segment,
+    method: TimingMethod,
+    config: &Config,
+) -> time::Duration {
+    match config.general.gold_source {
+        GoldSource::StoredBest => segment.best_segment_time()[method]
             .unwrap_or_default()
-            .to_duration()
+            .to_duration(),
+        GoldSource::SessionBest => {
+            session_best_segment_duration_for(segment, method, config.session_start_attempt())
+                .unwrap_or_default()
+        }
     }
 }
 
+/// Split time of segment `index` in the attempt on `timer`. Splits taken
+/// before a saved attempt was resumed are skipped on the timer and count
+/// at their saved times, see [`resumed_split_time`].
+pub fn attempt_split_time(timer: &Timer, index: usize) -> Time {
+    let Some(segment) = timer.run().segments().get(index) else {
+        return Time::default();
+    };
+    let split = segment.split_time();
+    if split.real_time.is_none()
+        && split.game_time.is_none()
+        && timer
+            .current_split_index()
+            .is_some_and(|current| index < current)
+        && let Some(saved) = resumed_split_time(index)
+    {
+        return saved;
+    }
+    split
+}
+
+pub fn segment_split_time(timer: &Timer, index: usize) -> time::Duration {
+    attempt_split_time(timer, index)[timer.current_timing_method()]
+        .unwrap_or_default()
+        .to_duration()
+}
+
 pub fn segment_best_time(segment: &livesplit_core::Segment, timer: &Timer) -> time::Duration {
     segment
         .comparison_timing_method("Best Segments", timer.current_timing_method())
@@ -80,32 +152,423 @@ pub fn segment_best_time(segment: &livesplit_core::Segment, timer: &Timer) -> ti
 }
 
 pub fn segment_comparison_time(segment: &livesplit_core::Segment, timer: &Timer) -> time::Duration {
+    segment_comparison_time_for(segment, timer, timer.current_comparison())
+}
+
+/// Same as `segment_comparison_time`, against `comparison` instead of the
+/// current comparison.
+pub fn segment_comparison_time_for(
+    segment: &livesplit_core::Segment,
+    timer: &Timer,
+    comparison: &str,
+) -> time::Duration {
     segment
-        .comparison_timing_method(timer.current_comparison(), timer.current_timing_method())
+        .comparison_timing_method(comparison, timer.current_timing_method())
         .unwrap_or_default()
         .to_duration()
 }
 
+/// Comparison the analytical infos (previous segment, possible time save,
+/// current pace) measure against: `general.analytics-comparison` when the
+/// run has it, the current comparison otherwise.
+pub fn analytics_comparison<'a>(timer: &'a Timer, config: &'a Config) -> &'a str {
+    config
+        .general
+        .analytics_comparison
+        .as_deref()
+        .filter(|name| {
+            timer
+                .run()
+                .comparisons()
+                .any(|comparison| comparison == *name)
+        })
+        .unwrap_or_else(|| timer.current_comparison())
+}
+
+/// Per-segment duration of the current comparison at `index`, taken against
+/// the previous segment's comparison split. `None` when the comparison has no
+/// time for this segment, e.g. a PB comparison on splits that were never finished.
+pub fn segment_comparison_duration(timer: &Timer, index: usize) -> Option<time::Duration> {
+    // A later split of the comparison may be earlier than the one before
+    signed_segment_comparison_duration(timer, index).map(time::Duration::abs)
+}
+
+/// Same as [`segment_comparison_duration`] but keeping the sign. It's
+/// negative when the comparison splits earlier here than on the segment
+/// before, which only happens with broken comparison data.
+pub fn signed_segment_comparison_duration(timer: &Timer, index: usize) -> Option<time::Duration> {
+    let segments = timer.run().segments();
+    let comparison = timer.current_comparison();
+    let method = timer.current_timing_method();
+
+    let split = segments
+        .get(index)?
+        .comparison_timing_method(comparison, method)?
+        .to_duration();
+    let previous = index
+        .checked_sub(1)
+        .and_then(|prev| segments[prev].comparison_timing_method(comparison, method))
+        .map_or(time::Duration::ZERO, |t| t.to_duration());
+
+    Some(split.checked_sub(previous).unwrap_or_default())
+}
+
+/// The current comparison's split time at `index`, from the start of the
+/// run. `None` when the comparison has no time there.
+pub fn comparison_split_duration(timer: &Timer, index: usize) -> Option<time::Duration> {
+    timer
+        .run()
+        .segments()
+        .get(index)?
+        .comparison_timing_method(timer.current_comparison(), timer.current_timing_method())
+        .map(|time| time.to_duration())
+}
+
+/// The comparison time shown for the segment at `index` below the timer:
+/// its split time when `cumulative`, its segment time otherwise.
+pub fn comparison_line_duration(
+    timer: &Timer,
+    index: usize,
+    cumulative: bool,
+) -> Option<time::Duration> {
+    if cumulative {
+        comparison_split_duration(timer, index)
+    } else {
+        segment_comparison_duration(timer, index)
+    }
+}
+
+/// Personal best time of the segment at `index` in the current timing
+/// method. When the PB skipped the splits right before it, the time runs
+/// from the last PB split before those, so it covers them too. `None` when
+/// the PB has no time for this split.
+pub fn pb_segment_duration(timer: &Timer, index: usize) -> Option<time::Duration> {
+    let segments = timer.run().segments();
+    let method = timer.current_timing_method();
+    let split = segments.get(index)?.personal_best_split_time()[method]?.to_duration();
+    let previous = segments[..index]
+        .iter()
+        .rev()
+        .find_map(|segment| segment.personal_best_split_time()[method])
+        .map_or(time::Duration::ZERO, |time| time.to_duration());
+    Some(split.checked_sub(previous).unwrap_or_default())
+}
+
+/// Time of the segment at `index` in the current attempt: the recorded
+/// segment once it's split, the running time shifted back by `video_offset`
+/// while it's the current one. `None` for upcoming or skipped segments and
+/// outside an attempt.
+pub fn attempt_segment_duration(
+    timer: &Timer,
+    index: usize,
+    video_offset: time::Duration,
+) -> Option<time::Duration> {
+    if timer.current_phase() == TimerPhase::NotRunning {
+        return None;
+    }
+    let current = timer.current_split_index()?;
+    if index == current {
+        return current_segment_elapsed(timer, video_offset);
+    }
+    if index > current {
+        return None;
+    }
+    let method = timer.current_timing_method();
+    let split = attempt_split_time(timer, index)[method]?.to_duration();
+    let previous = (0..index)
+        .rev()
+        .find_map(|k| attempt_split_time(timer, k)[method])
+        .map_or(time::Duration::ZERO, |time| time.to_duration());
+    Some(split.checked_sub(previous).unwrap_or_default())
+}
+
+/// Final time of the current comparison, the number to beat. `None` when
+/// the comparison has no time for the last split.
+pub fn target_final_time(timer: &Timer) -> Option<time::Duration> {
+    timer
+        .run()
+        .segments()
+        .last()?
+        .comparison_timing_method(timer.current_comparison(), timer.current_timing_method())
+        .map(|time| time.to_duration())
+}
+
+/// What the comparison picker previews of a comparison before switching.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComparisonSummary {
+    /// The comparison's time for the last split.
+    pub final_time: Option<time::Duration>,
+    /// This attempt against the comparison at the last completed split both
+    /// have a time for, negative while ahead.
+    pub delta_at_last_split: Option<time::Duration>,
+}
+
+/// [`ComparisonSummary`] of `comparison`, without switching to it. Only the
+/// stored comparison times are read, nothing is recalculated.
+pub fn comparison_summary(timer: &Timer, comparison: &str) -> ComparisonSummary {
+    let method = timer.current_timing_method();
+    let segments = timer.run().segments();
+    let final_time = segments
+        .last()
+        .and_then(|segment| segment.comparison_timing_method(comparison, method))
+        .map(|time| time.to_duration());
+    let done = timer.current_split_index().unwrap_or(0).min(segments.len());
+    let delta_at_last_split = (0..done).rev().find_map(|index| {
+        let split = attempt_split_time(timer, index)[method]?;
+        let compared = segments[index].comparison_timing_method(comparison, method)?;
+        Some(split.to_duration() - compared.to_duration())
+    });
+    ComparisonSummary {
+        final_time,
+        delta_at_last_split,
+    }
+}
+
+/// Generated comparisons that are useful from the first attempt on.
+const ALWAYS_CYCLED: [&str; 2] = [personal_best::NAME, best_segments::NAME];
+
+/// Adds livesplit-core's "Latest Run" comparison, the times of the most
+/// recent attempt, to a run that doesn't generate it yet. Must be called
+/// before the run is handed to the timer, which generates the comparisons.
+pub fn ensure_latest_run(run: &mut Run) {
+    let generators = run.comparison_generators_mut();
+    if !generators
+        .iter()
+        .any(|generator| generator.name() == latest_run::NAME)
+    {
+        generators.push(Box::new(latest_run::LatestRun));
+    }
+}
+
+/// Names of the padded gold comparisons all start with this.
+pub const PADDED_GOLDS_PREFIX: &str = "Golds +";
+
+fn trimmed_number(value: f64) -> String {
+    let text = format!("{value:.2}");
+    text.trim_end_matches('0').trim_end_matches('.').to_owned()
+}
+
+/// Name of the comparison `padding` generates, e.g. "Golds +5%" or
+/// "Golds +5% +2s".
+pub fn padded_gold_name(padding: &PaddedGoldComparison) -> String {
+    let padding = padding.clamped();
+    let mut parts = Vec::new();
+    if padding.percent > 0.0 {
+        parts.push(format!("+{}%", trimmed_number(padding.percent)));
+    }
+    if padding.flat_seconds > 0.0 || parts.is_empty() {
+        parts.push(format!("+{}s", trimmed_number(padding.flat_seconds)));
+    }
+    format!("Golds {}", parts.join(" "))
+}
+
+/// Split times of the padded gold comparison, summed from the start: the
+/// `best` time of each segment padded as `padding` says, or its `fallback`
+/// time as it is when it has no best. `None` from the first segment with
+/// neither on, or where the sum no longer fits. The padding is clamped, see
+/// [`PaddedGoldComparison::clamped`].
+pub fn padded_gold_times(
+    best: &[Option<time::Duration>],
+    fallback: &[Option<time::Duration>],
+    padding: &PaddedGoldComparison,
+) -> Vec<Option<time::Duration>> {
+    let padding = padding.clamped();
+    let factor = 1.0 + padding.percent / 100.0;
+    let flat = time::Duration::seconds_f64(padding.flat_seconds);
+    let mut total = Some(time::Duration::ZERO);
+    best.iter()
+        .enumerate()
+        .map(|(index, best)| {
+            let segment = match best {
+                Some(best) => time::Duration::checked_seconds_f64(best.as_seconds_f64() * factor)
+                    .and_then(|padded| padded.checked_add(flat)),
+                None => fallback.get(index).copied().flatten(),
+            };
+            total = total
+                .zip(segment)
+                .and_then(|(total, segment)| total.checked_add(segment));
+            total
+        })
+        .collect()
+}
+
+/// Generates the padded gold comparison, see [`padded_gold_times`].
+/// livesplit-core runs it again whenever the golds may have changed.
+#[derive(Debug, Clone)]
+pub struct PaddedGolds {
+    name: String,
+    padding: PaddedGoldComparison,
+}
+
+impl ComparisonGenerator for PaddedGolds {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn generate(&mut self, segments: &mut [Segment], _: &[Attempt]) {
+        for method in [TimingMethod::RealTime, TimingMethod::GameTime] {
+            let best: Vec<_> = segments
+                .iter()
+                .map(|segment| segment.best_segment_time()[method].map(|time| time.to_duration()))
+                .collect();
+            // The personal best's segment times
+            let fallback: Vec<_> = (0..segments.len())
+                .map(|index| {
+                    let split = segments[index].personal_best_split_time()[method]?;
+                    let previous = match index {
+                        0 => time::Duration::ZERO,
+                        _ => segments[index - 1].personal_best_split_time()[method]?.to_duration(),
+                    };
+                    Some(split.to_duration() - previous)
+                })
+                .collect();
+            let times = padded_gold_times(&best, &fallback, &self.padding);
+            for (segment, time) in segments.iter_mut().zip(times) {
+                segment.comparison_mut(&self.name)[method] = time.map(TimeSpan::from);
+            }
+        }
+    }
+}
+
+/// Adds the padded gold comparison `padding` asks for, replacing one made
+/// with other settings. Like [`ensure_latest_run`], before the run is handed
+/// to the timer. Custom comparisons saved with `persist` give way to it,
+/// whatever padding they were made with, so none is listed twice or left
+/// behind once the padding changes.
+pub fn ensure_padded_golds(run: &mut Run, padding: &PaddedGoldComparison) {
+    run.comparison_generators_mut()
+        .retain(|generator| !generator.name().starts_with(PADDED_GOLDS_PREFIX));
+    run.custom_comparisons_mut()
+        .retain(|comparison| !comparison.starts_with(PADDED_GOLDS_PREFIX));
+    if !padding.enabled {
+        return;
+    }
+    run.comparison_generators_mut().push(Box::new(PaddedGolds {
+        name: padded_gold_name(padding),
+        padding: padding.clamped(),
+    }));
+}
+
+/// Turns the padded gold comparison into a custom one, so its times are
+/// saved with the run. Generated comparisons aren't.
+pub fn persist_padded_golds(run: &mut Run) {
+    let generators = run.comparison_generators_mut();
+    let Some(index) = generators
+        .iter()
+        .position(|generator| generator.name().starts_with(PADDED_GOLDS_PREFIX))
+    else {
+        return;
+    };
+    let name = generators.remove(index).name().to_owned();
+    if !run.custom_comparisons().contains(&name) {
+        run.custom_comparisons_mut().push(name);
+    }
+}
+
+/// Comparisons offered when switching, in cycle order. Custom comparisons are
+/// always kept. livesplit-core's generated ones need `min_attempts` attempts
+/// of history, since before that they mostly repeat the personal best. The
+/// latest run only needs one attempt to pace against. Comparisons generated
+/// from anything else, like the padded golds or inline comparisons from the
+/// config, need none.
+pub fn cycle_comparisons(run: &Run, min_attempts: u32) -> Vec<String> {
+    let attempts = run.attempt_history().len();
+    let enough_history = attempts >= min_attempts as usize;
+    let from_history: Vec<_> = comparison::default_generators()
+        .iter()
+        .map(|generator| generator.name().to_owned())
+        .filter(|name| !ALWAYS_CYCLED.contains(&name.as_str()))
+        .collect();
+    let needs_history = |name: &str| match name {
+        latest_run::NAME => attempts == 0,
+        name => from_history.iter().any(|generated| generated == name),
+    };
+    let custom = run.custom_comparisons().iter().map(String::as_str);
+    let generated = run
+        .comparison_generators()
+        .iter()
+        .map(|generator| generator.name())
+        .filter(|name| enough_history || !needs_history(name));
+    custom.chain(generated).map(str::to_owned).collect()
+}
+
+/// The comparison after (or before) `current` in `order`, wrapping around.
+/// A comparison that isn't part of the cycle continues from the start (or end).
+pub fn next_in_cycle<'a>(order: &'a [String], current: &str, forward: bool) -> Option<&'a str> {
+    let len = order.len();
+    if len == 0 {
+        return None;
+    }
+    let next = match order.iter().position(|name| name == current) {
+        Some(index) if forward => (index + 1) % len,
+        Some(index) => (index + len - 1) % len,
+        None if forward => 0,
+        None => len - 1,
+    };
+    Some(order[next].as_str())
+}
+
+/// Switches to the next or previous comparison in the filtered cycle. Used
+/// for the comparison hotkeys instead of livesplit-core's own cycling, which
+/// would visit every comparison.
+pub fn switch_comparison(timer: &mut Timer, forward: bool, min_attempts: u32) {
+    let order = cycle_comparisons(timer.run(), min_attempts);
+    if let Some(next) = next_in_cycle(&order, timer.current_comparison(), forward) {
+        let _ = timer.set_current_comparison(next);
+    }
+}
+
+/// Spots the resets that saved a new personal best, from the frames of the
+/// timer. Resets come from the hotkeys, the global hotkey system and the
+/// auto splitter alike, so they are noticed by what they leave behind: an
+/// attempt over, added to the history along with a different final PB
+/// time. Splits loaded between attempts don't count.
+#[derive(Debug, Default)]
+pub struct NewPbWatch {
+    seen: Option<(TimerPhase, usize, Time)>,
+}
+
+impl NewPbWatch {
+    /// Returns true on the first frame after a reset that saved a new PB.
+    pub fn observe(&mut self, timer: &Timer) -> bool {
+        let run = timer.run();
+        let Some(last) = run.segments().last() else {
+            return false;
+        };
+        let phase = timer.current_phase();
+        let attempts = run.attempt_history().len();
+        let pb = last.personal_best_split_time();
+        let new_pb = phase == TimerPhase::NotRunning
+            && self
+                .seen
+                .is_some_and(|(seen_phase, seen_attempts, seen_pb)| {
+                    seen_phase != TimerPhase::NotRunning
+                        && attempts > seen_attempts
+                        && pb != seen_pb
+                });
+        self.seen = Some((phase, attempts, pb));
+        new_pb
+    }
+}
+
 pub fn previous_comparison_values(timer: &Timer, index: usize) -> (time::Duration, time::Duration) {
-    use livesplit_core::TimingMethod;
+    previous_comparison_values_for(timer, index, timer.current_comparison())
+}
+
+/// Same as `previous_comparison_values`, against `comparison`.
+pub fn previous_comparison_values_for(
+    timer: &Timer,
+    index: usize,
+    comparison: &str,
+) -> (time::Duration, time::Duration) {
     let segments = timer.run().segments();
     if index > 0 {
         let prev = &segments[index - 1];
         let prev_comp_duration = prev
-            .comparison_timing_method(timer.current_comparison(), timer.current_timing_method())
+            .comparison_timing_method(comparison, timer.current_timing_method())
             .unwrap_or_default()
             .to_duration();
-        let prev_split_time = if timer.current_timing_method() == TimingMethod::GameTime {
-            prev.split_time()
-                .game_time
-                .unwrap_or_default()
-                .to_duration()
-        } else {
-            prev.split_time()
-                .real_time
-                .unwrap_or_default()
-                .to_duration()
-        };
+        let prev_split_time = segment_split_time(timer, index - 1);
         (prev_comp_duration, prev_split_time)
     } else {
         (time::Duration::ZERO, time::Duration::ZERO)
@@ -113,7 +576,6 @@ pub fn previous_comparison_values(timer: &Timer, index: usize) -> (time::Duratio
 }
 
 pub fn best_comparison_values(timer: &Timer, index: usize) -> (time::Duration, time::Duration) {
-    use livesplit_core::TimingMethod;
     let segments = timer.run().segments();
     if index > 0 {
         let prev = &segments[index - 1];
@@ -121,23 +583,33 @@ pub fn best_comparison_values(timer: &Timer, index: usize) -> (time::Duration, t
             .comparison_timing_method("Best Segments", timer.current_timing_method())
             .unwrap_or_default()
             .to_duration();
-        let prev_split_time = if timer.current_timing_method() == TimingMethod::GameTime {
-            prev.split_time()
-                .game_time
-                .unwrap_or_default()
-                .to_duration()
-        } else {
-            prev.split_time()
-                .real_time
-                .unwrap_or_default()
-                .to_duration()
-        };
+        let prev_split_time = segment_split_time(timer, index - 1);
         (prev_best_duration, prev_split_time)
     } else {
         (time::Duration::ZERO, time::Duration::ZERO)
     }
 }
 
+/// Where a delta is shown, for `format.invert-deltas`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeltaWidget {
+    /// The split list.
+    Splits,
+    /// The readouts under the timer.
+    Info,
+}
+
+/// [`format_signed`] for a delta shown in `widget`, with the sign flipped when
+/// `format.invert-deltas` reads it as time left there. Only the text changes:
+/// callers color by the real delta, so ahead stays green either way.
+pub fn format_delta(diff: time::Duration, config: &Config, widget: DeltaWidget) -> String {
+    let inverted = match widget {
+        DeltaWidget::Splits => config.format.invert_deltas.splits,
+        DeltaWidget::Info => config.format.invert_deltas.info,
+    };
+    format_signed(if inverted { -diff } else { diff }, config)
+}
+
 pub fn format_signed(diff: time::Duration, config: &Config) -> String {
     let sign = if diff.is_positive() {
         "+"
@@ -146,11 +618,19 @@ pub fn format_signed(diff: time::Duration, config: &Config) -> String {
     } else {
         "~"
     };
-    let abs = diff.abs();
-    let formatted = config.format.split.format_segment_time(&abs);
+    let formatted = config
+        .format
+        .split
+        .format_duration_rounded(&diff, config.format.delta_rounding);
     format!("{sign}{formatted}")
 }
 
+/// `class`, unless it's empty or `style.monochrome` leaves the colors out.
+/// For the classes that only color a widget, like the delta's.
+pub fn color_class(config: &Config, class: &'static str) -> Option<&'static str> {
+    (!class.is_empty() && !config.style.monochrome.unwrap_or(false)).then_some(class)
+}
+
 pub fn classify_split_label(
     comparison_duration: time::Duration,
     split_duration: time::Duration,
@@ -183,12 +663,182 @@ pub fn classify_split_label(
 pub fn previous_split_combined_gold_and_prev_comparison(
     timer: &Timer,
     index: usize,
+) -> (time::Duration, time::Duration, time::Duration) {
+    previous_split_combined_gold_with(
+        timer,
+        index,
+        timer.current_comparison(),
+        timer.current_timing_method(),
+        |segment| best_segment_duration(segment, timer),
+    )
+}
+
+/// Same as `previous_split_combined_gold_and_prev_comparison`, but the combined
+/// gold is built from the gold source selected in `config`.
+pub fn previous_split_combined_gold_for_config(
+    timer: &Timer,
+    index: usize,
+    config: &Config,
+) -> (time::Duration, time::Duration, time::Duration) {
+    previous_split_combined_gold_with(
+        timer,
+        index,
+        timer.current_comparison(),
+        timer.current_timing_method(),
+        |segment| gold_segment_duration(segment, timer, config),
+    )
+}
+
+/// What the running segment can still save against `comparison`: the
+/// comparison's time for it, skipped segments before it included, minus the
+/// combined gold. `None` when the comparison has no time for it.
+pub fn live_possible_time_save(timer: &Timer, comparison: &str) -> Option<time::Duration> {
+    let index = timer.current_split_index().unwrap_or(0);
+    let segment = timer.run().segments().get(index)?;
+    let comparison_time = segment_comparison_time_for(segment, timer, comparison);
+    if comparison_time == time::Duration::ZERO {
+        return None;
+    }
+    let method = timer.current_timing_method();
+    let (_, combined_gold, previous_comparison_time) =
+        previous_split_combined_gold_with(timer, index, comparison, method, |segment| {
+            best_segment_duration(segment, timer)
+        });
+    Some(
+        comparison_time
+            .checked_sub(previous_comparison_time)
+            .unwrap_or_default()
+            .checked_sub(combined_gold)
+            .unwrap_or_default(),
+    )
+}
+
+/// How far ahead (negative) or behind segment `index` was split against the
+/// current comparison. `None` when it wasn't split, was skipped, or the
+/// comparison has no time for it.
+pub fn split_delta(timer: &Timer, index: usize) -> Option<time::Duration> {
+    let segment = timer.run().segments().get(index)?;
+    let split_time = segment_split_time(timer, index);
+    let comparison_time = segment_comparison_time(segment, timer);
+    if split_time == time::Duration::ZERO || comparison_time == time::Duration::ZERO {
+        return None;
+    }
+    split_time.checked_sub(comparison_time)
+}
+
+/// Whether segment `index` was a gold, ahead or behind once it was split, as
+/// the class the splits list puts on its delta. `None` when the segment has
+/// no split time (not reached or skipped) or nothing to compare against.
+/// Monochrome is left to `color_class` where the class is added.
+pub fn split_delta_class(timer: &Timer, config: &Config, index: usize) -> Option<&'static str> {
+    split_delta_class_for(timer, config, index, timer.current_timing_method())
+}
+
+/// Same as `split_delta_class`, under `method` instead of the timer's.
+pub fn split_delta_class_for(
+    timer: &Timer,
+    config: &Config,
+    index: usize,
+    method: TimingMethod,
+) -> Option<&'static str> {
+    let segment = timer.run().segments().get(index)?;
+    let comparison = timer.current_comparison();
+    let split_time = split_time_for(timer, index, method);
+    let comparison_time = comparison_time_for(segment, comparison, method);
+    if split_time == time::Duration::ZERO || comparison_time == time::Duration::ZERO {
+        return None;
+    }
+    let (previous_split_time, gold_duration, previous_comparison_duration) =
+        previous_split_combined_gold_with(timer, index, comparison, method, |segment| {
+            gold_segment_duration_for(segment, method, config)
+        });
+    let comparison_duration = comparison_time
+        .checked_sub(previous_comparison_duration)
+        .unwrap_or_default()
+        .abs();
+    let split_duration = split_time
+        .checked_sub(previous_split_time)
+        .unwrap_or_default();
+    let diff = split_time.checked_sub(comparison_time).unwrap_or_default();
+    Some(classify_split_label(
+        comparison_duration,
+        split_duration,
+        diff,
+        gold_duration,
+        false,
+    ))
+    .filter(|class| !class.is_empty())
+}
+
+/// The split time of segment `index` under `method`, ZERO when it has none.
+fn split_time_for(timer: &Timer, index: usize, method: TimingMethod) -> time::Duration {
+    attempt_split_time(timer, index)[method]
+        .unwrap_or_default()
+        .to_duration()
+}
+
+fn comparison_time_for(
+    segment: &livesplit_core::Segment,
+    comparison: &str,
+    method: TimingMethod,
+) -> time::Duration {
+    segment
+        .comparison_timing_method(comparison, method)
+        .unwrap_or_default()
+        .to_duration()
+}
+
+/// Which of gold, ahead or behind a split segment was under each timing
+/// method. The two disagree when e.g. loads made a segment a gold in game
+/// time only. Worked out once per split, so toggling the timing method
+/// only picks the other one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MethodClasses {
+    pub real_time: Option<&'static str>,
+    pub game_time: Option<&'static str>,
+}
+
+impl MethodClasses {
+    /// Classes of split segment `index`, see `split_delta_class`.
+    pub fn of(timer: &Timer, config: &Config, index: usize) -> Self {
+        Self {
+            real_time: split_delta_class_for(timer, config, index, TimingMethod::RealTime),
+            game_time: split_delta_class_for(timer, config, index, TimingMethod::GameTime),
+        }
+    }
+
+    pub fn get(self, method: TimingMethod) -> Option<&'static str> {
+        match method {
+            TimingMethod::RealTime => self.real_time,
+            TimingMethod::GameTime => self.game_time,
+        }
+    }
+
+    /// Says when only one of the timing methods has the segment as a gold,
+    /// e.g. "Gold (IGT only)". `None` when they agree or the segment wasn't
+    /// timed with both.
+    pub fn gold_note(self) -> Option<&'static str> {
+        match (self.real_time?, self.game_time?) {
+            ("goldsplit", "goldsplit") => None,
+            ("goldsplit", _) => Some("Gold (RTA only)"),
+            (_, "goldsplit") => Some("Gold (IGT only)"),
+            _ => None,
+        }
+    }
+}
+
+fn previous_split_combined_gold_with(
+    timer: &Timer,
+    index: usize,
+    comparison: &str,
+    method: TimingMethod,
+    gold: impl Fn(&livesplit_core::Segment) -> time::Duration,
 ) -> (time::Duration, time::Duration, time::Duration) {
     let segments = timer.run().segments();
     let mut last_non_skipped: Option<usize> = None;
     if index > 0 {
         for k in (0..index).rev() {
-            if segment_split_time(&segments[k], timer) != time::Duration::ZERO {
+            if split_time_for(timer, k, method) != time::Duration::ZERO {
                 last_non_skipped = Some(k);
                 break;
             }
@@ -201,59 +851,328 @@ pub fn previous_split_combined_gold_and_prev_comparison(
     let mut combined_gold = time::Duration::ZERO;
     for k in start..=index {
         combined_gold = combined_gold
-            .checked_add(best_segment_duration(&segments[k], timer))
+            .checked_add(gold(&segments[k]))
             .unwrap_or_default();
     }
 
     // The previous split time is either the last non-skipped split time, or ZERO if none.
-    let previous_split_time = last_non_skipped.map_or(time::Duration::ZERO, |k| {
-        segment_split_time(&segments[k], timer)
-    });
+    let previous_split_time =
+        last_non_skipped.map_or(time::Duration::ZERO, |k| split_time_for(timer, k, method));
 
     let previous_comparison_time = last_non_skipped.map_or(time::Duration::ZERO, |k| {
-        segment_comparison_time(&segments[k], timer)
+        comparison_time_for(&segments[k], comparison, method)
     });
 
     (previous_split_time, combined_gold, previous_comparison_time)
 }
 
-#[cfg(test)]
-mod classify_split_labels_tests {
-    use super::*;
-    use time::Duration;
+/// Live state of the current segment against its gold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GoldPace {
+    /// Still achievable: time left before the gold is lost.
+    Ahead(time::Duration),
+    /// Already over the gold by this much.
+    Over(time::Duration),
+}
 
-    #[test]
-    fn classify_gold_when_first_split() {
-        let comparison = Duration::ZERO;
-        let split_duration = Duration::seconds(8);
-        let diff = Duration::ZERO;
-        let gold = Duration::ZERO;
+impl GoldPace {
+    /// Elapsed time relative to the gold, negative while still achievable.
+    pub fn diff(self) -> time::Duration {
+        match self {
+            GoldPace::Ahead(remaining) => -remaining,
+            GoldPace::Over(over) => over,
+        }
+    }
+}
 
-        let class = classify_split_label(comparison, split_duration, diff, gold, false);
-        assert!(class == "goldsplit", "Expected goldsplit: got {class:?}",);
+/// Compares the time spent in a segment against its gold. A split only counts
+/// as gold when it is strictly faster, so reaching the gold duration flips to `Over`.
+/// `None` when there is no gold to chase.
+pub fn gold_pace(gold: time::Duration, elapsed: time::Duration) -> Option<GoldPace> {
+    if gold <= time::Duration::ZERO {
+        return None;
+    }
+    if elapsed < gold {
+        Some(GoldPace::Ahead(gold - elapsed))
+    } else {
+        Some(GoldPace::Over(elapsed - gold))
     }
+}
 
-    #[test]
-    fn classify_gold_when_not_running_and_new_best_and_ahead() {
-        let comparison = Duration::seconds(10);
-        let split_duration = Duration::seconds(8);
-        let diff = Duration::seconds(-2);
-        let gold = Duration::seconds(9);
+/// Time save shown on a split row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeSave {
+    /// Upcoming segment: the comparison's segment minus the gold, never negative.
+    Possible(time::Duration),
+    /// Completed segment: our segment minus the comparison's, negative when
+    /// time was saved.
+    Actual(time::Duration),
+}
 
-        let class = classify_split_label(comparison, split_duration, diff, gold, false);
-        assert!(class == "goldsplit", "Expected goldsplit: got {class:?}",);
+/// Time save for the row at `index` while `current` is the running segment.
+/// The running segment has none, and neither do skipped segments or
+/// segments missing a gold or comparison time.
+pub fn segment_time_save(
+    timer: &Timer,
+    config: &Config,
+    current: Option<usize>,
+    index: usize,
+) -> Option<TimeSave> {
+    let segment = timer.run().segments().get(index)?;
+    match current {
+        Some(current) if current == index => None,
+        Some(current) if current > index => {
+            let split_time = segment_split_time(timer, index);
+            let comparison_time = segment_comparison_time(segment, timer);
+            if split_time == time::Duration::ZERO || comparison_time == time::Duration::ZERO {
+                return None;
+            }
+            let (previous_split_time, _, previous_comparison_time) =
+                previous_split_combined_gold_for_config(timer, index, config);
+            Some(TimeSave::Actual(
+                (split_time - previous_split_time) - (comparison_time - previous_comparison_time),
+            ))
+        }
+        _ => {
+            let gold = gold_segment_duration(segment, timer, config);
+            if gold == time::Duration::ZERO {
+                return None;
+            }
+            let comparison = segment_comparison_duration(timer, index)?;
+            Some(TimeSave::Possible(
+                (comparison - gold).max(time::Duration::ZERO),
+            ))
+        }
     }
+}
 
-    #[test]
-    fn classify_gold_when_not_running_and_zero_gold_duration() {
-        // When gold duration is ZERO and not running, we treat it as gold (first split behavior)
-        let comparison = Duration::ZERO;
-        let split_duration = Duration::seconds(12);
-        let diff = Duration::ZERO;
-        let gold = Duration::ZERO;
+/// "1.20" for a possible save in the segment format, "-0.80" or "+2.10" for
+/// an actual one, formatted like the deltas of the split list.
+pub fn format_time_save(time_save: TimeSave, config: &Config) -> String {
+    match time_save {
+        TimeSave::Possible(save) => config.format.segment.format_duration(&save),
+        TimeSave::Actual(diff) => format_delta(diff, config, DeltaWidget::Splits),
+    }
+}
 
-        let class = classify_split_label(comparison, split_duration, diff, gold, false);
-        assert!(
+/// Time spent since the last non-skipped split, while an attempt is in progress,
+/// as shown with `video_offset`. Recomputed from the timer on every call, so
+/// undoing a split measures the restored segment again.
+pub fn current_segment_elapsed(
+    timer: &Timer,
+    video_offset: time::Duration,
+) -> Option<time::Duration> {
+    let phase = timer.current_phase();
+    if !(phase.is_running() || phase.is_paused()) {
+        return None;
+    }
+    let index = timer.current_split_index()?;
+    let method = timer.current_timing_method();
+    let (previous_split_time, _, _) =
+        previous_split_combined_gold_with(timer, index, timer.current_comparison(), method, |_| {
+            time::Duration::ZERO
+        });
+    Some(
+        current_attempt_running_duration(timer, video_offset)
+            .checked_sub(previous_split_time)
+            .unwrap_or_default(),
+    )
+}
+
+/// What the rest of the run takes at gold pace, summed once per split so
+/// the countdown only subtracts on each tick.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemainingBest {
+    /// The segments from each index to the end summed, with a trailing zero.
+    suffix: Vec<time::Duration>,
+    /// The sum from each index stands in a comparison time for a missing
+    /// best segment.
+    estimated: Vec<bool>,
+    /// The sum from each index lacks a segment with neither time.
+    incomplete: Vec<bool>,
+}
+
+impl RemainingBest {
+    /// Sums `best` segment times, with `fallback` standing in for the
+    /// missing ones unless `policy` hides them.
+    pub fn from_segments(
+        best: &[Option<time::Duration>],
+        fallback: &[Option<time::Duration>],
+        policy: RemainingWithoutBest,
+    ) -> Self {
+        let len = best.len();
+        let mut suffix = vec![time::Duration::ZERO; len + 1];
+        let mut estimated = vec![false; len + 1];
+        let mut incomplete = vec![false; len + 1];
+        for index in (0..len).rev() {
+            let fallback = match policy {
+                RemainingWithoutBest::Comparison => fallback.get(index).copied().flatten(),
+                RemainingWithoutBest::Hide => None,
+            };
+            let (time, is_estimate) = match (best[index], fallback) {
+                (Some(best), _) => (Some(best), false),
+                (None, Some(fallback)) => (Some(fallback), true),
+                (None, None) => (None, false),
+            };
+            suffix[index] = suffix[index + 1] + time.unwrap_or_default();
+            estimated[index] = estimated[index + 1] || is_estimate;
+            incomplete[index] = incomplete[index + 1] || time.is_none();
+        }
+        Self {
+            suffix,
+            estimated,
+            incomplete,
+        }
+    }
+
+    /// The best segments of `timer`'s run, in the current timing method,
+    /// with `comparison`'s segment times as the fallback.
+    pub fn new(timer: &Timer, comparison: &str, policy: RemainingWithoutBest) -> Self {
+        let method = timer.current_timing_method();
+        let segments = timer.run().segments();
+        let best: Vec<_> = segments
+            .iter()
+            .map(|segment| segment.best_segment_time()[method].map(|time| time.to_duration()))
+            .collect();
+        let fallback: Vec<_> = segments
+            .iter()
+            .enumerate()
+            .map(|(index, segment)| {
+                let split = segment.comparison_timing_method(comparison, method)?;
+                let previous = match index {
+                    0 => time::Duration::ZERO,
+                    _ => segments[index - 1]
+                        .comparison_timing_method(comparison, method)?
+                        .to_duration(),
+                };
+                Some(split.to_duration() - previous)
+            })
+            .collect();
+        Self::from_segments(&best, &fallback, policy)
+    }
+
+    /// Time left at gold pace with `elapsed` spent since the split before
+    /// `start`, in the running segment `current` (past the skipped ones from
+    /// `start` on). It never drops below what's left after the running
+    /// segment, however far over its gold that one is.
+    pub fn remaining(
+        &self,
+        start: usize,
+        current: usize,
+        elapsed: time::Duration,
+    ) -> time::Duration {
+        let last = self.suffix.len() - 1;
+        let after = self.suffix[(current + 1).min(last)];
+        (self.suffix[start.min(last)] - elapsed).max(after)
+    }
+
+    pub fn is_estimated(&self, start: usize) -> bool {
+        self.estimated[start.min(self.estimated.len() - 1)]
+    }
+
+    pub fn is_incomplete(&self, start: usize) -> bool {
+        self.incomplete[start.min(self.incomplete.len() - 1)]
+    }
+}
+
+/// The first segment the running segment's elapsed time covers: the one
+/// after the last split that wasn't skipped.
+pub fn segments_since_last_split(timer: &Timer, current: usize) -> usize {
+    let method = timer.current_timing_method();
+    let segments = timer.run().segments();
+    (0..current.min(segments.len()))
+        .rev()
+        .find(|index| segments[*index].split_time()[method].is_some())
+        .map_or(0, |index| index + 1)
+}
+
+/// Gold pace of the running segment. Skipped segments right before it are
+/// folded into the gold, since the elapsed time covers them too.
+pub fn current_gold_pace(timer: &Timer, config: &Config) -> Option<GoldPace> {
+    let elapsed = current_segment_elapsed(timer, video_offset(config))?;
+    let index = timer.current_split_index()?;
+    let (_, combined_gold, _) = previous_split_combined_gold_for_config(timer, index, config);
+    gold_pace(combined_gold, elapsed)
+}
+
+/// Whether the segment being run, or the first one before the start, has a
+/// gold to chase. Skipped segments right before it count like in
+/// [`current_gold_pace`]. True once the run is over.
+pub fn current_segment_has_gold(timer: &Timer, config: &Config) -> bool {
+    let index = timer.current_split_index().unwrap_or(0);
+    if index >= timer.run().len() {
+        return true;
+    }
+    let (_, combined_gold, _) = previous_split_combined_gold_for_config(timer, index, config);
+    combined_gold > time::Duration::ZERO
+}
+
+/// Row class for the live gold indicator, applied on top of the
+/// comparison-based coloring. `None` when the segment has no gold to chase.
+pub fn live_gold_row_class(pace: Option<GoldPace>) -> Option<&'static str> {
+    match pace? {
+        GoldPace::Ahead(_) => Some("on-gold-pace"),
+        GoldPace::Over(_) => Some("gold-lost"),
+    }
+}
+
+/// How long until the running segment can no longer beat its gold or the
+/// current comparison, whichever comes first. `None` once both are lost.
+pub fn time_left_to_threshold(timer: &Timer, config: &Config) -> Option<time::Duration> {
+    let gold_left = current_gold_pace(timer, config).and_then(|pace| match pace {
+        GoldPace::Ahead(left) => Some(left),
+        GoldPace::Over(_) => None,
+    });
+
+    let comparison_left = timer.current_split().and_then(|segment| {
+        let comparison = segment_comparison_time(segment, timer);
+        let left = comparison - current_attempt_running_duration(timer, video_offset(config));
+        (comparison != time::Duration::ZERO && left.is_positive()).then_some(left)
+    });
+
+    match (gold_left, comparison_left) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+#[cfg(test)]
+mod classify_split_labels_tests {
+    use super::*;
+    use time::Duration;
+
+    #[test]
+    fn classify_gold_when_first_split() {
+        let comparison = Duration::ZERO;
+        let split_duration = Duration::seconds(8);
+        let diff = Duration::ZERO;
+        let gold = Duration::ZERO;
+
+        let class = classify_split_label(comparison, split_duration, diff, gold, false);
+        assert!(class == "goldsplit", "Expected goldsplit: got {class:?}",);
+    }
+
+    #[test]
+    fn classify_gold_when_not_running_and_new_best_and_ahead() {
+        let comparison = Duration::seconds(10);
+        let split_duration = Duration::seconds(8);
+        let diff = Duration::seconds(-2);
+        let gold = Duration::seconds(9);
+
+        let class = classify_split_label(comparison, split_duration, diff, gold, false);
+        assert!(class == "goldsplit", "Expected goldsplit: got {class:?}",);
+    }
+
+    #[test]
+    fn classify_gold_when_not_running_and_zero_gold_duration() {
+        // When gold duration is ZERO and not running, we treat it as gold (first split behavior)
+        let comparison = Duration::ZERO;
+        let split_duration = Duration::seconds(12);
+        let diff = Duration::ZERO;
+        let gold = Duration::ZERO;
+
+        let class = classify_split_label(comparison, split_duration, diff, gold, false);
+        assert!(
             class == "goldsplit",
             "Expected goldsplit when gold duration is zero and not running: got {class:?}",
         );
@@ -330,6 +1249,56 @@ mod classify_split_labels_tests {
     }
 }
 
+#[cfg(test)]
+mod sum_of_best_tests {
+    use super::*;
+    use crate::test_support::make_timer;
+
+    /// Golds of 10s and 20s around a segment that was always skipped.
+    fn run_with_a_skipped_segment() -> Timer {
+        make_timer(
+            TimingMethod::RealTime,
+            &[
+                ("A", None, Some(10.0)),
+                ("Skipped", None, None),
+                ("C", None, Some(20.0)),
+            ],
+        )
+    }
+
+    #[test]
+    fn a_missing_best_voids_the_total_by_default() {
+        let timer = run_with_a_skipped_segment();
+        assert_eq!(SkippedInSumOfBest::default(), SkippedInSumOfBest::Void);
+        assert_eq!(sum_of_best(&timer, SkippedInSumOfBest::Void), None);
+    }
+
+    #[test]
+    fn excluded_segments_leave_the_others_summed() {
+        let timer = run_with_a_skipped_segment();
+        assert_eq!(
+            sum_of_best(&timer, SkippedInSumOfBest::Exclude),
+            Some(time::Duration::seconds(30))
+        );
+    }
+
+    #[test]
+    fn both_policies_agree_without_a_missing_best() {
+        let mut run = Run::new();
+        for gold in [10.0, 5.5] {
+            let mut segment = Segment::new("Split");
+            segment.set_best_segment_time(
+                Time::new().with_real_time(Some(TimeSpan::from_seconds(gold))),
+            );
+            run.push_segment(segment);
+        }
+        let timer = Timer::new(run).expect("timer");
+        let expected = Some(time::Duration::milliseconds(15_500));
+        assert_eq!(sum_of_best(&timer, SkippedInSumOfBest::Void), expected);
+        assert_eq!(sum_of_best(&timer, SkippedInSumOfBest::Exclude), expected);
+    }
+}
+
 #[cfg(test)]
 mod skipped_segments_context_tests {
     use super::*;
@@ -503,3 +1472,1540 @@ mod skipped_segments_context_tests {
         );
     }
 }
+
+#[cfg(test)]
+mod gold_source_tests {
+    use super::*;
+    use crate::config::{Config, GoldSource};
+    use crate::test_support::{real_time, run_with};
+    use livesplit_core::{Run, Segment, Time, Timer};
+    use time::Duration;
+
+    /// One segment with a stored gold of 5s and history from attempts 1..=3.
+    /// Attempts 1 and 2 happened before the session, attempt 3 during it.
+    fn session_timer() -> Timer {
+        let mut run = run_with(TimingMethod::RealTime, &[("S0", None, Some(5.0))]);
+        let history = run.segment_mut(0).segment_history_mut();
+        history.insert(1, real_time(5.0));
+        history.insert(2, real_time(7.0));
+        history.insert(3, real_time(9.0));
+        // History of attempts the run doesn't know about gets moved aside
+        for index in 1..=3 {
+            run.add_attempt_with_index(Time::default(), index, None, None, None);
+        }
+        Timer::new(run).expect("timer")
+    }
+
+    fn config_with(source: GoldSource, session_start_attempt: i32) -> Config {
+        let mut config = Config::default();
+        config.general.gold_source = source;
+        let mut run = Run::new();
+        run.push_segment(Segment::new("S0"));
+        for _ in 0..session_start_attempt {
+            run.add_attempt_with_index(
+                Time::default(),
+                run.attempt_history().len() as i32 + 1,
+                None,
+                None,
+                None,
+            );
+        }
+        config.begin_session(&run);
+        config
+    }
+
+    #[test]
+    fn stored_best_uses_best_segment_time() {
+        let timer = session_timer();
+        let config = config_with(GoldSource::StoredBest, 2);
+        let gold = gold_segment_duration(&timer.run().segments()[0], &timer, &config);
+        assert_eq!(gold, Duration::seconds(5));
+    }
+
+    #[test]
+    fn session_best_only_considers_session_attempts() {
+        let timer = session_timer();
+        let config = config_with(GoldSource::SessionBest, 2);
+        assert_eq!(config.session_start_attempt(), 2);
+        let gold = gold_segment_duration(&timer.run().segments()[0], &timer, &config);
+        assert_eq!(gold, Duration::seconds(9));
+    }
+
+    #[test]
+    fn session_best_is_zero_without_session_attempts() {
+        let timer = session_timer();
+        let config = config_with(GoldSource::SessionBest, 3);
+        let gold = gold_segment_duration(&timer.run().segments()[0], &timer, &config);
+        assert_eq!(gold, Duration::ZERO);
+    }
+
+    #[test]
+    fn split_between_session_and_stored_best_classifies_by_source() {
+        let timer = session_timer();
+        let split_duration = Duration::seconds(8);
+        let comparison = Duration::seconds(10);
+        let diff = Duration::seconds(-2);
+
+        let stored = config_with(GoldSource::StoredBest, 2);
+        let (_, gold, _) = previous_split_combined_gold_for_config(&timer, 0, &stored);
+        assert_eq!(
+            classify_split_label(comparison, split_duration, diff, gold, false),
+            "greensplit"
+        );
+
+        let session = config_with(GoldSource::SessionBest, 2);
+        let (_, gold, _) = previous_split_combined_gold_for_config(&timer, 0, &session);
+        assert_eq!(
+            classify_split_label(comparison, split_duration, diff, gold, false),
+            "goldsplit"
+        );
+    }
+}
+
+#[cfg(test)]
+mod gold_pace_tests {
+    use super::*;
+    use time::Duration;
+
+    #[test]
+    fn ahead_before_gold() {
+        assert_eq!(
+            gold_pace(Duration::seconds(30), Duration::milliseconds(17_700)),
+            Some(GoldPace::Ahead(Duration::milliseconds(12_300)))
+        );
+    }
+
+    #[test]
+    fn flips_exactly_at_gold_duration() {
+        let gold = Duration::seconds(30);
+        assert_eq!(
+            gold_pace(gold, gold - Duration::milliseconds(1)),
+            Some(GoldPace::Ahead(Duration::milliseconds(1)))
+        );
+        assert_eq!(gold_pace(gold, gold), Some(GoldPace::Over(Duration::ZERO)));
+    }
+
+    #[test]
+    fn over_after_gold() {
+        assert_eq!(
+            gold_pace(Duration::seconds(30), Duration::milliseconds(32_100)),
+            Some(GoldPace::Over(Duration::milliseconds(2_100)))
+        );
+    }
+
+    #[test]
+    fn hidden_without_gold() {
+        assert_eq!(gold_pace(Duration::ZERO, Duration::seconds(5)), None);
+    }
+
+    #[test]
+    fn row_class_flips_at_gold_duration() {
+        let gold = Duration::seconds(30);
+        assert_eq!(
+            live_gold_row_class(gold_pace(gold, gold - Duration::milliseconds(1))),
+            Some("on-gold-pace")
+        );
+        assert_eq!(
+            live_gold_row_class(gold_pace(gold, gold)),
+            Some("gold-lost")
+        );
+        assert_eq!(live_gold_row_class(gold_pace(Duration::ZERO, gold)), None);
+    }
+
+    #[test]
+    fn skipped_previous_splits_fold_into_the_gold() {
+        use livesplit_core::{Run, Segment, Time, TimeSpan, Timer};
+
+        let gold = Time::new().with_real_time(Some(TimeSpan::from_seconds(60.0)));
+        let mut run = Run::new();
+        for name in ["S0", "S1", "S2"] {
+            let mut segment = Segment::new(name);
+            segment.set_best_segment_time(gold);
+            run.push_segment(segment);
+        }
+        let mut timer = Timer::new(run).expect("timer");
+        let config = Config::default();
+
+        assert_eq!(current_gold_pace(&timer, &config), None);
+
+        timer.start();
+        timer.skip_split();
+        // Elapsed covers S0 and S1, so both golds count: well over a single gold left
+        match current_gold_pace(&timer, &config) {
+            Some(GoldPace::Ahead(left)) => assert!(left > Duration::seconds(60)),
+            other => panic!("Expected to be ahead of the combined gold, got {other:?}"),
+        }
+        assert_eq!(
+            live_gold_row_class(current_gold_pace(&timer, &config)),
+            Some("on-gold-pace")
+        );
+    }
+
+    #[test]
+    fn segments_without_a_gold_have_nothing_to_chase() {
+        use livesplit_core::{Run, Segment, Time, TimeSpan, Timer};
+
+        let mut run = Run::new();
+        let mut first = Segment::new("S0");
+        first.set_best_segment_time(Time::new().with_real_time(Some(TimeSpan::from_seconds(60.0))));
+        run.push_segment(first);
+        run.push_segment(Segment::new("S1"));
+        let mut timer = Timer::new(run).expect("timer");
+        let config = Config::default();
+
+        assert!(current_segment_has_gold(&timer, &config));
+        timer.start();
+        assert!(current_segment_has_gold(&timer, &config));
+        timer.split();
+        assert!(!current_segment_has_gold(&timer, &config));
+        assert_eq!(current_gold_pace(&timer, &config), None);
+        timer.split();
+        assert!(current_segment_has_gold(&timer, &config));
+    }
+
+    #[test]
+    fn diff_is_negative_while_achievable() {
+        assert_eq!(
+            GoldPace::Ahead(Duration::seconds(2)).diff(),
+            Duration::seconds(-2)
+        );
+        assert_eq!(
+            GoldPace::Over(Duration::seconds(2)).diff(),
+            Duration::seconds(2)
+        );
+    }
+}
+
+#[cfg(test)]
+mod time_save_tests {
+    use super::*;
+    use crate::formatters::Rounding;
+    use crate::test_support::{make_timer, real_time};
+    use livesplit_core::{Run, Segment, TimingMethod};
+    use time::Duration;
+
+    /// Three segments with PB splits at 10/25/none and golds of 8/12/none.
+    /// The last one has no PB either, as the timer would take the gold from it.
+    fn pb_timer() -> Timer {
+        make_timer(
+            TimingMethod::RealTime,
+            &[
+                ("A", Some(10.0), Some(8.0)),
+                ("B", Some(25.0), Some(12.0)),
+                ("C", None, None),
+            ],
+        )
+    }
+
+    #[test]
+    fn upcoming_rows_show_comparison_minus_gold() {
+        let timer = pb_timer();
+        let config = Config::default();
+        assert_eq!(
+            segment_time_save(&timer, &config, None, 0),
+            Some(TimeSave::Possible(Duration::seconds(2)))
+        );
+        assert_eq!(
+            segment_time_save(&timer, &config, None, 1),
+            Some(TimeSave::Possible(Duration::seconds(3)))
+        );
+    }
+
+    #[test]
+    fn missing_gold_has_no_time_save() {
+        let timer = pb_timer();
+        let config = Config::default();
+        assert_eq!(segment_time_save(&timer, &config, None, 2), None);
+    }
+
+    #[test]
+    fn possible_save_is_capped_at_zero() {
+        let mut run = Run::new();
+        let mut segment = Segment::new("A");
+        segment.set_personal_best_split_time(real_time(10.0));
+        // A gold slower than the comparison, e.g. with session golds
+        segment.set_best_segment_time(real_time(11.0));
+        run.push_segment(segment);
+        let timer = Timer::new(run).expect("timer");
+        assert_eq!(
+            segment_time_save(&timer, &Config::default(), None, 0),
+            Some(TimeSave::Possible(Duration::ZERO))
+        );
+    }
+
+    #[test]
+    fn current_row_has_no_time_save() {
+        let mut timer = pb_timer();
+        let config = Config::default();
+        timer.start();
+        assert_eq!(segment_time_save(&timer, &config, Some(0), 0), None);
+        // Rows after the current one still show what could be saved
+        assert_eq!(
+            segment_time_save(&timer, &config, Some(0), 1),
+            Some(TimeSave::Possible(Duration::seconds(3)))
+        );
+    }
+
+    #[test]
+    fn completed_rows_show_time_against_comparison() {
+        let mut timer = pb_timer();
+        let config = Config::default();
+        timer.start();
+        timer.split();
+        // The split was a few moments in, way under the 10s comparison
+        match segment_time_save(&timer, &config, Some(1), 0) {
+            Some(TimeSave::Actual(diff)) => assert!(diff < Duration::seconds(-9)),
+            other => panic!("Expected an actual time save, got {other:?}"),
+        }
+
+        timer.skip_split();
+        assert_eq!(segment_time_save(&timer, &config, Some(2), 1), None);
+    }
+
+    #[test]
+    fn formats_like_the_deltas() {
+        let mut config = Config::default();
+        let format = |save, config: &Config| format_time_save(save, config);
+        let possible = TimeSave::Possible(Duration::milliseconds(1_200));
+        let saved = TimeSave::Actual(Duration::milliseconds(-2_149));
+        let lost = TimeSave::Actual(Duration::milliseconds(2_100));
+        assert_eq!(format(possible, &config), "1.20");
+        assert_eq!(format(saved, &config), "-2.14");
+        assert_eq!(format(lost, &config), "+2.10");
+        assert_eq!(format(TimeSave::Actual(Duration::ZERO), &config), "~0.00");
+
+        config.format.delta_rounding = Rounding::Nearest;
+        assert_eq!(format(saved, &config), "-2.15");
+        config.format.invert_deltas.splits = true;
+        assert_eq!(format(saved, &config), "+2.15");
+        assert_eq!(format(lost, &config), "-2.10");
+        // A possible save is a time, not a delta
+        assert_eq!(format(possible, &config), "1.20");
+    }
+}
+
+#[cfg(test)]
+mod segment_elapsed_tests {
+    use super::*;
+    use livesplit_core::{Run, Segment, TimeSpan};
+    use time::Duration;
+
+    #[test]
+    fn measures_from_the_last_split_while_running() {
+        let mut run = Run::new();
+        run.push_segment(Segment::new("S0"));
+        run.push_segment(Segment::new("S1"));
+        // Start ten seconds in so the first segment is clearly longer
+        run.set_offset(TimeSpan::from_seconds(10.0));
+        let mut timer = Timer::new(run).expect("timer");
+
+        assert_eq!(current_segment_elapsed(&timer, Duration::ZERO), None);
+
+        timer.start();
+        let first = current_segment_elapsed(&timer, Duration::ZERO).expect("running");
+        assert!(first >= Duration::seconds(10));
+
+        timer.split();
+        let second = current_segment_elapsed(&timer, Duration::ZERO).expect("running");
+        assert!(second < Duration::seconds(10));
+
+        timer.reset(false);
+        assert_eq!(current_segment_elapsed(&timer, Duration::ZERO), None);
+    }
+}
+
+#[cfg(test)]
+mod pb_segment_tests {
+    use super::*;
+    use livesplit_core::{Segment, Time, TimeSpan, TimingMethod};
+    use time::Duration;
+
+    fn pb(real: Option<f64>, game: Option<f64>) -> Time {
+        Time::new()
+            .with_real_time(real.map(TimeSpan::from_seconds))
+            .with_game_time(game.map(TimeSpan::from_seconds))
+    }
+
+    fn timer(pbs: &[Time]) -> Timer {
+        let mut run = Run::new();
+        for (i, time) in pbs.iter().enumerate() {
+            let mut segment = Segment::new(format!("S{i}"));
+            segment.set_personal_best_split_time(*time);
+            run.push_segment(segment);
+        }
+        Timer::new(run).expect("timer")
+    }
+
+    #[test]
+    fn pb_segment_is_the_difference_of_pb_splits() {
+        let timer = timer(&[
+            pb(Some(10.0), None),
+            pb(Some(25.5), None),
+            pb(Some(40.0), None),
+        ]);
+        assert_eq!(pb_segment_duration(&timer, 0), Some(Duration::seconds(10)));
+        assert_eq!(
+            pb_segment_duration(&timer, 1),
+            Some(Duration::milliseconds(15_500))
+        );
+        assert_eq!(
+            pb_segment_duration(&timer, 2),
+            Some(Duration::milliseconds(14_500))
+        );
+        assert_eq!(pb_segment_duration(&timer, 3), None);
+    }
+
+    #[test]
+    fn pb_segment_after_a_skipped_split_covers_both() {
+        let timer = timer(&[pb(Some(10.0), None), pb(None, None), pb(Some(40.0), None)]);
+        assert_eq!(pb_segment_duration(&timer, 1), None);
+        assert_eq!(pb_segment_duration(&timer, 2), Some(Duration::seconds(30)));
+    }
+
+    #[test]
+    fn pb_segment_uses_the_current_timing_method() {
+        let mut timer = timer(&[pb(Some(10.0), Some(8.0)), pb(Some(25.0), Some(20.0))]);
+        timer.set_current_timing_method(TimingMethod::GameTime);
+        assert_eq!(pb_segment_duration(&timer, 1), Some(Duration::seconds(12)));
+    }
+
+    #[test]
+    fn attempt_segment_is_recorded_or_running() {
+        let mut run = Run::new();
+        run.push_segment(Segment::new("S0"));
+        run.push_segment(Segment::new("S1"));
+        run.push_segment(Segment::new("S2"));
+        // Start ten seconds in so the first segment is clearly measurable
+        run.set_offset(TimeSpan::from_seconds(10.0));
+        let mut timer = Timer::new(run).expect("timer");
+        assert_eq!(
+            attempt_segment_duration(&timer, 0, time::Duration::ZERO),
+            None
+        );
+
+        timer.start();
+        timer.split();
+        let first = attempt_segment_duration(&timer, 0, time::Duration::ZERO).expect("split");
+        assert!(first >= Duration::seconds(10));
+        assert!(attempt_segment_duration(&timer, 1, time::Duration::ZERO).is_some());
+        assert_eq!(
+            attempt_segment_duration(&timer, 2, time::Duration::ZERO),
+            None
+        );
+
+        timer.skip_split();
+        assert_eq!(
+            attempt_segment_duration(&timer, 1, time::Duration::ZERO),
+            None
+        );
+    }
+}
+
+#[cfg(test)]
+mod no_completed_runs_tests {
+    use super::*;
+    use crate::formatters::time::{TimeFormat, TimeFormatPreset};
+    use crate::test_support::{real_time, run_with};
+    use livesplit_core::{Time, Timer};
+
+    /// Two attempts that both reset in the second segment: golds and history
+    /// exist for the first segment, but there is no personal best.
+    fn unfinished_timer() -> Timer {
+        let mut run = run_with(
+            TimingMethod::RealTime,
+            &[("S0", None, Some(10.0)), ("S1", None, None)],
+        );
+        run.set_game_name("Game");
+        run.set_category_name("Any%");
+        let history = run.segment_mut(0).segment_history_mut();
+        history.insert(1, real_time(12.0));
+        history.insert(2, real_time(10.0));
+
+        run.add_attempt_with_index(Time::default(), 1, None, None, None);
+        run.add_attempt_with_index(Time::default(), 2, None, None, None);
+
+        Timer::new(run).expect("timer")
+    }
+
+    #[test]
+    fn pb_comparison_is_empty_without_panicking() {
+        let timer = unfinished_timer();
+        assert_eq!(timer.current_comparison(), "Personal Best");
+        for index in 0..timer.run().len() {
+            assert_eq!(segment_comparison_duration(&timer, index), None);
+        }
+        assert_eq!(segment_comparison_duration(&timer, 5), None);
+    }
+
+    #[test]
+    fn pb_values_format_as_placeholder() {
+        let timer = unfinished_timer();
+        let format = TimeFormat::from_preset(TimeFormatPreset::ShowDecimals);
+        let text = format.format_duration_opt(segment_comparison_duration(&timer, 0));
+        assert_eq!(text, "--");
+    }
+
+    #[test]
+    fn best_segments_are_still_shown() {
+        let mut timer = unfinished_timer();
+        timer.set_current_comparison("Best Segments").unwrap();
+        assert_eq!(
+            segment_comparison_duration(&timer, 0),
+            Some(time::Duration::seconds(10))
+        );
+        // Nothing is known about the second segment
+        assert_eq!(segment_comparison_duration(&timer, 1), None);
+    }
+}
+
+#[cfg(test)]
+mod signed_comparison_tests {
+    use super::*;
+    use crate::test_support::run_on;
+    use livesplit_core::comparison::ComparisonGenerator;
+    use livesplit_core::{Attempt, Segment, TimeSpan, Timer};
+
+    /// Generates 0:30, 0:20 and 0:50, the second split earlier than the
+    /// first. The timer evens out a personal best like that, but leaves
+    /// generated comparisons alone.
+    #[derive(Debug, Clone)]
+    struct Pace;
+
+    impl ComparisonGenerator for Pace {
+        fn name(&self) -> &str {
+            "Pace"
+        }
+
+        fn generate(&mut self, segments: &mut [Segment], _: &[Attempt]) {
+            for (segment, seconds) in segments.iter_mut().zip([30.0, 20.0, 50.0]) {
+                segment.comparison_mut("Pace").real_time = Some(TimeSpan::from_seconds(seconds));
+            }
+        }
+    }
+
+    fn pace_timer() -> Timer {
+        let mut run = run_on(&["S0", "S1", "S2"]);
+        run.comparison_generators_mut().push(Box::new(Pace));
+        let mut timer = Timer::new(run).expect("timer");
+        timer.set_current_comparison("Pace").unwrap();
+        timer
+    }
+
+    #[test]
+    fn non_monotonic_comparison_is_negative_when_signed() {
+        let timer = pace_timer();
+        assert_eq!(
+            signed_segment_comparison_duration(&timer, 1),
+            Some(time::Duration::seconds(-10))
+        );
+        assert_eq!(
+            segment_comparison_duration(&timer, 1),
+            Some(time::Duration::seconds(10))
+        );
+    }
+
+    #[test]
+    fn monotonic_segments_keep_their_value() {
+        let timer = pace_timer();
+        for index in [0, 2] {
+            assert_eq!(
+                signed_segment_comparison_duration(&timer, index),
+                segment_comparison_duration(&timer, index)
+            );
+        }
+        assert_eq!(
+            signed_segment_comparison_duration(&timer, 2),
+            Some(time::Duration::seconds(30))
+        );
+    }
+}
+
+#[cfg(test)]
+mod comparison_line_tests {
+    use super::*;
+    use crate::test_support::make_timer;
+
+    fn pb_timer() -> Timer {
+        make_timer(
+            TimingMethod::RealTime,
+            &[
+                ("S0", Some(30.0), None),
+                ("S1", Some(50.0), None),
+                ("S2", None, None),
+            ],
+        )
+    }
+
+    #[test]
+    fn segment_times_are_the_default() {
+        let timer = pb_timer();
+        assert_eq!(
+            comparison_line_duration(&timer, 0, false),
+            Some(time::Duration::seconds(30))
+        );
+        assert_eq!(
+            comparison_line_duration(&timer, 1, false),
+            Some(time::Duration::seconds(20))
+        );
+        assert_eq!(comparison_line_duration(&timer, 2, false), None);
+    }
+
+    #[test]
+    fn cumulative_times_count_from_the_start() {
+        let timer = pb_timer();
+        assert_eq!(
+            comparison_line_duration(&timer, 0, true),
+            Some(time::Duration::seconds(30))
+        );
+        assert_eq!(
+            comparison_line_duration(&timer, 1, true),
+            Some(time::Duration::seconds(50))
+        );
+        assert_eq!(comparison_line_duration(&timer, 2, true), None);
+        assert_eq!(comparison_line_duration(&timer, 3, true), None);
+    }
+}
+
+#[cfg(test)]
+mod delta_inversion_tests {
+    use super::*;
+
+    fn ms(ms: i64) -> time::Duration {
+        time::Duration::milliseconds(ms)
+    }
+
+    #[test]
+    fn deltas_keep_their_sign_by_default() {
+        let config = Config::default();
+        for widget in [DeltaWidget::Splits, DeltaWidget::Info] {
+            assert_eq!(format_delta(ms(-1_500), &config, widget), "-1.50");
+            assert_eq!(format_delta(ms(2_250), &config, widget), "+2.25");
+        }
+    }
+
+    #[test]
+    fn inverted_deltas_read_as_time_left() {
+        let mut config = Config::default();
+        config.format.invert_deltas.info = true;
+        // 1.5s ahead is 1.5s of buffer left
+        assert_eq!(
+            format_delta(ms(-1_500), &config, DeltaWidget::Info),
+            "+1.50"
+        );
+        assert_eq!(format_delta(ms(2_250), &config, DeltaWidget::Info), "-2.25");
+        assert_eq!(format_delta(ms(0), &config, DeltaWidget::Info), "~0.00");
+        // Only in the widgets it's enabled for
+        assert_eq!(
+            format_delta(ms(-1_500), &config, DeltaWidget::Splits),
+            "-1.50"
+        );
+    }
+
+    #[test]
+    fn inversion_leaves_the_delta_colors_alone() {
+        let mut config = Config::default();
+        config.format.invert_deltas.splits = true;
+        let (comparison, split, gold) = (ms(10_000), ms(9_000), ms(8_000));
+        let diff = split - comparison;
+        // Ahead by a second: shown as a second left, still colored ahead
+        assert_eq!(format_delta(diff, &config, DeltaWidget::Splits), "+1.00");
+        assert_eq!(
+            classify_split_label(comparison, split, diff, gold, false),
+            "greensplit"
+        );
+    }
+}
+
+#[cfg(test)]
+mod delta_rounding_tests {
+    use super::*;
+    use crate::formatters::Rounding;
+    use livesplit_core::{Time, TimeSpan, TimingMethod};
+
+    #[test]
+    fn deltas_truncate_by_default() {
+        let config = Config::default();
+        assert_eq!(
+            format_signed(time::Duration::milliseconds(2_149), &config),
+            "+2.14"
+        );
+        assert_eq!(
+            format_signed(time::Duration::milliseconds(-2_149), &config),
+            "-2.14"
+        );
+    }
+
+    #[test]
+    fn rounded_deltas_leave_split_times_truncated() {
+        let mut config = Config::default();
+        config.format.delta_rounding = Rounding::Nearest;
+        assert_eq!(
+            format_signed(time::Duration::milliseconds(2_149), &config),
+            "+2.15"
+        );
+        assert_eq!(
+            format_signed(time::Duration::milliseconds(-2_149), &config),
+            "-2.15"
+        );
+
+        let split = Time::new().with_real_time(Some(TimeSpan::from_milliseconds(2_149.0)));
+        assert_eq!(
+            config
+                .format
+                .split
+                .format_split_time(&split, TimingMethod::RealTime),
+            "2.14"
+        );
+        assert_eq!(
+            config
+                .format
+                .segment
+                .format_duration(&time::Duration::milliseconds(2_149)),
+            "2.14"
+        );
+    }
+
+    #[test]
+    fn deltas_only_follow_the_delta_rounding() {
+        let mut config = Config::default();
+        config.format.split.rounding = Rounding::Nearest;
+        assert_eq!(
+            format_signed(time::Duration::milliseconds(2_149), &config),
+            "+2.14"
+        );
+        config.format.split.rounding = Rounding::Truncate;
+        config.format.delta_rounding = Rounding::Nearest;
+        assert_eq!(
+            format_signed(time::Duration::milliseconds(2_149), &config),
+            "+2.15"
+        );
+    }
+
+    #[test]
+    fn deltas_come_from_the_times_not_the_rounded_comparison() {
+        let mut run = livesplit_core::Run::new();
+        let mut segment = livesplit_core::Segment::new("S0");
+        segment.set_personal_best_split_time(
+            Time::new().with_game_time(Some(TimeSpan::from_seconds(83.5))),
+        );
+        run.push_segment(segment);
+        let mut timer = Timer::new(run).expect("timer");
+        timer.set_current_timing_method(TimingMethod::GameTime);
+        timer.start();
+        timer.initialize_game_time();
+        timer.pause_game_time();
+        timer.set_game_time(TimeSpan::from_seconds(83.75));
+        timer.split();
+
+        let mut config = Config::default();
+        // Whole seconds, where truncating and rounding disagree
+        config.format.comparison.show_decimals = false;
+        let comparison = |config: &Config| {
+            let time = segment_comparison_time(&timer.run().segments()[0], &timer);
+            config.format.comparison.format_duration(&time)
+        };
+        assert_eq!(comparison(&config), "1:23");
+        config.format.comparison.rounding = Rounding::Nearest;
+        assert_eq!(comparison(&config), "1:24");
+        let split = config.format.comparison.format_split_time(
+            &timer.run().segments()[0].split_time(),
+            TimingMethod::GameTime,
+        );
+        assert_eq!(split, "1:24");
+
+        // Both show 1:24, the delta is still the quarter second between them
+        let delta = split_delta(&timer, 0).expect("split");
+        assert_eq!(delta, time::Duration::milliseconds(250));
+        assert_eq!(format_delta(delta, &config, DeltaWidget::Splits), "+0.25");
+    }
+}
+
+#[cfg(test)]
+mod target_final_time_tests {
+    use super::*;
+    use crate::test_support::make_timer;
+    use livesplit_core::{Run, Segment, Timer};
+
+    fn timer_with_final(pb_final: Option<f64>) -> Timer {
+        make_timer(
+            TimingMethod::RealTime,
+            &[("S0", Some(10.0), Some(9.0)), ("S1", pb_final, Some(15.0))],
+        )
+    }
+
+    #[test]
+    fn uses_last_split_of_current_comparison() {
+        let mut timer = timer_with_final(Some(25.0));
+        assert_eq!(target_final_time(&timer), Some(time::Duration::seconds(25)));
+
+        timer.set_current_comparison("Best Segments").unwrap();
+        assert_eq!(target_final_time(&timer), Some(time::Duration::seconds(24)));
+    }
+
+    #[test]
+    fn blank_without_final_time() {
+        let timer = timer_with_final(None);
+        assert_eq!(target_final_time(&timer), None);
+
+        let mut run = Run::new();
+        run.push_segment(Segment::new("Only"));
+        let timer = Timer::new(run).expect("timer");
+        assert_eq!(target_final_time(&timer), None);
+    }
+}
+
+#[cfg(test)]
+mod comparison_summary_tests {
+    use super::*;
+    use crate::test_support::{game_time, run_with, split_at};
+    use livesplit_core::{Timer, TimingMethod};
+
+    /// PB 10/25, golds 9/15 and a custom "Race" comparison without a final
+    /// time, in game time.
+    fn race_timer() -> Timer {
+        let mut run = run_with(
+            TimingMethod::GameTime,
+            &[
+                ("S0", Some(10.0), Some(9.0)),
+                ("S1", Some(25.0), Some(15.0)),
+            ],
+        );
+        run.add_custom_comparison("Race").unwrap();
+        *run.segment_mut(0).comparison_mut("Race") = game_time(12.0);
+        let mut timer = Timer::new(run).expect("timer");
+        timer.set_current_timing_method(TimingMethod::GameTime);
+        timer
+    }
+
+    #[test]
+    fn final_times_before_the_attempt() {
+        let timer = race_timer();
+        assert_eq!(
+            comparison_summary(&timer, "Personal Best"),
+            ComparisonSummary {
+                final_time: Some(time::Duration::seconds(25)),
+                delta_at_last_split: None,
+            }
+        );
+        assert_eq!(
+            comparison_summary(&timer, "Best Segments").final_time,
+            Some(time::Duration::seconds(24))
+        );
+        assert_eq!(comparison_summary(&timer, "Race").final_time, None);
+    }
+
+    #[test]
+    fn delta_at_the_last_completed_split() {
+        let mut timer = race_timer();
+        timer.start();
+        timer.initialize_game_time();
+        timer.pause_game_time();
+        split_at(&mut timer, 11.0);
+
+        assert_eq!(
+            comparison_summary(&timer, "Personal Best").delta_at_last_split,
+            Some(time::Duration::seconds(1))
+        );
+        assert_eq!(
+            comparison_summary(&timer, "Best Segments").delta_at_last_split,
+            Some(time::Duration::seconds(2))
+        );
+        assert_eq!(
+            comparison_summary(&timer, "Race"),
+            ComparisonSummary {
+                final_time: None,
+                delta_at_last_split: Some(time::Duration::seconds(-1)),
+            }
+        );
+        // The switch path is left alone
+        assert_eq!(timer.current_comparison(), "Personal Best");
+    }
+
+    #[test]
+    fn comparisons_without_the_split_fall_back_to_an_earlier_one() {
+        let mut timer = race_timer();
+        timer.start();
+        timer.initialize_game_time();
+        timer.pause_game_time();
+        split_at(&mut timer, 11.0);
+        split_at(&mut timer, 26.0);
+
+        assert_eq!(
+            comparison_summary(&timer, "Personal Best").delta_at_last_split,
+            Some(time::Duration::seconds(1))
+        );
+        // Race has no time for S1, so S0 is used
+        assert_eq!(
+            comparison_summary(&timer, "Race").delta_at_last_split,
+            Some(time::Duration::seconds(-1))
+        );
+    }
+}
+
+#[cfg(test)]
+mod pb_switch_tests {
+    use super::*;
+    use crate::test_support::{game_time, make_timer, split_at};
+    use livesplit_core::{Time, TimeSpan, TimingMethod};
+
+    /// PB splits at 10/25 with golds of half of them, in game time.
+    fn game_time_timer() -> Timer {
+        make_timer(
+            TimingMethod::GameTime,
+            &[
+                ("S0", Some(10.0), Some(5.0)),
+                ("S1", Some(25.0), Some(12.5)),
+            ],
+        )
+    }
+
+    fn run_attempt(timer: &mut Timer, splits: &[f64]) {
+        timer.start();
+        timer.initialize_game_time();
+        timer.pause_game_time();
+        for split in splits {
+            split_at(timer, *split);
+        }
+    }
+
+    /// Runs an attempt and resets it, with the watch seeing a frame before
+    /// and after like the tick does. Whether it spotted a new PB.
+    fn watched_attempt(watch: &mut NewPbWatch, timer: &mut Timer, splits: &[f64]) -> bool {
+        assert!(!watch.observe(timer));
+        run_attempt(timer, splits);
+        assert!(!watch.observe(timer));
+        timer.reset(true);
+        watch.observe(timer)
+    }
+
+    #[test]
+    fn a_reset_saving_a_new_pb_is_spotted_once() {
+        let mut watch = NewPbWatch::default();
+        let mut timer = game_time_timer();
+        assert!(watched_attempt(&mut watch, &mut timer, &[9.0, 24.0]));
+        assert!(!watch.observe(&timer));
+        assert_eq!(
+            timer.run().segments()[1]
+                .personal_best_split_time()
+                .game_time,
+            Some(TimeSpan::from_seconds(24.0))
+        );
+    }
+
+    #[test]
+    fn only_genuine_pbs_are_spotted() {
+        let mut watch = NewPbWatch::default();
+        let mut timer = game_time_timer();
+        // Slower than the PB
+        assert!(!watched_attempt(&mut watch, &mut timer, &[11.0, 26.0]));
+        // Ahead, but reset before the last split
+        assert!(!watched_attempt(&mut watch, &mut timer, &[5.0]));
+        // Discarded without updating the splits
+        run_attempt(&mut timer, &[9.0, 24.0]);
+        timer.reset(false);
+        assert!(!watch.observe(&timer));
+    }
+
+    #[test]
+    fn loading_other_splits_is_not_a_new_pb() {
+        let mut watch = NewPbWatch::default();
+        let mut timer = game_time_timer();
+        assert!(!watch.observe(&timer));
+        let mut run = timer.run().clone();
+        run.add_attempt(Time::default(), None, None, None);
+        run.segment_mut(1)
+            .set_personal_best_split_time(game_time(20.0));
+        timer.set_run(run).unwrap();
+        assert!(!watch.observe(&timer));
+    }
+}
+
+#[cfg(test)]
+mod remaining_best_tests {
+    use super::*;
+
+    fn secs(seconds: i64) -> time::Duration {
+        time::Duration::seconds(seconds)
+    }
+
+    #[test]
+    fn suffix_sums_count_from_each_segment() {
+        let remaining = RemainingBest::from_segments(
+            &[Some(secs(10)), Some(secs(20)), Some(secs(30))],
+            &[None, None, None],
+            RemainingWithoutBest::Comparison,
+        );
+        assert_eq!(remaining.suffix, [secs(60), secs(50), secs(30), secs(0)]);
+        assert!(!remaining.is_estimated(0));
+        assert!(!remaining.is_incomplete(0));
+    }
+
+    #[test]
+    fn missing_bests_take_the_comparison_or_hide() {
+        let best = [Some(secs(10)), None, Some(secs(30))];
+        let fallback = [Some(secs(12)), Some(secs(25)), Some(secs(33))];
+        let remaining =
+            RemainingBest::from_segments(&best, &fallback, RemainingWithoutBest::Comparison);
+        assert_eq!(remaining.remaining(0, 0, time::Duration::ZERO), secs(65));
+        assert!(remaining.is_estimated(0));
+        assert!(remaining.is_estimated(1));
+        // Past the segment without a best, the sum is exact again
+        assert!(!remaining.is_estimated(2));
+
+        let hidden = RemainingBest::from_segments(&best, &fallback, RemainingWithoutBest::Hide);
+        assert!(hidden.is_incomplete(1));
+        assert!(!hidden.is_estimated(1));
+        assert!(!hidden.is_incomplete(2));
+    }
+
+    #[test]
+    fn countdown_clamps_at_the_segments_after_the_current_one() {
+        let remaining = RemainingBest::from_segments(
+            &[Some(secs(10)), Some(secs(20)), Some(secs(30))],
+            &[None, None, None],
+            RemainingWithoutBest::Comparison,
+        );
+        let at = |start, current, elapsed| {
+            remaining.remaining(start, current, time::Duration::seconds(elapsed))
+        };
+        assert_eq!(at(1, 1, 0), secs(50));
+        assert_eq!(at(1, 1, 15), secs(35));
+        // Over the gold, the rest of the run is still left
+        assert_eq!(at(1, 1, 25), secs(30));
+        assert_eq!(at(2, 2, 40), time::Duration::ZERO);
+        // Skipped segments before the current one are in the elapsed time
+        assert_eq!(at(0, 1, 25), secs(35));
+        assert_eq!(at(3, 3, 5), time::Duration::ZERO);
+    }
+
+    #[test]
+    fn elapsed_time_starts_after_the_last_real_split() {
+        use livesplit_core::{Segment, TimeSpan, TimingMethod};
+
+        let mut run = Run::new();
+        for name in ["S0", "S1", "S2"] {
+            run.push_segment(Segment::new(name));
+        }
+        let mut timer = Timer::new(run).expect("timer");
+        timer.set_current_timing_method(TimingMethod::GameTime);
+        timer.start();
+        timer.initialize_game_time();
+        timer.pause_game_time();
+        assert_eq!(segments_since_last_split(&timer, 0), 0);
+        timer.set_game_time(TimeSpan::from_seconds(5.0));
+        timer.split();
+        timer.skip_split();
+        assert_eq!(segments_since_last_split(&timer, 2), 1);
+    }
+}
+
+#[cfg(test)]
+mod padded_golds_tests {
+    use super::*;
+    use crate::test_support::run_with;
+    use livesplit_core::run::parser::composite;
+    use livesplit_core::run::saver::livesplit::save_run;
+
+    fn secs(seconds: f64) -> Option<time::Duration> {
+        Some(time::Duration::seconds_f64(seconds))
+    }
+
+    fn padding(percent: f64, flat_seconds: f64) -> PaddedGoldComparison {
+        PaddedGoldComparison {
+            enabled: true,
+            percent,
+            flat_seconds,
+            persist: false,
+        }
+    }
+
+    /// Golds of 10s and 20s around a segment without one, PB of 12/40/65.
+    fn run() -> Run {
+        run_with(
+            TimingMethod::RealTime,
+            &[
+                ("S0", Some(12.0), Some(10.0)),
+                ("S1", Some(40.0), None),
+                ("S2", Some(65.0), Some(20.0)),
+            ],
+        )
+    }
+
+    #[test]
+    fn golds_are_padded_and_summed() {
+        let best = [secs(10.0), secs(20.0)];
+        assert_eq!(
+            padded_gold_times(&best, &[None, None], &padding(5.0, 0.0)),
+            vec![secs(10.5), secs(31.5)]
+        );
+        assert_eq!(
+            padded_gold_times(&best, &[None, None], &padding(10.0, 1.0)),
+            vec![secs(12.0), secs(35.0)]
+        );
+        assert_eq!(
+            padded_gold_times(&best, &[], &padding(0.0, 0.0)),
+            vec![secs(10.0), secs(30.0)]
+        );
+    }
+
+    #[test]
+    fn segments_without_a_gold_use_the_fallback() {
+        let best = [secs(10.0), None, secs(20.0), None, secs(5.0)];
+        let fallback = [secs(99.0), secs(28.0), None, None, None];
+        assert_eq!(
+            padded_gold_times(&best, &fallback, &padding(5.0, 0.0)),
+            // Nothing to go on for S3, so nothing from there on
+            vec![secs(10.5), secs(38.5), secs(59.5), None, None]
+        );
+    }
+
+    #[test]
+    fn names_follow_the_padding() {
+        assert_eq!(padded_gold_name(&padding(5.0, 0.0)), "Golds +5%");
+        assert_eq!(padded_gold_name(&padding(2.5, 1.0)), "Golds +2.5% +1s");
+        assert_eq!(padded_gold_name(&padding(0.0, 0.75)), "Golds +0.75s");
+        assert_eq!(padded_gold_name(&padding(0.0, 0.0)), "Golds +0s");
+        assert!(padded_gold_name(&padding(-3.0, -1.0)).starts_with(PADDED_GOLDS_PREFIX));
+        assert_eq!(
+            padded_gold_name(&padding(f64::INFINITY, f64::NAN)),
+            "Golds +1000%"
+        );
+    }
+
+    #[test]
+    fn out_of_range_padding_is_clamped() {
+        let best = [secs(10.0), secs(20.0)];
+        assert_eq!(
+            padded_gold_times(&best, &[], &padding(f64::INFINITY, f64::INFINITY)),
+            vec![secs(3_710.0), secs(7_530.0)]
+        );
+        assert_eq!(
+            padded_gold_times(&best, &[], &padding(1e300, f64::NAN)),
+            vec![secs(110.0), secs(330.0)]
+        );
+        assert_eq!(
+            padded_gold_times(&best, &[], &padding(f64::NEG_INFINITY, -1e300)),
+            vec![secs(10.0), secs(30.0)]
+        );
+
+        // A sum past what a duration holds ends the comparison there
+        let huge = [secs(4e18), secs(4e18), secs(4e18), secs(1.0)];
+        let times = padded_gold_times(&huge, &[], &padding(0.0, 0.0));
+        assert!(times[1].is_some());
+        assert_eq!(times[2..], [None, None]);
+    }
+
+    #[test]
+    fn the_timer_generates_the_comparison() {
+        let mut run = run();
+        ensure_padded_golds(&mut run, &padding(10.0, 0.0));
+        // Replaced, not added twice
+        ensure_padded_golds(&mut run, &padding(5.0, 0.0));
+        let timer = Timer::new(run).unwrap();
+        let names: Vec<_> = timer.run().comparisons().collect();
+        assert!(names.contains(&"Golds +5%") && !names.contains(&"Golds +10%"));
+        assert!(cycle_comparisons(timer.run(), 3).contains(&"Golds +5%".to_owned()));
+
+        // The timer takes S1's missing gold from its PB segment of 28s
+        let splits: Vec<_> = timer
+            .run()
+            .segments()
+            .iter()
+            .map(|segment| {
+                segment
+                    .comparison_timing_method("Golds +5%", TimingMethod::RealTime)
+                    .map(|time| time.to_duration())
+            })
+            .collect();
+        assert_eq!(splits, vec![secs(10.5), secs(39.9), secs(60.9)]);
+
+        let mut run = timer.into_run(true);
+        ensure_padded_golds(&mut run, &PaddedGoldComparison::default());
+        assert!(
+            !run.comparisons()
+                .any(|name| name.starts_with(PADDED_GOLDS_PREFIX))
+        );
+    }
+
+    fn saved_comparisons(run: &Run) -> Vec<String> {
+        let mut text = String::new();
+        save_run(run, &mut text).unwrap();
+        let run = composite::parse(text.as_bytes(), None).unwrap().run;
+        run.custom_comparisons().to_vec()
+    }
+
+    #[test]
+    fn the_comparison_is_only_saved_when_persisted() {
+        let mut run = run();
+        ensure_padded_golds(&mut run, &padding(5.0, 0.0));
+        let run = Timer::new(run).unwrap().into_run(true);
+        assert!(!saved_comparisons(&run).contains(&"Golds +5%".to_owned()));
+
+        let mut persisted = run.clone();
+        persist_padded_golds(&mut persisted);
+        assert!(saved_comparisons(&persisted).contains(&"Golds +5%".to_owned()));
+
+        // Loaded again, the generator takes its place instead of doubling it
+        let mut text = String::new();
+        save_run(&persisted, &mut text).unwrap();
+        let mut loaded = composite::parse(text.as_bytes(), None).unwrap().run;
+        ensure_padded_golds(&mut loaded, &padding(5.0, 0.0));
+        let timer = Timer::new(loaded).unwrap();
+        let count = timer
+            .run()
+            .comparisons()
+            .filter(|name| *name == "Golds +5%")
+            .count();
+        assert_eq!(count, 1);
+
+        // One persisted with other padding goes too
+        let mut loaded = composite::parse(text.as_bytes(), None).unwrap().run;
+        ensure_padded_golds(&mut loaded, &padding(10.0, 0.0));
+        let names: Vec<_> = Timer::new(loaded)
+            .unwrap()
+            .run()
+            .comparisons()
+            .map(str::to_owned)
+            .collect();
+        assert!(names.contains(&"Golds +10%".to_owned()));
+        assert!(!names.contains(&"Golds +5%".to_owned()));
+        let mut loaded = composite::parse(text.as_bytes(), None).unwrap().run;
+        ensure_padded_golds(&mut loaded, &PaddedGoldComparison::default());
+        assert!(
+            !loaded
+                .custom_comparisons()
+                .contains(&"Golds +5%".to_owned())
+        );
+    }
+}
+
+#[cfg(test)]
+mod comparison_cycle_tests {
+    use super::*;
+    use livesplit_core::{Segment, Time, TimeSpan};
+
+    fn run_with_attempts(attempts: i32) -> Run {
+        let mut run = Run::new();
+        run.push_segment(Segment::new("S0"));
+        for index in 1..=attempts {
+            run.add_attempt_with_index(Time::default(), index, None, None, None);
+        }
+        run
+    }
+
+    fn generated_names(run: &Run) -> Vec<String> {
+        run.comparison_generators()
+            .iter()
+            .map(|generator| generator.name().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn few_attempts_keep_only_pb_and_best_segments() {
+        let run = run_with_attempts(0);
+        assert_eq!(
+            cycle_comparisons(&run, 3),
+            vec![personal_best::NAME, best_segments::NAME]
+        );
+        // The latest run joins them from the first attempt on
+        let mut run = run_with_attempts(2);
+        ensure_latest_run(&mut run);
+        assert_eq!(
+            cycle_comparisons(&run, 3),
+            vec![personal_best::NAME, best_segments::NAME, latest_run::NAME]
+        );
+    }
+
+    #[test]
+    fn latest_run_is_added_once() {
+        let mut run = run_with_attempts(0);
+        ensure_latest_run(&mut run);
+        ensure_latest_run(&mut run);
+        let names = generated_names(&run);
+        assert_eq!(
+            names
+                .iter()
+                .filter(|name| *name == latest_run::NAME)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn latest_run_follows_the_last_completed_attempt() {
+        let mut run = Run::new();
+        for name in ["S0", "S1"] {
+            run.push_segment(Segment::new(name));
+        }
+        ensure_latest_run(&mut run);
+        let mut timer = Timer::new(run).expect("timer");
+        timer.set_current_timing_method(livesplit_core::TimingMethod::GameTime);
+        assert!(
+            timer
+                .run()
+                .comparisons()
+                .any(|name| name == latest_run::NAME)
+        );
+
+        for splits in [[10.0, 25.0], [11.0, 24.0]] {
+            timer.start();
+            timer.initialize_game_time();
+            timer.pause_game_time();
+            for split in splits {
+                timer.set_game_time(TimeSpan::from_seconds(split));
+                timer.split();
+            }
+            timer.reset(true);
+
+            let latest: Vec<_> = timer
+                .run()
+                .segments()
+                .iter()
+                .map(|segment| segment.comparison(latest_run::NAME).game_time)
+                .collect();
+            assert_eq!(
+                latest,
+                splits.map(|split| Some(TimeSpan::from_seconds(split)))
+            );
+        }
+    }
+
+    #[test]
+    fn enough_attempts_keep_everything() {
+        let run = run_with_attempts(10);
+        let mut expected = vec![personal_best::NAME.to_owned()];
+        expected.extend(generated_names(&run));
+        assert_eq!(cycle_comparisons(&run, 3), expected);
+        assert!(expected.len() > 2);
+    }
+
+    #[test]
+    fn custom_comparisons_are_always_kept() {
+        let mut run = run_with_attempts(0);
+        run.add_custom_comparison("Friend's PB").unwrap();
+        assert_eq!(
+            cycle_comparisons(&run, 3),
+            vec![personal_best::NAME, "Friend's PB", best_segments::NAME]
+        );
+    }
+
+    #[test]
+    fn cycle_wraps_both_ways() {
+        let order: Vec<String> = ["A", "B", "C"].map(str::to_owned).to_vec();
+        assert_eq!(next_in_cycle(&order, "A", true), Some("B"));
+        assert_eq!(next_in_cycle(&order, "C", true), Some("A"));
+        assert_eq!(next_in_cycle(&order, "A", false), Some("C"));
+        assert_eq!(next_in_cycle(&order, "Hidden", true), Some("A"));
+        assert_eq!(next_in_cycle(&order, "Hidden", false), Some("C"));
+        assert_eq!(next_in_cycle(&[], "A", true), None);
+    }
+
+    #[test]
+    fn hotkey_cycle_visits_the_listed_order() {
+        for attempts in [0, 2, 10] {
+            let mut timer = Timer::new(run_with_attempts(attempts)).expect("timer");
+            let order = cycle_comparisons(timer.run(), 3);
+
+            let mut visited = vec![timer.current_comparison().to_owned()];
+            for _ in 1..order.len() {
+                switch_comparison(&mut timer, true, 3);
+                visited.push(timer.current_comparison().to_owned());
+            }
+            assert_eq!(visited, order, "{attempts} attempts");
+
+            // One more wraps around, and going back retraces the order
+            switch_comparison(&mut timer, true, 3);
+            assert_eq!(timer.current_comparison(), order[0]);
+            switch_comparison(&mut timer, false, 3);
+            assert_eq!(timer.current_comparison(), order[order.len() - 1]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod analytics_comparison_tests {
+    use super::*;
+    use crate::test_support::make_timer;
+    use livesplit_core::Timer;
+
+    /// PB splits at 10/25 and golds of 8/12, displaying Best Segments.
+    fn best_segments_timer() -> Timer {
+        let mut timer = make_timer(
+            TimingMethod::RealTime,
+            &[
+                ("S0", Some(10.0), Some(8.0)),
+                ("S1", Some(25.0), Some(12.0)),
+            ],
+        );
+        timer.set_current_comparison(best_segments::NAME).unwrap();
+        timer
+    }
+
+    fn config_with(analytics: Option<&str>) -> Config {
+        let mut config = Config::default();
+        config.general.analytics_comparison = analytics.map(str::to_owned);
+        config
+    }
+
+    #[test]
+    fn defaults_to_the_current_comparison() {
+        let timer = best_segments_timer();
+        assert_eq!(
+            analytics_comparison(&timer, &config_with(None)),
+            best_segments::NAME
+        );
+        // A comparison the run doesn't have falls back as well
+        assert_eq!(
+            analytics_comparison(&timer, &config_with(Some("Nope"))),
+            best_segments::NAME
+        );
+    }
+
+    #[test]
+    fn possible_time_save_follows_the_analytics_comparison() {
+        let timer = best_segments_timer();
+        let config = config_with(Some(personal_best::NAME));
+        assert_eq!(analytics_comparison(&timer, &config), personal_best::NAME);
+
+        // Displayed comparison is Best Segments, against which nothing is left to save
+        assert_eq!(
+            live_possible_time_save(&timer, timer.current_comparison()),
+            Some(time::Duration::ZERO)
+        );
+        assert_eq!(
+            live_possible_time_save(&timer, analytics_comparison(&timer, &config)),
+            Some(time::Duration::seconds(2))
+        );
+    }
+
+    #[test]
+    fn previous_segment_values_use_the_given_comparison() {
+        let timer = best_segments_timer();
+        let config = config_with(Some(personal_best::NAME));
+        let comparison = analytics_comparison(&timer, &config);
+        assert_eq!(
+            previous_comparison_values_for(&timer, 1, comparison).0,
+            time::Duration::seconds(10)
+        );
+        assert_eq!(
+            previous_comparison_values(&timer, 1).0,
+            time::Duration::seconds(8)
+        );
+    }
+}
+
+#[cfg(test)]
+mod method_classes_tests {
+    use super::*;
+    use livesplit_core::{Run, Segment, Time, TimeSpan, Timer};
+
+    fn both(real: f64, game: f64) -> Time {
+        Time::new()
+            .with_real_time(Some(TimeSpan::from_seconds(real)))
+            .with_game_time(Some(TimeSpan::from_seconds(game)))
+    }
+
+    /// Three segments split at 8, 25 and 40s of game time, a few real
+    /// milliseconds apart. The first is a gold in game time only, the
+    /// second in real time only.
+    fn disagreeing_timer() -> Timer {
+        let mut run = Run::new();
+        for (index, (real_gold, game_gold)) in [(0.001, 10.0), (3600.0, 9.0), (0.001, 9.0)]
+            .into_iter()
+            .enumerate()
+        {
+            let pb = 10.0 * (index + 1) as f64;
+            let mut segment = Segment::new(format!("S{index}"));
+            segment.set_personal_best_split_time(both(pb, pb));
+            segment.set_best_segment_time(both(real_gold, game_gold));
+            run.push_segment(segment);
+        }
+        let mut timer = Timer::new(run).expect("timer");
+        timer.start();
+        timer.initialize_game_time();
+        timer.pause_game_time();
+        for game in [8.0, 25.0, 40.0] {
+            std::thread::sleep(std::time::Duration::from_millis(2));
+            timer.set_game_time(TimeSpan::from_seconds(game));
+            timer.split();
+        }
+        timer
+    }
+
+    #[test]
+    fn each_method_is_classified_on_its_own() {
+        let mut timer = disagreeing_timer();
+        let config = Config::default();
+        let classes: Vec<_> = (0..3)
+            .map(|index| MethodClasses::of(&timer, &config, index))
+            .collect();
+        assert_eq!(
+            classes,
+            [
+                MethodClasses {
+                    real_time: Some("greensplit"),
+                    game_time: Some("goldsplit"),
+                },
+                MethodClasses {
+                    real_time: Some("goldsplit"),
+                    game_time: Some("redsplit"),
+                },
+                MethodClasses {
+                    real_time: Some("greensplit"),
+                    game_time: Some("redsplit"),
+                },
+            ]
+        );
+        let notes: Vec<_> = classes.iter().map(|classes| classes.gold_note()).collect();
+        assert_eq!(
+            notes,
+            [Some("Gold (IGT only)"), Some("Gold (RTA only)"), None]
+        );
+
+        // The active method's class is the one the delta shows
+        for method in [TimingMethod::RealTime, TimingMethod::GameTime] {
+            timer.set_current_timing_method(method);
+            for (index, classes) in classes.iter().enumerate() {
+                assert_eq!(
+                    split_delta_class(&timer, &config, index),
+                    classes.get(method)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn monochrome_leaves_the_colors_out() {
+        let timer = disagreeing_timer();
+        let mut config = Config::default();
+        assert_eq!(color_class(&config, "goldsplit"), Some("goldsplit"));
+        assert_eq!(color_class(&config, ""), None);
+
+        config.style.monochrome = Some(true);
+        assert_eq!(color_class(&config, "goldsplit"), None);
+        for index in 0..3 {
+            let class = split_delta_class(&timer, &config, index);
+            assert!(class.is_some());
+            assert_eq!(class.and_then(|class| color_class(&config, class)), None);
+        }
+        // Only the colors go, the gold note stays
+        assert_eq!(
+            MethodClasses::of(&timer, &config, 0).gold_note(),
+            Some("Gold (IGT only)")
+        );
+        // The deltas keep their sign
+        let delta = split_delta(&timer, 2).expect("split");
+        assert!(format_delta(delta, &config, DeltaWidget::Splits).starts_with('-'));
+        assert!(format_delta(-delta, &config, DeltaWidget::Splits).starts_with('+'));
+    }
+
+    #[test]
+    fn segments_timed_with_one_method_have_no_note() {
+        let classes = MethodClasses {
+            real_time: Some("goldsplit"),
+            game_time: None,
+        };
+        assert_eq!(classes.gold_note(), None);
+        assert_eq!(MethodClasses::default().gold_note(), None);
+    }
+}
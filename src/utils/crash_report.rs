@@ -0,0 +1,396 @@
+//! Crash reports for panics.
+//!
+//! [`install_panic_hook`] runs first thing in `main`. On a panic it writes a
+//! report to `crash-reports` in the data directory, with the panic message,
+//! location and backtrace, the version, what the timer showed, the splits
+//! file and the last [`LOG_LINES`] log lines, then hands the panic on to the
+//! default hook, so it's printed and a debugger stops on it as before. The
+//! next start offers to open the report.
+//!
+//! The hook runs wherever the panic happened, possibly with the timer or
+//! the log locked, and a panic inside it aborts. So it only ever tries the
+//! locks, leaves out what it can't get, cuts every field to a size and
+//! gives up quietly when the report can't be written.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard, RwLock, TryLockError, Weak};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use livesplit_core::{Time, TimeSpan, Timer};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+use crate::config::data_dir;
+use crate::ui::timer::modal::DisplaySnapshot;
+
+/// Log lines kept for the report.
+pub const LOG_LINES: usize = 200;
+/// Longest a field of the report gets, in bytes.
+const MAX_FIELD: usize = 16 * 1024;
+const MAX_BACKTRACE: usize = 64 * 1024;
+const PENDING_FILE: &str = "pending";
+
+static LOG: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static TIMER: Mutex<Option<Weak<RwLock<Timer>>>> = Mutex::new(None);
+static SPLITS: Mutex<Option<PathBuf>> = Mutex::new(None);
+/// Set while a report is written, so a second panic doesn't write another.
+static WRITING: AtomicBool = AtomicBool::new(false);
+
+/// `crash-reports` in the data directory, see [`data_dir`].
+pub fn crash_report_dir() -> PathBuf {
+    data_dir().map_or_else(
+        || std::env::temp_dir().join("tuxsplit-crash-reports"),
+        |dir| dir.join("crash-reports"),
+    )
+}
+
+/// The timer a report describes.
+pub fn watch_timer(timer: &std::sync::Arc<RwLock<Timer>>) {
+    *lock(&TIMER) = Some(std::sync::Arc::downgrade(timer));
+}
+
+/// The splits file a report names, the one read last.
+pub fn set_splits_path(path: &Path) {
+    *lock(&SPLITS) = Some(path.to_owned());
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// The lock's value if it's free. `None` while it's held, so the hook never
+/// waits on a lock the panicking code holds.
+fn try_lock<T>(mutex: &Mutex<T>) -> Option<MutexGuard<'_, T>> {
+    match mutex.try_lock() {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
+/// Keeps the last [`LOG_LINES`] log lines in memory.
+pub struct LogRing;
+
+struct LineVisitor(String);
+
+impl Visit for LineVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, " {value:?}");
+        } else {
+            let _ = write!(self.0, " {}={value:?}", field.name());
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for LogRing {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut line = LineVisitor(format!("{} {}:", metadata.level(), metadata.target()));
+        event.record(&mut line);
+        push_log_line(line.0);
+    }
+}
+
+pub fn push_log_line(line: String) {
+    let Some(mut log) = try_lock(&LOG) else {
+        return;
+    };
+    if log.len() == LOG_LINES {
+        log.pop_front();
+    }
+    log.push_back(truncate(&line, MAX_FIELD));
+}
+
+/// `text` cut to at most `max` bytes, on a character boundary.
+pub fn truncate(text: &str, max: usize) -> String {
+    if text.len() <= max {
+        return text.to_owned();
+    }
+    let end = (0..=max)
+        .rev()
+        .find(|index| text.is_char_boundary(*index))
+        .unwrap_or_default();
+    format!("{}… ({} bytes cut)", &text[..end], text.len() - end)
+}
+
+/// The timer's times as measured, without the video offset, or why it
+/// couldn't be read.
+pub fn timer_snapshot(timer: &RwLock<Timer>) -> Result<DisplaySnapshot, &'static str> {
+    match timer.try_read() {
+        Ok(timer) => Ok(DisplaySnapshot::capture(&timer, time::Duration::ZERO)),
+        Err(TryLockError::WouldBlock) => Err("the timer was locked"),
+        Err(TryLockError::Poisoned(_)) => Err("the timer lock was poisoned"),
+    }
+}
+
+fn watched_snapshot() -> Result<DisplaySnapshot, &'static str> {
+    let timer = try_lock(&TIMER)
+        .ok_or("the timer was being replaced")?
+        .as_ref()
+        .and_then(Weak::upgrade)
+        .ok_or("no timer yet")?;
+    timer_snapshot(&timer)
+}
+
+#[derive(Debug)]
+pub struct CrashReport {
+    pub message: String,
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub backtrace: String,
+    pub created: u64,
+    pub snapshot: Result<DisplaySnapshot, &'static str>,
+    pub splits: Option<PathBuf>,
+    pub log: Vec<String>,
+}
+
+fn seconds(span: Option<TimeSpan>) -> String {
+    span.map_or_else(
+        || "-".to_owned(),
+        |span| format!("{:.3}s", span.total_seconds()),
+    )
+}
+
+fn time_text(time: Time) -> String {
+    format!(
+        "real {}, game {}",
+        seconds(time.real_time),
+        seconds(time.game_time)
+    )
+}
+
+impl CrashReport {
+    /// What's known about the panic in `info`, as of now.
+    pub fn capture(info: &PanicHookInfo<'_>) -> Self {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| (*message).to_owned())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_owned());
+        Self {
+            message,
+            location: info.location().map(ToString::to_string),
+            thread: std::thread::current().name().map(str::to_owned),
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            created: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+            snapshot: watched_snapshot(),
+            splits: try_lock(&SPLITS).and_then(|splits| splits.clone()),
+            log: try_lock(&LOG).map_or_else(
+                || vec!["(the log was locked)".to_owned()],
+                |log| log.iter().cloned().collect(),
+            ),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "TuxSplit crash report");
+        let _ = writeln!(out, "Version: {}", env!("CARGO_PKG_VERSION"));
+        let _ = writeln!(out, "Time: {} (Unix)", self.created);
+        let _ = writeln!(
+            out,
+            "Thread: {}",
+            truncate(self.thread.as_deref().unwrap_or("unnamed"), MAX_FIELD)
+        );
+        let _ = writeln!(out, "Panic: {}", truncate(&self.message, MAX_FIELD));
+        if let Some(location) = &self.location {
+            let _ = writeln!(out, "Location: {}", truncate(location, MAX_FIELD));
+        }
+
+        let _ = writeln!(out, "\n[Timer]");
+        match &self.snapshot {
+            Ok(snapshot) => {
+                let _ = writeln!(out, "Phase: {:?}", snapshot.phase);
+                let _ = writeln!(out, "Split index: {:?}", snapshot.split_index);
+                let _ = writeln!(
+                    out,
+                    "Attempt duration: {}",
+                    seconds(Some(snapshot.attempt_duration))
+                );
+                let _ = writeln!(out, "Current time: {}", time_text(snapshot.current_time));
+                let name =
+                    |name: &Option<String>| truncate(name.as_deref().unwrap_or("-"), MAX_FIELD);
+                let _ = writeln!(out, "Current split: {}", name(&snapshot.current_split_name));
+                let _ = writeln!(out, "Next split: {}", name(&snapshot.next_split_name));
+            }
+            Err(reason) => {
+                let _ = writeln!(out, "Unavailable: {reason}");
+            }
+        }
+        let splits = self.splits.as_ref().map_or_else(
+            || "none".to_owned(),
+            |path| truncate(&path.display().to_string(), MAX_FIELD),
+        );
+        let _ = writeln!(out, "Splits: {splits}");
+
+        let _ = writeln!(out, "\n[Backtrace]");
+        let _ = writeln!(out, "{}", truncate(&self.backtrace, MAX_BACKTRACE));
+
+        let _ = writeln!(out, "\n[Last log lines]");
+        let skip = self.log.len().saturating_sub(LOG_LINES);
+        for line in &self.log[skip..] {
+            let _ = writeln!(out, "{}", truncate(line, MAX_FIELD));
+        }
+        out
+    }
+}
+
+/// Writes `report` to `dir` and marks it for the next start.
+pub fn write_report(dir: &Path, report: &CrashReport) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let mut path = dir.join(format!("crash-{}.txt", report.created));
+    let mut count = 1;
+    while path.exists() {
+        count += 1;
+        path = dir.join(format!("crash-{}-{count}.txt", report.created));
+    }
+    fs::write(&path, report.to_text())?;
+    fs::write(dir.join(PENDING_FILE), path.to_string_lossy().as_bytes())?;
+    Ok(path)
+}
+
+/// The report of the last crash, if it wasn't offered yet. It's only offered
+/// once.
+pub fn take_pending_report(dir: &Path) -> Option<PathBuf> {
+    let marker = dir.join(PENDING_FILE);
+    let path = fs::read_to_string(&marker).ok()?;
+    let _ = fs::remove_file(&marker);
+    let path = PathBuf::from(path.trim());
+    path.is_file().then_some(path)
+}
+
+/// Writes a crash report on every panic, before the default hook runs.
+pub fn install_panic_hook() {
+    let default = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if !WRITING.swap(true, Ordering::SeqCst) {
+            let report = CrashReport::capture(info);
+            match write_report(&crash_report_dir(), &report) {
+                Ok(path) => eprintln!("TuxSplit crashed, a report was saved to {}", path.display()),
+                Err(err) => eprintln!("TuxSplit crashed, the report could not be saved: {err}"),
+            }
+            WRITING.store(false, Ordering::SeqCst);
+        }
+        default(info);
+    }));
+}
+
+#[cfg(test)]
+mod crash_report_tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use livesplit_core::{Run, Segment, TimerPhase};
+    use std::sync::Arc;
+
+    fn report(snapshot: Result<DisplaySnapshot, &'static str>) -> CrashReport {
+        CrashReport {
+            message: "index out of bounds".to_owned(),
+            location: Some("src/ui/timer/body.rs:120:9".to_owned()),
+            thread: Some("main".to_owned()),
+            backtrace: "0: tuxsplit::main".to_owned(),
+            created: 1_760_000_000,
+            snapshot,
+            splits: Some(PathBuf::from("/home/runner/celeste.lss")),
+            log: (0..LOG_LINES + 50)
+                .map(|index| format!("line {index}"))
+                .collect(),
+        }
+    }
+
+    fn timer() -> Arc<RwLock<Timer>> {
+        let mut run = Run::new();
+        run.push_segment(Segment::new("Prologue"));
+        run.push_segment(Segment::new("City"));
+        Timer::new(run).unwrap().into_shared()
+    }
+
+    #[test]
+    fn reports_carry_the_timer_state() {
+        let snapshot = DisplaySnapshot {
+            phase: TimerPhase::Running,
+            split_index: Some(1),
+            attempt_duration: TimeSpan::from_seconds(154.25),
+            current_time: Time::new().with_real_time(Some(TimeSpan::from_seconds(154.25))),
+            current_split_name: Some("City".to_owned()),
+            next_split_name: None,
+        };
+        let text = report(Ok(snapshot)).to_text();
+        assert!(text.contains(&format!("Version: {}", env!("CARGO_PKG_VERSION"))));
+        assert!(text.contains("Panic: index out of bounds"));
+        assert!(text.contains("Location: src/ui/timer/body.rs:120:9"));
+        assert!(text.contains("Phase: Running"));
+        assert!(text.contains("Split index: Some(1)"));
+        assert!(text.contains("Attempt duration: 154.250s"));
+        assert!(text.contains("Current time: real 154.250s, game -"));
+        assert!(text.contains("Current split: City\nNext split: -"));
+        assert!(text.contains("Splits: /home/runner/celeste.lss"));
+        assert!(text.contains("0: tuxsplit::main"));
+        // Only the last lines of the log
+        assert!(!text.contains("line 49\n"));
+        assert!(text.contains("line 50\n") && text.contains("line 249\n"));
+    }
+
+    #[test]
+    fn a_poisoned_timer_is_left_out() {
+        let shared = timer();
+        assert!(timer_snapshot(&shared).is_ok());
+        let poisoner = shared.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoner.write().unwrap();
+            panic!("poison the timer lock");
+        })
+        .join();
+        assert!(shared.is_poisoned());
+
+        let snapshot = timer_snapshot(&shared);
+        assert_eq!(snapshot.unwrap_err(), "the timer lock was poisoned");
+        let text = report(timer_snapshot(&shared)).to_text();
+        assert!(text.contains("Unavailable: the timer lock was poisoned"));
+        assert!(!text.contains("Phase:"));
+
+        let held = timer();
+        let _guard = held.write().unwrap();
+        assert_eq!(timer_snapshot(&held).unwrap_err(), "the timer was locked");
+    }
+
+    #[test]
+    fn huge_fields_are_cut() {
+        let mut report = report(Err("no timer yet"));
+        report.message = "é".repeat(MAX_FIELD);
+        let text = report.to_text();
+        assert!(text.len() < 3 * MAX_FIELD);
+        assert!(text.contains(&format!("({} bytes cut)", MAX_FIELD)));
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("aé", 2), "a… (2 bytes cut)");
+    }
+
+    #[test]
+    fn the_last_report_is_offered_once() {
+        let dir = temp_dir("crash");
+        let first = write_report(&dir, &report(Err("no timer yet"))).unwrap();
+        let second = write_report(&dir, &report(Err("no timer yet"))).unwrap();
+        assert_ne!(first, second);
+        assert!(
+            fs::read_to_string(&first)
+                .unwrap()
+                .starts_with("TuxSplit crash report")
+        );
+
+        assert_eq!(take_pending_report(&dir), Some(second));
+        assert_eq!(take_pending_report(&dir), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,287 @@
+//! Drilling one segment: tracking the reps of a drill and their statistics.
+//!
+//! A drill runs on the normal timer. The runner starts it as usual, the
+//! splits before the drilled segment are skipped right away, and splitting
+//! at the end of the segment finishes the rep. [`DrillSession::observe`]
+//! follows the phase and split index of every frame and tells the caller
+//! what to do with the timer; it never touches the timer itself.
+
+use livesplit_core::{Timer, TimerPhase};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RepState {
+    /// Waiting for the timer to be started.
+    Waiting,
+    Running,
+    /// Split at the end, waiting for the reset.
+    Done,
+}
+
+/// What the caller should do after a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrillStep {
+    Continue,
+    /// The rep started, skip this many splits to reach the drilled segment.
+    SkipSplits(usize),
+    /// The rep was recorded, reset the timer after the pause.
+    Recorded(time::Duration),
+    /// The drilled split was skipped, reset without recording.
+    Missed,
+    /// The timer was reset during the rep.
+    Abandoned,
+    /// The last rep was recorded.
+    Finished(time::Duration),
+}
+
+#[derive(Debug, Clone)]
+pub struct DrillSession {
+    segment: usize,
+    name: String,
+    /// 0 drills until stopped.
+    target_reps: usize,
+    reps: Vec<time::Duration>,
+    rep: RepState,
+}
+
+/// Time of the rep that just split the drilled segment. The earlier splits
+/// were skipped, so it runs from the start, less the run's start offset.
+fn rep_time(timer: &Timer, segment: usize) -> Option<time::Duration> {
+    let split = timer.run().segments().get(segment)?.split_time()[timer.current_timing_method()]?;
+    Some(split.to_duration() - timer.run().offset().to_duration())
+}
+
+impl DrillSession {
+    pub fn new(segment: usize, name: &str, target_reps: usize) -> Self {
+        Self {
+            segment,
+            name: name.to_owned(),
+            target_reps,
+            reps: Vec::new(),
+            rep: RepState::Waiting,
+        }
+    }
+
+    pub fn segment(&self) -> usize {
+        self.segment
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn target_reps(&self) -> usize {
+        self.target_reps
+    }
+
+    pub fn reps(&self) -> &[time::Duration] {
+        &self.reps
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.target_reps > 0 && self.reps.len() >= self.target_reps
+    }
+
+    pub fn observe(&mut self, timer: &Timer) -> DrillStep {
+        let phase = timer.current_phase();
+        if phase == TimerPhase::NotRunning {
+            let abandoned = self.rep == RepState::Running;
+            self.rep = RepState::Waiting;
+            return if abandoned {
+                DrillStep::Abandoned
+            } else {
+                DrillStep::Continue
+            };
+        }
+        let Some(index) = timer.current_split_index() else {
+            return DrillStep::Continue;
+        };
+        match self.rep {
+            RepState::Waiting => {
+                self.rep = RepState::Running;
+                DrillStep::SkipSplits(self.segment.saturating_sub(index))
+            }
+            RepState::Running if index > self.segment || phase == TimerPhase::Ended => {
+                self.rep = RepState::Done;
+                match rep_time(timer, self.segment) {
+                    Some(time) => {
+                        self.reps.push(time);
+                        if self.is_complete() {
+                            DrillStep::Finished(time)
+                        } else {
+                            DrillStep::Recorded(time)
+                        }
+                    }
+                    None => DrillStep::Missed,
+                }
+            }
+            RepState::Running | RepState::Done => DrillStep::Continue,
+        }
+    }
+
+    /// Statistics of the reps so far against the segment's gold and its
+    /// time in the comparison. `None` before the first rep.
+    pub fn summary(
+        &self,
+        gold: Option<time::Duration>,
+        comparison: Option<time::Duration>,
+    ) -> Option<DrillSummary> {
+        let best = self.reps.iter().copied().min()?;
+        let seconds: Vec<f64> = self.reps.iter().map(|rep| rep.as_seconds_f64()).collect();
+        let mean = seconds.iter().sum::<f64>() / seconds.len() as f64;
+        let std_dev = (seconds.len() > 1).then(|| {
+            let variance = seconds.iter().map(|x| (x - mean).powi(2)).sum::<f64>()
+                / (seconds.len() - 1) as f64;
+            time::Duration::seconds_f64(variance.sqrt())
+        });
+        Some(DrillSummary {
+            reps: self.reps.len(),
+            best,
+            average: time::Duration::seconds_f64(mean),
+            std_dev,
+            gold,
+            comparison,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrillSummary {
+    pub reps: usize,
+    pub best: time::Duration,
+    pub average: time::Duration,
+    /// Sample standard deviation, from the second rep on.
+    pub std_dev: Option<time::Duration>,
+    pub gold: Option<time::Duration>,
+    /// The segment's time in the comparison the drill was started with.
+    pub comparison: Option<time::Duration>,
+}
+
+impl DrillSummary {
+    /// How far `time` is behind `reference`, negative when ahead.
+    pub fn versus(
+        time: time::Duration,
+        reference: Option<time::Duration>,
+    ) -> Option<time::Duration> {
+        reference.map(|reference| time - reference)
+    }
+}
+
+#[cfg(test)]
+mod drill_tests {
+    use super::*;
+    use crate::test_support::timer_on;
+    use livesplit_core::TimeSpan;
+
+    /// Runs one rep on `timer`, splitting `seconds` after the start.
+    fn rep(drill: &mut DrillSession, timer: &mut Timer, seconds: f64) -> DrillStep {
+        assert_eq!(drill.observe(timer), DrillStep::Continue);
+        timer.start();
+        timer.initialize_game_time();
+        timer.pause_game_time();
+        if let DrillStep::SkipSplits(skips) = drill.observe(timer) {
+            for _ in 0..skips {
+                timer.skip_split();
+            }
+        }
+        assert_eq!(timer.current_split_index(), Some(drill.segment()));
+        timer.set_game_time(TimeSpan::from_seconds(seconds));
+        timer.split();
+        let step = drill.observe(timer);
+        timer.reset(false);
+        step
+    }
+
+    fn secs(seconds: f64) -> time::Duration {
+        time::Duration::seconds_f64(seconds)
+    }
+
+    fn game_time_timer(segments: &[&str]) -> Timer {
+        let mut timer = timer_on(segments);
+        timer.set_current_timing_method(livesplit_core::TimingMethod::GameTime);
+        timer
+    }
+
+    #[test]
+    fn reps_skip_to_the_segment_and_record_its_time() {
+        let mut timer = game_time_timer(&["A", "B", "C"]);
+        let mut drill = DrillSession::new(1, "B", 3);
+
+        assert_eq!(
+            rep(&mut drill, &mut timer, 12.5),
+            DrillStep::Recorded(secs(12.5))
+        );
+        assert_eq!(
+            rep(&mut drill, &mut timer, 11.0),
+            DrillStep::Recorded(secs(11.0))
+        );
+        assert!(!drill.is_complete());
+        assert_eq!(
+            rep(&mut drill, &mut timer, 13.5),
+            DrillStep::Finished(secs(13.5))
+        );
+        assert_eq!(drill.reps(), [secs(12.5), secs(11.0), secs(13.5)]);
+        assert!(drill.is_complete());
+    }
+
+    #[test]
+    fn the_last_segment_ends_the_attempt() {
+        let mut timer = game_time_timer(&["A", "B"]);
+        let mut drill = DrillSession::new(1, "B", 0);
+        assert_eq!(
+            rep(&mut drill, &mut timer, 9.0),
+            DrillStep::Recorded(secs(9.0))
+        );
+        // Without a target the drill goes on until stopped
+        assert!(!drill.is_complete());
+    }
+
+    #[test]
+    fn resets_and_skips_record_nothing() {
+        let mut timer = game_time_timer(&["A", "B"]);
+        let mut drill = DrillSession::new(0, "A", 5);
+
+        timer.start();
+        assert_eq!(drill.observe(&timer), DrillStep::SkipSplits(0));
+        timer.reset(false);
+        assert_eq!(drill.observe(&timer), DrillStep::Abandoned);
+
+        timer.start();
+        drill.observe(&timer);
+        timer.skip_split();
+        assert_eq!(drill.observe(&timer), DrillStep::Missed);
+        // Later frames wait for the reset
+        assert_eq!(drill.observe(&timer), DrillStep::Continue);
+        timer.reset(false);
+        assert_eq!(drill.observe(&timer), DrillStep::Continue);
+        assert!(drill.reps().is_empty());
+    }
+
+    #[test]
+    fn summary_compares_the_reps_with_gold_and_comparison() {
+        let mut timer = game_time_timer(&["A", "B"]);
+        let mut drill = DrillSession::new(1, "B", 0);
+        assert_eq!(drill.summary(None, None), None);
+
+        rep(&mut drill, &mut timer, 10.0);
+        let single = drill.summary(None, None).unwrap();
+        assert_eq!(single.std_dev, None);
+
+        for seconds in [12.0, 14.0] {
+            rep(&mut drill, &mut timer, seconds);
+        }
+        let summary = drill.summary(Some(secs(9.5)), Some(secs(12.5))).unwrap();
+        assert_eq!(summary.reps, 3);
+        assert_eq!(summary.best, secs(10.0));
+        assert_eq!(summary.average, secs(12.0));
+        assert_eq!(summary.std_dev, Some(secs(2.0)));
+        assert_eq!(
+            DrillSummary::versus(summary.best, summary.gold),
+            Some(secs(0.5))
+        );
+        assert_eq!(
+            DrillSummary::versus(summary.average, summary.comparison),
+            Some(secs(-0.5))
+        );
+        assert_eq!(DrillSummary::versus(summary.best, None), None);
+    }
+}
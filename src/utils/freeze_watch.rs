@@ -0,0 +1,249 @@
+//! Telling a hard-frozen game from a running one, from samples of its
+//! `/proc/<pid>/stat`.
+//!
+//! A process counts as frozen when it is stopped (`T`, `t`), a zombie
+//! (`Z`), or hasn't used any CPU time for the configured while. It counts
+//! as responsive again once it uses CPU time outside of those states.
+
+use std::time::{Duration, Instant};
+
+/// What a sample of `/proc/<pid>/stat` is read for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcSample {
+    /// The state letter, `R`, `S`, `T`...
+    pub state: char,
+    /// User and system time used so far, in clock ticks.
+    pub cpu_ticks: u64,
+}
+
+/// Reads a line of `/proc/<pid>/stat`. The command name in parentheses may
+/// itself hold spaces and parentheses, so fields are counted from the last
+/// closing one.
+pub fn parse_stat(stat: &str) -> Option<ProcSample> {
+    let (_, rest) = stat.rsplit_once(')')?;
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let state = fields.first()?.chars().next()?;
+    // utime and stime are the 14th and 15th fields, the state the 3rd
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(ProcSample {
+        state,
+        cpu_ticks: utime + stime,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FreezeReason {
+    Stopped,
+    Zombie,
+    /// No CPU time used for this long.
+    Idle(Duration),
+}
+
+impl std::fmt::Display for FreezeReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FreezeReason::Stopped => write!(f, "was stopped"),
+            FreezeReason::Zombie => write!(f, "became a zombie"),
+            FreezeReason::Idle(idle) => write!(f, "used no CPU for {}s", idle.as_secs()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FreezeChange {
+    Froze(FreezeReason),
+    Recovered,
+}
+
+#[derive(Debug)]
+pub struct FreezeDetector {
+    /// No CPU time for this long counts as frozen. Zero leaves the CPU
+    /// time out and only looks at the state.
+    idle_after: Duration,
+    last_ticks: Option<u64>,
+    idle_since: Option<Instant>,
+    frozen: bool,
+}
+
+impl FreezeDetector {
+    pub fn new(idle_after: Duration) -> Self {
+        Self {
+            idle_after,
+            last_ticks: None,
+            idle_since: None,
+            frozen: false,
+        }
+    }
+
+    pub fn frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// Takes the sample made at `now`, or `None` when the process is gone,
+    /// and returns whether that changed anything.
+    pub fn observe(&mut self, sample: Option<ProcSample>, now: Instant) -> Option<FreezeChange> {
+        let Some(sample) = sample else {
+            // An exited game doesn't come back responsive, the next one is
+            // watched from scratch
+            *self = Self::new(self.idle_after);
+            return None;
+        };
+        let progressed = self
+            .last_ticks
+            .replace(sample.cpu_ticks)
+            .is_some_and(|last| sample.cpu_ticks > last);
+        if progressed {
+            self.idle_since = None;
+        } else {
+            self.idle_since.get_or_insert(now);
+        }
+
+        let reason = match sample.state {
+            'T' | 't' => Some(FreezeReason::Stopped),
+            'Z' => Some(FreezeReason::Zombie),
+            _ => self
+                .idle_since
+                .map(|since| now.saturating_duration_since(since))
+                .filter(|idle| !self.idle_after.is_zero() && *idle >= self.idle_after)
+                .map(FreezeReason::Idle),
+        };
+        match (self.frozen, reason) {
+            (false, Some(reason)) => {
+                self.frozen = true;
+                Some(FreezeChange::Froze(reason))
+            }
+            (true, None) if progressed => {
+                self.frozen = false;
+                Some(FreezeChange::Recovered)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod freeze_watch_tests {
+    use super::*;
+
+    fn stat(state: char, utime: u64, stime: u64) -> String {
+        format!(
+            "4242 (Game (x64).exe) {state} 1 4242 4242 0 -1 4194560 1000 0 0 0 \
+             {utime} {stime} 0 0 20 0 8 0 12345 1000000 500 18446744073709551615"
+        )
+    }
+
+    fn sample(state: char, ticks: u64) -> Option<ProcSample> {
+        parse_stat(&stat(state, ticks, 0))
+    }
+
+    #[test]
+    fn stat_lines_are_parsed_past_the_command_name() {
+        assert_eq!(
+            parse_stat(&stat('S', 120, 30)),
+            Some(ProcSample {
+                state: 'S',
+                cpu_ticks: 150
+            })
+        );
+        assert_eq!(parse_stat("4242 (Game) S 1"), None);
+        assert_eq!(parse_stat(""), None);
+    }
+
+    #[test]
+    fn a_busy_process_is_never_frozen() {
+        let mut detector = FreezeDetector::new(Duration::from_secs(5));
+        let start = Instant::now();
+        for second in 0..20 {
+            assert_eq!(
+                detector.observe(
+                    sample('R', 100 + second * 10),
+                    start + Duration::from_secs(second)
+                ),
+                None
+            );
+        }
+        assert!(!detector.frozen());
+    }
+
+    #[test]
+    fn no_cpu_for_the_threshold_freezes_and_progress_recovers() {
+        let mut detector = FreezeDetector::new(Duration::from_secs(5));
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        detector.observe(sample('S', 100), at(0));
+        for second in 1..5 {
+            assert_eq!(detector.observe(sample('S', 100), at(second)), None);
+        }
+        assert_eq!(
+            detector.observe(sample('S', 100), at(6)),
+            Some(FreezeChange::Froze(FreezeReason::Idle(
+                Duration::from_secs(6)
+            )))
+        );
+        // Reported once
+        assert_eq!(detector.observe(sample('S', 100), at(7)), None);
+        assert_eq!(
+            detector.observe(sample('R', 104), at(8)),
+            Some(FreezeChange::Recovered)
+        );
+        assert!(!detector.frozen());
+    }
+
+    #[test]
+    fn stopped_and_zombie_states_freeze_right_away() {
+        let start = Instant::now();
+        for (state, reason) in [('T', FreezeReason::Stopped), ('Z', FreezeReason::Zombie)] {
+            let mut detector = FreezeDetector::new(Duration::from_secs(30));
+            detector.observe(sample('R', 100), start);
+            assert_eq!(
+                detector.observe(sample(state, 110), start + Duration::from_secs(1)),
+                Some(FreezeChange::Froze(reason))
+            );
+        }
+    }
+
+    #[test]
+    fn a_stopped_process_recovers_once_it_runs_again() {
+        let mut detector = FreezeDetector::new(Duration::ZERO);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        detector.observe(sample('R', 100), at(0));
+        detector.observe(sample('T', 100), at(1));
+        // Continued, but nothing ran yet
+        assert_eq!(detector.observe(sample('S', 100), at(2)), None);
+        assert!(detector.frozen());
+        assert_eq!(
+            detector.observe(sample('R', 101), at(3)),
+            Some(FreezeChange::Recovered)
+        );
+    }
+
+    #[test]
+    fn zero_threshold_ignores_idle_processes() {
+        let mut detector = FreezeDetector::new(Duration::ZERO);
+        let start = Instant::now();
+        for second in 0..60 {
+            assert_eq!(
+                detector.observe(sample('S', 100), start + Duration::from_secs(second)),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn an_exited_process_forgets_the_freeze() {
+        let mut detector = FreezeDetector::new(Duration::ZERO);
+        let start = Instant::now();
+        detector.observe(sample('R', 100), start);
+        detector.observe(sample('Z', 100), start + Duration::from_secs(1));
+        assert!(detector.frozen());
+        assert_eq!(detector.observe(None, start + Duration::from_secs(2)), None);
+        assert!(!detector.frozen());
+        // A relaunched game starts from a fresh baseline
+        assert_eq!(
+            detector.observe(sample('R', 5), start + Duration::from_secs(3)),
+            None
+        );
+    }
+}
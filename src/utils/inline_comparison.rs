@@ -0,0 +1,350 @@
+//! Comparisons written out in the config, e.g. a target pace.
+//!
+//! `general.inline-comparisons` lists split times for the loaded run, in
+//! segment order or by segment name. Each becomes a generated comparison of
+//! the run when the splits are loaded, so it can be cycled to and selected
+//! like any other without touching the splits file, and it isn't saved
+//! into it. Times that don't fit the run are left out and reported: extra
+//! times, names no segment has, and times that don't parse. Segments
+//! without a time have none in the comparison.
+
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+
+use livesplit_core::comparison::ComparisonGenerator;
+use livesplit_core::run::Attempt;
+use livesplit_core::{Run, Segment, TimeSpan, TimingMethod};
+
+use crate::config::{InlineComparison, InlineTimes};
+use crate::formatters::time::parse_hms;
+
+/// A time of an inline comparison that didn't make it into the run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InlineProblem {
+    /// The run has another comparison of this name.
+    NameTaken(String),
+    /// More times than the run has segments.
+    ExtraTimes {
+        comparison: String,
+        extra: usize,
+    },
+    /// No segment has this name.
+    UnknownSegment {
+        comparison: String,
+        segment: String,
+    },
+    Unparsable {
+        comparison: String,
+        time: String,
+    },
+}
+
+impl std::fmt::Display for InlineProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            InlineProblem::NameTaken(name) => {
+                write!(f, "The splits already have a comparison named {name:?}")
+            }
+            InlineProblem::ExtraTimes { comparison, extra } => {
+                write!(f, "{comparison:?} has {extra} more times than segments")
+            }
+            InlineProblem::UnknownSegment {
+                comparison,
+                segment,
+            } => write!(
+                f,
+                "{comparison:?} has a time for {segment:?}, no such segment"
+            ),
+            InlineProblem::Unparsable { comparison, time } => {
+                write!(f, "{comparison:?} has a time {time:?} that can't be read")
+            }
+        }
+    }
+}
+
+thread_local! {
+    /// Names of the inline comparisons added so far. The run's generators
+    /// can't be told apart by type, so only the ones of these names are
+    /// replaced, never livesplit-core's own.
+    static ADDED: RefCell<BTreeSet<String>> = const { RefCell::new(BTreeSet::new()) };
+}
+
+/// Reads a split time like "2:10.5". The decimals may be left out.
+fn parse_split_time(text: &str) -> Option<TimeSpan> {
+    parse_hms(text.trim()).ok().map(TimeSpan::from)
+}
+
+/// Whether `comparison` is meant for `run`.
+fn applies_to(comparison: &InlineComparison, run: &Run) -> bool {
+    let matches = |wanted: &Option<String>, actual: &str| {
+        wanted
+            .as_deref()
+            .is_none_or(|wanted| wanted.trim().eq_ignore_ascii_case(actual.trim()))
+    };
+    matches(&comparison.game, run.game_name()) && matches(&comparison.category, run.category_name())
+}
+
+/// The split time of each of `segments` in `comparison`.
+pub fn align_times(
+    comparison: &InlineComparison,
+    segments: &[Segment],
+) -> (Vec<Option<TimeSpan>>, Vec<InlineProblem>) {
+    let name = &comparison.name;
+    let mut times = vec![None; segments.len()];
+    let mut problems = Vec::new();
+    let mut parsed = |time: &str| {
+        let parsed = parse_split_time(time);
+        if parsed.is_none() {
+            problems.push(InlineProblem::Unparsable {
+                comparison: name.clone(),
+                time: time.to_owned(),
+            });
+        }
+        parsed
+    };
+    let mut unknown = Vec::new();
+    match &comparison.times {
+        InlineTimes::InOrder(list) => {
+            for (slot, time) in times.iter_mut().zip(list) {
+                *slot = parsed(time);
+            }
+            if list.len() > segments.len() {
+                unknown.push(InlineProblem::ExtraTimes {
+                    comparison: name.clone(),
+                    extra: list.len() - segments.len(),
+                });
+            }
+        }
+        InlineTimes::ByName(by_name) => {
+            for (segment, time) in by_name {
+                match segments
+                    .iter()
+                    .position(|candidate| candidate.name().trim() == segment.trim())
+                {
+                    Some(index) => times[index] = parsed(time),
+                    None => unknown.push(InlineProblem::UnknownSegment {
+                        comparison: name.clone(),
+                        segment: segment.clone(),
+                    }),
+                }
+            }
+        }
+    }
+    problems.extend(unknown);
+    (times, problems)
+}
+
+/// Generates an inline comparison. Its times come from the config, so they
+/// only change when the splits are loaded again.
+#[derive(Debug, Clone)]
+pub struct InlineTimesGenerator {
+    name: String,
+    times: Vec<Option<TimeSpan>>,
+}
+
+impl ComparisonGenerator for InlineTimesGenerator {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn generate(&mut self, segments: &mut [Segment], _: &[Attempt]) {
+        for (index, segment) in segments.iter_mut().enumerate() {
+            let time = self.times.get(index).copied().flatten();
+            let comparison = segment.comparison_mut(&self.name);
+            comparison[TimingMethod::RealTime] = time;
+            comparison[TimingMethod::GameTime] = time;
+        }
+    }
+}
+
+/// Adds the inline comparisons of `comparisons` meant for `run`, replacing
+/// the ones added before. Like the other generated comparisons, before the
+/// run is handed to the timer. Returns what didn't fit.
+pub fn ensure_inline_comparisons(
+    run: &mut Run,
+    comparisons: &[InlineComparison],
+) -> Vec<InlineProblem> {
+    ADDED.with_borrow(|added| {
+        run.comparison_generators_mut()
+            .retain(|generator| !added.contains(generator.name()));
+    });
+
+    let mut problems = Vec::new();
+    for comparison in comparisons {
+        let name = comparison.name.trim();
+        if name.is_empty() || !applies_to(comparison, run) {
+            continue;
+        }
+        if run.comparisons().any(|existing| existing == name) {
+            problems.push(InlineProblem::NameTaken(name.to_owned()));
+            continue;
+        }
+        let (times, mut misfits) = align_times(comparison, run.segments());
+        problems.append(&mut misfits);
+        ADDED.with_borrow_mut(|added| added.insert(name.to_owned()));
+        run.comparison_generators_mut()
+            .push(Box::new(InlineTimesGenerator {
+                name: name.to_owned(),
+                times,
+            }));
+    }
+    problems
+}
+
+#[cfg(test)]
+mod inline_comparison_tests {
+    use super::*;
+    use crate::utils::comparisons::cycle_comparisons;
+    use livesplit_core::Timer;
+    use std::collections::BTreeMap;
+
+    fn run() -> Run {
+        let mut run = Run::new();
+        run.set_game_name("Celeste");
+        run.set_category_name("Any%");
+        for name in ["Prologue", "City", "Site"] {
+            run.push_segment(Segment::new(name));
+        }
+        run
+    }
+
+    fn in_order(name: &str, times: &[&str]) -> InlineComparison {
+        InlineComparison {
+            name: name.to_owned(),
+            times: InlineTimes::InOrder(times.iter().map(|time| (*time).to_owned()).collect()),
+            ..InlineComparison::default()
+        }
+    }
+
+    fn seconds(times: &[Option<TimeSpan>]) -> Vec<Option<f64>> {
+        times
+            .iter()
+            .map(|time| time.map(|time| time.total_seconds()))
+            .collect()
+    }
+
+    #[test]
+    fn split_times_are_read_with_or_without_decimals() {
+        let seconds = |text| parse_split_time(text).map(|time| time.total_seconds());
+        assert_eq!(seconds("2:10"), Some(130.0));
+        assert_eq!(seconds(" 2:10.5 "), Some(130.5));
+        assert_eq!(seconds("1:00:00"), Some(3600.0));
+        assert_eq!(seconds("45"), Some(45.0));
+        assert_eq!(seconds("soon"), None);
+    }
+
+    #[test]
+    fn times_align_by_index_or_name() {
+        let run = run();
+        let (times, problems) = align_times(&in_order("Target", &["20", "2:30"]), run.segments());
+        assert_eq!(seconds(&times), [Some(20.0), Some(150.0), None]);
+        assert!(problems.is_empty());
+
+        let by_name = InlineComparison {
+            name: "Target".to_owned(),
+            times: InlineTimes::ByName(BTreeMap::from([
+                ("Site".to_owned(), "5:20.0".to_owned()),
+                ("Summit".to_owned(), "30:00".to_owned()),
+            ])),
+            ..InlineComparison::default()
+        };
+        let (times, problems) = align_times(&by_name, run.segments());
+        assert_eq!(seconds(&times), [None, None, Some(320.0)]);
+        assert_eq!(
+            problems,
+            [InlineProblem::UnknownSegment {
+                comparison: "Target".to_owned(),
+                segment: "Summit".to_owned()
+            }]
+        );
+    }
+
+    #[test]
+    fn mismatched_counts_are_reported() {
+        let run = run();
+        let (times, problems) = align_times(
+            &in_order("Target", &["20", "later", "5:20", "8:00"]),
+            run.segments(),
+        );
+        assert_eq!(seconds(&times), [Some(20.0), None, Some(320.0)]);
+        assert_eq!(
+            problems,
+            [
+                InlineProblem::Unparsable {
+                    comparison: "Target".to_owned(),
+                    time: "later".to_owned()
+                },
+                InlineProblem::ExtraTimes {
+                    comparison: "Target".to_owned(),
+                    extra: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn inline_comparisons_can_be_selected() {
+        let mut run = run();
+        let comparisons = [
+            in_order("Sub 6", &["20", "2:30", "5:50"]),
+            InlineComparison {
+                game: Some("Celeste 64".to_owned()),
+                ..in_order("Other game", &["1"])
+            },
+            in_order("Personal Best", &["1"]),
+        ];
+        let problems = ensure_inline_comparisons(&mut run, &comparisons);
+        assert_eq!(
+            problems,
+            [InlineProblem::NameTaken("Personal Best".to_owned())]
+        );
+        // Loaded again, e.g. after editing the splits
+        assert_eq!(ensure_inline_comparisons(&mut run, &comparisons).len(), 1);
+
+        let mut timer = Timer::new(run).unwrap();
+        let order = cycle_comparisons(timer.run(), 3);
+        assert_eq!(
+            order.iter().filter(|name| *name == "Sub 6").count(),
+            1,
+            "{order:?}"
+        );
+        assert!(!order.contains(&"Other game".to_owned()));
+        assert!(timer.set_current_comparison("Sub 6").is_ok());
+        let times: Vec<_> = timer
+            .run()
+            .segments()
+            .iter()
+            .map(|segment| segment.comparison(timer.current_comparison()).real_time)
+            .collect();
+        assert_eq!(seconds(&times), [Some(20.0), Some(150.0), Some(350.0)]);
+    }
+
+    #[test]
+    fn generated_comparisons_are_never_replaced() {
+        use livesplit_core::comparison::{best_segments, latest_run};
+
+        let mut run = run();
+        let generators = run.comparison_generators().len();
+        let comparisons = [
+            in_order(best_segments::NAME, &["1", "2", "3"]),
+            in_order(latest_run::NAME, &["1"]),
+        ];
+        for _ in 0..2 {
+            assert_eq!(
+                ensure_inline_comparisons(&mut run, &comparisons),
+                [
+                    InlineProblem::NameTaken(best_segments::NAME.to_owned()),
+                    InlineProblem::NameTaken(latest_run::NAME.to_owned()),
+                ]
+            );
+            assert_eq!(run.comparison_generators().len(), generators);
+        }
+
+        // A comparison taken out of the config goes away on the next load
+        ensure_inline_comparisons(&mut run, &[in_order("Sub 6", &["20"])]);
+        assert!(run.comparisons().any(|name| name == "Sub 6"));
+        ensure_inline_comparisons(&mut run, &[]);
+        assert!(!run.comparisons().any(|name| name == "Sub 6"));
+        assert!(run.comparisons().any(|name| name == best_segments::NAME));
+    }
+}
@@ -1 +1,15 @@
+pub mod attempt_edit;
+pub mod bulk_edit;
 pub mod comparisons;
+pub mod crash_report;
+pub mod drill;
+pub mod freeze_watch;
+pub mod inline_comparison;
+pub mod offset_conflict;
+#[cfg(feature = "gamepad")]
+pub mod pedal;
+pub mod setting_history;
+pub mod startup;
+pub mod stats;
+pub mod trace_timing;
+pub mod video_offset;
@@ -0,0 +1,131 @@
+//! A start offset together with an auto splitter that starts the runs.
+//!
+//! An offset like -5s is meant for starting by hand ahead of the run: the
+//! timer counts up to zero and the run begins there. An auto splitter
+//! starts the timer at the moment the run begins instead, so the countdown
+//! ends up in every time, off by the offset. The auto splitting runtime
+//! doesn't tell whether the loaded module starts runs, so that is learnt
+//! from the first start that neither TuxSplit nor a global hotkey can have
+//! made, and remembered for the splits file.
+
+use livesplit_core::TimeSpan;
+
+/// Who started an attempt, as far as can be told.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartSource {
+    /// A key press in a window, a pedal, the split button, a race or a
+    /// resumed attempt.
+    TuxSplit,
+    /// The timer reached by a global hotkey or the auto splitter, whichever
+    /// it was.
+    Outside,
+    AutoSplitter,
+}
+
+/// Where the start just seen came from. `issued_here` is whether TuxSplit
+/// started the timer itself since the previous tick.
+pub fn start_source(issued_here: bool, auto_splitter: bool, global_hotkeys: bool) -> StartSource {
+    if issued_here {
+        StartSource::TuxSplit
+    } else if auto_splitter && !global_hotkeys {
+        StartSource::AutoSplitter
+    } else {
+        StartSource::Outside
+    }
+}
+
+/// What to do about a start, given what's stored for the splits file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartResponse {
+    /// Nothing to learn or undo.
+    Keep,
+    /// The auto splitter starts this file's runs, which wasn't known yet.
+    Remember,
+    /// The file ignores the auto splitter's starts, reset the attempt.
+    Undo,
+}
+
+pub fn respond_to_start(source: StartSource, known_to_start: bool, ignored: bool) -> StartResponse {
+    match source {
+        StartSource::AutoSplitter if ignored => StartResponse::Undo,
+        StartSource::AutoSplitter if !known_to_start => StartResponse::Remember,
+        _ => StartResponse::Keep,
+    }
+}
+
+/// Whether the runs of a file starting at `offset` get the countdown in
+/// their times.
+pub fn offset_conflicts(
+    offset: TimeSpan,
+    auto_splitter: bool,
+    known_to_start: bool,
+    ignored: bool,
+) -> bool {
+    offset != TimeSpan::zero() && auto_splitter && known_to_start && !ignored
+}
+
+/// e.g. "Runs start at -5.00s, and the auto splitter starts them too".
+pub fn describe_conflict(offset: TimeSpan) -> String {
+    format!(
+        "Runs start at {:.2}s, and the auto splitter starts them too",
+        offset.total_seconds()
+    )
+}
+
+#[cfg(test)]
+mod offset_conflict_tests {
+    use super::*;
+
+    #[test]
+    fn only_starts_nothing_else_can_have_made_are_the_auto_splitters() {
+        let cases = [
+            // issued here, auto splitter, global hotkeys
+            ((true, true, false), StartSource::TuxSplit),
+            ((true, false, true), StartSource::TuxSplit),
+            ((false, true, false), StartSource::AutoSplitter),
+            ((false, true, true), StartSource::Outside),
+            ((false, false, true), StartSource::Outside),
+            ((false, false, false), StartSource::Outside),
+        ];
+        for ((issued, auto_splitter, hotkeys), expected) in cases {
+            assert_eq!(
+                start_source(issued, auto_splitter, hotkeys),
+                expected,
+                "issued {issued}, auto splitter {auto_splitter}, hotkeys {hotkeys}"
+            );
+        }
+    }
+
+    #[test]
+    fn auto_starts_are_remembered_once_or_undone() {
+        use StartResponse::{Keep, Remember, Undo};
+        let auto = StartSource::AutoSplitter;
+        assert_eq!(respond_to_start(auto, false, false), Remember);
+        assert_eq!(respond_to_start(auto, true, false), Keep);
+        assert_eq!(respond_to_start(auto, false, true), Undo);
+        assert_eq!(respond_to_start(auto, true, true), Undo);
+        for source in [StartSource::TuxSplit, StartSource::Outside] {
+            for (known, ignored) in [(false, false), (true, false), (true, true)] {
+                assert_eq!(respond_to_start(source, known, ignored), Keep);
+            }
+        }
+    }
+
+    #[test]
+    fn offsets_conflict_with_auto_splitters_that_start() {
+        let countdown = TimeSpan::from_seconds(-5.0);
+        assert!(offset_conflicts(countdown, true, true, false));
+        // A positive offset is off by as much
+        let head_start = TimeSpan::from_seconds(1.5);
+        assert!(offset_conflicts(head_start, true, true, false));
+
+        assert!(!offset_conflicts(TimeSpan::zero(), true, true, false));
+        assert!(!offset_conflicts(countdown, false, true, false));
+        assert!(!offset_conflicts(countdown, true, false, false));
+        assert!(!offset_conflicts(countdown, true, true, true));
+        assert_eq!(
+            describe_conflict(countdown),
+            "Runs start at -5.00s, and the auto splitter starts them too"
+        );
+    }
+}
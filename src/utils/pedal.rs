@@ -0,0 +1,356 @@
+//! Foot pedals and gamepad buttons bound to timer actions.
+//!
+//! Many pedals show up as a gamepad rather than a keyboard, so the global
+//! hotkeys never see them. The listener in [`crate::io::gamepad`] turns what
+//! the devices send into [`PadEvent`]s, and [`PedalInput`] keeps track of the
+//! connected devices, turns presses on the configured device into actions
+//! and, while a button is being captured, binds the next press instead.
+//! Devices are told apart by name, which is what survives replugging them.
+//! Unplugging the configured device mid-run leaves its buttons dead, so
+//! that and plugging it back in are worth a notice.
+
+use std::collections::BTreeSet;
+
+use crate::config::{GamepadOptions, PadBinding};
+use crate::ui::hotkeys::HotkeyAction;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PadEvent {
+    Connected(String),
+    Disconnected(String),
+    Pressed { device: String, button: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PadOutcome {
+    Nothing,
+    Run(HotkeyAction),
+    /// A press was captured, to be bound to its action on `device`.
+    Bound {
+        device: String,
+        binding: PadBinding,
+    },
+    DevicesChanged,
+}
+
+impl GamepadOptions {
+    /// The action `button` of `device` is bound to.
+    pub fn action_for(&self, device: &str, button: &str) -> Option<HotkeyAction> {
+        if self.device.as_deref() != Some(device) {
+            return None;
+        }
+        self.buttons
+            .iter()
+            .find(|binding| binding.button == button)
+            .map(|binding| binding.action)
+    }
+
+    /// Binds the button of `device`, replacing whatever the button or the
+    /// action was bound to. Binding a button of another device switches to
+    /// that device, and the buttons of the previous one are dropped.
+    pub fn bind(&mut self, device: String, binding: PadBinding) {
+        if self.device.as_ref() != Some(&device) {
+            self.device = Some(device);
+            self.buttons.clear();
+        }
+        self.buttons
+            .retain(|bound| bound.button != binding.button && bound.action != binding.action);
+        self.buttons.push(binding);
+    }
+
+    /// Listens to `device` from now on, none with `None`. The buttons only
+    /// stay bound when it's the same device.
+    pub fn select_device(&mut self, device: Option<String>) {
+        if self.device != device {
+            self.device = device;
+            self.buttons.clear();
+        }
+    }
+}
+
+/// The connected devices and the capture of a button, see the module docs.
+#[derive(Debug, Default)]
+pub struct PedalInput {
+    connected: BTreeSet<String>,
+    /// Configured devices unplugged since they were last seen.
+    lost: BTreeSet<String>,
+    capturing: Option<HotkeyAction>,
+}
+
+impl PedalInput {
+    /// The names of the connected devices, in order.
+    pub fn devices(&self) -> Vec<String> {
+        self.connected.iter().cloned().collect()
+    }
+
+    /// The action the next press is bound to, if capturing.
+    pub fn capturing(&self) -> Option<HotkeyAction> {
+        self.capturing
+    }
+
+    pub fn start_capture(&mut self, action: HotkeyAction) {
+        self.capturing = Some(action);
+    }
+
+    pub fn cancel_capture(&mut self) {
+        self.capturing = None;
+    }
+
+    /// What to tell about `event` before it's handled: the configured device
+    /// was unplugged, or came back after that. Devices present when the
+    /// listener starts aren't announced.
+    pub fn hotplug_notice(&mut self, options: &GamepadOptions, event: &PadEvent) -> Option<String> {
+        match event {
+            PadEvent::Disconnected(device)
+                if options.device.as_ref() == Some(device) && self.connected.contains(device) =>
+            {
+                self.lost.insert(device.clone());
+                Some(format!(
+                    "{device} disconnected, its buttons do nothing until it's back"
+                ))
+            }
+            PadEvent::Connected(device) | PadEvent::Pressed { device, .. }
+                if self.lost.remove(device) =>
+            {
+                (options.device.as_ref() == Some(device)).then(|| format!("{device} reconnected"))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn handle(&mut self, options: &GamepadOptions, event: PadEvent) -> PadOutcome {
+        match event {
+            PadEvent::Connected(device) => {
+                if self.connected.insert(device) {
+                    return PadOutcome::DevicesChanged;
+                }
+            }
+            PadEvent::Disconnected(device) => {
+                if self.connected.remove(&device) {
+                    return PadOutcome::DevicesChanged;
+                }
+            }
+            PadEvent::Pressed { device, button } => {
+                // A press also tells about a device connected before listening
+                self.connected.insert(device.clone());
+                if let Some(action) = self.capturing.take() {
+                    return PadOutcome::Bound {
+                        device,
+                        binding: PadBinding { button, action },
+                    };
+                }
+                if let Some(action) = options.action_for(&device, &button) {
+                    return PadOutcome::Run(action);
+                }
+            }
+        }
+        PadOutcome::Nothing
+    }
+}
+
+#[cfg(test)]
+mod pedal_tests {
+    use super::*;
+
+    const PEDAL: &str = "PCsensor FootSwitch";
+
+    fn press(device: &str, button: &str) -> PadEvent {
+        PadEvent::Pressed {
+            device: device.to_owned(),
+            button: button.to_owned(),
+        }
+    }
+
+    fn pedal_bound_to_split() -> GamepadOptions {
+        GamepadOptions {
+            device: Some(PEDAL.to_owned()),
+            buttons: vec![PadBinding {
+                button: "South".to_owned(),
+                action: HotkeyAction::Split,
+            }],
+        }
+    }
+
+    #[test]
+    fn presses_of_the_configured_device_run_their_action() {
+        let options = pedal_bound_to_split();
+        let mut input = PedalInput::default();
+        assert_eq!(
+            input.handle(&options, press(PEDAL, "South")),
+            PadOutcome::Run(HotkeyAction::Split)
+        );
+        assert_eq!(
+            input.handle(&options, press(PEDAL, "East")),
+            PadOutcome::Nothing
+        );
+        // The same button on another device isn't bound
+        assert_eq!(
+            input.handle(&options, press("Xbox Controller", "South")),
+            PadOutcome::Nothing
+        );
+    }
+
+    #[test]
+    fn nothing_runs_without_a_device() {
+        let options = GamepadOptions {
+            device: None,
+            ..pedal_bound_to_split()
+        };
+        let mut input = PedalInput::default();
+        assert_eq!(
+            input.handle(&options, press(PEDAL, "South")),
+            PadOutcome::Nothing
+        );
+    }
+
+    #[test]
+    fn capturing_binds_the_next_press_instead_of_running_it() {
+        let mut options = pedal_bound_to_split();
+        let mut input = PedalInput::default();
+        input.start_capture(HotkeyAction::Reset);
+        let outcome = input.handle(&options, press(PEDAL, "South"));
+        assert_eq!(
+            outcome,
+            PadOutcome::Bound {
+                device: PEDAL.to_owned(),
+                binding: PadBinding {
+                    button: "South".to_owned(),
+                    action: HotkeyAction::Reset,
+                },
+            }
+        );
+        assert_eq!(input.capturing(), None);
+        let PadOutcome::Bound { device, binding } = outcome else {
+            unreachable!();
+        };
+        options.bind(device, binding);
+        assert_eq!(
+            input.handle(&options, press(PEDAL, "South")),
+            PadOutcome::Run(HotkeyAction::Reset)
+        );
+        // The button moved, Split is left unbound
+        assert_eq!(options.buttons.len(), 1);
+    }
+
+    #[test]
+    fn a_cancelled_capture_binds_nothing() {
+        let options = pedal_bound_to_split();
+        let mut input = PedalInput::default();
+        input.start_capture(HotkeyAction::Undo);
+        input.cancel_capture();
+        assert_eq!(
+            input.handle(&options, press(PEDAL, "South")),
+            PadOutcome::Run(HotkeyAction::Split)
+        );
+    }
+
+    #[test]
+    fn binding_another_device_switches_to_it() {
+        let mut options = pedal_bound_to_split();
+        options.bind(
+            PEDAL.to_owned(),
+            PadBinding {
+                button: "code-289".to_owned(),
+                action: HotkeyAction::Pause,
+            },
+        );
+        assert_eq!(options.buttons.len(), 2);
+        assert_eq!(
+            options.action_for(PEDAL, "code-289"),
+            Some(HotkeyAction::Pause)
+        );
+
+        options.bind(
+            "Other Pedal".to_owned(),
+            PadBinding {
+                button: "South".to_owned(),
+                action: HotkeyAction::Skip,
+            },
+        );
+        assert_eq!(options.device.as_deref(), Some("Other Pedal"));
+        assert_eq!(options.buttons.len(), 1);
+        assert_eq!(options.action_for(PEDAL, "South"), None);
+
+        options.select_device(None);
+        assert!(options.buttons.is_empty());
+    }
+
+    #[test]
+    fn bindings_read_from_the_config() {
+        let options: GamepadOptions = serde_yaml::from_str(
+            "device: PCsensor FootSwitch\nbuttons:\n  - button: code-289\n    action: undo-all-pauses\n",
+        )
+        .expect("gamepad options");
+        assert_eq!(
+            options.action_for(PEDAL, "code-289"),
+            Some(HotkeyAction::UndoAllPauses)
+        );
+    }
+
+    #[test]
+    fn unplugging_the_configured_device_is_announced() {
+        let options = pedal_bound_to_split();
+        let mut input = PedalInput::default();
+        let connected = PadEvent::Connected(PEDAL.to_owned());
+        let unplugged = PadEvent::Disconnected(PEDAL.to_owned());
+        let notice = |input: &mut PedalInput, event: PadEvent| {
+            let notice = input.hotplug_notice(&options, &event);
+            input.handle(&options, event);
+            notice
+        };
+
+        // Already plugged in when listening starts
+        assert_eq!(notice(&mut input, connected.clone()), None);
+        assert_eq!(
+            notice(&mut input, unplugged.clone()).as_deref(),
+            Some("PCsensor FootSwitch disconnected, its buttons do nothing until it's back")
+        );
+        assert_eq!(notice(&mut input, unplugged.clone()), None);
+        assert_eq!(
+            notice(&mut input, connected.clone()).as_deref(),
+            Some("PCsensor FootSwitch reconnected")
+        );
+        assert_eq!(notice(&mut input, connected), None);
+
+        // Other devices come and go quietly
+        let other = "Xbox Controller".to_owned();
+        assert_eq!(notice(&mut input, PadEvent::Connected(other.clone())), None);
+        assert_eq!(notice(&mut input, PadEvent::Disconnected(other)), None);
+
+        // A press is enough to tell it's back
+        notice(&mut input, unplugged);
+        assert_eq!(
+            notice(&mut input, press(PEDAL, "South")).as_deref(),
+            Some("PCsensor FootSwitch reconnected")
+        );
+    }
+
+    #[test]
+    fn devices_follow_the_hotplug_events() {
+        let options = GamepadOptions::default();
+        let mut input = PedalInput::default();
+        let connected = PadEvent::Connected(PEDAL.to_owned());
+        assert_eq!(
+            input.handle(&options, connected.clone()),
+            PadOutcome::DevicesChanged
+        );
+        assert_eq!(input.handle(&options, connected), PadOutcome::Nothing);
+        assert_eq!(input.devices(), vec![PEDAL.to_owned()]);
+
+        let unplugged = PadEvent::Disconnected(PEDAL.to_owned());
+        assert_eq!(
+            input.handle(&options, unplugged.clone()),
+            PadOutcome::DevicesChanged
+        );
+        assert_eq!(input.handle(&options, unplugged), PadOutcome::Nothing);
+        assert!(input.devices().is_empty());
+
+        // Plugged back in, its presses work again right away
+        let options = pedal_bound_to_split();
+        input.handle(&options, PadEvent::Connected(PEDAL.to_owned()));
+        assert_eq!(
+            input.handle(&options, press(PEDAL, "South")),
+            PadOutcome::Run(HotkeyAction::Split)
+        );
+    }
+}
@@ -0,0 +1,279 @@
+//! Recent changes of the settings that change what the display shows, so an
+//! accidental switch can be undone.
+//!
+//! The history follows the comparison and timing method seen on each frame
+//! rather than the code paths that switch them, so changes from global
+//! hotkeys, the comparison picker or the settings all count. Quick changes
+//! of the same setting count as one, so cycling through several comparisons
+//! by accident is undone in one go.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use livesplit_core::{Timer, TimingMethod};
+
+/// Changes kept for undoing, older ones are forgotten.
+pub const HISTORY_CAPACITY: usize = 8;
+/// Changes of the same setting this close together count as one.
+pub const COALESCE_WINDOW: Duration = Duration::from_secs(2);
+
+/// What the display-affecting settings are on a frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplaySettings {
+    pub comparison: String,
+    pub timing_method: TimingMethod,
+}
+
+impl DisplaySettings {
+    pub fn of(timer: &Timer) -> Self {
+        Self {
+            comparison: timer.current_comparison().to_owned(),
+            timing_method: timer.current_timing_method(),
+        }
+    }
+}
+
+/// A setting along with a value for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Setting {
+    Comparison(String),
+    TimingMethod(TimingMethod),
+}
+
+impl Setting {
+    fn same_kind(&self, other: &Setting) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
+    fn apply_to(&self, settings: &mut DisplaySettings) {
+        match self {
+            Setting::Comparison(name) => settings.comparison.clone_from(name),
+            Setting::TimingMethod(method) => settings.timing_method = *method,
+        }
+    }
+
+    fn read(&self, settings: &DisplaySettings) -> Setting {
+        match self {
+            Setting::Comparison(_) => Setting::Comparison(settings.comparison.clone()),
+            Setting::TimingMethod(_) => Setting::TimingMethod(settings.timing_method),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SettingChange {
+    /// The value before the change, what undoing restores.
+    previous: Setting,
+    /// When the setting last changed.
+    at: Instant,
+}
+
+#[derive(Debug, Default)]
+pub struct SettingHistory {
+    changes: VecDeque<SettingChange>,
+    seen: Option<DisplaySettings>,
+}
+
+impl SettingHistory {
+    /// Records what changed since the last frame. Returns the new value
+    /// when there is a change to undo.
+    pub fn observe(&mut self, current: &DisplaySettings, now: Instant) -> Option<Setting> {
+        let seen = self.seen.replace(current.clone())?;
+        let mut changed = None;
+        for previous in [
+            Setting::Comparison(seen.comparison.clone()),
+            Setting::TimingMethod(seen.timing_method),
+        ] {
+            let value = previous.read(current);
+            if value != previous && self.record(previous, current, now) {
+                changed = Some(value);
+            }
+        }
+        changed
+    }
+
+    /// Returns whether a change is left to undo.
+    fn record(&mut self, previous: Setting, current: &DisplaySettings, now: Instant) -> bool {
+        if let Some(latest) = self.changes.back_mut()
+            && latest.previous.same_kind(&previous)
+            && now.saturating_duration_since(latest.at) <= COALESCE_WINDOW
+        {
+            if latest.previous.read(current) == latest.previous {
+                // Cycled back to where it started
+                self.changes.pop_back();
+                return false;
+            }
+            latest.at = now;
+            return true;
+        }
+        if self.changes.len() == HISTORY_CAPACITY {
+            self.changes.pop_front();
+        }
+        self.changes.push_back(SettingChange { previous, at: now });
+        true
+    }
+
+    /// Takes back the most recent change and returns the value to restore.
+    /// The restored value is expected on the next frame and isn't recorded.
+    pub fn undo(&mut self) -> Option<Setting> {
+        let change = self.changes.pop_back()?;
+        if let Some(seen) = self.seen.as_mut() {
+            change.previous.apply_to(seen);
+        }
+        Some(change.previous)
+    }
+
+    /// Forgets everything, for when other splits are loaded.
+    pub fn clear(&mut self) {
+        self.changes.clear();
+        self.seen = None;
+    }
+}
+
+#[cfg(test)]
+mod setting_history_tests {
+    use super::*;
+
+    fn settings(comparison: &str, timing_method: TimingMethod) -> DisplaySettings {
+        DisplaySettings {
+            comparison: comparison.to_owned(),
+            timing_method,
+        }
+    }
+
+    fn pb() -> DisplaySettings {
+        settings("Personal Best", TimingMethod::RealTime)
+    }
+
+    fn comparison(name: &str) -> Setting {
+        Setting::Comparison(name.to_owned())
+    }
+
+    #[test]
+    fn nothing_to_undo() {
+        let mut history = SettingHistory::default();
+        assert_eq!(history.undo(), None);
+        let start = Instant::now();
+        assert_eq!(history.observe(&pb(), start), None);
+        assert_eq!(history.observe(&pb(), start + Duration::from_secs(1)), None);
+        assert_eq!(history.undo(), None);
+    }
+
+    #[test]
+    fn a_change_is_undone_to_the_previous_value() {
+        let mut history = SettingHistory::default();
+        let start = Instant::now();
+        history.observe(&pb(), start);
+        let game_time = settings("Personal Best", TimingMethod::GameTime);
+        assert_eq!(
+            history.observe(&game_time, start + Duration::from_secs(5)),
+            Some(Setting::TimingMethod(TimingMethod::GameTime))
+        );
+        assert_eq!(
+            history.undo(),
+            Some(Setting::TimingMethod(TimingMethod::RealTime))
+        );
+        // The restored value shows up on the next frame without being recorded
+        assert_eq!(history.observe(&pb(), start + Duration::from_secs(6)), None);
+        assert_eq!(history.changes.len(), 0);
+    }
+
+    #[test]
+    fn rapid_changes_are_undone_at_once() {
+        let mut history = SettingHistory::default();
+        let start = Instant::now();
+        history.observe(&pb(), start);
+        for (ms, name) in [
+            (100, "Best Segments"),
+            (400, "Average Segments"),
+            (700, "Latest Run"),
+        ] {
+            assert_eq!(
+                history.observe(
+                    &settings(name, TimingMethod::RealTime),
+                    start + Duration::from_millis(ms)
+                ),
+                Some(comparison(name))
+            );
+        }
+        assert_eq!(history.changes.len(), 1);
+        assert_eq!(history.undo(), Some(comparison("Personal Best")));
+    }
+
+    #[test]
+    fn cycling_back_leaves_nothing_to_undo() {
+        let mut history = SettingHistory::default();
+        let start = Instant::now();
+        history.observe(&pb(), start);
+        history.observe(
+            &settings("Best Segments", TimingMethod::RealTime),
+            start + Duration::from_millis(200),
+        );
+        assert_eq!(
+            history.observe(&pb(), start + Duration::from_millis(500)),
+            None
+        );
+        assert_eq!(history.undo(), None);
+    }
+
+    #[test]
+    fn undo_twice_walks_back_two_changes() {
+        let mut history = SettingHistory::default();
+        let start = Instant::now();
+        history.observe(&pb(), start);
+        history.observe(
+            &settings("Best Segments", TimingMethod::RealTime),
+            start + Duration::from_secs(10),
+        );
+        history.observe(
+            &settings("Best Segments", TimingMethod::GameTime),
+            start + Duration::from_secs(11),
+        );
+        // Separated by more than the coalescing window
+        history.observe(
+            &settings("Average Segments", TimingMethod::GameTime),
+            start + Duration::from_secs(20),
+        );
+        assert_eq!(history.changes.len(), 3);
+
+        assert_eq!(history.undo(), Some(comparison("Best Segments")));
+        assert_eq!(
+            history.undo(),
+            Some(Setting::TimingMethod(TimingMethod::RealTime))
+        );
+        assert_eq!(
+            history.changes.back().map(|change| &change.previous),
+            Some(&comparison("Personal Best"))
+        );
+    }
+
+    #[test]
+    fn only_the_recent_changes_are_kept() {
+        let mut history = SettingHistory::default();
+        let start = Instant::now();
+        history.observe(&pb(), start);
+        for step in 1..=HISTORY_CAPACITY as u64 + 3 {
+            let name = format!("Comparison {step}");
+            history.observe(
+                &settings(&name, TimingMethod::RealTime),
+                start + Duration::from_secs(10 * step),
+            );
+        }
+        assert_eq!(history.changes.len(), HISTORY_CAPACITY);
+    }
+
+    #[test]
+    fn clearing_forgets_the_changes_and_the_last_frame() {
+        let mut history = SettingHistory::default();
+        let start = Instant::now();
+        history.observe(&pb(), start);
+        history.observe(
+            &settings("Best Segments", TimingMethod::RealTime),
+            start + Duration::from_secs(5),
+        );
+        history.clear();
+        assert_eq!(history.undo(), None);
+        // The first frame after is the new baseline
+        assert_eq!(history.observe(&pb(), start + Duration::from_secs(6)), None);
+    }
+}
@@ -0,0 +1,315 @@
+//! How long startup takes, phase by phase, and the work kept off its way.
+//!
+//! Reading the config and the splits, generating comparisons, setting up
+//! the hotkeys and building the window are timed, and the total up to the
+//! window being shown is logged in one line once the deferred work is done.
+//! With `--profile-startup` the same is written to `startup-profile.json`
+//! in the config directory.
+//!
+//! Work that the window doesn't need, like loading the auto splitter, is
+//! handed to [`defer`] and only runs once the window is up.
+
+use std::cell::RefCell;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::{debug, info, warn};
+
+static STARTED: LazyLock<Instant> = LazyLock::new(Instant::now);
+static PROFILE: Mutex<StartupProfile> = Mutex::new(StartupProfile::new());
+static WRITE_PROFILE: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static DEFERRED: RefCell<DeferredTasks> = RefCell::new(DeferredTasks::default());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartupPhase {
+    Config,
+    Run,
+    Comparisons,
+    Hotkeys,
+    AutoSplitter,
+    Window,
+}
+
+impl StartupPhase {
+    fn name(self) -> &'static str {
+        match self {
+            StartupPhase::Config => "config",
+            StartupPhase::Run => "run",
+            StartupPhase::Comparisons => "comparisons",
+            StartupPhase::Hotkeys => "hotkeys",
+            StartupPhase::AutoSplitter => "auto-splitter",
+            StartupPhase::Window => "window",
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Durations of the startup phases, in the order they first ran.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StartupProfile {
+    /// Each phase, whether it was deferred, and its time.
+    phases: Vec<(StartupPhase, bool, Duration)>,
+    /// From the start of the process to the window being shown.
+    ready: Option<Duration>,
+}
+
+impl StartupProfile {
+    pub const fn new() -> Self {
+        Self {
+            phases: Vec::new(),
+            ready: None,
+        }
+    }
+
+    /// Adds `duration` to `phase`, which may run more than once.
+    pub fn record(&mut self, phase: StartupPhase, deferred: bool, duration: Duration) {
+        match self
+            .phases
+            .iter_mut()
+            .find(|(p, d, _)| *p == phase && *d == deferred)
+        {
+            Some((_, _, total)) => *total += duration,
+            None => self.phases.push((phase, deferred, duration)),
+        }
+    }
+
+    pub fn set_ready(&mut self, since_start: Duration) {
+        self.ready = Some(since_start);
+    }
+
+    /// Time of the phases the window waited for.
+    pub fn critical_path(&self) -> Duration {
+        self.phases
+            .iter()
+            .filter(|(_, deferred, _)| !deferred)
+            .map(|(_, _, duration)| *duration)
+            .sum()
+    }
+
+    /// e.g. "Ready in 182.4ms (phases 160.2ms): config 1.2ms, run 40.1ms,
+    /// …, deferred auto-splitter 80.3ms".
+    pub fn summary(&self) -> String {
+        let mut out = match self.ready {
+            Some(ready) => format!("Ready in {:.1}ms", millis(ready)),
+            None => "Not ready yet".to_owned(),
+        };
+        let _ = write!(out, " (phases {:.1}ms)", millis(self.critical_path()));
+        for (index, (phase, deferred, duration)) in self.phases.iter().enumerate() {
+            out.push_str(if index == 0 { ": " } else { ", " });
+            if *deferred {
+                out.push_str("deferred ");
+            }
+            let _ = write!(out, "{} {:.1}ms", phase.name(), millis(*duration));
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{\n");
+        if let Some(ready) = self.ready {
+            let _ = writeln!(out, "  \"ready_ms\": {:.3},", millis(ready));
+        }
+        let _ = writeln!(
+            out,
+            "  \"critical_path_ms\": {:.3},",
+            millis(self.critical_path())
+        );
+        out.push_str("  \"phases\": [");
+        for (index, (phase, deferred, duration)) in self.phases.iter().enumerate() {
+            out.push_str(if index == 0 { "\n" } else { ",\n" });
+            let _ = write!(
+                out,
+                "    {{ \"phase\": \"{}\", \"deferred\": {deferred}, \"ms\": {:.3} }}",
+                phase.name(),
+                millis(*duration)
+            );
+        }
+        out.push_str("\n  ]\n}\n");
+        out
+    }
+}
+
+/// Tasks handed to [`defer`], in the order they were.
+#[derive(Default)]
+pub struct DeferredTasks {
+    tasks: Vec<(StartupPhase, Box<dyn FnOnce()>)>,
+}
+
+impl DeferredTasks {
+    pub fn push(&mut self, phase: StartupPhase, task: impl FnOnce() + 'static) {
+        self.tasks.push((phase, Box::new(task)));
+    }
+
+    pub fn pending(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Runs every task, recording each in `profile`.
+    pub fn run_all(&mut self, profile: &mut StartupProfile) {
+        for (phase, task) in std::mem::take(&mut self.tasks) {
+            let start = Instant::now();
+            task();
+            profile.record(phase, true, start.elapsed());
+        }
+    }
+}
+
+/// Starts the startup clock, first thing in `main`.
+pub fn begin(write_profile: bool) {
+    LazyLock::force(&STARTED);
+    WRITE_PROFILE.store(write_profile, Ordering::Relaxed);
+}
+
+/// Runs `f` as `phase` of the startup.
+pub fn measure<T>(phase: StartupPhase, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let value = f();
+    PROFILE
+        .lock()
+        .unwrap()
+        .record(phase, false, start.elapsed());
+    value
+}
+
+/// Leaves `task` for when the window is shown.
+pub fn defer(phase: StartupPhase, task: impl FnOnce() + 'static) {
+    DEFERRED.with(|deferred| deferred.borrow_mut().push(phase, task));
+}
+
+/// Runs the tasks handed to [`defer`] so far and records them. The profile
+/// isn't locked meanwhile, the tasks may [`measure`] phases of their own.
+fn run_deferred() {
+    let mut tasks = DEFERRED.with(|deferred| std::mem::take(&mut *deferred.borrow_mut()));
+    debug!("Running {} deferred startup tasks", tasks.pending());
+    let mut ran = StartupProfile::new();
+    tasks.run_all(&mut ran);
+    let mut profile = PROFILE.lock().unwrap();
+    for (phase, deferred, duration) in ran.phases {
+        profile.record(phase, deferred, duration);
+    }
+}
+
+/// Marks the window as shown and runs the deferred tasks once GTK is idle,
+/// then reports the startup into `profile_dir`.
+pub fn window_shown(profile_dir: PathBuf) {
+    PROFILE.lock().unwrap().set_ready(STARTED.elapsed());
+    glib::idle_add_local_once(move || {
+        run_deferred();
+        let profile = PROFILE.lock().unwrap();
+        info!("Startup: {}", profile.summary());
+        if WRITE_PROFILE.load(Ordering::Relaxed) {
+            let path = profile_dir.join("startup-profile.json");
+            match std::fs::write(&path, profile.to_json()) {
+                Ok(()) => info!("Wrote the startup profile to {}", path.display()),
+                Err(e) => warn!("Could not write {}: {e}", path.display()),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod startup_tests {
+    use super::*;
+    use std::rc::Rc;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn phases_add_up_without_the_deferred_ones() {
+        let mut profile = StartupProfile::new();
+        profile.record(StartupPhase::Config, false, ms(2));
+        profile.record(StartupPhase::Run, false, ms(30));
+        profile.record(StartupPhase::Comparisons, false, ms(5));
+        profile.record(StartupPhase::Comparisons, false, ms(1));
+        profile.record(StartupPhase::Window, false, ms(60));
+        profile.record(StartupPhase::AutoSplitter, true, ms(200));
+        profile.set_ready(ms(110));
+
+        assert_eq!(profile.critical_path(), ms(98));
+        assert_eq!(
+            profile.summary(),
+            "Ready in 110.0ms (phases 98.0ms): config 2.0ms, run 30.0ms, comparisons 6.0ms, \
+             window 60.0ms, deferred auto-splitter 200.0ms"
+        );
+        let json = profile.to_json();
+        assert!(json.contains("\"ready_ms\": 110.000,"), "{json}");
+        assert!(
+            json.contains("{ \"phase\": \"auto-splitter\", \"deferred\": true, \"ms\": 200.000 }"),
+            "{json}"
+        );
+    }
+
+    #[test]
+    fn deferred_tasks_wait_for_the_window() {
+        let ran = Rc::new(RefCell::new(Vec::new()));
+        let mut tasks = DeferredTasks::default();
+        for name in ["auto splitter", "comparisons"] {
+            let ran = ran.clone();
+            tasks.push(StartupPhase::AutoSplitter, move || {
+                ran.borrow_mut().push(name)
+            });
+        }
+        assert_eq!(tasks.pending(), 2);
+        assert!(ran.borrow().is_empty());
+
+        let mut profile = StartupProfile::new();
+        tasks.run_all(&mut profile);
+        assert_eq!(*ran.borrow(), ["auto splitter", "comparisons"]);
+        assert_eq!(tasks.pending(), 0);
+        assert_eq!(profile.critical_path(), Duration::ZERO);
+        assert_eq!(profile.phases.len(), 1);
+    }
+
+    #[test]
+    fn deferring_only_queues_the_task() {
+        let ran = Rc::new(RefCell::new(false));
+        let flag = ran.clone();
+        defer(StartupPhase::AutoSplitter, move || {
+            *flag.borrow_mut() = true
+        });
+        assert!(!*ran.borrow());
+        assert_eq!(DEFERRED.with(|deferred| deferred.borrow().pending()), 1);
+    }
+
+    /// Phases of the tasks waiting for the window on this thread.
+    fn deferred_phases() -> Vec<StartupPhase> {
+        DEFERRED.with_borrow(|deferred| deferred.tasks.iter().map(|(phase, _)| *phase).collect())
+    }
+
+    #[test]
+    fn context_init_leaves_the_auto_splitter_for_the_window() {
+        crate::context::defer_startup_work();
+        // Running the task would need the context, which isn't made here
+        assert_eq!(deferred_phases(), [StartupPhase::AutoSplitter]);
+    }
+
+    #[test]
+    fn deferred_tasks_can_measure_phases_of_their_own() {
+        let ran = Rc::new(RefCell::new(false));
+        let flag = ran.clone();
+        defer(StartupPhase::AutoSplitter, move || {
+            measure(StartupPhase::Hotkeys, || *flag.borrow_mut() = true);
+        });
+        run_deferred();
+        assert!(*ran.borrow());
+        assert_eq!(deferred_phases(), []);
+        let profile = PROFILE.lock().unwrap();
+        assert!(
+            profile
+                .phases
+                .iter()
+                .any(|(phase, deferred, _)| *phase == StartupPhase::AutoSplitter && *deferred)
+        );
+    }
+}
@@ -0,0 +1,450 @@
+//! Per-segment spread statistics from the segment history.
+//!
+//! Skipped and reset attempts have no time for a segment and are left out, and
+//! so is the segment after a skip, whose time covers both segments. Samples
+//! further than `outlier_z` population standard deviations from the mean of
+//! the other samples are dropped before computing the sample standard
+//! deviation, so a single choke doesn't make an otherwise steady segment look
+//! inconsistent. The sample itself is left out of that mean and deviation,
+//! otherwise a large enough choke inflates them until it no longer stands out.
+//!
+//! Where attempts are reset comes from the same history: an attempt that
+//! wasn't finished has times up to the segment it was reset on, and empty
+//! entries for the segments it skipped.
+
+use livesplit_core::{Run, TimingMethod};
+
+/// Below this many samples the spread is not meaningful.
+pub const MIN_SAMPLES: usize = 3;
+/// Below this many resets the share of each segment is not meaningful.
+pub const MIN_RESETS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SegmentConsistency {
+    pub samples: usize,
+    /// Mean segment time, in seconds.
+    pub mean: f64,
+    /// Sample standard deviation, in seconds.
+    pub std_dev: f64,
+}
+
+impl SegmentConsistency {
+    /// Standard deviation relative to the mean, in percent. Lower is steadier.
+    pub fn consistency_percent(&self) -> f64 {
+        if self.mean > 0.0 {
+            self.std_dev / self.mean * 100.0
+        } else {
+            0.0
+        }
+    }
+}
+
+fn mean(samples: &[f64]) -> f64 {
+    samples.iter().sum::<f64>() / samples.len() as f64
+}
+
+/// `samples` without the ones further than `outlier_z` population standard
+/// deviations from the mean of the others. A sample differing from others
+/// that are all equal is always an outlier. A non-positive `outlier_z`
+/// keeps everything.
+fn without_outliers(samples: &[f64], outlier_z: f64) -> Vec<f64> {
+    if samples.len() < 2 || outlier_z <= 0.0 {
+        return samples.to_vec();
+    }
+    let others = (samples.len() - 1) as f64;
+    let sum: f64 = samples.iter().sum();
+    let sum_of_squares: f64 = samples.iter().map(|x| x * x).sum();
+    samples
+        .iter()
+        .copied()
+        .filter(|x| {
+            let others_mean = (sum - x) / others;
+            let others_variance =
+                ((sum_of_squares - x * x) / others - others_mean.powi(2)).max(0.0);
+            (x - others_mean).abs() <= outlier_z * others_variance.sqrt()
+        })
+        .collect()
+}
+
+/// Mean and sample standard deviation of `samples` after dropping outliers,
+/// or `None` with fewer than `MIN_SAMPLES` samples left.
+pub fn consistency(samples: &[f64], outlier_z: f64) -> Option<SegmentConsistency> {
+    if samples.len() < MIN_SAMPLES {
+        return None;
+    }
+
+    let kept = without_outliers(samples, outlier_z);
+    if kept.len() < MIN_SAMPLES {
+        return None;
+    }
+
+    let kept_mean = mean(&kept);
+    let variance =
+        kept.iter().map(|x| (x - kept_mean).powi(2)).sum::<f64>() / (kept.len() - 1) as f64;
+    Some(SegmentConsistency {
+        samples: kept.len(),
+        mean: kept_mean,
+        std_dev: variance.sqrt(),
+    })
+}
+
+/// Times of segment `index` from the history, in seconds, for `method`.
+/// Attempts that skipped or never reached the segment are excluded, and so
+/// are the ones that skipped the segment before it.
+pub fn segment_samples(run: &Run, index: usize, method: TimingMethod) -> Vec<f64> {
+    let Some(segment) = run.segments().get(index) else {
+        return Vec::new();
+    };
+    let previous = index
+        .checked_sub(1)
+        .and_then(|previous| run.segments().get(previous));
+    segment
+        .segment_history()
+        .iter()
+        .filter(|(id, _)| {
+            previous
+                .and_then(|previous| previous.segment_history().get(*id))
+                .is_none_or(|time| time[method].is_some())
+        })
+        .filter_map(|(_, time)| time[method])
+        .map(|time| time.total_seconds())
+        .collect()
+}
+
+/// Expected final time of a segment that has been running for `elapsed`
+/// seconds: the mean of the past times that lasted at least that long, once
+/// outliers are dropped. A history that is bimodal because of a skip thus
+/// projects the slow mode once the fast one can't happen anymore. `None`
+/// with fewer than `min_samples` samples, or when no past time ran this long.
+pub fn conditional_expected_time(
+    samples: &[f64],
+    elapsed: f64,
+    outlier_z: f64,
+    min_samples: usize,
+) -> Option<f64> {
+    let kept = without_outliers(samples, outlier_z);
+    if kept.len() < min_samples.max(1) {
+        return None;
+    }
+    let longer: Vec<f64> = kept.into_iter().filter(|x| *x >= elapsed).collect();
+    (!longer.is_empty()).then(|| mean(&longer))
+}
+
+pub fn segment_consistency(
+    run: &Run,
+    index: usize,
+    method: TimingMethod,
+    outlier_z: f64,
+) -> Option<SegmentConsistency> {
+    consistency(&segment_samples(run, index, method), outlier_z)
+}
+
+/// Up to `count` segments ranked from least to most consistent, as
+/// `(segment index, stats)`. Segments without enough samples are left out.
+pub fn least_consistent(
+    run: &Run,
+    method: TimingMethod,
+    outlier_z: f64,
+    count: usize,
+) -> Vec<(usize, SegmentConsistency)> {
+    let mut ranked: Vec<(usize, SegmentConsistency)> = (0..run.len())
+        .filter_map(|index| {
+            segment_consistency(run, index, method, outlier_z).map(|stats| (index, stats))
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.1.consistency_percent()
+            .total_cmp(&a.1.consistency_percent())
+    });
+    ranked.truncate(count);
+    ranked
+}
+
+/// "σ = 4.2s, consistency 3.1%", or "n/a" without enough samples.
+pub fn format_consistency(stats: Option<&SegmentConsistency>) -> String {
+    stats.map_or_else(
+        || "n/a".to_owned(),
+        |stats| {
+            format!(
+                "σ = {:.1}s, consistency {:.1}%",
+                stats.std_dev,
+                stats.consistency_percent()
+            )
+        },
+    )
+}
+
+/// How many of the recorded attempts were reset on each segment. An attempt
+/// was reset on the segment after its last split, or after the skips that
+/// followed it. Finished attempts aren't counted.
+pub fn reset_counts(run: &Run) -> Vec<usize> {
+    let segments = run.segments();
+    let mut counts = vec![0; run.len()];
+    for attempt in run.attempt_history() {
+        let time = attempt.time();
+        if time.real_time.is_some() || time.game_time.is_some() {
+            continue;
+        }
+        let id = attempt.index();
+        let entry = |index: usize| segments.get(index)?.segment_history().get(id);
+        let mut reset_on = (0..segments.len())
+            .rev()
+            .find(|&index| {
+                entry(index)
+                    .is_some_and(|time| time.real_time.is_some() || time.game_time.is_some())
+            })
+            .map_or(0, |index| index + 1);
+        // Skips have an empty entry, unless nothing was split after them
+        while entry(reset_on).is_some() {
+            reset_on += 1;
+        }
+        if let Some(count) = counts.get_mut(reset_on) {
+            *count += 1;
+        }
+    }
+    counts
+}
+
+/// The share of the resets that happened on each segment, from 0 to 1, or
+/// `None` with fewer than `MIN_RESETS` resets.
+pub fn reset_shares(run: &Run) -> Option<Vec<f64>> {
+    let counts = reset_counts(run);
+    let total: usize = counts.iter().sum();
+    (total >= MIN_RESETS).then(|| {
+        counts
+            .iter()
+            .map(|count| *count as f64 / total as f64)
+            .collect()
+    })
+}
+
+/// "32% of resets happen here".
+pub fn format_reset_share(share: f64) -> String {
+    format!("{:.0}% of resets happen here", share * 100.0)
+}
+
+#[cfg(test)]
+mod stats_tests {
+    use super::*;
+    use livesplit_core::{Segment, Time, TimeSpan, Timer};
+
+    const NO_OUTLIERS: f64 = 0.0;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    fn segment_with_history(name: &str, times: &[Option<f64>]) -> Segment {
+        let mut segment = Segment::new(name);
+        for (i, time) in times.iter().enumerate() {
+            let time = time.map_or_else(Time::default, |secs| {
+                Time::new().with_real_time(Some(TimeSpan::from_seconds(secs)))
+            });
+            segment.segment_history_mut().insert(i as i32 + 1, time);
+        }
+        segment
+    }
+
+    #[test]
+    fn projection_is_the_mean_of_the_times_still_possible() {
+        // A skip makes half the attempts ~30s and the other half ~60s
+        let bimodal = [29.0, 30.0, 31.0, 59.0, 60.0, 61.0];
+        let at_start = conditional_expected_time(&bimodal, 0.0, 3.0, 5).unwrap();
+        assert!(close(at_start, 45.0));
+        let past_the_skip = conditional_expected_time(&bimodal, 35.0, 3.0, 5).unwrap();
+        assert!(close(past_the_skip, 60.0));
+        // Right on a past time still counts it
+        let on_time = conditional_expected_time(&bimodal, 60.0, 3.0, 5).unwrap();
+        assert!(close(on_time, 60.5));
+        assert_eq!(conditional_expected_time(&bimodal, 62.0, 3.0, 5), None);
+    }
+
+    #[test]
+    fn projection_needs_enough_history() {
+        let history = [10.0, 11.0, 12.0, 13.0];
+        assert_eq!(conditional_expected_time(&history, 0.0, 3.0, 5), None);
+        let projected = conditional_expected_time(&history, 0.0, 3.0, 4).unwrap();
+        assert!(close(projected, 11.5));
+        assert_eq!(conditional_expected_time(&[], 0.0, 3.0, 0), None);
+    }
+
+    #[test]
+    fn projection_ignores_outliers() {
+        let mut history = vec![10.0; 10];
+        history.push(500.0);
+        let projected = conditional_expected_time(&history, 5.0, 3.0, 5).unwrap();
+        assert!(close(projected, 10.0));
+        // Not even once the regular times are out of reach
+        assert_eq!(conditional_expected_time(&history, 11.0, 3.0, 5), None);
+        // Without the guard the choke drags the projection up
+        let unguarded = conditional_expected_time(&history, 5.0, NO_OUTLIERS, 5).unwrap();
+        assert!(close(unguarded, 600.0 / 11.0));
+    }
+
+    #[test]
+    fn hand_computed_sample_deviation() {
+        // mean 12, squared deviations 4 + 0 + 4, / (3 - 1) = 4, sqrt = 2
+        let stats = consistency(&[10.0, 12.0, 14.0], NO_OUTLIERS).unwrap();
+        assert_eq!(stats.samples, 3);
+        assert!(close(stats.mean, 12.0));
+        assert!(close(stats.std_dev, 2.0));
+        assert!(close(stats.consistency_percent(), 100.0 / 6.0));
+
+        // mean 5, squared deviations 9 + 1 + 1 + 9 = 20, / 3, sqrt = 2.5819...
+        let stats = consistency(&[2.0, 4.0, 6.0, 8.0], NO_OUTLIERS).unwrap();
+        assert!(close(stats.std_dev, (20.0f64 / 3.0).sqrt()));
+    }
+
+    #[test]
+    fn outliers_beyond_z_score_are_dropped() {
+        // 10, 11 and 12 three times over and a 100. With the 100 in the mean
+        // and deviation it is not even 3 standard deviations out, against the
+        // other nine it is far beyond that
+        let mut samples: Vec<f64> = [10.0, 11.0, 12.0].repeat(3);
+        samples.push(100.0);
+
+        let filtered = consistency(&samples, 3.0).unwrap();
+        assert_eq!(filtered.samples, 9);
+        assert!(close(filtered.mean, 11.0));
+
+        // The regular times are within reach of each other
+        let regular = consistency(&samples[..9], 1.5).unwrap();
+        assert_eq!(regular.samples, 9);
+    }
+
+    #[test]
+    fn a_choke_against_identical_times_is_dropped() {
+        let mut samples = vec![10.0; 9];
+        samples.push(100.0);
+        let filtered = consistency(&samples, 3.0).unwrap();
+        assert_eq!(filtered.samples, 9);
+        assert!(close(filtered.std_dev, 0.0));
+        assert_eq!(consistency(&samples, NO_OUTLIERS).unwrap().samples, 10);
+    }
+
+    #[test]
+    fn too_few_samples_is_not_available() {
+        assert_eq!(consistency(&[], 3.0), None);
+        assert_eq!(consistency(&[10.0, 11.0], 3.0), None);
+        assert_eq!(format_consistency(None), "n/a");
+    }
+
+    #[test]
+    fn skipped_attempts_are_excluded() {
+        let mut run = Run::new();
+        run.push_segment(segment_with_history(
+            "A",
+            &[Some(10.0), None, Some(12.0), Some(14.0)],
+        ));
+        assert_eq!(segment_samples(&run, 0, TimingMethod::RealTime).len(), 3);
+        let stats = segment_consistency(&run, 0, TimingMethod::RealTime, NO_OUTLIERS).unwrap();
+        assert!(close(stats.std_dev, 2.0));
+
+        // Only real time was recorded
+        assert_eq!(
+            segment_consistency(&run, 0, TimingMethod::GameTime, NO_OUTLIERS),
+            None
+        );
+    }
+
+    #[test]
+    fn times_after_a_skip_are_excluded() {
+        let mut run = Run::new();
+        run.push_segment(segment_with_history(
+            "A",
+            &[Some(10.0), None, Some(12.0), Some(14.0)],
+        ));
+        // The second attempt skipped A, so its 50 covers both segments
+        run.push_segment(segment_with_history(
+            "B",
+            &[Some(20.0), Some(50.0), Some(22.0), Some(24.0)],
+        ));
+        assert_eq!(
+            segment_samples(&run, 1, TimingMethod::RealTime),
+            [20.0, 22.0, 24.0]
+        );
+        assert!(segment_samples(&run, 2, TimingMethod::RealTime).is_empty());
+    }
+
+    #[test]
+    fn ranks_least_consistent_first() {
+        let mut run = Run::new();
+        run.push_segment(segment_with_history(
+            "Steady",
+            &[Some(10.0), Some(10.1), Some(9.9)],
+        ));
+        run.push_segment(segment_with_history(
+            "Wild",
+            &[Some(10.0), Some(20.0), Some(30.0)],
+        ));
+        run.push_segment(segment_with_history("New", &[Some(5.0)]));
+        run.push_segment(segment_with_history(
+            "Shaky",
+            &[Some(10.0), Some(12.0), Some(14.0)],
+        ));
+
+        let ranked = least_consistent(&run, TimingMethod::RealTime, NO_OUTLIERS, 5);
+        let order: Vec<usize> = ranked.iter().map(|(index, _)| *index).collect();
+        assert_eq!(order, vec![1, 3, 0]);
+
+        assert_eq!(
+            least_consistent(&run, TimingMethod::RealTime, NO_OUTLIERS, 1).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn resets_are_counted_on_the_segment_they_happened() {
+        let mut run = Run::new();
+        for name in ["Forsaken City", "Old Site", "Celestial Resort"] {
+            run.push_segment(Segment::new(name));
+        }
+        let mut timer = Timer::new(run).unwrap();
+        // Splits (true) and skips (false) of each attempt before it ended:
+        // 2 resets on the first segment, 7 on the second, 3 on the last, 1 finished.
+        // Resetting drops the skip nothing was split after, so that reset counts
+        // on the first segment; the skip before a split stays
+        let mut attempts = vec![vec![], vec![false]];
+        attempts.extend(vec![vec![true]; 7]);
+        attempts.extend(vec![vec![true, true]; 2]);
+        attempts.push(vec![false, true]);
+        attempts.push(vec![true, true, true]);
+        for attempt in attempts {
+            timer.start();
+            for split in attempt {
+                std::thread::sleep(std::time::Duration::from_millis(1));
+                if split {
+                    timer.split();
+                } else {
+                    timer.skip_split();
+                }
+            }
+            timer.reset(true);
+        }
+
+        let run = timer.run();
+        assert_eq!(reset_counts(run), [2, 7, 3]);
+        let shares = reset_shares(run).unwrap();
+        assert!(close(shares[1], 7.0 / 12.0));
+        assert_eq!(format_reset_share(shares[1]), "58% of resets happen here");
+        assert_eq!(format_reset_share(shares[0]), "17% of resets happen here");
+
+        let mut run = run.clone();
+        run.clear_history();
+        assert_eq!(reset_counts(&run), [0, 0, 0]);
+        assert_eq!(reset_shares(&run), None);
+    }
+
+    #[test]
+    fn formats_summary() {
+        let stats = SegmentConsistency {
+            samples: 5,
+            mean: 135.0,
+            std_dev: 4.2,
+        };
+        assert_eq!(
+            format_consistency(Some(&stats)),
+            "σ = 4.2s, consistency 3.1%"
+        );
+    }
+}
@@ -0,0 +1,430 @@
+//! Timing traces for bug reports, with `logging.trace-timing`.
+//!
+//! Timer commands, splits file loads and saves, and refresh ticks open
+//! spans on the [`TIMING_TARGET`] target, at the trace level. The default
+//! filter drops them at the callsite, so the spans cost a disabled check
+//! until the option is on; fields that take work to compute are only filled
+//! in for an enabled span. With the option on they are logged as each span
+//! closes, with its duration, to `logging.trace-file` or the standard
+//! output.
+//!
+//! Commands from key presses, pedals and the split button get a span around
+//! them. Global hotkeys and the auto splitter reach the timer inside
+//! livesplit-core, so their changes get one when the refresh tick notices
+//! the phase or split index moved without a command, see
+//! [`trace_outside_change`]. The span then starts at the tick rather than
+//! the key press.
+
+use std::cell::Cell;
+use std::fs::OpenOptions;
+use std::sync::Mutex;
+
+use livesplit_core::{Timer, TimerPhase};
+use tracing::field::{self, Empty};
+use tracing::{Level, Span, error, trace_span};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::fmt::{self, format::FmtSpan};
+use tracing_subscriber::prelude::*;
+
+use crate::config::LoggingOptions;
+use crate::ui::hotkeys::{ActionSource, HotkeyAction};
+use crate::ui::timer::frame_budget::TickTimings;
+use crate::utils::crash_report::LogRing;
+
+pub const TIMING_TARGET: &str = "tuxsplit::timing";
+
+thread_local! {
+    /// The phase and split index a command or a tick last left the timer in.
+    static LAST_SEEN: Cell<Option<(TimerPhase, Option<usize>)>> = const { Cell::new(None) };
+}
+
+/// A span around `action`, with the outcome recorded by [`record_command`].
+pub fn command_span(action: HotkeyAction, source: ActionSource, phase: TimerPhase) -> Span {
+    trace_span!(
+        target: TIMING_TARGET,
+        "command",
+        ?action,
+        ?source,
+        before = ?phase,
+        after = Empty,
+        split_index = Empty,
+    )
+}
+
+/// Records the phase and split index `span`'s command left the timer in.
+pub fn record_command(span: &Span, phase: TimerPhase, split_index: Option<usize>) {
+    LAST_SEEN.set(Some((phase, split_index)));
+    span.record("after", field::debug(phase));
+    span.record("split_index", field::debug(split_index));
+}
+
+/// The command that took the timer from `before` to `after`, as phase and
+/// split index. `skipped` is whether the segment passed has no split time.
+pub fn inferred_action(
+    before: (TimerPhase, Option<usize>),
+    after: (TimerPhase, Option<usize>),
+    skipped: bool,
+) -> Option<HotkeyAction> {
+    let ((before_phase, before_index), (after_phase, after_index)) = (before, after);
+    if before == after {
+        None
+    } else if after_phase == TimerPhase::NotRunning {
+        Some(HotkeyAction::Reset)
+    } else if before_phase == TimerPhase::NotRunning {
+        Some(HotkeyAction::Split)
+    } else if after_index > before_index {
+        Some(if skipped {
+            HotkeyAction::Skip
+        } else {
+            HotkeyAction::Split
+        })
+    } else if after_index < before_index {
+        Some(HotkeyAction::Undo)
+    } else {
+        Some(HotkeyAction::Pause)
+    }
+}
+
+/// Opens a command span for a change no command span covered, which
+/// `source` made. Called by every refresh tick.
+pub fn trace_outside_change(timer: &Timer, source: ActionSource) {
+    let after = (timer.current_phase(), timer.current_split_index());
+    let Some(before) = LAST_SEEN.replace(Some(after)) else {
+        return;
+    };
+    let skipped = || {
+        before.1.is_some_and(|index| {
+            timer.run().segments().get(index).is_some_and(|segment| {
+                segment.split_time()[timer.current_timing_method()].is_none()
+            })
+        })
+    };
+    if let Some(action) = inferred_action(before, after, before != after && skipped()) {
+        let span = command_span(action, source, before.0);
+        record_command(&span, after.0, after.1);
+    }
+}
+
+/// A span around reading or writing the splits file at `path`, with its
+/// size recorded in `bytes`.
+pub fn splits_io_span(operation: &'static str, path: &std::path::Path) -> Span {
+    trace_span!(
+        target: TIMING_TARGET,
+        "splits_io",
+        operation,
+        path = %path.display(),
+        bytes = Empty,
+    )
+}
+
+/// A span around a refresh tick, with what it did recorded by
+/// [`record_tick`].
+pub fn tick_span() -> Span {
+    trace_span!(
+        target: TIMING_TARGET,
+        "tick",
+        phase = Empty,
+        snapshot_us = Empty,
+        header_us = Empty,
+        body_us = Empty,
+        footer_us = Empty,
+    )
+}
+
+pub fn record_tick(span: &Span, phase: TimerPhase, timings: &TickTimings) {
+    if span.is_disabled() {
+        return;
+    }
+    span.record("phase", field::debug(phase));
+    span.record("snapshot_us", timings.snapshot.as_micros() as u64);
+    span.record("header_us", timings.header.as_micros() as u64);
+    span.record("body_us", timings.body.as_micros() as u64);
+    span.record("footer_us", timings.footer.as_micros() as u64);
+}
+
+/// Sets up logging to the standard output up to `max_level`, plus the
+/// timing traces if `options` asks for them. The same lines are kept in
+/// memory for crash reports.
+pub fn init_tracing(max_level: Level, options: &LoggingOptions) {
+    let trace_file = options
+        .trace_file
+        .as_ref()
+        .filter(|_| options.trace_timing)
+        .map(|path| {
+            let file = OpenOptions::new().create(true).append(true).open(path);
+            (path, file)
+        });
+    let to_file = matches!(trace_file, Some((_, Ok(_))));
+
+    let mut stdout_filter = Targets::new().with_default(max_level);
+    if options.trace_timing && !to_file {
+        stdout_filter = stdout_filter.with_target(TIMING_TARGET, Level::TRACE);
+    }
+    let stdout = fmt::layer()
+        .with_span_events(FmtSpan::CLOSE)
+        .with_filter(stdout_filter);
+
+    let mut file_error = None;
+    let file = match trace_file {
+        Some((_, Ok(file))) => Some(
+            fmt::layer()
+                .with_ansi(false)
+                .with_writer(Mutex::new(file))
+                .with_span_events(FmtSpan::CLOSE)
+                .with_filter(Targets::new().with_target(TIMING_TARGET, Level::TRACE)),
+        ),
+        Some((path, Err(err))) => {
+            file_error = Some(format!("{}: {err}", path.display()));
+            None
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(stdout)
+        .with(file)
+        .with(LogRing.with_filter(Targets::new().with_default(max_level)))
+        .init();
+    if let Some(err) = file_error {
+        error!("Could not open the timing trace file {err}, tracing to the standard output");
+    }
+}
+
+#[cfg(test)]
+mod trace_timing_tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::fmt::Debug;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tracing::Subscriber;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing_subscriber::layer::{Context, Layer};
+    use tracing_subscriber::registry::LookupSpan;
+
+    type Closed = Arc<Mutex<Vec<(&'static str, BTreeMap<&'static str, String>)>>>;
+
+    #[derive(Default)]
+    struct Fields(BTreeMap<&'static str, String>);
+
+    impl Visit for Fields {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name(), value.to_owned());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0.insert(field.name(), format!("{value:?}"));
+        }
+    }
+
+    /// Keeps the fields of every span that closes.
+    struct Capture(Closed);
+
+    impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Capture {
+        fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+            let mut fields = Fields::default();
+            attrs.record(&mut fields);
+            if let Some(span) = ctx.span(id) {
+                span.extensions_mut().insert(fields);
+            }
+        }
+
+        fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+            if let Some(span) = ctx.span(id)
+                && let Some(fields) = span.extensions_mut().get_mut::<Fields>()
+            {
+                values.record(fields);
+            }
+        }
+
+        fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+            if let Some(span) = ctx.span(&id) {
+                let fields = span.extensions_mut().remove::<Fields>().unwrap_or_default();
+                self.0.lock().unwrap().push((span.name(), fields.0));
+            }
+        }
+    }
+
+    fn capture(
+        level: Level,
+        f: impl FnOnce(),
+    ) -> Vec<(&'static str, BTreeMap<&'static str, String>)> {
+        let closed = Closed::default();
+        let filter = Targets::new().with_target(TIMING_TARGET, level);
+        let subscriber =
+            tracing_subscriber::registry().with(Capture(closed.clone()).with_filter(filter));
+        tracing::subscriber::with_default(subscriber, f);
+        std::mem::take(&mut *closed.lock().unwrap())
+    }
+
+    #[test]
+    fn command_spans_carry_the_source_and_outcome() {
+        let closed = capture(Level::TRACE, || {
+            let span = command_span(
+                HotkeyAction::Split,
+                ActionSource::SplitButton,
+                TimerPhase::NotRunning,
+            );
+            let _entered = span.enter();
+            record_command(&span, TimerPhase::Running, Some(0));
+        });
+        assert_eq!(closed.len(), 1);
+        let (name, fields) = &closed[0];
+        assert_eq!(*name, "command");
+        assert_eq!(fields["action"], "Split");
+        assert_eq!(fields["source"], "SplitButton");
+        assert_eq!(fields["before"], "NotRunning");
+        assert_eq!(fields["after"], "Running");
+        assert_eq!(fields["split_index"], "Some(0)");
+    }
+
+    #[test]
+    fn outside_changes_are_told_apart() {
+        use TimerPhase::{Ended, NotRunning, Paused, Running};
+        let cases = [
+            (
+                (NotRunning, None),
+                (Running, Some(0)),
+                false,
+                Some(HotkeyAction::Split),
+            ),
+            (
+                (Running, Some(0)),
+                (Running, Some(1)),
+                false,
+                Some(HotkeyAction::Split),
+            ),
+            (
+                (Running, Some(0)),
+                (Running, Some(1)),
+                true,
+                Some(HotkeyAction::Skip),
+            ),
+            (
+                (Running, Some(2)),
+                (Ended, Some(3)),
+                false,
+                Some(HotkeyAction::Split),
+            ),
+            (
+                (Ended, Some(3)),
+                (Running, Some(2)),
+                false,
+                Some(HotkeyAction::Undo),
+            ),
+            (
+                (Running, Some(1)),
+                (Paused, Some(1)),
+                false,
+                Some(HotkeyAction::Pause),
+            ),
+            (
+                (Paused, Some(1)),
+                (Running, Some(1)),
+                false,
+                Some(HotkeyAction::Pause),
+            ),
+            (
+                (Paused, Some(1)),
+                (NotRunning, None),
+                false,
+                Some(HotkeyAction::Reset),
+            ),
+            ((Running, Some(1)), (Running, Some(1)), false, None),
+        ];
+        for (before, after, skipped, expected) in cases {
+            assert_eq!(
+                inferred_action(before, after, skipped),
+                expected,
+                "{before:?} -> {after:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn only_changes_without_a_command_span_are_traced() {
+        use livesplit_core::{Run, Segment};
+        let mut run = Run::new();
+        run.push_segment(Segment::new("A"));
+        run.push_segment(Segment::new("B"));
+        let mut timer = Timer::new(run).unwrap();
+
+        let closed = capture(Level::TRACE, || {
+            // The first tick only learns where the timer is
+            trace_outside_change(&timer, ActionSource::Outside);
+            timer.start();
+            trace_outside_change(&timer, ActionSource::Outside);
+
+            let span = command_span(
+                HotkeyAction::Skip,
+                ActionSource::Keyboard,
+                TimerPhase::Running,
+            );
+            timer.skip_split();
+            record_command(&span, timer.current_phase(), timer.current_split_index());
+            drop(span);
+            trace_outside_change(&timer, ActionSource::Outside);
+
+            timer.split();
+            trace_outside_change(&timer, ActionSource::AutoSplitter);
+        });
+        let traced: Vec<_> = closed
+            .iter()
+            .map(|(_, fields)| (fields["action"].as_str(), fields["source"].as_str()))
+            .collect();
+        assert_eq!(
+            traced,
+            [
+                ("Split", "Outside"),
+                ("Skip", "Keyboard"),
+                ("Split", "AutoSplitter")
+            ]
+        );
+    }
+
+    #[test]
+    fn io_and_tick_spans_carry_their_measurements() {
+        let closed = capture(Level::TRACE, || {
+            let span = splits_io_span("save", std::path::Path::new("/tmp/run.lss"));
+            span.record("bytes", 1234);
+            drop(span);
+
+            let span = tick_span();
+            let timings = TickTimings {
+                snapshot: Duration::from_micros(40),
+                header: Duration::from_micros(5),
+                body: Duration::from_micros(900),
+                footer: Duration::from_micros(120),
+            };
+            record_tick(&span, TimerPhase::Running, &timings);
+        });
+        let fields: BTreeMap<_, _> = closed.into_iter().collect();
+        let io = &fields["splits_io"];
+        assert_eq!(io["operation"], "save");
+        assert_eq!(io["path"], "/tmp/run.lss");
+        assert_eq!(io["bytes"], "1234");
+        let tick = &fields["tick"];
+        assert_eq!(tick["phase"], "Running");
+        assert_eq!(tick["body_us"], "900");
+        assert_eq!(tick["footer_us"], "120");
+    }
+
+    #[test]
+    fn filtered_out_spans_are_disabled() {
+        let closed = capture(Level::DEBUG, || {
+            let span = tick_span();
+            assert!(span.is_disabled());
+            record_tick(&span, TimerPhase::Running, &TickTimings::default());
+            assert!(
+                command_span(
+                    HotkeyAction::Reset,
+                    ActionSource::Keyboard,
+                    TimerPhase::Ended
+                )
+                .is_disabled()
+            );
+        });
+        assert!(closed.is_empty());
+    }
+}
@@ -0,0 +1,177 @@
+//! Display offset for runners watching the game through a capture card.
+//!
+//! The capture shows the game late by `behavior.video-offset-ms`, so the
+//! splits are pressed late by the same amount. Every live time the display
+//! reads (the big timer, the running segment, live deltas and pace, the
+//! [`DisplaySnapshot`] a dialog freezes) takes the offset from
+//! [`video_offset`] and is shown that much earlier, in step with what the
+//! runner sees. The timer itself is never touched: recorded split times,
+//! saved files and exports stay as measured, and code passing a zero offset
+//! sees the times as measured.
+//!
+//! [`DisplaySnapshot`]: crate::ui::timer::modal::DisplaySnapshot
+
+use livesplit_core::{Time, TimeSpan, Timer, TimerPhase};
+use time::Duration as TimeDuration;
+
+use crate::config::Config;
+
+/// The offset live times are shown with under `config`.
+pub fn video_offset(config: &Config) -> TimeDuration {
+    TimeDuration::milliseconds(i64::from(config.behavior.video_offset_ms))
+}
+
+/// `attempt` as the capture shows it. Only an attempt in progress is
+/// shifted, a finished one shows what was recorded.
+pub fn shift_for_display(
+    attempt: TimeDuration,
+    phase: TimerPhase,
+    offset: TimeDuration,
+) -> TimeDuration {
+    if matches!(phase, TimerPhase::Running | TimerPhase::Paused) {
+        attempt.saturating_sub(offset).max(TimeDuration::ZERO)
+    } else {
+        attempt
+    }
+}
+
+/// Both timing methods of `time`, see [`shift_for_display`].
+pub fn shift_time_for_display(time: Time, phase: TimerPhase, offset: TimeDuration) -> Time {
+    let shift = |span: Option<TimeSpan>| {
+        span.map(|span| shift_for_display(span.to_duration(), phase, offset).into())
+    };
+    Time::new()
+        .with_real_time(shift(time.real_time))
+        .with_game_time(shift(time.game_time))
+}
+
+/// The timer's attempt duration shifted back by `offset`.
+pub fn displayed_attempt_duration(timer: &Timer, offset: TimeDuration) -> TimeDuration {
+    shift_for_display(
+        timer.current_attempt_duration().to_duration(),
+        timer.current_phase(),
+        offset,
+    )
+}
+
+#[cfg(test)]
+mod video_offset_tests {
+    use super::*;
+    use crate::formatters::{TimeFormat, displayed_timer_duration};
+    use crate::io::integrity::save_timer_stamped;
+    use crate::ui::timer::modal::DisplaySnapshot;
+    use crate::utils::comparisons::{current_attempt_running_duration, current_segment_elapsed};
+    use livesplit_core::{Run, Segment, TimingMethod};
+
+    fn ms(ms: i64) -> TimeDuration {
+        TimeDuration::milliseconds(ms)
+    }
+
+    /// `shifted` is `measured` less `offset`, give or take the instants
+    /// between reading the two from a running clock.
+    fn assert_shifted(measured: TimeDuration, shifted: TimeDuration, offset: TimeDuration) {
+        let slack = shifted - (measured - offset);
+        assert!(
+            slack >= TimeDuration::ZERO && slack < ms(50),
+            "{shifted} is not {measured} less {offset}"
+        );
+    }
+
+    /// Paused 150ms into the second segment, longer than the offsets used.
+    fn paused_timer() -> Timer {
+        let mut run = Run::new();
+        run.push_segment(Segment::new("One"));
+        run.push_segment(Segment::new("Two"));
+        let mut timer = Timer::new(run).unwrap();
+        timer.start();
+        std::thread::sleep(std::time::Duration::from_millis(150));
+        timer.split();
+        std::thread::sleep(std::time::Duration::from_millis(150));
+        timer.pause();
+        timer
+    }
+
+    #[test]
+    fn live_times_shift_by_exactly_the_offset() {
+        for phase in [TimerPhase::Running, TimerPhase::Paused] {
+            let shifted = shift_for_display(ms(10_000), phase, ms(80));
+            assert_eq!(shifted, ms(9_920));
+
+            let shown = |attempt| {
+                displayed_timer_duration(attempt, ms(0), ms(1_000), ms(0), TimingMethod::RealTime)
+            };
+            assert_eq!(shown(ms(10_000)) - shown(shifted), ms(80));
+        }
+    }
+
+    #[test]
+    fn finished_and_idle_timers_are_not_shifted() {
+        for phase in [TimerPhase::NotRunning, TimerPhase::Ended] {
+            assert_eq!(shift_for_display(ms(10_000), phase, ms(80)), ms(10_000));
+        }
+    }
+
+    #[test]
+    fn the_start_of_an_attempt_does_not_go_negative() {
+        assert_eq!(
+            shift_for_display(ms(30), TimerPhase::Running, ms(80)),
+            TimeDuration::ZERO
+        );
+    }
+
+    #[test]
+    fn the_snapshot_and_the_pace_are_shifted() {
+        let timer = paused_timer();
+        let offset = ms(120);
+
+        let measured = DisplaySnapshot::capture(&timer, TimeDuration::ZERO);
+        let shown = DisplaySnapshot::capture(&timer, offset);
+        assert_shifted(
+            measured.attempt_duration.to_duration(),
+            shown.attempt_duration.to_duration(),
+            offset,
+        );
+        let real_time =
+            |snapshot: &DisplaySnapshot| snapshot.current_time.real_time.unwrap().to_duration();
+        assert_shifted(real_time(&measured), real_time(&shown), offset);
+
+        assert_shifted(
+            current_attempt_running_duration(&timer, TimeDuration::ZERO),
+            current_attempt_running_duration(&timer, offset),
+            offset,
+        );
+        assert_shifted(
+            current_segment_elapsed(&timer, TimeDuration::ZERO).unwrap(),
+            current_segment_elapsed(&timer, offset).unwrap(),
+            offset,
+        );
+    }
+
+    #[test]
+    fn the_offset_comes_from_the_config() {
+        let mut config = crate::config::Config::default();
+        assert_eq!(video_offset(&config), TimeDuration::ZERO);
+        config.behavior.video_offset_ms = 120;
+        assert_eq!(video_offset(&config), ms(120));
+    }
+
+    #[test]
+    fn stored_times_are_unaffected() {
+        let mut timer = paused_timer();
+        timer.resume();
+        timer.split();
+        let recorded = timer.run().segments()[0].split_time();
+
+        let mut saved = String::new();
+        save_timer_stamped(&timer, &mut saved).unwrap();
+        // Rendering with the offset reads the timer but leaves it alone
+        let _ = current_attempt_running_duration(&timer, ms(100));
+        let _ = TimeFormat::default().format_timer(&timer, ms(100));
+        let _ = DisplaySnapshot::capture(&timer, ms(100));
+        let mut saved_after = String::new();
+        save_timer_stamped(&timer, &mut saved_after).unwrap();
+
+        assert_eq!(timer.run().segments()[0].split_time(), recorded);
+        assert_eq!(saved, saved_after);
+    }
+}